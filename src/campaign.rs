@@ -15,119 +15,3257 @@
 
 //! The program interface to the back-end data and control layer.
 
+pub mod archive;
+pub mod attrition;
+pub mod audit;
+mod backup;
+pub mod batch;
+mod cache;
+pub mod checklist;
+pub mod combat;
+pub mod config;
+pub mod construction;
 mod data;
-mod empire;
+pub mod deadline;
+pub mod development;
+pub mod dialect;
+pub mod dice;
+pub mod diplomacy;
+pub mod display;
+pub mod economy;
+pub mod empire;
+pub mod escort;
+pub mod espionage;
+pub mod facility;
+#[cfg(test)]
+mod fixture;
+pub mod flight;
+pub mod government;
+pub mod history;
+pub mod intel;
+pub mod journal;
+pub mod lane;
+pub mod locale;
+pub mod map;
+pub mod morale;
+mod movement;
+pub mod naming;
+pub mod notice;
+pub mod options;
+pub mod orders;
+pub mod overlay;
+pub mod overview;
+pub mod ownership;
+pub mod preview;
+pub mod printable;
+pub mod production;
+pub mod raid;
+pub mod recognition;
+pub mod repair;
+mod report;
+pub mod rules;
+pub mod ruleset;
+pub mod scorch;
+pub mod service;
+pub mod setting;
+pub mod shipyard;
+pub mod statement;
 pub mod system;
-mod unit;
+pub mod tech;
+pub mod template;
+pub mod trade;
+pub mod transfer;
+pub mod turn;
+pub mod unit;
+pub mod validation;
+pub mod war;
+pub mod workbook;
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
+use std::{fs, path};
 
+use audit::LogEntry;
+use batch::Change;
+use cache::Cache;
+use combat::{CombatModifier, Combatant};
+use config::Config;
+use construction::{Batch, Build};
 use data::DataStore;
-use system::System;
+use deadline::{Deadline, Status};
+use development::Development;
+use dice::Dice;
+use diplomacy::Treaty;
+use economy::EmpireIncome;
+use empire::{Empire, LedgerEntry};
+use escort::Escort;
+use espionage::Operation;
+use facility::Facility;
+use flight::Station;
+use government::Modifiers;
+use history::History;
+use intel::IntelItem;
+use journal::JournalEntry;
+use lane::Lane;
+use locale::Locale;
+use options::CampaignOptions;
+use orders::{Order, OrderTemplate};
+use overlay::Layers;
+use overview::CampaignSummary;
+use preview::Preview;
+use production::Production;
+use raid::Raid;
+use recognition::Identification;
+use repair::{RepairJob, RepairPlan};
+use rules::RefEntry;
+use ruleset::Ruleset;
+use scorch::ScorchOrder;
+use service::ClassRecord;
+use statement::Statement;
+use system::{BulkEdit, Duplicates, PlanetType, System};
+use tech::TechStatus;
+use transfer::Transfer;
+use turn::TurnReport;
+use unit::{
+    Fleet, FleetSummary, Flight, GroundType, GroundUnit, Hull, Maintenance, ShipLocation, ShipType,
+};
+use war::War;
 
 /// A Campaign, in addition to having the same meaning as in the VBAM rules,
 /// is the control layer managing the conduct of the game itself. Every
 /// campaign has a name which is used as the name of the backend database.
 pub struct Campaign {
     name: String,
+    dir: path::PathBuf,
     data: DataStore,
     turn: i32,
+    history: RefCell<History>,
+    cache: RefCell<Cache>,
 }
 
 impl Campaign {
+    /// Add a combat modifier, returning its ID.
+    pub async fn add_combat_modifier(&self, m: &CombatModifier) -> Result<i64, String> {
+        match self.data.insert_combat_modifier(m).await {
+            Ok(id) => Ok(id),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Place a finished facility of the given kind at a system, without
+    /// building or paying for it.
+    pub async fn add_facility(&self, sys: &System, kind: &str) -> Result<Facility, String> {
+        facility::check(kind, sys, &self.facilities().await?)?;
+        let mut f = Facility::new(sys.id, kind);
+        let before = self.snapshot().await?;
+        match self.data.insert_facility(&f).await {
+            Ok(id) => f.id = id,
+            Err(e) => return Err(e.to_string()),
+        }
+        self.record(format!("Add {} at {}", kind, sys.name), before)
+            .await?;
+        Ok(f)
+    }
+
+    /// Add a new empire, returning its ID. The campaign may be set up for
+    /// fewer empires than want to join.
+    pub async fn add_empire(&self, emp: &Empire) -> Result<i64, String> {
+        emp.check_government()?;
+        let options = self.options().await?;
+        if !options.empire_allowed(self.empires().await?.len()) {
+            return Err(format!(
+                "The campaign is set up for {} empires",
+                options.empires
+            ));
+        }
+        match self.data.insert_empire(emp).await {
+            Ok(id) => {
+                self.cache.borrow_mut().invalidate();
+                Ok(id)
+            }
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Attach an intel item to an empire's packet for the current turn.
+    pub async fn add_intel(
+        &self,
+        empire: i64,
+        kind: &str,
+        text: &str,
+    ) -> Result<IntelItem, String> {
+        let mut item = IntelItem::new(empire, self.turn, kind, text);
+        match self.data.insert_intel(&item).await {
+            Ok(id) => {
+                item.id = id;
+                Ok(item)
+            }
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Add a jump lane, returning its ID.
+    pub async fn add_lane(&self, lane: &Lane) -> Result<i64, String> {
+        match self.data.insert_lane(lane).await {
+            Ok(id) => Ok(id),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Add an order to an empire's orders for the current turn.
+    pub async fn add_order(&self, empire: i64, text: &str) -> Result<Order, String> {
+        match self
+            .data
+            .insert_orders(&[Order::new(empire, self.turn, text)])
+            .await
+        {
+            Ok(mut v) => Ok(v.remove(0)),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Add an order for one of the phases, made by one of the phase's
+    /// editors, returning it with its ID filled in.
+    pub async fn add_phase_order(&self, order: &Order) -> Result<Order, String> {
+        match self.data.insert_orders(std::slice::from_ref(order)).await {
+            Ok(mut v) => Ok(v.remove(0)),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Add a ship class after checking it against its hull, returning the
+    /// class with its ID and construction cost filled in. The empire's other
+    /// classes on the same hull become obsolete.
+    pub async fn add_ship_type(&self, stype: &ShipType) -> Result<ShipType, String> {
+        let mut stype = self.costed_ship_type(stype).await?;
+        match self.data.insert_ship_type(&stype).await {
+            Ok(id) => stype.id = id,
+            Err(e) => return Err(e.to_string()),
+        }
+        match self.data.supersede_ship_types(&stype, self.turn).await {
+            Ok(_) => Ok(stype),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Set one of an empire's fleets escorting another of its fleets in the
+    /// same system.
+    pub async fn assign_escort(
+        &self,
+        escort: &FleetSummary,
+        convoy: &FleetSummary,
+    ) -> Result<Escort, String> {
+        escort::check(escort, convoy, &self.escorts().await?)?;
+        let mut e = Escort::new(escort.id, convoy.id);
+        let before = self.snapshot().await?;
+        match self.data.insert_escort(&e).await {
+            Ok(id) => {
+                e.id = id;
+                e.escort_name = escort.name.clone();
+                e.convoy_name = convoy.name.clone();
+                self.record(format!("{} escorts {}", escort.name, convoy.name), before)
+                    .await?;
+                Ok(e)
+            }
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Change an empire's treasury and tech level by hand, checked against
+    /// the deficit limit, and post the change to its ledger with a note.
+    /// Returns the updated empire.
+    pub async fn adjust_financials(
+        &self,
+        emp: &Empire,
+        treasury: i32,
+        tech: i32,
+        note: &str,
+    ) -> Result<Empire, String> {
+        let limit = self.deficit_limit().await?;
+        emp.check_financials(treasury, tech, note, limit)?;
+        if treasury == emp.treasury && tech == emp.tech {
+            return Ok(emp.clone());
+        }
+        let entry = LedgerEntry::new(
+            emp.id,
+            self.turn,
+            treasury - emp.treasury,
+            tech - emp.tech,
+            note,
+        );
+        let before = self.snapshot().await?;
+        if let Err(e) = self.data.post_ledger(&entry).await {
+            return Err(e.to_string());
+        }
+        self.record(format!("Change {} finances", emp.name), before)
+            .await?;
+        Ok(Empire {
+            treasury,
+            tech,
+            ..emp.clone()
+        })
+    }
+
+    /// Resolve the current turn, phase by phase, and advance to the next.
+    pub async fn advance_turn(&mut self) -> Result<TurnReport, String> {
+        self.backup(format!("Before turn {}", self.turn).as_str())
+            .await?;
+        let result = turn::advance(&self.data, self.turn).await;
+        self.cache.borrow_mut().invalidate();
+        match result {
+            Ok(r) => {
+                self.turn += 1;
+                self.history.borrow_mut().clear();
+                // The turn is over whether or not its report can be filed.
+                if let Ok(tmpl) = self.template(template::TURN_REPORT) {
+                    let name = format!("Turn {}.txt", r.turn);
+                    let text = template::render(tmpl.as_str(), &r.values());
+                    let _ = self.attach("reports", name.as_str(), text.as_str());
+                }
+                Ok(r)
+            }
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Save a text file among the campaign's attached files, in one of the
+    /// archive folders (notes, reports, snapshots or templates).
+    pub fn attach(&self, folder: &str, name: &str, text: &str) -> Result<(), String> {
+        let dir = DataStore::attachments(&self.dir, &self.name);
+        let mut files = serde_json::Map::new();
+        files.insert(format!("{}/{}", folder, name), text.into());
+        archive::restore_files(&dir, &files)
+    }
+
+    /// Make a list of changes together, returning the ID of the row each
+    /// added, updated or deleted. Nothing is changed if any change fails,
+    /// and the whole batch is undone as one.
+    #[allow(unused)]
+    pub async fn apply_batch(&self, changes: Vec<Change>) -> Result<Vec<i64>, String> {
+        let options = self.options().await?;
+        let mut empires = self.empires().await?.len();
+        let types = self.planet_types().await?;
+        for (n, c) in changes.iter().enumerate() {
+            let checked = match c {
+                Change::AddEmpire(_) if !options.empire_allowed(empires) => Err(format!(
+                    "The campaign is set up for {} empires",
+                    options.empires
+                )),
+                Change::AddEmpire(e) | Change::UpdateEmpire(e) => e.check_government(),
+                Change::AddSystem(s) | Change::UpdateSystem(s) => s.validate(&types),
+                _ => Ok(()),
+            };
+            if let Err(e) = checked {
+                return Err(format!("Change {}: {}", n + 1, e));
+            }
+            if let Change::AddEmpire(_) = c {
+                empires += 1;
+            }
+        }
+        let label = batch::label(&changes);
+        if changes.iter().any(Change::is_delete) {
+            self.backup(label.as_str()).await?;
+        }
+        let before = self.snapshot().await?;
+        let ids = match self.data.apply_batch(&changes).await {
+            Ok(v) => v,
+            Err(e) => return Err(e.to_string()),
+        };
+        self.record(label, before).await?;
+        Ok(ids)
+    }
+
+    /// Add the orders in a template to its empire's orders for the current
+    /// turn, returning the new orders.
+    pub async fn apply_order_template(&self, tmpl: &OrderTemplate) -> Result<Vec<Order>, String> {
+        match self.data.insert_orders(&tmpl.orders(self.turn)).await {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Return the names of the campaign's backup snapshots, newest first.
+    pub fn backups(&self) -> Result<Vec<String>, String> {
+        match DataStore::list_backups(&self.dir, &self.name) {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Return the construction queue of all empires.
+    pub async fn builds(&self) -> Result<Vec<Build>, String> {
+        match self.data.get_builds().await {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Make the same change to every given system at once, as one edit.
+    pub async fn bulk_update_systems(
+        &self,
+        systems: &[System],
+        edit: &BulkEdit,
+    ) -> Result<(), String> {
+        if systems.is_empty() || edit.is_empty() {
+            return Ok(());
+        }
+        let ids: Vec<i64> = systems.iter().map(|s| s.id).collect();
+        let before = self.snapshot().await?;
+        match self.data.bulk_update_systems(&ids, edit).await {
+            Ok(_) => {
+                self.record(format!("Edit {} systems", ids.len()), before)
+                    .await
+            }
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Cancel a build, refunding its cost.
+    pub async fn cancel_build(&self, build: &Build) -> Result<(), String> {
+        let before = self.snapshot().await?;
+        match self.data.cancel_build(build).await {
+            Ok(_) => {
+                self.record(format!("Cancel {}", build.item_name()), before)
+                    .await
+            }
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Cancel a development order, refunding its cost.
+    pub async fn cancel_development(&self, order: &Development) -> Result<(), String> {
+        let label = format!(
+            "Cancel {} development at {}",
+            order.target.to_lowercase(),
+            order.system_name
+        );
+        let before = self.snapshot().await?;
+        match self.data.delete_development(order).await {
+            Ok(_) => self.record(label, before).await,
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Cancel an espionage operation, refunding its intel points.
+    pub async fn cancel_operation(&self, op: &Operation) -> Result<(), String> {
+        let label = format!("Cancel {} {}", op.empire_name, op.mission.to_lowercase());
+        let before = self.snapshot().await?;
+        match self.data.delete_operation(op).await {
+            Ok(_) => self.record(label, before).await,
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Cancel a raid order.
+    pub async fn cancel_raid(&self, raid: &Raid) -> Result<(), String> {
+        let label = format!("Cancel raid on {}", raid.system_name);
+        let before = self.snapshot().await?;
+        match self.data.delete_raid(raid).await {
+            Ok(_) => self.record(label, before).await,
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Cancel a scorched earth order.
+    pub async fn cancel_scorch(&self, order: &ScorchOrder) -> Result<(), String> {
+        let label = format!("Cancel scorched earth at {}", order.system_name);
+        let before = self.snapshot().await?;
+        match self.data.delete_scorch_order(order).await {
+            Ok(_) => self.record(label, before).await,
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Cancel a ship or class transfer before it is carried out.
+    pub async fn cancel_transfer(&self, transfer: &Transfer) -> Result<(), String> {
+        let label = format!("Cancel transfer of {}", transfer.item_name);
+        let before = self.snapshot().await?;
+        match self.data.delete_transfer(transfer).await {
+            Ok(_) => self.record(label, before).await,
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
     /// Close the data connection.
     pub async fn close(&self) {
         self.data.close().await;
     }
 
+    /// Check the campaign is ready for the turn to be advanced, returning
+    /// every check that fails.
+    pub async fn checklist(&self) -> Result<Vec<checklist::Item>, String> {
+        let empires = self.empires().await?;
+        let mut ordered = BTreeSet::new();
+        for e in &empires {
+            if !self.orders(e.id).await?.is_empty() {
+                ordered.insert(e.id);
+            }
+        }
+        let fought = match self.data.get_battle_sites(self.turn).await {
+            Ok(v) => v,
+            Err(e) => return Err(e.to_string()),
+        };
+        let orphans = match self.data.get_orphans().await {
+            Ok(v) => v,
+            Err(e) => return Err(e.to_string()),
+        };
+        let mut items = checklist::missing_orders(&empires, &ordered);
+        items.extend(checklist::unfought_battles(
+            &self.systems().await?,
+            &self.fleet_summaries().await?,
+            &self.treaties().await?,
+            &fought,
+        ));
+        items.extend(checklist::over_capacity(&self.production().await?));
+        items.extend(checklist::orphans(&orphans));
+        Ok(items)
+    }
+
+    /// Return every ship class's service record, by empire.
+    pub async fn class_records(&self) -> Result<Vec<ClassRecord>, String> {
+        match self.data.get_class_records().await {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Return a report of every ship class's service record: ships built,
+    /// lost and still in service, and the kills credited to them.
+    pub async fn class_report(&self) -> Result<String, String> {
+        match self.data.get_class_records().await {
+            Ok(v) => Ok(service::report(&v)),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Check the campaign's database can still be reached. Only a database
+    /// out of reach, locked or lost fails the check, as reconnecting may put
+    /// that right.
+    pub async fn check_connection(&self) -> Result<(), String> {
+        match self.data.check().await {
+            Err(e) if e.is_unavailable() => Err(e.to_string()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Return the ships, flights and starbases that would fight a battle at
+    /// a system.
+    pub async fn combatants(&self, sys: &System) -> Result<Vec<Combatant>, String> {
+        match self.data.get_combatants(sys.id).await {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Return the combat modifiers used by the combat resolver.
+    pub async fn combat_modifiers(&self) -> Result<Vec<CombatModifier>, String> {
+        match self.data.get_combat_modifiers().await {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Return whether the optional colonial administration rule is in use.
+    pub async fn colonial_admin(&self) -> Result<bool, String> {
+        match self.data.get_rule_option(economy::COLONIAL_ADMIN).await {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Compute an empire's income for the turn, net of maintenance, and add it
+    /// to the empire's treasury.
+    #[allow(unused)]
+    pub async fn compute_income(&self, empire: i64) -> Result<EmpireIncome, String> {
+        let systems = match self.data.get_systems_by_owner(empire).await {
+            Ok(v) => v,
+            Err(e) => return Err(e.to_string()),
+        };
+        let income = self.expected_income(empire, &systems).await?;
+        match self.data.adjust_treasury(empire, income.net()).await {
+            Ok(_) => Ok(income),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    // Work out the income an empire can expect from the systems it holds,
+    // net of maintenance, without adding it to the treasury.
+    async fn expected_income(
+        &self,
+        empire: i64,
+        systems: &[System],
+    ) -> Result<EmpireIncome, String> {
+        let maintenance = match self.data.get_maintenance(empire).await {
+            Ok(m) => m.total(),
+            Err(e) => return Err(e.to_string()),
+        };
+        let emp = match self.data.get_empires().await {
+            Ok(v) => v.into_iter().find(|e| e.id == empire),
+            Err(e) => return Err(e.to_string()),
+        };
+        let rules = self.ruleset().await?;
+        let mut income = EmpireIncome::new(systems, maintenance, rules);
+        if self.colonial_admin().await? {
+            income = income.with_admin_limit(emp.as_ref().map_or(0, |e| e.tech), systems);
+        }
+        if let Some(emp) = &emp {
+            income = income.with_government(&Modifiers::new(emp));
+        }
+        Ok(income)
+    }
+
+    /// Create a new fleet, returning its ID.
+    pub async fn create_fleet(&self, fleet: &Fleet) -> Result<i64, String> {
+        match self.data.insert_fleet(fleet).await {
+            Ok(id) => Ok(id),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Return the folder campaigns are kept in.
+    pub fn data_dir() -> path::PathBuf {
+        Config::load().data_dir()
+    }
+
+    /// Return every empire's order deadline for the current turn.
+    pub async fn deadlines(&self) -> Result<Vec<Deadline>, String> {
+        match self.data.get_deadlines(self.turn).await {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Return the development orders given this turn.
+    pub async fn developments(&self) -> Result<Vec<Development>, String> {
+        match self.data.get_developments(self.turn).await {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Return how far into deficit an empire's treasury may be taken by
+    /// hand. Campaigns that never set a limit allow no deficit.
+    pub async fn deficit_limit(&self) -> Result<i32, String> {
+        match self.data.get_rule_value(economy::DEFICIT_LIMIT).await {
+            Ok(v) => Ok(v.unwrap_or(0)),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
     /// Delete an existing campaign.
     pub fn delete(name: &str) -> Result<(), String> {
-        if let Err(e) = DataStore::delete(name) {
+        if let Err(e) = DataStore::delete(&Self::data_dir(), name) {
             return Err(e.to_string());
         }
         Ok(())
     }
 
-    /// Import systems from the specified CSV file.
-    pub async fn import_systems(&mut self, file: &str) -> Result<(), String> {
-        let sys = system::read_from_csv(file)?;
-        if let Err(e) = self.data.add_systems(sys).await {
-            return Err(e.to_string());
+    /// Return the names of the systems in a CSV file that are already in the
+    /// campaign.
+    pub async fn duplicate_systems(&self, file: &str) -> Result<Vec<String>, String> {
+        let existing = self.systems().await?;
+        Ok(system::read_from_csv(file)?
+            .into_iter()
+            .map(|s| s.name)
+            .filter(|name| existing.iter().any(|s| s.name == *name))
+            .collect())
+    }
+
+    /// Return the GM's economic phase report.
+    pub async fn economic_report(&self) -> Result<String, String> {
+        let systems = match self.data.get_systems().await {
+            Ok(v) => v,
+            Err(e) => return Err(e.to_string()),
+        };
+        let empires = self.empires().await?;
+        let admin_limits = self.colonial_admin().await?;
+        let routes = trade::routes(&self.treaties().await?, &systems, &self.lanes().await?);
+        let rules = self.ruleset().await?;
+        let locale = self.locale().await?;
+        let mut report = economy::economic_report(&systems, &empires, admin_limits, rules, locale);
+        report.push_str(&trade::report(&routes));
+        Ok(report)
+    }
+
+    /// Return the names of the empires by ID.
+    pub async fn empire_names(&self) -> Result<BTreeMap<i64, String>, String> {
+        if let Some(names) = self.cache.borrow().empire_names() {
+            return Ok(names);
         }
-        Ok(())
+        let names: BTreeMap<i64, String> = self
+            .empires()
+            .await?
+            .into_iter()
+            .map(|e| (e.id, e.name))
+            .collect();
+        self.cache.borrow_mut().set_empire_names(&names);
+        Ok(names)
     }
 
-    /// Return names of available campaigns.
-    pub fn campaigns() -> Result<Vec<String>, String> {
-        match DataStore::available_campaigns() {
+    /// Return the empires in the campaign.
+    pub async fn empires(&self) -> Result<Vec<Empire>, String> {
+        match self.data.get_empires().await {
             Ok(v) => Ok(v),
             Err(e) => Err(e.to_string()),
         }
     }
 
-    /// Delete the specified system.
-    pub async fn delete_system(&self, sys: &System) -> Result<(), String> {
-        match self.data.delete_system(sys).await {
-            Ok(_) => Ok(()),
+    /// Return every ship an empire has, wherever it is.
+    pub async fn empire_ships(&self, empire: i64) -> Result<Vec<ShipLocation>, String> {
+        match self.data.get_ships_by_owner(empire).await {
+            Ok(v) => Ok(v),
             Err(e) => Err(e.to_string()),
         }
     }
 
-    /// Campaign name.
-    pub fn name(&self) -> &String {
-        &self.name
+    /// Return every fleet escorting another.
+    pub async fn escorts(&self) -> Result<Vec<Escort>, String> {
+        match self.data.get_escorts().await {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e.to_string()),
+        }
     }
 
-    /// Create a new campaign.
-    pub async fn new(name: String) -> Result<Self, String> {
-        let data = match DataStore::new(name.as_str()).await {
-            Ok(d) => d,
-            Err(e) => return Err(e.to_string()),
-        };
-
-        Ok(Self {
-            name: name.to_owned(),
-            data,
-            turn: 0,
-        })
+    /// End a treaty before it expires.
+    pub async fn end_treaty(&self, treaty: &Treaty) -> Result<(), String> {
+        let before = self.snapshot().await?;
+        match self.data.delete_treaty(treaty).await {
+            Ok(_) => {
+                self.record(
+                    format!(
+                        "End {} between {} and {}",
+                        treaty.ttype, treaty.a_name, treaty.b_name
+                    ),
+                    before,
+                )
+                .await
+            }
+            Err(e) => Err(e.to_string()),
+        }
     }
 
-    /// Open an existing campaign.
-    pub async fn open(name: &str) -> Result<Self, String> {
-        let data = match DataStore::open(name).await {
-            Ok(d) => d,
+    /// Write the whole campaign, with its attached notes, reports and
+    /// snapshots, to a single archive file that can be imported on another
+    /// machine. The GM's journal is left out unless asked for.
+    pub async fn export(&self, path: &str, journal: bool) -> Result<(), String> {
+        let mut archive = match self.data.export().await {
+            Ok(v) => v,
             Err(e) => return Err(e.to_string()),
         };
-        let turn = match data.current_turn().await {
-            Ok(i) => i,
+        if !journal {
+            if let Some(tables) = archive["tables"].as_object_mut() {
+                tables.remove("journal");
+            }
+        }
+        archive["format"] = archive::FORMAT.into();
+        archive["version"] = archive::VERSION.into();
+        archive["name"] = serde_json::Value::from(self.name.as_str());
+        let dir = DataStore::attachments(&self.dir, &self.name);
+        archive["files"] = archive::collect_files(&dir)?.into();
+        let text = match serde_json::to_string_pretty(&archive) {
+            Ok(t) => t,
             Err(e) => return Err(e.to_string()),
         };
+        match fs::write(path, text) {
+            Ok(_) => Ok(()),
+            Err(e) => Err(e.to_string()),
+        }
+    }
 
-        Ok(Self {
-            name: name.to_owned(),
-            data,
-            turn,
-        })
+    /// Write the GM's journal to a Markdown file.
+    pub async fn export_journal(&self, path: &str) -> Result<(), String> {
+        let text = journal::to_markdown(self.name.as_str(), &self.journal().await?);
+        match fs::write(path, text) {
+            Ok(_) => Ok(()),
+            Err(e) => Err(e.to_string()),
+        }
     }
 
-    /// Return the systems in the campaign.
-    pub async fn systems(&self) -> Result<Vec<System>, String> {
-        match self.data.get_systems().await {
+    /// Write a printable page of the campaign as it stands, with the map
+    /// and what each empire holds, to an HTML file.
+    pub async fn export_printable(&self, path: &str) -> Result<(), String> {
+        let systems = self.systems().await?;
+        let summaries = printable::summarize(
+            &self.empires().await?,
+            &systems,
+            &self.fleet_summaries().await?,
+            self.ruleset().await?,
+        );
+        let html = printable::page(
+            self.title().as_str(),
+            &systems,
+            &self.lanes().await?,
+            &summaries,
+            self.locale().await?,
+        );
+        match fs::write(path, html) {
+            Ok(_) => Ok(()),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Give default orders to every empire whose orders are missing and who
+    /// has none entered for the current turn. The empire's "Default" order
+    /// template is used if it has one, then last turn's orders, and failing
+    /// both a single hold order. Returns the names of the empires given
+    /// orders.
+    pub async fn fill_missing_orders(&self) -> Result<Vec<String>, String> {
+        let today = self.today().await?;
+        let mut filled = Vec::new();
+        for d in self.deadlines().await? {
+            if d.status(today.as_str()) != Status::Missing
+                || !self.orders(d.empire).await?.is_empty()
+            {
+                continue;
+            }
+            let templates = self.order_templates(d.empire).await?;
+            match templates.iter().find(|t| t.name == "Default") {
+                Some(t) => {
+                    self.apply_order_template(t).await?;
+                }
+                None => {
+                    if self.repeat_last_orders(d.empire).await.is_err() {
+                        self.add_order(d.empire, "Hold position").await?;
+                    }
+                }
+            }
+            filled.push(d.empire_name);
+        }
+        Ok(filled)
+    }
+
+    /// Return the ships in a fleet.
+    pub async fn fleet_ships(&self, fleet: i64) -> Result<Vec<ShipLocation>, String> {
+        match self.data.get_fleet_ships(fleet).await {
             Ok(v) => Ok(v),
             Err(e) => Err(e.to_string()),
         }
     }
 
-    /// Campaign title including turn number.
-    pub fn title(&self) -> String {
-        format!("{} Turn {}", self.name, self.turn)
+    /// Return every facility.
+    pub async fn facilities(&self) -> Result<Vec<Facility>, String> {
+        match self.data.get_facilities().await {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e.to_string()),
+        }
     }
 
-    /// Update the given system, which must have a valid ID.
-    pub async fn update_system(&self, sys: &System) -> Result<(), String> {
-        match self.data.update_system(sys).await {
-            Ok(_) => Ok(()),
+    /// Return the flights at a system, based there or aboard carriers there.
+    pub async fn flights(&self, location: i64) -> Result<Vec<Flight>, String> {
+        match self.data.get_flights(location).await {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Return the condition summary of every fleet.
+    pub async fn fleet_summaries(&self) -> Result<Vec<FleetSummary>, String> {
+        match self.data.get_fleet_summaries().await {
+            Ok(v) => Ok(v),
             Err(e) => Err(e.to_string()),
         }
     }
+
+    /// Return the ground units garrisoned at a system.
+    pub async fn garrison(&self, sys: &System) -> Result<Vec<GroundUnit>, String> {
+        match self.data.get_ground_units(sys.id).await {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Return the ground unit types.
+    pub async fn ground_types(&self) -> Result<Vec<GroundType>, String> {
+        match self.data.get_ground_types().await {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Return the hull classes.
+    pub async fn hulls(&self) -> Result<Vec<Hull>, String> {
+        match self.data.get_hulls().await {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Return the enemy ship classes an empire has identified.
+    pub async fn identifications(&self, empire: i64) -> Result<Vec<Identification>, String> {
+        match self.data.get_identifications(empire).await {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Record that espionage has given an empire the details of another
+    /// empire's ship class.
+    pub async fn identify_class(&self, empire: i64, class: &ShipType) -> Result<(), String> {
+        if class.empire == empire {
+            return Err(format!("{} is the empire's own class", class.class));
+        }
+        let item = Identification::new(empire, class.id, self.turn, recognition::ESPIONAGE);
+        match self.data.insert_identifications(&[item]).await {
+            Ok(_) => Ok(()),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Add the orders in a file to an empire's orders for the current turn,
+    /// returning the validation report of all its orders. Each line holds an
+    /// order's phase, cost and text separated by tabs, or a free-form order.
+    pub async fn import_orders(&self, empire: i64, file: &str) -> Result<String, String> {
+        let text = dialect::read_file(file).map_err(|e| format!("{}: {}", file, e))?;
+        let orders: Vec<Order> = text
+            .lines()
+            .filter(|l| !l.trim().is_empty())
+            .map(|l| Order::from_line(empire, self.turn, l))
+            .collect();
+        if orders.is_empty() {
+            return Err(format!("{} holds no orders", file));
+        }
+        if let Err(e) = self.data.insert_orders(&orders).await {
+            return Err(e.to_string());
+        }
+        self.validate_orders(empire).await
+    }
+
+    /// Import systems from the specified CSV file, doing as asked with those
+    /// already in the campaign. Nothing is imported if any system has an
+    /// unknown planet type or more capacity than its type allows.
+    pub async fn import_systems(&mut self, file: &str, dup: Duplicates) -> Result<(), String> {
+        let sys = system::read_from_csv(file)?;
+        let types = self.planet_types().await?;
+        let errors: Vec<String> = sys
+            .iter()
+            .filter_map(|s| s.validate(&types).err())
+            .collect();
+        if !errors.is_empty() {
+            return Err(errors.join("\n"));
+        }
+        let (added, replaced) = system::sort_imports(sys, &self.systems().await?, dup)?;
+        let label = "Import systems".to_string();
+        if !replaced.is_empty() {
+            self.backup(label.as_str()).await?;
+        }
+        let before = self.snapshot().await?;
+        if let Err(e) = self.data.import_systems(&added, &replaced).await {
+            return Err(e.to_string());
+        }
+        self.record(label, before).await
+    }
+
+    /// Return a summary of each available campaign.
+    pub async fn campaign_summaries() -> Result<Vec<CampaignSummary>, String> {
+        match DataStore::campaign_summaries().await {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Return names of available campaigns.
+    pub fn campaigns() -> Result<Vec<String>, String> {
+        match DataStore::available_campaigns() {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Disband a fleet along with every ship in it.
+    pub async fn disband_fleet(&self, fleet: i64) -> Result<(), String> {
+        let label = "Disband fleet".to_string();
+        self.backup(label.as_str()).await?;
+        let before = self.snapshot().await?;
+        match self.data.delete_fleet(fleet).await {
+            Ok(_) => self.record(label, before).await,
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Disband a flight.
+    pub async fn disband_flight(&self, flight: &Flight) -> Result<(), String> {
+        let label = format!("Disband flight at {}", flight.station);
+        let before = self.snapshot().await?;
+        match self.data.delete_flight(flight).await {
+            Ok(_) => self.record(label, before).await,
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Demolish a facility.
+    pub async fn demolish_facility(&self, f: &Facility) -> Result<(), String> {
+        let label = format!("Demolish {} at {}", f.kind, f.system_name);
+        self.backup(label.as_str()).await?;
+        let before = self.snapshot().await?;
+        match self.data.delete_facility(f).await {
+            Ok(_) => self.record(label, before).await,
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Disband ground units.
+    pub async fn disband_ground_units(&self, units: &[i64]) -> Result<(), String> {
+        let label = "Disband ground units".to_string();
+        self.backup(label.as_str()).await?;
+        let before = self.snapshot().await?;
+        match self.data.delete_ground_units(units).await {
+            Ok(_) => self.record(label, before).await,
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Delete the specified combat modifier.
+    pub async fn delete_combat_modifier(&self, m: &CombatModifier) -> Result<(), String> {
+        let label = format!("Delete {}", m.name);
+        self.backup(label.as_str()).await?;
+        let before = self.snapshot().await?;
+        match self.data.delete_combat_modifier(m).await {
+            Ok(_) => self.record(label, before).await,
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Delete the specified empire, with everything kept for it. Systems it
+    /// owned become unowned. Refused while it still has forces in service.
+    pub async fn delete_empire(&self, emp: &Empire) -> Result<(), String> {
+        let label = format!("Delete {}", emp.name);
+        self.backup(label.as_str()).await?;
+        let before = self.snapshot().await?;
+        match self.data.delete_empire(emp).await {
+            Ok(_) => self.record(label, before).await,
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Forget an empire's identification of a ship class.
+    pub async fn delete_identification(&self, item: &Identification) -> Result<(), String> {
+        match self.data.delete_identification(item).await {
+            Ok(_) => Ok(()),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Remove an intel item from its packet.
+    pub async fn delete_intel(&self, item: &IntelItem) -> Result<(), String> {
+        let label = "Delete intel".to_string();
+        self.backup(label.as_str()).await?;
+        let before = self.snapshot().await?;
+        match self.data.delete_intel(item).await {
+            Ok(_) => self.record(label, before).await,
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Delete the specified jump lane.
+    pub async fn delete_lane(&self, lane: &Lane) -> Result<(), String> {
+        let label = format!("Delete lane {}-{}", lane.a_name, lane.b_name);
+        self.backup(label.as_str()).await?;
+        let before = self.snapshot().await?;
+        match self.data.delete_lane(lane).await {
+            Ok(_) => self.record(label, before).await,
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Remove an order.
+    pub async fn delete_order(&self, order: &Order) -> Result<(), String> {
+        let label = "Delete order".to_string();
+        self.backup(label.as_str()).await?;
+        let before = self.snapshot().await?;
+        match self.data.delete_order(order).await {
+            Ok(_) => self.record(label, before).await,
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Remove an order template.
+    pub async fn delete_order_template(&self, tmpl: &OrderTemplate) -> Result<(), String> {
+        let label = format!("Delete {} template", tmpl.name);
+        self.backup(label.as_str()).await?;
+        let before = self.snapshot().await?;
+        match self.data.delete_order_template(tmpl).await {
+            Ok(_) => self.record(label, before).await,
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Remove a ship class. Classes with ships still in service cannot be
+    /// removed.
+    pub async fn delete_ship_type(&self, stype: &ShipType) -> Result<(), String> {
+        let ships = self.ships_by_class(stype.id).await?;
+        if !ships.is_empty() {
+            return Err(format!(
+                "{} ships of the {} class are still in service",
+                ships.len(),
+                stype.class
+            ));
+        }
+        let label = format!("Delete {}", stype.class);
+        self.backup(label.as_str()).await?;
+        let before = self.snapshot().await?;
+        match self.data.delete_ship_type(stype).await {
+            Ok(_) => self.record(label, before).await,
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Delete the specified system, with everything kept for it. Refused
+    /// while fleets or flights are there.
+    pub async fn delete_system(&self, sys: &System) -> Result<(), String> {
+        let label = format!("Delete {}", sys.name);
+        self.backup(label.as_str()).await?;
+        let before = self.snapshot().await?;
+        match self.data.delete_system(sys).await {
+            Ok(_) => self.record(label, before).await,
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Create a new campaign from an archive file written by `export`. The
+    /// campaign takes the name stored in the archive.
+    pub async fn import(path: &str) -> Result<Self, String> {
+        let text = match fs::read_to_string(path) {
+            Ok(t) => t,
+            Err(e) => return Err(e.to_string()),
+        };
+        let archive: serde_json::Value = match serde_json::from_str(text.as_str()) {
+            Ok(v) => v,
+            Err(e) => return Err(e.to_string()),
+        };
+        archive::check(&archive)?;
+        let name = match archive.get("name").and_then(|n| n.as_str()) {
+            Some(n) => naming::validate(n, &Self::campaigns()?)?,
+            None => return Err("The archive has no campaign name".to_string()),
+        };
+
+        let data = match DataStore::new(name.as_str()).await {
+            Ok(d) => d,
+            Err(e) => return Err(e.to_string()),
+        };
+        if let Err(e) = data.import(&archive).await {
+            // Don't leave a half-imported campaign behind.
+            data.close().await;
+            let _ = DataStore::delete(&Self::data_dir(), name.as_str());
+            return Err(e.to_string());
+        }
+        if let Some(files) = archive.get("files").and_then(|f| f.as_object()) {
+            let dir = DataStore::attachments(&Self::data_dir(), name.as_str());
+            let restored = archive::restore_files(&dir, files);
+            if let Err(e) = restored {
+                data.close().await;
+                let _ = DataStore::delete(&Self::data_dir(), name.as_str());
+                return Err(e);
+            }
+        }
+        let turn = match data.current_turn().await {
+            Ok(i) => i,
+            Err(e) => return Err(e.to_string()),
+        };
+        let entry = LogEntry::new(
+            turn,
+            audit::IMPORT,
+            format!("Imported from {}", path).as_str(),
+        );
+        if let Err(e) = data.insert_log(&[entry]).await {
+            return Err(e.to_string());
+        }
+
+        Ok(Self {
+            name,
+            dir: Self::data_dir(),
+            data,
+            turn,
+            history: RefCell::new(History::default()),
+            cache: RefCell::new(Cache::default()),
+        })
+    }
+
+    /// Create a new campaign with the default options from the sheets of a
+    /// campaign workbook saved as CSV in a folder. See `workbook` for what
+    /// they hold. Nothing is created if any sheet cannot be read or refers to
+    /// something that does not exist.
+    pub async fn import_workbook(name: String, folder: &str) -> Result<Self, String> {
+        let book = workbook::read_folder(folder)?;
+        let c = Self::new(name, &CampaignOptions::default()).await?;
+        let checked = match (c.planet_types().await, c.hulls().await) {
+            (Ok(types), Ok(hulls)) => book.check(&types, &hulls),
+            (Err(e), _) | (_, Err(e)) => Err(e),
+        };
+        let added = match checked {
+            Ok(_) => match c.data.add_workbook(&book).await {
+                Ok(_) => Ok(()),
+                Err(e) => Err(e.to_string()),
+            },
+            Err(e) => Err(e),
+        };
+        if let Err(e) = added {
+            // Don't leave an empty campaign behind.
+            let name = c.name.clone();
+            c.close().await;
+            let _ = DataStore::delete(&Self::data_dir(), name.as_str());
+            return Err(e);
+        }
+        c.write_log(
+            audit::IMPORT,
+            format!("Imported workbook from {}", folder).as_str(),
+        )
+        .await?;
+        Ok(c)
+    }
+
+    /// Write the default report templates into the campaign's templates
+    /// folder, leaving any already there alone, and return the folder.
+    pub fn install_templates(&self) -> Result<path::PathBuf, String> {
+        let dir = DataStore::attachments(&self.dir, &self.name).join(template::FOLDER);
+        for (name, text) in template::DEFAULTS {
+            if !dir.join(name).exists() {
+                self.attach(template::FOLDER, name, text)?;
+            }
+        }
+        Ok(dir)
+    }
+
+    /// Return the intel items attached to an empire's packet for the current
+    /// turn.
+    pub async fn intel(&self, empire: i64) -> Result<Vec<IntelItem>, String> {
+        match self.data.get_intel(empire, self.turn).await {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Launch a planetary assault on a system with ground units carried from
+    /// one of the invader's systems, returning the assault report. If the
+    /// system falls it passes to the invader and the surviving attackers
+    /// garrison it; otherwise they stay where they were.
+    pub async fn invade(
+        &self,
+        from: &System,
+        target: &System,
+        units: &[i64],
+        seed: u64,
+    ) -> Result<String, String> {
+        if from.owner == 0 || from.owner == target.owner {
+            return Err(format!("{} cannot invade {}", from.owner_name, target.name));
+        }
+        let cap = match self.data.get_transport_capacity(from.owner, from.id).await {
+            Ok(c) => c,
+            Err(e) => return Err(e.to_string()),
+        };
+        if (units.len() as i32) > cap {
+            return Err(format!(
+                "Only {} units can be carried from {}",
+                cap, from.name
+            ));
+        }
+        let attackers: Vec<GroundUnit> = self
+            .garrison(from)
+            .await?
+            .into_iter()
+            .filter(|u| units.contains(&u.id))
+            .collect();
+        let defenders = self.garrison(target).await?;
+        let mut dice = Dice::new(seed)
+            .with_context(format!("{} assault on {}", from.owner_name, target.name).as_str());
+        let result = combat::resolve_invasion(
+            target,
+            from.owner_name.as_str(),
+            &attackers,
+            &defenders,
+            self.ruleset().await?,
+            &mut dice,
+        );
+        self.write_rolls(&dice).await?;
+        let landed: Vec<i64> = attackers
+            .iter()
+            .map(|u| u.id)
+            .filter(|id| !result.lost.contains(id))
+            .collect();
+        let mut report = result.report;
+        let invader = if result.captured {
+            // The owner's scorched earth orders are carried out before the
+            // invader takes possession.
+            report.push_str(&self.scorch_earth(target, from.owner).await?);
+            Some(from.owner)
+        } else {
+            None
+        };
+        let applied = self
+            .data
+            .apply_invasion(target.id, invader, &result.lost, &landed)
+            .await;
+        self.cache.borrow_mut().invalidate();
+        if let Err(e) = applied {
+            return Err(e.to_string());
+        }
+        let costs = self
+            .ground_types()
+            .await?
+            .iter()
+            .map(|t| (t.id, t.cost))
+            .collect();
+        let sides = (from.owner, target.owner);
+        let casualties = war::invasion_casualties(
+            self.turn,
+            sides,
+            &attackers,
+            &defenders,
+            &result.lost,
+            &costs,
+        );
+        if target.owner != 0 {
+            if let Err(e) = self.data.insert_wars(&[sides], self.turn).await {
+                return Err(e.to_string());
+            }
+        }
+        match self.data.insert_casualties(&casualties).await {
+            Ok(_) => Ok(report),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Return the GM's journal entries, in turn order.
+    pub async fn journal(&self) -> Result<Vec<JournalEntry>, String> {
+        match self.data.get_journal().await {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Return the journal entry for a turn, which is empty if none has been
+    /// written.
+    pub async fn journal_entry(&self, turn: i32) -> Result<JournalEntry, String> {
+        match self.data.get_journal_entry(turn).await {
+            Ok(e) => Ok(e.unwrap_or_else(|| JournalEntry::new(turn, ""))),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Return an empire's ledger of manual treasury and tech changes.
+    pub async fn ledger(&self, empire: i64) -> Result<Vec<LedgerEntry>, String> {
+        match self.data.get_ledger(empire).await {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Return the locale numbers and dates are written in.
+    pub async fn locale(&self) -> Result<&'static Locale, String> {
+        Ok(self.options().await?.locale())
+    }
+
+    /// Return the audit log, oldest entry first.
+    pub async fn log(&self) -> Result<Vec<LogEntry>, String> {
+        match self.data.get_log().await {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Return the jump lanes.
+    pub async fn lanes(&self) -> Result<Vec<Lane>, String> {
+        match self.data.get_lanes().await {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Load the ground unit types, hulls and planet types of a setting module
+    /// file, returning the setting's name.
+    pub async fn load_setting(&self, file: &str) -> Result<String, String> {
+        let pack = setting::read_from_file(file)?;
+        let before = self.snapshot().await?;
+        if let Err(e) = self.data.load_setting(&pack).await {
+            return Err(e.to_string());
+        }
+        self.record(format!("Load {} setting", pack.name), before)
+            .await?;
+        Ok(pack.name)
+    }
+
+    /// Read the campaign's setting module file again, picking up edits made
+    /// since it was loaded, and return the setting's name. The module is
+    /// checked in full before anything is replaced, so a broken edit leaves
+    /// the campaign as it was.
+    pub async fn reload_setting(&self) -> Result<String, String> {
+        let file = self.options().await?.setting_file;
+        if file.is_empty() {
+            return Err("The campaign uses the standard setting, with no module file".to_string());
+        }
+        self.load_setting(file.as_str()).await
+    }
+
+    /// Return the maintenance breakdown for an empire's ships.
+    #[allow(unused)]
+    pub async fn maintenance(&self, empire: i64) -> Result<Maintenance, String> {
+        match self.data.get_maintenance(empire).await {
+            Ok(m) => Ok(m),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Return the GM's summary of the forces present at a system.
+    pub async fn military_summary(&self, sys: &System) -> Result<String, String> {
+        let fleets = self.fleet_summaries().await?;
+        let garrison = self.garrison(sys).await?;
+        Ok(report::military_summary(sys, &fleets, &garrison))
+    }
+
+    /// Return each empire's empire-wide morale modifier, leaving out
+    /// empires without one.
+    pub async fn morale_modifiers(&self) -> Result<BTreeMap<i64, i32>, String> {
+        match self.data.get_morale_modifiers().await {
+            Ok(m) => Ok(m),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Mothball ships at their empire's systems, or return mothballed ships
+    /// to service.
+    pub async fn mothball_ships(&self, ships: &[ShipLocation], moth: bool) -> Result<(), String> {
+        let action = if moth {
+            shipyard::MOTHBALL
+        } else {
+            shipyard::ACTIVATE
+        };
+        let systems = self.systems().await?;
+        for ship in ships {
+            match systems.iter().find(|s| s.id == ship.location) {
+                Some(sys) => shipyard::check(action, ship, sys)?,
+                None => return Err(format!("{} #{} is not in any system", ship.class, ship.id)),
+            };
+        }
+        let ids: Vec<i64> = ships.iter().map(|s| s.id).collect();
+        let before = self.snapshot().await?;
+        match self.data.set_mothballed(&ids, moth).await {
+            Ok(_) => {
+                self.record(format!("{} {} ships", action, ids.len()), before)
+                    .await
+            }
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Merge one fleet into another. Both must belong to the same empire and
+    /// be in the same system.
+    pub async fn merge_fleets(&self, from: i64, into: i64) -> Result<(), String> {
+        if from == into {
+            return Err("Cannot merge a fleet into itself".to_string());
+        }
+        let a = match self.data.get_fleet(from).await {
+            Ok(f) => f,
+            Err(e) => return Err(e.to_string()),
+        };
+        let b = match self.data.get_fleet(into).await {
+            Ok(f) => f,
+            Err(e) => return Err(e.to_string()),
+        };
+        if a.owner != b.owner || a.location != b.location {
+            return Err(format!(
+                "{} and {} must have the same owner and location to merge",
+                a.name, b.name
+            ));
+        }
+        match self.data.merge_fleets(from, into).await {
+            Ok(_) => Ok(()),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Campaign name.
+    pub fn name(&self) -> &String {
+        &self.name
+    }
+
+    /// Create a new campaign with the given options, on its starting turn.
+    /// The name must pass `naming::validate`.
+    pub async fn new(name: String, options: &CampaignOptions) -> Result<Self, String> {
+        let name = naming::validate(&name, &Self::campaigns()?)?;
+        options.validate()?;
+        let data = match DataStore::new(name.as_str()).await {
+            Ok(d) => d,
+            Err(e) => return Err(e.to_string()),
+        };
+        if let Err(e) = data.set_campaign_options(options).await {
+            return Err(e.to_string());
+        }
+        if let Err(e) = data.set_turn(options.start_turn).await {
+            return Err(e.to_string());
+        }
+
+        Ok(Self {
+            name: name.to_owned(),
+            dir: Self::data_dir(),
+            data,
+            turn: options.start_turn,
+            history: RefCell::new(History::default()),
+            cache: RefCell::new(Cache::default()),
+        })
+    }
+
+    /// Open an existing campaign.
+    pub async fn open(name: &str) -> Result<Self, String> {
+        Self::open_in(Self::data_dir(), name).await
+    }
+
+    /// Open a campaign database wherever it is kept. Its attached files and
+    /// backups are kept beside it, and the folder campaigns are kept in is
+    /// left as it is.
+    pub async fn open_file(file: &path::Path) -> Result<Self, String> {
+        if file.extension().is_none_or(|e| e != "db") {
+            return Err(format!("{} is not a campaign database", file.display()));
+        }
+        let file = fs::canonicalize(file).map_err(|e| format!("{}: {}", file.display(), e))?;
+        match (file.parent(), file.file_stem().and_then(|s| s.to_str())) {
+            (Some(dir), Some(stem)) => {
+                Self::open_in(dir.to_path_buf(), &stem.replace('_', " ")).await
+            }
+            _ => Err(format!("{} is not a campaign database", file.display())),
+        }
+    }
+
+    // Open the campaign of the given name kept in a folder.
+    async fn open_in(dir: path::PathBuf, name: &str) -> Result<Self, String> {
+        let data = match DataStore::open_file(&DataStore::path(&dir, name)).await {
+            Ok(d) => d,
+            Err(e) => return Err(e.to_string()),
+        };
+        let turn = match data.current_turn().await {
+            Ok(i) => i,
+            Err(e) => return Err(e.to_string()),
+        };
+
+        Ok(Self {
+            name: name.to_owned(),
+            dir,
+            data,
+            turn,
+            history: RefCell::new(History::default()),
+            cache: RefCell::new(Cache::default()),
+        })
+    }
+
+    /// Return the espionage operations ordered this turn.
+    pub async fn operations(&self) -> Result<Vec<Operation>, String> {
+        match self.data.get_operations(self.turn).await {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Return the options the campaign was created with.
+    pub async fn options(&self) -> Result<CampaignOptions, String> {
+        match self.data.get_campaign_options().await {
+            Ok(o) => Ok(o),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Work out the map overlays, showing fog of war for the given empire if
+    /// any. Battles are those of the last turn resolved.
+    pub async fn overlays(&self, empire: Option<i64>) -> Result<Layers, String> {
+        let systems = self.systems().await?;
+        let lanes = self.lanes().await?;
+        let routes = trade::routes(&self.treaties().await?, &systems, &lanes);
+        let battles = match self.data.get_battle_sites(self.turn - 1).await {
+            Ok(v) => v,
+            Err(e) => return Err(e.to_string()),
+        };
+        Ok(Layers::new(
+            &systems,
+            &lanes,
+            &self.fleet_summaries().await?,
+            &self.facilities().await?,
+            &routes,
+            &battles,
+            empire,
+        ))
+    }
+
+    /// Return an empire's order templates.
+    pub async fn order_templates(&self, empire: i64) -> Result<Vec<OrderTemplate>, String> {
+        match self.data.get_order_templates(empire).await {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Return an empire's orders for the current turn.
+    pub async fn orders(&self, empire: i64) -> Result<Vec<Order>, String> {
+        match self.data.get_orders(empire, self.turn).await {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Economic order for the current turn spending EP on research, or on
+    /// developing one of the empire's systems.
+    pub fn economic_order(
+        &self,
+        empire: i64,
+        sys: Option<&System>,
+        amount: i32,
+    ) -> Result<Order, String> {
+        orders::economic(empire, self.turn, sys, amount)
+    }
+
+    /// Construction order for the current turn building ships of one of the
+    /// empire's current classes at one of its systems.
+    pub fn construction_order(
+        &self,
+        empire: i64,
+        sys: &System,
+        class: &ShipType,
+        count: i32,
+    ) -> Result<Order, String> {
+        orders::construction(empire, self.turn, sys, class, count)
+    }
+
+    /// Construction order for the current turn repairing, mothballing,
+    /// reactivating or scrapping one of the empire's ships.
+    pub async fn shipyard_order(
+        &self,
+        empire: i64,
+        ship: &ShipLocation,
+        action: &str,
+    ) -> Result<Order, String> {
+        let systems = self.systems().await?;
+        match systems.iter().find(|s| s.id == ship.location) {
+            Some(sys) => orders::shipyard(empire, self.turn, ship, sys, action),
+            None => Err(format!("{} #{} is not in any system", ship.class, ship.id)),
+        }
+    }
+
+    /// Movement order for the current turn sending one of the empire's
+    /// fleets along a route through the given systems, which must follow
+    /// the jump lanes within the fleet's allowance.
+    pub async fn movement_order(
+        &self,
+        empire: i64,
+        fleet: &FleetSummary,
+        route: &[System],
+    ) -> Result<Order, String> {
+        let mut ids = vec![fleet.location];
+        ids.extend(route.iter().map(|s| s.id));
+        self.validate_move(fleet.id, &ids).await?;
+        let names: Vec<String> = route.iter().map(|s| s.name.clone()).collect();
+        orders::movement(empire, self.turn, fleet, &names)
+    }
+
+    /// Movement order for the current turn sending a fleet to a system by the
+    /// shortest way along the lanes. A destination beyond the fleet's reach
+    /// this turn is reached over the turns that follow.
+    pub async fn destination_order(
+        &self,
+        empire: i64,
+        fleet: &FleetSummary,
+        dest: &System,
+    ) -> Result<Order, String> {
+        let lanes = self.lanes().await?;
+        if movement::path(&lanes, fleet.location, dest.id).is_none() {
+            return Err(format!(
+                "No way from {} to {}",
+                fleet.location_name, dest.name
+            ));
+        }
+        orders::movement(empire, self.turn, fleet, std::slice::from_ref(&dest.name))
+    }
+
+    /// Diplomacy order for the current turn proposing a treaty to another
+    /// empire.
+    pub async fn diplomacy_order(
+        &self,
+        empire: i64,
+        other: &Empire,
+        ttype: &str,
+    ) -> Result<Order, String> {
+        let empires = self.empires().await?;
+        if let Some(emp) = empires.iter().find(|e| e.id == empire) {
+            government::check_treaty(emp, other, ttype)?;
+        }
+        let treaties = self.treaties().await?;
+        orders::diplomacy(empire, self.turn, other, ttype, &treaties)
+    }
+
+    /// Order an empire to develop a system, or to found a colony at an
+    /// unowned one, this turn. The cost is paid from the empire's treasury
+    /// at once; the attempt is made in the construction phase.
+    pub async fn order_development(
+        &self,
+        empire: &Empire,
+        sys: &System,
+        target: &str,
+    ) -> Result<Development, String> {
+        development::check(sys, empire.id, target)?;
+        if target == development::COLONY {
+            match self.data.get_colony_ship(empire.id, sys.id).await {
+                Ok(Some(_)) => (),
+                Ok(None) => {
+                    return Err(format!(
+                        "{} has no colony ship at {}",
+                        empire.name, sys.name
+                    ))
+                }
+                Err(e) => return Err(e.to_string()),
+            }
+        }
+        let amount = development::cost(sys, target);
+        let mut d = Development::new(empire.id, self.turn, sys.id, target, amount);
+        let before = self.snapshot().await?;
+        match self.data.insert_development(&d).await {
+            Ok(id) => {
+                d.id = id;
+                d.empire_name = empire.name.clone();
+                d.system_name = sys.name.clone();
+                self.record(format!("{} development at {}", target, sys.name), before)
+                    .await?;
+                Ok(d)
+            }
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Put an empire's intel points into an espionage mission against
+    /// another empire this turn, or into counter-espionage, whose target is
+    /// the empire itself. The points are paid from the empire's treasury at
+    /// once; the operation is resolved in the intelligence phase.
+    pub async fn order_operation(
+        &self,
+        empire: &Empire,
+        target: &Empire,
+        mission: &str,
+        points: i32,
+    ) -> Result<Operation, String> {
+        if !espionage::MISSIONS.contains(&mission) {
+            return Err(format!("There is no {} mission", mission));
+        }
+        if points < 1 {
+            return Err("An operation needs at least one intel point".to_string());
+        }
+        if (mission == espionage::COUNTER_ESPIONAGE) != (target.id == empire.id) {
+            return Err(format!(
+                "{} cannot target {} with {}",
+                empire.name, target.name, mission
+            ));
+        }
+        let mut op = Operation::new(empire.id, self.turn, target.id, mission, points);
+        let before = self.snapshot().await?;
+        match self.data.insert_operation(&op).await {
+            Ok(id) => {
+                op.id = id;
+                op.empire_name = empire.name.clone();
+                op.target_name = target.name.clone();
+                self.record(format!("{} {}", empire.name, mission), before)
+                    .await?;
+                Ok(op)
+            }
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Order a raid by an empire's ships at an enemy system for the current
+    /// turn, returning it with its ID filled in. The strategic warfare rules
+    /// must be in use.
+    pub async fn order_raid(
+        &self,
+        empire: i64,
+        sys: &System,
+        target: &str,
+    ) -> Result<Raid, String> {
+        if !self.strategic_warfare().await? {
+            return Err("The strategic warfare rules are not in use".to_string());
+        }
+        if !raid::TARGETS.contains(&target) {
+            return Err(format!("Raids cannot target {}", target));
+        }
+        if sys.owner == 0 || sys.owner == empire {
+            return Err(format!("{} is not an enemy system", sys.name));
+        }
+        let mut r = Raid::new(empire, self.turn, sys.id, target);
+        match self.data.insert_raid(&r).await {
+            Ok(id) => {
+                r.id = id;
+                r.system_name = sys.name.clone();
+                Ok(r)
+            }
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Order a crippled ship repaired at the shipyard it is in, paying for
+    /// the repair now. The repair is made in the construction phase as the
+    /// shipyard's capacity allows.
+    pub async fn order_repair(&self, ship: &ShipLocation) -> Result<Build, String> {
+        let systems = self.systems().await?;
+        let sys = match systems.iter().find(|s| s.id == ship.location) {
+            Some(s) => s,
+            None => return Err(format!("{} #{} is not in any system", ship.class, ship.id)),
+        };
+        let name = format!("{} #{}", ship.class, ship.id);
+        let mut build = Build::new(ship.owner, sys.id, construction::REPAIR, ship.id, &name);
+        build.cost = shipyard::check(shipyard::REPAIR, ship, sys)?;
+        self.queue(sys, build).await
+    }
+
+    /// Order the owner of a system to destroy part of it should the system
+    /// fall this turn.
+    pub async fn order_scorch(&self, sys: &System, target: &str) -> Result<ScorchOrder, String> {
+        if !scorch::TARGETS.contains(&target) {
+            return Err(format!("Scorched earth cannot target {}", target));
+        }
+        if sys.owner == 0 {
+            return Err(format!("{} has no owner", sys.name));
+        }
+        let mut o = ScorchOrder::new(sys.owner, self.turn, sys.id, target);
+        let before = self.snapshot().await?;
+        match self.data.insert_scorch_order(&o).await {
+            Ok(id) => {
+                o.id = id;
+                o.empire_name = sys.owner_name.clone();
+                o.system_name = sys.name.clone();
+                self.record(format!("Scorched earth at {}", sys.name), before)
+                    .await?;
+                Ok(o)
+            }
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Return the planet types reference table.
+    pub async fn planet_types(&self) -> Result<Vec<PlanetType>, String> {
+        match self.data.get_planet_types().await {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Produce an empire's player packet for the current turn.
+    pub async fn player_packet(&self, emp: &Empire) -> Result<String, String> {
+        let systems = match self.data.get_systems_by_owner(emp.id).await {
+            Ok(v) => v,
+            Err(e) => return Err(e.to_string()),
+        };
+        let garrisons = match self.data.get_ground_units_by_owner(emp.id).await {
+            Ok(v) => v,
+            Err(e) => return Err(e.to_string()),
+        };
+        let intel = match self.data.get_intel(emp.id, self.turn).await {
+            Ok(v) => v,
+            Err(e) => return Err(e.to_string()),
+        };
+        let known: Vec<i64> = self
+            .identifications(emp.id)
+            .await?
+            .iter()
+            .map(|i| i.stype)
+            .collect();
+        let classes = recognition::visible(emp.id, &self.ship_types().await?, &known);
+        let tmpl = self.template(template::PLAYER_PACKET)?;
+        Ok(report::player_packet(
+            tmpl.as_str(),
+            self.turn,
+            emp,
+            &systems,
+            &garrisons,
+            &classes,
+            &intel,
+            self.ruleset().await?,
+            self.locale().await?,
+        ))
+    }
+
+    /// Return every empire's production for the construction phase.
+    pub async fn production(&self) -> Result<Vec<Production>, String> {
+        Ok(production::summarize(
+            &self.empires().await?,
+            &self.systems().await?,
+            &self.facilities().await?,
+            &self.builds().await?,
+        ))
+    }
+
+    /// Resolve the current turn against a copy of the campaign, returning
+    /// its report and the changes it would make without making them.
+    pub async fn preview_turn(&self) -> Result<Preview, String> {
+        match preview::preview(&self.data, self.turn).await {
+            Ok(p) => Ok(p),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Queue a facility of the given kind for construction at a system, paid
+    /// for by the system's owner.
+    pub async fn queue_facility(&self, sys: &System, kind: &str) -> Result<Build, String> {
+        let spec = facility::check(kind, sys, &self.facilities().await?)?;
+        let queued = self.builds().await?;
+        if queued
+            .iter()
+            .any(|b| b.system == sys.id && b.kind == "Facility" && b.name == kind)
+        {
+            return Err(format!("{} is already building a {}", sys.name, kind));
+        }
+        let mut build = Build::new(sys.owner, sys.id, "Facility", 0, kind);
+        build.cost = spec.cost;
+        build.turns = spec.turns;
+        self.queue(sys, build).await
+    }
+
+    /// Queue a ground unit for construction at a system, paid for by the
+    /// system's owner.
+    pub async fn queue_ground(&self, sys: &System, gtype: &GroundType) -> Result<Build, String> {
+        let mut build = Build::new(sys.owner, sys.id, "Ground", gtype.id, gtype.name.as_str());
+        build.cost = gtype.cost;
+        self.queue(sys, build).await
+    }
+
+    /// Queue a batch of identical ships entered as one line, such as
+    /// "6 x Frigate at Senor Prime".
+    pub async fn queue_batch(&self, line: &str) -> Result<Build, String> {
+        let batch = Batch::parse(line)?;
+        let systems = self.systems().await?;
+        let sys = match systems
+            .iter()
+            .find(|s| s.name.eq_ignore_ascii_case(batch.system.as_str()))
+        {
+            Some(s) => s,
+            None => return Err(format!("Unknown system {}", batch.system)),
+        };
+        let types = self.ship_types().await?;
+        let stype = match types
+            .iter()
+            .find(|t| t.empire == sys.owner && t.class.eq_ignore_ascii_case(batch.class.as_str()))
+        {
+            Some(t) => t,
+            None => return Err(format!("{} has no {} class", sys.owner_name, batch.class)),
+        };
+        self.queue_ships(sys, stype, batch.count).await
+    }
+
+    /// Queue a ship for construction at a system. The ship's class must
+    /// belong to the system's owner.
+    pub async fn queue_ship(&self, sys: &System, stype: &ShipType) -> Result<Build, String> {
+        self.queue_ships(sys, stype, 1).await
+    }
+
+    /// Queue several ships of one class for construction at a system as a
+    /// single build.
+    pub async fn queue_ships(
+        &self,
+        sys: &System,
+        stype: &ShipType,
+        count: i32,
+    ) -> Result<Build, String> {
+        if count < 1 {
+            return Err("At least one ship must be built".to_string());
+        }
+        if stype.empire != sys.owner {
+            return Err(format!(
+                "{} cannot build the {} class",
+                sys.name, stype.class
+            ));
+        }
+        if stype.retired {
+            return Err(format!("The {} class is retired", stype.class));
+        }
+        let hull = match self.data.get_hull(stype.hull.as_str()).await {
+            Ok(h) => h,
+            Err(_) => return Err(format!("Unknown hull {}", stype.hull)),
+        };
+        let tech = self.tech_for_hull(sys.owner, &hull).await?;
+        let mut build = Build::new(sys.owner, sys.id, "Ship", stype.id, stype.class.as_str());
+        build.cost = tech::ship_cost(stype.construction_cost(&hull), &hull, tech) * count;
+        build.count = count;
+        build.turns = construction::ship_build_turns(&hull);
+        self.queue(sys, build).await
+    }
+
+    /// Raise a ground unit in a system's garrison.
+    pub async fn raise_ground_unit(&self, sys: &System, gtype: &GroundType) -> Result<(), String> {
+        match self.data.insert_ground_unit(gtype.id, sys.id).await {
+            Ok(_) => Ok(()),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Return the raids ordered for the current turn.
+    pub async fn raids(&self) -> Result<Vec<Raid>, String> {
+        match self.data.get_raids(self.turn).await {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Resolve a battle between the fleets at a system, applying the damage
+    /// to the ships and returning the battle report.
+    ///
+    /// Each side fights under the combat modifiers for the conditions chosen
+    /// for it, by category and name, and for those it is found in: out of
+    /// supply, or with nothing but crippled ships. The rolls follow from the
+    /// campaign's dice seed, the turn and the system, or from the seed given
+    /// in a campaign without one.
+    pub async fn resolve_battle(
+        &self,
+        sys: &System,
+        chosen: &BTreeMap<i64, Vec<(String, String)>>,
+        seed: u64,
+    ) -> Result<String, String> {
+        self.fight(sys, None, chosen, seed).await
+    }
+
+    /// Resolve an assault on a system through the defended jump points of a
+    /// lane into it, as a battle in which the system's owner fires first. The
+    /// sides' modifiers and the rolls are worked out as for `resolve_battle`.
+    pub async fn resolve_assault(
+        &self,
+        sys: &System,
+        lane: &Lane,
+        chosen: &BTreeMap<i64, Vec<(String, String)>>,
+        seed: u64,
+    ) -> Result<String, String> {
+        let from = match lane.other_end(sys.id) {
+            Some(_) if lane.a == sys.id => lane.b_name.as_str(),
+            Some(_) => lane.a_name.as_str(),
+            None => return Err(format!("The lane does not lead to {}", sys.name)),
+        };
+        if !lane.defended {
+            return Err(format!(
+                "The lane from {} has no defended jump points",
+                from
+            ));
+        }
+        if sys.owner == 0 {
+            return Err(format!(
+                "{} has no owner to defend its jump points",
+                sys.name
+            ));
+        }
+        self.fight(sys, Some(from), chosen, seed).await
+    }
+
+    /// Stop a fleet escorting another.
+    pub async fn release_escort(&self, escort: &Escort) -> Result<(), String> {
+        let label = format!(
+            "{} stops escorting {}",
+            escort.escort_name, escort.convoy_name
+        );
+        let before = self.snapshot().await?;
+        match self.data.delete_escort(escort).await {
+            Ok(_) => self.record(label, before).await,
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Return how the crippled ships waiting for repair would fare if the
+    /// construction phase were resolved now.
+    pub async fn repair_plan(&self) -> Result<RepairPlan, String> {
+        let systems = self.systems().await?;
+        match self.data.get_repair_jobs().await {
+            Ok(v) => Ok(repair::plan(
+                &v,
+                &repair::capacity(&systems, &self.facilities().await?),
+            )),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Repair a crippled facility, charging its owner what repairing a ship
+    /// of the same cost would.
+    pub async fn repair_facility(&self, f: &Facility) -> Result<(), String> {
+        if !f.crip {
+            return Err(format!(
+                "The {} at {} is not crippled",
+                f.kind, f.system_name
+            ));
+        }
+        let cost = repair::cost(facility::spec(f.kind.as_str()).map_or(0, |s| s.cost));
+        let empires = self.empires().await?;
+        let emp = match empires.iter().find(|e| e.id == f.owner) {
+            Some(e) => e,
+            None => return Err(format!("{} has no owner to pay", f.system_name)),
+        };
+        if emp.treasury < cost {
+            return Err(format!(
+                "{} cannot afford {} EP with {} EP in the treasury",
+                emp.name, cost, emp.treasury
+            ));
+        }
+        let before = self.snapshot().await?;
+        let repaired = Facility {
+            crip: false,
+            ..f.clone()
+        };
+        if let Err(e) = self.data.adjust_treasury(emp.id, -cost).await {
+            return Err(e.to_string());
+        }
+        if let Err(e) = self.data.update_facility(&repaired).await {
+            return Err(e.to_string());
+        }
+        self.record(format!("Repair {} at {}", f.kind, f.system_name), before)
+            .await
+    }
+
+    /// Copy an empire's orders from the previous turn into the current turn,
+    /// returning the new orders.
+    pub async fn repeat_last_orders(&self, empire: i64) -> Result<Vec<Order>, String> {
+        let last = match self.data.get_orders(empire, self.turn - 1).await {
+            Ok(v) => v,
+            Err(e) => return Err(e.to_string()),
+        };
+        if last.is_empty() {
+            return Err("No orders were given last turn".to_string());
+        }
+        let orders: Vec<Order> = last.iter().map(|o| o.for_turn(self.turn)).collect();
+        match self.data.insert_orders(&orders).await {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Redo the last undone edit, returning what it was, or None if there
+    /// is nothing to redo.
+    pub async fn redo(&mut self) -> Result<Option<String>, String> {
+        let current = self.snapshot().await?;
+        let step = self.history.borrow_mut().redo(&current)?;
+        match step {
+            Some(s) => {
+                self.restore(&s.after).await?;
+                self.write_log(audit::EDIT, format!("Redo {}", s.label).as_str())
+                    .await?;
+                Ok(Some(s.label))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Description of the edit that would be redone, if any.
+    pub fn redo_label(&self) -> Option<String> {
+        self.history.borrow().redo_label().map(|l| l.to_string())
+    }
+
+    /// Make new connections to the campaign's database, as when it was out
+    /// of reach for a while.
+    pub async fn reconnect(&mut self) -> Result<(), String> {
+        match self.data.reconnect().await {
+            Ok(_) => Ok(()),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Rename the campaign, with its attached files and backups. The new
+    /// name must pass `naming::validate`, though it may differ from the old
+    /// only in case.
+    pub async fn rename(&mut self, new: &str) -> Result<(), String> {
+        let others: Vec<String> = Self::campaigns()?
+            .into_iter()
+            .filter(|c| *c != self.name)
+            .collect();
+        let new = naming::validate(new, &others)?;
+        self.data.close().await;
+        let renamed = DataStore::rename(&self.dir, self.name.as_str(), new.as_str());
+        if renamed.is_ok() {
+            self.name = new;
+        }
+        self.data = match DataStore::open_file(&DataStore::path(&self.dir, &self.name)).await {
+            Ok(d) => d,
+            Err(e) => return Err(e.to_string()),
+        };
+        if let Err(e) = renamed {
+            return Err(e.to_string());
+        }
+        self.write_log(audit::EDIT, format!("Renamed to {}", self.name).as_str())
+            .await
+    }
+
+    /// Roll the campaign back to one of its backup snapshots. The current
+    /// state is backed up first, so the restore can itself be reversed.
+    pub async fn restore_backup(&mut self, backup: &str) -> Result<(), String> {
+        self.backup(format!("Before restoring {}", backup).as_str())
+            .await?;
+        self.data.close().await;
+        let restored = DataStore::restore_backup(&self.dir, self.name.as_str(), backup);
+        self.data = match DataStore::open_file(&DataStore::path(&self.dir, &self.name)).await {
+            Ok(d) => d,
+            Err(e) => return Err(e.to_string()),
+        };
+        if let Err(e) = restored {
+            return Err(e.to_string());
+        }
+        self.history.borrow_mut().clear();
+        self.cache.borrow_mut().invalidate();
+        self.turn = match self.data.current_turn().await {
+            Ok(t) => t,
+            Err(e) => return Err(e.to_string()),
+        };
+        self.write_log(
+            audit::EDIT,
+            format!("Restored snapshot {}", backup).as_str(),
+        )
+        .await
+    }
+
+    /// Retire a ship class, so no more can be built while the ships already
+    /// in service remain, or return it to production.
+    pub async fn retire_ship_type(&self, stype: &ShipType, retired: bool) -> Result<(), String> {
+        let label = format!(
+            "{} {}",
+            if retired { "Retire" } else { "Reinstate" },
+            stype.class
+        );
+        let before = self.snapshot().await?;
+        let stype = ShipType {
+            retired,
+            ..stype.clone()
+        };
+        match self.data.update_ship_type(&stype).await {
+            Ok(_) => self.record(label, before).await,
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Return the rules reference for the campaign's rules data.
+    pub async fn rules_reference(&self) -> Result<Vec<RefEntry>, String> {
+        let modifiers = self.combat_modifiers().await?;
+        let hulls = match self.data.get_hulls().await {
+            Ok(v) => v,
+            Err(e) => return Err(e.to_string()),
+        };
+        let ground_types = match self.data.get_ground_types().await {
+            Ok(v) => v,
+            Err(e) => return Err(e.to_string()),
+        };
+        let options = self.options().await?;
+        Ok(rules::reference(
+            &options,
+            &modifiers,
+            &hulls,
+            &ground_types,
+        ))
+    }
+
+    /// Return the ruleset of the campaign's edition.
+    pub async fn ruleset(&self) -> Result<&'static dyn Ruleset, String> {
+        Ok(self.options().await?.ruleset())
+    }
+
+    /// Copy the campaign to a new one, with its attached files, and open the
+    /// copy, leaving this campaign as it is. The name must pass
+    /// `naming::validate`.
+    pub async fn save_as(&self, new: &str) -> Result<Self, String> {
+        let new = naming::validate(new, &Self::campaigns()?)?;
+        let saved = self
+            .data
+            .save_as(&self.dir, self.name.as_str(), new.as_str());
+        if let Err(e) = saved.await {
+            return Err(e.to_string());
+        }
+        let copy = Self::open_in(self.dir.clone(), new.as_str()).await?;
+        copy.write_log(
+            audit::EDIT,
+            format!("Copied from {} on turn {}", self.name, self.turn).as_str(),
+        )
+        .await?;
+        Ok(copy)
+    }
+
+    /// Save an empire's orders for the current turn as a named template.
+    pub async fn save_order_template(
+        &self,
+        empire: i64,
+        name: &str,
+    ) -> Result<OrderTemplate, String> {
+        let orders = self.orders(empire).await?;
+        if orders.is_empty() {
+            return Err("There are no orders to save".to_string());
+        }
+        let mut tmpl = OrderTemplate::new(empire, name, &orders);
+        match self.data.insert_order_template(&tmpl).await {
+            Ok(id) => {
+                tmpl.id = id;
+                Ok(tmpl)
+            }
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Turn the optional colonial administration rule on or off.
+    pub async fn set_colonial_admin(&self, on: bool) -> Result<(), String> {
+        match self.data.set_rule_option(economy::COLONIAL_ADMIN, on).await {
+            Ok(_) => Ok(()),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Set the system a fleet is bound for, which it makes for by the
+    /// shortest way along the lanes over the turns that follow, or clear its
+    /// course.
+    pub async fn set_course(
+        &self,
+        fleet: &FleetSummary,
+        dest: Option<&System>,
+    ) -> Result<(), String> {
+        let course = match dest {
+            Some(d) => match movement::path(&self.lanes().await?, fleet.location, d.id) {
+                Some(path) => path,
+                None => return Err(format!("No way from {} to {}", fleet.location_name, d.name)),
+            },
+            None => Vec::new(),
+        };
+        let label = match dest {
+            Some(d) => format!("Send {} to {}", fleet.name, d.name),
+            None => format!("Clear the course of {}", fleet.name),
+        };
+        let before = self.snapshot().await?;
+        match self.data.set_course(fleet.id, &course).await {
+            Ok(_) => self.record(label, before).await,
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Set the seed the dice of every turn take in, 0 for none.
+    pub async fn set_dice_seed(&self, seed: u64) -> Result<(), String> {
+        let mut options = self.options().await?;
+        options.dice_seed = seed;
+        match self.data.set_campaign_options(&options).await {
+            Ok(_) => Ok(()),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Set the locale numbers and dates are written in.
+    pub async fn set_locale(&self, tag: &str) -> Result<(), String> {
+        let mut options = self.options().await?;
+        options.locale = tag.to_string();
+        options.validate()?;
+        match self.data.set_campaign_options(&options).await {
+            Ok(_) => Ok(()),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Set how far into deficit an empire's treasury may be taken by hand.
+    pub async fn set_deficit_limit(&self, limit: i32) -> Result<(), String> {
+        if limit < 0 {
+            return Err("The deficit limit cannot be negative".to_string());
+        }
+        match self
+            .data
+            .set_rule_value(economy::DEFICIT_LIMIT, limit)
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Write the journal entry for a turn. Clearing the text removes the
+    /// entry.
+    pub async fn set_journal_entry(&self, turn: i32, text: &str) -> Result<(), String> {
+        let before = self.snapshot().await?;
+        match self
+            .data
+            .set_journal_entry(&JournalEntry::new(turn, text))
+            .await
+        {
+            Ok(_) => {
+                self.record(format!("Journal for turn {}", turn), before)
+                    .await
+            }
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Return the scorched earth orders standing at a system this turn.
+    pub async fn scorch_orders(&self, sys: &System) -> Result<Vec<ScorchOrder>, String> {
+        match self.data.get_scorch_orders(sys.id, self.turn).await {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    // Carry out the owner's scorched earth orders at a falling system,
+    // returning what was done. Breaking the owner's treaties with the
+    // invader and telling every other empire are the diplomatic cost.
+    async fn scorch_earth(&self, target: &System, invader: i64) -> Result<String, String> {
+        let orders: Vec<ScorchOrder> = self
+            .scorch_orders(target)
+            .await?
+            .into_iter()
+            .filter(|o| o.empire == target.owner)
+            .collect();
+        if orders.is_empty() {
+            return Ok(String::new());
+        }
+        let mut sys = target.clone();
+        let targets: Vec<&str> = orders.iter().map(|o| o.target.as_str()).collect();
+        let mut report = scorch::destroy(&mut sys, &targets);
+        if let Err(e) = self.data.apply_scorched_earth(&sys, &orders).await {
+            return Err(e.to_string());
+        }
+
+        for t in self.treaties().await? {
+            if (t.a == target.owner && t.b == invader) || (t.a == invader && t.b == target.owner) {
+                if let Err(e) = self.data.delete_treaty(&t).await {
+                    return Err(e.to_string());
+                }
+                report.push_str(&format!("The {} was broken\n", t.describe()));
+            }
+        }
+        let notice = format!(
+            "{} forces laid waste to {} before it fell",
+            target.owner_name, target.name
+        );
+        for emp in self.empires().await? {
+            if emp.id == target.owner {
+                continue;
+            }
+            if let Err(e) = self
+                .data
+                .insert_intel(&IntelItem::new(emp.id, self.turn, intel::NOTICE, &notice))
+                .await
+            {
+                return Err(e.to_string());
+            }
+        }
+        Ok(report)
+    }
+
+    /// Set the priority of a crippled ship for repair. Higher priorities
+    /// are repaired first when a system cannot repair every ship.
+    pub async fn set_repair_priority(&self, job: &RepairJob, priority: i32) -> Result<(), String> {
+        let before = self.snapshot().await?;
+        match self.data.set_repair_priority(job.ship, priority).await {
+            Ok(_) => {
+                self.record(
+                    format!("Repair priority of {} #{}", job.class, job.ship),
+                    before,
+                )
+                .await
+            }
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Set an empire's empire-wide morale modifier, applied to every
+    /// system it owns in the morale phase.
+    pub async fn set_morale_modifier(&self, empire: i64, amount: i32) -> Result<(), String> {
+        match self.data.set_morale_modifier(empire, amount).await {
+            Ok(_) => Ok(()),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Set an empire's order deadline for the current turn. The due date is
+    /// in YYYY-MM-DD form.
+    pub async fn set_deadline(&self, empire: i64, due: &str) -> Result<(), String> {
+        if !deadline::is_date(due) {
+            return Err(format!("{} is not a YYYY-MM-DD date", due));
+        }
+        match self.data.set_deadline(empire, self.turn, due).await {
+            Ok(_) => Ok(()),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Set an empire's research spending for the current turn, paid from its
+    /// treasury.
+    pub async fn set_research(&self, empire: i64, amount: i32) -> Result<(), String> {
+        if amount < 0 {
+            return Err("Research spending cannot be negative".to_string());
+        }
+        match self.data.set_research(empire, self.turn, amount).await {
+            Ok(_) => Ok(()),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Keep campaigns in the given folder from now on, creating it if need
+    /// be. Choosing the default folder forgets any other.
+    pub fn set_data_dir(dir: &path::Path) -> Result<(), String> {
+        fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+        let config = Config {
+            data_dir: if dir == config::default_data_dir() {
+                None
+            } else {
+                Some(dir.to_path_buf())
+            },
+        };
+        config.save()
+    }
+
+    /// Turn the optional attrition rules on or off.
+    pub async fn set_attrition(&self, on: bool) -> Result<(), String> {
+        match self.data.set_rule_option(attrition::ATTRITION, on).await {
+            Ok(_) => Ok(()),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Turn the optional strategic warfare rules on or off.
+    pub async fn set_strategic_warfare(&self, on: bool) -> Result<(), String> {
+        match self.data.set_rule_option(raid::STRATEGIC_WARFARE, on).await {
+            Ok(_) => Ok(()),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Scrap a ship at one of its empire's systems, returning the EP it
+    /// recovers.
+    pub async fn scrap_ship(&self, ship: &ShipLocation) -> Result<i32, String> {
+        let systems = self.systems().await?;
+        match systems.iter().find(|s| s.id == ship.location) {
+            Some(sys) => shipyard::check(shipyard::SCRAP, ship, sys)?,
+            None => return Err(format!("{} #{} is not in any system", ship.class, ship.id)),
+        };
+        let label = format!("Scrap {} #{}", ship.class, ship.id);
+        self.backup(label.as_str()).await?;
+        let before = self.snapshot().await?;
+        let value = shipyard::scrap_value(ship);
+        match self.data.scrap_ship(ship, value).await {
+            Ok(_) => {
+                self.record(label, before).await?;
+                Ok(value)
+            }
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Return every ship of a class with its fleet and location.
+    pub async fn ships_by_class(&self, stype: i64) -> Result<Vec<ShipLocation>, String> {
+        match self.data.get_ships_by_class(stype).await {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Return the ship classes of all empires.
+    pub async fn ship_types(&self) -> Result<Vec<ShipType>, String> {
+        match self.data.get_ship_types().await {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Sign a treaty between two empires on the current turn. The treaty
+    /// lapses at the end of the expiration turn, if one is given.
+    pub async fn sign_treaty(
+        &self,
+        a: i64,
+        b: i64,
+        ttype: &str,
+        expires: Option<i32>,
+    ) -> Result<Treaty, String> {
+        if a == b {
+            return Err("A treaty needs two different empires".to_string());
+        }
+        let empires = self.empires().await?;
+        let find = |id: i64| empires.iter().find(|e| e.id == id);
+        if let (Some(a), Some(b)) = (find(a), find(b)) {
+            government::check_treaty(a, b, ttype)?;
+        }
+        if let Some(t) = expires {
+            if t < self.turn {
+                return Err(format!("Turn {} has already passed", t));
+            }
+        }
+        let mut treaty = Treaty::new(a, b, ttype, self.turn, expires);
+        treaty.id = match self.data.insert_treaty(&treaty).await {
+            Ok(id) => id,
+            Err(e) => return Err(e.to_string()),
+        };
+        // Signing any treaty ends a war between the two.
+        match self.data.end_war(a, b, self.turn).await {
+            Ok(_) => Ok(treaty),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Split the given ships off a fleet into a new fleet in the same system,
+    /// returning the new fleet's ID.
+    pub async fn split_fleet(&self, fleet: i64, name: &str, ships: &[i64]) -> Result<i64, String> {
+        if ships.is_empty() {
+            return Err("No ships selected to split off".to_string());
+        }
+        let from = match self.data.get_fleet(fleet).await {
+            Ok(f) => f,
+            Err(e) => return Err(e.to_string()),
+        };
+        match self.data.split_fleet(&from, name, ships).await {
+            Ok(id) => Ok(id),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Return whether the optional attrition rules are in use.
+    pub async fn attrition(&self) -> Result<bool, String> {
+        match self.data.get_rule_option(attrition::ATTRITION).await {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Return whether the optional strategic warfare rules are in use.
+    pub async fn strategic_warfare(&self) -> Result<bool, String> {
+        match self.data.get_rule_option(raid::STRATEGIC_WARFARE).await {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Return an empire's economic statement: where it stands now and the
+    /// income it can expect this turn.
+    pub async fn statement(&self, empire: i64) -> Result<Statement, String> {
+        let standing = match self.data.get_standing(empire, self.turn).await {
+            Ok(s) => s,
+            Err(e) => return Err(e.to_string()),
+        };
+        let systems = match self.data.get_systems_by_owner(empire).await {
+            Ok(v) => v,
+            Err(e) => return Err(e.to_string()),
+        };
+        let income = self.expected_income(empire, &systems).await?;
+        let rules = self.ruleset().await?;
+        Ok(Statement::new(standing, income, &systems, rules))
+    }
+
+    /// Station a flight aboard a carrier or at a base where it is, if there
+    /// is room; a new flight is added there. Returns the flight as
+    /// stationed.
+    pub async fn station_flight(&self, flight: &Flight, to: &Station) -> Result<Flight, String> {
+        let present = self.flights(flight.location).await?;
+        flight::check(flight, to, &present)?;
+        let mut stationed = flight::station(flight, to);
+        let before = self.snapshot().await?;
+        let result = if flight.id == 0 {
+            self.data
+                .insert_flight(&stationed)
+                .await
+                .map(|id| stationed.id = id)
+        } else {
+            self.data.update_flight(&stationed).await
+        };
+        if let Err(e) = result {
+            return Err(e.to_string());
+        }
+        self.record(format!("Station flight at {}", to.name()), before)
+            .await?;
+        Ok(stationed)
+    }
+
+    /// Record that an empire has submitted its orders for the current turn.
+    pub async fn submit_orders(&self, empire: i64) -> Result<(), String> {
+        let now = self.today().await?;
+        match self
+            .data
+            .set_submitted(empire, self.turn, now.as_str())
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Return the systems in the campaign.
+    pub async fn systems(&self) -> Result<Vec<System>, String> {
+        if let Some(v) = self.cache.borrow().systems() {
+            return Ok(v);
+        }
+        match self.data.get_systems().await {
+            Ok(v) => {
+                self.cache.borrow_mut().set_systems(&v);
+                Ok(v)
+            }
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Return the systems owned by an empire, or the unowned systems if the
+    /// owner is zero.
+    pub async fn systems_by_owner(&self, owner: i64) -> Result<Vec<System>, String> {
+        Ok(self
+            .systems()
+            .await?
+            .into_iter()
+            .filter(|s| s.owner == owner)
+            .collect())
+    }
+
+    /// Campaign title including turn number.
+    pub fn title(&self) -> String {
+        format!("{} Turn {}", self.name, self.turn)
+    }
+
+    /// Carry ground units from one system to another. The system owner's
+    /// active ships at the source must have a point of capacity for every
+    /// unit moved.
+    pub async fn transfer_ground_units(
+        &self,
+        from: &System,
+        to: &System,
+        units: &[i64],
+    ) -> Result<(), String> {
+        if from.id == to.id {
+            return Err("Units must be transferred to a different system".to_string());
+        }
+        let cap = match self.data.get_transport_capacity(from.owner, from.id).await {
+            Ok(c) => c,
+            Err(e) => return Err(e.to_string()),
+        };
+        if (units.len() as i32) > cap {
+            return Err(format!(
+                "Only {} units can be carried from {}",
+                cap, from.name
+            ));
+        }
+        match self.data.move_ground_units(units, to.id).await {
+            Ok(_) => Ok(()),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Agree to transfer one of an empire's ship classes to another empire,
+    /// for a price or, if the price is zero, as a gift, returning the
+    /// transfer with its ID filled in. The class is copied to the buyer in
+    /// the diplomacy phase of the current turn.
+    pub async fn transfer_class(
+        &self,
+        class: &ShipType,
+        buyer: i64,
+        price: i32,
+    ) -> Result<Transfer, String> {
+        let mut t = Transfer::class(class.empire, buyer, self.turn, class.id, price);
+        t.item_name = format!("{} class", class.class);
+        self.agree_transfer(t).await
+    }
+
+    /// Agree to transfer one of an empire's ships to another empire, for a
+    /// price or, if the price is zero, as a gift, returning the transfer
+    /// with its ID filled in. The ship changes hands where it is in the
+    /// diplomacy phase of the current turn.
+    pub async fn transfer_ship(
+        &self,
+        ship: &ShipLocation,
+        buyer: i64,
+        price: i32,
+    ) -> Result<Transfer, String> {
+        let mut t = Transfer::ship(ship.owner, buyer, self.turn, ship.id, price);
+        t.item_name = format!("{} #{}", ship.class, ship.id);
+        self.agree_transfer(t).await
+    }
+
+    /// Return the ship and class transfers agreed for the current turn.
+    pub async fn transfers(&self) -> Result<Vec<Transfer>, String> {
+        match self.data.get_transfers(self.turn).await {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Return every empire's research standing for the current turn.
+    pub async fn tech_status(&self) -> Result<Vec<TechStatus>, String> {
+        match self.data.get_tech_status(self.turn).await {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Current local date and time in ISO 8601 form.
+    pub async fn today(&self) -> Result<String, String> {
+        match self.data.today().await {
+            Ok(t) => Ok(t),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Return the treaties between empires.
+    pub async fn treaties(&self) -> Result<Vec<Treaty>, String> {
+        match self.data.get_treaties().await {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Current turn number.
+    pub fn turn(&self) -> i32 {
+        self.turn
+    }
+
+    /// Check an empire's orders for the current turn against the campaign,
+    /// returning a report of those that are illegal and why.
+    pub async fn validate_orders(&self, empire: i64) -> Result<String, String> {
+        let empires = self.empires().await?;
+        let emp = match empires.iter().find(|e| e.id == empire) {
+            Some(e) => e,
+            None => return Err(format!("No empire has ID {}", empire)),
+        };
+        let orders = self.orders(empire).await?;
+        let systems = self.systems().await?;
+        let fleets = self.fleet_summaries().await?;
+        let classes = self.ship_types().await?;
+        let lanes = self.lanes().await?;
+        let treaties = self.treaties().await?;
+        let escorts = self.escorts().await?;
+        let ships = self.empire_ships(empire).await?;
+        let ctx = validation::OrderContext {
+            empire: emp,
+            systems: &systems,
+            fleets: &fleets,
+            classes: &classes,
+            lanes: &lanes,
+            empires: &empires,
+            treaties: &treaties,
+            escorts: &escorts,
+            ships: &ships,
+        };
+        let rejected = validation::check(&orders, &ctx);
+        Ok(validation::report(&emp.name, &rejected))
+    }
+
+    /// Check that a fleet can follow a route this turn. The route lists the
+    /// systems visited, starting with the fleet's current location. A fleet
+    /// with escorts, or escorting another, moves at the pace of the slowest
+    /// fleet in its group.
+    pub async fn validate_move(&self, fleet: i64, route: &[i64]) -> Result<(), String> {
+        let lanes = match self.data.get_lanes().await {
+            Ok(v) => v,
+            Err(e) => return Err(e.to_string()),
+        };
+        let mut allowance = i32::MAX;
+        for id in escort::group(&self.escorts().await?, fleet) {
+            match self.data.get_fleet_allowance(id).await {
+                Ok(a) => allowance = allowance.min(a),
+                Err(e) => return Err(e.to_string()),
+            }
+        }
+        movement::validate_route(&lanes, route, allowance)
+    }
+
+    /// Undo the last edit, returning what it was, or None if there is
+    /// nothing to undo.
+    pub async fn undo(&mut self) -> Result<Option<String>, String> {
+        let current = self.snapshot().await?;
+        let step = self.history.borrow_mut().undo(&current)?;
+        match step {
+            Some(s) => {
+                self.restore(&s.before).await?;
+                self.write_log(audit::EDIT, format!("Undo {}", s.label).as_str())
+                    .await?;
+                Ok(Some(s.label))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Description of the edit that would be undone, if any.
+    pub fn undo_label(&self) -> Option<String> {
+        self.history.borrow().undo_label().map(|l| l.to_string())
+    }
+
+    /// Update the given combat modifier, which must have a valid ID.
+    pub async fn update_combat_modifier(&self, m: &CombatModifier) -> Result<(), String> {
+        let before = self.snapshot().await?;
+        match self.data.update_combat_modifier(m).await {
+            Ok(_) => self.record(format!("Edit {}", m.name), before).await,
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Update the given empire, which must have a valid ID.
+    pub async fn update_empire(&self, emp: &Empire) -> Result<(), String> {
+        emp.check_government()?;
+        let before = self.snapshot().await?;
+        match self.data.update_empire(emp).await {
+            Ok(_) => self.record(format!("Edit {}", emp.name), before).await,
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Update the given fleet, which must have a valid ID. Moving a fleet
+    /// moves the rest of its escort group with it.
+    pub async fn update_fleet(&self, fleet: &Fleet) -> Result<(), String> {
+        let moved = match self.data.get_fleet(fleet.id).await {
+            Ok(f) => f.location != fleet.location,
+            Err(e) => return Err(e.to_string()),
+        };
+        let group: Vec<i64> = escort::group(&self.escorts().await?, fleet.id)
+            .into_iter()
+            .filter(|id| *id != fleet.id)
+            .collect();
+        let before = self.snapshot().await?;
+        if let Err(e) = self.data.update_fleet(fleet).await {
+            return Err(e.to_string());
+        }
+        if moved && !group.is_empty() {
+            if let Err(e) = self.data.move_fleets(&group, fleet.location).await {
+                return Err(e.to_string());
+            }
+        }
+        self.record(format!("Edit {}", fleet.name), before).await
+    }
+
+    /// Update the given jump lane, which must have a valid ID.
+    pub async fn update_lane(&self, lane: &Lane) -> Result<(), String> {
+        let before = self.snapshot().await?;
+        match self.data.update_lane(lane).await {
+            Ok(_) => {
+                self.record(format!("Edit lane {}-{}", lane.a_name, lane.b_name), before)
+                    .await
+            }
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Update a ship class after checking it against its hull. Returns the
+    /// class with its construction cost recalculated.
+    pub async fn update_ship_type(&self, stype: &ShipType) -> Result<ShipType, String> {
+        let stype = self.costed_ship_type(stype).await?;
+        let before = self.snapshot().await?;
+        match self.data.update_ship_type(&stype).await {
+            Ok(_) => {
+                self.record(format!("Edit {}", stype.class), before).await?;
+                Ok(stype)
+            }
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Return every war, ongoing or ended.
+    pub async fn wars(&self) -> Result<Vec<War>, String> {
+        match self.data.get_wars().await {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Update the given system, which must have a valid ID and a known planet
+    /// type.
+    pub async fn update_system(&self, sys: &System) -> Result<(), String> {
+        sys.validate(&self.planet_types().await?)?;
+        let before = self.snapshot().await?;
+        match self.data.update_system(sys).await {
+            Ok(_) => self.record(format!("Edit {}", sys.name), before).await,
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Return a report of what a war has cost each side.
+    pub async fn war_costs(&self, w: &War) -> Result<String, String> {
+        match self.data.get_casualties().await {
+            Ok(v) => Ok(war::report(std::slice::from_ref(w), &v)),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Return a report of every war and what it has cost each side.
+    pub async fn war_report(&self) -> Result<String, String> {
+        let wars = match self.data.get_wars().await {
+            Ok(v) => v,
+            Err(e) => return Err(e.to_string()),
+        };
+        match self.data.get_casualties().await {
+            Ok(v) => Ok(war::report(&wars, &v)),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    // Validate a ship class against its hull and fill in its cost.
+    async fn costed_ship_type(&self, stype: &ShipType) -> Result<ShipType, String> {
+        let hull = match self.data.get_hull(stype.hull.as_str()).await {
+            Ok(h) => h,
+            Err(_) => return Err(format!("Unknown hull {}", stype.hull)),
+        };
+        stype.validate(&hull)?;
+        let tech = self.tech_for_hull(stype.empire, &hull).await?;
+        Ok(ShipType {
+            cost: tech::ship_cost(stype.construction_cost(&hull), &hull, tech),
+            ..stype.clone()
+        })
+    }
+
+    // Record an edit in the undo history, given the tables from before it,
+    // and in the audit log.
+    async fn record(&self, label: String, before: serde_json::Value) -> Result<(), String> {
+        self.cache.borrow_mut().invalidate();
+        let after = self.snapshot().await?;
+        self.history
+            .borrow_mut()
+            .record(label.as_str(), before, after);
+        self.write_log(audit::category(label.as_str()), label.as_str())
+            .await
+    }
+
+    // Add an entry for the current turn to the audit log.
+    async fn write_log(&self, category: &str, text: &str) -> Result<(), String> {
+        match self
+            .data
+            .insert_log(&[LogEntry::new(self.turn, category, text)])
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    // Fight a battle at a system, an assault through the jump points from
+    // another if one is named, and record its results all together.
+    async fn fight(
+        &self,
+        sys: &System,
+        assault_from: Option<&str>,
+        chosen: &BTreeMap<i64, Vec<(String, String)>>,
+        seed: u64,
+    ) -> Result<String, String> {
+        let ships = self.combatants(sys).await?;
+        let rules = self.ruleset().await?;
+        let table = self.combat_modifiers().await?;
+        let supplied = overlay::supply(
+            &self.systems().await?,
+            &self.lanes().await?,
+            &self.facilities().await?,
+        );
+        let mut mods = BTreeMap::new();
+        let found = combat::conditions(&ships, supplied.get(&sys.id).unwrap_or(&BTreeSet::new()));
+        for (owner, mut conditions) in found {
+            if let Some(v) = chosen.get(&owner) {
+                conditions.extend(v.iter().map(|(c, n)| (c.as_str(), n.as_str())));
+            }
+            mods.insert(owner, combat::side_modifier(&table, &conditions));
+        }
+
+        let context = match assault_from {
+            Some(_) => format!("Assault on {}", sys.name),
+            None => format!("Battle at {}", sys.name),
+        };
+        let dice = match self.options().await?.dice_seed {
+            0 => Dice::new(seed),
+            n => Dice::for_turn(n, combat::seed(self.turn, sys.id)),
+        };
+        let mut dice = dice.with_context(context.as_str());
+        let outcome = match assault_from {
+            Some(from) => {
+                combat::resolve_assault(&sys.name, from, sys.owner, &ships, &mods, rules, &mut dice)
+            }
+            None => combat::resolve(sys.name.as_str(), &ships, &mods, rules, &mut dice),
+        };
+        if let Err(e) = self.data.begin_work().await {
+            return Err(e.to_string());
+        }
+        let recorded = self
+            .record_battle(sys, &context, &ships, &outcome, &dice)
+            .await;
+        let ended = match recorded {
+            Ok(_) => self.data.commit_work().await,
+            Err(_) => self.data.rollback_work().await,
+        };
+        recorded?;
+        match ended {
+            Ok(_) => Ok(outcome.report),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    // Record what came of a battle: the rolls, the damage done, the wars it
+    // began, the losses and service of the ships, and the designs each side
+    // saw.
+    async fn record_battle(
+        &self,
+        sys: &System,
+        context: &str,
+        ships: &[Combatant],
+        outcome: &combat::Outcome,
+        dice: &Dice,
+    ) -> Result<(), String> {
+        let rules = self.ruleset().await?;
+        self.write_rolls(dice).await?;
+        if let Err(e) = self
+            .data
+            .apply_battle(&outcome.crippled, &outcome.destroyed)
+            .await
+        {
+            return Err(e.to_string());
+        }
+        if let Err(e) = self.data.insert_battle(self.turn, sys.id).await {
+            return Err(e.to_string());
+        }
+        let costs = self
+            .ship_types()
+            .await?
+            .iter()
+            .map(|t| (t.id, t.cost))
+            .collect();
+        let casualties = war::battle_casualties(
+            self.turn,
+            ships,
+            &outcome.crippled,
+            &outcome.destroyed,
+            &costs,
+        );
+        if let Err(e) = self
+            .data
+            .insert_wars(&war::fighting(ships), self.turn)
+            .await
+        {
+            return Err(e.to_string());
+        }
+        if let Err(e) = self.data.insert_casualties(&casualties).await {
+            return Err(e.to_string());
+        }
+        let service = service::from_battle(self.turn, ships, &outcome.destroyed, rules);
+        if let Err(e) = self.data.insert_service(&service).await {
+            return Err(e.to_string());
+        }
+        let result = format!(
+            "{}: {} crippled, {} destroyed",
+            context,
+            outcome.crippled.len(),
+            outcome.destroyed.len()
+        );
+        self.write_log(audit::COMBAT, result.as_str()).await?;
+        // Every side now knows the designs it fought.
+        let seen = recognition::from_battle(ships, self.turn);
+        match self.data.insert_identifications(&seen).await {
+            Ok(_) => Ok(()),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    // Add the rolls made with some dice to the audit log.
+    async fn write_rolls(&self, dice: &Dice) -> Result<(), String> {
+        let entries: Vec<LogEntry> = dice
+            .rolls()
+            .iter()
+            .map(|r| LogEntry::new(self.turn, audit::ROLL, &r.to_string()))
+            .collect();
+        match self.data.insert_log(&entries).await {
+            Ok(_) => Ok(()),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    // Put the tables back as they were in a snapshot, along with the turn.
+    async fn restore(&mut self, tables: &serde_json::Value) -> Result<(), String> {
+        self.cache.borrow_mut().invalidate();
+        if let Err(e) = self.data.import(tables).await {
+            return Err(e.to_string());
+        }
+        match self.data.current_turn().await {
+            Ok(t) => {
+                self.turn = t;
+                Ok(())
+            }
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    // Take a snapshot of every table for the undo history. The audit log is
+    // left out, so undoing an edit never removes it from the log.
+    async fn snapshot(&self) -> Result<serde_json::Value, String> {
+        let mut tables = match self.data.export().await {
+            Ok(v) => v,
+            Err(e) => return Err(e.to_string()),
+        };
+        if let Some(t) = tables["tables"].as_object_mut() {
+            t.remove(audit::TABLE);
+        }
+        Ok(tables)
+    }
+
+    // Back up the database before a turn advance or a delete, removing the
+    // oldest backups beyond the limit.
+    async fn backup(&self, reason: &str) -> Result<(), String> {
+        let dir = DataStore::backups(&self.dir, &self.name);
+        if let Err(e) = fs::create_dir_all(&dir) {
+            return Err(e.to_string());
+        }
+        let base = backup::name(self.today().await?.as_str(), reason);
+        let mut name = base.clone();
+        let mut n = 1;
+        while dir.join(format!("{}.{}", name, backup::EXTENSION)).exists() {
+            n += 1;
+            name = format!("{} {}", base, n);
+        }
+        let file = dir.join(format!("{}.{}", name, backup::EXTENSION));
+        if let Err(e) = self.data.write_backup(&file).await {
+            return Err(e.to_string());
+        }
+        for old in backup::expired(&self.backups()?) {
+            let file = dir.join(format!("{}.{}", old, backup::EXTENSION));
+            if let Err(e) = fs::remove_file(file) {
+                return Err(e.to_string());
+            }
+        }
+        Ok(())
+    }
+
+    // Record a transfer whose terms are sound.
+    async fn agree_transfer(&self, mut t: Transfer) -> Result<Transfer, String> {
+        t.check_terms()?;
+        if self.empires().await?.iter().all(|e| e.id != t.buyer) {
+            return Err("No such empire to transfer to".to_string());
+        }
+        match self.data.insert_transfer(&t).await {
+            Ok(id) => {
+                t.id = id;
+                Ok(t)
+            }
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    // Add a build to the queue if the system is owned and its owner can pay.
+    async fn queue(&self, sys: &System, mut build: Build) -> Result<Build, String> {
+        let empires = self.empires().await?;
+        let emp = match empires.iter().find(|e| e.id == sys.owner) {
+            Some(e) => e,
+            None => return Err(format!("{} has no owner to build there", sys.name)),
+        };
+        if emp.treasury < build.cost {
+            return Err(format!(
+                "{} cannot afford {} EP with {} EP in the treasury",
+                emp.name, build.cost, emp.treasury
+            ));
+        }
+        match self.data.insert_build(&build).await {
+            Ok(id) => {
+                build.id = id;
+                build.empire_name = emp.name.clone();
+                build.system_name = sys.name.clone();
+                Ok(build)
+            }
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    // Return the campaign's template of the given name, or the default if it
+    // has none.
+    fn template(&self, name: &str) -> Result<String, String> {
+        let file = DataStore::attachments(&self.dir, &self.name)
+            .join(template::FOLDER)
+            .join(name);
+        if file.is_file() {
+            return fs::read_to_string(&file).map_err(|e| format!("{}: {}", name, e));
+        }
+        match template::DEFAULTS.iter().find(|(n, _)| *n == name) {
+            Some((_, text)) => Ok(text.to_string()),
+            None => Err(format!("No template named {}", name)),
+        }
+    }
+
+    // Return an empire's tech level, if it is high enough to build on a hull.
+    async fn tech_for_hull(&self, empire: i64, hull: &Hull) -> Result<i32, String> {
+        let empires = self.empires().await?;
+        let emp = match empires.iter().find(|e| e.id == empire) {
+            Some(e) => e,
+            None => return Err("The class must belong to an empire".to_string()),
+        };
+        let needed = hull.tech;
+        if emp.tech < needed {
+            return Err(format!(
+                "{} needs tech level {} to build {} hulls",
+                emp.name, needed, hull.name
+            ));
+        }
+        Ok(emp.tech)
+    }
 }
 
 #[cfg(test)]
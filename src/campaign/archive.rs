@@ -0,0 +1,134 @@
+// Copyright 2022 David Terhune
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Campaign archive (.vbamcma) files.
+//!
+//! An archive is a JSON document holding the campaign name, every database
+//! table, and the text files attached to the campaign: notes, generated
+//! reports, snapshots and report templates. See docs/decisions/0004 for the
+//! layout.
+
+use serde_json::{Map, Value};
+use std::{fs, path};
+
+/// File name extension of campaign archives.
+pub const EXTENSION: &str = "vbamcma";
+
+/// Format marker stored in every archive.
+pub const FORMAT: &str = "vbamcma";
+
+/// Version of the archive layout written by this program.
+pub const VERSION: i64 = 1;
+
+/// Folders of attached files bundled into an archive.
+pub const FOLDERS: [&str; 4] = ["notes", "reports", "snapshots", "templates"];
+
+/// Check that an archive is one this program can read. Archives written
+/// before the format marker was added hold only tables and are accepted.
+pub fn check(archive: &Value) -> Result<(), String> {
+    match archive.get("format").and_then(|f| f.as_str()) {
+        None => Ok(()),
+        Some(FORMAT) => match archive.get("version").and_then(|v| v.as_i64()) {
+            Some(v) if v <= VERSION => Ok(()),
+            Some(v) => Err(format!(
+                "The archive is version {}, newer than this program reads",
+                v
+            )),
+            None => Err("The archive has no version".to_string()),
+        },
+        Some(f) => Err(format!("Not a campaign archive: {}", f)),
+    }
+}
+
+/// Read the attached files under a campaign folder, keyed by their path
+/// relative to it, such as "notes/Turn 3.txt".
+pub fn collect_files(dir: &path::Path) -> Result<Map<String, Value>, String> {
+    let mut files = Map::new();
+    for folder in FOLDERS {
+        let sub = dir.join(folder);
+        if !sub.is_dir() {
+            continue;
+        }
+        let entries = fs::read_dir(&sub).map_err(|e| e.to_string())?;
+        for entry in entries {
+            let entry = entry.map_err(|e| e.to_string())?;
+            if !entry.path().is_file() {
+                continue;
+            }
+            let name = entry.file_name().to_string_lossy().to_string();
+            let text = fs::read_to_string(entry.path())
+                .map_err(|e| format!("{}/{}: {}", folder, name, e))?;
+            files.insert(format!("{}/{}", folder, name), Value::from(text));
+        }
+    }
+    Ok(files)
+}
+
+/// Write the attached files of an archive under a campaign folder. Only
+/// plain file names within the known folders are accepted.
+pub fn restore_files(dir: &path::Path, files: &Map<String, Value>) -> Result<(), String> {
+    for (key, text) in files {
+        let (folder, name) = match key.split_once('/') {
+            Some((f, n)) if FOLDERS.contains(&f) && is_plain_name(n) => (f, n),
+            _ => return Err(format!("Bad attached file name {}", key)),
+        };
+        let text = match text.as_str() {
+            Some(t) => t,
+            None => return Err(format!("{} is not text", key)),
+        };
+        let sub = dir.join(folder);
+        fs::create_dir_all(&sub).map_err(|e| e.to_string())?;
+        fs::write(sub.join(name), text).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+// Whether a name is a single path component that stays inside its folder.
+fn is_plain_name(name: &str) -> bool {
+    !name.is_empty() && name != "." && name != ".." && !name.contains(['/', '\\', ':'])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check, collect_files, restore_files};
+    use serde_json::{json, Map, Value};
+    use std::fs;
+
+    #[test]
+    fn versions() {
+        assert!(check(&json!({"tables": {}})).is_ok());
+        assert!(check(&json!({"format": "vbamcma", "version": 1})).is_ok());
+        assert!(check(&json!({"format": "vbamcma", "version": 99})).is_err());
+        assert!(check(&json!({"format": "other", "version": 1})).is_err());
+    }
+
+    #[test]
+    fn files_round_trip() {
+        let dir = std::env::temp_dir().join(format!("vbamcma-archive-{}", std::process::id()));
+        let mut files = Map::new();
+        files.insert("notes/GM.txt".to_string(), Value::from("Kili are bluffing"));
+        files.insert("reports/Turn 1.txt".to_string(), Value::from("Income"));
+        restore_files(&dir, &files).unwrap();
+        assert_eq!(files, collect_files(&dir).unwrap());
+        fs::remove_dir_all(&dir).unwrap();
+
+        let mut bad = Map::new();
+        bad.insert("notes/../escape.txt".to_string(), Value::from(""));
+        assert!(restore_files(&dir, &bad).is_err());
+        let mut bad = Map::new();
+        bad.insert("secrets/x.txt".to_string(), Value::from(""));
+        assert!(restore_files(&dir, &bad).is_err());
+    }
+}
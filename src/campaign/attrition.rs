@@ -0,0 +1,179 @@
+// Copyright 2022 David Terhune
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Attrition: the wear on ships operating beyond their empire's supply.
+//!
+//! Under the optional attrition rules, every active ship at a system its
+//! empire's supply does not reach makes a check at the end of the movement
+//! phase. Ships in hostile territory, a system held by an empire they are at
+//! war with, fail more often. A failed check cripples an intact ship and
+//! wrecks one that is already crippled.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::dice::Dice;
+use super::system::System;
+use super::unit::ShipLocation;
+use super::war::War;
+
+/// Control key of the optional attrition rules.
+pub const ATTRITION: &str = "attrition";
+
+/// Sides of the die rolled for each ship.
+pub const CHECK_DIE: i32 = 10;
+
+/// Highest roll that fails the check.
+pub const FAIL: i32 = 1;
+
+/// Added to the highest failing roll in hostile territory.
+pub const HOSTILE: i32 = 1;
+
+/// Seed for a turn's attrition rolls, so a turn resolves the same way
+/// however often it is replayed.
+pub fn seed(turn: i32) -> u64 {
+    ((turn as u64) << 32) ^ 0x4154_5452
+}
+
+/// The ships attrition has worn down, and a line on each for the report.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Outcome {
+    pub crippled: Vec<i64>,
+    pub destroyed: Vec<i64>,
+    pub report: Vec<(i64, String)>,
+}
+
+/// Check every active ship beyond its empire's supply, given the empires
+/// supplying each system. The report is of each failed check, with the
+/// empire whose ship it was.
+pub fn resolve(
+    ships: &[ShipLocation],
+    systems: &[System],
+    supplied: &BTreeMap<i64, BTreeSet<i64>>,
+    wars: &[War],
+    dice: &mut Dice,
+) -> Outcome {
+    let owners: BTreeMap<i64, i64> = systems.iter().map(|s| (s.id, s.owner)).collect();
+    let at_war = |a: i64, b: i64| {
+        wars.iter()
+            .any(|w| w.ended.is_none() && ((w.a == a && w.b == b) || (w.a == b && w.b == a)))
+    };
+    let mut outcome = Outcome::default();
+    for ship in ships {
+        let in_supply = supplied
+            .get(&ship.location)
+            .is_some_and(|e| e.contains(&ship.owner));
+        if ship.moth || ship.location == 0 || in_supply {
+            continue;
+        }
+        let owner = owners.get(&ship.location).copied().unwrap_or(0);
+        let hostile = owner != 0 && at_war(ship.owner, owner);
+        let fail = if hostile { FAIL + HOSTILE } else { FAIL };
+        if dice.roll(CHECK_DIE) > fail {
+            continue;
+        }
+        let name = format!("{} #{}", ship.class, ship.id);
+        let line = if ship.crip {
+            outcome.destroyed.push(ship.id);
+            format!("{} was lost to attrition at {}", name, ship.location_name)
+        } else {
+            outcome.crippled.push(ship.id);
+            format!(
+                "{} was crippled by attrition at {}",
+                name, ship.location_name
+            )
+        };
+        outcome.report.push((ship.owner, line));
+    }
+    outcome
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, BTreeSet};
+
+    use super::{resolve, Outcome, FAIL};
+    use crate::campaign::dice::Dice;
+    use crate::campaign::system::tests::systems;
+    use crate::campaign::unit::ShipLocation;
+    use crate::campaign::war::War;
+
+    fn ship(id: i64, location: i64, crip: bool, moth: bool) -> ShipLocation {
+        ShipLocation {
+            id,
+            class: "Avenger".to_string(),
+            fleet_name: "Raiders".to_string(),
+            location_name: "Tibron".to_string(),
+            crip,
+            moth,
+            owner: 1,
+            location,
+            cost: 10,
+            cap: 0,
+        }
+    }
+
+    #[test]
+    fn out_of_supply() {
+        let mut sys = systems();
+        for (n, s) in sys.iter_mut().enumerate() {
+            s.id = n as i64 + 1;
+        }
+        sys[3].owner = 2;
+        let supplied = BTreeMap::from([(1, BTreeSet::from([1]))]);
+        let wars = vec![War {
+            id: 1,
+            a: 2,
+            b: 1,
+            started: 0,
+            ended: None,
+            a_name: String::new(),
+            b_name: String::new(),
+        }];
+
+        // Ships in supply, mothballed or in no system never wear.
+        let safe = vec![
+            ship(1, 1, false, false),
+            ship(2, 4, false, true),
+            ship(3, 0, true, false),
+        ];
+        let mut dice = Dice::new(1);
+        assert_eq!(
+            Outcome::default(),
+            resolve(&safe, &sys, &supplied, &wars, &mut dice)
+        );
+        assert!(dice.rolls().is_empty());
+
+        // Out of supply, every ship rolls; failures cripple intact ships and
+        // wreck crippled ones.
+        let ships: Vec<ShipLocation> = (0..200)
+            .map(|n| ship(n + 10, 4, n % 2 == 1, false))
+            .collect();
+        let mut dice = Dice::new(7);
+        let outcome = resolve(&ships, &sys, &supplied, &wars, &mut dice);
+        assert_eq!(200, dice.rolls().len());
+        let failed = dice.rolls().iter().filter(|r| r.result <= FAIL + 1).count();
+        assert_eq!(failed, outcome.crippled.len() + outcome.destroyed.len());
+        assert_eq!(failed, outcome.report.len());
+        assert!(outcome.crippled.iter().all(|id| id % 2 == 0));
+        assert!(outcome.destroyed.iter().all(|id| id % 2 == 1));
+
+        // Away from hostile territory only the lowest roll fails.
+        sys[3].owner = 0;
+        let mut dice = Dice::new(7);
+        let outcome = resolve(&ships, &sys, &supplied, &wars, &mut dice);
+        let failed = dice.rolls().iter().filter(|r| r.result <= FAIL).count();
+        assert_eq!(failed, outcome.report.len());
+    }
+}
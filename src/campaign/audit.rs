@@ -0,0 +1,138 @@
+// Copyright 2022 David Terhune
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The audit log: a record of every change made to a campaign, with the
+//! turn and time it was made, so disputes with players can be settled from
+//! what actually happened.
+//!
+//! The log is only ever added to. Undoing an edit logs the undo rather than
+//! removing the edit, so the log is left out of the undo snapshots.
+
+/// Table the log is kept in.
+pub const TABLE: &str = "log";
+
+/// Campaigns created from an archive or workbook.
+pub const IMPORT: &str = "Import";
+
+/// Edits to the campaign, and their undoing and redoing.
+pub const EDIT: &str = "Edit";
+
+/// Deletions, disbandments and the like.
+pub const DELETE: &str = "Delete";
+
+/// What happened as a turn was resolved.
+pub const TURN: &str = "Turn";
+
+/// Battles and raids.
+pub const COMBAT: &str = "Combat";
+
+/// Dice rolled, what for, and what came up.
+pub const ROLL: &str = "Roll";
+
+/// Every category of entry.
+pub const CATEGORIES: [&str; 6] = [IMPORT, EDIT, DELETE, TURN, COMBAT, ROLL];
+
+// First words of the labels of edits that remove something.
+const REMOVALS: [&str; 5] = ["Cancel", "Delete", "Demolish", "Disband", "Scrap"];
+
+/// An entry in the log. The time it was made is set by the store.
+#[derive(sqlx::FromRow, Clone, Debug, PartialEq, Eq)]
+pub struct LogEntry {
+    pub id: i64,
+    pub turn: i32,
+    pub stamp: String,
+    pub category: String,
+    pub text: String,
+}
+
+impl LogEntry {
+    /// Create a new log entry.
+    pub fn new(turn: i32, category: &str, text: &str) -> Self {
+        Self {
+            id: 0,
+            turn,
+            stamp: String::new(),
+            category: category.to_string(),
+            text: text.to_string(),
+        }
+    }
+
+    /// Convert to string as a row of tab-separated fields.
+    pub fn as_row(&self) -> String {
+        format!(
+            "{}\t{}\t{}\t{}",
+            self.turn, self.stamp, self.category, self.text
+        )
+    }
+}
+
+/// Category of an edit, going by the label it is undone under.
+pub fn category(label: &str) -> &'static str {
+    let first = label.split_whitespace().next().unwrap_or("");
+    if REMOVALS.contains(&first) {
+        DELETE
+    } else {
+        EDIT
+    }
+}
+
+/// The entries of the given turn and category, if either is given.
+pub fn filter<'a>(
+    entries: &'a [LogEntry],
+    turn: Option<i32>,
+    category: Option<&str>,
+) -> Vec<&'a LogEntry> {
+    entries
+        .iter()
+        .filter(|e| turn.is_none_or(|t| e.turn == t))
+        .filter(|e| category.is_none_or(|c| e.category == c))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{category, filter, LogEntry, COMBAT, DELETE, EDIT, TURN};
+
+    #[test]
+    fn categories() {
+        assert_eq!(DELETE, category("Delete Senor Prime"));
+        assert_eq!(DELETE, category("Disband flight at Senor Prime base"));
+        assert_eq!(EDIT, category("Edit Senorian"));
+        assert_eq!(EDIT, category("Deleted"));
+        assert_eq!(EDIT, category(""));
+    }
+
+    #[test]
+    fn filtering() {
+        let log = [
+            LogEntry::new(1, EDIT, "Edit Senorian"),
+            LogEntry::new(1, COMBAT, "Battle at Zev'rch"),
+            LogEntry::new(2, COMBAT, "Battle at Tibron"),
+            LogEntry::new(2, TURN, "Income: Senorian collected 30 EP"),
+        ];
+        assert_eq!(4, filter(&log, None, None).len());
+        assert_eq!(2, filter(&log, Some(1), None).len());
+        assert_eq!(2, filter(&log, None, Some(COMBAT)).len());
+        let v = filter(&log, Some(2), Some(COMBAT));
+        assert_eq!(
+            vec!["Battle at Tibron"],
+            v.iter().map(|e| e.text.as_str()).collect::<Vec<_>>()
+        );
+
+        let mut e = log[0].clone();
+        e.stamp = "2022-10-01 12:00:00".to_string();
+        assert_eq!("1\t2022-10-01 12:00:00\tEdit\tEdit Senorian", e.as_row());
+    }
+}
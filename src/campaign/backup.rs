@@ -0,0 +1,82 @@
+// Copyright 2022 David Terhune
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Automatic backups of the campaign database.
+//!
+//! A copy of the database is taken before each turn advance and each delete.
+//! Backups are named for when they were taken and why, so sorting the names
+//! puts them in order.
+
+/// File name extension of backups.
+pub const EXTENSION: &str = "db";
+
+/// Most backups kept for a campaign. The oldest are removed beyond this.
+pub const LIMIT: usize = 50;
+
+/// Name of a backup taken at the given local time ("YYYY-MM-DD HH:MM:SS")
+/// for the given reason. Characters that are not safe in file names are
+/// replaced.
+pub fn name(when: &str, reason: &str) -> String {
+    let safe = |c: char| {
+        if c.is_alphanumeric() || c == ' ' || c == '-' || c == '\'' {
+            c
+        } else {
+            '-'
+        }
+    };
+    format!("{} {}", when, reason).chars().map(safe).collect()
+}
+
+/// Names of backups to remove so that only the newest `LIMIT` are kept.
+pub fn expired(names: &[String]) -> Vec<String> {
+    let mut sorted = names.to_vec();
+    sorted.sort();
+    let excess = sorted.len().saturating_sub(LIMIT);
+    sorted.truncate(excess);
+    sorted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{expired, name, LIMIT};
+
+    #[test]
+    fn names() {
+        assert_eq!(
+            "2026-10-15 12-30-00 Before turn 3",
+            name("2026-10-15 12:30:00", "Before turn 3")
+        );
+        assert_eq!(
+            "2026-10-15 12-30-00 Delete Zev'rch-Tibron",
+            name("2026-10-15 12:30:00", "Delete Zev'rch/Tibron")
+        );
+    }
+
+    #[test]
+    fn expiry() {
+        let mut names: Vec<String> = (0..LIMIT + 2)
+            .map(|i| format!("2026-10-15 12-00-{:02} Before turn {}", i, i))
+            .collect();
+        names.reverse();
+        assert_eq!(
+            vec![
+                "2026-10-15 12-00-00 Before turn 0".to_string(),
+                "2026-10-15 12-00-01 Before turn 1".to_string()
+            ],
+            expired(&names)
+        );
+        assert!(expired(&names[..LIMIT]).is_empty());
+    }
+}
@@ -0,0 +1,121 @@
+// Copyright 2022 David Terhune
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Bulk data entry: a list of changes made together or not at all.
+//!
+//! Importers and scripts build a list of changes and hand it to
+//! `Campaign::apply_batch`, which makes them in one transaction. A change
+//! may refer to a row added earlier in the same batch by a negative ID: -1
+//! is the row the first change added, -2 the second's, and so on.
+
+use std::fmt;
+
+use super::empire::Empire;
+use super::lane::Lane;
+use super::system::System;
+use super::unit::Fleet;
+
+/// One change to a campaign. Updates and deletes name their row by ID.
+#[allow(unused)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Change {
+    AddEmpire(Empire),
+    UpdateEmpire(Empire),
+    DeleteEmpire(i64),
+    AddSystem(System),
+    /// An update of a system whose owner is 0 leaves the owner as it was.
+    UpdateSystem(System),
+    DeleteSystem(i64),
+    AddLane(Lane),
+    UpdateLane(Lane),
+    DeleteLane(i64),
+    AddFleet(Fleet),
+    UpdateFleet(Fleet),
+    DeleteFleet(i64),
+}
+
+impl Change {
+    /// Whether the change deletes a row.
+    pub fn is_delete(&self) -> bool {
+        matches!(
+            self,
+            Self::DeleteEmpire(_)
+                | Self::DeleteSystem(_)
+                | Self::DeleteLane(_)
+                | Self::DeleteFleet(_)
+        )
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::AddEmpire(e) => write!(f, "Add empire {}", e.name),
+            Self::UpdateEmpire(e) => write!(f, "Edit empire {}", e.name),
+            Self::DeleteEmpire(id) => write!(f, "Delete empire {}", id),
+            Self::AddSystem(s) => write!(f, "Add system {}", s.name),
+            Self::UpdateSystem(s) => write!(f, "Edit system {}", s.name),
+            Self::DeleteSystem(id) => write!(f, "Delete system {}", id),
+            Self::AddLane(l) => write!(f, "Add lane {}-{}", l.a, l.b),
+            Self::UpdateLane(l) => write!(f, "Edit lane {}", l.id),
+            Self::DeleteLane(id) => write!(f, "Delete lane {}", id),
+            Self::AddFleet(fl) => write!(f, "Add fleet {}", fl.name),
+            Self::UpdateFleet(fl) => write!(f, "Edit fleet {}", fl.name),
+            Self::DeleteFleet(id) => write!(f, "Delete fleet {}", id),
+        }
+    }
+}
+
+/// The ID a change refers to, given the IDs of the rows added by the
+/// changes before it. A negative ID is the row added by an earlier change.
+pub fn resolve(id: i64, added: &[i64]) -> Result<i64, String> {
+    if id >= 0 {
+        return Ok(id);
+    }
+    match added.get((-id - 1) as usize) {
+        Some(id) => Ok(*id),
+        None => Err(format!("ID {} refers to a change not yet made", id)),
+    }
+}
+
+/// A label for a batch in the undo history.
+pub fn label(changes: &[Change]) -> String {
+    match changes {
+        [one] => one.to_string(),
+        _ => format!("Apply {} changes", changes.len()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{label, resolve, Change};
+
+    #[test]
+    fn references() {
+        let added = [7, 9];
+        assert_eq!(Ok(3), resolve(3, &added));
+        assert_eq!(Ok(0), resolve(0, &added));
+        assert_eq!(Ok(7), resolve(-1, &added));
+        assert_eq!(Ok(9), resolve(-2, &added));
+        assert!(resolve(-3, &added).is_err());
+
+        assert_eq!("Delete fleet 4", label(&[Change::DeleteFleet(4)]));
+        assert!(Change::DeleteFleet(4).is_delete());
+        assert_eq!(
+            "Apply 2 changes",
+            label(&[Change::DeleteFleet(4), Change::DeleteLane(2)])
+        );
+    }
+}
@@ -0,0 +1,82 @@
+// Copyright 2022 David Terhune
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Cache of the data windows show over and over.
+//!
+//! Map redraws and browser refreshes ask for the same systems and empire
+//! names many times between edits. The campaign keeps what it last read
+//! here, and clears it whenever it writes something that could change it.
+
+use std::collections::BTreeMap;
+
+use super::system::System;
+
+/// What was last read, if it has not been cleared since.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Cache {
+    empire_names: Option<BTreeMap<i64, String>>,
+    systems: Option<Vec<System>>,
+}
+
+impl Cache {
+    /// Cached names of the empires by ID.
+    pub fn empire_names(&self) -> Option<BTreeMap<i64, String>> {
+        self.empire_names.clone()
+    }
+
+    /// Keep the names of the empires by ID.
+    pub fn set_empire_names(&mut self, names: &BTreeMap<i64, String>) {
+        self.empire_names = Some(names.clone());
+    }
+
+    /// Cached list of every system.
+    pub fn systems(&self) -> Option<Vec<System>> {
+        self.systems.clone()
+    }
+
+    /// Keep the list of every system.
+    pub fn set_systems(&mut self, systems: &[System]) {
+        self.systems = Some(systems.to_vec());
+    }
+
+    /// Forget everything, so the next request reads afresh.
+    pub fn invalidate(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::Cache;
+    use crate::campaign::system::tests::systems;
+
+    #[test]
+    fn invalidate() {
+        let mut cache = Cache::default();
+        assert_eq!(None, cache.systems());
+        assert_eq!(None, cache.empire_names());
+
+        cache.set_systems(&systems());
+        let names = BTreeMap::from([(1, "Senorian".to_string())]);
+        cache.set_empire_names(&names);
+        assert_eq!(Some(systems()), cache.systems());
+        assert_eq!(Some(names), cache.empire_names());
+
+        cache.invalidate();
+        assert_eq!(Cache::default(), cache);
+    }
+}
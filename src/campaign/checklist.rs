@@ -0,0 +1,286 @@
+// Copyright 2022 David Terhune
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Checks made before the turn is advanced.
+//!
+//! Advancing resolves the turn for good, so first the moderator is shown what
+//! is still outstanding: empires with no orders in, battles not yet fought,
+//! shipyards asked for more than they can build, and units no empire holds.
+//! Each item says where to go to put it right.
+
+use std::collections::BTreeSet;
+
+use super::diplomacy::Treaty;
+use super::empire::Empire;
+use super::ownership;
+use super::production::Production;
+use super::system::System;
+use super::unit::FleetSummary;
+
+/// Names of the checks.
+pub const ORDERS: &str = "Orders";
+pub const BATTLES: &str = "Battles";
+pub const CONSTRUCTION: &str = "Construction";
+pub const ORPHANS: &str = "Orphaned units";
+
+/// Where to go to put a failed check right.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Fix {
+    /// The orders of an empire.
+    Orders(i64),
+    /// A battle at a system.
+    Battle(i64),
+    /// The construction queue.
+    Construction,
+    /// The garrison of a system.
+    Garrison(i64),
+    /// The fleets.
+    Fleets,
+}
+
+/// A check that failed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Item {
+    pub check: &'static str,
+    pub text: String,
+    pub fix: Fix,
+}
+
+impl Item {
+    /// Convert to string as a row of tab-separated fields.
+    pub fn as_row(&self) -> String {
+        format!("{}\t{}", self.check, self.text)
+    }
+}
+
+/// A unit no empire holds: a ground unit at a system without an owner, or a
+/// fleet without an owner or a location.
+#[derive(sqlx::FromRow, Clone, Debug, PartialEq, Eq)]
+pub struct Orphan {
+    pub kind: String,
+    pub id: i64,
+    pub name: String,
+    pub system: i64,
+    pub system_name: String,
+}
+
+/// Kinds of orphaned unit.
+pub const GROUND: &str = "Ground";
+pub const FLEET: &str = "Fleet";
+
+/// Empires with no orders for the turn, given those that have some.
+pub fn missing_orders(empires: &[Empire], ordered: &BTreeSet<i64>) -> Vec<Item> {
+    empires
+        .iter()
+        .filter(|e| !ordered.contains(&e.id))
+        .map(|e| Item {
+            check: ORDERS,
+            text: format!("{} has no orders", e.name),
+            fix: Fix::Orders(e.id),
+        })
+        .collect()
+}
+
+/// Systems where hostile forces meet, as the combat phase finds them, and
+/// no battle has been fought this turn. Empires with a treaty are at peace.
+pub fn unfought_battles(
+    systems: &[System],
+    fleets: &[FleetSummary],
+    treaties: &[Treaty],
+    fought: &[i64],
+) -> Vec<Item> {
+    let at_peace = |a: i64, b: i64| {
+        treaties
+            .iter()
+            .any(|t| (t.a == a && t.b == b) || (t.a == b && t.b == a))
+    };
+    systems
+        .iter()
+        .filter(|s| !fought.contains(&s.id))
+        .filter(|s| {
+            let present: Vec<i64> = fleets
+                .iter()
+                .filter(|f| f.location == s.id && f.ships > 0)
+                .map(|f| f.owner)
+                .collect();
+            !ownership::battle(s, &present, at_peace).is_empty()
+        })
+        .map(|s| Item {
+            check: BATTLES,
+            text: format!("Battle at {} not fought", s.name),
+            fix: Fix::Battle(s.id),
+        })
+        .collect()
+}
+
+/// Systems asked to build more than they can.
+pub fn over_capacity(production: &[Production]) -> Vec<Item> {
+    production
+        .iter()
+        .flat_map(|p| {
+            p.yards.iter().filter(|y| y.is_over()).map(move |y| Item {
+                check: CONSTRUCTION,
+                text: format!(
+                    "{} has {} EP queued at {}, which can build {}",
+                    p.name, y.queued, y.name, y.capacity
+                ),
+                fix: Fix::Construction,
+            })
+        })
+        .collect()
+}
+
+/// Units no empire holds.
+pub fn orphans(units: &[Orphan]) -> Vec<Item> {
+    units
+        .iter()
+        .map(|u| match u.kind.as_str() {
+            GROUND => Item {
+                check: ORPHANS,
+                text: format!("{} at {} has no owner", u.name, u.system_name),
+                fix: Fix::Garrison(u.system),
+            },
+            _ => Item {
+                check: ORPHANS,
+                text: format!("{} fleet has no owner or location", u.name),
+                fix: Fix::Fleets,
+            },
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::{
+        missing_orders, orphans, over_capacity, unfought_battles, Fix, Orphan, FLEET, GROUND,
+    };
+    use crate::campaign::diplomacy::Treaty;
+    use crate::campaign::empire::tests::empires;
+    use crate::campaign::production::{Production, YardLoad};
+    use crate::campaign::system::tests::systems;
+    use crate::campaign::unit::FleetSummary;
+
+    fn fleet(owner: i64, location: i64) -> FleetSummary {
+        FleetSummary {
+            id: owner,
+            name: "Raiders".to_string(),
+            owner,
+            location,
+            speed: 0,
+            owner_name: String::new(),
+            location_name: String::new(),
+            ships: 2,
+            crippled: 0,
+            atk: 4,
+            def: 4,
+            maint: 0,
+            jumps: 0,
+        }
+    }
+
+    #[test]
+    fn orders() {
+        let mut emp = empires();
+        emp.truncate(3);
+        for (n, e) in emp.iter_mut().enumerate() {
+            e.id = n as i64 + 1;
+        }
+        let items = missing_orders(&emp, &BTreeSet::from([1, 3]));
+        assert_eq!(1, items.len());
+        assert_eq!(Fix::Orders(2), items[0].fix);
+        assert_eq!("Orders\tHuman has no orders", items[0].as_row());
+    }
+
+    #[test]
+    fn battles() {
+        let mut sys = systems();
+        for (n, s) in sys.iter_mut().enumerate() {
+            s.id = n as i64 + 1;
+            s.owner = 0;
+            s.minor = String::new();
+        }
+        sys[0].owner = 1;
+        let fleets = vec![fleet(2, 1), fleet(2, 2), fleet(3, 2)];
+        let items = unfought_battles(&sys, &fleets, &[], &[]);
+        assert_eq!(
+            vec![Fix::Battle(1), Fix::Battle(2)],
+            items.iter().map(|i| i.fix).collect::<Vec<Fix>>()
+        );
+
+        // Battles already fought, and empires at peace, are not listed.
+        let treaty = Treaty {
+            id: 1,
+            a: 3,
+            b: 2,
+            ttype: "Peace".to_string(),
+            signed: 0,
+            expires: None,
+            a_name: String::new(),
+            b_name: String::new(),
+        };
+        assert!(unfought_battles(&sys, &fleets, &[treaty], &[1]).is_empty());
+    }
+
+    #[test]
+    fn construction() {
+        let yard = |queued| YardLoad {
+            system: 1,
+            name: "Senor Prime".to_string(),
+            capacity: 10,
+            builds: 1,
+            queued,
+        };
+        let p = Production {
+            empire: 1,
+            name: "Senorian".to_string(),
+            available: 30,
+            queued: 20,
+            remaining: 10,
+            yards: vec![yard(8), yard(12)],
+        };
+        let items = over_capacity(&[p]);
+        assert_eq!(1, items.len());
+        assert_eq!(
+            "Senorian has 12 EP queued at Senor Prime, which can build 10",
+            items[0].text
+        );
+    }
+
+    #[test]
+    fn orphaned() {
+        let units = vec![
+            Orphan {
+                kind: GROUND.to_string(),
+                id: 1,
+                name: "Militia".to_string(),
+                system: 4,
+                system_name: "Tibron".to_string(),
+            },
+            Orphan {
+                kind: FLEET.to_string(),
+                id: 2,
+                name: "Home Fleet".to_string(),
+                system: 0,
+                system_name: String::new(),
+            },
+        ];
+        let items = orphans(&units);
+        assert_eq!(Fix::Garrison(4), items[0].fix);
+        assert_eq!("Militia at Tibron has no owner", items[0].text);
+        assert_eq!(Fix::Fleets, items[1].fix);
+    }
+}
@@ -0,0 +1,846 @@
+// Copyright 2022 David Terhune
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Combat rules data and the combat resolver.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::dice::Dice;
+use super::facility;
+use super::ruleset::Ruleset;
+use super::system::System;
+use super::unit::GroundUnit;
+
+/// Most point defense enemy ships put up against a strike: the highest roll
+/// on a d10 that downs a flight.
+pub const POINT_DEFENSE: usize = 5;
+
+/// Categories of combat modifier the game master chooses for each side.
+pub const TERRAIN: &str = "Terrain";
+pub const CREW: &str = "Crew";
+pub const LEADER: &str = "Leader";
+
+/// The conditions a side can be found to fight under, and their categories.
+pub const CONDITION: &str = "Condition";
+pub const CRIPPLED: &str = "Crippled";
+pub const SUPPLY: &str = "Supply";
+pub const OUT_OF_SUPPLY: &str = "Out of Supply";
+
+/// A combat modifier: an adjustment to attack and defense that applies under
+/// a named condition within a category (terrain, condition, supply, crew
+/// grade, leader).
+#[derive(sqlx::FromRow, Clone, Debug, PartialEq, Eq)]
+pub struct CombatModifier {
+    pub id: i64,
+    pub category: String,
+    pub name: String,
+    pub atk: i32,
+    pub def: i32,
+}
+
+impl CombatModifier {
+    /// Create a new combat modifier.
+    pub fn new(category: &str, name: &str, atk: i32, def: i32) -> Self {
+        Self {
+            id: 0,
+            category: category.to_string(),
+            name: name.to_string(),
+            atk,
+            def,
+        }
+    }
+
+    /// Convert to string as a row of tab-separated fields.
+    pub fn as_row(&self) -> String {
+        format!(
+            "{}\t{}\t{:+}\t{:+}",
+            self.category, self.name, self.atk, self.def
+        )
+    }
+}
+
+/// Look up a modifier by category and name. Conditions with no modifier
+/// defined have no effect.
+pub fn find_modifier<'a>(
+    mods: &'a [CombatModifier],
+    category: &str,
+    name: &str,
+) -> Option<&'a CombatModifier> {
+    mods.iter()
+        .find(|m| m.category == category && m.name == name)
+}
+
+/// The modifier a side fights under: the sum of those defined for each of
+/// its conditions, given by category and name, and named for them.
+pub fn side_modifier(mods: &[CombatModifier], conditions: &[(&str, &str)]) -> CombatModifier {
+    let found: Vec<&CombatModifier> = conditions
+        .iter()
+        .filter_map(|(category, name)| find_modifier(mods, category, name))
+        .collect();
+    let names: Vec<&str> = found.iter().map(|m| m.name.as_str()).collect();
+    CombatModifier::new(
+        "Side",
+        names.join(", ").as_str(),
+        found.iter().map(|m| m.atk).sum(),
+        found.iter().map(|m| m.def).sum(),
+    )
+}
+
+/// The conditions each side in a battle is found to fight under, given the
+/// empires supplying the system: out of supply, if its empire is not among
+/// them, and crippled, if it has nothing but crippled ships there. Natives
+/// are always at home.
+pub fn conditions(
+    ships: &[Combatant],
+    supplied: &BTreeSet<i64>,
+) -> BTreeMap<i64, Vec<(&'static str, &'static str)>> {
+    let mut found: BTreeMap<i64, Vec<(&str, &str)>> = BTreeMap::new();
+    for c in ships {
+        found.entry(c.owner).or_default();
+    }
+    for (owner, list) in found.iter_mut() {
+        if *owner > 0 && !supplied.contains(owner) {
+            list.push((SUPPLY, OUT_OF_SUPPLY));
+        }
+        if ships.iter().filter(|c| c.owner == *owner).all(|c| c.crip) {
+            list.push((CONDITION, CRIPPLED));
+        }
+    }
+    found
+}
+
+/// Seed for the rolls of a battle fought at a system, so a battle fought
+/// again from the same state on the same turn comes out the same.
+pub fn seed(turn: i32, system: i64) -> u64 {
+    ((turn as u64) << 32) ^ (system as u64) ^ 0x4241_5454
+}
+
+/// A ship, flight or starbase taking part in a battle. Flights have their
+/// IDs negated, and starbases theirs offset by `facility::COMBAT_ID`, so
+/// they are not mistaken for ships.
+#[derive(sqlx::FromRow, Clone, Debug, PartialEq, Eq)]
+pub struct Combatant {
+    pub id: i64,
+    pub owner: i64,
+    pub owner_name: String,
+    pub class: String,
+    #[sqlx(default)]
+    pub stype: i64,
+    pub atk: i32,
+    pub def: i32,
+    pub crip: bool,
+}
+
+impl Combatant {
+    /// Whether this is a flight of fighters rather than a ship.
+    pub fn is_flight(&self) -> bool {
+        self.id < 0
+    }
+
+    /// Whether this is a system's facility rather than a ship.
+    pub fn is_facility(&self) -> bool {
+        self.id >= facility::COMBAT_ID
+    }
+
+    /// Attack the ship contributes. Crippled ships fight with what the
+    /// rules in use leave them.
+    pub fn attack(&self, rules: &dyn Ruleset) -> i32 {
+        if self.crip {
+            rules.crippled_attack(self.atk)
+        } else {
+            self.atk
+        }
+    }
+}
+
+/// The result of a battle: the ships crippled and destroyed, and the report
+/// of how it went.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Outcome {
+    pub crippled: Vec<i64>,
+    pub destroyed: Vec<i64>,
+    pub report: String,
+}
+
+/// Resolve a battle between the ships present in a system.
+///
+/// Ships fight by owner, and every side fires on all its enemies. Each round
+/// a side rolls a d10 and scores the damage the rules give for its total
+/// attack and the roll, plus its attack modifier. Damage is applied to enemy
+/// ships in order: a ship is crippled by damage equal to its defense (plus
+/// its side's defense modifier), and a crippled ship is destroyed by the
+/// same amount again. Fire is simultaneous. The battle ends when only one
+/// side is left, or after the rules' number of rounds.
+///
+/// Before the first round, carriers and bases launch their flights that are
+/// not crippled in a strike on the enemy ships. Point defense downs some of
+/// them, and the rest strike past the enemy flights, then return to fight
+/// in the rounds that follow. Flights lost are counted one by one.
+pub fn resolve(
+    location: &str,
+    ships: &[Combatant],
+    mods: &BTreeMap<i64, CombatModifier>,
+    rules: &dyn Ruleset,
+    dice: &mut Dice,
+) -> Outcome {
+    let report = format!("Battle at {}\n", location);
+    fight(report, ships, None, mods, rules, dice)
+}
+
+/// Resolve an assault through a defended jump point into a system held by
+/// the defender.
+///
+/// The defender's ships and fortifications fire one volley before the
+/// battle, which the attackers cannot answer. The battle then goes on as
+/// any other, with the survivors.
+pub fn resolve_assault(
+    location: &str,
+    from: &str,
+    defender: i64,
+    ships: &[Combatant],
+    mods: &BTreeMap<i64, CombatModifier>,
+    rules: &dyn Ruleset,
+    dice: &mut Dice,
+) -> Outcome {
+    let report = format!(
+        "Assault on {} through the jump point from {}\n",
+        location, from
+    );
+    fight(report, ships, Some(defender), mods, rules, dice)
+}
+
+// A step of a battle: the unanswered volley of the side with first fire,
+// the carriers' strike, or a round of gunnery.
+#[derive(Clone, Copy)]
+enum Step {
+    FirstFire,
+    Strike,
+    Round(i32),
+}
+
+// Fight a battle, starting with an unanswered volley by the side with first
+// fire, if any, then the carriers' strike.
+fn fight(
+    mut report: String,
+    ships: &[Combatant],
+    first_fire: Option<i64>,
+    mods: &BTreeMap<i64, CombatModifier>,
+    rules: &dyn Ruleset,
+    dice: &mut Dice,
+) -> Outcome {
+    let mut fleet: Vec<Combatant> = ships.to_vec();
+    let mut destroyed: Vec<i64> = Vec::new();
+
+    let sides = |fleet: &[Combatant]| -> BTreeMap<i64, String> {
+        fleet
+            .iter()
+            .map(|c| (c.owner, c.owner_name.clone()))
+            .collect()
+    };
+    if sides(&fleet).len() < 2 {
+        report.push_str("No opposing forces present.\n");
+        return Outcome {
+            report,
+            ..Default::default()
+        };
+    }
+    let modifier = |owner: i64| mods.get(&owner).map_or((0, 0), |m| (m.atk, m.def));
+    for (owner, name) in sides(&fleet) {
+        if let Some(m) = mods.get(&owner).filter(|m| !m.name.is_empty()) {
+            report.push_str(&format!(
+                "{} fights under {} ({:+} attack, {:+} defense)\n",
+                name, m.name, m.atk, m.def
+            ));
+        }
+    }
+
+    let present = sides(&fleet);
+    let opening = first_fire.and_then(|owner| present.get_key_value(&owner));
+    let mut steps = Vec::new();
+    if opening.is_some() {
+        steps.push(Step::FirstFire);
+    }
+    steps.push(Step::Strike);
+    steps.extend((1..=rules.battle_rounds()).map(Step::Round));
+    for step in steps {
+        let present = sides(&fleet);
+        if present.len() < 2 {
+            break;
+        }
+        let mut lost = Vec::new();
+        let damage = match step {
+            Step::FirstFire => {
+                report.push_str("\nFirst fire\n");
+                let firing: Vec<(&i64, &String)> = opening.into_iter().collect();
+                gunnery(&fleet, &firing, &modifier, rules, dice, &mut report)
+            }
+            Step::Strike => strike(
+                &fleet,
+                &present,
+                &modifier,
+                rules,
+                dice,
+                &mut lost,
+                &mut report,
+            ),
+            Step::Round(r) => {
+                report.push_str(&format!("\nRound {}\n", r));
+                let firing: Vec<(&i64, &String)> = present.iter().collect();
+                gunnery(&fleet, &firing, &modifier, rules, dice, &mut report)
+            }
+        };
+
+        // Strikes are made on ships, past the flights screening them.
+        let strike = matches!(step, Step::Strike);
+        for (owner, mut dmg) in damage {
+            let targets = fleet
+                .iter_mut()
+                .filter(|c| c.owner != owner && !(strike && c.is_flight()));
+            for target in targets {
+                if lost.contains(&target.id) {
+                    continue;
+                }
+                let def = (target.def + modifier(target.owner).1).max(1);
+                if !target.crip && dmg >= def {
+                    dmg -= def;
+                    target.crip = true;
+                    report.push_str(&format!(
+                        "{} {} crippled\n",
+                        target.owner_name, target.class
+                    ));
+                }
+                if target.crip && dmg >= def {
+                    dmg -= def;
+                    lost.push(target.id);
+                    report.push_str(&format!(
+                        "{} {} destroyed\n",
+                        target.owner_name, target.class
+                    ));
+                }
+                if dmg <= 0 {
+                    break;
+                }
+            }
+        }
+        fleet.retain(|c| !lost.contains(&c.id));
+        destroyed.extend(lost);
+    }
+
+    report.push_str("\nResult\n");
+    for (owner, name) in sides(ships) {
+        let left: Vec<&Combatant> = fleet.iter().filter(|c| c.owner == owner).collect();
+        let crippled = left.iter().filter(|c| c.crip).count();
+        report.push_str(&format!(
+            "{}: {} ships remain ({} crippled)\n",
+            name,
+            left.len(),
+            crippled
+        ));
+    }
+
+    let crippled = fleet
+        .iter()
+        .filter(|c| c.crip && !ships.iter().any(|s| s.id == c.id && s.crip))
+        .map(|c| c.id)
+        .collect();
+    Outcome {
+        crippled,
+        destroyed,
+        report,
+    }
+}
+
+// Roll for each firing side's gunnery, returning the damage each scores.
+fn gunnery(
+    fleet: &[Combatant],
+    firing: &[(&i64, &String)],
+    modifier: &dyn Fn(i64) -> (i32, i32),
+    rules: &dyn Ruleset,
+    dice: &mut Dice,
+    report: &mut String,
+) -> Vec<(i64, i32)> {
+    // Every side rolls before any damage is applied.
+    let mut damage = Vec::new();
+    for (owner, name) in firing {
+        let attack: i32 = fleet
+            .iter()
+            .filter(|c| c.owner == **owner)
+            .map(|c| c.attack(rules))
+            .sum();
+        let roll = dice.roll(10);
+        let dmg = (rules.damage(attack, roll) + modifier(**owner).0).max(0);
+        report.push_str(&format!(
+            "{} attacks with {}, rolls {}, scores {} damage\n",
+            name, attack, roll, dmg
+        ));
+        damage.push((**owner, dmg));
+    }
+    damage
+}
+
+// Launch every side's flights that are not crippled against its enemies'
+// ships. Each flight runs a d10 roll against the point defense of the enemy
+// ships, and is lost on a roll no higher than the number of them not
+// crippled, up to `POINT_DEFENSE`. The survivors strike together and return.
+// The flights lost are added to `lost`, and the damage each side's strike
+// scores is returned.
+fn strike(
+    fleet: &[Combatant],
+    present: &BTreeMap<i64, String>,
+    modifier: &dyn Fn(i64) -> (i32, i32),
+    rules: &dyn Ruleset,
+    dice: &mut Dice,
+    lost: &mut Vec<i64>,
+    report: &mut String,
+) -> Vec<(i64, i32)> {
+    let mut damage = Vec::new();
+    let mut launching = false;
+    for (owner, name) in present {
+        let launched: Vec<&Combatant> = fleet
+            .iter()
+            .filter(|c| c.owner == *owner && c.is_flight() && !c.crip)
+            .collect();
+        if launched.is_empty() {
+            continue;
+        }
+        if !launching {
+            report.push_str("\nStrike\n");
+            launching = true;
+        }
+        let defense = fleet
+            .iter()
+            .filter(|c| c.owner != *owner && !c.is_flight() && !c.crip)
+            .count()
+            .min(POINT_DEFENSE) as i32;
+        report.push_str(&format!(
+            "{} launches {} flights against point defense {}\n",
+            name,
+            launched.len(),
+            defense
+        ));
+        let mut attack = 0;
+        let mut back = 0;
+        for c in launched {
+            if dice.roll(10) <= defense {
+                lost.push(c.id);
+                report.push_str(&format!("{} {} lost\n", c.owner_name, c.class));
+            } else {
+                attack += c.attack(rules);
+                back += 1;
+            }
+        }
+        if back == 0 {
+            report.push_str(&format!("None of {}'s flights return\n", name));
+            continue;
+        }
+        let roll = dice.roll(10);
+        let dmg = (rules.damage(attack, roll) + modifier(*owner).0).max(0);
+        report.push_str(&format!(
+            "{} strikes with {}, rolls {}, scores {} damage; {} flights return\n",
+            name, attack, roll, dmg, back
+        ));
+        damage.push((*owner, dmg));
+    }
+    damage
+}
+
+/// The result of a planetary assault: the ground units lost on both sides,
+/// whether the system fell, and the report of how it went.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Invasion {
+    pub lost: Vec<i64>,
+    pub captured: bool,
+    pub report: String,
+}
+
+/// Resolve a planetary assault on a system by the invader's ground units
+/// against the system's garrison.
+///
+/// Each round both sides roll a d10 and score the damage the rules give for
+/// their total attack and the roll. Damage destroys enemy units in order,
+/// each taking damage equal to its defense. A blockaded garrison is cut off
+/// from supply and defends at half strength. The system falls when the
+/// garrison is wiped out with attackers left; an undefended system falls at
+/// once. The attackers withdraw if they are not done after the rules' number
+/// of assault rounds.
+pub fn resolve_invasion(
+    target: &System,
+    invader: &str,
+    attackers: &[GroundUnit],
+    defenders: &[GroundUnit],
+    rules: &dyn Ruleset,
+    dice: &mut Dice,
+) -> Invasion {
+    let mut atk: Vec<GroundUnit> = attackers.to_vec();
+    let mut def: Vec<GroundUnit> = defenders.to_vec();
+    let mut lost = Vec::new();
+    let mut report = format!("{} assault on {}\n", invader, target.name);
+    if target.blockaded && !def.is_empty() {
+        report.push_str("The blockaded garrison defends at half strength.\n");
+    }
+    let defense = |u: &GroundUnit, defending: bool| {
+        if defending && target.blockaded {
+            (u.def / 2).max(1)
+        } else {
+            u.def.max(1)
+        }
+    };
+
+    let mut round = 0;
+    while round < rules.assault_rounds() && !atk.is_empty() && !def.is_empty() {
+        round += 1;
+        report.push_str(&format!("\nRound {}\n", round));
+
+        // Both sides roll before any losses are removed.
+        let mut fire = Vec::new();
+        for (name, side) in [(invader, &atk), (target.owner_name.as_str(), &def)] {
+            let attack: i32 = side.iter().map(|u| u.atk).sum();
+            let roll = dice.roll(10);
+            let dmg = rules.damage(attack, roll);
+            report.push_str(&format!(
+                "{} attacks with {}, rolls {}, scores {} damage\n",
+                name, attack, roll, dmg
+            ));
+            fire.push(dmg);
+        }
+
+        for (mut dmg, targets, defending) in [(fire[0], &mut def, true), (fire[1], &mut atk, false)]
+        {
+            let mut killed = Vec::new();
+            for u in targets.iter() {
+                let d = defense(u, defending);
+                if dmg < d {
+                    break;
+                }
+                dmg -= d;
+                killed.push(u.id);
+                report.push_str(&format!(
+                    "{} {} destroyed\n",
+                    if defending {
+                        target.owner_name.as_str()
+                    } else {
+                        invader
+                    },
+                    u.name
+                ));
+            }
+            targets.retain(|u| !killed.contains(&u.id));
+            lost.extend(killed);
+        }
+    }
+
+    let captured = def.is_empty() && !atk.is_empty();
+    if captured {
+        report.push_str(&format!("\n{} falls to {}\n", target.name, invader));
+    } else {
+        report.push_str(&format!("\n{} holds {}\n", target.owner_name, target.name));
+    }
+    report.push_str(&format!(
+        "Attackers left: {}\nDefenders left: {}\n",
+        atk.len(),
+        def.len()
+    ));
+    Invasion {
+        lost,
+        captured,
+        report,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        conditions, find_modifier, resolve, resolve_assault, resolve_invasion, side_modifier,
+        CombatModifier, Combatant, CONDITION, CREW, CRIPPLED, LEADER, OUT_OF_SUPPLY, SUPPLY,
+        TERRAIN,
+    };
+    use crate::campaign::dice::Dice;
+    use crate::campaign::facility::COMBAT_ID;
+    use crate::campaign::flight::{FLIGHT, FLIGHT_ATK, FLIGHT_DEF};
+    use crate::campaign::ruleset::SecondEdition;
+    use crate::campaign::system::tests::systems;
+    use crate::campaign::unit::GroundUnit;
+    use std::collections::{BTreeMap, BTreeSet};
+
+    fn ship(id: i64, owner: i64, atk: i32, def: i32) -> Combatant {
+        Combatant {
+            id,
+            owner,
+            owner_name: format!("Empire {}", owner),
+            class: "Frigate".to_string(),
+            stype: 0,
+            atk,
+            def,
+            crip: false,
+        }
+    }
+
+    fn flight(id: i64, owner: i64) -> Combatant {
+        Combatant {
+            class: FLIGHT.to_string(),
+            ..ship(id, owner, FLIGHT_ATK, FLIGHT_DEF)
+        }
+    }
+
+    fn troops(id: i64, name: &str, atk: i32, def: i32) -> GroundUnit {
+        GroundUnit {
+            id,
+            gtype: 0,
+            loc: 0,
+            name: name.to_string(),
+            abbr: String::new(),
+            atk,
+            def,
+        }
+    }
+
+    #[test]
+    fn as_row() {
+        let m = CombatModifier::new("Crew", "Green", -1, 0);
+        assert_eq!("Crew\tGreen\t-1\t+0", m.as_row());
+    }
+
+    #[test]
+    fn find() {
+        let mods = vec![
+            CombatModifier::new("Crew", "Green", -1, 0),
+            CombatModifier::new("Crew", "Elite", 2, 1),
+        ];
+        assert_eq!(2, find_modifier(&mods, "Crew", "Elite").unwrap().atk);
+        assert!(find_modifier(&mods, "Terrain", "Elite").is_none());
+    }
+
+    #[test]
+    fn side() {
+        let mods = vec![
+            CombatModifier::new(TERRAIN, "Nebula", -1, 0),
+            CombatModifier::new(CREW, "Elite", 2, 1),
+            CombatModifier::new(SUPPLY, OUT_OF_SUPPLY, -1, -1),
+        ];
+        let m = side_modifier(&mods, &[(CREW, "Elite"), (SUPPLY, OUT_OF_SUPPLY)]);
+        assert_eq!((1, 0), (m.atk, m.def));
+        assert_eq!("Elite, Out of Supply", m.name);
+        // Conditions with no modifier are passed over.
+        let m = side_modifier(&mods, &[(LEADER, "Average")]);
+        assert_eq!((0, 0, ""), (m.atk, m.def, m.name.as_str()));
+    }
+
+    #[test]
+    fn found_conditions() {
+        let mut hulk = ship(3, 2, 3, 3);
+        hulk.crip = true;
+        let ships = vec![ship(1, 1, 3, 3), ship(2, 0, 3, 3), hulk];
+        let found = conditions(&ships, &BTreeSet::from([2]));
+        assert_eq!(vec![(SUPPLY, OUT_OF_SUPPLY)], found[&1]);
+        assert!(found[&0].is_empty());
+        assert_eq!(vec![(CONDITION, CRIPPLED)], found[&2]);
+    }
+
+    #[test]
+    fn no_battle() {
+        let ships = vec![ship(1, 1, 3, 3), ship(2, 1, 3, 3)];
+        let out = resolve(
+            "Tibron",
+            &ships,
+            &BTreeMap::new(),
+            &SecondEdition,
+            &mut Dice::new(1),
+        );
+        assert!(out.crippled.is_empty());
+        assert!(out.destroyed.is_empty());
+        assert!(out.report.contains("No opposing forces"));
+    }
+
+    #[test]
+    fn defended_assault() {
+        let mut fort = ship(COMBAT_ID + 1, 2, 100, 50);
+        fort.class = "Starbase".to_string();
+        let ships = vec![ship(1, 1, 5, 1), fort];
+        let mods = BTreeMap::new();
+
+        // The fortifications' volley leaves the attacker no chance to fire.
+        let out = resolve_assault(
+            "Tibron",
+            "Zev'rch",
+            2,
+            &ships,
+            &mods,
+            &SecondEdition,
+            &mut Dice::new(3),
+        );
+        assert_eq!(vec![1], out.destroyed);
+        assert!(out
+            .report
+            .starts_with("Assault on Tibron through the jump point from Zev'rch"));
+        assert!(out.report.contains("First fire"));
+        assert!(!out.report.contains("Empire 1 attacks"));
+
+        // In an open battle the attacker fires back.
+        let out = resolve("Tibron", &ships, &mods, &SecondEdition, &mut Dice::new(3));
+        assert!(out.report.contains("Empire 1 attacks"));
+    }
+
+    #[test]
+    fn seeded_battle() {
+        let ships = vec![
+            ship(1, 1, 6, 3),
+            ship(2, 1, 6, 3),
+            ship(3, 2, 2, 2),
+            ship(4, 2, 2, 2),
+        ];
+        let mods = BTreeMap::new();
+        let out = resolve("Tibron", &ships, &mods, &SecondEdition, &mut Dice::new(7));
+        assert_eq!(
+            out,
+            resolve("Tibron", &ships, &mods, &SecondEdition, &mut Dice::new(7))
+        );
+        // Twelve attack scores at least six damage a round, enough to cripple
+        // and destroy one of the weaker ships every round.
+        assert!(out.destroyed.contains(&3));
+        assert!(out.destroyed.contains(&4));
+        assert!(out.report.contains("Empire 2: 0 ships remain"));
+    }
+
+    #[test]
+    fn defense_modifier() {
+        let ships = vec![ship(1, 1, 4, 3), ship(2, 2, 0, 3)];
+        let mut mods = BTreeMap::new();
+        mods.insert(2, CombatModifier::new("Terrain", "Fortress", 0, 10));
+        let out = resolve("Tibron", &ships, &mods, &SecondEdition, &mut Dice::new(3));
+        assert!(out.crippled.is_empty());
+        assert!(out.destroyed.is_empty());
+        assert!(out
+            .report
+            .contains("Empire 2 fights under Fortress (+0 attack, +10 defense)"));
+    }
+
+    #[test]
+    fn strike() {
+        let mut ships: Vec<Combatant> = (1..=6).map(|i| flight(-i, 1)).collect();
+        ships.extend((1..=5).map(|i| ship(i, 2, 0, 100)));
+        let mods = BTreeMap::new();
+        let out = resolve("Tibron", &ships, &mods, &SecondEdition, &mut Dice::new(5));
+        assert!(out
+            .report
+            .contains("Empire 1 launches 6 flights against point defense 5"));
+        // Unanswered, flights are lost only to point defense, each on its own.
+        let lost: Vec<&i64> = out.destroyed.iter().filter(|id| **id < 0).collect();
+        assert!(!lost.is_empty());
+        assert_eq!(lost.len(), out.report.matches("Flight lost").count());
+    }
+
+    #[test]
+    fn no_strike() {
+        let ships = vec![ship(1, 1, 3, 3), ship(2, 2, 3, 3)];
+        let mods = BTreeMap::new();
+        let out = resolve("Tibron", &ships, &mods, &SecondEdition, &mut Dice::new(5));
+        assert!(!out.report.contains("Strike"));
+    }
+
+    #[test]
+    fn undefended_falls() {
+        let target = systems()[1].clone();
+        let out = resolve_invasion(
+            &target,
+            "Kili",
+            &[troops(1, "Marines", 4, 8)],
+            &[],
+            &SecondEdition,
+            &mut Dice::new(1),
+        );
+        assert!(out.captured);
+        assert!(out.lost.is_empty());
+    }
+
+    #[test]
+    fn assault() {
+        let mut target = systems()[0].clone();
+        target.owner_name = "Senorian".to_string();
+        let attackers = vec![
+            troops(1, "Light Armor", 8, 4),
+            troops(2, "Light Armor", 8, 4),
+            troops(3, "Marines", 4, 8),
+        ];
+        let defenders = vec![troops(4, "Militia", 1, 4)];
+        let out = resolve_invasion(
+            &target,
+            "Kili",
+            &attackers,
+            &defenders,
+            &SecondEdition,
+            &mut Dice::new(5),
+        );
+        assert_eq!(
+            out,
+            resolve_invasion(
+                &target,
+                "Kili",
+                &attackers,
+                &defenders,
+                &SecondEdition,
+                &mut Dice::new(5)
+            )
+        );
+        // Twenty attack always destroys the lone militia in the first round,
+        // and its one attack can't destroy anything in return.
+        assert!(out.captured);
+        assert_eq!(vec![4], out.lost);
+        assert!(out.report.contains("Senor Prime falls to Kili"));
+
+        // A strong garrison holds.
+        let defenders = vec![troops(4, "Fortress", 20, 100)];
+        let out = resolve_invasion(
+            &target,
+            "Kili",
+            &attackers,
+            &defenders,
+            &SecondEdition,
+            &mut Dice::new(5),
+        );
+        assert!(!out.captured);
+        assert!(!out.lost.contains(&4));
+        assert!(out.report.contains("Senorian holds Senor Prime"));
+    }
+
+    #[test]
+    fn blockaded_garrison() {
+        let mut target = systems()[0].clone();
+        let defenders = vec![troops(2, "Mobile Infantry", 0, 8)];
+        // Five attack never scores the eight damage needed.
+        let weak = vec![troops(1, "Light Infantry", 5, 100)];
+        let out = resolve_invasion(
+            &target,
+            "Kili",
+            &weak,
+            &defenders,
+            &SecondEdition,
+            &mut Dice::new(2),
+        );
+        assert!(!out.captured);
+        // Seven attack always scores the four needed once blockaded.
+        target.blockaded = true;
+        let strong = vec![troops(1, "Light Armor", 7, 100)];
+        let out = resolve_invasion(
+            &target,
+            "Kili",
+            &strong,
+            &defenders,
+            &SecondEdition,
+            &mut Dice::new(2),
+        );
+        assert!(out.captured);
+        assert!(out.report.contains("half strength"));
+    }
+}
@@ -0,0 +1,113 @@
+// Copyright 2022 David Terhune
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Preferences of the program itself, rather than of any one campaign.
+//!
+//! They are kept as JSON in the user's configuration directory. A missing
+//! or unreadable file is no preferences, so the program always starts.
+
+use std::fs;
+use std::path::PathBuf;
+
+use serde_json::{json, Value};
+
+/// Name of the program's folders.
+const APP: &str = "vbamcma";
+
+/// Name of the preferences file.
+const FILE: &str = "config.json";
+
+/// The program's preferences.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Config {
+    /// Folder the campaign databases are kept in, if not the default.
+    pub data_dir: Option<PathBuf>,
+}
+
+impl Config {
+    /// Read the preferences, or the defaults if there are none.
+    pub fn load() -> Self {
+        file()
+            .and_then(|f| fs::read_to_string(f).ok())
+            .and_then(|text| Self::from_json(text.as_str()).ok())
+            .unwrap_or_default()
+    }
+
+    /// Write the preferences.
+    pub fn save(&self) -> Result<(), String> {
+        let file = file().ok_or("No configuration folder to keep preferences in")?;
+        if let Some(dir) = file.parent() {
+            fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        fs::write(&file, self.to_json()).map_err(|e| format!("{}: {}", file.display(), e))
+    }
+
+    /// Folder the campaign databases are kept in.
+    pub fn data_dir(&self) -> PathBuf {
+        match &self.data_dir {
+            Some(d) => d.clone(),
+            None => default_data_dir(),
+        }
+    }
+
+    /// Read preferences from JSON text. Preferences it does not name keep
+    /// their defaults.
+    pub fn from_json(text: &str) -> Result<Self, String> {
+        let v: Value = serde_json::from_str(text).map_err(|e| e.to_string())?;
+        Ok(Self {
+            data_dir: v["data_dir"].as_str().map(PathBuf::from),
+        })
+    }
+
+    /// The preferences as JSON text.
+    pub fn to_json(&self) -> String {
+        let data_dir = self.data_dir.as_ref().map(|d| d.to_string_lossy());
+        json!({ "data_dir": data_dir }).to_string()
+    }
+}
+
+/// Folder the campaign databases are kept in unless another is chosen: the
+/// program's folder in the user's data directory.
+pub fn default_data_dir() -> PathBuf {
+    dirs::data_dir().unwrap_or_default().join(APP)
+}
+
+// The preferences file, if the user has a configuration directory.
+fn file() -> Option<PathBuf> {
+    dirs::config_dir().map(|d| d.join(APP).join(FILE))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::{default_data_dir, Config};
+
+    #[test]
+    fn json_round_trip() {
+        let config = Config {
+            data_dir: Some(PathBuf::from("/srv/campaigns")),
+        };
+        assert_eq!(Ok(config.clone()), Config::from_json(&config.to_json()));
+        assert_eq!(PathBuf::from("/srv/campaigns"), config.data_dir());
+
+        let config = Config::default();
+        assert_eq!(Ok(config.clone()), Config::from_json(&config.to_json()));
+        assert_eq!(default_data_dir(), config.data_dir());
+
+        assert_eq!(Ok(Config::default()), Config::from_json("{}"));
+        assert!(Config::from_json("data_dir = /srv").is_err());
+    }
+}
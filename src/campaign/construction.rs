@@ -0,0 +1,169 @@
+// Copyright 2022 David Terhune
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Interface to the construction queue.
+
+use super::unit::Hull;
+
+/// Kinds of item a system can build.
+pub const KINDS: [&str; 3] = ["Ship", "Ground", "Facility"];
+
+/// Kind of build repairing a crippled ship, the item being the ship. Repairs
+/// are carried out as repair capacity allows rather than with the rest of
+/// the queue.
+pub const REPAIR: &str = "Repair";
+
+/// Name of the fleet new ships join at the system that built them.
+pub const NEW_CONSTRUCTION: &str = "New Construction";
+
+/// An item under construction at a system. The item is the ship class or
+/// ground unit type being built; facilities have no item. A batch of
+/// identical units shares one build, with the cost covering all of them.
+#[derive(sqlx::FromRow, Clone, Debug, PartialEq, Eq)]
+pub struct Build {
+    pub id: i64,
+    pub empire: i64,
+    pub system: i64,
+    pub kind: String,
+    pub item: i64,
+    pub name: String,
+    pub cost: i32,
+    pub turns: i32,
+    pub count: i32,
+    #[sqlx(default)]
+    pub empire_name: String,
+    #[sqlx(default)]
+    pub system_name: String,
+}
+
+impl Build {
+    /// Create a new build.
+    pub fn new(empire: i64, system: i64, kind: &str, item: i64, name: &str) -> Self {
+        Self {
+            id: 0,
+            empire,
+            system,
+            kind: kind.to_string(),
+            item,
+            name: name.to_string(),
+            cost: 0,
+            turns: 1,
+            count: 1,
+            empire_name: String::new(),
+            system_name: String::new(),
+        }
+    }
+
+    /// Convert to string as a row of tab-separated fields.
+    pub fn as_row(&self) -> String {
+        format!(
+            "{}\t{}\t{}\t{}\t{}\t{}",
+            self.empire_name,
+            self.system_name,
+            self.kind,
+            self.item_name(),
+            self.cost,
+            self.turns
+        )
+    }
+
+    /// Name of the item, prefixed with the count for a batch.
+    pub fn item_name(&self) -> String {
+        if self.count > 1 {
+            format!("{} \u{d7} {}", self.count, self.name)
+        } else {
+            self.name.clone()
+        }
+    }
+}
+
+/// A batch of ships entered as one line, such as "6 x Frigate at Senor
+/// Prime".
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Batch {
+    pub count: i32,
+    pub class: String,
+    pub system: String,
+}
+
+impl Batch {
+    /// Parse a batch line of the form "<count> x <class> at <system>". The
+    /// multiplication sign or an asterisk may stand in for the "x".
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let bad = || {
+            format!(
+                "Expected \"<count> x <class> at <system>\", not \"{}\"",
+                line
+            )
+        };
+        let line = line.trim();
+        let split = line.find(|c: char| !c.is_ascii_digit()).ok_or_else(bad)?;
+        let count: i32 = line[..split].parse().map_err(|_| bad())?;
+        let rest = line[split..].trim_start();
+        let rest = rest
+            .strip_prefix(|c| c == 'x' || c == 'X' || c == '*' || c == '\u{d7}')
+            .ok_or_else(bad)?;
+        let (class, system) = rest.rsplit_once(" at ").ok_or_else(bad)?;
+        let (class, system) = (class.trim(), system.trim());
+        if count < 1 || class.is_empty() || system.is_empty() {
+            return Err(bad());
+        }
+        Ok(Self {
+            count,
+            class: class.to_string(),
+            system: system.to_string(),
+        })
+    }
+}
+
+/// Turns needed to build a ship on the given hull: one turn for every four
+/// points of hull size, rounded up.
+pub fn ship_build_turns(hull: &Hull) -> i32 {
+    ((hull.size + 3) / 4).max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ship_build_turns, Batch};
+    use crate::campaign::unit::Hull;
+
+    #[test]
+    fn build_turns() {
+        let hull = |size| Hull {
+            name: String::new(),
+            size,
+            maint: 1,
+            tech: 0,
+        };
+        assert_eq!(1, ship_build_turns(&hull(1)));
+        assert_eq!(1, ship_build_turns(&hull(4)));
+        assert_eq!(2, ship_build_turns(&hull(5)));
+        assert_eq!(3, ship_build_turns(&hull(10)));
+    }
+
+    #[test]
+    fn batch() {
+        let b = Batch::parse("6 \u{d7} Frigate at Senor Prime").unwrap();
+        assert_eq!(6, b.count);
+        assert_eq!("Frigate", b.class);
+        assert_eq!("Senor Prime", b.system);
+        assert_eq!(b, Batch::parse(" 6x Frigate  at Senor Prime ").unwrap());
+        assert_eq!(b, Batch::parse("6 * Frigate at Senor Prime").unwrap());
+        assert!(Batch::parse("Frigate at Senor Prime").is_err());
+        assert!(Batch::parse("0 x Frigate at Senor Prime").is_err());
+        assert!(Batch::parse("6 x Frigate").is_err());
+        assert!(Batch::parse("6 x  at Senor Prime").is_err());
+    }
+}
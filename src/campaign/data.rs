@@ -15,20 +15,115 @@
 
 //! Data storage layer.
 
-use sqlx::{Row, SqlitePool};
+use serde_json::{json, Map, Value};
+use sqlx::pool::PoolConnection;
+use sqlx::sqlite::SqliteConnectOptions;
+use sqlx::{
+    Column, Connection, QueryBuilder, Row, Sqlite, SqliteConnection, SqlitePool, Transaction,
+    TypeInfo, ValueRef,
+};
+use std::collections::BTreeMap;
+use std::ops::{Deref, DerefMut};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, UNIX_EPOCH};
 use std::{error, fmt, fs, io, num, path};
+use tokio::sync::{MappedMutexGuard, Mutex, MutexGuard};
 
-use super::empire::Empire;
-use super::system::System;
+use super::audit::LogEntry;
+use super::backup;
+use super::batch::{self, Change};
+use super::checklist::{self, Orphan};
+use super::combat::{CombatModifier, Combatant};
+use super::config::Config;
+use super::construction::{self, Build, NEW_CONSTRUCTION};
+use super::deadline::Deadline;
+use super::development::{Development, Resolution};
+use super::diplomacy::Treaty;
+use super::empire::{Empire, LedgerEntry};
+use super::escort::Escort;
+use super::espionage::Operation;
+use super::facility::{self, Facility};
+use super::flight;
+use super::intel::IntelItem;
+use super::journal::JournalEntry;
+use super::lane::Lane;
+use super::map;
+use super::movement::{self, Transit};
+use super::options::{self, CampaignOptions};
+use super::orders::{self, Order, OrderTemplate};
+use super::overview::CampaignSummary;
+use super::raid::Raid;
+use super::recognition::{Identification, TRANSFER};
+use super::repair::RepairJob;
+use super::scorch::{self, ScorchOrder};
+use super::service::{ClassRecord, Service};
+use super::setting::{self, Setting};
+use super::statement::Standing;
+use super::system::{BulkEdit, PlanetType, System};
+use super::tech::TechStatus;
+use super::transfer::Transfer;
+use super::unit::{
+    Fleet, FleetSummary, Flight, GroundType, GroundUnit, Hull, Maintenance, MaintenanceLine,
+    ShipLocation, ShipStatus, ShipType,
+};
+use super::war::{Casualty, War};
+use super::workbook::Workbook;
 
-type DataResult<T> = Result<T, DataError>;
+pub type DataResult<T> = Result<T, DataError>;
+
+/// Version of the database schema this program writes. Increase it whenever a
+/// table or column is added, and add the step that brings databases of the
+/// previous version up to date to `DataStore::migrate`.
+const SCHEMA_VERSION: i32 = 27;
+
+/// Control key holding a database's schema version.
+const SCHEMA_KEY: &str = "schema";
+
+/// Most rows added by one multi-row INSERT, keeping the values bound to it
+/// under SQLite's limit.
+const INSERT_BATCH: usize = 100;
+
+/// How long an operation waits out a lock held on the database by another
+/// program, such as a file sync client, before giving up.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Attempts made to connect to a database that cannot be reached, and the
+/// pause between them.
+const CONNECT_ATTEMPTS: u32 = 3;
+const CONNECT_DELAY: Duration = Duration::from_millis(500);
+
+/// Hulls with their tech level. A hull with none, as in campaigns from before
+/// hulls had one, needs the standard level for its size (`tech::hull_tech`).
+const HULLS: &str = "SELECT name, size, maint, COALESCE(tech, MAX(size - 4, 0) / 2) AS tech
+    FROM hulls";
 
 /// Data storage layer Error type.
 #[derive(Debug)]
 pub enum DataError {
+    Archive(String),
+    Batch(usize, Box<DataError>),
     Io(io::Error),
+    Json(serde_json::Error),
     Parse(num::ParseIntError),
+    Refused(String),
+    Schema(String),
     Sqlx(sqlx::Error),
+    Task(tokio::task::JoinError),
+    Unavailable(String),
+}
+
+impl DataError {
+    /// Whether the error came from the database file being out of reach,
+    /// locked or lost, rather than from what was asked of it. Reconnecting
+    /// may put it right.
+    pub fn is_unavailable(&self) -> bool {
+        match self {
+            Self::Batch(_, e) => e.is_unavailable(),
+            Self::Unavailable(_) => true,
+            _ => false,
+        }
+    }
 }
 
 impl fmt::Display for DataError {
@@ -37,9 +132,16 @@ impl fmt::Display for DataError {
             f,
             "{}",
             match self {
+                Self::Archive(e) => e.to_string(),
+                Self::Batch(n, e) => format!("Change {}: {}", n, e),
                 Self::Io(e) => e.to_string(),
+                Self::Json(e) => e.to_string(),
                 Self::Parse(e) => e.to_string(),
+                Self::Refused(e) => e.to_string(),
+                Self::Schema(e) => e.to_string(),
                 Self::Sqlx(e) => e.to_string(),
+                Self::Task(e) => e.to_string(),
+                Self::Unavailable(e) => format!("The campaign database is unavailable: {}", e),
             }
         )
     }
@@ -53,6 +155,12 @@ impl From<io::Error> for DataError {
     }
 }
 
+impl From<serde_json::Error> for DataError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Json(e)
+    }
+}
+
 impl From<num::ParseIntError> for DataError {
     fn from(e: num::ParseIntError) -> Self {
         Self::Parse(e)
@@ -61,13 +169,82 @@ impl From<num::ParseIntError> for DataError {
 
 impl From<sqlx::Error> for DataError {
     fn from(e: sqlx::Error) -> Self {
+        // A name already taken is the GM's to fix, so say so plainly.
+        if let sqlx::Error::Database(db) = &e {
+            match db.message() {
+                "UNIQUE constraint failed: empires.name" => {
+                    return Self::Refused("Another empire already has that name".to_string())
+                }
+                "UNIQUE constraint failed: systems.name" => {
+                    return Self::Refused("Another system already has that name".to_string())
+                }
+                _ => (),
+            }
+        }
+        if unavailable(&e) {
+            return Self::Unavailable(e.to_string());
+        }
         Self::Sqlx(e)
     }
 }
 
-/// Persistent storage for a campaign's data.
+// Whether an error means the database could not be reached or used at all:
+// it is busy or locked, cannot be opened, could not be read or written, or
+// the connection to it is gone.
+fn unavailable(e: &sqlx::Error) -> bool {
+    match e {
+        sqlx::Error::Io(_) | sqlx::Error::PoolTimedOut | sqlx::Error::PoolClosed => true,
+        sqlx::Error::Database(db) => {
+            // SQLITE_BUSY, SQLITE_LOCKED, SQLITE_IOERR and SQLITE_CANTOPEN,
+            // which extended codes carry in their low byte.
+            let code = db.code().and_then(|c| c.parse::<i32>().ok()).unwrap_or(0);
+            matches!(code & 0xff, 5 | 6 | 10 | 14)
+        }
+        _ => false,
+    }
+}
+
+impl From<tokio::task::JoinError> for DataError {
+    fn from(e: tokio::task::JoinError) -> Self {
+        Self::Task(e)
+    }
+}
+
+/// Persistent storage for a campaign's data. Clones share the connection
+/// pool, so work can be handed to other tasks, and any unit of work begun.
+#[derive(Clone)]
 pub struct DataStore {
     pool: SqlitePool,
+    url: String,
+    work: Arc<Mutex<Option<Transaction<'static, Sqlite>>>>,
+}
+
+// The connection an operation runs on: one from the pool, or the
+// transaction of the unit of work under way. A transaction begun on the
+// latter is a savepoint within it.
+enum Conn<'a> {
+    Pool(PoolConnection<Sqlite>),
+    Work(MappedMutexGuard<'a, Transaction<'static, Sqlite>>),
+}
+
+impl Deref for Conn<'_> {
+    type Target = SqliteConnection;
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            Self::Pool(c) => c,
+            Self::Work(tx) => tx,
+        }
+    }
+}
+
+impl DerefMut for Conn<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        match self {
+            Self::Pool(c) => c,
+            Self::Work(tx) => tx,
+        }
+    }
 }
 
 impl DataStore {
@@ -75,19 +252,349 @@ impl DataStore {
     #[allow(unused)]
     pub async fn add_empires(&self, empires: Vec<Empire>) -> DataResult<()> {
         for e in empires {
-            self.insert_empire(e).await?
+            self.insert_empire(&e).await?;
         }
         Ok(())
     }
 
-    /// Add systems to the store.
+    /// Add systems to the store. They are all added, or none are.
+    #[allow(unused)]
     pub async fn add_systems(&self, systems: Vec<System>) -> DataResult<()> {
-        for s in systems {
-            self.insert_system(s).await?
+        let mut conn = self.conn().await?;
+        let mut tx = conn.begin().await?;
+        Self::insert_systems(&mut tx, &systems).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Add the empires, systems, classes and fleets read from a campaign
+    /// workbook, which must have passed `Workbook::check`. They are all
+    /// added, or none are.
+    pub async fn add_workbook(&self, book: &Workbook) -> DataResult<()> {
+        let mut conn = self.conn().await?;
+        let mut tx = conn.begin().await?;
+        let mut empires = BTreeMap::new();
+        for e in &book.empires {
+            let id = sqlx::query("INSERT INTO empires (name, treasury, tech) VALUES(?, ?, ?)")
+                .bind(e.name.as_str())
+                .bind(e.treasury)
+                .bind(e.tech)
+                .execute(&mut tx)
+                .await?
+                .last_insert_rowid();
+            empires.insert(e.name.as_str(), id);
+        }
+        let mut systems = BTreeMap::new();
+        for sys in &book.systems {
+            let id = sqlx::query(
+                "INSERT INTO systems (name, ptype, raw, cap, pop, mor, ind, x, y, owner)
+                VALUES(?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            )
+            .bind(sys.name.as_str())
+            .bind(sys.ptype.as_str())
+            .bind(sys.raw)
+            .bind(sys.cap)
+            .bind(sys.pop)
+            .bind(sys.mor)
+            .bind(sys.ind)
+            .bind(sys.x)
+            .bind(sys.y)
+            .bind(empires.get(sys.owner_name.as_str()).copied())
+            .execute(&mut tx)
+            .await?
+            .last_insert_rowid();
+            systems.insert(sys.name.as_str(), id);
+        }
+        let mut classes = BTreeMap::new();
+        for t in &book.classes {
+            let id = sqlx::query(
+                "INSERT INTO ship_types (class, hull, cost, cr, atk, def, cap, speed, empire)
+                VALUES(?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            )
+            .bind(t.class.as_str())
+            .bind(t.hull.as_str())
+            .bind(t.cost)
+            .bind(t.cr)
+            .bind(t.atk)
+            .bind(t.def)
+            .bind(t.cap)
+            .bind(t.speed)
+            .bind(empires.get(t.empire_name.as_str()).copied())
+            .execute(&mut tx)
+            .await?
+            .last_insert_rowid();
+            classes.insert((t.empire_name.as_str(), t.class.as_str()), id);
+        }
+        for f in &book.fleets {
+            let fleet = sqlx::query("INSERT INTO fleets (name, owner, location) VALUES(?, ?, ?)")
+                .bind(f.name.as_str())
+                .bind(empires.get(f.empire.as_str()).copied())
+                .bind(systems.get(f.location.as_str()).copied())
+                .execute(&mut tx)
+                .await?
+                .last_insert_rowid();
+            for (class, count) in &f.ships {
+                for _ in 0..*count {
+                    sqlx::query("INSERT INTO ships (stype, fleet, crip, moth) VALUES(?, ?, 0, 0)")
+                        .bind(classes.get(&(f.empire.as_str(), class.as_str())).copied())
+                        .bind(fleet)
+                        .execute(&mut tx)
+                        .await?;
+                }
+            }
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Add amounts, which may be negative, to empires' treasuries in one
+    /// transaction.
+    pub async fn adjust_treasuries(&self, changes: &[(i64, i32)]) -> DataResult<()> {
+        let mut conn = self.conn().await?;
+        let mut tx = conn.begin().await?;
+        for (empire, amount) in changes {
+            sqlx::query("UPDATE empires SET treasury = treasury + ? WHERE id = ?")
+                .bind(amount)
+                .bind(empire)
+                .execute(&mut tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Add an amount, which may be negative, to an empire's treasury.
+    pub async fn adjust_treasury(&self, empire: i64, amount: i32) -> DataResult<()> {
+        sqlx::query("UPDATE empires SET treasury = treasury + ? WHERE id = ?")
+            .bind(amount)
+            .bind(empire)
+            .execute(&mut *self.conn().await?)
+            .await?;
+        Ok(())
+    }
+
+    /// Make a list of changes in one transaction, returning the ID of the
+    /// row each added, updated or deleted. If any change fails, none are
+    /// made, and the error says which.
+    pub async fn apply_batch(&self, changes: &[Change]) -> DataResult<Vec<i64>> {
+        let mut conn = self.conn().await?;
+        let mut tx = conn.begin().await?;
+        let mut ids = Vec::new();
+        for (n, c) in changes.iter().enumerate() {
+            match Self::apply_change(&mut tx, c, &ids).await {
+                Ok(id) => ids.push(id),
+                Err(e) => return Err(DataError::Batch(n + 1, Box::new(e))),
+            }
         }
+        tx.commit().await?;
+        Ok(ids)
+    }
+
+    /// Record the losses of a battle: cripple the surviving damaged ships
+    /// and remove the destroyed ones, with the flights they carried. Flights
+    /// are given by their negated IDs.
+    pub async fn apply_battle(&self, crippled: &[i64], destroyed: &[i64]) -> DataResult<()> {
+        let mut conn = self.conn().await?;
+        let mut tx = conn.begin().await?;
+        for id in crippled {
+            let (sql, id) = if *id >= facility::COMBAT_ID {
+                (
+                    "UPDATE facilities SET crip = 1 WHERE id = ?",
+                    id - facility::COMBAT_ID,
+                )
+            } else if *id < 0 {
+                ("UPDATE flights SET crip = 1 WHERE id = ?", -id)
+            } else {
+                ("UPDATE ships SET crip = 1 WHERE id = ?", *id)
+            };
+            sqlx::query(sql).bind(id).execute(&mut tx).await?;
+        }
+        for id in destroyed {
+            if *id >= facility::COMBAT_ID {
+                sqlx::query("DELETE FROM facilities WHERE id = ?")
+                    .bind(id - facility::COMBAT_ID)
+                    .execute(&mut tx)
+                    .await?;
+                continue;
+            }
+            if *id < 0 {
+                sqlx::query("DELETE FROM flights WHERE id = ?")
+                    .bind(-id)
+                    .execute(&mut tx)
+                    .await?;
+                continue;
+            }
+            sqlx::query("DELETE FROM flights WHERE carrier = ?")
+                .bind(id)
+                .execute(&mut tx)
+                .await?;
+            sqlx::query("DELETE FROM construction WHERE kind = ? AND item = ?")
+                .bind(construction::REPAIR)
+                .bind(id)
+                .execute(&mut tx)
+                .await?;
+            sqlx::query("DELETE FROM ships WHERE id = ?")
+                .bind(id)
+                .execute(&mut tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Record what came of a turn's development orders, all in one
+    /// transaction: save the systems, use up the colony ships, and refund
+    /// the orders that could not be carried out.
+    pub async fn apply_developments(&self, resolutions: &[Resolution]) -> DataResult<()> {
+        let mut conn = self.conn().await?;
+        let mut tx = conn.begin().await?;
+        for r in resolutions {
+            let sys = &r.system;
+            sqlx::query(
+                "UPDATE systems SET
+                (owner, pop, mor, ind, dev, fails, minor, contender, share) =
+                (NULLIF(?, 0), ?, ?, ?, ?, ?, ?, ?, ?) WHERE id = ?",
+            )
+            .bind(sys.owner)
+            .bind(sys.pop)
+            .bind(sys.mor)
+            .bind(sys.ind)
+            .bind(sys.dev)
+            .bind(sys.fails)
+            .bind(sys.minor.as_str())
+            .bind(sys.contender)
+            .bind(sys.share)
+            .bind(sys.id)
+            .execute(&mut tx)
+            .await?;
+            for ship in &r.ships {
+                sqlx::query("DELETE FROM ships WHERE id = ?")
+                    .bind(ship)
+                    .execute(&mut tx)
+                    .await?;
+            }
+            for d in &r.refunded {
+                sqlx::query("DELETE FROM developments WHERE id = ?")
+                    .bind(d.id)
+                    .execute(&mut tx)
+                    .await?;
+                sqlx::query("UPDATE empires SET treasury = treasury + ? WHERE id = ?")
+                    .bind(d.amount)
+                    .bind(d.empire)
+                    .execute(&mut tx)
+                    .await?;
+            }
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Record the result of a planetary assault: remove the ground units
+    /// lost, and if the system fell, give it to the invader, land the
+    /// surviving attackers there, lift any blockade and note the conquest.
+    pub async fn apply_invasion(
+        &self,
+        target: i64,
+        invader: Option<i64>,
+        lost: &[i64],
+        landed: &[i64],
+    ) -> DataResult<()> {
+        let mut conn = self.conn().await?;
+        let mut tx = conn.begin().await?;
+        for id in lost {
+            sqlx::query("DELETE FROM ground_units WHERE id = ?")
+                .bind(id)
+                .execute(&mut tx)
+                .await?;
+        }
+        if let Some(owner) = invader {
+            sqlx::query(
+                "UPDATE systems SET owner = ?, blockaded = 0, minor = '', contender = 0,
+                share = 100, policy = '' WHERE id = ?",
+            )
+            .bind(owner)
+            .bind(target)
+            .execute(&mut tx)
+            .await?;
+            sqlx::query(
+                "INSERT INTO conquests (system, turn)
+                VALUES(?, (SELECT CAST(value AS INTEGER) FROM control WHERE key = 'turn'))
+                ON CONFLICT (system) DO UPDATE SET turn = excluded.turn",
+            )
+            .bind(target)
+            .execute(&mut tx)
+            .await?;
+            for id in landed {
+                sqlx::query("UPDATE ground_units SET loc = ? WHERE id = ?")
+                    .bind(target)
+                    .bind(id)
+                    .execute(&mut tx)
+                    .await?;
+            }
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Record where fleets stopped moving, each with the rest of its course,
+    /// all in one transaction. A fleet with no course left has arrived.
+    pub async fn apply_movement(&self, transits: &[Transit]) -> DataResult<()> {
+        let mut conn = self.conn().await?;
+        let mut tx = conn.begin().await?;
+        for t in transits {
+            sqlx::query("UPDATE fleets SET location = ? WHERE id = ?")
+                .bind(t.at)
+                .bind(t.fleet)
+                .execute(&mut tx)
+                .await?;
+            Self::replace_course(&mut tx, t.fleet, &t.course).await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Carry out scorched earth orders at a system: save what is left of it,
+    /// lose its builds if the shipyards went, and use up the orders.
+    pub async fn apply_scorched_earth(
+        &self,
+        sys: &System,
+        orders: &[ScorchOrder],
+    ) -> DataResult<()> {
+        let mut conn = self.conn().await?;
+        let mut tx = conn.begin().await?;
+        sqlx::query("UPDATE systems SET raw = ?, cap = ?, pop = ?, mor = ?, ind = ? WHERE id = ?")
+            .bind(sys.raw)
+            .bind(sys.cap)
+            .bind(sys.pop)
+            .bind(sys.mor)
+            .bind(sys.ind)
+            .bind(sys.id)
+            .execute(&mut tx)
+            .await?;
+        if orders.iter().any(|o| o.target == scorch::SHIPYARDS) {
+            sqlx::query("DELETE FROM construction WHERE system = ?")
+                .bind(sys.id)
+                .execute(&mut tx)
+                .await?;
+        }
+        for o in orders {
+            sqlx::query("DELETE FROM scorch_orders WHERE id = ?")
+                .bind(o.id)
+                .execute(&mut tx)
+                .await?;
+        }
+        tx.commit().await?;
         Ok(())
     }
 
+    /// Return the folder holding the attached files of a campaign kept in
+    /// `dir`: its notes, generated reports, snapshots and report templates.
+    /// The folder sits beside the database and is not created until
+    /// something is attached.
+    pub fn attachments(dir: &path::Path, name: &str) -> path::PathBuf {
+        dir.join(name.replace(' ', "_"))
+    }
+
     /// Return list of available campaigns.
     pub fn available_campaigns() -> DataResult<Vec<String>> {
         let folder = Self::folder()?;
@@ -114,421 +621,6455 @@ impl DataStore {
         Ok(names)
     }
 
+    /// Return the folder holding the backups of a campaign kept in `dir`.
+    /// The folder is not created until a backup is written.
+    pub fn backups(dir: &path::Path, name: &str) -> path::PathBuf {
+        dir.join("backups").join(name.replace(' ', "_"))
+    }
+
+    /// Begin a unit of work: every change made through the store, or any of
+    /// its clones, until it is committed or rolled back is made in one
+    /// transaction.
+    pub async fn begin_work(&self) -> DataResult<()> {
+        let mut work = self.work.lock().await;
+        if work.is_none() {
+            *work = Some(self.pool.begin().await?);
+        }
+        Ok(())
+    }
+
+    /// Make the same change to every given system, all together or not at
+    /// all, returning the systems as they were left.
+    pub async fn bulk_update_systems(
+        &self,
+        ids: &[i64],
+        edit: &BulkEdit,
+    ) -> DataResult<Vec<System>> {
+        let mut conn = self.conn().await?;
+        let mut tx = conn.begin().await?;
+        let mut v = Vec::new();
+        for id in ids {
+            let sys: System = sqlx::query_as("SELECT * FROM systems WHERE id = ?")
+                .bind(id)
+                .fetch_one(&mut tx)
+                .await?;
+            let sys = edit.apply(&sys);
+            Self::apply_change(&mut tx, &Change::UpdateSystem(sys.clone()), &[]).await?;
+            // An update leaves the owner alone when it is not set.
+            if edit.owner == Some(0) {
+                sqlx::query("UPDATE systems SET owner = NULL WHERE id = ?")
+                    .bind(id)
+                    .execute(&mut tx)
+                    .await?;
+            }
+            v.push(sys);
+        }
+        tx.commit().await?;
+        Ok(v)
+    }
+
+    /// Summarize every available campaign: its turn and empire count, read
+    /// from a read-only connection so that nothing is migrated or written,
+    /// and when its database was last modified. A campaign whose database
+    /// cannot be read is still listed, without turn or empires.
+    pub async fn campaign_summaries() -> DataResult<Vec<CampaignSummary>> {
+        // SQLite formats the modification times, in local time.
+        let clock = SqlitePool::connect("sqlite::memory:").await?;
+        let mut summaries = Vec::new();
+        for name in Self::available_campaigns()? {
+            let dbpath = Self::path(&Self::folder()?, &name);
+            let secs = fs::metadata(&dbpath)
+                .and_then(|m| m.modified())
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_secs() as i64);
+            let modified: Option<String> = match secs {
+                Some(s) => {
+                    sqlx::query_scalar("SELECT datetime(?, 'unixepoch', 'localtime')")
+                        .bind(s)
+                        .fetch_one(&clock)
+                        .await?
+                }
+                None => None,
+            };
+
+            let url = format!("sqlite://{}?mode=ro", dbpath.to_str().unwrap());
+            let (turn, empires) = match SqlitePool::connect(url.as_str()).await {
+                Ok(pool) => {
+                    let counts = Self::summarize(&pool).await;
+                    pool.close().await;
+                    counts.unwrap_or((None, None))
+                }
+                Err(_) => (None, None),
+            };
+            summaries.push(CampaignSummary {
+                name,
+                turn,
+                empires,
+                modified: modified.unwrap_or_default(),
+            });
+        }
+        clock.close().await;
+        summaries.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(summaries)
+    }
+
+    /// Remove a build from the construction queue, refunding its cost.
+    pub async fn cancel_build(&self, build: &Build) -> DataResult<()> {
+        let mut conn = self.conn().await?;
+        let mut tx = conn.begin().await?;
+        sqlx::query("DELETE FROM construction WHERE id = ?")
+            .bind(build.id)
+            .execute(&mut tx)
+            .await?;
+        sqlx::query("UPDATE empires SET treasury = treasury + ? WHERE id = ?")
+            .bind(build.cost)
+            .bind(build.empire)
+            .execute(&mut tx)
+            .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Check the database can still be reached.
+    pub async fn check(&self) -> DataResult<()> {
+        sqlx::query("SELECT 1")
+            .execute(&mut *self.conn().await?)
+            .await?;
+        Ok(())
+    }
+
     /// Close the underlying storage.
     pub async fn close(&self) {
         self.pool.close().await
     }
 
+    /// Commit the unit of work under way, if there is one.
+    pub async fn commit_work(&self) -> DataResult<()> {
+        if let Some(tx) = self.work.lock().await.take() {
+            tx.commit().await?;
+        }
+        Ok(())
+    }
+
     /// Return the current turn number.
     pub async fn current_turn(&self) -> DataResult<i32> {
         let r = sqlx::query("SELECT value FROM control WHERE key = 'turn'")
-            .fetch_one(&self.pool)
+            .fetch_one(&mut *self.conn().await?)
             .await?;
         let val: String = r.get("value");
         let turn = val.parse::<i32>()?;
         Ok(turn)
     }
 
-    /// Delete a persistent store by name.
-    pub fn delete(name: &str) -> DataResult<()> {
-        let dbpath = Self::path(name)?;
-        fs::remove_file(dbpath)?;
+    /// Delete a persistent store kept in `dir` by name, along with its
+    /// attached files and backups.
+    pub fn delete(dir: &path::Path, name: &str) -> DataResult<()> {
+        fs::remove_file(Self::path(dir, name))?;
+        for dir in [Self::attachments(dir, name), Self::backups(dir, name)] {
+            if dir.exists() {
+                fs::remove_dir_all(dir)?;
+            }
+        }
         Ok(())
     }
 
-    /// Delete an existing system.
-    pub async fn delete_system(&self, sys: &System) -> DataResult<()> {
-        sqlx::query("DELETE FROM systems WHERE id=?")
-            .bind(sys.id)
-            .execute(&self.pool)
+    /// Delete a combat modifier.
+    pub async fn delete_combat_modifier(&self, m: &CombatModifier) -> DataResult<()> {
+        sqlx::query("DELETE FROM combat_modifiers WHERE id = ?")
+            .bind(m.id)
+            .execute(&mut *self.conn().await?)
             .await?;
         Ok(())
     }
 
-    /// Return the name for the empire ID.
-    pub async fn get_empire_name(&self, id: i64) -> DataResult<String> {
-        let n = sqlx::query("SELECT name FROM empires WHERE id=?")
-            .bind(id)
-            .fetch_one(&self.pool)
+    /// Delete a development order, refunding what was paid for it.
+    pub async fn delete_development(&self, order: &Development) -> DataResult<()> {
+        let mut conn = self.conn().await?;
+        let mut tx = conn.begin().await?;
+        sqlx::query("DELETE FROM developments WHERE id = ?")
+            .bind(order.id)
+            .execute(&mut tx)
             .await?;
-        Ok(n.get(0))
+        sqlx::query("UPDATE empires SET treasury = treasury + ? WHERE id = ?")
+            .bind(order.amount)
+            .bind(order.empire)
+            .execute(&mut tx)
+            .await?;
+        tx.commit().await?;
+        Ok(())
     }
 
-    /// Return a system by ID.
-    #[allow(unused)]
-    pub async fn get_system_by_id(&self, id: i64) -> DataResult<System> {
-        let mut sys: System = sqlx::query_as("SELECT * FROM systems WHERE id = ?")
-            .bind(id)
-            .fetch_one(&self.pool)
-            .await?;
-        sys.owner_name = match sys.owner {
-            0 => "None".to_string(),
-            n => self.get_empire_name(n).await?,
-        };
-        Ok(sys)
+    /// Delete an existing empire, with its orders, records and ship classes.
+    /// Systems it owned become unowned and its claims on others are dropped.
+    /// Refused while it still has fleets, flights or ships in service.
+    pub async fn delete_empire(&self, emp: &Empire) -> DataResult<()> {
+        self.apply(Change::DeleteEmpire(emp.id)).await.map(|_| ())
     }
 
-    /// Return a system by name.
-    #[allow(unused)]
-    pub async fn get_system_by_name(&self, name: &str) -> DataResult<System> {
-        let mut sys: System = sqlx::query_as("SELECT * FROM systems WHERE name = ?")
-            .bind(name)
-            .fetch_one(&self.pool)
+    /// Stop a fleet escorting another.
+    pub async fn delete_escort(&self, escort: &Escort) -> DataResult<()> {
+        sqlx::query("DELETE FROM escorts WHERE id = ?")
+            .bind(escort.id)
+            .execute(&mut *self.conn().await?)
             .await?;
-        sys.owner_name = match sys.owner {
-            0 => "None".to_string(),
-            n => self.get_empire_name(n).await?,
-        };
-        Ok(sys)
+        Ok(())
     }
 
-    /// Return the systems from the store.
-    pub async fn get_systems(&self) -> DataResult<Vec<System>> {
-        let v: Vec<System> = sqlx::query_as("SELECT * FROM systems")
-            .fetch_all(&self.pool)
-            .await?;
-        let mut res = Vec::new();
-        for mut s in v {
-            s.owner_name = match s.owner {
-                0 => "None".to_string(),
-                n => self.get_empire_name(n).await?,
-            };
-            res.push(s)
-        }
-        Ok(res)
+    /// Delete a facility.
+    pub async fn delete_facility(&self, facility: &Facility) -> DataResult<()> {
+        sqlx::query("DELETE FROM facilities WHERE id = ?")
+            .bind(facility.id)
+            .execute(&mut *self.conn().await?)
+            .await?;
+        Ok(())
     }
 
-    /// Create a new data store using the specified name.
-    pub async fn new(name: &str) -> DataResult<Self> {
-        let dbpath = Self::path(name)?;
-        if dbpath.exists() {
-            // This database already exists, so can't create a new campaign
-            // with the same name.
-            return Err(DataError::Io(io::Error::from(io::ErrorKind::AlreadyExists)));
-        }
+    /// Disband a fleet, removing the fleet and every ship in it, with the
+    /// flights they carry.
+    pub async fn delete_fleet(&self, fleet: i64) -> DataResult<()> {
+        self.apply(Change::DeleteFleet(fleet)).await.map(|_| ())
+    }
 
-        // Create and connect to the database.
-        let url = format!("sqlite://{}?mode=rwc", dbpath.to_str().unwrap());
-        let pool = SqlitePool::connect(url.as_str()).await?;
+    /// Disband a flight.
+    pub async fn delete_flight(&self, flight: &Flight) -> DataResult<()> {
+        sqlx::query("DELETE FROM flights WHERE id = ?")
+            .bind(flight.id)
+            .execute(&mut *self.conn().await?)
+            .await?;
+        Ok(())
+    }
 
-        Self::create_tables(&pool).await?;
-        Ok(Self { pool })
+    /// Disband ground units.
+    pub async fn delete_ground_units(&self, units: &[i64]) -> DataResult<()> {
+        let mut conn = self.conn().await?;
+        let mut tx = conn.begin().await?;
+        for id in units {
+            sqlx::query("DELETE FROM ground_units WHERE id = ?")
+                .bind(id)
+                .execute(&mut tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(())
     }
 
-    /// Open an existing data store.
-    pub async fn open(name: &str) -> DataResult<Self> {
-        let dbpath = Self::path(name)?;
+    /// Delete an empire's identification of a ship class.
+    pub async fn delete_identification(&self, item: &Identification) -> DataResult<()> {
+        sqlx::query("DELETE FROM identifications WHERE id = ?")
+            .bind(item.id)
+            .execute(&mut *self.conn().await?)
+            .await?;
+        Ok(())
+    }
 
-        // Connect to the database.
-        let url = format!("sqlite://{}", dbpath.to_str().unwrap());
-        let pool = SqlitePool::connect(url.as_str()).await?;
+    /// Delete an intel item.
+    pub async fn delete_intel(&self, item: &IntelItem) -> DataResult<()> {
+        sqlx::query("DELETE FROM intel WHERE id = ?")
+            .bind(item.id)
+            .execute(&mut *self.conn().await?)
+            .await?;
+        Ok(())
+    }
 
-        Ok(Self { pool })
+    /// Delete a jump lane.
+    pub async fn delete_lane(&self, lane: &Lane) -> DataResult<()> {
+        self.apply(Change::DeleteLane(lane.id)).await.map(|_| ())
     }
 
-    /// Update an existing system.
-    pub async fn update_system(&self, sys: &System) -> DataResult<()> {
-        if sys.owner == 0 {
-            // Skip updating owner if it's not set.
-            sqlx::query(
-                "UPDATE systems SET
-                (name, ptype, raw, cap, pop, mor, ind, dev, fails) =
-                (?, ?, ?, ?, ?, ?, ?, ?, ?) WHERE id = ?",
-            )
-            .bind(sys.name.as_str())
-            .bind(sys.ptype.as_str())
-            .bind(sys.raw)
-            .bind(sys.cap)
-            .bind(sys.pop)
-            .bind(sys.mor)
-            .bind(sys.ind)
-            .bind(sys.dev)
-            .bind(sys.fails)
-            .bind(sys.id)
-            .execute(&self.pool)
+    /// Delete an espionage operation, refunding its intel points.
+    pub async fn delete_operation(&self, op: &Operation) -> DataResult<()> {
+        let mut conn = self.conn().await?;
+        let mut tx = conn.begin().await?;
+        sqlx::query("DELETE FROM espionage WHERE id = ?")
+            .bind(op.id)
+            .execute(&mut tx)
             .await?;
-        } else {
-            sqlx::query(
-                "UPDATE systems SET
-                (name, ptype, raw, cap, pop, mor, ind, dev, fails, owner) =
-                (?, ?, ?, ?, ?, ?, ?, ?, ?, ?) WHERE id = ?",
-            )
-            .bind(sys.name.as_str())
-            .bind(sys.ptype.as_str())
-            .bind(sys.raw)
-            .bind(sys.cap)
-            .bind(sys.pop)
-            .bind(sys.mor)
-            .bind(sys.ind)
-            .bind(sys.dev)
-            .bind(sys.fails)
-            .bind(sys.owner)
-            .bind(sys.id)
-            .execute(&self.pool)
+        sqlx::query("UPDATE empires SET treasury = treasury + ? WHERE id = ?")
+            .bind(op.points)
+            .bind(op.empire)
+            .execute(&mut tx)
             .await?;
-        }
+        tx.commit().await?;
         Ok(())
     }
 
-    async fn create_controls_table(pool: &SqlitePool) -> DataResult<()> {
-        sqlx::query(
-            "CREATE TABLE IF NOT EXISTS control (
-            key TEXT PRIMARY KEY,
-            value TEXT)",
-        )
-        .execute(pool)
-        .await?;
+    /// Delete an order.
+    pub async fn delete_order(&self, order: &Order) -> DataResult<()> {
+        sqlx::query("DELETE FROM orders WHERE id = ?")
+            .bind(order.id)
+            .execute(&mut *self.conn().await?)
+            .await?;
+        Ok(())
+    }
 
-        sqlx::query(
-            "INSERT INTO control VALUES
-            ('turn', '0')",
-        )
-        .execute(pool)
-        .await?;
+    /// Delete an order template.
+    pub async fn delete_order_template(&self, tmpl: &OrderTemplate) -> DataResult<()> {
+        sqlx::query("DELETE FROM order_templates WHERE id = ?")
+            .bind(tmpl.id)
+            .execute(&mut *self.conn().await?)
+            .await?;
+        Ok(())
+    }
 
+    /// Delete a raid order.
+    pub async fn delete_raid(&self, raid: &Raid) -> DataResult<()> {
+        sqlx::query("DELETE FROM raids WHERE id = ?")
+            .bind(raid.id)
+            .execute(&mut *self.conn().await?)
+            .await?;
         Ok(())
     }
 
-    async fn create_empires_table(pool: &SqlitePool) -> DataResult<()> {
-        sqlx::query(
-            "CREATE TABLE IF NOT EXISTS empires (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            name TEXT,
-            treasury INTEGER DEFAULT 0,
-            tech INTEGER DEFAULT 0)",
+    /// Delete a scorched earth order.
+    pub async fn delete_scorch_order(&self, order: &ScorchOrder) -> DataResult<()> {
+        sqlx::query("DELETE FROM scorch_orders WHERE id = ?")
+            .bind(order.id)
+            .execute(&mut *self.conn().await?)
+            .await?;
+        Ok(())
+    }
+
+    /// Delete a ship class, and every empire's identification of it.
+    pub async fn delete_ship_type(&self, stype: &ShipType) -> DataResult<()> {
+        let mut conn = self.conn().await?;
+        let mut tx = conn.begin().await?;
+        sqlx::query("DELETE FROM identifications WHERE stype = ?")
+            .bind(stype.id)
+            .execute(&mut tx)
+            .await?;
+        sqlx::query("DELETE FROM ship_types WHERE id = ?")
+            .bind(stype.id)
+            .execute(&mut tx)
+            .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Delete an existing system, with its lanes, facilities, garrison and
+    /// the orders aimed at it. Refused while fleets or flights are there.
+    pub async fn delete_system(&self, sys: &System) -> DataResult<()> {
+        self.apply(Change::DeleteSystem(sys.id)).await.map(|_| ())
+    }
+
+    /// Delete a transfer before it is carried out.
+    pub async fn delete_transfer(&self, transfer: &Transfer) -> DataResult<()> {
+        sqlx::query("DELETE FROM transfers WHERE id = ?")
+            .bind(transfer.id)
+            .execute(&mut *self.conn().await?)
+            .await?;
+        Ok(())
+    }
+
+    /// Delete a treaty.
+    pub async fn delete_treaty(&self, treaty: &Treaty) -> DataResult<()> {
+        sqlx::query("DELETE FROM treaties WHERE id = ?")
+            .bind(treaty.id)
+            .execute(&mut *self.conn().await?)
+            .await?;
+        Ok(())
+    }
+
+    /// End any war between two empires as of the given turn.
+    pub async fn end_war(&self, a: i64, b: i64, turn: i32) -> DataResult<()> {
+        sqlx::query("UPDATE wars SET ended = ? WHERE a = ? AND b = ? AND ended IS NULL")
+            .bind(turn)
+            .bind(a.min(b))
+            .bind(a.max(b))
+            .execute(&mut *self.conn().await?)
+            .await?;
+        Ok(())
+    }
+
+    /// Copy the contents of every table into a JSON value of the form
+    /// `{"tables": {"name": {"columns": [...], "rows": [[...], ...]}}}`.
+    pub async fn export(&self) -> DataResult<Value> {
+        let names: Vec<String> = sqlx::query_scalar(
+            "SELECT name FROM sqlite_master
+            WHERE type = 'table' AND name NOT LIKE 'sqlite_%'
+            ORDER BY name",
         )
-        .execute(pool)
+        .fetch_all(&mut *self.conn().await?)
+        .await?;
+
+        let mut tables = Map::new();
+        for name in names {
+            let columns: Vec<String> =
+                sqlx::query_scalar("SELECT name FROM pragma_table_info(?) ORDER BY cid")
+                    .bind(name.as_str())
+                    .fetch_all(&mut *self.conn().await?)
+                    .await?;
+            let mut rows = Vec::new();
+            for r in sqlx::query(format!("SELECT * FROM \"{}\"", name).as_str())
+                .fetch_all(&mut *self.conn().await?)
+                .await?
+            {
+                let mut row = Vec::new();
+                for c in r.columns() {
+                    let raw = r.try_get_raw(c.ordinal())?;
+                    if raw.is_null() {
+                        row.push(Value::Null);
+                        continue;
+                    }
+                    let kind = raw.type_info().name().to_string();
+                    row.push(match kind.as_str() {
+                        "INTEGER" | "BOOLEAN" => json!(r.try_get::<i64, _>(c.ordinal())?),
+                        "REAL" => json!(r.try_get::<f64, _>(c.ordinal())?),
+                        "TEXT" => json!(r.try_get::<String, _>(c.ordinal())?),
+                        _ => {
+                            return Err(DataError::Archive(format!(
+                                "{}.{} holds unsupported {} data",
+                                name,
+                                c.name(),
+                                kind
+                            )))
+                        }
+                    });
+                }
+                rows.push(Value::Array(row));
+            }
+            tables.insert(name, json!({ "columns": columns, "rows": rows }));
+        }
+        Ok(json!({ "tables": tables }))
+    }
+
+    /// Remove a completed build from the construction queue and place the
+    /// new units or facility at its system. Ships join the empire's new
+    /// construction fleet there, which is created if needed.
+    pub async fn finish_build(&self, build: &Build) -> DataResult<()> {
+        let mut conn = self.conn().await?;
+        let mut tx = conn.begin().await?;
+        sqlx::query("DELETE FROM construction WHERE id = ?")
+            .bind(build.id)
+            .execute(&mut tx)
+            .await?;
+        match build.kind.as_str() {
+            "Ship" => {
+                let found: Option<i64> = sqlx::query_scalar(
+                    "SELECT id FROM fleets WHERE owner = ? AND location = ? AND name = ?",
+                )
+                .bind(build.empire)
+                .bind(build.system)
+                .bind(NEW_CONSTRUCTION)
+                .fetch_optional(&mut tx)
+                .await?;
+                let fleet = match found {
+                    Some(id) => id,
+                    None => {
+                        sqlx::query("INSERT INTO fleets (name, owner, location) VALUES(?, ?, ?)")
+                            .bind(NEW_CONSTRUCTION)
+                            .bind(build.empire)
+                            .bind(build.system)
+                            .execute(&mut tx)
+                            .await?
+                            .last_insert_rowid()
+                    }
+                };
+                for _ in 0..build.count {
+                    sqlx::query("INSERT INTO ships (stype, fleet, crip, moth) VALUES(?, ?, 0, 0)")
+                        .bind(build.item)
+                        .bind(fleet)
+                        .execute(&mut tx)
+                        .await?;
+                }
+                sqlx::query(
+                    "INSERT INTO service (turn, stype, built)
+                    VALUES((SELECT CAST(value AS INTEGER) FROM control WHERE key = 'turn'), ?, ?)",
+                )
+                .bind(build.item)
+                .bind(build.count)
+                .execute(&mut tx)
+                .await?;
+            }
+            "Ground" => {
+                for _ in 0..build.count {
+                    sqlx::query("INSERT INTO ground_units (gtype, loc) VALUES(?, ?)")
+                        .bind(build.item)
+                        .bind(build.system)
+                        .execute(&mut tx)
+                        .await?;
+                }
+            }
+            _ => {
+                for _ in 0..build.count {
+                    sqlx::query("INSERT INTO facilities (system, kind) VALUES(?, ?)")
+                        .bind(build.system)
+                        .bind(build.name.as_str())
+                        .execute(&mut tx)
+                        .await?;
+                }
+            }
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Return the systems where battles were fought in a turn.
+    pub async fn get_battle_sites(&self, turn: i32) -> DataResult<Vec<i64>> {
+        let v = sqlx::query_scalar("SELECT system FROM battles WHERE turn = ? ORDER BY system")
+            .bind(turn)
+            .fetch_all(&mut *self.conn().await?)
+            .await?;
+        Ok(v)
+    }
+
+    /// Return the construction queue of all empires.
+    pub async fn get_builds(&self) -> DataResult<Vec<Build>> {
+        let v = sqlx::query_as(
+            "SELECT c.*, COALESCE(e.name, 'None') AS empire_name,
+            COALESCE(s.name, 'None') AS system_name
+            FROM construction c
+            LEFT JOIN empires e ON c.empire = e.id
+            LEFT JOIN systems s ON c.system = s.id
+            ORDER BY empire_name, system_name, c.id",
+        )
+        .fetch_all(&mut *self.conn().await?)
+        .await?;
+        Ok(v)
+    }
+
+    /// Return the options the campaign was created with.
+    pub async fn get_campaign_options(&self) -> DataResult<CampaignOptions> {
+        let v: Vec<(String, String)> = sqlx::query_as("SELECT key, value FROM control")
+            .fetch_all(&mut *self.conn().await?)
+            .await?;
+        Ok(CampaignOptions::from_control(&v.into_iter().collect()))
+    }
+
+    /// Return the units lost or crippled in every war.
+    pub async fn get_casualties(&self) -> DataResult<Vec<Casualty>> {
+        let v = sqlx::query_as("SELECT * FROM casualties ORDER BY id")
+            .fetch_all(&mut *self.conn().await?)
+            .await?;
+        Ok(v)
+    }
+
+    /// Return the service record of every ship class, by empire and class.
+    pub async fn get_class_records(&self) -> DataResult<Vec<ClassRecord>> {
+        let v = sqlx::query_as(
+            "SELECT t.id AS stype, t.class, t.hull, t.cost,
+            COALESCE(e.name, '') AS empire_name,
+            COALESCE(SUM(s.built), 0) AS built,
+            (SELECT COUNT(*) FROM ships WHERE stype = t.id) AS active,
+            COALESCE(SUM(s.fought), 0) AS fought,
+            COALESCE(SUM(s.lost), 0) AS lost,
+            COALESCE(SUM(s.kills), 0.0) AS kills
+            FROM ship_types t
+            LEFT JOIN empires e ON t.empire = e.id
+            LEFT JOIN service s ON s.stype = t.id
+            GROUP BY t.id
+            ORDER BY empire_name, t.class",
+        )
+        .fetch_all(&mut *self.conn().await?)
+        .await?;
+        Ok(v)
+    }
+
+    /// Return the ID of a ship able to found a colony: one of the empire's
+    /// undamaged, active ships at the system that can carry cargo, the
+    /// smallest carrier first.
+    pub async fn get_colony_ship(&self, owner: i64, loc: i64) -> DataResult<Option<i64>> {
+        let id = sqlx::query_scalar(
+            "SELECT s.id
+            FROM ships s
+            JOIN ship_types t ON s.stype = t.id
+            JOIN fleets f ON s.fleet = f.id
+            WHERE f.owner = ? AND f.location = ? AND t.cap > 0 AND s.crip = 0 AND s.moth = 0
+            ORDER BY t.cap, s.id
+            LIMIT 1",
+        )
+        .bind(owner)
+        .bind(loc)
+        .fetch_optional(&mut *self.conn().await?)
+        .await?;
+        Ok(id)
+    }
+
+    /// Return the combat modifiers.
+    pub async fn get_combat_modifiers(&self) -> DataResult<Vec<CombatModifier>> {
+        let v = sqlx::query_as("SELECT * FROM combat_modifiers ORDER BY category, id")
+            .fetch_all(&mut *self.conn().await?)
+            .await?;
+        Ok(v)
+    }
+
+    /// Return the active ships at a system that would fight in a battle there,
+    /// with the flights based there or aboard them and the system's
+    /// starbases. Mothballed ships take no part. Each empire's starbases and
+    /// flights come before its ships, screening them. Flights fight under
+    /// their negated IDs, and starbases under their IDs plus
+    /// `facility::COMBAT_ID`.
+    pub async fn get_combatants(&self, location: i64) -> DataResult<Vec<Combatant>> {
+        let bases: Vec<(i64, i64, String, String, bool)> = sqlx::query_as(
+            "SELECT f.id, s.owner, COALESCE(e.name, 'None'), f.kind, f.crip
+            FROM facilities f
+            JOIN systems s ON f.system = s.id
+            LEFT JOIN empires e ON s.owner = e.id
+            WHERE f.system = ? AND s.owner IS NOT NULL
+            ORDER BY f.id",
+        )
+        .bind(location)
+        .fetch_all(&mut *self.conn().await?)
+        .await?;
+        let mut v: Vec<Combatant> = bases
+            .into_iter()
+            .filter_map(|(id, owner, owner_name, kind, crip)| {
+                let spec = facility::spec(kind.as_str()).filter(|s| s.def > 0)?;
+                Some(Combatant {
+                    id: facility::COMBAT_ID + id,
+                    owner,
+                    owner_name,
+                    class: kind,
+                    stype: 0,
+                    atk: spec.atk,
+                    def: spec.def,
+                    crip,
+                })
+            })
+            .collect();
+        let flights: Vec<Combatant> = sqlx::query_as(
+            "SELECT -l.id AS id, l.owner AS owner,
+            COALESCE(e.name, 'None') AS owner_name,
+            ? AS class, l.atk AS atk, l.def AS def, l.crip AS crip
+            FROM flights l
+            LEFT JOIN ships s ON l.carrier = s.id
+            LEFT JOIN fleets f ON s.fleet = f.id
+            LEFT JOIN empires e ON l.owner = e.id
+            WHERE COALESCE(f.location, l.base) = ? AND COALESCE(s.moth, 0) = 0
+            ORDER BY l.id",
+        )
+        .bind(flight::FLIGHT)
+        .bind(location)
+        .fetch_all(&mut *self.conn().await?)
+        .await?;
+        v.extend(flights);
+        let ships: Vec<Combatant> = sqlx::query_as(
+            "SELECT s.id AS id, f.owner AS owner,
+            COALESCE(e.name, 'None') AS owner_name,
+            t.class AS class, s.stype AS stype, t.atk AS atk, t.def AS def, s.crip AS crip
+            FROM ships s
+            JOIN ship_types t ON s.stype = t.id
+            JOIN fleets f ON s.fleet = f.id
+            LEFT JOIN empires e ON f.owner = e.id
+            LEFT JOIN escorts x ON x.escort = f.id
+            WHERE f.location = ? AND s.moth = 0
+            ORDER BY f.owner, x.id IS NULL, s.id",
+        )
+        .bind(location)
+        .fetch_all(&mut *self.conn().await?)
+        .await?;
+        v.extend(ships);
+        v.sort_by_key(|c| c.owner);
+        Ok(v)
+    }
+
+    /// Return the systems conquered on or after the given turn.
+    pub async fn get_conquered_since(&self, turn: i32) -> DataResult<Vec<i64>> {
+        let v = sqlx::query_scalar("SELECT system FROM conquests WHERE turn >= ?")
+            .bind(turn)
+            .fetch_all(&mut *self.conn().await?)
+            .await?;
+        Ok(v)
+    }
+
+    /// Return the course of every fleet under way, the systems still ahead
+    /// of it in order, by fleet ID.
+    pub async fn get_courses(&self) -> DataResult<BTreeMap<i64, Vec<i64>>> {
+        let rows: Vec<(i64, i64)> =
+            sqlx::query_as("SELECT fleet, system FROM courses ORDER BY fleet, step")
+                .fetch_all(&mut *self.conn().await?)
+                .await?;
+        let mut courses: BTreeMap<i64, Vec<i64>> = BTreeMap::new();
+        for (fleet, system) in rows {
+            courses.entry(fleet).or_default().push(system);
+        }
+        Ok(courses)
+    }
+
+    /// Return every empire's order deadline for a turn. Empires without a
+    /// deadline have an empty due date.
+    pub async fn get_deadlines(&self, turn: i32) -> DataResult<Vec<Deadline>> {
+        let v = sqlx::query_as(
+            "SELECT e.id AS empire, ? AS turn, COALESCE(d.due, '') AS due, d.submitted,
+            e.name AS empire_name
+            FROM empires e
+            LEFT JOIN deadlines d ON d.empire = e.id AND d.turn = ?
+            ORDER BY e.name",
+        )
+        .bind(turn)
+        .bind(turn)
+        .fetch_all(&mut *self.conn().await?)
+        .await?;
+        Ok(v)
+    }
+
+    /// Return the development orders given for a turn.
+    pub async fn get_developments(&self, turn: i32) -> DataResult<Vec<Development>> {
+        let v = sqlx::query_as(
+            "SELECT d.*, e.name AS empire_name, s.name AS system_name
+            FROM developments d
+            JOIN empires e ON d.empire = e.id
+            JOIN systems s ON d.system = s.id
+            WHERE d.turn = ?
+            ORDER BY d.id",
+        )
+        .bind(turn)
+        .fetch_all(&mut *self.conn().await?)
+        .await?;
+        Ok(v)
+    }
+
+    /// Return the name for the empire ID.
+    #[allow(unused)]
+    pub async fn get_empire_name(&self, id: i64) -> DataResult<String> {
+        let n = sqlx::query("SELECT name FROM empires WHERE id=?")
+            .bind(id)
+            .fetch_one(&mut *self.conn().await?)
+            .await?;
+        Ok(n.get(0))
+    }
+
+    /// Return the empires from the store.
+    pub async fn get_empires(&self) -> DataResult<Vec<Empire>> {
+        let v = sqlx::query_as("SELECT * FROM empires ORDER BY name")
+            .fetch_all(&mut *self.conn().await?)
+            .await?;
+        Ok(v)
+    }
+
+    /// Return every fleet escorting another.
+    pub async fn get_escorts(&self) -> DataResult<Vec<Escort>> {
+        let v = sqlx::query_as(
+            "SELECT x.*, e.name AS escort_name, c.name AS convoy_name
+            FROM escorts x
+            JOIN fleets e ON x.escort = e.id
+            JOIN fleets c ON x.convoy = c.id
+            ORDER BY c.name, e.name",
+        )
+        .fetch_all(&mut *self.conn().await?)
+        .await?;
+        Ok(v)
+    }
+
+    /// Return every facility, with its owner and system name.
+    pub async fn get_facilities(&self) -> DataResult<Vec<Facility>> {
+        let v = sqlx::query_as(
+            "SELECT f.*, COALESCE(s.owner, 0) AS owner, s.name AS system_name
+            FROM facilities f
+            JOIN systems s ON f.system = s.id
+            ORDER BY s.name, f.kind",
+        )
+        .fetch_all(&mut *self.conn().await?)
+        .await?;
+        Ok(v)
+    }
+
+    /// Return a fleet by ID.
+    pub async fn get_fleet(&self, id: i64) -> DataResult<Fleet> {
+        let f = sqlx::query_as("SELECT * FROM fleets WHERE id = ?")
+            .bind(id)
+            .fetch_one(&mut *self.conn().await?)
+            .await?;
+        Ok(f)
+    }
+
+    /// Return the number of jumps a fleet can make in a turn.
+    pub async fn get_fleet_allowance(&self, fleet: i64) -> DataResult<i32> {
+        let r = sqlx::query(
+            "SELECT f.speed AS speed, MIN(t.speed) AS class_speed
+            FROM fleets f
+            LEFT JOIN ships s ON s.fleet = f.id
+            LEFT JOIN ship_types t ON s.stype = t.id
+            WHERE f.id = ?
+            GROUP BY f.id",
+        )
+        .bind(fleet)
+        .fetch_one(&mut *self.conn().await?)
+        .await?;
+        Ok(movement::allowance(r.get("speed"), r.get("class_speed")))
+    }
+
+    /// Return the ships in a fleet.
+    pub async fn get_fleet_ships(&self, fleet: i64) -> DataResult<Vec<ShipLocation>> {
+        let v = sqlx::query_as(
+            "SELECT s.id AS id, t.class AS class,
+            COALESCE(f.name, 'None') AS fleet_name,
+            COALESCE(y.name, 'None') AS location_name,
+            s.crip AS crip, s.moth AS moth, f.owner AS owner, f.location AS location,
+            t.cost AS cost, t.cap AS cap
+            FROM ships s
+            JOIN ship_types t ON s.stype = t.id
+            JOIN fleets f ON s.fleet = f.id
+            LEFT JOIN systems y ON f.location = y.id
+            WHERE s.fleet = ?
+            ORDER BY t.class, s.id",
+        )
+        .bind(fleet)
+        .fetch_all(&mut *self.conn().await?)
+        .await?;
+        Ok(v)
+    }
+
+    /// Return the condition summary of every fleet.
+    pub async fn get_fleet_summaries(&self) -> DataResult<Vec<FleetSummary>> {
+        let mut v: Vec<FleetSummary> = sqlx::query_as(
+            "SELECT f.id AS id, f.name AS name,
+            COALESCE(f.owner, 0) AS owner, COALESCE(f.location, 0) AS location,
+            f.speed AS speed,
+            COALESCE(e.name, 'None') AS owner_name,
+            COALESCE(y.name, 'None') AS location_name,
+            COUNT(s.id) AS ships,
+            COALESCE(SUM(s.crip), 0) AS crippled,
+            COALESCE(SUM(t.atk), 0) AS atk,
+            COALESCE(SUM(t.def), 0) AS def
+            FROM fleets f
+            LEFT JOIN empires e ON f.owner = e.id
+            LEFT JOIN systems y ON f.location = y.id
+            LEFT JOIN ships s ON s.fleet = f.id
+            LEFT JOIN ship_types t ON s.stype = t.id
+            GROUP BY f.id
+            ORDER BY owner_name, f.name",
+        )
+        .fetch_all(&mut *self.conn().await?)
+        .await?;
+
+        // Maintenance depends on ship status, so total it here rather than in
+        // the aggregate query.
+        let rules = self.get_campaign_options().await?.ruleset();
+        let rows = sqlx::query(
+            "SELECT s.fleet, COALESCE(h.maint, 0) AS maint, s.crip, s.moth,
+            COUNT(*) AS count
+            FROM ships s
+            JOIN ship_types t ON s.stype = t.id
+            LEFT JOIN hulls h ON h.name = t.hull
+            GROUP BY s.fleet, t.hull, s.crip, s.moth",
+        )
+        .fetch_all(&mut *self.conn().await?)
+        .await?;
+        for r in rows {
+            let fleet: i64 = r.get("fleet");
+            let status = ShipStatus::from_flags(r.get("crip"), r.get("moth"));
+            let count: i32 = r.get("count");
+            if let Some(f) = v.iter_mut().find(|f| f.id == fleet) {
+                f.maint += count * rules.maintenance(status, r.get("maint"));
+            }
+        }
+
+        // Movement allowance depends on the slowest class in each fleet.
+        let rows = sqlx::query(
+            "SELECT f.id AS fleet, f.speed AS speed, MIN(t.speed) AS class_speed
+            FROM fleets f
+            LEFT JOIN ships s ON s.fleet = f.id
+            LEFT JOIN ship_types t ON s.stype = t.id
+            GROUP BY f.id",
+        )
+        .fetch_all(&mut *self.conn().await?)
+        .await?;
+        for r in rows {
+            let fleet: i64 = r.get("fleet");
+            if let Some(f) = v.iter_mut().find(|f| f.id == fleet) {
+                f.jumps = movement::allowance(r.get("speed"), r.get("class_speed"));
+            }
+        }
+        Ok(v)
+    }
+
+    /// Return the flights at a system: those based there and those aboard
+    /// carriers in fleets there.
+    pub async fn get_flights(&self, location: i64) -> DataResult<Vec<Flight>> {
+        let v = sqlx::query_as(
+            "SELECT l.*, COALESCE(f.location, l.base) AS location,
+            CASE WHEN l.carrier IS NULL THEN y.name || ' base'
+            ELSE t.class || ' #' || s.id END AS station
+            FROM flights l
+            LEFT JOIN ships s ON l.carrier = s.id
+            LEFT JOIN ship_types t ON s.stype = t.id
+            LEFT JOIN fleets f ON s.fleet = f.id
+            LEFT JOIN systems y ON l.base = y.id
+            WHERE COALESCE(f.location, l.base) = ?
+            ORDER BY l.owner, l.id",
+        )
+        .bind(location)
+        .fetch_all(&mut *self.conn().await?)
+        .await?;
+        Ok(v)
+    }
+
+    /// Return the ground unit types.
+    pub async fn get_ground_types(&self) -> DataResult<Vec<GroundType>> {
+        let v = sqlx::query_as("SELECT * FROM ground_types ORDER BY id")
+            .fetch_all(&mut *self.conn().await?)
+            .await?;
+        Ok(v)
+    }
+
+    /// Return the ground units garrisoned at a system.
+    pub async fn get_ground_units(&self, loc: i64) -> DataResult<Vec<GroundUnit>> {
+        let v = sqlx::query_as(
+            "SELECT u.*, g.name AS name, g.abbr AS abbr, g.atk AS atk, g.def AS def
+            FROM ground_units u
+            JOIN ground_types g ON u.gtype = g.id
+            WHERE u.loc = ?
+            ORDER BY u.id",
+        )
+        .bind(loc)
+        .fetch_all(&mut *self.conn().await?)
+        .await?;
+        Ok(v)
+    }
+
+    /// Return the ground units garrisoned at systems an empire owns.
+    pub async fn get_ground_units_by_owner(&self, owner: i64) -> DataResult<Vec<GroundUnit>> {
+        let v = sqlx::query_as(
+            "SELECT u.*, g.name AS name, g.abbr AS abbr, g.atk AS atk, g.def AS def
+            FROM ground_units u
+            JOIN ground_types g ON u.gtype = g.id
+            JOIN systems s ON u.loc = s.id
+            WHERE s.owner = ?
+            ORDER BY u.loc, u.id",
+        )
+        .bind(owner)
+        .fetch_all(&mut *self.conn().await?)
+        .await?;
+        Ok(v)
+    }
+
+    /// Return the hull class with the given name.
+    pub async fn get_hull(&self, name: &str) -> DataResult<Hull> {
+        let h = sqlx::query_as(format!("{} WHERE name = ?", HULLS).as_str())
+            .bind(name)
+            .fetch_one(&mut *self.conn().await?)
+            .await?;
+        Ok(h)
+    }
+
+    /// Return the hull classes.
+    pub async fn get_hulls(&self) -> DataResult<Vec<Hull>> {
+        let v = sqlx::query_as(format!("{} ORDER BY size", HULLS).as_str())
+            .fetch_all(&mut *self.conn().await?)
+            .await?;
+        Ok(v)
+    }
+
+    /// Return the ship classes an empire has identified, earliest first.
+    pub async fn get_identifications(&self, empire: i64) -> DataResult<Vec<Identification>> {
+        let v = sqlx::query_as(
+            "SELECT i.*, t.class AS class, COALESCE(e.name, 'None') AS owner_name
+            FROM identifications i
+            JOIN ship_types t ON i.stype = t.id
+            LEFT JOIN empires e ON t.empire = e.id
+            WHERE i.empire = ?
+            ORDER BY i.turn, i.id",
+        )
+        .bind(empire)
+        .fetch_all(&mut *self.conn().await?)
+        .await?;
+        Ok(v)
+    }
+
+    /// Return the intel items for an empire's packet on a turn.
+    pub async fn get_intel(&self, empire: i64, turn: i32) -> DataResult<Vec<IntelItem>> {
+        let v = sqlx::query_as("SELECT * FROM intel WHERE empire = ? AND turn = ? ORDER BY id")
+            .bind(empire)
+            .bind(turn)
+            .fetch_all(&mut *self.conn().await?)
+            .await?;
+        Ok(v)
+    }
+
+    /// Return the journal entries, in turn order.
+    pub async fn get_journal(&self) -> DataResult<Vec<JournalEntry>> {
+        let v = sqlx::query_as::<_, JournalEntry>("SELECT * FROM journal ORDER BY turn")
+            .fetch_all(&mut *self.conn().await?)
+            .await?;
+        Ok(v)
+    }
+
+    /// Return the journal entry for a turn, if one has been written.
+    pub async fn get_journal_entry(&self, turn: i32) -> DataResult<Option<JournalEntry>> {
+        let e = sqlx::query_as::<_, JournalEntry>("SELECT * FROM journal WHERE turn = ?")
+            .bind(turn)
+            .fetch_optional(&mut *self.conn().await?)
+            .await?;
+        Ok(e)
+    }
+
+    /// Return the jump lanes with the names of the systems they connect.
+    pub async fn get_lanes(&self) -> DataResult<Vec<Lane>> {
+        let v = sqlx::query_as(
+            "SELECT l.*, COALESCE(sa.name, '') AS a_name, COALESCE(sb.name, '') AS b_name
+            FROM lanes l
+            LEFT JOIN systems sa ON l.a = sa.id
+            LEFT JOIN systems sb ON l.b = sb.id
+            ORDER BY a_name, b_name",
+        )
+        .fetch_all(&mut *self.conn().await?)
+        .await?;
+        Ok(v)
+    }
+
+    /// Return an empire's ledger of manual treasury and tech changes.
+    pub async fn get_ledger(&self, empire: i64) -> DataResult<Vec<LedgerEntry>> {
+        let v = sqlx::query_as("SELECT * FROM ledger WHERE empire = ? ORDER BY turn, id")
+            .bind(empire)
+            .fetch_all(&mut *self.conn().await?)
+            .await?;
+        Ok(v)
+    }
+
+    /// Return the audit log, oldest entry first.
+    pub async fn get_log(&self) -> DataResult<Vec<LogEntry>> {
+        let v = sqlx::query_as("SELECT * FROM log ORDER BY id")
+            .fetch_all(&mut *self.conn().await?)
+            .await?;
+        Ok(v)
+    }
+
+    /// Return the maintenance due from an empire's ships, grouped by hull and
+    /// status, and from its facilities, by kind. Ships pay by the rules of
+    /// the campaign's edition; those whose hull is not in the hulls table
+    /// cost nothing.
+    #[allow(unused)]
+    pub async fn get_maintenance(&self, empire: i64) -> DataResult<Maintenance> {
+        let rows = sqlx::query(
+            "SELECT t.hull, COALESCE(h.maint, 0) AS maint, s.crip, s.moth,
+            COUNT(*) AS count
+            FROM ships s
+            JOIN ship_types t ON s.stype = t.id
+            JOIN fleets f ON s.fleet = f.id
+            LEFT JOIN hulls h ON h.name = t.hull
+            WHERE f.owner = ?
+            GROUP BY t.hull, s.crip, s.moth
+            ORDER BY COALESCE(h.size, 0), t.hull, s.crip, s.moth",
+        )
+        .bind(empire)
+        .fetch_all(&mut *self.conn().await?)
+        .await?;
+
+        let rules = self.get_campaign_options().await?.ruleset();
+        let mut m = Maintenance::default();
+        for r in rows {
+            let status = ShipStatus::from_flags(r.get("crip"), r.get("moth"));
+            m.lines.push(MaintenanceLine {
+                hull: r.get("hull"),
+                status,
+                count: r.get("count"),
+                each: rules.maintenance(status, r.get("maint")),
+            });
+        }
+
+        let rows: Vec<(String, bool, i32)> = sqlx::query_as(
+            "SELECT f.kind, f.crip, COUNT(*)
+            FROM facilities f
+            JOIN systems s ON f.system = s.id
+            WHERE s.owner = ?
+            GROUP BY f.kind, f.crip
+            ORDER BY f.kind, f.crip",
+        )
+        .bind(empire)
+        .fetch_all(&mut *self.conn().await?)
+        .await?;
+        for (kind, crip, count) in rows {
+            // Crippled facilities are charged at the crippled ship rate.
+            let status = ShipStatus::from_flags(crip, false);
+            let base = facility::spec(kind.as_str()).map_or(0, |s| s.maint);
+            m.lines.push(MaintenanceLine {
+                hull: kind,
+                status,
+                count,
+                each: rules.maintenance(status, base),
+            });
+        }
+        Ok(m)
+    }
+
+    /// Return each empire's empire-wide morale modifier. Empires without one
+    /// are left out.
+    pub async fn get_morale_modifiers(&self) -> DataResult<BTreeMap<i64, i32>> {
+        let rows: Vec<(i64, i32)> =
+            sqlx::query_as("SELECT empire, amount FROM morale_modifiers WHERE amount != 0")
+                .fetch_all(&mut *self.conn().await?)
+                .await?;
+        Ok(rows.into_iter().collect())
+    }
+
+    /// Return every empire's movement orders for a turn, in the order given.
+    pub async fn get_movement_orders(&self, turn: i32) -> DataResult<Vec<Order>> {
+        let v = sqlx::query_as("SELECT * FROM orders WHERE turn = ? AND phase = ? ORDER BY id")
+            .bind(turn)
+            .bind(orders::MOVEMENT)
+            .fetch_all(&mut *self.conn().await?)
+            .await?;
+        Ok(v)
+    }
+
+    /// Return the espionage operations ordered for a turn.
+    pub async fn get_operations(&self, turn: i32) -> DataResult<Vec<Operation>> {
+        let v = sqlx::query_as(
+            "SELECT o.*, e.name AS empire_name, t.name AS target_name
+            FROM espionage o
+            JOIN empires e ON o.empire = e.id
+            JOIN empires t ON o.target = t.id
+            WHERE o.turn = ?
+            ORDER BY o.id",
+        )
+        .bind(turn)
+        .fetch_all(&mut *self.conn().await?)
+        .await?;
+        Ok(v)
+    }
+
+    /// Return an empire's order templates.
+    pub async fn get_order_templates(&self, empire: i64) -> DataResult<Vec<OrderTemplate>> {
+        let v = sqlx::query_as("SELECT * FROM order_templates WHERE empire = ? ORDER BY name")
+            .bind(empire)
+            .fetch_all(&mut *self.conn().await?)
+            .await?;
+        Ok(v)
+    }
+
+    /// Return an empire's orders for a turn.
+    pub async fn get_orders(&self, empire: i64, turn: i32) -> DataResult<Vec<Order>> {
+        let v = sqlx::query_as("SELECT * FROM orders WHERE empire = ? AND turn = ? ORDER BY id")
+            .bind(empire)
+            .bind(turn)
+            .fetch_all(&mut *self.conn().await?)
+            .await?;
+        Ok(v)
+    }
+
+    /// Return the units no empire holds: ground units at systems without an
+    /// owner, and fleets without an owner or a location.
+    pub async fn get_orphans(&self) -> DataResult<Vec<Orphan>> {
+        let v = sqlx::query_as(
+            "SELECT ? AS kind, g.id AS id, t.name AS name, s.id AS system,
+            s.name AS system_name
+            FROM ground_units g
+            JOIN ground_types t ON g.gtype = t.id
+            JOIN systems s ON g.loc = s.id
+            WHERE COALESCE(s.owner, 0) = 0
+            UNION ALL
+            SELECT ?, f.id, f.name, COALESCE(f.location, 0), COALESCE(s.name, '')
+            FROM fleets f
+            LEFT JOIN systems s ON f.location = s.id
+            WHERE f.owner IS NULL OR f.location IS NULL
+            ORDER BY kind DESC, id",
+        )
+        .bind(checklist::GROUND)
+        .bind(checklist::FLEET)
+        .fetch_all(&mut *self.conn().await?)
+        .await?;
+        Ok(v)
+    }
+
+    /// Return the planet types reference table.
+    pub async fn get_planet_types(&self) -> DataResult<Vec<PlanetType>> {
+        let v = sqlx::query_as("SELECT * FROM planet_types ORDER BY max_cap DESC, name")
+            .fetch_all(&mut *self.conn().await?)
+            .await?;
+        Ok(v)
+    }
+
+    /// Return the raids ordered for a turn, in the order they were given.
+    pub async fn get_raids(&self, turn: i32) -> DataResult<Vec<Raid>> {
+        let v = sqlx::query_as(
+            "SELECT r.*, e.name AS empire_name, s.name AS system_name
+            FROM raids r
+            JOIN empires e ON r.empire = e.id
+            JOIN systems s ON r.system = s.id
+            WHERE r.turn = ?
+            ORDER BY r.id",
+        )
+        .bind(turn)
+        .fetch_all(&mut *self.conn().await?)
+        .await?;
+        Ok(v)
+    }
+
+    /// Return the crippled ships whose repairs are due and can be made where
+    /// they are: in fleets at their empire's unblockaded systems, and not
+    /// mothballed.
+    pub async fn get_repair_jobs(&self) -> DataResult<Vec<RepairJob>> {
+        let v = sqlx::query_as(
+            "SELECT s.id AS ship, f.owner AS empire, f.location AS system,
+            t.class AS class, t.cost AS cost, s.repair_priority AS priority,
+            e.name AS empire_name, y.name AS system_name
+            FROM ships s
+            JOIN construction c ON c.kind = ? AND c.item = s.id AND c.turns <= 1
+            JOIN fleets f ON s.fleet = f.id
+            JOIN ship_types t ON s.stype = t.id
+            JOIN systems y ON f.location = y.id
+            JOIN empires e ON f.owner = e.id
+            WHERE s.crip <> 0 AND s.moth = 0 AND y.owner = f.owner AND y.blockaded = 0
+            ORDER BY y.name, s.repair_priority DESC, s.id",
+        )
+        .bind(construction::REPAIR)
+        .fetch_all(&mut *self.conn().await?)
+        .await?;
+        Ok(v)
+    }
+
+    /// Return whether an optional rule is in use. Rules never set are off.
+    pub async fn get_rule_option(&self, key: &str) -> DataResult<bool> {
+        let v: Option<String> = sqlx::query_scalar("SELECT value FROM control WHERE key = ?")
+            .bind(key)
+            .fetch_optional(&mut *self.conn().await?)
+            .await?;
+        Ok(v.as_deref() == Some("1"))
+    }
+
+    /// Return a numeric rule setting, if it has been set.
+    pub async fn get_rule_value(&self, key: &str) -> DataResult<Option<i32>> {
+        let v: Option<String> = sqlx::query_scalar("SELECT value FROM control WHERE key = ?")
+            .bind(key)
+            .fetch_optional(&mut *self.conn().await?)
+            .await?;
+        match v {
+            Some(v) => Ok(Some(v.parse()?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Return the scorched earth orders standing at a system for a turn.
+    pub async fn get_scorch_orders(&self, system: i64, turn: i32) -> DataResult<Vec<ScorchOrder>> {
+        let v = sqlx::query_as(
+            "SELECT o.*, e.name AS empire_name, s.name AS system_name
+            FROM scorch_orders o
+            JOIN empires e ON o.empire = e.id
+            JOIN systems s ON o.system = s.id
+            WHERE o.system = ? AND o.turn = ?
+            ORDER BY o.id",
+        )
+        .bind(system)
+        .bind(turn)
+        .fetch_all(&mut *self.conn().await?)
+        .await?;
+        Ok(v)
+    }
+
+    /// Return the ship classes of all empires.
+    pub async fn get_ship_types(&self) -> DataResult<Vec<ShipType>> {
+        let v = sqlx::query_as(
+            "SELECT t.*, COALESCE(e.name, 'None') AS empire_name,
+            (SELECT COUNT(*) FROM ships s WHERE s.stype = t.id AND s.moth = 0) AS active
+            FROM ship_types t
+            LEFT JOIN empires e ON t.empire = e.id
+            ORDER BY empire_name, t.class",
+        )
+        .fetch_all(&mut *self.conn().await?)
+        .await?;
+        Ok(v)
+    }
+
+    /// Return every ship of a class with its fleet and location.
+    pub async fn get_ships_by_class(&self, stype: i64) -> DataResult<Vec<ShipLocation>> {
+        let v = sqlx::query_as(
+            "SELECT s.id AS id, t.class AS class,
+            COALESCE(f.name, 'None') AS fleet_name,
+            COALESCE(y.name, 'None') AS location_name,
+            s.crip AS crip, s.moth AS moth
+            FROM ships s
+            JOIN ship_types t ON s.stype = t.id
+            LEFT JOIN fleets f ON s.fleet = f.id
+            LEFT JOIN systems y ON f.location = y.id
+            WHERE s.stype = ?
+            ORDER BY location_name, fleet_name, s.id",
+        )
+        .bind(stype)
+        .fetch_all(&mut *self.conn().await?)
+        .await?;
+        Ok(v)
+    }
+
+    /// Return where every ship of an empire is, by fleet.
+    pub async fn get_ships_by_owner(&self, owner: i64) -> DataResult<Vec<ShipLocation>> {
+        let v = sqlx::query_as(
+            "SELECT s.id AS id, t.class AS class, f.name AS fleet_name,
+            COALESCE(y.name, 'None') AS location_name,
+            s.crip AS crip, s.moth AS moth, f.owner AS owner, f.location AS location,
+            t.cost AS cost, t.cap AS cap
+            FROM ships s
+            JOIN ship_types t ON s.stype = t.id
+            JOIN fleets f ON s.fleet = f.id
+            LEFT JOIN systems y ON f.location = y.id
+            WHERE f.owner = ?
+            ORDER BY fleet_name, t.class, s.id",
+        )
+        .bind(owner)
+        .fetch_all(&mut *self.conn().await?)
+        .await?;
+        Ok(v)
+    }
+
+    /// Return where an empire stands for a turn: its treasury and tech level,
+    /// the systems it holds, what it has under construction and its research
+    /// spending.
+    pub async fn get_standing(&self, empire: i64, turn: i32) -> DataResult<Standing> {
+        let v = sqlx::query_as(
+            "SELECT e.id AS empire, e.name AS name, e.treasury AS treasury, e.tech AS tech,
+            (SELECT COUNT(*) FROM systems s WHERE s.owner = e.id) AS systems,
+            (SELECT COUNT(*) FROM construction c WHERE c.empire = e.id) AS builds,
+            (SELECT COALESCE(SUM(c.cost), 0) FROM construction c WHERE c.empire = e.id)
+                AS building,
+            COALESCE(r.amount, 0) AS research
+            FROM empires e
+            LEFT JOIN research r ON r.empire = e.id AND r.turn = ?
+            WHERE e.id = ?",
+        )
+        .bind(turn)
+        .bind(empire)
+        .fetch_one(&mut *self.conn().await?)
+        .await?;
+        Ok(v)
+    }
+
+    /// Return a system by ID.
+    #[allow(unused)]
+    pub async fn get_system_by_id(&self, id: i64) -> DataResult<System> {
+        let sys = sqlx::query_as(
+            "SELECT s.*, COALESCE(e.name, 'None') AS owner_name,
+            COALESCE(c.name, '') AS contender_name
+            FROM systems s
+            LEFT JOIN empires e ON s.owner = e.id
+            LEFT JOIN empires c ON s.contender = c.id
+            WHERE s.id = ?",
+        )
+        .bind(id)
+        .fetch_one(&mut *self.conn().await?)
         .await?;
+        Ok(sys)
+    }
+
+    /// Return a system by name.
+    #[allow(unused)]
+    pub async fn get_system_by_name(&self, name: &str) -> DataResult<System> {
+        let sys = sqlx::query_as(
+            "SELECT s.*, COALESCE(e.name, 'None') AS owner_name,
+            COALESCE(c.name, '') AS contender_name
+            FROM systems s
+            LEFT JOIN empires e ON s.owner = e.id
+            LEFT JOIN empires c ON s.contender = c.id
+            WHERE s.name = ?",
+        )
+        .bind(name)
+        .fetch_one(&mut *self.conn().await?)
+        .await?;
+        Ok(sys)
+    }
+
+    /// Return the systems from the store.
+    pub async fn get_systems(&self) -> DataResult<Vec<System>> {
+        let v = sqlx::query_as(
+            "SELECT s.*, COALESCE(e.name, 'None') AS owner_name,
+            COALESCE(c.name, '') AS contender_name
+            FROM systems s
+            LEFT JOIN empires e ON s.owner = e.id
+            LEFT JOIN empires c ON s.contender = c.id",
+        )
+        .fetch_all(&mut *self.conn().await?)
+        .await?;
+        Ok(v)
+    }
+
+    /// Return the systems owned by an empire, or the unowned systems if the
+    /// owner is zero.
+    pub async fn get_systems_by_owner(&self, owner: i64) -> DataResult<Vec<System>> {
+        let v = if owner == 0 {
+            sqlx::query_as(
+                "SELECT *, 'None' AS owner_name FROM systems WHERE owner IS NULL OR owner = 0",
+            )
+            .fetch_all(&mut *self.conn().await?)
+            .await?
+        } else {
+            sqlx::query_as(
+                "SELECT s.*, COALESCE(e.name, 'None') AS owner_name,
+                COALESCE(c.name, '') AS contender_name
+                FROM systems s
+                LEFT JOIN empires e ON s.owner = e.id
+                LEFT JOIN empires c ON s.contender = c.id
+                WHERE s.owner = ?",
+            )
+            .bind(owner)
+            .fetch_all(&mut *self.conn().await?)
+            .await?
+        };
+        Ok(v)
+    }
+
+    /// Return the systems an empire contests with their owners.
+    pub async fn get_systems_contested_by(&self, empire: i64) -> DataResult<Vec<System>> {
+        let v = sqlx::query_as(
+            "SELECT s.*, COALESCE(e.name, 'None') AS owner_name,
+            COALESCE(c.name, '') AS contender_name
+            FROM systems s
+            LEFT JOIN empires e ON s.owner = e.id
+            LEFT JOIN empires c ON s.contender = c.id
+            WHERE s.contender = ? AND s.owner <> s.contender",
+        )
+        .bind(empire)
+        .fetch_all(&mut *self.conn().await?)
+        .await?;
+        Ok(v)
+    }
+
+    /// Return every empire's research standing for a turn.
+    pub async fn get_tech_status(&self, turn: i32) -> DataResult<Vec<TechStatus>> {
+        let v = sqlx::query_as(
+            "SELECT e.id AS empire, e.name AS empire_name, e.tech AS tech,
+            COALESCE(r.amount, 0) AS spending, COALESCE(p.points, 0) AS points
+            FROM empires e
+            LEFT JOIN research r ON r.empire = e.id AND r.turn = ?
+            LEFT JOIN tech_progress p ON p.empire = e.id
+            ORDER BY e.name",
+        )
+        .bind(turn)
+        .fetch_all(&mut *self.conn().await?)
+        .await?;
+        Ok(v)
+    }
+
+    /// Return the transfers agreed for a turn, in the order they were agreed,
+    /// with what is transferred and who holds it now.
+    pub async fn get_transfers(&self, turn: i32) -> DataResult<Vec<Transfer>> {
+        let v = sqlx::query_as(
+            "SELECT t.*, se.name AS seller_name, be.name AS buyer_name,
+            COALESCE(st.class || ' #' || sh.id, ct.class || ' class') AS item_name,
+            COALESCE(f.owner, ct.empire, 0) AS owner
+            FROM transfers t
+            JOIN empires se ON t.seller = se.id
+            JOIN empires be ON t.buyer = be.id
+            LEFT JOIN ships sh ON t.ship = sh.id
+            LEFT JOIN ship_types st ON sh.stype = st.id
+            LEFT JOIN fleets f ON sh.fleet = f.id
+            LEFT JOIN ship_types ct ON t.stype = ct.id
+            WHERE t.turn = ?
+            ORDER BY t.id",
+        )
+        .bind(turn)
+        .fetch_all(&mut *self.conn().await?)
+        .await?;
+        Ok(v)
+    }
+
+    /// Return the carrying capacity of an empire's active ships at a system.
+    pub async fn get_transport_capacity(&self, owner: i64, loc: i64) -> DataResult<i32> {
+        let cap: i32 = sqlx::query_scalar(
+            "SELECT COALESCE(SUM(t.cap), 0)
+            FROM ships s
+            JOIN ship_types t ON s.stype = t.id
+            JOIN fleets f ON s.fleet = f.id
+            WHERE f.owner = ? AND f.location = ? AND s.crip = 0 AND s.moth = 0",
+        )
+        .bind(owner)
+        .bind(loc)
+        .fetch_one(&mut *self.conn().await?)
+        .await?;
+        Ok(cap)
+    }
+
+    /// Return the treaties between empires.
+    pub async fn get_treaties(&self) -> DataResult<Vec<Treaty>> {
+        let v = sqlx::query_as(
+            "SELECT t.*, COALESCE(a.name, 'None') AS a_name, COALESCE(b.name, 'None') AS b_name
+            FROM treaties t
+            LEFT JOIN empires a ON t.a = a.id
+            LEFT JOIN empires b ON t.b = b.id
+            ORDER BY t.signed, t.id",
+        )
+        .fetch_all(&mut *self.conn().await?)
+        .await?;
+        Ok(v)
+    }
+
+    /// Return every war, in the order they broke out, with the names of the
+    /// empires fighting it.
+    pub async fn get_wars(&self) -> DataResult<Vec<War>> {
+        let v = sqlx::query_as(
+            "SELECT w.*, COALESCE(a.name, 'None') AS a_name, COALESCE(b.name, 'None') AS b_name
+            FROM wars w
+            LEFT JOIN empires a ON w.a = a.id
+            LEFT JOIN empires b ON w.b = b.id
+            ORDER BY w.started, w.id",
+        )
+        .fetch_all(&mut *self.conn().await?)
+        .await?;
+        Ok(v)
+    }
+
+    /// Replace the contents of the store's tables with those in an exported
+    /// JSON value. Tables missing from the export keep their current rows.
+    pub async fn import(&self, archive: &Value) -> DataResult<()> {
+        let tables = match archive.get("tables").and_then(|t| t.as_object()) {
+            Some(t) => t,
+            None => return Err(DataError::Archive("No tables in archive".to_string())),
+        };
+        let known: Vec<String> = sqlx::query_scalar(
+            "SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%'",
+        )
+        .fetch_all(&mut *self.conn().await?)
+        .await?;
+
+        // Tables are replaced one at a time, so foreign keys are turned off
+        // while they are, lest deleting the old rows cascade to tables
+        // already replaced. References are checked once all are in place.
+        let mut conn = self.pool.acquire().await?;
+        sqlx::query("PRAGMA foreign_keys = OFF")
+            .execute(&mut conn)
+            .await?;
+        let result = Self::replace_tables(&mut conn, tables, &known).await;
+        sqlx::query("PRAGMA foreign_keys = ON")
+            .execute(&mut conn)
+            .await?;
+        result
+    }
+
+    /// Add new systems and replace existing ones, as a system import does.
+    /// Each replaced system takes every value given, including its owner.
+    /// They are all written, or none are.
+    pub async fn import_systems(&self, added: &[System], replaced: &[System]) -> DataResult<()> {
+        let mut conn = self.conn().await?;
+        let mut tx = conn.begin().await?;
+        Self::insert_systems(&mut tx, added).await?;
+        for sys in replaced {
+            sqlx::query(
+                "UPDATE systems SET
+                (name, ptype, raw, cap, pop, mor, ind, dev, fails, blockaded, x, y, minor,
+                contender, share, policy, owner) =
+                (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                WHERE id = ?",
+            )
+            .bind(sys.name.as_str())
+            .bind(sys.ptype.as_str())
+            .bind(sys.raw)
+            .bind(sys.cap)
+            .bind(sys.pop)
+            .bind(sys.mor)
+            .bind(sys.ind)
+            .bind(sys.dev)
+            .bind(sys.fails)
+            .bind(sys.blockaded)
+            .bind(sys.x)
+            .bind(sys.y)
+            .bind(sys.minor.as_str())
+            .bind(sys.contender)
+            .bind(sys.share)
+            .bind(sys.policy.as_str())
+            .bind(if sys.owner == 0 {
+                None
+            } else {
+                Some(sys.owner)
+            })
+            .bind(sys.id)
+            .execute(&mut tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Note a battle fought at a system in a turn.
+    pub async fn insert_battle(&self, turn: i32, system: i64) -> DataResult<()> {
+        sqlx::query("INSERT OR IGNORE INTO battles (turn, system) VALUES(?, ?)")
+            .bind(turn)
+            .bind(system)
+            .execute(&mut *self.conn().await?)
+            .await?;
+        Ok(())
+    }
+
+    /// Add a build to the construction queue, paying its cost from the
+    /// empire's treasury. Returns the build's ID.
+    pub async fn insert_build(&self, build: &Build) -> DataResult<i64> {
+        let mut conn = self.conn().await?;
+        let mut tx = conn.begin().await?;
+        let r = sqlx::query(
+            "INSERT INTO construction (empire, system, kind, item, name, cost, turns, count)
+            VALUES(?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(build.empire)
+        .bind(build.system)
+        .bind(build.kind.as_str())
+        .bind(build.item)
+        .bind(build.name.as_str())
+        .bind(build.cost)
+        .bind(build.turns)
+        .bind(build.count)
+        .execute(&mut tx)
+        .await?;
+        sqlx::query("UPDATE empires SET treasury = treasury - ? WHERE id = ?")
+            .bind(build.cost)
+            .bind(build.empire)
+            .execute(&mut tx)
+            .await?;
+        tx.commit().await?;
+        Ok(r.last_insert_rowid())
+    }
+
+    /// Note units lost or crippled, each against the war between its empire
+    /// and its enemy, which is started if they were not already at war.
+    pub async fn insert_casualties(&self, casualties: &[Casualty]) -> DataResult<()> {
+        let mut conn = self.conn().await?;
+        let mut tx = conn.begin().await?;
+        for c in casualties {
+            let war = Self::war_between(&mut tx, c.empire, c.enemy, c.turn).await?;
+            sqlx::query(
+                "INSERT INTO casualties (war, turn, empire, enemy, kind, destroyed, cost)
+                VALUES(?, ?, ?, ?, ?, ?, ?)",
+            )
+            .bind(war)
+            .bind(c.turn)
+            .bind(c.empire)
+            .bind(c.enemy)
+            .bind(c.kind.as_str())
+            .bind(c.destroyed)
+            .bind(c.cost)
+            .execute(&mut tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Add a combat modifier, returning its ID.
+    pub async fn insert_combat_modifier(&self, m: &CombatModifier) -> DataResult<i64> {
+        let r = sqlx::query(
+            "INSERT INTO combat_modifiers (category, name, atk, def) VALUES(?, ?, ?, ?)",
+        )
+        .bind(m.category.as_str())
+        .bind(m.name.as_str())
+        .bind(m.atk)
+        .bind(m.def)
+        .execute(&mut *self.conn().await?)
+        .await?;
+        Ok(r.last_insert_rowid())
+    }
+
+    /// Add a development order, paying for it from the empire's treasury,
+    /// and return its ID.
+    pub async fn insert_development(&self, order: &Development) -> DataResult<i64> {
+        let mut conn = self.conn().await?;
+        let mut tx = conn.begin().await?;
+        let r = sqlx::query(
+            "INSERT INTO developments (empire, turn, system, target, amount)
+            VALUES(?, ?, ?, ?, ?)",
+        )
+        .bind(order.empire)
+        .bind(order.turn)
+        .bind(order.system)
+        .bind(order.target.as_str())
+        .bind(order.amount)
+        .execute(&mut tx)
+        .await?;
+        sqlx::query("UPDATE empires SET treasury = treasury - ? WHERE id = ?")
+            .bind(order.amount)
+            .bind(order.empire)
+            .execute(&mut tx)
+            .await?;
+        tx.commit().await?;
+        Ok(r.last_insert_rowid())
+    }
+
+    /// Add a new empire, returning its ID.
+    pub async fn insert_empire(&self, emp: &Empire) -> DataResult<i64> {
+        self.apply(Change::AddEmpire(emp.clone())).await
+    }
+
+    /// Set a fleet escorting another, returning the escort's ID.
+    pub async fn insert_escort(&self, escort: &Escort) -> DataResult<i64> {
+        let r = sqlx::query("INSERT INTO escorts (escort, convoy) VALUES(?, ?)")
+            .bind(escort.escort)
+            .bind(escort.convoy)
+            .execute(&mut *self.conn().await?)
+            .await?;
+        Ok(r.last_insert_rowid())
+    }
+
+    /// Add a facility, returning its ID.
+    pub async fn insert_facility(&self, facility: &Facility) -> DataResult<i64> {
+        let r = sqlx::query("INSERT INTO facilities (system, kind, crip) VALUES(?, ?, ?)")
+            .bind(facility.system)
+            .bind(facility.kind.as_str())
+            .bind(facility.crip)
+            .execute(&mut *self.conn().await?)
+            .await?;
+        Ok(r.last_insert_rowid())
+    }
+
+    /// Add a new fleet, returning its ID.
+    pub async fn insert_fleet(&self, fleet: &Fleet) -> DataResult<i64> {
+        self.apply(Change::AddFleet(fleet.clone())).await
+    }
+
+    /// Add a new flight, returning its ID.
+    pub async fn insert_flight(&self, flight: &Flight) -> DataResult<i64> {
+        let r = sqlx::query(
+            "INSERT INTO flights (owner, carrier, base, atk, def, crip) VALUES(?, ?, ?, ?, ?, ?)",
+        )
+        .bind(flight.owner)
+        .bind(flight.carrier)
+        .bind(flight.base)
+        .bind(flight.atk)
+        .bind(flight.def)
+        .bind(flight.crip)
+        .execute(&mut *self.conn().await?)
+        .await?;
+        Ok(r.last_insert_rowid())
+    }
+
+    /// Add a ground unit to a system's garrison, returning its ID.
+    pub async fn insert_ground_unit(&self, gtype: i64, loc: i64) -> DataResult<i64> {
+        let r = sqlx::query("INSERT INTO ground_units (gtype, loc) VALUES(?, ?)")
+            .bind(gtype)
+            .bind(loc)
+            .execute(&mut *self.conn().await?)
+            .await?;
+        Ok(r.last_insert_rowid())
+    }
+
+    /// Record ship classes identified by empires. A class an empire has
+    /// already identified keeps its first identification.
+    pub async fn insert_identifications(&self, items: &[Identification]) -> DataResult<()> {
+        let mut conn = self.conn().await?;
+        let mut tx = conn.begin().await?;
+        for i in items {
+            sqlx::query(
+                "INSERT INTO identifications (empire, stype, turn, source) VALUES(?, ?, ?, ?)
+                ON CONFLICT (empire, stype) DO NOTHING",
+            )
+            .bind(i.empire)
+            .bind(i.stype)
+            .bind(i.turn)
+            .bind(i.source.as_str())
+            .execute(&mut tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Add an intel item, returning its ID.
+    pub async fn insert_intel(&self, item: &IntelItem) -> DataResult<i64> {
+        let r = sqlx::query("INSERT INTO intel (empire, turn, kind, text) VALUES(?, ?, ?, ?)")
+            .bind(item.empire)
+            .bind(item.turn)
+            .bind(item.kind.as_str())
+            .bind(item.text.as_str())
+            .execute(&mut *self.conn().await?)
+            .await?;
+        Ok(r.last_insert_rowid())
+    }
+
+    /// Add a jump lane, returning its ID.
+    pub async fn insert_lane(&self, lane: &Lane) -> DataResult<i64> {
+        self.apply(Change::AddLane(lane.clone())).await
+    }
+
+    /// Add entries to the audit log, all stamped with the current local
+    /// time.
+    pub async fn insert_log(&self, entries: &[LogEntry]) -> DataResult<()> {
+        let mut conn = self.conn().await?;
+        let mut tx = conn.begin().await?;
+        for e in entries {
+            sqlx::query(
+                "INSERT INTO log (turn, stamp, category, text)
+                VALUES(?, datetime('now', 'localtime'), ?, ?)",
+            )
+            .bind(e.turn)
+            .bind(e.category.as_str())
+            .bind(e.text.as_str())
+            .execute(&mut tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Add an espionage operation, paying one EP per intel point from the
+    /// empire's treasury, and return its ID.
+    pub async fn insert_operation(&self, op: &Operation) -> DataResult<i64> {
+        let mut conn = self.conn().await?;
+        let mut tx = conn.begin().await?;
+        let r = sqlx::query(
+            "INSERT INTO espionage (empire, turn, target, mission, points)
+            VALUES(?, ?, ?, ?, ?)",
+        )
+        .bind(op.empire)
+        .bind(op.turn)
+        .bind(op.target)
+        .bind(op.mission.as_str())
+        .bind(op.points)
+        .execute(&mut tx)
+        .await?;
+        sqlx::query("UPDATE empires SET treasury = treasury - ? WHERE id = ?")
+            .bind(op.points)
+            .bind(op.empire)
+            .execute(&mut tx)
+            .await?;
+        tx.commit().await?;
+        Ok(r.last_insert_rowid())
+    }
+
+    /// Add an order template, returning its ID.
+    pub async fn insert_order_template(&self, tmpl: &OrderTemplate) -> DataResult<i64> {
+        let r = sqlx::query("INSERT INTO order_templates (empire, name, text) VALUES(?, ?, ?)")
+            .bind(tmpl.empire)
+            .bind(tmpl.name.as_str())
+            .bind(tmpl.text.as_str())
+            .execute(&mut *self.conn().await?)
+            .await?;
+        Ok(r.last_insert_rowid())
+    }
+
+    /// Add a list of orders, returning them with their IDs filled in.
+    pub async fn insert_orders(&self, orders: &[Order]) -> DataResult<Vec<Order>> {
+        let mut conn = self.conn().await?;
+        let mut tx = conn.begin().await?;
+        let mut v = Vec::new();
+        for o in orders {
+            let r = sqlx::query(
+                "INSERT INTO orders (empire, turn, text, phase, cost) VALUES(?, ?, ?, ?, ?)",
+            )
+            .bind(o.empire)
+            .bind(o.turn)
+            .bind(o.text.as_str())
+            .bind(o.phase.as_str())
+            .bind(o.cost)
+            .execute(&mut tx)
+            .await?;
+            v.push(Order {
+                id: r.last_insert_rowid(),
+                ..o.clone()
+            });
+        }
+        tx.commit().await?;
+        Ok(v)
+    }
+
+    /// Add a raid order, returning its ID.
+    pub async fn insert_raid(&self, raid: &Raid) -> DataResult<i64> {
+        let r = sqlx::query("INSERT INTO raids (empire, turn, system, target) VALUES(?, ?, ?, ?)")
+            .bind(raid.empire)
+            .bind(raid.turn)
+            .bind(raid.system)
+            .bind(raid.target.as_str())
+            .execute(&mut *self.conn().await?)
+            .await?;
+        Ok(r.last_insert_rowid())
+    }
+
+    /// Add a scorched earth order, returning its ID.
+    pub async fn insert_scorch_order(&self, order: &ScorchOrder) -> DataResult<i64> {
+        let r = sqlx::query(
+            "INSERT INTO scorch_orders (empire, turn, system, target) VALUES(?, ?, ?, ?)",
+        )
+        .bind(order.empire)
+        .bind(order.turn)
+        .bind(order.system)
+        .bind(order.target.as_str())
+        .execute(&mut *self.conn().await?)
+        .await?;
+        Ok(r.last_insert_rowid())
+    }
+
+    /// Note what ship classes did: ships delivered, or what they brought to
+    /// a battle, lost and destroyed.
+    pub async fn insert_service(&self, service: &[Service]) -> DataResult<()> {
+        let mut conn = self.conn().await?;
+        let mut tx = conn.begin().await?;
+        for s in service {
+            sqlx::query(
+                "INSERT INTO service (turn, stype, built, fought, lost, kills)
+                VALUES(?, ?, ?, ?, ?, ?)",
+            )
+            .bind(s.turn)
+            .bind(s.stype)
+            .bind(s.built)
+            .bind(s.fought)
+            .bind(s.lost)
+            .bind(s.kills)
+            .execute(&mut tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Add a ship class, returning its ID.
+    pub async fn insert_ship_type(&self, stype: &ShipType) -> DataResult<i64> {
+        let r = sqlx::query(
+            "INSERT INTO ship_types
+            (class, hull, cost, cr, atk, def, cap, speed, empire, obsolete, retired)
+            VALUES(?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(stype.class.as_str())
+        .bind(stype.hull.as_str())
+        .bind(stype.cost)
+        .bind(stype.cr)
+        .bind(stype.atk)
+        .bind(stype.def)
+        .bind(stype.cap)
+        .bind(stype.speed)
+        .bind(stype.empire)
+        .bind(stype.obsolete)
+        .bind(stype.retired)
+        .execute(&mut *self.conn().await?)
+        .await?;
+        Ok(r.last_insert_rowid())
+    }
+
+    /// Add a transfer, returning its ID.
+    pub async fn insert_transfer(&self, transfer: &Transfer) -> DataResult<i64> {
+        let r = sqlx::query(
+            "INSERT INTO transfers (turn, seller, buyer, ship, stype, price)
+            VALUES(?, ?, ?, ?, ?, ?)",
+        )
+        .bind(transfer.turn)
+        .bind(transfer.seller)
+        .bind(transfer.buyer)
+        .bind(transfer.ship)
+        .bind(transfer.stype)
+        .bind(transfer.price)
+        .execute(&mut *self.conn().await?)
+        .await?;
+        Ok(r.last_insert_rowid())
+    }
+
+    /// Add a treaty, returning its ID.
+    pub async fn insert_treaty(&self, treaty: &Treaty) -> DataResult<i64> {
+        let r = sqlx::query(
+            "INSERT INTO treaties (a, b, ttype, signed, expires) VALUES(?, ?, ?, ?, ?)",
+        )
+        .bind(treaty.a)
+        .bind(treaty.b)
+        .bind(treaty.ttype.as_str())
+        .bind(treaty.signed)
+        .bind(treaty.expires)
+        .execute(&mut *self.conn().await?)
+        .await?;
+        Ok(r.last_insert_rowid())
+    }
+
+    /// Note pairs of empires that fought in the given turn, starting a war
+    /// between any two not already at war.
+    pub async fn insert_wars(&self, pairs: &[(i64, i64)], turn: i32) -> DataResult<()> {
+        let mut conn = self.conn().await?;
+        let mut tx = conn.begin().await?;
+        for (a, b) in pairs {
+            Self::war_between(&mut tx, *a, *b, turn).await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Return the names of the backups of a campaign kept in `dir`, newest
+    /// first.
+    pub fn list_backups(dir: &path::Path, name: &str) -> DataResult<Vec<String>> {
+        let dir = Self::backups(dir, name);
+        if !dir.is_dir() {
+            return Ok(Vec::new());
+        }
+        let mut names = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let file = entry?.path();
+            if file.extension().is_some_and(|e| e == backup::EXTENSION) {
+                if let Some(stem) = file.file_stem().and_then(|f| f.to_str()) {
+                    names.push(stem.to_string());
+                }
+            }
+        }
+        names.sort();
+        names.reverse();
+        Ok(names)
+    }
+
+    /// Replace the campaign's ground unit types, hulls and planet types with
+    /// those of a setting module, and remember its name. Ground types keep
+    /// their IDs where the module has one of the same name. A list the
+    /// module leaves out is kept as it is. Nothing changes if the campaign
+    /// still has garrisons, ship classes or systems of a kind the module
+    /// drops.
+    pub async fn load_setting(&self, setting: &Setting) -> DataResult<()> {
+        let mut conn = self.conn().await?;
+        let mut tx = conn.begin().await?;
+
+        if !setting.ground_types.is_empty() {
+            let keep: Vec<&str> = setting
+                .ground_types
+                .iter()
+                .map(|g| g.name.as_str())
+                .collect();
+            let used: Vec<String> = sqlx::query_scalar(
+                "SELECT DISTINCT g.name FROM ground_types g JOIN ground_units u ON u.gtype = g.id",
+            )
+            .fetch_all(&mut tx)
+            .await?;
+            if let Some(name) = used.iter().find(|n| !keep.contains(&n.as_str())) {
+                return Err(DataError::Refused(format!(
+                    "{} units are still garrisoned, and the setting has no such type",
+                    name
+                )));
+            }
+            let mut added = Vec::new();
+            for g in &setting.ground_types {
+                let updated = sqlx::query(
+                    "UPDATE ground_types SET abbr = ?, cost = ?, atk = ?, def = ? WHERE name = ?",
+                )
+                .bind(g.abbr.as_str())
+                .bind(g.cost)
+                .bind(g.atk)
+                .bind(g.def)
+                .bind(g.name.as_str())
+                .execute(&mut tx)
+                .await?;
+                if updated.rows_affected() == 0 {
+                    added.push(g.clone());
+                }
+            }
+            let names: Vec<String> = sqlx::query_scalar("SELECT name FROM ground_types")
+                .fetch_all(&mut tx)
+                .await?;
+            for name in names.iter().filter(|n| !keep.contains(&n.as_str())) {
+                sqlx::query("DELETE FROM ground_types WHERE name = ?")
+                    .bind(name)
+                    .execute(&mut tx)
+                    .await?;
+            }
+            Self::insert_ground_types(&mut tx, &added).await?;
+        }
+
+        if !setting.hulls.is_empty() {
+            let used: Vec<String> = sqlx::query_scalar("SELECT DISTINCT hull FROM ship_types")
+                .fetch_all(&mut tx)
+                .await?;
+            if let Some(name) = used
+                .iter()
+                .find(|n| !setting.hulls.iter().any(|h| h.name == **n))
+            {
+                return Err(DataError::Refused(format!(
+                    "Ship classes are built on {} hulls, and the setting has no such hull",
+                    name
+                )));
+            }
+            sqlx::query("DELETE FROM hulls").execute(&mut tx).await?;
+            Self::insert_hulls(&mut tx, &setting.hulls).await?;
+        }
+
+        if !setting.planet_types.is_empty() {
+            let used: Vec<String> = sqlx::query_scalar("SELECT DISTINCT ptype FROM systems")
+                .fetch_all(&mut tx)
+                .await?;
+            if let Some(name) = used
+                .iter()
+                .find(|n| !setting.planet_types.iter().any(|t| t.name == **n))
+            {
+                return Err(DataError::Refused(format!(
+                    "Systems are of type {}, and the setting has no such planet type",
+                    name
+                )));
+            }
+            sqlx::query("DELETE FROM planet_types")
+                .execute(&mut tx)
+                .await?;
+            Self::insert_planet_types(&mut tx, &setting.planet_types).await?;
+        }
+
+        for (key, value) in [
+            (options::SETTING, &setting.name),
+            (options::SETTING_FILE, &setting.file),
+        ] {
+            sqlx::query(
+                "INSERT INTO control (key, value) VALUES(?, ?)
+                ON CONFLICT (key) DO UPDATE SET value = excluded.value",
+            )
+            .bind(key)
+            .bind(value.as_str())
+            .execute(&mut tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Move every ship of one fleet into another and remove the emptied
+    /// fleet.
+    pub async fn merge_fleets(&self, from: i64, into: i64) -> DataResult<()> {
+        let mut conn = self.conn().await?;
+        let mut tx = conn.begin().await?;
+        sqlx::query("DELETE FROM escorts WHERE escort = ? OR convoy = ?")
+            .bind(from)
+            .bind(from)
+            .execute(&mut tx)
+            .await?;
+        sqlx::query("UPDATE ships SET fleet = ? WHERE fleet = ?")
+            .bind(into)
+            .bind(from)
+            .execute(&mut tx)
+            .await?;
+        sqlx::query("DELETE FROM fleets WHERE id = ?")
+            .bind(from)
+            .execute(&mut tx)
+            .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Move fleets to a system together.
+    pub async fn move_fleets(&self, fleets: &[i64], location: i64) -> DataResult<()> {
+        let mut conn = self.conn().await?;
+        let mut tx = conn.begin().await?;
+        for id in fleets {
+            sqlx::query("UPDATE fleets SET location = ? WHERE id = ?")
+                .bind(location)
+                .bind(id)
+                .execute(&mut tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Move ground units to another system.
+    pub async fn move_ground_units(&self, units: &[i64], loc: i64) -> DataResult<()> {
+        let mut conn = self.conn().await?;
+        let mut tx = conn.begin().await?;
+        for id in units {
+            sqlx::query("UPDATE ground_units SET loc = ? WHERE id = ?")
+                .bind(loc)
+                .bind(id)
+                .execute(&mut tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Create a new data store using the specified name.
+    pub async fn new(name: &str) -> DataResult<Self> {
+        let dbpath = Self::path(&Self::folder()?, name);
+        if dbpath.exists() {
+            // This database already exists, so can't create a new campaign
+            // with the same name.
+            return Err(DataError::Io(io::Error::from(io::ErrorKind::AlreadyExists)));
+        }
+
+        // Create and connect to the database.
+        let url = format!("sqlite://{}?mode=rwc", dbpath.to_str().unwrap());
+        let pool = Self::connect(url.as_str()).await?;
+
+        Self::create_tables(&pool).await?;
+        Ok(Self::with_pool(pool, url))
+    }
+
+    /// Open an existing data store by the path to its database, wherever
+    /// it is kept.
+    pub async fn open_file(dbpath: &path::Path) -> DataResult<Self> {
+        if !dbpath.is_file() {
+            return Err(DataError::Io(io::Error::from(io::ErrorKind::NotFound)));
+        }
+
+        // Connect to the database.
+        let url = format!("sqlite://{}", dbpath.to_str().unwrap());
+        let pool = Self::connect(url.as_str()).await?;
+
+        Self::migrate(&pool).await?;
+        Ok(Self::with_pool(pool, url))
+    }
+
+    /// Return the path to the database of a campaign kept in `dir`.
+    pub fn path(dir: &path::Path, name: &str) -> path::PathBuf {
+        // Create SQLite file name by converting spaces in the campaign name
+        // to underscores and adding the '.db' extension.
+        dir.join(name.replace(' ', "_") + ".db")
+    }
+
+    /// Apply a ledger entry to its empire's treasury and tech level, and
+    /// record it. Returns the entry's ID.
+    pub async fn post_ledger(&self, entry: &LedgerEntry) -> DataResult<i64> {
+        let mut conn = self.conn().await?;
+        let mut tx = conn.begin().await?;
+        sqlx::query("UPDATE empires SET treasury = treasury + ?, tech = tech + ? WHERE id = ?")
+            .bind(entry.amount)
+            .bind(entry.tech)
+            .bind(entry.empire)
+            .execute(&mut tx)
+            .await?;
+        let r = sqlx::query(
+            "INSERT INTO ledger (empire, turn, amount, tech, note) VALUES(?, ?, ?, ?, ?)",
+        )
+        .bind(entry.empire)
+        .bind(entry.turn)
+        .bind(entry.amount)
+        .bind(entry.tech)
+        .bind(entry.note.as_str())
+        .execute(&mut tx)
+        .await?;
+        tx.commit().await?;
+        Ok(r.last_insert_rowid())
+    }
+
+    /// Drop the connections to the database and make new ones, as when its
+    /// file was out of reach for a while. The old connections are kept if
+    /// the database still cannot be reached.
+    pub async fn reconnect(&mut self) -> DataResult<()> {
+        let pool = Self::connect(self.url.as_str()).await?;
+        sqlx::query("SELECT 1").execute(&pool).await?;
+        self.pool.close().await;
+        self.pool = pool;
+        Ok(())
+    }
+
+    /// Rename a persistent store kept in `dir`, along with its attached
+    /// files and backups. The store must be closed first, and opened again
+    /// under its new name.
+    pub fn rename(dir: &path::Path, name: &str, new: &str) -> DataResult<()> {
+        let dbpath = Self::path(dir, name);
+        let newpath = Self::path(dir, new);
+        fs::rename(&dbpath, &newpath)?;
+        // Any journal goes with its database.
+        for ext in ["db-wal", "db-shm"] {
+            let journal = dbpath.with_extension(ext);
+            if journal.exists() {
+                fs::rename(journal, newpath.with_extension(ext))?;
+            }
+        }
+        for (from, to) in [
+            (Self::attachments(dir, name), Self::attachments(dir, new)),
+            (Self::backups(dir, name), Self::backups(dir, new)),
+        ] {
+            if from.exists() {
+                fs::rename(from, to)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Repair the given ships, clearing their priorities for the next time
+    /// they are crippled.
+    pub async fn repair_ships(&self, ships: &[i64]) -> DataResult<()> {
+        let mut conn = self.conn().await?;
+        let mut tx = conn.begin().await?;
+        for id in ships {
+            sqlx::query("UPDATE ships SET crip = 0, repair_priority = 0 WHERE id = ?")
+                .bind(id)
+                .execute(&mut tx)
+                .await?;
+            sqlx::query("DELETE FROM construction WHERE kind = ? AND item = ?")
+                .bind(construction::REPAIR)
+                .bind(id)
+                .execute(&mut tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Replace the database of a campaign kept in `dir` with one of its
+    /// backups. The store must be closed first, and opened again afterwards.
+    pub fn restore_backup(dir: &path::Path, name: &str, backup: &str) -> DataResult<()> {
+        let mut file = Self::backups(dir, name).join(backup);
+        file.set_extension(backup::EXTENSION);
+        let dbpath = Self::path(dir, name);
+        fs::copy(file, &dbpath)?;
+        // Drop any journal left from the replaced database.
+        for ext in ["db-wal", "db-shm"] {
+            let journal = dbpath.with_extension(ext);
+            if journal.exists() {
+                fs::remove_file(journal)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Throw away every change made in the unit of work under way, if there
+    /// is one.
+    pub async fn rollback_work(&self) -> DataResult<()> {
+        if let Some(tx) = self.work.lock().await.take() {
+            tx.rollback().await?;
+        }
+        Ok(())
+    }
+
+    /// Copy this store, which is named `name` and kept in `dir`, to a new
+    /// store of another name beside it, with its attached files. The copy
+    /// starts with no backups.
+    pub async fn save_as(&self, dir: &path::Path, name: &str, new: &str) -> DataResult<()> {
+        let newpath = Self::path(dir, new);
+        if newpath.exists() {
+            return Err(DataError::Refused(format!(
+                "There is already a campaign named {}",
+                new
+            )));
+        }
+        self.write_backup(&newpath).await?;
+        let from = Self::attachments(dir, name);
+        if from.exists() {
+            Self::copy_folder(&from, &Self::attachments(dir, new))?;
+        }
+        Ok(())
+    }
+
+    /// Scrap a ship, crediting its owner with what it recovers. Any repair
+    /// ordered for the ship is refunded, and any flights aboard are lost.
+    pub async fn scrap_ship(&self, ship: &ShipLocation, value: i32) -> DataResult<()> {
+        let mut conn = self.conn().await?;
+        let mut tx = conn.begin().await?;
+        sqlx::query(
+            "UPDATE empires SET treasury = treasury + ? + COALESCE(
+            (SELECT SUM(cost) FROM construction WHERE kind = ? AND item = ?), 0)
+            WHERE id = ?",
+        )
+        .bind(value)
+        .bind(construction::REPAIR)
+        .bind(ship.id)
+        .bind(ship.owner)
+        .execute(&mut tx)
+        .await?;
+        sqlx::query("DELETE FROM construction WHERE kind = ? AND item = ?")
+            .bind(construction::REPAIR)
+            .bind(ship.id)
+            .execute(&mut tx)
+            .await?;
+        sqlx::query("DELETE FROM flights WHERE carrier = ?")
+            .bind(ship.id)
+            .execute(&mut tx)
+            .await?;
+        sqlx::query("DELETE FROM ships WHERE id = ?")
+            .bind(ship.id)
+            .execute(&mut tx)
+            .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Copy the store into a new one held in memory, for trying out changes
+    /// that are thrown away with it.
+    pub async fn scratch(&self) -> DataResult<Self> {
+        let url = "sqlite::memory:".to_string();
+        let pool = Self::connect(url.as_str()).await?;
+        Self::create_tables(&pool).await?;
+        let copy = Self::with_pool(pool, url);
+        copy.import(&self.export().await?).await?;
+        Ok(copy)
+    }
+
+    /// Save the campaign's options.
+    pub async fn set_campaign_options(&self, options: &CampaignOptions) -> DataResult<()> {
+        let mut conn = self.conn().await?;
+        let mut tx = conn.begin().await?;
+        for (key, value) in options.to_control() {
+            sqlx::query(
+                "INSERT INTO control (key, value) VALUES(?, ?)
+                ON CONFLICT (key) DO UPDATE SET value = excluded.value",
+            )
+            .bind(key)
+            .bind(value)
+            .execute(&mut tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Set the course a fleet is to follow, replacing any it had. An empty
+    /// course leaves the fleet where it is.
+    pub async fn set_course(&self, fleet: i64, course: &[i64]) -> DataResult<()> {
+        let mut conn = self.conn().await?;
+        let mut tx = conn.begin().await?;
+        Self::replace_course(&mut tx, fleet, course).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Set an empire's order deadline for a turn.
+    pub async fn set_deadline(&self, empire: i64, turn: i32, due: &str) -> DataResult<()> {
+        sqlx::query(
+            "INSERT INTO deadlines (empire, turn, due) VALUES(?, ?, ?)
+            ON CONFLICT (empire, turn) DO UPDATE SET due = excluded.due",
+        )
+        .bind(empire)
+        .bind(turn)
+        .bind(due)
+        .execute(&mut *self.conn().await?)
+        .await?;
+        Ok(())
+    }
+
+    /// Write the journal entry for its turn, replacing any there was. An
+    /// entry with no text removes the turn's entry.
+    pub async fn set_journal_entry(&self, entry: &JournalEntry) -> DataResult<()> {
+        if entry.text.trim().is_empty() {
+            sqlx::query("DELETE FROM journal WHERE turn = ?")
+                .bind(entry.turn)
+                .execute(&mut *self.conn().await?)
+                .await?;
+        } else {
+            sqlx::query(
+                "INSERT INTO journal (turn, text) VALUES(?, ?)
+                ON CONFLICT (turn) DO UPDATE SET text = excluded.text",
+            )
+            .bind(entry.turn)
+            .bind(entry.text.as_str())
+            .execute(&mut *self.conn().await?)
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Set an empire's empire-wide morale modifier.
+    pub async fn set_morale_modifier(&self, empire: i64, amount: i32) -> DataResult<()> {
+        sqlx::query(
+            "INSERT INTO morale_modifiers (empire, amount) VALUES(?, ?)
+            ON CONFLICT (empire) DO UPDATE SET amount = excluded.amount",
+        )
+        .bind(empire)
+        .bind(amount)
+        .execute(&mut *self.conn().await?)
+        .await?;
+        Ok(())
+    }
+
+    /// Mothball ships, or return them to service.
+    pub async fn set_mothballed(&self, ships: &[i64], moth: bool) -> DataResult<()> {
+        let mut conn = self.conn().await?;
+        let mut tx = conn.begin().await?;
+        for id in ships {
+            sqlx::query("UPDATE ships SET moth = ? WHERE id = ?")
+                .bind(moth)
+                .bind(id)
+                .execute(&mut tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Set the order in which a ship is repaired. Higher priorities are
+    /// repaired first.
+    pub async fn set_repair_priority(&self, ship: i64, priority: i32) -> DataResult<()> {
+        sqlx::query("UPDATE ships SET repair_priority = ? WHERE id = ?")
+            .bind(priority)
+            .bind(ship)
+            .execute(&mut *self.conn().await?)
+            .await?;
+        Ok(())
+    }
+
+    /// Set an empire's research spending for a turn, paying for it from the
+    /// treasury. Any amount set earlier in the turn is refunded first.
+    pub async fn set_research(&self, empire: i64, turn: i32, amount: i32) -> DataResult<()> {
+        let mut conn = self.conn().await?;
+        let mut tx = conn.begin().await?;
+        let old: Option<i32> =
+            sqlx::query_scalar("SELECT amount FROM research WHERE empire = ? AND turn = ?")
+                .bind(empire)
+                .bind(turn)
+                .fetch_optional(&mut tx)
+                .await?;
+        sqlx::query(
+            "INSERT INTO research (empire, turn, amount) VALUES(?, ?, ?)
+            ON CONFLICT (empire, turn) DO UPDATE SET amount = excluded.amount",
+        )
+        .bind(empire)
+        .bind(turn)
+        .bind(amount)
+        .execute(&mut tx)
+        .await?;
+        sqlx::query("UPDATE empires SET treasury = treasury + ? - ? WHERE id = ?")
+            .bind(old.unwrap_or(0))
+            .bind(amount)
+            .bind(empire)
+            .execute(&mut tx)
+            .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Turn an optional rule on or off.
+    pub async fn set_rule_option(&self, key: &str, on: bool) -> DataResult<()> {
+        sqlx::query(
+            "INSERT INTO control (key, value) VALUES(?, ?)
+            ON CONFLICT (key) DO UPDATE SET value = excluded.value",
+        )
+        .bind(key)
+        .bind(if on { "1" } else { "0" })
+        .execute(&mut *self.conn().await?)
+        .await?;
+        Ok(())
+    }
+
+    /// Set a numeric rule setting.
+    pub async fn set_rule_value(&self, key: &str, value: i32) -> DataResult<()> {
+        sqlx::query(
+            "INSERT INTO control (key, value) VALUES(?, ?)
+            ON CONFLICT (key) DO UPDATE SET value = excluded.value",
+        )
+        .bind(key)
+        .bind(value.to_string())
+        .execute(&mut *self.conn().await?)
+        .await?;
+        Ok(())
+    }
+
+    /// Record when an empire submitted its orders for a turn.
+    pub async fn set_submitted(&self, empire: i64, turn: i32, when: &str) -> DataResult<()> {
+        sqlx::query(
+            "INSERT INTO deadlines (empire, turn, due, submitted) VALUES(?, ?, '', ?)
+            ON CONFLICT (empire, turn) DO UPDATE SET submitted = excluded.submitted",
+        )
+        .bind(empire)
+        .bind(turn)
+        .bind(when)
+        .execute(&mut *self.conn().await?)
+        .await?;
+        Ok(())
+    }
+
+    /// Set an empire's tech level and the research points it carries toward
+    /// the next level.
+    pub async fn set_tech(&self, empire: i64, tech: i32, points: i32) -> DataResult<()> {
+        let mut conn = self.conn().await?;
+        let mut tx = conn.begin().await?;
+        sqlx::query("UPDATE empires SET tech = ? WHERE id = ?")
+            .bind(tech)
+            .bind(empire)
+            .execute(&mut tx)
+            .await?;
+        sqlx::query(
+            "INSERT INTO tech_progress (empire, points) VALUES(?, ?)
+            ON CONFLICT (empire) DO UPDATE SET points = excluded.points",
+        )
+        .bind(empire)
+        .bind(points)
+        .execute(&mut tx)
+        .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Set the current turn number.
+    pub async fn set_turn(&self, turn: i32) -> DataResult<()> {
+        sqlx::query("UPDATE control SET value = ? WHERE key = 'turn'")
+            .bind(turn.to_string())
+            .execute(&mut *self.conn().await?)
+            .await?;
+        Ok(())
+    }
+
+    /// Move the given ships out of their fleet into a new fleet with the same
+    /// owner and location, returning the new fleet's ID.
+    pub async fn split_fleet(&self, from: &Fleet, name: &str, ships: &[i64]) -> DataResult<i64> {
+        let mut conn = self.conn().await?;
+        let mut tx = conn.begin().await?;
+        let r = sqlx::query("INSERT INTO fleets (name, owner, location, speed) VALUES(?, ?, ?, ?)")
+            .bind(name)
+            .bind(from.owner)
+            .bind(from.location)
+            .bind(from.speed)
+            .execute(&mut tx)
+            .await?;
+        let id = r.last_insert_rowid();
+        for s in ships {
+            sqlx::query("UPDATE ships SET fleet = ? WHERE id = ? AND fleet = ?")
+                .bind(id)
+                .bind(s)
+                .bind(from.id)
+                .execute(&mut tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(id)
+    }
+
+    /// Mark the other classes of an empire built on the same hull as a new
+    /// class obsolete as of the given turn. Classes already obsolete keep the
+    /// turn they were first superseded.
+    pub async fn supersede_ship_types(&self, newer: &ShipType, turn: i32) -> DataResult<()> {
+        sqlx::query(
+            "UPDATE ship_types SET obsolete = ?
+            WHERE empire = ? AND hull = ? AND id != ? AND obsolete IS NULL",
+        )
+        .bind(turn)
+        .bind(newer.empire)
+        .bind(newer.hull.as_str())
+        .bind(newer.id)
+        .execute(&mut *self.conn().await?)
+        .await?;
+        Ok(())
+    }
+
+    /// Return the current local date and time in ISO 8601 form.
+    pub async fn today(&self) -> DataResult<String> {
+        let now = sqlx::query_scalar("SELECT datetime('now', 'localtime')")
+            .fetch_one(&mut *self.conn().await?)
+            .await?;
+        Ok(now)
+    }
+
+    /// Carry out a transfer in one transaction. The price is posted to both
+    /// empires' ledgers. A ship joins a fleet of the buyer's where it is,
+    /// named for the seller, with any flights it carries, and the buyer
+    /// identifies its class; a class is copied to the buyer.
+    pub async fn transfer(&self, t: &Transfer) -> DataResult<()> {
+        let mut conn = self.conn().await?;
+        let mut tx = conn.begin().await?;
+        if t.price > 0 {
+            for (empire, amount, note) in [
+                (
+                    t.seller,
+                    t.price,
+                    format!("Sold {} to {}", t.item_name, t.buyer_name),
+                ),
+                (
+                    t.buyer,
+                    -t.price,
+                    format!("Bought {} from {}", t.item_name, t.seller_name),
+                ),
+            ] {
+                sqlx::query("UPDATE empires SET treasury = treasury + ? WHERE id = ?")
+                    .bind(amount)
+                    .bind(empire)
+                    .execute(&mut tx)
+                    .await?;
+                sqlx::query(
+                    "INSERT INTO ledger (empire, turn, amount, tech, note) VALUES(?, ?, ?, 0, ?)",
+                )
+                .bind(empire)
+                .bind(t.turn)
+                .bind(amount)
+                .bind(note.as_str())
+                .execute(&mut tx)
+                .await?;
+            }
+        }
+        if let Some(ship) = t.ship {
+            let (stype, location): (i64, i64) = sqlx::query_as(
+                "SELECT s.stype, f.location FROM ships s
+                JOIN fleets f ON s.fleet = f.id
+                WHERE s.id = ?",
+            )
+            .bind(ship)
+            .fetch_one(&mut tx)
+            .await?;
+            let name = format!("From {}", t.seller_name);
+            let fleet: Option<i64> = sqlx::query_scalar(
+                "SELECT id FROM fleets WHERE owner = ? AND location = ? AND name = ?",
+            )
+            .bind(t.buyer)
+            .bind(location)
+            .bind(name.as_str())
+            .fetch_optional(&mut tx)
+            .await?;
+            let fleet = match fleet {
+                Some(id) => id,
+                None => sqlx::query("INSERT INTO fleets (name, owner, location) VALUES(?, ?, ?)")
+                    .bind(name.as_str())
+                    .bind(t.buyer)
+                    .bind(location)
+                    .execute(&mut tx)
+                    .await?
+                    .last_insert_rowid(),
+            };
+            sqlx::query("UPDATE ships SET fleet = ?, repair_priority = 0 WHERE id = ?")
+                .bind(fleet)
+                .bind(ship)
+                .execute(&mut tx)
+                .await?;
+            sqlx::query("UPDATE flights SET owner = ? WHERE carrier = ?")
+                .bind(t.buyer)
+                .bind(ship)
+                .execute(&mut tx)
+                .await?;
+            sqlx::query(
+                "INSERT INTO identifications (empire, stype, turn, source) VALUES(?, ?, ?, ?)
+                ON CONFLICT (empire, stype) DO NOTHING",
+            )
+            .bind(t.buyer)
+            .bind(stype)
+            .bind(t.turn)
+            .bind(TRANSFER)
+            .execute(&mut tx)
+            .await?;
+        }
+        if let Some(stype) = t.stype {
+            sqlx::query(
+                "INSERT INTO ship_types
+                (class, hull, cost, cr, atk, def, cap, speed, empire, obsolete, retired)
+                SELECT class, hull, cost, cr, atk, def, cap, speed, ?, obsolete, 0
+                FROM ship_types WHERE id = ?",
+            )
+            .bind(t.buyer)
+            .bind(stype)
+            .execute(&mut tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Update the turns remaining on a build.
+    pub async fn update_build(&self, build: &Build) -> DataResult<()> {
+        sqlx::query("UPDATE construction SET turns = ? WHERE id = ?")
+            .bind(build.turns)
+            .bind(build.id)
+            .execute(&mut *self.conn().await?)
+            .await?;
+        Ok(())
+    }
+
+    /// Update an existing combat modifier.
+    pub async fn update_combat_modifier(&self, m: &CombatModifier) -> DataResult<()> {
+        sqlx::query(
+            "UPDATE combat_modifiers SET (category, name, atk, def) = (?, ?, ?, ?)
+            WHERE id = ?",
+        )
+        .bind(m.category.as_str())
+        .bind(m.name.as_str())
+        .bind(m.atk)
+        .bind(m.def)
+        .bind(m.id)
+        .execute(&mut *self.conn().await?)
+        .await?;
+        Ok(())
+    }
+
+    /// Update an existing empire.
+    pub async fn update_empire(&self, emp: &Empire) -> DataResult<()> {
+        self.apply(Change::UpdateEmpire(emp.clone()))
+            .await
+            .map(|_| ())
+    }
+
+    /// Update an existing facility.
+    pub async fn update_facility(&self, facility: &Facility) -> DataResult<()> {
+        sqlx::query("UPDATE facilities SET (kind, crip) = (?, ?) WHERE id = ?")
+            .bind(facility.kind.as_str())
+            .bind(facility.crip)
+            .bind(facility.id)
+            .execute(&mut *self.conn().await?)
+            .await?;
+        Ok(())
+    }
+
+    /// Update an existing fleet's name, owner, location and speed.
+    pub async fn update_fleet(&self, fleet: &Fleet) -> DataResult<()> {
+        self.apply(Change::UpdateFleet(fleet.clone()))
+            .await
+            .map(|_| ())
+    }
+
+    /// Update an existing flight's station, ratings and condition.
+    pub async fn update_flight(&self, flight: &Flight) -> DataResult<()> {
+        sqlx::query(
+            "UPDATE flights SET (carrier, base, atk, def, crip) = (?, ?, ?, ?, ?) WHERE id = ?",
+        )
+        .bind(flight.carrier)
+        .bind(flight.base)
+        .bind(flight.atk)
+        .bind(flight.def)
+        .bind(flight.crip)
+        .bind(flight.id)
+        .execute(&mut *self.conn().await?)
+        .await?;
+        Ok(())
+    }
+
+    /// Update an existing jump lane.
+    pub async fn update_lane(&self, lane: &Lane) -> DataResult<()> {
+        self.apply(Change::UpdateLane(lane.clone()))
+            .await
+            .map(|_| ())
+    }
+
+    /// Update an existing ship class.
+    pub async fn update_ship_type(&self, stype: &ShipType) -> DataResult<()> {
+        sqlx::query(
+            "UPDATE ship_types
+            SET class = ?, hull = ?, cost = ?, cr = ?, atk = ?, def = ?, cap = ?, speed = ?,
+            empire = ?, obsolete = ?, retired = ?
+            WHERE id = ?",
+        )
+        .bind(stype.class.as_str())
+        .bind(stype.hull.as_str())
+        .bind(stype.cost)
+        .bind(stype.cr)
+        .bind(stype.atk)
+        .bind(stype.def)
+        .bind(stype.cap)
+        .bind(stype.speed)
+        .bind(stype.empire)
+        .bind(stype.obsolete)
+        .bind(stype.retired)
+        .bind(stype.id)
+        .execute(&mut *self.conn().await?)
+        .await?;
+        Ok(())
+    }
+
+    /// Update an existing system.
+    pub async fn update_system(&self, sys: &System) -> DataResult<()> {
+        self.apply(Change::UpdateSystem(sys.clone()))
+            .await
+            .map(|_| ())
+    }
+
+    /// Write a consistent copy of the database to a new file.
+    pub async fn write_backup(&self, file: &path::Path) -> DataResult<()> {
+        sqlx::query("VACUUM INTO ?")
+            .bind(file.to_string_lossy().to_string())
+            .execute(&mut *self.conn().await?)
+            .await?;
+        Ok(())
+    }
+
+    /// Add a column to a table, unless it already has it.
+    async fn add_column(
+        pool: &SqlitePool,
+        table: &str,
+        column: &str,
+        decl: &str,
+    ) -> DataResult<()> {
+        let n: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM pragma_table_info(?) WHERE name = ?")
+            .bind(table)
+            .bind(column)
+            .fetch_one(pool)
+            .await?;
+        if n == 0 {
+            sqlx::query(
+                format!("ALTER TABLE \"{}\" ADD COLUMN {} {}", table, column, decl).as_str(),
+            )
+            .execute(pool)
+            .await?;
+        }
+        Ok(())
+    }
+
+    // Make one change in a transaction of its own.
+    async fn apply(&self, change: Change) -> DataResult<i64> {
+        let mut conn = self.conn().await?;
+        let mut tx = conn.begin().await?;
+        let id = Self::apply_change(&mut tx, &change, &[]).await?;
+        tx.commit().await?;
+        Ok(id)
+    }
+
+    // Make one change on a connection, given the IDs of the rows added by
+    // the changes before it in its batch.
+    async fn apply_change(
+        conn: &mut SqliteConnection,
+        change: &Change,
+        added: &[i64],
+    ) -> DataResult<i64> {
+        let id = |id: i64| batch::resolve(id, added).map_err(DataError::Refused);
+        match change {
+            Change::AddEmpire(emp) => {
+                let r = sqlx::query(
+                    "INSERT INTO empires (name, treasury, tech, government, traits)
+                    VALUES(?, ?, ?, ?, ?)",
+                )
+                .bind(emp.name.as_str())
+                .bind(emp.treasury)
+                .bind(emp.tech)
+                .bind(emp.government.as_str())
+                .bind(emp.traits.as_str())
+                .execute(&mut *conn)
+                .await?;
+                Ok(r.last_insert_rowid())
+            }
+            Change::UpdateEmpire(emp) => {
+                let emp_id = id(emp.id)?;
+                sqlx::query(
+                    "UPDATE empires SET (name, treasury, tech, government, traits) = (?, ?, ?, ?, ?)
+                    WHERE id = ?",
+                )
+                .bind(emp.name.as_str())
+                .bind(emp.treasury)
+                .bind(emp.tech)
+                .bind(emp.government.as_str())
+                .bind(emp.traits.as_str())
+                .bind(emp_id)
+                .execute(&mut *conn)
+                .await?;
+                Ok(emp_id)
+            }
+            Change::DeleteEmpire(emp) => {
+                let emp = id(*emp)?;
+                let forces: i64 = sqlx::query_scalar(
+                    "SELECT (SELECT COUNT(*) FROM fleets WHERE owner = ?)
+                    + (SELECT COUNT(*) FROM flights WHERE owner = ?)
+                    + (SELECT COUNT(*) FROM ships s JOIN ship_types t ON s.stype = t.id
+                    WHERE t.empire = ?)",
+                )
+                .bind(emp)
+                .bind(emp)
+                .bind(emp)
+                .fetch_one(&mut *conn)
+                .await?;
+                if forces > 0 {
+                    let name = Self::name_of(conn, "empires", emp).await?;
+                    return Err(DataError::Refused(format!(
+                        "{} still has fleets, flights or ships of its classes; disband them first",
+                        name
+                    )));
+                }
+                sqlx::query("UPDATE systems SET contender = 0, share = 100 WHERE contender = ?")
+                    .bind(emp)
+                    .execute(&mut *conn)
+                    .await?;
+                sqlx::query("DELETE FROM empires WHERE id = ?")
+                    .bind(emp)
+                    .execute(&mut *conn)
+                    .await?;
+                Ok(emp)
+            }
+            Change::AddSystem(sys) => {
+                let owner = match sys.owner {
+                    0 => None,
+                    o => Some(id(o)?),
+                };
+                let r = sqlx::query(
+                    "INSERT INTO systems
+                    (name, ptype, raw, cap, pop, mor, ind, dev, fails, blockaded, x, y, minor,
+                    contender, share, policy, owner)
+                    VALUES(?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                )
+                .bind(sys.name.as_str())
+                .bind(sys.ptype.as_str())
+                .bind(sys.raw)
+                .bind(sys.cap)
+                .bind(sys.pop)
+                .bind(sys.mor)
+                .bind(sys.ind)
+                .bind(sys.dev)
+                .bind(sys.fails)
+                .bind(sys.blockaded)
+                .bind(sys.x)
+                .bind(sys.y)
+                .bind(sys.minor.as_str())
+                .bind(id(sys.contender)?)
+                .bind(sys.share)
+                .bind(sys.policy.as_str())
+                .bind(owner)
+                .execute(&mut *conn)
+                .await?;
+                Ok(r.last_insert_rowid())
+            }
+            Change::UpdateSystem(sys) => {
+                let sys_id = id(sys.id)?;
+                // Skip updating owner if it's not set.
+                let owner = match sys.owner {
+                    0 => None,
+                    o => Some(id(o)?),
+                };
+                sqlx::query(
+                    "UPDATE systems SET
+                    (name, ptype, raw, cap, pop, mor, ind, dev, fails, blockaded, x, y, minor,
+                    contender, share, policy, owner) =
+                    (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, COALESCE(?, owner))
+                    WHERE id = ?",
+                )
+                .bind(sys.name.as_str())
+                .bind(sys.ptype.as_str())
+                .bind(sys.raw)
+                .bind(sys.cap)
+                .bind(sys.pop)
+                .bind(sys.mor)
+                .bind(sys.ind)
+                .bind(sys.dev)
+                .bind(sys.fails)
+                .bind(sys.blockaded)
+                .bind(sys.x)
+                .bind(sys.y)
+                .bind(sys.minor.as_str())
+                .bind(id(sys.contender)?)
+                .bind(sys.share)
+                .bind(sys.policy.as_str())
+                .bind(owner)
+                .bind(sys_id)
+                .execute(&mut *conn)
+                .await?;
+                Ok(sys_id)
+            }
+            Change::DeleteSystem(sys) => {
+                let sys = id(*sys)?;
+                let forces: i64 = sqlx::query_scalar(
+                    "SELECT (SELECT COUNT(*) FROM fleets WHERE location = ?)
+                    + (SELECT COUNT(*) FROM flights WHERE base = ?)",
+                )
+                .bind(sys)
+                .bind(sys)
+                .fetch_one(&mut *conn)
+                .await?;
+                if forces > 0 {
+                    let name = Self::name_of(conn, "systems", sys).await?;
+                    return Err(DataError::Refused(format!(
+                        "{} still has fleets or flights there; move them first",
+                        name
+                    )));
+                }
+                sqlx::query("DELETE FROM systems WHERE id=?")
+                    .bind(sys)
+                    .execute(&mut *conn)
+                    .await?;
+                Ok(sys)
+            }
+            Change::AddLane(lane) => {
+                let r = sqlx::query("INSERT INTO lanes (a, b, ltype, defended) VALUES(?, ?, ?, ?)")
+                    .bind(id(lane.a)?)
+                    .bind(id(lane.b)?)
+                    .bind(lane.ltype.as_str())
+                    .bind(lane.defended)
+                    .execute(&mut *conn)
+                    .await?;
+                Ok(r.last_insert_rowid())
+            }
+            Change::UpdateLane(lane) => {
+                let lane_id = id(lane.id)?;
+                sqlx::query("UPDATE lanes SET (a, b, ltype, defended) = (?, ?, ?, ?) WHERE id = ?")
+                    .bind(id(lane.a)?)
+                    .bind(id(lane.b)?)
+                    .bind(lane.ltype.as_str())
+                    .bind(lane.defended)
+                    .bind(lane_id)
+                    .execute(&mut *conn)
+                    .await?;
+                Ok(lane_id)
+            }
+            Change::DeleteLane(lane) => {
+                let lane = id(*lane)?;
+                sqlx::query("DELETE FROM lanes WHERE id = ?")
+                    .bind(lane)
+                    .execute(&mut *conn)
+                    .await?;
+                Ok(lane)
+            }
+            Change::AddFleet(fleet) => {
+                let r = sqlx::query(
+                    "INSERT INTO fleets (name, owner, location, speed) VALUES(?, ?, ?, ?)",
+                )
+                .bind(fleet.name.as_str())
+                .bind(id(fleet.owner)?)
+                .bind(id(fleet.location)?)
+                .bind(fleet.speed)
+                .execute(&mut *conn)
+                .await?;
+                Ok(r.last_insert_rowid())
+            }
+            Change::UpdateFleet(fleet) => {
+                let fleet_id = id(fleet.id)?;
+                sqlx::query(
+                    "UPDATE fleets SET (name, owner, location, speed) = (?, ?, ?, ?) WHERE id = ?",
+                )
+                .bind(fleet.name.as_str())
+                .bind(id(fleet.owner)?)
+                .bind(id(fleet.location)?)
+                .bind(fleet.speed)
+                .bind(fleet_id)
+                .execute(&mut *conn)
+                .await?;
+                Ok(fleet_id)
+            }
+            Change::DeleteFleet(fleet) => {
+                // Disbanding takes the fleet's ships and the flights they
+                // carry with it.
+                let fleet = id(*fleet)?;
+                sqlx::query("DELETE FROM escorts WHERE escort = ? OR convoy = ?")
+                    .bind(fleet)
+                    .bind(fleet)
+                    .execute(&mut *conn)
+                    .await?;
+                sqlx::query(
+                    "DELETE FROM flights WHERE carrier IN (SELECT id FROM ships WHERE fleet = ?)",
+                )
+                .bind(fleet)
+                .execute(&mut *conn)
+                .await?;
+                sqlx::query(
+                    "DELETE FROM construction
+                    WHERE kind = ? AND item IN (SELECT id FROM ships WHERE fleet = ?)",
+                )
+                .bind(construction::REPAIR)
+                .bind(fleet)
+                .execute(&mut *conn)
+                .await?;
+                sqlx::query("DELETE FROM ships WHERE fleet = ?")
+                    .bind(fleet)
+                    .execute(&mut *conn)
+                    .await?;
+                sqlx::query("DELETE FROM fleets WHERE id = ?")
+                    .bind(fleet)
+                    .execute(&mut *conn)
+                    .await?;
+                Ok(fleet)
+            }
+        }
+    }
+
+    // The first table with a row referring to a row that is missing, if any.
+    async fn broken_reference(tx: &mut Transaction<'_, Sqlite>) -> DataResult<Option<String>> {
+        let table = sqlx::query_scalar("SELECT \"table\" FROM pragma_foreign_key_check LIMIT 1")
+            .fetch_optional(&mut *tx)
+            .await?;
+        Ok(table)
+    }
+
+    // Connect to a database, enforcing foreign keys on every connection and
+    // waiting out locks. A database that cannot be reached is tried again a
+    // few times, in case it is only held up for a moment.
+    // The connection to run an operation on: that of the unit of work under
+    // way, if there is one, or else one from the pool.
+    async fn conn(&self) -> DataResult<Conn<'_>> {
+        let work = self.work.lock().await;
+        if work.is_some() {
+            return Ok(Conn::Work(MutexGuard::map(work, |w| w.as_mut().unwrap())));
+        }
+        drop(work);
+        Ok(Conn::Pool(self.pool.acquire().await?))
+    }
+
+    async fn connect(url: &str) -> DataResult<SqlitePool> {
+        let options = SqliteConnectOptions::from_str(url)?
+            .foreign_keys(true)
+            .busy_timeout(BUSY_TIMEOUT);
+        let mut attempt = 1;
+        loop {
+            match SqlitePool::connect_with(options.clone()).await {
+                Ok(pool) => return Ok(pool),
+                Err(e) if attempt < CONNECT_ATTEMPTS && unavailable(&e) => {
+                    attempt += 1;
+                    tokio::task::spawn_blocking(|| std::thread::sleep(CONNECT_DELAY)).await?;
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    // Copy a folder and everything in it.
+    fn copy_folder(from: &path::Path, to: &path::Path) -> DataResult<()> {
+        fs::create_dir_all(to)?;
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            let target = to.join(entry.file_name());
+            if entry.file_type()?.is_dir() {
+                Self::copy_folder(&entry.path(), &target)?;
+            } else {
+                fs::copy(entry.path(), target)?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn create_battles_table(pool: &SqlitePool) -> DataResult<()> {
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS battles (
+            turn INTEGER,
+            system INTEGER REFERENCES systems (id) ON DELETE CASCADE,
+            PRIMARY KEY (turn, system))",
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn create_casualties_table(pool: &SqlitePool) -> DataResult<()> {
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS casualties (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            war INTEGER REFERENCES wars (id) ON DELETE CASCADE,
+            turn INTEGER,
+            empire INTEGER REFERENCES empires (id) ON DELETE CASCADE,
+            enemy INTEGER REFERENCES empires (id) ON DELETE CASCADE,
+            kind TEXT,
+            destroyed INTEGER,
+            cost INTEGER)",
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn create_combat_modifiers_table(pool: &SqlitePool) -> DataResult<()> {
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS combat_modifiers (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            category TEXT,
+            name TEXT,
+            atk INTEGER DEFAULT 0,
+            def INTEGER DEFAULT 0)",
+        )
+        .execute(pool)
+        .await?;
+
+        sqlx::query(
+            "INSERT INTO combat_modifiers
+            (category, name, atk, def)
+            VALUES
+            ('Terrain', 'Open Space', 0, 0),
+            ('Terrain', 'Nebula', -1, 0),
+            ('Terrain', 'Asteroid Field', 0, 1),
+            ('Condition', 'Crippled', -2, -2),
+            ('Supply', 'Out of Supply', -1, -1),
+            ('Crew', 'Green', -1, 0),
+            ('Crew', 'Regular', 0, 0),
+            ('Crew', 'Veteran', 1, 0),
+            ('Crew', 'Elite', 2, 1),
+            ('Leader', 'Average', 1, 0),
+            ('Leader', 'Exceptional', 2, 1)",
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn create_conquests_table(pool: &SqlitePool) -> DataResult<()> {
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS conquests (
+            system INTEGER PRIMARY KEY REFERENCES systems (id) ON DELETE CASCADE,
+            turn INTEGER)",
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn create_construction_table(pool: &SqlitePool) -> DataResult<()> {
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS construction (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            empire INTEGER REFERENCES empires (id) ON DELETE CASCADE,
+            system INTEGER REFERENCES systems (id) ON DELETE CASCADE,
+            kind TEXT,
+            item INTEGER,
+            name TEXT,
+            cost INTEGER,
+            turns INTEGER,
+            count INTEGER DEFAULT 1)",
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn create_controls_table(pool: &SqlitePool) -> DataResult<()> {
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS control (
+            key TEXT PRIMARY KEY,
+            value TEXT)",
+        )
+        .execute(pool)
+        .await?;
+
+        sqlx::query(
+            "INSERT INTO control VALUES
+            ('turn', '0'),
+            (?, ?)",
+        )
+        .bind(SCHEMA_KEY)
+        .bind(SCHEMA_VERSION.to_string())
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn create_courses_table(pool: &SqlitePool) -> DataResult<()> {
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS courses (
+            fleet INTEGER REFERENCES fleets (id) ON DELETE CASCADE,
+            step INTEGER,
+            system INTEGER REFERENCES systems (id) ON DELETE CASCADE,
+            PRIMARY KEY (fleet, step))",
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn create_deadlines_table(pool: &SqlitePool) -> DataResult<()> {
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS deadlines (
+            empire INTEGER REFERENCES empires (id) ON DELETE CASCADE,
+            turn INTEGER,
+            due TEXT,
+            submitted TEXT,
+            PRIMARY KEY (empire, turn))",
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn create_developments_table(pool: &SqlitePool) -> DataResult<()> {
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS developments (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            empire INTEGER REFERENCES empires (id) ON DELETE CASCADE,
+            turn INTEGER,
+            system INTEGER REFERENCES systems (id) ON DELETE CASCADE,
+            target TEXT,
+            amount INTEGER,
+            UNIQUE (system, turn, target))",
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn create_empires_table(pool: &SqlitePool) -> DataResult<()> {
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS empires (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT UNIQUE,
+            treasury INTEGER DEFAULT 0,
+            tech INTEGER DEFAULT 0,
+            government TEXT DEFAULT 'Monarchy',
+            traits TEXT DEFAULT '')",
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn create_escorts_table(pool: &SqlitePool) -> DataResult<()> {
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS escorts (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            escort INTEGER UNIQUE REFERENCES fleets (id) ON DELETE CASCADE,
+            convoy INTEGER REFERENCES fleets (id) ON DELETE CASCADE)",
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn create_espionage_table(pool: &SqlitePool) -> DataResult<()> {
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS espionage (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            empire INTEGER REFERENCES empires (id) ON DELETE CASCADE,
+            turn INTEGER,
+            target INTEGER REFERENCES empires (id) ON DELETE CASCADE,
+            mission TEXT,
+            points INTEGER)",
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn create_facilities_table(pool: &SqlitePool) -> DataResult<()> {
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS facilities (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            system INTEGER REFERENCES systems (id) ON DELETE CASCADE,
+            kind TEXT,
+            crip INTEGER DEFAULT 0)",
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn create_fleets_table(pool: &SqlitePool) -> DataResult<()> {
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS fleets (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT,
+            owner INTEGER REFERENCES empires (id) ON DELETE RESTRICT,
+            location INTEGER REFERENCES systems (id) ON DELETE RESTRICT,
+            speed INTEGER DEFAULT 0)",
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn create_flights_table(pool: &SqlitePool) -> DataResult<()> {
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS flights (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            owner INTEGER REFERENCES empires (id) ON DELETE RESTRICT,
+            carrier INTEGER REFERENCES ships (id) ON DELETE CASCADE,
+            base INTEGER REFERENCES systems (id) ON DELETE RESTRICT,
+            atk INTEGER DEFAULT 1,
+            def INTEGER DEFAULT 1,
+            crip INTEGER DEFAULT 0)",
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn create_ground_types_table(pool: &SqlitePool) -> DataResult<()> {
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS ground_types (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT,
+            abbr TEXT,
+            cost INTEGER,
+            atk INTEGER,
+            def INTEGER)",
+        )
+        .execute(pool)
+        .await?;
+
+        let mut tx = pool.begin().await?;
+        Self::insert_ground_types(&mut tx, &setting::standard().ground_types).await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn create_ground_units_table(pool: &SqlitePool) -> DataResult<()> {
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS ground_units (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            gtype INTEGER REFERENCES ground_types (id) ON DELETE RESTRICT,
+            loc INTEGER REFERENCES systems (id) ON DELETE CASCADE)",
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn create_hulls_table(pool: &SqlitePool) -> DataResult<()> {
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS hulls (
+            name TEXT PRIMARY KEY,
+            size INTEGER,
+            maint INTEGER,
+            tech INTEGER)",
+        )
+        .execute(pool)
+        .await?;
+
+        let mut tx = pool.begin().await?;
+        Self::insert_hulls(&mut tx, &setting::standard().hulls).await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn create_identifications_table(pool: &SqlitePool) -> DataResult<()> {
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS identifications (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            empire INTEGER REFERENCES empires (id) ON DELETE CASCADE,
+            stype INTEGER REFERENCES ship_types (id) ON DELETE CASCADE,
+            turn INTEGER,
+            source TEXT,
+            UNIQUE (empire, stype))",
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn create_indexes(pool: &SqlitePool) -> DataResult<()> {
+        for stmt in [
+            "CREATE INDEX IF NOT EXISTS systems_owner ON systems (owner)",
+            "CREATE INDEX IF NOT EXISTS ships_fleet ON ships (fleet)",
+            "CREATE INDEX IF NOT EXISTS fleets_owner_location ON fleets (owner, location)",
+            "CREATE INDEX IF NOT EXISTS ground_units_loc ON ground_units (loc)",
+            "CREATE INDEX IF NOT EXISTS lanes_a ON lanes (a)",
+            "CREATE INDEX IF NOT EXISTS lanes_b ON lanes (b)",
+            "CREATE INDEX IF NOT EXISTS orders_empire_turn ON orders (empire, turn)",
+        ] {
+            sqlx::query(stmt).execute(pool).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn create_intel_table(pool: &SqlitePool) -> DataResult<()> {
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS intel (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            empire INTEGER REFERENCES empires (id) ON DELETE CASCADE,
+            turn INTEGER,
+            kind TEXT,
+            text TEXT)",
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn create_journal_table(pool: &SqlitePool) -> DataResult<()> {
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS journal (
+            turn INTEGER PRIMARY KEY,
+            text TEXT)",
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn create_lanes_table(pool: &SqlitePool) -> DataResult<()> {
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS lanes (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            a INTEGER REFERENCES systems (id) ON DELETE CASCADE,
+            b INTEGER REFERENCES systems (id) ON DELETE CASCADE,
+            ltype TEXT DEFAULT 'Major',
+            defended INTEGER DEFAULT 0)",
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn create_ledger_table(pool: &SqlitePool) -> DataResult<()> {
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS ledger (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            empire INTEGER REFERENCES empires (id) ON DELETE CASCADE,
+            turn INTEGER,
+            amount INTEGER,
+            tech INTEGER,
+            note TEXT)",
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn create_log_table(pool: &SqlitePool) -> DataResult<()> {
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS log (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            turn INTEGER,
+            stamp TEXT,
+            category TEXT,
+            text TEXT)",
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn create_morale_modifiers_table(pool: &SqlitePool) -> DataResult<()> {
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS morale_modifiers (
+            empire INTEGER PRIMARY KEY REFERENCES empires (id) ON DELETE CASCADE,
+            amount INTEGER)",
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn create_order_templates_table(pool: &SqlitePool) -> DataResult<()> {
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS order_templates (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            empire INTEGER REFERENCES empires (id) ON DELETE CASCADE,
+            name TEXT,
+            text TEXT)",
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn create_orders_table(pool: &SqlitePool) -> DataResult<()> {
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS orders (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            empire INTEGER REFERENCES empires (id) ON DELETE CASCADE,
+            turn INTEGER,
+            text TEXT,
+            phase TEXT DEFAULT '',
+            cost INTEGER DEFAULT 0)",
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn create_planet_types_table(pool: &SqlitePool) -> DataResult<()> {
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS planet_types (
+            name TEXT PRIMARY KEY,
+            description TEXT,
+            max_cap INTEGER,
+            mor_mod INTEGER)",
+        )
+        .execute(pool)
+        .await?;
+
+        let mut tx = pool.begin().await?;
+        Self::insert_planet_types(&mut tx, &setting::standard().planet_types).await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn create_raids_table(pool: &SqlitePool) -> DataResult<()> {
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS raids (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            empire INTEGER REFERENCES empires (id) ON DELETE CASCADE,
+            turn INTEGER,
+            system INTEGER REFERENCES systems (id) ON DELETE CASCADE,
+            target TEXT)",
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn create_research_table(pool: &SqlitePool) -> DataResult<()> {
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS research (
+            empire INTEGER REFERENCES empires (id) ON DELETE CASCADE,
+            turn INTEGER,
+            amount INTEGER,
+            PRIMARY KEY (empire, turn))",
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn create_scorch_orders_table(pool: &SqlitePool) -> DataResult<()> {
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS scorch_orders (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            empire INTEGER REFERENCES empires (id) ON DELETE CASCADE,
+            turn INTEGER,
+            system INTEGER REFERENCES systems (id) ON DELETE CASCADE,
+            target TEXT,
+            UNIQUE (system, turn, target))",
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn create_service_table(pool: &SqlitePool) -> DataResult<()> {
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS service (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            turn INTEGER,
+            stype INTEGER REFERENCES ship_types (id) ON DELETE CASCADE,
+            built INTEGER DEFAULT 0,
+            fought INTEGER DEFAULT 0,
+            lost INTEGER DEFAULT 0,
+            kills REAL DEFAULT 0)",
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn create_ship_types_table(pool: &SqlitePool) -> DataResult<()> {
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS ship_types (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            class TEXT,
+            hull TEXT,
+            cost INTEGER,
+            cr INTEGER,
+            atk INTEGER,
+            def INTEGER,
+            cap INTEGER DEFAULT 0,
+            speed INTEGER DEFAULT 1,
+            empire INTEGER REFERENCES empires (id) ON DELETE CASCADE,
+            obsolete INTEGER,
+            retired INTEGER DEFAULT 0)",
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn create_ships_table(pool: &SqlitePool) -> DataResult<()> {
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS ships (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            stype INTEGER REFERENCES ship_types (id) ON DELETE RESTRICT,
+            fleet INTEGER REFERENCES fleets (id) ON DELETE CASCADE,
+            crip INTEGER DEFAULT 0,
+            moth INTEGER DEFAULT 0,
+            repair_priority INTEGER DEFAULT 0)",
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn create_systems_table(pool: &SqlitePool) -> DataResult<()> {
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS systems (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT UNIQUE,
+            ptype TEXT,
+            raw INTEGER,
+            cap INTEGER,
+            pop INTEGER,
+            mor INTEGER,
+            ind INTEGER,
+            dev INTEGER DEFAULT 0,
+            fails INTEGER DEFAULT 0,
+            blockaded INTEGER DEFAULT 0,
+            x INTEGER DEFAULT 0,
+            y INTEGER DEFAULT 0,
+            minor TEXT DEFAULT '',
+            contender INTEGER DEFAULT 0,
+            share INTEGER DEFAULT 100,
+            policy TEXT DEFAULT '',
+            owner INTEGER REFERENCES empires (id) ON DELETE SET NULL)",
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn create_tables(pool: &SqlitePool) -> DataResult<()> {
+        Self::create_battles_table(pool).await?;
+        Self::create_casualties_table(pool).await?;
+        Self::create_combat_modifiers_table(pool).await?;
+        Self::create_conquests_table(pool).await?;
+        Self::create_construction_table(pool).await?;
+        Self::create_controls_table(pool).await?;
+        Self::create_courses_table(pool).await?;
+        Self::create_deadlines_table(pool).await?;
+        Self::create_developments_table(pool).await?;
+        Self::create_empires_table(pool).await?;
+        Self::create_escorts_table(pool).await?;
+        Self::create_espionage_table(pool).await?;
+        Self::create_facilities_table(pool).await?;
+        Self::create_fleets_table(pool).await?;
+        Self::create_flights_table(pool).await?;
+        Self::create_ground_types_table(pool).await?;
+        Self::create_ground_units_table(pool).await?;
+        Self::create_hulls_table(pool).await?;
+        Self::create_identifications_table(pool).await?;
+        Self::create_intel_table(pool).await?;
+        Self::create_journal_table(pool).await?;
+        Self::create_lanes_table(pool).await?;
+        Self::create_ledger_table(pool).await?;
+        Self::create_log_table(pool).await?;
+        Self::create_morale_modifiers_table(pool).await?;
+        Self::create_order_templates_table(pool).await?;
+        Self::create_orders_table(pool).await?;
+        Self::create_planet_types_table(pool).await?;
+        Self::create_raids_table(pool).await?;
+        Self::create_research_table(pool).await?;
+        Self::create_scorch_orders_table(pool).await?;
+        Self::create_service_table(pool).await?;
+        Self::create_ship_types_table(pool).await?;
+        Self::create_ships_table(pool).await?;
+        Self::create_systems_table(pool).await?;
+        Self::create_tech_progress_table(pool).await?;
+        Self::create_transfers_table(pool).await?;
+        Self::create_treaties_table(pool).await?;
+        Self::create_wars_table(pool).await?;
+        Self::create_indexes(pool).await
+    }
+
+    async fn create_tech_progress_table(pool: &SqlitePool) -> DataResult<()> {
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS tech_progress (
+            empire INTEGER PRIMARY KEY REFERENCES empires (id) ON DELETE CASCADE,
+            points INTEGER)",
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn create_transfers_table(pool: &SqlitePool) -> DataResult<()> {
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS transfers (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            turn INTEGER,
+            seller INTEGER REFERENCES empires (id) ON DELETE CASCADE,
+            buyer INTEGER REFERENCES empires (id) ON DELETE CASCADE,
+            ship INTEGER REFERENCES ships (id) ON DELETE CASCADE,
+            stype INTEGER REFERENCES ship_types (id) ON DELETE CASCADE,
+            price INTEGER DEFAULT 0)",
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn create_treaties_table(pool: &SqlitePool) -> DataResult<()> {
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS treaties (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            a INTEGER REFERENCES empires (id) ON DELETE CASCADE,
+            b INTEGER REFERENCES empires (id) ON DELETE CASCADE,
+            ttype TEXT,
+            signed INTEGER,
+            expires INTEGER)",
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn create_wars_table(pool: &SqlitePool) -> DataResult<()> {
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS wars (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            a INTEGER REFERENCES empires (id) ON DELETE CASCADE,
+            b INTEGER REFERENCES empires (id) ON DELETE CASCADE,
+            started INTEGER,
+            ended INTEGER)",
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    fn folder() -> DataResult<path::PathBuf> {
+        // Put databases where the preferences say, by default the user's
+        // data directory under the program name.
+        let dbpath = Config::load().data_dir();
+
+        // Create folder if it doesn't exist.
+        if !dbpath.exists() {
+            fs::create_dir_all(&dbpath)?
+        }
+
+        Ok(dbpath)
+    }
+
+    /// Whether the database has a table of the given name.
+    async fn has_table(pool: &SqlitePool, name: &str) -> DataResult<bool> {
+        let n: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = ?",
+        )
+        .bind(name)
+        .fetch_one(pool)
+        .await?;
+        Ok(n > 0)
+    }
+
+    // Insert ground unit types as part of a transaction.
+    async fn insert_ground_types(
+        tx: &mut Transaction<'_, Sqlite>,
+        types: &[GroundType],
+    ) -> DataResult<()> {
+        for g in types {
+            sqlx::query(
+                "INSERT INTO ground_types (name, abbr, cost, atk, def) VALUES(?, ?, ?, ?, ?)",
+            )
+            .bind(g.name.as_str())
+            .bind(g.abbr.as_str())
+            .bind(g.cost)
+            .bind(g.atk)
+            .bind(g.def)
+            .execute(&mut *tx)
+            .await?;
+        }
+        Ok(())
+    }
+
+    // Insert hull classes as part of a transaction.
+    async fn insert_hulls(tx: &mut Transaction<'_, Sqlite>, hulls: &[Hull]) -> DataResult<()> {
+        for h in hulls {
+            sqlx::query("INSERT INTO hulls (name, size, maint, tech) VALUES(?, ?, ?, ?)")
+                .bind(h.name.as_str())
+                .bind(h.size)
+                .bind(h.maint)
+                .bind(h.tech)
+                .execute(&mut *tx)
+                .await?;
+        }
+        Ok(())
+    }
+
+    // Insert planet types as part of a transaction.
+    async fn insert_planet_types(
+        tx: &mut Transaction<'_, Sqlite>,
+        types: &[PlanetType],
+    ) -> DataResult<()> {
+        for t in types {
+            sqlx::query(
+                "INSERT INTO planet_types (name, description, max_cap, mor_mod)
+                VALUES(?, ?, ?, ?)",
+            )
+            .bind(t.name.as_str())
+            .bind(t.description.as_str())
+            .bind(t.max_cap)
+            .bind(t.mor_mod)
+            .execute(&mut *tx)
+            .await?;
+        }
+        Ok(())
+    }
+
+    // Insert systems in batches as part of a transaction.
+    async fn insert_systems(
+        tx: &mut Transaction<'_, Sqlite>,
+        systems: &[System],
+    ) -> DataResult<()> {
+        for chunk in systems.chunks(INSERT_BATCH) {
+            let mut qb: QueryBuilder<Sqlite> = QueryBuilder::new(
+                "INSERT INTO systems (name, ptype, raw, cap, pop, mor, ind, x, y) ",
+            );
+            qb.push_values(chunk, |mut b, sys| {
+                b.push_bind(sys.name.as_str())
+                    .push_bind(sys.ptype.as_str())
+                    .push_bind(sys.raw)
+                    .push_bind(sys.cap)
+                    .push_bind(sys.pop)
+                    .push_bind(sys.mor)
+                    .push_bind(sys.ind)
+                    .push_bind(sys.x)
+                    .push_bind(sys.y);
+            });
+            qb.build().execute(&mut *tx).await?;
+        }
+        Ok(())
+    }
+
+    /// Bring an older database up to the current schema. Each step only adds
+    /// what is missing, so a step cut short is finished the next time the
+    /// database is opened.
+    async fn migrate(pool: &SqlitePool) -> DataResult<()> {
+        let version = Self::schema_version(pool).await?;
+        if version > SCHEMA_VERSION {
+            return Err(DataError::Schema(format!(
+                "Campaign was saved by a newer version of the program (schema {}, expected {})",
+                version, SCHEMA_VERSION
+            )));
+        }
+        if version == SCHEMA_VERSION {
+            return Ok(());
+        }
+
+        if version < 1 {
+            Self::migrate_unversioned(pool).await?;
+        }
+        if version < 2 {
+            Self::create_raids_table(pool).await?;
+        }
+        if version < 3 {
+            Self::add_column(pool, "ship_types", "obsolete", "INTEGER").await?;
+            Self::add_column(pool, "ship_types", "retired", "INTEGER DEFAULT 0").await?;
+        }
+        if version < 4 {
+            Self::add_column(pool, "systems", "x", "INTEGER DEFAULT 0").await?;
+            Self::add_column(pool, "systems", "y", "INTEGER DEFAULT 0").await?;
+            // Lay out the existing systems on the default grid, in ID order.
+            // Those already placed keep their place if the step is repeated.
+            sqlx::query(
+                "UPDATE systems SET
+                x = ((SELECT COUNT(*) FROM systems s WHERE s.id < systems.id) % ?) * ?,
+                y = ((SELECT COUNT(*) FROM systems s WHERE s.id < systems.id) / ?) * ?
+                WHERE x = 0 AND y = 0",
+            )
+            .bind(map::GRID_COLUMNS)
+            .bind(map::GRID_SPACING)
+            .bind(map::GRID_COLUMNS)
+            .bind(map::GRID_SPACING)
+            .execute(pool)
+            .await?;
+        }
+        if version < 5 {
+            Self::create_journal_table(pool).await?;
+        }
+        if version < 6 {
+            Self::add_column(pool, "orders", "phase", "TEXT DEFAULT ''").await?;
+            Self::add_column(pool, "orders", "cost", "INTEGER DEFAULT 0").await?;
+        }
+        if version < 7 {
+            Self::add_column(pool, "ships", "repair_priority", "INTEGER DEFAULT 0").await?;
+        }
+        if version < 8 {
+            Self::create_scorch_orders_table(pool).await?;
+        }
+        if version < 9 {
+            Self::add_column(pool, "systems", "minor", "TEXT DEFAULT ''").await?;
+            Self::add_column(pool, "systems", "contender", "INTEGER DEFAULT 0").await?;
+            Self::add_column(pool, "systems", "share", "INTEGER DEFAULT 100").await?;
+        }
+        if version < 10 {
+            Self::create_identifications_table(pool).await?;
+        }
+        if version < 11 {
+            Self::create_developments_table(pool).await?;
+        }
+        if version < 12 {
+            Self::create_espionage_table(pool).await?;
+        }
+        if version < 13 {
+            Self::create_escorts_table(pool).await?;
+        }
+        if version < 14 {
+            Self::create_battles_table(pool).await?;
+        }
+        if version < 15 {
+            Self::add_column(pool, "empires", "government", "TEXT DEFAULT 'Monarchy'").await?;
+            Self::add_column(pool, "empires", "traits", "TEXT DEFAULT ''").await?;
+        }
+        if version < 16 {
+            Self::create_flights_table(pool).await?;
+        }
+        if version < 17 {
+            Self::create_facilities_table(pool).await?;
+        }
+        if version < 18 {
+            Self::create_log_table(pool).await?;
+        }
+        if version < 20 {
+            Self::create_wars_table(pool).await?;
+            Self::create_casualties_table(pool).await?;
+        }
+        // Versions 19 and 21 changed constraints, which means rebuilding the
+        // tables. A rebuild gives every table the current schema, so one
+        // does for both.
+        if version < 21 {
+            Self::rename_duplicates(pool).await?;
+            Self::rebuild_tables(pool).await?;
+        }
+        if version < 22 {
+            Self::create_transfers_table(pool).await?;
+        }
+        if version < 23 {
+            // Hulls without a tech level need the standard one for their size.
+            Self::add_column(pool, "hulls", "tech", "INTEGER").await?;
+        }
+        if version < 24 {
+            Self::add_column(pool, "systems", "policy", "TEXT DEFAULT ''").await?;
+        }
+        if version < 25 {
+            Self::add_column(pool, "lanes", "defended", "INTEGER DEFAULT 0").await?;
+        }
+        if version < 26 {
+            Self::create_service_table(pool).await?;
+        }
+        if version < 27 {
+            Self::create_courses_table(pool).await?;
+        }
+
+        sqlx::query(
+            "INSERT INTO control (key, value) VALUES(?, ?)
+            ON CONFLICT (key) DO UPDATE SET value = excluded.value",
+        )
+        .bind(SCHEMA_KEY)
+        .bind(SCHEMA_VERSION.to_string())
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Upgrade a database written before schema versions were kept. It has
+    /// at least the tables of the first release, and may have any of those
+    /// added since.
+    async fn migrate_unversioned(pool: &SqlitePool) -> DataResult<()> {
+        // Reference tables get their defaults only when they are created.
+        if !Self::has_table(pool, "combat_modifiers").await? {
+            Self::create_combat_modifiers_table(pool).await?;
+        }
+        if !Self::has_table(pool, "hulls").await? {
+            Self::create_hulls_table(pool).await?;
+        }
+        if !Self::has_table(pool, "planet_types").await? {
+            Self::create_planet_types_table(pool).await?;
+        }
+        Self::create_conquests_table(pool).await?;
+        Self::create_construction_table(pool).await?;
+        Self::create_deadlines_table(pool).await?;
+        Self::create_intel_table(pool).await?;
+        Self::create_lanes_table(pool).await?;
+        Self::create_ledger_table(pool).await?;
+        Self::create_morale_modifiers_table(pool).await?;
+        Self::create_order_templates_table(pool).await?;
+        Self::create_orders_table(pool).await?;
+        Self::create_research_table(pool).await?;
+        Self::create_tech_progress_table(pool).await?;
+        Self::create_treaties_table(pool).await?;
+
+        Self::add_column(pool, "construction", "count", "INTEGER DEFAULT 1").await?;
+        Self::add_column(pool, "fleets", "speed", "INTEGER DEFAULT 0").await?;
+        Self::add_column(pool, "ship_types", "speed", "INTEGER DEFAULT 1").await?;
+        Self::add_column(pool, "systems", "blockaded", "INTEGER DEFAULT 0").await?;
+
+        Self::create_indexes(pool).await
+    }
+
+    // The name of a row of a table with a name column.
+    async fn name_of(conn: &mut SqliteConnection, table: &str, id: i64) -> DataResult<String> {
+        let name: Option<String> =
+            sqlx::query_scalar(format!("SELECT name FROM {} WHERE id = ?", table).as_str())
+                .bind(id)
+                .fetch_optional(&mut *conn)
+                .await?;
+        Ok(name.unwrap_or_default())
+    }
+
+    // Rebuild every table in the current schema, keeping its rows, for
+    // changes SQLite cannot make to a table in place, such as to what its
+    // foreign keys do on delete.
+    async fn rebuild_tables(pool: &SqlitePool) -> DataResult<()> {
+        let fresh = Self::connect("sqlite::memory:").await?;
+        Self::create_tables(&fresh).await?;
+        let schema: Vec<(String, String)> = sqlx::query_as(
+            "SELECT name, sql FROM sqlite_master
+            WHERE type = 'table' AND name NOT LIKE 'sqlite_%'",
+        )
+        .fetch_all(&fresh)
+        .await?;
+        fresh.close().await;
+
+        // As on import, foreign keys are off while the tables are swapped.
+        let mut conn = pool.acquire().await?;
+        sqlx::query("PRAGMA foreign_keys = OFF")
+            .execute(&mut conn)
+            .await?;
+        let result = Self::swap_tables(&mut conn, &schema).await;
+        sqlx::query("PRAGMA foreign_keys = ON")
+            .execute(&mut conn)
+            .await?;
+        result?;
+        Self::create_indexes(pool).await
+    }
+
+    // Rename every empire or system named the same as one added before it,
+    // adding its ID, so that names can be made unique.
+    async fn rename_duplicates(pool: &SqlitePool) -> DataResult<()> {
+        for table in ["empires", "systems"] {
+            sqlx::query(
+                format!(
+                    "UPDATE {0} SET name = name || ' (' || id || ')'
+                    WHERE id NOT IN (SELECT MIN(id) FROM {0} GROUP BY name)",
+                    table
+                )
+                .as_str(),
+            )
+            .execute(pool)
+            .await?;
+        }
+        Ok(())
+    }
+
+    // Replace a fleet's course with another, in a transaction.
+    async fn replace_course(
+        tx: &mut Transaction<'_, Sqlite>,
+        fleet: i64,
+        course: &[i64],
+    ) -> DataResult<()> {
+        sqlx::query("DELETE FROM courses WHERE fleet = ?")
+            .bind(fleet)
+            .execute(&mut *tx)
+            .await?;
+        for (step, system) in course.iter().enumerate() {
+            sqlx::query("INSERT INTO courses (fleet, step, system) VALUES(?, ?, ?)")
+                .bind(fleet)
+                .bind(step as i64)
+                .bind(system)
+                .execute(&mut *tx)
+                .await?;
+        }
+        Ok(())
+    }
+
+    // Replace the rows of tables with those of an export, in a transaction
+    // on a connection with foreign keys turned off, refusing the export if
+    // any of its rows refer to rows that are missing.
+    async fn replace_tables(
+        conn: &mut SqliteConnection,
+        tables: &Map<String, Value>,
+        known: &[String],
+    ) -> DataResult<()> {
+        let mut tx = conn.begin().await?;
+        for (name, table) in tables {
+            if !known.contains(name) {
+                return Err(DataError::Archive(format!("Unknown table {}", name)));
+            }
+            let columns: Vec<&str> = match table.get("columns").and_then(|c| c.as_array()) {
+                Some(v) => v.iter().filter_map(|c| c.as_str()).collect(),
+                None => return Err(DataError::Archive(format!("No columns for {}", name))),
+            };
+            let rows = match table.get("rows").and_then(|r| r.as_array()) {
+                Some(v) => v,
+                None => return Err(DataError::Archive(format!("No rows for {}", name))),
+            };
+
+            sqlx::query(format!("DELETE FROM \"{}\"", name).as_str())
+                .execute(&mut tx)
+                .await?;
+            let sql = format!(
+                "INSERT INTO \"{}\" ({}) VALUES({})",
+                name,
+                columns
+                    .iter()
+                    .map(|c| format!("\"{}\"", c))
+                    .collect::<Vec<String>>()
+                    .join(", "),
+                vec!["?"; columns.len()].join(", ")
+            );
+            for row in rows {
+                let values = match row.as_array() {
+                    Some(v) if v.len() == columns.len() => v,
+                    _ => return Err(DataError::Archive(format!("Bad row in {}", name))),
+                };
+                let mut q = sqlx::query(sql.as_str());
+                for v in values {
+                    q = match v {
+                        Value::Null => q.bind(Option::<i64>::None),
+                        Value::Bool(b) => q.bind(*b),
+                        Value::Number(n) if n.is_i64() => q.bind(n.as_i64()),
+                        Value::Number(n) => q.bind(n.as_f64()),
+                        Value::String(t) => q.bind(t.as_str()),
+                        _ => return Err(DataError::Archive(format!("Bad value in {}", name))),
+                    };
+                }
+                q.execute(&mut tx).await?;
+            }
+        }
+        // The tables are in this program's schema, whichever version the
+        // archive came from.
+        sqlx::query(
+            "INSERT INTO control (key, value) VALUES(?, ?)
+            ON CONFLICT (key) DO UPDATE SET value = excluded.value",
+        )
+        .bind(SCHEMA_KEY)
+        .bind(SCHEMA_VERSION.to_string())
+        .execute(&mut tx)
+        .await?;
+        if let Some(table) = Self::broken_reference(&mut tx).await? {
+            return Err(DataError::Archive(format!(
+                "Rows of {} refer to rows that are missing",
+                table
+            )));
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Schema version of a database, which is 0 for one written before
+    /// versions were kept.
+    async fn schema_version(pool: &SqlitePool) -> DataResult<i32> {
+        let v: Option<String> = sqlx::query_scalar("SELECT value FROM control WHERE key = ?")
+            .bind(SCHEMA_KEY)
+            .fetch_optional(pool)
+            .await?;
+        match v {
+            Some(v) => Ok(v.parse()?),
+            None => Ok(0),
+        }
+    }
+
+    /// Turn and empire count of a campaign's database.
+    async fn summarize(pool: &SqlitePool) -> DataResult<(Option<i32>, Option<i64>)> {
+        let turn: Option<i32> =
+            sqlx::query_scalar("SELECT CAST(value AS INTEGER) FROM control WHERE key = 'turn'")
+                .fetch_optional(pool)
+                .await?;
+        let empires: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM empires")
+            .fetch_one(pool)
+            .await?;
+        Ok((turn, Some(empires)))
+    }
+
+    // Swap each table for a new one made from its CREATE statement, copying
+    // the rows across, all in one transaction so that a database is never
+    // left half rebuilt.
+    async fn swap_tables(
+        conn: &mut SqliteConnection,
+        schema: &[(String, String)],
+    ) -> DataResult<()> {
+        let mut tx = conn.begin().await?;
+        for (name, sql) in schema {
+            let old: Vec<String> = sqlx::query_scalar("SELECT name FROM pragma_table_info(?)")
+                .bind(name.as_str())
+                .fetch_all(&mut tx)
+                .await?;
+            let new = format!("{}_new", name);
+            let columns = match sql.find('(') {
+                Some(i) => &sql[i..],
+                None => return Err(DataError::Schema(format!("Bad definition of {}", name))),
+            };
+            sqlx::query(format!("CREATE TABLE \"{}\" {}", new, columns).as_str())
+                .execute(&mut tx)
+                .await?;
+            // A table new in this schema has no rows to copy.
+            if !old.is_empty() {
+                let kept: Vec<String> =
+                    sqlx::query_scalar("SELECT name FROM pragma_table_info(?) ORDER BY cid")
+                        .bind(new.as_str())
+                        .fetch_all(&mut tx)
+                        .await?
+                        .into_iter()
+                        .filter(|c| old.contains(c))
+                        .map(|c| format!("\"{}\"", c))
+                        .collect();
+                let kept = kept.join(", ");
+                sqlx::query(
+                    format!(
+                        "INSERT INTO \"{}\" ({}) SELECT {} FROM \"{}\"",
+                        new, kept, kept, name
+                    )
+                    .as_str(),
+                )
+                .execute(&mut tx)
+                .await?;
+                sqlx::query(format!("DROP TABLE \"{}\"", name).as_str())
+                    .execute(&mut tx)
+                    .await?;
+            }
+            sqlx::query(format!("ALTER TABLE \"{}\" RENAME TO \"{}\"", new, name).as_str())
+                .execute(&mut tx)
+                .await?;
+        }
+        if let Some(table) = Self::broken_reference(&mut tx).await? {
+            return Err(DataError::Schema(format!(
+                "Rows of {} refer to rows that are missing",
+                table
+            )));
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    // The ID of the war being fought between two empires, starting one in
+    // the given turn if they are not at war.
+    async fn war_between(
+        tx: &mut Transaction<'_, Sqlite>,
+        a: i64,
+        b: i64,
+        turn: i32,
+    ) -> DataResult<i64> {
+        let (a, b) = (a.min(b), a.max(b));
+        let id: Option<i64> =
+            sqlx::query_scalar("SELECT id FROM wars WHERE a = ? AND b = ? AND ended IS NULL")
+                .bind(a)
+                .bind(b)
+                .fetch_optional(&mut *tx)
+                .await?;
+        if let Some(id) = id {
+            return Ok(id);
+        }
+        let r = sqlx::query("INSERT INTO wars (a, b, started) VALUES(?, ?, ?)")
+            .bind(a)
+            .bind(b)
+            .bind(turn)
+            .execute(&mut *tx)
+            .await?;
+        Ok(r.last_insert_rowid())
+    }
+
+    // A store using the given connection pool, with no unit of work under way.
+    fn with_pool(pool: SqlitePool, url: String) -> Self {
+        Self {
+            pool,
+            url,
+            work: Arc::new(Mutex::new(None)),
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::{DataError, DataStore, INSERT_BATCH, SCHEMA_KEY, SCHEMA_VERSION};
+    use crate::campaign::audit::{self, LogEntry};
+    use crate::campaign::batch::Change;
+    use crate::campaign::checklist;
+    use crate::campaign::combat::CombatModifier;
+    use crate::campaign::construction::{self, Build, NEW_CONSTRUCTION};
+    use crate::campaign::development::{self, Development};
+    use crate::campaign::diplomacy::Treaty;
+    use crate::campaign::empire::tests::empires;
+    use crate::campaign::empire::{Empire, LedgerEntry};
+    use crate::campaign::escort::Escort;
+    use crate::campaign::espionage::{self, Operation};
+    use crate::campaign::facility::{self, Facility};
+    use crate::campaign::flight;
+    use crate::campaign::government;
+    use crate::campaign::intel::IntelItem;
+    use crate::campaign::journal::JournalEntry;
+    use crate::campaign::lane::Lane;
+    use crate::campaign::map;
+    use crate::campaign::movement::Transit;
+    use crate::campaign::options::CampaignOptions;
+    use crate::campaign::orders::{self, Order, OrderTemplate};
+    use crate::campaign::raid::{Raid, STRATEGIC_WARFARE};
+    use crate::campaign::recognition::{Identification, BATTLE, ESPIONAGE, TRANSFER};
+    use crate::campaign::scorch::{self, ScorchOrder};
+    use crate::campaign::service::Service;
+    use crate::campaign::setting;
+    use crate::campaign::system::tests::systems;
+    use crate::campaign::system::{BulkEdit, System};
+    use crate::campaign::transfer::Transfer;
+    use crate::campaign::unit::{Fleet, Flight, GroundUnit, ShipStatus, ShipType};
+    use crate::campaign::war::{self, Casualty};
+    use crate::campaign::workbook::tests::workbook;
+
+    pub async fn init_data() -> DataStore {
+        let url = "sqlite::memory:".to_string();
+        let pool = DataStore::connect(url.as_str()).await.unwrap();
+        DataStore::create_tables(&pool).await.unwrap();
+        DataStore::with_pool(pool, url)
+    }
+
+    // An empire's treasury.
+    async fn treasury(instance: &DataStore, empire: i64) -> i32 {
+        let v = instance.get_empires().await.unwrap();
+        v.iter().find(|e| e.id == empire).unwrap().treasury
+    }
+
+    #[tokio::test]
+    async fn add_systems() {
+        let instance = init_data().await;
+        instance.add_systems(systems()).await.unwrap();
+        for exp in systems() {
+            let act = instance
+                .get_system_by_name(exp.name.as_str())
+                .await
+                .unwrap();
+            assert_eq!(exp.name, act.name);
+            assert_eq!(exp.ptype, act.ptype);
+            assert_eq!(exp.raw, act.raw);
+            assert_eq!(exp.cap, act.cap);
+            assert_eq!(exp.pop, act.pop);
+            assert_eq!(exp.mor, act.mor);
+            assert_eq!(exp.ind, act.ind);
+            assert_eq!(exp.dev, act.dev);
+            assert_eq!(exp.fails, act.fails);
+        }
+    }
+
+    #[tokio::test]
+    async fn add_systems_batch() {
+        let instance = init_data().await;
+        let many: Vec<System> = (0..INSERT_BATCH * 5 + 3)
+            .map(|i| {
+                let mut sys = systems()[0].clone();
+                sys.name = format!("System {}", i);
+                sys
+            })
+            .collect();
+        instance.add_systems(many.clone()).await.unwrap();
+        assert_eq!(many.len(), instance.get_systems().await.unwrap().len());
+        let last = instance
+            .get_system_by_name(many.last().unwrap().name.as_str())
+            .await
+            .unwrap();
+        assert_eq!(many[0].ind, last.ind);
+
+        // A failure part way through adds none of them.
+        sqlx::query(
+            "CREATE TRIGGER reject_bad BEFORE INSERT ON systems WHEN NEW.name = 'Bad'
+            BEGIN SELECT RAISE(ABORT, 'bad system'); END",
+        )
+        .execute(&instance.pool)
+        .await
+        .unwrap();
+        let mut more: Vec<System> = many
+            .iter()
+            .map(|s| System {
+                name: format!("More {}", s.name),
+                ..s.clone()
+            })
+            .collect();
+        more[INSERT_BATCH * 2].name = "Bad".to_string();
+        assert!(instance.add_systems(more).await.is_err());
+        assert_eq!(many.len(), instance.get_systems().await.unwrap().len());
+    }
+
+    #[tokio::test]
+    async fn bulk_update_systems() {
+        let instance = init_data().await;
+        instance.add_empires(empires()).await.unwrap();
+        instance.add_systems(systems()).await.unwrap();
+        let edit = BulkEdit {
+            owner: Some(2),
+            morale: -1,
+            blockaded: Some(true),
+        };
+        let v = instance.bulk_update_systems(&[1, 2], &edit).await.unwrap();
+        assert_eq!(2, v.len());
+        for (n, exp) in systems().iter().take(2).enumerate() {
+            let sys = instance.get_system_by_id(n as i64 + 1).await.unwrap();
+            assert_eq!((2, exp.mor - 1, true), (sys.owner, sys.mor, sys.blockaded));
+        }
+        assert_eq!(0, instance.get_system_by_id(3).await.unwrap().owner);
+
+        let edit = BulkEdit {
+            owner: Some(0),
+            ..Default::default()
+        };
+        instance.bulk_update_systems(&[1], &edit).await.unwrap();
+        let sys = instance.get_system_by_id(1).await.unwrap();
+        assert_eq!((0, true), (sys.owner, sys.blockaded));
+
+        // A system that is not there changes none of them.
+        let edit = BulkEdit {
+            morale: -5,
+            ..Default::default()
+        };
+        assert!(instance.bulk_update_systems(&[2, 99], &edit).await.is_err());
+        assert_eq!(
+            systems()[1].mor - 1,
+            instance.get_system_by_id(2).await.unwrap().mor
+        );
+    }
+
+    #[tokio::test]
+    async fn adjust_treasury() {
+        let instance = init_data().await;
+        instance.add_empires(empires()).await.unwrap();
+        instance.adjust_treasury(1, 10).await.unwrap();
+        instance.adjust_treasury(1, -3).await.unwrap();
+        let e = instance.get_empires().await.unwrap();
+        assert_eq!(7, e.iter().find(|e| e.id == 1).unwrap().treasury);
+
+        instance
+            .adjust_treasuries(&[(1, 5), (2, -4)])
+            .await
+            .unwrap();
+        let e = instance.get_empires().await.unwrap();
+        assert_eq!(12, e.iter().find(|e| e.id == 1).unwrap().treasury);
+        assert_eq!(-4, e.iter().find(|e| e.id == 2).unwrap().treasury);
+    }
+
+    #[tokio::test]
+    async fn apply_batch() {
+        let instance = init_data().await;
+        let mut home = System::new("Senor Prime", "HW", 5, 12, 10, 8, 10);
+        home.owner = -1;
+        let ids = instance
+            .apply_batch(&[
+                Change::AddEmpire(Empire::new("Senorian")),
+                Change::AddSystem(home),
+                Change::AddFleet(Fleet::new("Home Fleet", -1, -2)),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(3, ids.len());
+        let home = instance.get_system_by_id(ids[1]).await.unwrap();
+        assert_eq!(ids[0], home.owner);
+        assert_eq!(home.id, instance.get_fleet(ids[2]).await.unwrap().location);
+
+        // A failure undoes the changes before it.
+        let err = instance
+            .apply_batch(&[
+                Change::AddEmpire(Empire::new("Human")),
+                Change::DeleteSystem(home.id),
+            ])
+            .await
+            .unwrap_err();
+        assert_eq!(
+            "Change 2: Senor Prime still has fleets or flights there; move them first",
+            err.to_string()
+        );
+        assert_eq!(1, instance.get_empires().await.unwrap().len());
+        let err = instance
+            .apply_batch(&[Change::DeleteLane(-1)])
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("not yet made"));
+    }
+
+    #[tokio::test]
+    async fn create_indexes() {
+        let instance = init_data().await;
+        let n: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM sqlite_master
+            WHERE type = 'index' AND name IN
+            ('systems_owner', 'ships_fleet', 'fleets_owner_location', 'ground_units_loc')",
+        )
+        .fetch_one(&instance.pool)
+        .await
+        .unwrap();
+        assert_eq!(4, n);
+    }
+
+    #[tokio::test]
+    async fn construction() {
+        let instance = init_data().await;
+        instance.add_empires(empires()).await.unwrap();
+        instance.add_systems(systems()).await.unwrap();
+        sqlx::query(
+            "INSERT INTO ship_types (class, hull, cost, cr, atk, def, empire)
+            VALUES('Avenger', 'Cruiser', 9, 2, 4, 3, 1)",
+        )
+        .execute(&instance.pool)
+        .await
+        .unwrap();
+
+        let mut ship = Build::new(1, 1, "Ship", 1, "Avenger");
+        ship.cost = 9;
+        ship.turns = 2;
+        ship.id = instance.insert_build(&ship).await.unwrap();
+        let mut troops = Build::new(1, 1, "Ground", 1, "Militia");
+        troops.cost = 2;
+        troops.id = instance.insert_build(&troops).await.unwrap();
+        let treasury = |v: Vec<Empire>| v.into_iter().find(|e| e.id == 1).unwrap().treasury;
+        assert_eq!(-11, treasury(instance.get_empires().await.unwrap()));
+
+        let builds = instance.get_builds().await.unwrap();
+        assert_eq!(2, builds.len());
+        assert_eq!("Senor Prime", builds[0].system_name);
+
+        ship.turns = 1;
+        instance.update_build(&ship).await.unwrap();
+        instance.finish_build(&ship).await.unwrap();
+        // A second ship joins the same fleet.
+        instance.finish_build(&ship).await.unwrap();
+        let fleets = instance.get_fleet_summaries().await.unwrap();
+        assert_eq!(1, fleets.len());
+        assert_eq!(NEW_CONSTRUCTION, fleets[0].name);
+        assert_eq!(2, fleets[0].ships);
+        // A batch adds all its ships at once.
+        let mut batch = Build::new(1, 1, "Ship", 1, "Avenger");
+        batch.count = 3;
+        batch.cost = 27;
+        batch.id = instance.insert_build(&batch).await.unwrap();
+        instance.finish_build(&batch).await.unwrap();
+        let fleets = instance.get_fleet_summaries().await.unwrap();
+        assert_eq!(5, fleets[0].ships);
+
+        instance.cancel_build(&troops).await.unwrap();
+        assert!(instance.get_builds().await.unwrap().is_empty());
+        assert_eq!(-36, treasury(instance.get_empires().await.unwrap()));
+    }
+
+    #[tokio::test]
+    async fn invasion() {
+        let instance = init_data().await;
+        instance.add_empires(empires()).await.unwrap();
+        instance.add_systems(systems()).await.unwrap();
+        let militia = instance.insert_ground_unit(1, 1).await.unwrap();
+        let marines = instance.insert_ground_unit(6, 2).await.unwrap();
+        let armor = instance.insert_ground_unit(4, 2).await.unwrap();
+
+        // A failed assault only loses units.
+        instance
+            .apply_invasion(1, None, &[armor], &[marines])
+            .await
+            .unwrap();
+        assert_eq!(1, instance.get_ground_units(2).await.unwrap().len());
+        assert_eq!(0, instance.get_system_by_id(1).await.unwrap().owner);
+
+        instance
+            .apply_invasion(1, Some(2), &[militia], &[marines])
+            .await
+            .unwrap();
+        let sys = instance.get_system_by_id(1).await.unwrap();
+        assert_eq!(2, sys.owner);
+        assert!(!sys.blockaded);
+        assert_eq!(vec![1], instance.get_conquered_since(0).await.unwrap());
+        assert!(instance.get_conquered_since(1).await.unwrap().is_empty());
+        let garrison = instance.get_ground_units(1).await.unwrap();
+        assert_eq!(
+            vec![marines],
+            garrison.iter().map(|u| u.id).collect::<Vec<i64>>()
+        );
+    }
+
+    #[tokio::test]
+    async fn battle() {
+        let instance = init_data().await;
+        instance.add_empires(empires()).await.unwrap();
+        instance.add_systems(systems()).await.unwrap();
+        sqlx::query(
+            "INSERT INTO ship_types (class, hull, cost, cr, atk, def, empire)
+            VALUES('Avenger', 'Cruiser', 9, 2, 4, 3, 1), ('Lancer', 'Escort', 4, 1, 2, 2, 2)",
+        )
+        .execute(&instance.pool)
+        .await
+        .unwrap();
+        let ours = instance
+            .insert_fleet(&Fleet::new("Home", 1, 1))
+            .await
+            .unwrap();
+        let theirs = instance
+            .insert_fleet(&Fleet::new("Raid", 2, 1))
+            .await
+            .unwrap();
+        sqlx::query(
+            "INSERT INTO ships (stype, fleet, crip, moth)
+            VALUES(1, ?, 0, 0), (1, ?, 0, 1), (2, ?, 0, 0), (2, ?, 0, 0)",
+        )
+        .bind(ours)
+        .bind(ours)
+        .bind(theirs)
+        .bind(theirs)
+        .execute(&instance.pool)
+        .await
+        .unwrap();
+
+        let ships = instance.get_combatants(1).await.unwrap();
+        assert_eq!(3, ships.len());
+        assert_eq!("Senorian", ships[0].owner_name);
+        assert_eq!(4, ships[0].atk);
+        assert!(instance.get_combatants(2).await.unwrap().is_empty());
+
+        instance
+            .apply_battle(&[ships[1].id], &[ships[2].id])
+            .await
+            .unwrap();
+        let after = instance.get_combatants(1).await.unwrap();
+        assert_eq!(2, after.len());
+        assert!(after[1].crip);
+    }
+
+    #[tokio::test]
+    async fn flights() {
+        let instance = init_data().await;
+        instance.add_empires(empires()).await.unwrap();
+        instance.add_systems(systems()).await.unwrap();
+        let mut stype = ShipType::new("Starhawk", "Cruiser", 1);
+        stype.cap = 2;
+        let stype = instance.insert_ship_type(&stype).await.unwrap();
+        let fleet = instance
+            .insert_fleet(&Fleet::new("Home", 1, 1))
+            .await
+            .unwrap();
+        sqlx::query("INSERT INTO ships (stype, fleet) VALUES (?, ?)")
+            .bind(stype)
+            .bind(fleet)
+            .execute(&instance.pool)
+            .await
+            .unwrap();
+        let carrier = instance.get_fleet_ships(fleet).await.unwrap().remove(0);
+        assert_eq!(2, carrier.cap);
+
+        let mut aboard = Flight::new(1, 1, flight::FLIGHT_ATK, flight::FLIGHT_DEF);
+        aboard.carrier = Some(carrier.id);
+        aboard.id = instance.insert_flight(&aboard).await.unwrap();
+        let mut based = Flight::new(1, 1, flight::FLIGHT_ATK, flight::FLIGHT_DEF);
+        based.base = Some(1);
+        based.id = instance.insert_flight(&based).await.unwrap();
+        let v = instance.get_flights(1).await.unwrap();
+        assert_eq!(2, v.len());
+        assert_eq!("Starhawk #1", v[0].station);
+        assert_eq!("Senor Prime base", v[1].station);
+
+        // Flights fight ahead of their ships, under negated IDs.
+        let fighting = instance.get_combatants(1).await.unwrap();
+        assert_eq!(3, fighting.len());
+        assert_eq!(
+            (-aboard.id, flight::FLIGHT),
+            (fighting[0].id, fighting[0].class.as_str())
+        );
+        instance
+            .apply_battle(&[-aboard.id], &[-based.id])
+            .await
+            .unwrap();
+        let v = instance.get_flights(1).await.unwrap();
+        assert_eq!(1, v.len());
+        assert!(v[0].crip);
+
+        // Carried flights move with the carrier's fleet, and are lost with it.
+        let mut moved = instance.get_fleet(fleet).await.unwrap();
+        moved.location = 2;
+        instance.update_fleet(&moved).await.unwrap();
+        assert_eq!(2, instance.get_flights(2).await.unwrap()[0].location);
+        based.base = None;
+        based.carrier = Some(carrier.id);
+        based.id = instance.insert_flight(&based).await.unwrap();
+        instance.apply_battle(&[], &[carrier.id]).await.unwrap();
+        assert!(instance.get_flights(2).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn current_turn() {
+        let instance = init_data().await;
+        assert_eq!(0, instance.current_turn().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn combat_modifiers() {
+        let instance = init_data().await;
+        let defaults = instance.get_combat_modifiers().await.unwrap();
+        assert!(!defaults.is_empty());
+
+        let mut exp = CombatModifier::new("Terrain", "Ion Storm", -2, -1);
+        exp.id = instance.insert_combat_modifier(&exp).await.unwrap();
+        assert!(instance
+            .get_combat_modifiers()
+            .await
+            .unwrap()
+            .contains(&exp));
+
+        exp.atk = -3;
+        instance.update_combat_modifier(&exp).await.unwrap();
+        assert!(instance
+            .get_combat_modifiers()
+            .await
+            .unwrap()
+            .contains(&exp));
+
+        instance.delete_combat_modifier(&exp).await.unwrap();
+        assert_eq!(defaults, instance.get_combat_modifiers().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn deadlines() {
+        let instance = init_data().await;
+        instance.add_empires(empires()).await.unwrap();
+        let v = instance.get_deadlines(3).await.unwrap();
+        assert_eq!(empires().len(), v.len());
+        assert!(v.iter().all(|d| d.due.is_empty() && d.submitted.is_none()));
+
+        instance.set_deadline(1, 3, "2026-10-15").await.unwrap();
+        instance
+            .set_submitted(1, 3, "2026-10-14 09:30:00")
+            .await
+            .unwrap();
+        instance
+            .set_submitted(2, 3, "2026-10-16 10:00:00")
+            .await
+            .unwrap();
+        let v = instance.get_deadlines(3).await.unwrap();
+        let senorian = v.iter().find(|d| d.empire == 1).unwrap();
+        assert_eq!("2026-10-15", senorian.due);
+        assert_eq!(Some("2026-10-14 09:30:00".to_string()), senorian.submitted);
+        let human = v.iter().find(|d| d.empire == 2).unwrap();
+        assert!(human.due.is_empty());
+        assert!(human.submitted.is_some());
+        assert!(instance
+            .get_deadlines(4)
+            .await
+            .unwrap()
+            .iter()
+            .all(|d| d.due.is_empty()));
+        assert_eq!(19, instance.today().await.unwrap().len());
+    }
+
+    #[tokio::test]
+    async fn delete_empire() {
+        let instance = init_data().await;
+        instance.add_empires(empires()).await.unwrap();
+        instance.add_systems(systems()).await.unwrap();
+        let mut sys = instance.get_system_by_name("Senor Prime").await.unwrap();
+        sys.owner = 1;
+        instance.update_system(&sys).await.unwrap();
+
+        let emp = instance.get_empires().await.unwrap();
+        let senorian = emp.iter().find(|e| e.id == 1).unwrap();
+        instance.delete_empire(senorian).await.unwrap();
+        assert_eq!(
+            empires().len() - 1,
+            instance.get_empires().await.unwrap().len()
+        );
+        let sys = instance.get_system_by_id(sys.id).await.unwrap();
+        assert_eq!(0, sys.owner);
+        assert_eq!("None", sys.owner_name);
+
+        // Its orders go with it, but not while it has a fleet in service.
+        let human = emp.iter().find(|e| e.id == 2).unwrap();
+        instance
+            .insert_orders(&[Order::new(2, 0, "Build a shipyard")])
+            .await
+            .unwrap();
+        let fleet = instance
+            .insert_fleet(&Fleet::new("Home Fleet", 2, sys.id))
+            .await
+            .unwrap();
+        assert!(matches!(
+            instance.delete_empire(human).await,
+            Err(DataError::Refused(_))
+        ));
+        instance.delete_fleet(fleet).await.unwrap();
+        instance.delete_empire(human).await.unwrap();
+        assert!(instance.get_orders(2, 0).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn delete_intel() {
+        let instance = init_data().await;
+        instance.add_empires(empires()).await.unwrap();
+        let mut item = IntelItem::new(1, 0, "Rumor", "Unrest on Tibron");
+        item.id = instance.insert_intel(&item).await.unwrap();
+        instance.delete_intel(&item).await.unwrap();
+        assert!(instance.get_intel(1, 0).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn delete_system() {
+        let instance = init_data().await;
+        instance.add_empires(empires()).await.unwrap();
+        instance.add_systems(systems()).await.unwrap();
+        instance
+            .insert_lane(&Lane::new(1, 2, "Major"))
+            .await
+            .unwrap();
+
+        // Not while a fleet is there, but its lanes go with it.
+        let fleet = instance
+            .insert_fleet(&Fleet::new("Home Fleet", 1, 1))
+            .await
+            .unwrap();
+        let home = instance.get_system_by_id(1).await.unwrap();
+        assert!(matches!(
+            instance.delete_system(&home).await,
+            Err(DataError::Refused(_))
+        ));
+        instance.delete_fleet(fleet).await.unwrap();
+        instance.delete_system(&home).await.unwrap();
+        assert!(instance.get_lanes().await.unwrap().is_empty());
+
+        for s in systems() {
+            instance.delete_system(&s).await.unwrap();
+            assert!(instance.get_system_by_id(s.id).await.is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn export_import() {
+        let instance = init_data().await;
+        instance.add_empires(empires()).await.unwrap();
+        instance.add_systems(systems()).await.unwrap();
+        instance.adjust_treasury(1, 12).await.unwrap();
+        let mut home = instance.get_system_by_id(1).await.unwrap();
+        home.owner = 1;
+        home.blockaded = true;
+        instance.update_system(&home).await.unwrap();
+        instance.set_turn(4).await.unwrap();
+        let archive = instance.export().await.unwrap();
+
+        let copy = init_data().await;
+        copy.import(&archive).await.unwrap();
+        assert_eq!(4, copy.current_turn().await.unwrap());
+        assert_eq!(
+            instance.get_empires().await.unwrap(),
+            copy.get_empires().await.unwrap()
+        );
+        assert_eq!(
+            instance.get_systems().await.unwrap(),
+            copy.get_systems().await.unwrap()
+        );
+        assert_eq!(
+            instance.get_hulls().await.unwrap(),
+            copy.get_hulls().await.unwrap()
+        );
+        assert_eq!(archive, copy.export().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn migrate() {
+        // The tables of the first release, before versions were kept.
+        let pool = sqlx::SqlitePool::connect("sqlite::memory:").await.unwrap();
+        for stmt in [
+            "CREATE TABLE control (key TEXT PRIMARY KEY, value TEXT)",
+            "INSERT INTO control VALUES ('turn', '4')",
+            "CREATE TABLE empires (id INTEGER PRIMARY KEY AUTOINCREMENT, name TEXT,
+            treasury INTEGER DEFAULT 0, tech INTEGER DEFAULT 0)",
+            "CREATE TABLE fleets (id INTEGER PRIMARY KEY AUTOINCREMENT, name TEXT,
+            owner INTEGER REFERENCES empires (id), location INTEGER REFERENCES systems (id))",
+            "CREATE TABLE ground_types (id INTEGER PRIMARY KEY AUTOINCREMENT, name TEXT,
+            abbr TEXT, cost INTEGER, atk INTEGER, def INTEGER)",
+            "INSERT INTO ground_types (name, abbr, cost, atk, def)
+            VALUES ('Militia', 'MIL', 2, 4, 4)",
+            "CREATE TABLE ground_units (id INTEGER PRIMARY KEY AUTOINCREMENT,
+            gtype INTEGER REFERENCES ground_types (id), loc INTEGER REFERENCES systems (id))",
+            "CREATE TABLE ship_types (id INTEGER PRIMARY KEY AUTOINCREMENT, class TEXT,
+            hull TEXT, cost INTEGER, cr INTEGER, atk INTEGER, def INTEGER,
+            cap INTEGER DEFAULT 0, empire INTEGER REFERENCES empires (id))",
+            "CREATE TABLE ships (id INTEGER PRIMARY KEY AUTOINCREMENT,
+            stype INTEGER REFERENCES ship_types (id), fleet INTEGER REFERENCES fleets (id),
+            crip INTEGER DEFAULT 0, moth INTEGER DEFAULT 0)",
+            "CREATE TABLE systems (id INTEGER PRIMARY KEY AUTOINCREMENT, name TEXT,
+            ptype TEXT, raw INTEGER, cap INTEGER, pop INTEGER, mor INTEGER, ind INTEGER,
+            dev INTEGER DEFAULT 0, fails INTEGER DEFAULT 0,
+            owner INTEGER REFERENCES empires (id))",
+            "INSERT INTO empires (name) VALUES ('Senorian')",
+            "INSERT INTO systems (name, ptype, raw, cap, pop, mor, ind, owner)
+            VALUES ('Senor Prime', 'HW', 5, 12, 10, 8, 10, 1),
+            ('Tibron', 'Barren', 4, 6, 3, 2, 3, NULL),
+            ('Tibron', 'Barren', 4, 6, 3, 2, 3, NULL)",
+            "INSERT INTO fleets (name, owner, location) VALUES ('Home Fleet', 1, 1)",
+        ] {
+            sqlx::query(stmt).execute(&pool).await.unwrap();
+        }
+
+        // Migrating twice is the same as once.
+        DataStore::migrate(&pool).await.unwrap();
+        DataStore::migrate(&pool).await.unwrap();
+        assert_eq!(
+            SCHEMA_VERSION,
+            DataStore::schema_version(&pool).await.unwrap()
+        );
+
+        let instance = DataStore::with_pool(pool, String::new());
+        assert_eq!(4, instance.current_turn().await.unwrap());
+        assert_eq!(1, instance.get_ground_types().await.unwrap().len());
+        assert_eq!(9, instance.get_hulls().await.unwrap().len());
+        assert_eq!(4, instance.get_planet_types().await.unwrap().len());
+        assert_eq!(0, instance.get_fleet(1).await.unwrap().speed);
+        let sys = instance.get_system_by_name("Senor Prime").await.unwrap();
+        assert!(!sys.blockaded);
+        assert_eq!(map::grid_position(0), (sys.x, sys.y));
+        let sys = instance.get_system_by_name("Tibron").await.unwrap();
+        assert_eq!(map::grid_position(1), (sys.x, sys.y));
+        assert!(sys.minor.is_empty());
+        assert_eq!((0, 100), (sys.contender, sys.share));
+        // A second system of the same name is told apart by its ID.
+        assert_eq!(
+            3,
+            instance.get_system_by_name("Tibron (3)").await.unwrap().id
+        );
+        assert!(instance.get_builds().await.unwrap().is_empty());
+        assert!(instance.get_repair_jobs().await.unwrap().is_empty());
+        assert!(instance.get_identifications(1).await.unwrap().is_empty());
+        assert!(instance.get_developments(4).await.unwrap().is_empty());
+        assert!(instance.get_operations(4).await.unwrap().is_empty());
+        assert!(instance.get_escorts().await.unwrap().is_empty());
+        assert!(instance.get_battle_sites(4).await.unwrap().is_empty());
+        assert!(instance.get_flights(1).await.unwrap().is_empty());
+        assert!(instance.get_facilities().await.unwrap().is_empty());
+        assert!(instance.get_log().await.unwrap().is_empty());
+        assert!(instance.get_wars().await.unwrap().is_empty());
+        assert!(instance.get_casualties().await.unwrap().is_empty());
+        let on_delete: String =
+            sqlx::query_scalar("SELECT on_delete FROM pragma_foreign_key_list('systems')")
+                .fetch_one(&instance.pool)
+                .await
+                .unwrap();
+        assert_eq!("SET NULL", on_delete);
+        let emp = &instance.get_empires().await.unwrap()[0];
+        assert_eq!(
+            Empire::new("Senorian"),
+            Empire {
+                id: 0,
+                ..emp.clone()
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn ownership_states() {
+        let instance = init_data().await;
+        instance.add_empires(empires()).await.unwrap();
+        instance.add_systems(systems()).await.unwrap();
+        let mut sys = instance.get_system_by_name("Tibron").await.unwrap();
+        sys.owner = 1;
+        sys.contender = 2;
+        sys.share = 70;
+        instance.update_system(&sys).await.unwrap();
+        let mut neutral = instance.get_system_by_name("Zev'rch").await.unwrap();
+        neutral.minor = "Zev".to_string();
+        instance.update_system(&neutral).await.unwrap();
+
+        let contested = instance.get_systems_contested_by(2).await.unwrap();
+        assert_eq!(1, contested.len());
+        assert_eq!("Senorian", contested[0].owner_name);
+        assert_eq!("Human", contested[0].contender_name);
+        assert_eq!(70, contested[0].share);
+        assert!(instance
+            .get_systems_contested_by(1)
+            .await
+            .unwrap()
+            .is_empty());
+        let unowned = instance.get_systems_by_owner(0).await.unwrap();
+        assert!(unowned.iter().any(|s| s.minor == "Zev"));
+
+        // Invasion settles the contest.
+        instance
+            .apply_invasion(sys.id, Some(2), &[], &[])
+            .await
+            .unwrap();
+        let sys = instance.get_system_by_id(sys.id).await.unwrap();
+        assert_eq!((2, 0, 100), (sys.owner, sys.contender, sys.share));
+    }
+
+    #[tokio::test]
+    async fn raids() {
+        let instance = init_data().await;
+        instance.add_empires(empires()).await.unwrap();
+        instance.add_systems(systems()).await.unwrap();
+        let id = instance
+            .insert_raid(&Raid::new(1, 2, 4, "Shipyards"))
+            .await
+            .unwrap();
+        instance
+            .insert_raid(&Raid::new(2, 3, 1, "Trade"))
+            .await
+            .unwrap();
+
+        let v = instance.get_raids(2).await.unwrap();
+        assert_eq!(1, v.len());
+        assert_eq!(id, v[0].id);
+        assert_eq!("Senorian", v[0].empire_name);
+        assert_eq!("Tibron", v[0].system_name);
+
+        instance.delete_raid(&v[0]).await.unwrap();
+        assert!(instance.get_raids(2).await.unwrap().is_empty());
+        assert_eq!(1, instance.get_raids(3).await.unwrap().len());
+    }
+
+    #[tokio::test]
+    async fn transfers() {
+        let instance = init_data().await;
+        instance.add_empires(empires()).await.unwrap();
+        instance.add_systems(systems()).await.unwrap();
+        instance.adjust_treasury(2, 20).await.unwrap();
+        sqlx::query("INSERT INTO fleets (name, owner, location) VALUES ('Home Fleet', 1, 1)")
+            .execute(&instance.pool)
+            .await
+            .unwrap();
+        sqlx::query(
+            "INSERT INTO ship_types (class, hull, cost, cr, atk, def, empire)
+            VALUES ('Warden', 'Destroyer', 6, 3, 3, 3, 1)",
+        )
+        .execute(&instance.pool)
+        .await
+        .unwrap();
+        sqlx::query("INSERT INTO ships (stype, fleet) VALUES (1, 1), (1, 1)")
+            .execute(&instance.pool)
+            .await
+            .unwrap();
+        for t in [
+            Transfer::ship(1, 2, 3, 1, 8),
+            Transfer::class(1, 2, 3, 1, 0),
+        ] {
+            instance.insert_transfer(&t).await.unwrap();
+        }
+
+        let v = instance.get_transfers(3).await.unwrap();
+        assert_eq!(2, v.len());
+        assert_eq!("Warden #1", v[0].item_name);
+        assert_eq!("Warden class", v[1].item_name);
+        assert_eq!(
+            ("Senorian", "Human"),
+            (v[0].seller_name.as_str(), v[0].buyer_name.as_str())
+        );
+        assert!(v.iter().all(|t| t.owner == 1));
+        for t in &v {
+            instance.transfer(t).await.unwrap();
+        }
+
+        assert_eq!(8, treasury(&instance, 1).await);
+        assert_eq!(12, treasury(&instance, 2).await);
+        assert_eq!(
+            "Bought Warden #1 from Senorian",
+            instance.get_ledger(2).await.unwrap()[0].note
+        );
+        let fleets = instance.get_fleet_summaries().await.unwrap();
+        let bought = fleets.iter().find(|f| f.owner == 2).unwrap();
+        assert_eq!(
+            ("From Senorian", 1, 1),
+            (bought.name.as_str(), bought.location, bought.ships)
+        );
+        let known = instance.get_identifications(2).await.unwrap();
+        assert_eq!(TRANSFER, known[0].source);
+        let classes = instance.get_ship_types().await.unwrap();
+        assert_eq!(2, classes.iter().filter(|t| t.class == "Warden").count());
+        assert!(classes.iter().any(|t| t.empire == 2));
+        // The ship is the buyer's now, so it cannot be sold again.
+        assert_eq!(2, instance.get_transfers(3).await.unwrap()[0].owner);
+    }
+
+    #[tokio::test]
+    async fn schema_version() {
+        let instance = init_data().await;
+        assert_eq!(
+            SCHEMA_VERSION,
+            DataStore::schema_version(&instance.pool).await.unwrap()
+        );
+        DataStore::migrate(&instance.pool).await.unwrap();
+
+        sqlx::query("UPDATE control SET value = ? WHERE key = ?")
+            .bind((SCHEMA_VERSION + 1).to_string())
+            .bind(SCHEMA_KEY)
+            .execute(&instance.pool)
+            .await
+            .unwrap();
+        assert!(DataStore::migrate(&instance.pool).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn summarize() {
+        let instance = init_data().await;
+        instance.add_empires(empires()).await.unwrap();
+        instance.set_turn(4).await.unwrap();
+        let (turn, count) = DataStore::summarize(&instance.pool).await.unwrap();
+        assert_eq!(Some(4), turn);
+        assert_eq!(Some(empires().len() as i64), count);
+    }
+
+    #[tokio::test]
+    async fn supersede_ship_types() {
+        let instance = init_data().await;
+        instance.add_empires(empires()).await.unwrap();
+        let mut old = ShipType::new("Avenger", "Cruiser", 1);
+        old.id = instance.insert_ship_type(&old).await.unwrap();
+        let mut other = ShipType::new("Warden", "Destroyer", 1);
+        other.id = instance.insert_ship_type(&other).await.unwrap();
+        let mut theirs = ShipType::new("Lancer", "Cruiser", 2);
+        theirs.id = instance.insert_ship_type(&theirs).await.unwrap();
+
+        let mut newer = ShipType::new("Vindicator", "Cruiser", 1);
+        newer.id = instance.insert_ship_type(&newer).await.unwrap();
+        instance.supersede_ship_types(&newer, 7).await.unwrap();
+        let mut newest = ShipType::new("Paladin", "Cruiser", 1);
+        newest.id = instance.insert_ship_type(&newest).await.unwrap();
+        instance.supersede_ship_types(&newest, 9).await.unwrap();
+
+        let types = instance.get_ship_types().await.unwrap();
+        let obsolete = |class: &str| types.iter().find(|t| t.class == class).unwrap().obsolete;
+        assert_eq!(Some(7), obsolete("Avenger"));
+        assert_eq!(Some(9), obsolete("Vindicator"));
+        assert_eq!(None, obsolete("Paladin"));
+        assert_eq!(None, obsolete("Warden"));
+        assert_eq!(None, obsolete("Lancer"));
+    }
+
+    #[tokio::test]
+    async fn reconnect() {
+        let file =
+            std::env::temp_dir().join(format!("vbamcma-reconnect-{}.db", std::process::id()));
+        let url = format!("sqlite://{}?mode=rwc", file.to_str().unwrap());
+        let pool = DataStore::connect(url.as_str()).await.unwrap();
+        DataStore::create_tables(&pool).await.unwrap();
+        let mut instance = DataStore::with_pool(pool, url);
+        instance.add_systems(systems()).await.unwrap();
+
+        // Lost connections are reported as such, and can be made again.
+        instance.close().await;
+        assert!(instance.get_systems().await.unwrap_err().is_unavailable());
+        assert!(instance.check().await.is_err());
+        instance.reconnect().await.unwrap();
+        instance.check().await.unwrap();
+        assert_eq!(4, instance.get_systems().await.unwrap().len());
+        assert!(!DataError::Refused(String::new()).is_unavailable());
+
+        instance.close().await;
+        std::fs::remove_file(file).unwrap();
+    }
+
+    #[tokio::test]
+    async fn write_backup() {
+        // An in-memory database is vacuumed into memory too, so back up one
+        // kept in a file, as campaigns are.
+        let dir = std::env::temp_dir();
+        let source = dir.join(format!("vbamcma-source-{}.db", std::process::id()));
+        let url = format!("sqlite://{}?mode=rwc", source.to_str().unwrap());
+        let pool = DataStore::connect(url.as_str()).await.unwrap();
+        DataStore::create_tables(&pool).await.unwrap();
+        let instance = DataStore::with_pool(pool, url);
+        instance.add_systems(systems()).await.unwrap();
+        let file = dir.join(format!("vbamcma-backup-{}.db", std::process::id()));
+        instance.write_backup(&file).await.unwrap();
+
+        let copy = DataStore::open_file(&file).await.unwrap();
+        assert_eq!(
+            instance.get_systems().await.unwrap(),
+            copy.get_systems().await.unwrap()
+        );
+        copy.close().await;
+        instance.close().await;
+        std::fs::remove_file(&file).unwrap();
+        std::fs::remove_file(source).unwrap();
+        assert!(DataStore::open_file(&file).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn scratch() {
+        let instance = init_data().await;
+        instance.add_systems(systems()).await.unwrap();
+        let copy = instance.scratch().await.unwrap();
+        assert_eq!(
+            instance.export().await.unwrap(),
+            copy.export().await.unwrap()
+        );
+
+        // Changes to the copy are not seen in the original.
+        copy.set_turn(5).await.unwrap();
+        assert_eq!(0, instance.current_turn().await.unwrap());
+        copy.close().await;
+    }
+
+    #[tokio::test]
+    async fn work() {
+        let instance = init_data().await;
+        instance.begin_work().await.unwrap();
+        instance.add_systems(systems()).await.unwrap();
+        instance.set_turn(5).await.unwrap();
+        // Clones work in the same transaction.
+        let clone = instance.clone();
+        assert_eq!(5, clone.current_turn().await.unwrap());
+        instance.rollback_work().await.unwrap();
+        assert_eq!(0, instance.current_turn().await.unwrap());
+        assert!(instance.get_systems().await.unwrap().is_empty());
+
+        instance.begin_work().await.unwrap();
+        instance.add_systems(systems()).await.unwrap();
+        instance.commit_work().await.unwrap();
+        assert_eq!(systems().len(), instance.get_systems().await.unwrap().len());
+    }
+
+    #[tokio::test]
+    async fn add_workbook() {
+        let instance = init_data().await;
+        instance.add_workbook(&workbook()).await.unwrap();
+        let empires = instance.get_empires().await.unwrap();
+        assert_eq!(2, empires.len());
+        let senorian = empires.iter().find(|e| e.name == "Senorian").unwrap();
+        let home = instance.get_system_by_name("Senor Prime").await.unwrap();
+        assert_eq!(senorian.id, home.owner);
+        let tibron = instance.get_system_by_name("Tibron").await.unwrap();
+        assert_eq!(0, tibron.owner);
+        let fleets = instance.get_fleet_summaries().await.unwrap();
+        assert_eq!(1, fleets.len());
+        assert_eq!((home.id, 2), (fleets[0].location, fleets[0].ships));
+    }
+
+    #[tokio::test]
+    async fn espionage() {
+        let instance = init_data().await;
+        instance.add_empires(empires()).await.unwrap();
+        let before = treasury(&instance, 1).await;
+        let id = instance
+            .insert_operation(&Operation::new(1, 2, 2, espionage::STEAL_TECH, 3))
+            .await
+            .unwrap();
+        instance
+            .insert_operation(&Operation::new(1, 2, 1, espionage::COUNTER_ESPIONAGE, 2))
+            .await
+            .unwrap();
+        assert_eq!(before - 5, treasury(&instance, 1).await);
+        assert!(instance.get_operations(1).await.unwrap().is_empty());
+        let v = instance.get_operations(2).await.unwrap();
+        assert_eq!(2, v.len());
+        assert_eq!(id, v[0].id);
+        assert_eq!("Senorian", v[0].empire_name);
+        assert_eq!("Human", v[0].target_name);
+
+        // Cancelling refunds the points.
+        instance.delete_operation(&v[0]).await.unwrap();
+        assert_eq!(1, instance.get_operations(2).await.unwrap().len());
+        assert_eq!(before - 2, treasury(&instance, 1).await);
+    }
+
+    #[tokio::test]
+    async fn developments() {
+        let instance = init_data().await;
+        instance.add_empires(empires()).await.unwrap();
+        instance.add_systems(systems()).await.unwrap();
+        let before = treasury(&instance, 1).await;
+        let id = instance
+            .insert_development(&Development::new(1, 2, 1, development::POPULATION, 5))
+            .await
+            .unwrap();
+        assert!(instance
+            .insert_development(&Development::new(1, 2, 1, development::POPULATION, 5))
+            .await
+            .is_err());
+        assert_eq!(before - 5, treasury(&instance, 1).await);
+        assert!(instance.get_developments(1).await.unwrap().is_empty());
+        let v = instance.get_developments(2).await.unwrap();
+        assert_eq!(1, v.len());
+        assert_eq!(id, v[0].id);
+        assert_eq!("Senorian", v[0].empire_name);
+        assert_eq!("Senor Prime", v[0].system_name);
+
+        // Cancelling refunds the cost.
+        instance.delete_development(&v[0]).await.unwrap();
+        assert!(instance.get_developments(2).await.unwrap().is_empty());
+        assert_eq!(before, treasury(&instance, 1).await);
+
+        // Only a ship that can carry cargo founds a colony.
+        assert_eq!(None, instance.get_colony_ship(1, 2).await.unwrap());
+        let warship = instance
+            .insert_ship_type(&ShipType::new("Avenger", "Cruiser", 1))
+            .await
+            .unwrap();
+        instance
+            .finish_build(&Build::new(1, 2, "Ship", warship, ""))
+            .await
+            .unwrap();
+        assert_eq!(None, instance.get_colony_ship(1, 2).await.unwrap());
+        let mut ark = ShipType::new("Ark", "Frigate", 1);
+        ark.cap = 1;
+        let ark = instance.insert_ship_type(&ark).await.unwrap();
+        instance
+            .finish_build(&Build::new(1, 2, "Ship", ark, ""))
+            .await
+            .unwrap();
+        let ship = instance.get_ships_by_class(ark).await.unwrap()[0].id;
+        assert_eq!(Some(ship), instance.get_colony_ship(1, 2).await.unwrap());
+        assert_eq!(None, instance.get_colony_ship(2, 2).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn identifications() {
+        let instance = init_data().await;
+        instance.add_empires(empires()).await.unwrap();
+        let kestrel = ShipType::new("Kestrel", "Frigate", 2);
+        let stype = instance.insert_ship_type(&kestrel).await.unwrap();
+        instance
+            .insert_identifications(&[
+                Identification::new(1, stype, 3, ESPIONAGE),
+                Identification::new(1, stype, 5, BATTLE),
+            ])
+            .await
+            .unwrap();
+
+        // The first identification stands.
+        let v = instance.get_identifications(1).await.unwrap();
+        assert_eq!(1, v.len());
+        assert_eq!((3, ESPIONAGE), (v[0].turn, v[0].source.as_str()));
+        assert_eq!("Kestrel", v[0].class);
+        assert_eq!("Human", v[0].owner_name);
+        assert!(instance.get_identifications(2).await.unwrap().is_empty());
+
+        instance.delete_identification(&v[0]).await.unwrap();
+        assert!(instance.get_identifications(1).await.unwrap().is_empty());
+
+        // Deleting a class forgets it.
+        instance
+            .insert_identifications(&[Identification::new(1, stype, 6, BATTLE)])
+            .await
+            .unwrap();
+        let mut kestrel = kestrel;
+        kestrel.id = stype;
+        instance.delete_ship_type(&kestrel).await.unwrap();
+        assert!(instance.get_identifications(1).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn import_over_existing() {
+        let instance = init_data().await;
+        instance.add_empires(empires()).await.unwrap();
+        instance.add_systems(systems()).await.unwrap();
+        let mut home = instance.get_system_by_id(1).await.unwrap();
+        home.owner = 1;
+        instance.update_system(&home).await.unwrap();
+        let snapshot = instance.export().await.unwrap();
+
+        let emp = instance.get_empires().await.unwrap();
+        instance.delete_empire(&emp[0]).await.unwrap();
+        instance.delete_system(&home).await.unwrap();
+        instance.import(&snapshot).await.unwrap();
+        assert_eq!(snapshot, instance.export().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn import_rejects_broken_reference() {
+        let instance = init_data().await;
+        instance.add_empires(empires()).await.unwrap();
+        instance.add_systems(systems()).await.unwrap();
+        let mut archive = instance.export().await.unwrap();
+        archive["tables"]["empires"]["rows"] = serde_json::json!([]);
+        archive["tables"]["fleets"] = serde_json::json!({
+            "columns": ["id", "name", "owner", "location", "speed"],
+            "rows": [[1, "Home Fleet", 1, 1, 0]]
+        });
+        assert!(instance.import(&archive).await.is_err());
+        assert_eq!(empires().len(), instance.get_empires().await.unwrap().len());
+    }
+
+    #[tokio::test]
+    async fn import_systems() {
+        let instance = init_data().await;
+        instance.add_empires(empires()).await.unwrap();
+        instance.add_systems(systems()).await.unwrap();
+        let mut tibron = instance.get_system_by_name("Tibron").await.unwrap();
+        tibron.owner = 1;
+        instance.update_system(&tibron).await.unwrap();
+
+        let added = vec![System::new("Ixtli", "Poor", 1, 2, 1, 1, 1)];
+        let replaced = vec![System {
+            id: tibron.id,
+            ..System::new("Tibron", "Barren", 5, 6, 4, 3, 4)
+        }];
+        instance.import_systems(&added, &replaced).await.unwrap();
+        assert_eq!(5, instance.get_systems().await.unwrap().len());
+        let act = instance.get_system_by_name("Tibron").await.unwrap();
+        assert_eq!((0, 5), (act.owner, act.raw));
+    }
+
+    #[tokio::test]
+    async fn unique_names() {
+        let instance = init_data().await;
+        instance.add_empires(empires()).await.unwrap();
+        instance.add_systems(systems()).await.unwrap();
+        let err = instance
+            .insert_empire(&Empire::new("Human"))
+            .await
+            .unwrap_err();
+        assert_eq!("Another empire already has that name", err.to_string());
+        let mut sys = instance.get_system_by_name("Tibron").await.unwrap();
+        sys.name = "Senor Prime".to_string();
+        let err = instance.update_system(&sys).await.unwrap_err();
+        assert_eq!("Another system already has that name", err.to_string());
+        assert!(instance.add_systems(systems()).await.is_err());
+        assert_eq!(4, instance.get_systems().await.unwrap().len());
+    }
+
+    #[tokio::test]
+    async fn import_rejects_unknown_table() {
+        let instance = init_data().await;
+        let archive = serde_json::json!({
+            "tables": { "dragons": { "columns": ["id"], "rows": [[1]] } }
+        });
+        assert!(instance.import(&archive).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn fleets() {
+        let instance = init_data().await;
+        instance.add_empires(empires()).await.unwrap();
+        instance.add_systems(systems()).await.unwrap();
+        let mut exp = Fleet::new("Home Fleet", 1, 1);
+        exp.id = instance.insert_fleet(&exp).await.unwrap();
+        assert_eq!(exp, instance.get_fleet(exp.id).await.unwrap());
+
+        exp.name = "First Fleet".to_string();
+        exp.location = 2;
+        exp.speed = 1;
+        instance.update_fleet(&exp).await.unwrap();
+        assert_eq!(exp, instance.get_fleet(exp.id).await.unwrap());
+
+        instance.delete_fleet(exp.id).await.unwrap();
+        assert!(instance.get_fleet(exp.id).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn courses() {
+        let instance = init_data().await;
+        instance.add_empires(empires()).await.unwrap();
+        instance.add_systems(systems()).await.unwrap();
+        let fleet = instance
+            .insert_fleet(&Fleet::new("Home Fleet", 1, 1))
+            .await
+            .unwrap();
+        instance.set_course(fleet, &[2, 3, 4]).await.unwrap();
+        instance.set_course(fleet, &[4, 3]).await.unwrap();
+        assert_eq!(vec![4, 3], instance.get_courses().await.unwrap()[&fleet]);
+
+        let transit = Transit {
+            fleet,
+            owner: 1,
+            at: 4,
+            course: vec![3],
+            jumps: 1,
+        };
+        instance
+            .apply_movement(std::slice::from_ref(&transit))
+            .await
+            .unwrap();
+        assert_eq!(4, instance.get_fleet(fleet).await.unwrap().location);
+        assert_eq!(vec![3], instance.get_courses().await.unwrap()[&fleet]);
+        // Arriving ends the course.
+        let arrived = Transit {
+            at: 3,
+            course: Vec::new(),
+            ..transit
+        };
+        instance.apply_movement(&[arrived]).await.unwrap();
+        assert!(instance.get_courses().await.unwrap().is_empty());
+
+        instance
+            .insert_orders(&[
+                Order::in_phase(1, 0, orders::MOVEMENT, "Move Home Fleet to Tibron", 0),
+                Order::in_phase(1, 0, orders::ECONOMIC, "Spend 4 EP on research", 4),
+            ])
+            .await
+            .unwrap();
+        let v = instance.get_movement_orders(0).await.unwrap();
+        assert_eq!(1, v.len());
+        assert_eq!("Move Home Fleet to Tibron", v[0].text);
+    }
+
+    #[tokio::test]
+    async fn escorts() {
+        let instance = init_data().await;
+        instance.add_empires(empires()).await.unwrap();
+        instance.add_systems(systems()).await.unwrap();
+        let convoy = instance
+            .insert_fleet(&Fleet::new("Convoy", 1, 1))
+            .await
+            .unwrap();
+        let guard = instance
+            .insert_fleet(&Fleet::new("Guard", 1, 1))
+            .await
+            .unwrap();
+        let stype = instance
+            .insert_ship_type(&ShipType::new("Corvette", "Corvette", 1))
+            .await
+            .unwrap();
+        sqlx::query("INSERT INTO ships (stype, fleet) VALUES (?, ?), (?, ?)")
+            .bind(stype)
+            .bind(convoy)
+            .bind(stype)
+            .bind(guard)
+            .execute(&instance.pool)
+            .await
+            .unwrap();
+        let id = instance
+            .insert_escort(&Escort::new(guard, convoy))
+            .await
+            .unwrap();
+        assert!(instance
+            .insert_escort(&Escort::new(guard, convoy))
+            .await
+            .is_err());
+        let v = instance.get_escorts().await.unwrap();
+        assert_eq!(id, v[0].id);
+        assert_eq!(
+            ("Guard", "Convoy"),
+            (v[0].escort_name.as_str(), v[0].convoy_name.as_str())
+        );
+
+        // The escort's ships take fire first.
+        let ships = instance.get_combatants(1).await.unwrap();
+        assert_eq!(2, ships.len());
+        assert!(ships[0].id > ships[1].id);
+
+        instance.move_fleets(&[convoy, guard], 2).await.unwrap();
+        assert_eq!(2, instance.get_fleet(guard).await.unwrap().location);
+
+        // Disbanding either fleet ends the escort.
+        instance.delete_fleet(convoy).await.unwrap();
+        assert!(instance.get_escorts().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn merge_and_split_fleets() {
+        let instance = init_data().await;
+        instance.add_empires(empires()).await.unwrap();
+        instance.add_systems(systems()).await.unwrap();
+        let mut a = Fleet::new("Home Fleet", 1, 1);
+        a.id = instance.insert_fleet(&a).await.unwrap();
+        let b = instance
+            .insert_fleet(&Fleet::new("Reserve", 1, 1))
+            .await
+            .unwrap();
+        sqlx::query(
+            "INSERT INTO ship_types (class, hull, cost, cr, atk, def)
+            VALUES ('Avenger', 'Cruiser', 10, 5, 5, 5)",
+        )
+        .execute(&instance.pool)
+        .await
+        .unwrap();
+        sqlx::query("INSERT INTO ships (stype, fleet) VALUES (1, ?), (1, ?), (1, ?)")
+            .bind(a.id)
+            .bind(a.id)
+            .bind(b)
+            .execute(&instance.pool)
+            .await
+            .unwrap();
+
+        instance.merge_fleets(b, a.id).await.unwrap();
+        assert!(instance.get_fleet(b).await.is_err());
+        let ships = instance.get_fleet_ships(a.id).await.unwrap();
+        assert_eq!(3, ships.len());
+
+        let c = instance
+            .split_fleet(&a, "Detachment", &[ships[0].id])
+            .await
+            .unwrap();
+        let split = instance.get_fleet(c).await.unwrap();
+        assert_eq!(a.owner, split.owner);
+        assert_eq!(a.location, split.location);
+        assert_eq!(1, instance.get_fleet_ships(c).await.unwrap().len());
+        assert_eq!(2, instance.get_fleet_ships(a.id).await.unwrap().len());
+    }
+
+    #[tokio::test]
+    async fn get_fleet_allowance() {
+        let instance = init_data().await;
+        sqlx::query(
+            "INSERT INTO fleets (name, speed)
+            VALUES ('Strike Group', 0), ('Convoy', 1), ('Picket', 0)",
+        )
+        .execute(&instance.pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            "INSERT INTO ship_types (class, hull, cost, cr, atk, def, speed)
+            VALUES ('Lancer', 'Frigate', 4, 2, 2, 2, 3),
+            ('Avenger', 'Cruiser', 10, 5, 5, 5, 2)",
+        )
+        .execute(&instance.pool)
+        .await
+        .unwrap();
+        sqlx::query("INSERT INTO ships (stype, fleet) VALUES (1, 1), (2, 1), (1, 2)")
+            .execute(&instance.pool)
+            .await
+            .unwrap();
+
+        assert_eq!(2, instance.get_fleet_allowance(1).await.unwrap());
+        assert_eq!(1, instance.get_fleet_allowance(2).await.unwrap());
+        assert_eq!(1, instance.get_fleet_allowance(3).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn get_fleet_summaries() {
+        let instance = init_data().await;
+        instance.add_empires(empires()).await.unwrap();
+        instance.add_systems(systems()).await.unwrap();
+        sqlx::query(
+            "INSERT INTO fleets (name, owner, location)
+            VALUES ('Home Fleet', 1, 1), ('Reserve', 1, 2)",
+        )
+        .execute(&instance.pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            "INSERT INTO ship_types (class, hull, cost, cr, atk, def)
+            VALUES ('Avenger', 'Cruiser', 10, 5, 4, 6)",
+        )
+        .execute(&instance.pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            "INSERT INTO ships (stype, fleet, crip, moth)
+            VALUES (1, 1, 0, 0), (1, 1, 1, 0), (1, 1, 0, 1)",
+        )
+        .execute(&instance.pool)
+        .await
+        .unwrap();
+
+        let v = instance.get_fleet_summaries().await.unwrap();
+        assert_eq!(2, v.len());
+        let home = v.iter().find(|f| f.name == "Home Fleet").unwrap();
+        assert_eq!("Senor Prime", home.location_name);
+        assert_eq!(3, home.ships);
+        assert_eq!(1, home.crippled);
+        assert_eq!(12, home.atk);
+        assert_eq!(18, home.def);
+        assert_eq!(2 + 1 + 1, home.maint);
+        let reserve = v.iter().find(|f| f.name == "Reserve").unwrap();
+        assert_eq!(0, reserve.ships);
+        assert_eq!(0, reserve.maint);
+    }
+
+    #[tokio::test]
+    async fn get_empires() {
+        let instance = init_data().await;
+        instance.add_empires(empires()).await.unwrap();
+        let act = instance.get_empires().await.unwrap();
+        assert_eq!(empires().len(), act.len());
+        for w in act.windows(2) {
+            assert!(w[0].name <= w[1].name);
+        }
+    }
+
+    #[tokio::test]
+    async fn get_ships_by_class() {
+        let instance = init_data().await;
+        instance.add_empires(empires()).await.unwrap();
+        instance.add_systems(systems()).await.unwrap();
+        sqlx::query(
+            "INSERT INTO fleets (name, owner, location)
+            VALUES ('Home Fleet', 1, 1), ('Patrol', 1, 3)",
+        )
+        .execute(&instance.pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            "INSERT INTO ship_types (class, hull, cost, cr, atk, def, empire)
+            VALUES ('Warden', 'Destroyer', 6, 3, 3, 3, 1),
+            ('Avenger', 'Cruiser', 10, 5, 5, 5, 1)",
+        )
+        .execute(&instance.pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            "INSERT INTO ships (stype, fleet, crip)
+            VALUES (1, 1, 0), (1, 2, 1), (2, 1, 0)",
+        )
+        .execute(&instance.pool)
+        .await
+        .unwrap();
+
+        let types = instance.get_ship_types().await.unwrap();
+        assert_eq!(2, types.len());
+        assert!(types.iter().all(|t| t.empire_name == "Senorian"));
+        let warden = types.iter().find(|t| t.class == "Warden").unwrap();
+        assert_eq!(2, warden.active);
+
+        let v = instance.get_ships_by_class(1).await.unwrap();
+        assert_eq!(2, v.len());
+        assert!(v.iter().all(|s| s.class == "Warden"));
+        assert!(v
+            .iter()
+            .any(|s| s.fleet_name == "Patrol" && s.location_name == "Zev'rch" && s.crip));
+    }
+
+    #[tokio::test]
+    async fn get_systems_by_owner() {
+        let instance = init_data().await;
+        instance.add_empires(empires()).await.unwrap();
+        instance.add_systems(systems()).await.unwrap();
+        let mut sys = instance.get_system_by_name("Senor Prime").await.unwrap();
+        sys.owner = 1;
+        instance.update_system(&sys).await.unwrap();
+
+        let owned = instance.get_systems_by_owner(1).await.unwrap();
+        assert_eq!(1, owned.len());
+        assert_eq!("Senor Prime", owned[0].name);
+        assert_eq!("Senorian", owned[0].owner_name);
+        let unowned = instance.get_systems_by_owner(0).await.unwrap();
+        assert_eq!(systems().len() - 1, unowned.len());
+        assert!(instance.get_systems_by_owner(2).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn get_ground_types() {
+        let instance = init_data().await;
+        let types = instance.get_ground_types().await.unwrap();
+        assert!(types.iter().any(|t| t.abbr == "MIL"));
+    }
+
+    #[tokio::test]
+    async fn ground_units() {
+        let instance = init_data().await;
+        instance.add_empires(empires()).await.unwrap();
+        instance.add_systems(systems()).await.unwrap();
+        let a = instance.insert_ground_unit(1, 1).await.unwrap();
+        let b = instance.insert_ground_unit(2, 1).await.unwrap();
+        let garrison = instance.get_ground_units(1).await.unwrap();
+        assert_eq!(2, garrison.len());
+        assert_eq!("MIL", garrison[0].abbr);
+
+        let mut home = instance.get_system_by_id(1).await.unwrap();
+        home.owner = 1;
+        instance.update_system(&home).await.unwrap();
+        assert_eq!(
+            2,
+            instance.get_ground_units_by_owner(1).await.unwrap().len()
+        );
+
+        instance.move_ground_units(&[b], 2).await.unwrap();
+        assert_eq!(
+            1,
+            instance.get_ground_units_by_owner(1).await.unwrap().len()
+        );
+        assert_eq!(vec![a], ids(instance.get_ground_units(1).await.unwrap()));
+        assert_eq!(vec![b], ids(instance.get_ground_units(2).await.unwrap()));
+
+        instance.delete_ground_units(&[a, b]).await.unwrap();
+        assert!(instance.get_ground_units(1).await.unwrap().is_empty());
+        assert!(instance.get_ground_units(2).await.unwrap().is_empty());
+    }
+
+    fn ids(units: Vec<GroundUnit>) -> Vec<i64> {
+        units.into_iter().map(|u| u.id).collect()
+    }
+
+    #[tokio::test]
+    async fn get_transport_capacity() {
+        let instance = init_data().await;
+        instance.add_empires(empires()).await.unwrap();
+        instance.add_systems(systems()).await.unwrap();
+        sqlx::query(
+            "INSERT INTO ship_types (class, hull, cost, cr, atk, def, cap)
+            VALUES('Lifter', 'Frigate', 3, 1, 0, 1, 2)",
+        )
+        .execute(&instance.pool)
+        .await
+        .unwrap();
+        sqlx::query("INSERT INTO fleets (name, owner, location) VALUES('Lift', 1, 1)")
+            .execute(&instance.pool)
+            .await
+            .unwrap();
+        sqlx::query(
+            "INSERT INTO ships (stype, fleet, crip, moth)
+            VALUES(1, 1, 0, 0), (1, 1, 0, 0), (1, 1, 1, 0)",
+        )
+        .execute(&instance.pool)
+        .await
+        .unwrap();
+        assert_eq!(4, instance.get_transport_capacity(1, 1).await.unwrap());
+        assert_eq!(0, instance.get_transport_capacity(1, 2).await.unwrap());
+        assert_eq!(0, instance.get_transport_capacity(2, 1).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn get_hulls() {
+        let instance = init_data().await;
+        let hulls = instance.get_hulls().await.unwrap();
+        assert!(!hulls.is_empty());
+        for w in hulls.windows(2) {
+            assert!(w[0].size <= w[1].size);
+        }
+    }
+
+    #[tokio::test]
+    async fn load_setting() {
+        let instance = init_data().await;
+        instance.add_empires(empires()).await.unwrap();
+        instance.add_systems(systems()).await.unwrap();
+        let sys = instance.get_system_by_name("Tibron").await.unwrap();
+        instance.insert_ground_unit(1, sys.id).await.unwrap();
+        instance
+            .insert_ship_type(&ShipType::new("Avenger", "Cruiser", 1))
+            .await
+            .unwrap();
+
+        let mut pack = setting::standard();
+        pack.name = "Frontier".to_string();
+        pack.ground_types.retain(|g| g.name != "Militia");
+        // Militia are still garrisoned, so the whole setting is refused.
+        assert!(instance.load_setting(&pack).await.is_err());
+        assert_eq!(6, instance.get_ground_types().await.unwrap().len());
+
+        pack = setting::standard();
+        pack.name = "Frontier".to_string();
+        pack.ground_types.retain(|g| g.name != "Marines");
+        pack.ground_types[0].atk = 5;
+        pack.hulls.retain(|h| h.size <= 5);
+        pack.hulls[4].tech = 2;
+        pack.planet_types.clear();
+        pack.file = "frontier.json".to_string();
+        instance.load_setting(&pack).await.unwrap();
+        let ground = instance.get_ground_types().await.unwrap();
+        assert_eq!(5, ground.len());
+        assert_eq!((1, 5), (ground[0].id, ground[0].atk));
+        assert_eq!(2, instance.get_hull("Cruiser").await.unwrap().tech);
+        assert_eq!(5, instance.get_hulls().await.unwrap().len());
+        assert_eq!(4, instance.get_planet_types().await.unwrap().len());
+        let options = instance.get_campaign_options().await.unwrap();
+        assert_eq!("Frontier", options.setting);
+        assert_eq!("frontier.json", options.setting_file);
 
-        Ok(())
+        // Ship classes need their hulls.
+        pack.hulls.retain(|h| h.name != "Cruiser");
+        assert!(instance.load_setting(&pack).await.is_err());
     }
 
-    async fn create_fleets_table(pool: &SqlitePool) -> DataResult<()> {
-        sqlx::query(
-            "CREATE TABLE IF NOT EXISTS fleets (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            name TEXT,
-            owner INTEGER REFERENCES empires (id),
-            location INTEGER REFERENCES systems (id))",
-        )
-        .execute(pool)
-        .await?;
+    #[tokio::test]
+    async fn log() {
+        let instance = init_data().await;
+        instance
+            .insert_log(&[
+                LogEntry::new(3, audit::EDIT, "Edit Senorian"),
+                LogEntry::new(3, audit::COMBAT, "Battle at Zev'rch"),
+            ])
+            .await
+            .unwrap();
+        let v = instance.get_log().await.unwrap();
+        assert_eq!(2, v.len());
+        assert_eq!((3, audit::EDIT), (v[0].turn, v[0].category.as_str()));
+        assert_eq!("Battle at Zev'rch", v[1].text);
+        assert!(!v[0].stamp.is_empty());
+    }
 
-        Ok(())
+    #[tokio::test]
+    async fn facilities() {
+        let instance = init_data().await;
+        instance.add_empires(empires()).await.unwrap();
+        instance.add_systems(systems()).await.unwrap();
+        let mut home = instance.get_system_by_id(1).await.unwrap();
+        home.owner = 1;
+        instance.update_system(&home).await.unwrap();
+
+        let base = instance
+            .insert_facility(&Facility::new(1, facility::STARBASE))
+            .await
+            .unwrap();
+        let mut yard = Facility::new(1, facility::SHIPYARD);
+        yard.id = instance.insert_facility(&yard).await.unwrap();
+        let v = instance.get_facilities().await.unwrap();
+        assert_eq!(2, v.len());
+        assert_eq!((1, "Senor Prime"), (v[0].owner, v[0].system_name.as_str()));
+        assert_eq!(3, instance.get_maintenance(1).await.unwrap().total());
+
+        // Only the starbase fights.
+        let fighting = instance.get_combatants(1).await.unwrap();
+        assert_eq!(1, fighting.len());
+        assert_eq!(facility::COMBAT_ID + base, fighting[0].id);
+        assert_eq!(facility::STARBASE, fighting[0].class);
+        instance
+            .apply_battle(&[facility::COMBAT_ID + base], &[])
+            .await
+            .unwrap();
+        assert!(instance.get_combatants(1).await.unwrap()[0].crip);
+        // The crippled starbase's upkeep halves.
+        assert_eq!(1 + 1, instance.get_maintenance(1).await.unwrap().total());
+        instance
+            .apply_battle(&[], &[facility::COMBAT_ID + base])
+            .await
+            .unwrap();
+        assert!(instance.get_combatants(1).await.unwrap().is_empty());
+
+        yard.crip = true;
+        instance.update_facility(&yard).await.unwrap();
+        assert!(instance.get_facilities().await.unwrap()[0].crip);
+        instance.delete_facility(&yard).await.unwrap();
+
+        // A finished build becomes a facility.
+        let build = Build::new(1, 1, "Facility", 0, facility::SENSOR_POST);
+        instance.finish_build(&build).await.unwrap();
+        let v = instance.get_facilities().await.unwrap();
+        assert_eq!(
+            vec![facility::SENSOR_POST],
+            v.iter().map(|f| f.kind.as_str()).collect::<Vec<_>>()
+        );
+
+        // Facilities go with their system.
+        instance.delete_system(&home).await.unwrap();
+        assert!(instance.get_facilities().await.unwrap().is_empty());
     }
 
-    async fn create_ground_types_table(pool: &SqlitePool) -> DataResult<()> {
+    #[tokio::test]
+    async fn get_maintenance() {
+        let instance = init_data().await;
+        instance.add_empires(empires()).await.unwrap();
+        sqlx::query("INSERT INTO fleets (name, owner) VALUES ('Home Fleet', 1)")
+            .execute(&instance.pool)
+            .await
+            .unwrap();
         sqlx::query(
-            "CREATE TABLE IF NOT EXISTS ground_types (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            name TEXT,
-            abbr TEXT,
-            cost INTEGER,
-            atk INTEGER,
-            def INTEGER)",
+            "INSERT INTO ship_types (class, hull, cost, cr, atk, def)
+            VALUES ('Avenger', 'Cruiser', 10, 5, 5, 5)",
         )
-        .execute(pool)
-        .await?;
-
+        .execute(&instance.pool)
+        .await
+        .unwrap();
         sqlx::query(
-            "INSERT INTO ground_types
-            (name, abbr, cost, atk, def)
-            VALUES
-            ('Militia', 'MIL', 2, 4, 4),
-            ('Light Infantry', 'LI', 3, 4, 4),
-            ('Mobile Infantry', 'MI', 4, 4, 8),
-            ('Light Armor', 'LA', 4, 8, 4),
-            ('Mech Infantry', 'MECH', 8, 8, 8),
-            ('Marines', 'MAR', 6, 4, 8)",
+            "INSERT INTO ships (stype, fleet, crip, moth)
+            VALUES (1, 1, 0, 0), (1, 1, 0, 0), (1, 1, 1, 0), (1, 1, 0, 1)",
         )
-        .execute(pool)
-        .await?;
+        .execute(&instance.pool)
+        .await
+        .unwrap();
 
-        Ok(())
+        let m = instance.get_maintenance(1).await.unwrap();
+        assert_eq!(3, m.lines.len());
+        assert_eq!(ShipStatus::Active, m.lines[0].status);
+        assert_eq!(2, m.lines[0].count);
+        // Cruisers cost 2: two active, one crippled at half, one mothballed
+        // at a quarter rounded up.
+        assert_eq!(2 + 2 + 1 + 1, m.total());
+        assert_eq!(0, instance.get_maintenance(2).await.unwrap().total());
     }
 
-    async fn create_ground_units_table(pool: &SqlitePool) -> DataResult<()> {
-        sqlx::query(
-            "CREATE TABLE IF NOT EXISTS ground_units (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            gtype INTEGER REFERENCES ground_types (id),
-            loc INTEGER REFERENCES systems (id))",
-        )
-        .execute(pool)
-        .await?;
+    #[tokio::test]
+    async fn get_planet_types() {
+        let instance = init_data().await;
+        let types = instance.get_planet_types().await.unwrap();
+        assert_eq!(4, types.len());
+        assert_eq!("HW", types[0].name);
+        for sys in systems() {
+            assert!(sys.validate(&types).is_ok());
+        }
+    }
 
-        Ok(())
+    #[tokio::test]
+    async fn insert_intel() {
+        let instance = init_data().await;
+        instance.add_empires(empires()).await.unwrap();
+        let mut exp = IntelItem::new(1, 2, "Scan", "Kili fleet at Tibron");
+        exp.id = instance.insert_intel(&exp).await.unwrap();
+        assert_eq!(vec![exp], instance.get_intel(1, 2).await.unwrap());
+        assert!(instance.get_intel(1, 1).await.unwrap().is_empty());
+        assert!(instance.get_intel(2, 2).await.unwrap().is_empty());
     }
 
-    async fn create_ship_types_table(pool: &SqlitePool) -> DataResult<()> {
-        sqlx::query(
-            "CREATE TABLE IF NOT EXISTS ship_types (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            class TEXT,
-            hull TEXT,
-            cost INTEGER,
-            cr INTEGER,
-            atk INTEGER,
-            def INTEGER,
-            cap INTEGER DEFAULT 0,
-            empire INTEGER REFERENCES empires (id))",
-        )
-        .execute(pool)
-        .await?;
+    #[tokio::test]
+    async fn insert_orders() {
+        let instance = init_data().await;
+        instance.add_empires(empires()).await.unwrap();
+        let orders = vec![
+            Order::in_phase(1, 2, orders::CONSTRUCTION, "Build Corvette at Tibron", 6),
+            Order::new(1, 2, "Hold"),
+        ];
+        let exp = instance.insert_orders(&orders).await.unwrap();
+        assert!(exp.iter().all(|o| o.id > 0));
+        assert_eq!(exp, instance.get_orders(1, 2).await.unwrap());
+        assert!(instance.get_orders(1, 1).await.unwrap().is_empty());
 
-        Ok(())
+        instance.delete_order(&exp[0]).await.unwrap();
+        assert_eq!(
+            vec![exp[1].clone()],
+            instance.get_orders(1, 2).await.unwrap()
+        );
     }
 
-    async fn create_ships_table(pool: &SqlitePool) -> DataResult<()> {
-        sqlx::query(
-            "CREATE TABLE IF NOT EXISTS ships (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            stype INTEGER REFERENCES ship_types (id),
-            fleet INTEGER REFERENCES fleets (id),
-            crip INTEGER DEFAULT 0,
-            moth INTEGER DEFAULT 0)",
-        )
-        .execute(pool)
-        .await?;
+    #[tokio::test]
+    async fn order_templates() {
+        let instance = init_data().await;
+        instance.add_empires(empires()).await.unwrap();
+        let orders = vec![Order::new(1, 0, "Patrol Tibron")];
+        let mut exp = OrderTemplate::new(1, "Standing", &orders);
+        exp.id = instance.insert_order_template(&exp).await.unwrap();
+        assert_eq!(
+            vec![exp.clone()],
+            instance.get_order_templates(1).await.unwrap()
+        );
+        assert!(instance.get_order_templates(2).await.unwrap().is_empty());
 
-        Ok(())
+        instance.delete_order_template(&exp).await.unwrap();
+        assert!(instance.get_order_templates(1).await.unwrap().is_empty());
     }
 
-    async fn create_systems_table(pool: &SqlitePool) -> DataResult<()> {
-        sqlx::query(
-            "CREATE TABLE IF NOT EXISTS systems (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            name TEXT,
-            ptype TEXT,
-            raw INTEGER,
-            cap INTEGER,
-            pop INTEGER,
-            mor INTEGER,
-            ind INTEGER,
-            dev INTEGER DEFAULT 0,
-            fails INTEGER DEFAULT 0,
-            owner INTEGER REFERENCES empires (id))",
-        )
-        .execute(pool)
-        .await?;
+    #[tokio::test]
+    async fn insert_empire() {
+        let instance = init_data().await;
+        let mut exp = empires().remove(0);
+        exp.treasury = 25;
+        exp.tech = 2;
+        exp.id = instance.insert_empire(&exp).await.unwrap();
+        let act = instance.get_empires().await.unwrap();
+        assert_eq!(vec![exp], act);
+    }
 
-        Ok(())
+    #[tokio::test]
+    async fn journal() {
+        let instance = init_data().await;
+        assert!(instance.get_journal_entry(1).await.unwrap().is_none());
+
+        let mut entry = JournalEntry::new(2, "Ruled that lanes close in nebulae.");
+        instance.set_journal_entry(&entry).await.unwrap();
+        instance
+            .set_journal_entry(&JournalEntry::new(1, "Opening moves."))
+            .await
+            .unwrap();
+        entry.text = "Nebulae close lanes for a turn.".to_string();
+        instance.set_journal_entry(&entry).await.unwrap();
+        assert_eq!(
+            Some(entry.clone()),
+            instance.get_journal_entry(2).await.unwrap()
+        );
+        let v = instance.get_journal().await.unwrap();
+        assert_eq!(vec![1, 2], v.iter().map(|e| e.turn).collect::<Vec<i32>>());
+
+        entry.text = " \n".to_string();
+        instance.set_journal_entry(&entry).await.unwrap();
+        assert!(instance.get_journal_entry(2).await.unwrap().is_none());
     }
 
-    async fn create_tables(pool: &SqlitePool) -> DataResult<()> {
-        Self::create_controls_table(pool).await?;
-        Self::create_empires_table(pool).await?;
-        Self::create_fleets_table(pool).await?;
-        Self::create_ground_types_table(pool).await?;
-        Self::create_ground_units_table(pool).await?;
-        Self::create_ship_types_table(pool).await?;
-        Self::create_ships_table(pool).await?;
-        Self::create_systems_table(pool).await
+    #[tokio::test]
+    async fn lanes() {
+        let instance = init_data().await;
+        instance.add_systems(systems()).await.unwrap();
+        let mut lane = Lane::new(1, 2, "Major");
+        lane.id = instance.insert_lane(&lane).await.unwrap();
+        let v = instance.get_lanes().await.unwrap();
+        assert_eq!(1, v.len());
+        assert_eq!("Senor Prime", v[0].a_name);
+        assert_eq!("Vadurrinia", v[0].b_name);
+
+        lane.ltype = "Restricted".to_string();
+        lane.defended = true;
+        instance.update_lane(&lane).await.unwrap();
+        let v = instance.get_lanes().await.unwrap();
+        assert_eq!(("Restricted", true), (v[0].ltype.as_str(), v[0].defended));
+
+        instance.delete_lane(&lane).await.unwrap();
+        assert!(instance.get_lanes().await.unwrap().is_empty());
     }
 
-    fn folder() -> DataResult<path::PathBuf> {
-        // Put databases in the user's data directory...
-        let mut dbpath = if let Some(p) = dirs::data_dir() {
-            p
-        } else {
-            path::PathBuf::new()
-        };
-        // ... under the program name.
-        dbpath.push("vbamcma");
+    #[tokio::test]
+    async fn ledger() {
+        let instance = init_data().await;
+        instance.add_empires(empires()).await.unwrap();
+        let mut exp = LedgerEntry::new(1, 2, -4, 1, "Tribute for tech");
+        exp.id = instance.post_ledger(&exp).await.unwrap();
+        assert_eq!(vec![exp], instance.get_ledger(1).await.unwrap());
+        assert!(instance.get_ledger(2).await.unwrap().is_empty());
 
-        // Create folder if it doesn't exist.
-        if !dbpath.exists() {
-            fs::create_dir_all(&dbpath)?
-        }
+        let emp = instance.get_empires().await.unwrap();
+        let senorian = emp.iter().find(|e| e.id == 1).unwrap();
+        assert_eq!((-4, 1), (senorian.treasury, senorian.tech));
+    }
 
-        Ok(dbpath)
+    #[tokio::test]
+    async fn ship_types() {
+        let instance = init_data().await;
+        instance.add_empires(empires()).await.unwrap();
+        let hull = instance.get_hull("Cruiser").await.unwrap();
+        assert_eq!(5, hull.size);
+
+        let mut exp = ShipType::new("Avenger", "Cruiser", 1);
+        exp.atk = 4;
+        exp.cost = exp.construction_cost(&hull);
+        exp.id = instance.insert_ship_type(&exp).await.unwrap();
+        exp.empire_name = "Senorian".to_string();
+        assert_eq!(vec![exp.clone()], instance.get_ship_types().await.unwrap());
+
+        exp.def = 2;
+        instance.update_ship_type(&exp).await.unwrap();
+        assert_eq!(vec![exp.clone()], instance.get_ship_types().await.unwrap());
+
+        instance.delete_ship_type(&exp).await.unwrap();
+        assert!(instance.get_ship_types().await.unwrap().is_empty());
     }
 
-    #[allow(unused)]
-    async fn insert_empire(&self, emp: Empire) -> DataResult<()> {
-        sqlx::query("INSERT INTO empires (name) VALUES(?)")
-            .bind(emp.name.as_str())
-            .execute(&self.pool)
-            .await?;
-        Ok(())
+    #[tokio::test]
+    async fn morale_modifiers() {
+        let instance = init_data().await;
+        instance.add_empires(empires()).await.unwrap();
+        assert!(instance.get_morale_modifiers().await.unwrap().is_empty());
+        instance.set_morale_modifier(1, -1).await.unwrap();
+        instance.set_morale_modifier(2, 1).await.unwrap();
+        instance.set_morale_modifier(2, 0).await.unwrap();
+        let mods = instance.get_morale_modifiers().await.unwrap();
+        assert_eq!(1, mods.len());
+        assert_eq!(Some(&-1), mods.get(&1));
+    }
+
+    #[tokio::test]
+    async fn campaign_options() {
+        let instance = init_data().await;
+        assert_eq!(
+            CampaignOptions::default(),
+            instance.get_campaign_options().await.unwrap()
+        );
+        let options = CampaignOptions {
+            start_turn: 2,
+            empires: 4,
+            difficulty: "Hard".to_string(),
+            strategic_warfare: true,
+            ..Default::default()
+        };
+        instance.set_campaign_options(&options).await.unwrap();
+        assert_eq!(options, instance.get_campaign_options().await.unwrap());
+        // The optional rules share their settings with the rule toggles.
+        assert!(instance.get_rule_option(STRATEGIC_WARFARE).await.unwrap());
     }
 
-    async fn insert_system(&self, sys: System) -> DataResult<()> {
+    #[tokio::test]
+    async fn repair_jobs() {
+        let instance = init_data().await;
+        instance.add_empires(empires()).await.unwrap();
+        instance.add_systems(systems()).await.unwrap();
+        sqlx::query("UPDATE systems SET owner = 1 WHERE id = 1")
+            .execute(&instance.pool)
+            .await
+            .unwrap();
         sqlx::query(
-            "INSERT INTO systems (name, ptype, raw, cap, pop, mor, ind)
-            VALUES(?,?,?,?,?,?,?)",
+            "INSERT INTO fleets (name, owner, location)
+            VALUES ('Home Fleet', 1, 1), ('Patrol', 1, 3)",
         )
-        .bind(sys.name.as_str())
-        .bind(sys.ptype.as_str())
-        .bind(sys.raw)
-        .bind(sys.cap)
-        .bind(sys.pop)
-        .bind(sys.mor)
-        .bind(sys.ind)
-        .execute(&self.pool)
-        .await?;
-        Ok(())
+        .execute(&instance.pool)
+        .await
+        .unwrap();
+        instance
+            .insert_ship_type(&ShipType::new("Warden", "Destroyer", 1))
+            .await
+            .unwrap();
+        // Only crippled ships at home with repairs ordered can be repaired.
+        sqlx::query(
+            "INSERT INTO ships (stype, fleet, crip)
+            VALUES (1, 1, 1), (1, 1, 1), (1, 1, 0), (1, 2, 1), (1, 1, 1)",
+        )
+        .execute(&instance.pool)
+        .await
+        .unwrap();
+        for (ship, system) in [(1, 1), (2, 1), (4, 3)] {
+            let build = Build::new(1, system, construction::REPAIR, ship, "Warden");
+            instance.insert_build(&build).await.unwrap();
+        }
+
+        instance.set_repair_priority(2, 3).await.unwrap();
+        let jobs = instance.get_repair_jobs().await.unwrap();
+        let ids: Vec<i64> = jobs.iter().map(|j| j.ship).collect();
+        assert_eq!(vec![2, 1], ids);
+        assert_eq!(3, jobs[0].priority);
+        assert_eq!("Senor Prime", jobs[0].system_name);
+
+        instance.repair_ships(&[2]).await.unwrap();
+        let jobs = instance.get_repair_jobs().await.unwrap();
+        assert_eq!(1, jobs.len());
+        assert_eq!(1, jobs[0].ship);
+        assert_eq!(2, instance.get_builds().await.unwrap().len());
     }
 
-    fn path(name: &str) -> DataResult<path::PathBuf> {
-        // Create SQLite file name by converting spaces in the campaign name
-        // to underscores and adding the '.db' extension.
-        let dbname = name.replace(' ', "_") + ".db";
+    #[tokio::test]
+    async fn rule_options() {
+        let instance = init_data().await;
+        assert!(!instance.get_rule_option("test").await.unwrap());
+        instance.set_rule_option("test", true).await.unwrap();
+        assert!(instance.get_rule_option("test").await.unwrap());
+        instance.set_rule_option("test", false).await.unwrap();
+        assert!(!instance.get_rule_option("test").await.unwrap());
+
+        assert_eq!(None, instance.get_rule_value("limit").await.unwrap());
+        instance.set_rule_value("limit", 15).await.unwrap();
+        assert_eq!(Some(15), instance.get_rule_value("limit").await.unwrap());
+    }
 
-        let mut dbpath = Self::folder()?;
-        dbpath.push(dbname);
+    #[tokio::test]
+    async fn scorch_orders() {
+        let instance = init_data().await;
+        instance.add_empires(empires()).await.unwrap();
+        instance.add_systems(systems()).await.unwrap();
+        let mut sys = instance.get_system_by_name("Tibron").await.unwrap();
+        instance
+            .insert_build(&Build::new(1, sys.id, "Ground", 1, "Militia"))
+            .await
+            .unwrap();
+        let id = instance
+            .insert_scorch_order(&ScorchOrder::new(1, 2, sys.id, scorch::SHIPYARDS))
+            .await
+            .unwrap();
+        // One order per target.
+        assert!(instance
+            .insert_scorch_order(&ScorchOrder::new(1, 2, sys.id, scorch::SHIPYARDS))
+            .await
+            .is_err());
+        assert!(instance
+            .get_scorch_orders(sys.id, 1)
+            .await
+            .unwrap()
+            .is_empty());
+        let orders = instance.get_scorch_orders(sys.id, 2).await.unwrap();
+        assert_eq!(1, orders.len());
+        assert_eq!(id, orders[0].id);
+        assert_eq!("Tibron", orders[0].system_name);
 
-        Ok(dbpath)
+        sys.ind = 0;
+        instance.apply_scorched_earth(&sys, &orders).await.unwrap();
+        assert_eq!(0, instance.get_system_by_id(sys.id).await.unwrap().ind);
+        assert!(instance.get_builds().await.unwrap().is_empty());
+        assert!(instance
+            .get_scorch_orders(sys.id, 2)
+            .await
+            .unwrap()
+            .is_empty());
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::DataStore;
-    use crate::campaign::empire::tests::empires;
-    use crate::campaign::system::tests::systems;
+    #[tokio::test]
+    async fn set_turn() {
+        let instance = init_data().await;
+        instance.set_turn(3).await.unwrap();
+        assert_eq!(3, instance.current_turn().await.unwrap());
+    }
 
-    async fn init_data() -> DataStore {
-        let pool = sqlx::SqlitePool::connect("sqlite::memory:").await.unwrap();
-        DataStore::create_tables(&pool).await.unwrap();
-        DataStore { pool }
+    #[tokio::test]
+    async fn get_orphans() {
+        let instance = init_data().await;
+        instance.add_empires(empires()).await.unwrap();
+        instance.add_systems(systems()).await.unwrap();
+        let mut sys = instance.get_systems().await.unwrap();
+        sys.sort_by_key(|s| s.id);
+        sys[0].owner = 1;
+        instance.update_system(&sys[0]).await.unwrap();
+        instance.insert_ground_unit(1, sys[0].id).await.unwrap();
+        let stray = instance.insert_ground_unit(1, sys[1].id).await.unwrap();
+        sqlx::query(
+            "INSERT INTO fleets (name, owner, location) VALUES ('Home Fleet', 1, ?),
+            ('Ghost Fleet', 1, NULL)",
+        )
+        .bind(sys[0].id)
+        .execute(&instance.pool)
+        .await
+        .unwrap();
+
+        let v = instance.get_orphans().await.unwrap();
+        assert_eq!(2, v.len());
+        assert_eq!((checklist::GROUND, stray), (v[0].kind.as_str(), v[0].id));
+        assert_eq!(sys[1].name, v[0].system_name);
+        assert_eq!(checklist::FLEET, v[1].kind);
+        assert_eq!(("Ghost Fleet", 0), (v[1].name.as_str(), v[1].system));
     }
 
     #[tokio::test]
-    async fn add_systems() {
+    async fn get_standing() {
         let instance = init_data().await;
+        instance.add_empires(empires()).await.unwrap();
         instance.add_systems(systems()).await.unwrap();
-        for exp in systems() {
-            let act = instance
-                .get_system_by_name(exp.name.as_str())
-                .await
-                .unwrap();
-            assert_eq!(exp.name, act.name);
-            assert_eq!(exp.ptype, act.ptype);
-            assert_eq!(exp.raw, act.raw);
-            assert_eq!(exp.cap, act.cap);
-            assert_eq!(exp.pop, act.pop);
-            assert_eq!(exp.mor, act.mor);
-            assert_eq!(exp.ind, act.ind);
-            assert_eq!(exp.dev, act.dev);
-            assert_eq!(exp.fails, act.fails);
+        let mut sys = instance.get_systems().await.unwrap();
+        sys.sort_by_key(|s| s.id);
+        for s in sys.iter_mut().take(2) {
+            s.owner = 1;
+            instance.update_system(s).await.unwrap();
         }
+        let mut build = Build::new(1, sys[0].id, "Ground", 1, "Militia");
+        build.cost = 4;
+        instance.insert_build(&build).await.unwrap();
+        instance.insert_build(&build).await.unwrap();
+        instance.set_research(1, 0, 3).await.unwrap();
+
+        let s = instance.get_standing(1, 0).await.unwrap();
+        assert_eq!("Senorian", s.name);
+        assert_eq!(
+            (-11, 2, 2, 8, 3),
+            (s.treasury, s.systems, s.builds, s.building, s.research)
+        );
+        let s = instance.get_standing(2, 0).await.unwrap();
+        assert_eq!((0, 0, 0, 0), (s.systems, s.builds, s.building, s.research));
+        assert!(instance.get_standing(99, 0).await.is_err());
     }
 
     #[tokio::test]
-    async fn current_turn() {
+    async fn tech() {
         let instance = init_data().await;
-        assert_eq!(0, instance.current_turn().await.unwrap());
+        instance.add_empires(empires()).await.unwrap();
+        instance.set_research(1, 0, 8).await.unwrap();
+        instance.set_research(1, 0, 5).await.unwrap();
+        instance.set_tech(2, 1, 3).await.unwrap();
+
+        let status = instance.get_tech_status(0).await.unwrap();
+        let senorian = status.iter().find(|t| t.empire == 1).unwrap();
+        assert_eq!(5, senorian.spending);
+        assert_eq!(0, senorian.points);
+        let human = status.iter().find(|t| t.empire == 2).unwrap();
+        assert_eq!((1, 0, 3), (human.tech, human.spending, human.points));
+        assert_eq!(0, instance.get_tech_status(1).await.unwrap()[0].spending);
+
+        let treasury = |v: Vec<Empire>| v.into_iter().find(|e| e.id == 1).unwrap().treasury;
+        assert_eq!(-5, treasury(instance.get_empires().await.unwrap()));
     }
 
     #[tokio::test]
-    async fn delete_system() {
+    async fn treaties() {
         let instance = init_data().await;
-        instance.add_systems(systems()).await.unwrap();
-        for s in systems() {
-            instance.delete_system(&s).await.unwrap();
-            assert!(instance.get_system_by_id(s.id).await.is_err());
-        }
+        instance.add_empires(empires()).await.unwrap();
+        let mut exp = Treaty::new(1, 2, "Armistice", 3, Some(7));
+        exp.id = instance.insert_treaty(&exp).await.unwrap();
+        exp.a_name = "Senorian".to_string();
+        exp.b_name = "Human".to_string();
+        assert_eq!(vec![exp.clone()], instance.get_treaties().await.unwrap());
+
+        instance.delete_treaty(&exp).await.unwrap();
+        assert!(instance.get_treaties().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn update_empire() {
+        let instance = init_data().await;
+        instance.add_empires(empires()).await.unwrap();
+        let mut exp = instance.get_empires().await.unwrap().remove(0);
+        exp.name = "Brindaki Union".to_string();
+        exp.treasury = 40;
+        exp.tech = 3;
+        exp.government = government::DICTATORSHIP.to_string();
+        exp.traits = government::MILITARISTIC.to_string();
+        instance.update_empire(&exp).await.unwrap();
+        let act = instance.get_empires().await.unwrap();
+        assert!(act.contains(&exp));
     }
 
     #[tokio::test]
@@ -538,6 +7079,7 @@ mod tests {
         let original = instance.get_systems().await.unwrap();
         for mut exp in original {
             exp.fails = 1;
+            exp.blockaded = true;
             instance.update_system(&exp).await.unwrap();
             let act = instance.get_system_by_id(exp.id).await.unwrap();
             assert_eq!(exp.name, act.name);
@@ -549,6 +7091,7 @@ mod tests {
             assert_eq!(exp.ind, act.ind);
             assert_eq!(exp.dev, act.dev);
             assert_eq!(exp.fails, act.fails);
+            assert_eq!(exp.blockaded, act.blockaded);
         }
     }
 
@@ -577,4 +7120,150 @@ mod tests {
             assert_eq!(owner_name, act.owner_name);
         }
     }
+
+    #[tokio::test]
+    async fn battle_sites() {
+        let instance = init_data().await;
+        instance.add_systems(systems()).await.unwrap();
+        instance.insert_battle(3, 2).await.unwrap();
+        instance.insert_battle(3, 1).await.unwrap();
+        instance.insert_battle(3, 2).await.unwrap();
+        instance.insert_battle(4, 3).await.unwrap();
+        assert_eq!(vec![1, 2], instance.get_battle_sites(3).await.unwrap());
+        assert_eq!(vec![3], instance.get_battle_sites(4).await.unwrap());
+        assert!(instance.get_battle_sites(5).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn shipyard_work() {
+        let instance = init_data().await;
+        instance.add_empires(empires()).await.unwrap();
+        instance.add_systems(systems()).await.unwrap();
+        let fleet = instance
+            .insert_fleet(&Fleet::new("Home Fleet", 1, 1))
+            .await
+            .unwrap();
+        let mut stype = ShipType::new("Warden", "Destroyer", 1);
+        stype.cost = 8;
+        let stype = instance.insert_ship_type(&stype).await.unwrap();
+        sqlx::query("INSERT INTO ships (stype, fleet, crip) VALUES (?, ?, 1), (?, ?, 0)")
+            .bind(stype)
+            .bind(fleet)
+            .bind(stype)
+            .bind(fleet)
+            .execute(&instance.pool)
+            .await
+            .unwrap();
+        let ships = instance.get_ships_by_owner(1).await.unwrap();
+        assert_eq!(2, ships.len());
+        assert_eq!(
+            (1, 1, 8),
+            (ships[0].owner, ships[0].location, ships[0].cost)
+        );
+        assert!(instance.get_ships_by_owner(2).await.unwrap().is_empty());
+
+        instance.set_mothballed(&[ships[1].id], true).await.unwrap();
+        let after = instance.get_fleet_ships(fleet).await.unwrap();
+        assert!(!after[0].moth && after[1].moth);
+
+        // Scrapping refunds an ordered repair along with the ship's value.
+        let before = treasury(&instance, 1).await;
+        let mut build = Build::new(1, 1, construction::REPAIR, ships[0].id, "Warden");
+        build.cost = 4;
+        instance.insert_build(&build).await.unwrap();
+        instance.scrap_ship(&ships[0], 2).await.unwrap();
+        assert_eq!(before + 2, treasury(&instance, 1).await);
+        assert!(instance.get_builds().await.unwrap().is_empty());
+        assert_eq!(1, instance.get_fleet_ships(fleet).await.unwrap().len());
+    }
+
+    #[tokio::test]
+    async fn wars() {
+        let instance = init_data().await;
+        instance.add_empires(empires()).await.unwrap();
+        instance.insert_wars(&[(2, 1)], 3).await.unwrap();
+        instance
+            .insert_casualties(&[
+                Casualty::new(4, 1, 2, war::SHIP, true, 10),
+                Casualty::new(4, 2, 1, war::GROUND, true, 2),
+            ])
+            .await
+            .unwrap();
+        let wars = instance.get_wars().await.unwrap();
+        assert_eq!(1, wars.len());
+        assert_eq!(
+            (1, 2, 3, None),
+            (wars[0].a, wars[0].b, wars[0].started, wars[0].ended)
+        );
+        assert_eq!("Senorian-Human War", wars[0].name());
+        let casualties = instance.get_casualties().await.unwrap();
+        assert_eq!(2, casualties.len());
+        assert!(casualties.iter().all(|c| c.war == wars[0].id));
+
+        // Peace ends the war; the next battle starts another.
+        instance.end_war(2, 1, 5).await.unwrap();
+        instance
+            .insert_casualties(&[Casualty::new(6, 2, 1, war::SHIP, false, 0)])
+            .await
+            .unwrap();
+        let wars = instance.get_wars().await.unwrap();
+        assert_eq!(
+            vec![Some(5), None],
+            wars.iter().map(|w| w.ended).collect::<Vec<_>>()
+        );
+        assert_eq!(6, wars[1].started);
+        let casualties = instance.get_casualties().await.unwrap();
+        assert_eq!(wars[1].id, casualties[2].war);
+    }
+
+    #[tokio::test]
+    async fn service() {
+        let instance = init_data().await;
+        instance.add_empires(empires()).await.unwrap();
+        instance.add_systems(systems()).await.unwrap();
+        sqlx::query(
+            "INSERT INTO ship_types (class, hull, cost, cr, atk, def, empire)
+            VALUES('Avenger', 'Cruiser', 9, 2, 4, 3, 1), ('Kestrel', 'Frigate', 4, 1, 1, 1, 1)",
+        )
+        .execute(&instance.pool)
+        .await
+        .unwrap();
+        let mut batch = Build::new(1, 1, "Ship", 1, "Avenger");
+        batch.count = 3;
+        batch.id = instance.insert_build(&batch).await.unwrap();
+        instance.finish_build(&batch).await.unwrap();
+        let battle = Service {
+            turn: 1,
+            stype: 1,
+            fought: 3,
+            lost: 2,
+            kills: 2.5,
+            ..Service::default()
+        };
+        instance.insert_service(&[battle]).await.unwrap();
+
+        let records = instance.get_class_records().await.unwrap();
+        assert_eq!(2, records.len());
+        let avenger = &records[0];
+        assert_eq!("Senorian", avenger.empire_name);
+        assert_eq!(
+            (3, 3, 3, 2, 2.5),
+            (
+                avenger.built,
+                avenger.active,
+                avenger.fought,
+                avenger.lost,
+                avenger.kills
+            )
+        );
+        // A class that has done nothing has an empty record.
+        assert_eq!(
+            ("Kestrel", 0, 0.0),
+            (
+                records[1].class.as_str(),
+                records[1].built,
+                records[1].kills
+            )
+        );
+    }
 }
@@ -0,0 +1,146 @@
+// Copyright 2022 David Terhune
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Order submission deadlines.
+
+use std::fmt;
+
+use super::locale::Locale;
+
+/// Where an empire stands with its orders for a turn.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Status {
+    NoDeadline,
+    Pending,
+    Submitted,
+    Late,
+    Missing,
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::NoDeadline => "No deadline",
+                Self::Pending => "Pending",
+                Self::Submitted => "Submitted",
+                Self::Late => "Late",
+                Self::Missing => "Missing",
+            }
+        )
+    }
+}
+
+/// An empire's order deadline for a turn. Dates are ISO 8601 strings, so
+/// they compare correctly as text. The due date is the last day on which
+/// orders are on time.
+#[derive(sqlx::FromRow, Clone, Debug, PartialEq, Eq)]
+pub struct Deadline {
+    pub empire: i64,
+    pub turn: i32,
+    pub due: String,
+    pub submitted: Option<String>,
+    #[sqlx(default)]
+    pub empire_name: String,
+}
+
+impl Deadline {
+    /// Status of the deadline as of the given date.
+    pub fn status(&self, today: &str) -> Status {
+        let day = |d: &str| d.chars().take(10).collect::<String>();
+        match (&self.submitted, self.due.is_empty()) {
+            (Some(_), true) => Status::Submitted,
+            (Some(s), false) if day(s) > self.due => Status::Late,
+            (Some(_), false) => Status::Submitted,
+            (None, true) => Status::NoDeadline,
+            (None, false) if day(today) > self.due => Status::Missing,
+            (None, false) => Status::Pending,
+        }
+    }
+
+    /// Convert to string as a row of tab-separated fields, with dates
+    /// written in the given locale.
+    pub fn as_row(&self, today: &str, locale: &Locale) -> String {
+        format!(
+            "{}\t{}\t{}\t{}",
+            self.empire_name,
+            locale.date(&self.due),
+            locale.date(self.submitted.as_deref().unwrap_or("")),
+            self.status(today)
+        )
+    }
+}
+
+/// Whether the text is a date in YYYY-MM-DD form.
+pub fn is_date(text: &str) -> bool {
+    let parts: Vec<&str> = text.split('-').collect();
+    parts.len() == 3
+        && [4, 2, 2]
+            .iter()
+            .zip(&parts)
+            .all(|(n, p)| p.len() == *n && p.chars().all(|c| c.is_ascii_digit()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_date, Deadline, Status};
+    use crate::campaign::locale;
+
+    fn deadline(due: &str, submitted: Option<&str>) -> Deadline {
+        Deadline {
+            empire: 1,
+            turn: 0,
+            due: due.to_string(),
+            submitted: submitted.map(|s| s.to_string()),
+            empire_name: String::new(),
+        }
+    }
+
+    #[test]
+    fn status() {
+        let today = "2026-10-15 12:00:00";
+        assert_eq!(Status::NoDeadline, deadline("", None).status(today));
+        assert_eq!(Status::Pending, deadline("2026-10-15", None).status(today));
+        assert_eq!(Status::Missing, deadline("2026-10-14", None).status(today));
+        assert_eq!(
+            Status::Submitted,
+            deadline("2026-10-15", Some("2026-10-15 23:00:00")).status(today)
+        );
+        assert_eq!(
+            Status::Late,
+            deadline("2026-10-14", Some("2026-10-15 08:00:00")).status(today)
+        );
+    }
+
+    #[test]
+    fn as_row() {
+        let mut d = deadline("2026-10-14", Some("2026-10-15 08:00:00"));
+        d.empire_name = "Senorian".to_string();
+        assert_eq!(
+            "Senorian\t14.10.2026\t15.10.2026 08:00:00\tLate",
+            d.as_row("2026-10-15", locale::for_tag("de-DE"))
+        );
+    }
+
+    #[test]
+    fn date_format() {
+        assert!(is_date("2026-10-15"));
+        assert!(!is_date("2026-10-5"));
+        assert!(!is_date("15/10/2026"));
+        assert!(!is_date(""));
+    }
+}
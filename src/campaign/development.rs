@@ -0,0 +1,346 @@
+// Copyright 2022 David Terhune
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Development of systems and colonization of unowned ones.
+//!
+//! Development orders are paid for when they are given and resolved in the
+//! construction phase. Raising population, industry or infrastructure takes
+//! a d10 roll, and each failure counts against the system's morale that
+//! turn. A colony is founded whenever a colony ship, any ship able to carry
+//! cargo, waits at the system, and the ship is used up.
+
+use std::collections::BTreeMap;
+
+use super::dice::{Dice, Roll};
+use super::ownership;
+use super::system::System;
+
+/// Raise the system's population by one, up to its capacity.
+pub const POPULATION: &str = "Population";
+
+/// Raise the system's industry by one, up to its capacity.
+pub const INDUSTRY: &str = "Industry";
+
+/// Raise the system's development level by one, up to `MAX_DEV`.
+pub const INFRASTRUCTURE: &str = "Infrastructure";
+
+/// Settle an unowned system.
+pub const COLONY: &str = "Colony";
+
+/// What can be ordered.
+pub const TARGETS: [&str; 4] = [POPULATION, INDUSTRY, INFRASTRUCTURE, COLONY];
+
+/// Highest development level a system can reach.
+pub const MAX_DEV: i32 = 5;
+
+/// Chance in ten of an attempt succeeding at development level zero. Each
+/// level adds one, to at most nine.
+pub const BASE_CHANCE: i32 = 5;
+
+/// A development or colonization order for a turn, with what was paid.
+#[derive(sqlx::FromRow, Clone, Debug, PartialEq, Eq)]
+pub struct Development {
+    pub id: i64,
+    pub empire: i64,
+    pub turn: i32,
+    pub system: i64,
+    pub target: String,
+    pub amount: i32,
+    #[sqlx(default)]
+    pub empire_name: String,
+    #[sqlx(default)]
+    pub system_name: String,
+}
+
+impl Development {
+    /// Create a new development order.
+    pub fn new(empire: i64, turn: i32, system: i64, target: &str, amount: i32) -> Self {
+        Self {
+            id: 0,
+            empire,
+            turn,
+            system,
+            target: target.to_string(),
+            amount,
+            empire_name: String::new(),
+            system_name: String::new(),
+        }
+    }
+
+    /// Convert to string as a row of tab-separated fields.
+    pub fn as_row(&self) -> String {
+        format!(
+            "{}\t{}\t{}\t{}\t{}",
+            self.turn, self.empire_name, self.system_name, self.target, self.amount
+        )
+    }
+}
+
+/// What came of the development orders at a system in a turn.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Resolution {
+    /// The system once the orders were carried out.
+    pub system: System,
+    /// Colony ships used up.
+    pub ships: Vec<i64>,
+    /// Orders that could not be carried out, to be refunded.
+    pub refunded: Vec<Development>,
+    /// What happened, a line for each order.
+    pub report: Vec<String>,
+    /// The dice rolled for the attempts.
+    pub rolls: Vec<Roll>,
+}
+
+/// EP an order costs at a system. Infrastructure costs more at each level.
+pub fn cost(sys: &System, target: &str) -> i32 {
+    match target {
+        POPULATION | INDUSTRY => 5,
+        INFRASTRUCTURE => 10 * (sys.dev + 1),
+        COLONY => 10,
+        _ => 0,
+    }
+}
+
+/// Check an empire may give an order at a system: it must own the system
+/// to develop it, and colonies are founded only where no empire or native
+/// race holds the system.
+pub fn check(sys: &System, empire: i64, target: &str) -> Result<(), String> {
+    if target == COLONY {
+        if sys.owner != 0 {
+            return Err(format!("{} is already owned", sys.name));
+        }
+        if ownership::is_neutral(sys) {
+            return Err(format!("{} is held by the {}", sys.name, sys.minor));
+        }
+        return Ok(());
+    }
+    if sys.owner != empire {
+        return Err(format!("{} is not the empire's system", sys.name));
+    }
+    match target {
+        POPULATION if sys.pop >= sys.cap => Err(format!(
+            "{} is at its capacity of {} population",
+            sys.name, sys.cap
+        )),
+        INDUSTRY if sys.ind >= sys.cap => Err(format!(
+            "{} is at its capacity of {} industry",
+            sys.name, sys.cap
+        )),
+        INFRASTRUCTURE if sys.dev >= MAX_DEV => {
+            Err(format!("{} is at the highest development level", sys.name))
+        }
+        POPULATION | INDUSTRY | INFRASTRUCTURE => Ok(()),
+        _ => Err(format!("Development cannot target {}", target)),
+    }
+}
+
+/// Chance in ten of an attempt at the system succeeding.
+pub fn chance(sys: &System) -> i32 {
+    (BASE_CHANCE + sys.dev).min(9)
+}
+
+/// Make a development attempt with the given d10 roll, returning whether it
+/// succeeded. A failure is counted in the system's failed attempts.
+pub fn attempt(sys: &mut System, target: &str, roll: i32) -> bool {
+    if roll > chance(sys) {
+        sys.fails += 1;
+        return false;
+    }
+    match target {
+        POPULATION => sys.pop = (sys.pop + 1).min(sys.cap),
+        INDUSTRY => sys.ind = (sys.ind + 1).min(sys.cap),
+        INFRASTRUCTURE => sys.dev = (sys.dev + 1).min(MAX_DEV),
+        _ => (),
+    }
+    true
+}
+
+/// Found an empire's colony at an unowned system.
+pub fn colonize(sys: &mut System, empire: i64) {
+    sys.owner = empire;
+    sys.pop = sys.cap.min(1);
+    sys.mor = 1;
+    sys.ind = 0;
+    sys.dev = 0;
+    sys.fails = 0;
+    sys.minor.clear();
+    sys.contender = 0;
+    sys.share = 100;
+}
+
+/// Carry out the orders given at a system in turn, each seeing what those
+/// before it did. `ships` holds the colony ship each empire has at the
+/// system. Orders that can no longer be carried out are refunded. The dice
+/// take in the campaign's dice seed.
+pub fn resolve(
+    sys: &System,
+    orders: &[Development],
+    ships: &BTreeMap<i64, i64>,
+    turn: i32,
+    dice_seed: u64,
+) -> Resolution {
+    let mut r = Resolution {
+        system: sys.clone(),
+        ships: Vec::new(),
+        refunded: Vec::new(),
+        report: Vec::new(),
+        rolls: Vec::new(),
+    };
+    for d in orders {
+        let ship = match d.target.as_str() {
+            COLONY => ships.get(&d.empire).copied(),
+            _ => None,
+        };
+        let problem = match check(&r.system, d.empire, &d.target) {
+            Err(e) => Some(e),
+            Ok(_) if d.target == COLONY && ship.is_none() => {
+                Some("no colony ship was there".to_string())
+            }
+            Ok(_) => None,
+        };
+        if let Some(problem) = problem {
+            r.report.push(format!(
+                "{} {} order at {} refunded: {}",
+                d.empire_name,
+                d.target.to_lowercase(),
+                sys.name,
+                problem
+            ));
+            r.refunded.push(d.clone());
+            continue;
+        }
+
+        if let Some(ship) = ship {
+            colonize(&mut r.system, d.empire);
+            r.ships.push(ship);
+            r.report.push(format!(
+                "{} founded a colony at {}",
+                d.empire_name, sys.name
+            ));
+            continue;
+        }
+        let mut dice = Dice::for_turn(dice_seed, seed(turn, d.id)).with_context(
+            format!(
+                "{} {} at {}",
+                d.empire_name,
+                d.target.to_lowercase(),
+                sys.name
+            )
+            .as_str(),
+        );
+        let roll = dice.roll(10);
+        r.rolls.extend_from_slice(dice.rolls());
+        let success = attempt(&mut r.system, &d.target, roll);
+        r.report.push(format!(
+            "{} {} to raise the {} of {}",
+            d.empire_name,
+            if success { "succeeded" } else { "failed" },
+            d.target.to_lowercase(),
+            sys.name
+        ));
+    }
+    r
+}
+
+/// Seed for an order's roll, so a turn resolves the same way however often
+/// it is replayed.
+pub fn seed(turn: i32, order: i64) -> u64 {
+    ((turn as u64) << 32) ^ (order as u64) ^ 0x4445_5645
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::{
+        attempt, chance, check, colonize, cost, resolve, Development, COLONY, INDUSTRY,
+        INFRASTRUCTURE, MAX_DEV, POPULATION,
+    };
+    use crate::campaign::system::tests::systems;
+
+    #[test]
+    fn limits() {
+        let mut sys = systems()[0].clone();
+        sys.owner = 1;
+        assert!(check(&sys, 2, POPULATION).is_err());
+        assert!(check(&sys, 1, POPULATION).is_ok());
+        assert!(check(&sys, 1, COLONY).is_err());
+        assert!(check(&sys, 1, "Terraforming").is_err());
+
+        sys.pop = sys.cap;
+        sys.ind = sys.cap;
+        sys.dev = MAX_DEV;
+        assert!(check(&sys, 1, POPULATION).is_err());
+        assert!(check(&sys, 1, INDUSTRY).is_err());
+        assert!(check(&sys, 1, INFRASTRUCTURE).is_err());
+
+        sys.owner = 0;
+        assert!(check(&sys, 1, COLONY).is_ok());
+        sys.minor = "Vrokk".to_string();
+        assert!(check(&sys, 1, COLONY).is_err());
+    }
+
+    #[test]
+    fn attempts() {
+        let mut sys = systems()[0].clone();
+        sys.pop = 4;
+        assert_eq!(5, chance(&sys));
+        assert_eq!(10, cost(&sys, INFRASTRUCTURE));
+
+        assert!(attempt(&mut sys, POPULATION, 5));
+        assert_eq!(5, sys.pop);
+        assert!(!attempt(&mut sys, POPULATION, 6));
+        assert_eq!(5, sys.pop);
+        assert_eq!(1, sys.fails);
+
+        assert!(attempt(&mut sys, INFRASTRUCTURE, 1));
+        assert_eq!(1, sys.dev);
+        assert_eq!(6, chance(&sys));
+        assert_eq!(20, cost(&sys, INFRASTRUCTURE));
+    }
+
+    #[test]
+    fn colony() {
+        let mut sys = systems()[0].clone();
+        sys.owner = 0;
+        colonize(&mut sys, 2);
+        assert_eq!(2, sys.owner);
+        assert_eq!(1, sys.pop);
+        assert_eq!(1, sys.mor);
+        assert_eq!(0, sys.ind);
+    }
+
+    #[test]
+    fn rival_colonies() {
+        let mut sys = systems()[3].clone();
+        sys.id = 4;
+        sys.owner = 0;
+        let mut orders = Vec::new();
+        for (id, empire) in [(1, 2), (2, 3)] {
+            let mut d = Development::new(empire, 0, sys.id, COLONY, 10);
+            d.id = id;
+            orders.push(d);
+        }
+        let ships = BTreeMap::from([(2, 7), (3, 8)]);
+
+        // The first order settles the system, so the second is refunded.
+        let r = resolve(&sys, &orders, &ships, 0, 0);
+        assert_eq!(2, r.system.owner);
+        assert_eq!(vec![7], r.ships);
+        assert_eq!(vec![orders[1].clone()], r.refunded);
+        assert_eq!(2, r.report.len());
+        assert!(r.rolls.is_empty());
+    }
+}
@@ -0,0 +1,167 @@
+// Copyright 2022 David Terhune
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Reading imported files however the spreadsheet saved them.
+//!
+//! Spreadsheets set to a European locale separate fields with semicolons,
+//! and older ones save in the Windows code page rather than UTF-8. The
+//! encoding and delimiter are worked out from the file itself, so the GM
+//! never has to say which was used.
+
+use std::{fs, io, path};
+
+/// Delimiters recognized, in order of preference when a line has as many
+/// of one as another.
+pub const DELIMITERS: [u8; 3] = [b',', b';', b'\t'];
+
+// Characters of Windows code page 1252 from 0x80 to 0x9F. The five codes it
+// leaves undefined are read as the control characters of the same value.
+const CP1252: [char; 32] = [
+    '\u{20AC}', '\u{81}', '\u{201A}', '\u{192}', '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{2C6}', '\u{2030}', '\u{160}', '\u{2039}', '\u{152}', '\u{8D}', '\u{17D}', '\u{8F}',
+    '\u{90}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{2DC}', '\u{2122}', '\u{161}', '\u{203A}', '\u{153}', '\u{9D}', '\u{17E}', '\u{178}',
+];
+
+/// Decode the bytes of a text file. A byte order mark marks UTF-8 or
+/// UTF-16; otherwise the text is UTF-8 if it can be, and Windows-1252 if not.
+pub fn decode(bytes: &[u8]) -> String {
+    if let Some(rest) = bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]) {
+        return String::from_utf8_lossy(rest).into_owned();
+    }
+    if let Some(rest) = bytes.strip_prefix(&[0xFF, 0xFE]) {
+        return utf16(rest, u16::from_le_bytes);
+    }
+    if let Some(rest) = bytes.strip_prefix(&[0xFE, 0xFF]) {
+        return utf16(rest, u16::from_be_bytes);
+    }
+    match std::str::from_utf8(bytes) {
+        Ok(s) => s.to_string(),
+        Err(_) => bytes
+            .iter()
+            .map(|b| match b {
+                0x80..=0x9F => CP1252[(b - 0x80) as usize],
+                _ => *b as char,
+            })
+            .collect(),
+    }
+}
+
+/// The delimiter of CSV text: the one given by a leading "sep=" line, as
+/// Excel writes, or else the one found most often outside quotes in the
+/// first line.
+pub fn delimiter(text: &str) -> u8 {
+    let first = text.lines().find(|l| !l.trim().is_empty()).unwrap_or("");
+    if let Some(sep) = hint(first) {
+        return sep;
+    }
+    let mut counts = [0; DELIMITERS.len()];
+    let mut quoted = false;
+    for b in first.bytes() {
+        if b == b'"' {
+            quoted = !quoted;
+        } else if let Some(i) = DELIMITERS.iter().position(|d| *d == b && !quoted) {
+            counts[i] += 1;
+        }
+    }
+    // The first of the most frequent, so a line with none is read as commas.
+    let mut best = 0;
+    for (i, n) in counts.iter().enumerate() {
+        if *n > counts[best] {
+            best = i;
+        }
+    }
+    DELIMITERS[best]
+}
+
+/// A CSV reader of text in whichever dialect it was written. A flexible
+/// reader allows rows of different lengths.
+pub fn reader(text: String, flexible: bool) -> csv::Reader<io::Cursor<String>> {
+    let sep = delimiter(&text);
+    // The "sep=" line is not part of the data.
+    let text = match text.split_once('\n') {
+        Some((first, rest)) if hint(first).is_some() => rest.to_string(),
+        _ => text,
+    };
+    csv::ReaderBuilder::new()
+        .delimiter(sep)
+        .flexible(flexible)
+        .from_reader(io::Cursor::new(text))
+}
+
+/// Read a text file in whatever encoding it was saved.
+pub fn read_file<P: AsRef<path::Path>>(file: P) -> io::Result<String> {
+    fs::read(file).map(|b| decode(&b))
+}
+
+/// Open a CSV file in whatever dialect and encoding it was saved.
+pub fn open<P: AsRef<path::Path>>(
+    file: P,
+    flexible: bool,
+) -> io::Result<csv::Reader<io::Cursor<String>>> {
+    read_file(file).map(|text| reader(text, flexible))
+}
+
+// The delimiter named by an Excel "sep=" line.
+fn hint(line: &str) -> Option<u8> {
+    match line.trim_end().strip_prefix("sep=")?.as_bytes() {
+        [sep] => Some(*sep),
+        _ => None,
+    }
+}
+
+// Decode UTF-16 text, given how to read each code unit.
+fn utf16(bytes: &[u8], unit: fn([u8; 2]) -> u16) -> String {
+    let units: Vec<u16> = bytes.chunks_exact(2).map(|c| unit([c[0], c[1]])).collect();
+    String::from_utf16_lossy(&units)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode, delimiter, reader};
+
+    #[test]
+    fn encodings() {
+        assert_eq!("Zev'rch", decode(b"Zev'rch"));
+        assert_eq!("Zev'rch", decode(b"\xEF\xBB\xBFZev'rch"));
+        assert_eq!("Gödel €5", decode("Gödel €5".as_bytes()));
+        assert_eq!("Gödel €5", decode(b"G\xF6del \x805"));
+        assert_eq!("Hé", decode(b"\xFF\xFEH\x00\xE9\x00"));
+        assert_eq!("Hé", decode(b"\xFE\xFF\x00H\x00\xE9"));
+    }
+
+    #[test]
+    fn delimiters() {
+        assert_eq!(b',', delimiter("NAME,TYPE,RAW\n"));
+        assert_eq!(b';', delimiter("NAME;TYPE;RAW\n"));
+        assert_eq!(b'\t', delimiter("NAME\tTYPE\tRAW\n"));
+        assert_eq!(b';', delimiter("\n\"Fleet, Home\";Empire\n"));
+        assert_eq!(b',', delimiter("sep=,\nNAME;TYPE;RAW\n"));
+        assert_eq!(b',', delimiter("NAME\n"));
+    }
+
+    #[test]
+    fn records() {
+        let text = "sep=;\r\nNAME;SHIPS\r\nHome Fleet;\"2x Warhound; Kraken\"\r\n";
+        let mut rdr = reader(text.to_string(), false);
+        assert_eq!(
+            vec!["NAME", "SHIPS"],
+            rdr.headers().unwrap().iter().collect::<Vec<_>>()
+        );
+        let rows: Vec<csv::StringRecord> = rdr.records().map(Result::unwrap).collect();
+        assert_eq!(1, rows.len());
+        assert_eq!(Some("2x Warhound; Kraken"), rows[0].get(1));
+    }
+}
@@ -0,0 +1,147 @@
+// Copyright 2022 David Terhune
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Dice for every roll the campaign makes: battles, raids, invasions,
+//! espionage, development and research.
+//!
+//! Dice keep the rolls they make, so they can be written to the audit log,
+//! and are seeded so a roll made again gives the same result. Rolls made as
+//! a turn is resolved also take in the campaign's dice seed, which the GM
+//! may set to have every turn played out differently from another campaign.
+
+use std::fmt;
+
+/// A roll of one die: what it was rolled for, its number of sides, and the
+/// result.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Roll {
+    pub context: String,
+    pub sides: i32,
+    pub result: i32,
+}
+
+impl fmt::Display for Roll {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: d{} rolled {}",
+            self.context, self.sides, self.result
+        )
+    }
+}
+
+/// Small deterministic random number generator (SplitMix64), so a battle
+/// fought with the same seed always has the same result.
+#[derive(Clone, Debug)]
+pub struct Dice {
+    state: u64,
+    context: String,
+    rolls: Vec<Roll>,
+}
+
+impl Dice {
+    /// Create dice from a seed.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            state: seed,
+            context: String::new(),
+            rolls: Vec::new(),
+        }
+    }
+
+    /// Create dice for rolls made as a turn is resolved, from the campaign's
+    /// dice seed and the seed of what is rolled for. A campaign without a
+    /// dice seed rolls as it always has.
+    pub fn for_turn(campaign: u64, seed: u64) -> Self {
+        Self::new(campaign ^ seed)
+    }
+
+    /// Name what the rolls are made for, as the log shows it.
+    pub fn with_context(self, context: &str) -> Self {
+        Self {
+            context: context.to_string(),
+            ..self
+        }
+    }
+
+    /// Roll one die of the given number of sides.
+    pub fn roll(&mut self, sides: i32) -> i32 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+        let result = (z % sides.max(1) as u64) as i32 + 1;
+        self.rolls.push(Roll {
+            context: self.context.clone(),
+            sides,
+            result,
+        });
+        result
+    }
+
+    /// The rolls made so far, oldest first.
+    pub fn rolls(&self) -> &[Roll] {
+        &self.rolls
+    }
+}
+
+/// Read a dice seed as the GM writes it. Blank text is no seed.
+pub fn parse_seed(text: &str) -> Result<u64, String> {
+    match text.trim() {
+        "" => Ok(0),
+        t => t
+            .parse()
+            .map_err(|_| format!("{} is not a dice seed: use a whole number", t)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_seed, Dice};
+
+    #[test]
+    fn dice() {
+        let mut a = Dice::new(42);
+        let mut b = Dice::new(42);
+        let rolls: Vec<i32> = (0..100).map(|_| a.roll(10)).collect();
+        assert!(rolls.iter().all(|r| (1..=10).contains(r)));
+        assert_eq!(rolls, (0..100).map(|_| b.roll(10)).collect::<Vec<i32>>());
+    }
+
+    #[test]
+    fn recorded() {
+        let mut dice = Dice::new(7).with_context("Battle at Tibron");
+        let first = dice.roll(10);
+        dice.roll(6);
+        assert_eq!(2, dice.rolls().len());
+        assert_eq!(
+            format!("Battle at Tibron: d10 rolled {}", first),
+            dice.rolls()[0].to_string()
+        );
+        assert_eq!(6, dice.rolls()[1].sides);
+    }
+
+    #[test]
+    fn seeded() {
+        let roll = |mut d: Dice| (0..10).map(|_| d.roll(10)).collect::<Vec<i32>>();
+        assert_eq!(roll(Dice::new(99)), roll(Dice::for_turn(0, 99)));
+        assert_eq!(roll(Dice::for_turn(5, 99)), roll(Dice::for_turn(5, 99)));
+        assert_ne!(roll(Dice::for_turn(5, 99)), roll(Dice::for_turn(6, 99)));
+        assert_eq!(Ok(0), parse_seed(" "));
+        assert_eq!(Ok(1234), parse_seed("1234"));
+        assert!(parse_seed("-1").is_err());
+    }
+}
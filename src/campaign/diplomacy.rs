@@ -0,0 +1,110 @@
+// Copyright 2022 David Terhune
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Interface to treaties between empires.
+
+/// Kinds of treaty two empires can sign.
+pub const TREATY_TYPES: [&str; 4] = ["Non-Aggression", "Armistice", "Trade", "Alliance"];
+
+/// A treaty between two empires. A treaty with an expiration turn lapses at
+/// the end of that turn; one without stays in force until it is ended.
+#[derive(sqlx::FromRow, Clone, Debug, PartialEq, Eq)]
+pub struct Treaty {
+    pub id: i64,
+    pub a: i64,
+    pub b: i64,
+    pub ttype: String,
+    pub signed: i32,
+    pub expires: Option<i32>,
+    #[sqlx(default)]
+    pub a_name: String,
+    #[sqlx(default)]
+    pub b_name: String,
+}
+
+impl Treaty {
+    /// Create a new treaty signed on the given turn.
+    pub fn new(a: i64, b: i64, ttype: &str, signed: i32, expires: Option<i32>) -> Self {
+        Self {
+            id: 0,
+            a,
+            b,
+            ttype: ttype.to_string(),
+            signed,
+            expires,
+            a_name: String::new(),
+            b_name: String::new(),
+        }
+    }
+
+    /// Convert to string as a row of tab-separated fields.
+    pub fn as_row(&self) -> String {
+        format!(
+            "{}\t{}\t{}\t{}\t{}",
+            self.a_name,
+            self.b_name,
+            self.ttype,
+            self.signed,
+            match self.expires {
+                Some(t) => t.to_string(),
+                None => "Never".to_string(),
+            }
+        )
+    }
+
+    /// Whether the treaty lapses at the end of the given turn.
+    pub fn lapses(&self, turn: i32) -> bool {
+        match self.expires {
+            Some(t) => t <= turn,
+            None => false,
+        }
+    }
+
+    /// Description of the treaty for reports.
+    pub fn describe(&self) -> String {
+        format!(
+            "{} treaty between {} and {}",
+            self.ttype, self.a_name, self.b_name
+        )
+    }
+}
+
+/// Expiration turn of a treaty signed on the given turn that is in force for
+/// a number of turns, including the turn it is signed. A duration of zero or
+/// less makes the treaty indefinite.
+pub fn expiry(signed: i32, duration: i32) -> Option<i32> {
+    if duration > 0 {
+        Some(signed + duration - 1)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{expiry, Treaty};
+
+    #[test]
+    fn duration() {
+        let t = Treaty::new(1, 2, "Non-Aggression", 3, expiry(3, 10));
+        assert_eq!(Some(12), t.expires);
+        assert!(!t.lapses(11));
+        assert!(t.lapses(12));
+
+        let t = Treaty::new(1, 2, "Alliance", 3, expiry(3, 0));
+        assert_eq!(None, t.expires);
+        assert!(!t.lapses(1000));
+    }
+}
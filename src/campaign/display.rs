@@ -0,0 +1,423 @@
+// Copyright 2022 David Terhune
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Presentation of records as rows of text.
+//!
+//! The browser, reports and exports each want the same record laid out
+//! differently. A view picks the columns, their labels and the format, and
+//! renders headers and rows to match.
+
+use std::cmp::Ordering;
+
+use super::ownership;
+use super::system::System;
+
+/// Output format of a row.
+#[allow(unused)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// Tab-separated fields, as the browsers expect.
+    Tab,
+    /// Fields padded to fixed widths, for plain text reports.
+    Aligned,
+    /// Comma-separated fields, quoted where needed.
+    Csv,
+    /// An HTML table row.
+    Html,
+}
+
+/// A column of a system row.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Column {
+    Name,
+    Type,
+    Raw,
+    Cap,
+    Pop,
+    Mor,
+    Ind,
+    Dev,
+    Fails,
+    Blockade,
+    Policy,
+    Owner,
+}
+
+impl Column {
+    /// Every column, in the order the browser shows them.
+    pub const ALL: [Column; 12] = [
+        Column::Name,
+        Column::Type,
+        Column::Raw,
+        Column::Cap,
+        Column::Pop,
+        Column::Mor,
+        Column::Ind,
+        Column::Dev,
+        Column::Fails,
+        Column::Blockade,
+        Column::Policy,
+        Column::Owner,
+    ];
+
+    // Width of the column in aligned text.
+    fn width(&self) -> usize {
+        match self {
+            Self::Name | Self::Owner => 20,
+            Self::Policy => 12,
+            Self::Type | Self::Blockade => 8,
+            _ => 5,
+        }
+    }
+
+    // Whether the column holds a number, which aligns to the right.
+    fn numeric(&self) -> bool {
+        !matches!(
+            self,
+            Self::Name | Self::Type | Self::Blockade | Self::Policy | Self::Owner
+        )
+    }
+
+    /// Order two systems by this column: numbers by value, text without
+    /// regard to case, and unblockaded systems before blockaded ones. Ties
+    /// fall back to the name so the order is stable.
+    pub fn compare(&self, a: &System, b: &System) -> Ordering {
+        let by_name = || a.name.to_lowercase().cmp(&b.name.to_lowercase());
+        let ord = match self {
+            Self::Name => return by_name(),
+            Self::Type => a.ptype.to_lowercase().cmp(&b.ptype.to_lowercase()),
+            Self::Raw => a.raw.cmp(&b.raw),
+            Self::Cap => a.cap.cmp(&b.cap),
+            Self::Pop => a.pop.cmp(&b.pop),
+            Self::Mor => a.mor.cmp(&b.mor),
+            Self::Ind => a.ind.cmp(&b.ind),
+            Self::Dev => a.dev.cmp(&b.dev),
+            Self::Fails => a.fails.cmp(&b.fails),
+            Self::Blockade => a.blockaded.cmp(&b.blockaded),
+            Self::Policy => a.policy.cmp(&b.policy),
+            Self::Owner => a
+                .owner_name
+                .to_lowercase()
+                .cmp(&b.owner_name.to_lowercase()),
+        };
+        ord.then_with(by_name)
+    }
+}
+
+/// Which systems to show: those of an owner (0 for unowned), of a planet
+/// type, and whose name, owner's name or planet type holds the search text,
+/// ignoring case. Unset criteria match every system.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SystemFilter {
+    pub owner: Option<i64>,
+    pub ptype: Option<String>,
+    pub search: String,
+}
+
+impl SystemFilter {
+    /// Whether the system meets every criterion.
+    pub fn matches(&self, sys: &System) -> bool {
+        let text = self.search.trim().to_lowercase();
+        self.owner.is_none_or(|o| sys.owner == o)
+            && self.ptype.as_ref().is_none_or(|t| sys.ptype == *t)
+            && [&sys.name, &sys.owner_name, &sys.ptype]
+                .iter()
+                .any(|field| field.to_lowercase().contains(text.as_str()))
+    }
+}
+
+/// Text used for headers and flag values, so a view can be shown in another
+/// language.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Labels {
+    pub headers: [String; 12],
+    pub yes: String,
+}
+
+impl Labels {
+    /// Label of a column.
+    pub fn header(&self, col: Column) -> &str {
+        self.headers[col as usize].as_str()
+    }
+}
+
+impl Default for Labels {
+    fn default() -> Self {
+        let headers = [
+            "Name", "Type", "RAW", "CAP", "POP", "MOR", "IND", "Dev", "Fails", "Blockade",
+            "Policy", "Owner",
+        ];
+        Self {
+            headers: headers.map(|h| h.to_string()),
+            yes: "Yes".to_string(),
+        }
+    }
+}
+
+/// A rendering of systems: which columns, how they are labeled, and in what
+/// format.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SystemView {
+    pub columns: Vec<Column>,
+    pub format: Format,
+    pub labels: Labels,
+}
+
+impl Default for SystemView {
+    fn default() -> Self {
+        Self::new(Format::Tab)
+    }
+}
+
+impl SystemView {
+    /// View of every column in the given format.
+    pub fn new(format: Format) -> Self {
+        Self {
+            columns: Column::ALL.to_vec(),
+            format,
+            labels: Labels::default(),
+        }
+    }
+
+    /// Restrict the view to the given columns, in the given order.
+    #[allow(unused)]
+    pub fn with_columns(self, columns: &[Column]) -> Self {
+        Self {
+            columns: columns.to_vec(),
+            ..self
+        }
+    }
+
+    /// Use other labels for headers and flags.
+    #[allow(unused)]
+    pub fn with_labels(self, labels: Labels) -> Self {
+        Self { labels, ..self }
+    }
+
+    /// Labels of the columns.
+    pub fn headers(&self) -> Vec<String> {
+        self.columns
+            .iter()
+            .map(|c| self.labels.header(*c).to_string())
+            .collect()
+    }
+
+    /// Header row naming the columns.
+    pub fn header(&self) -> String {
+        self.join(&self.headers(), true)
+    }
+
+    /// Values of a system's columns, unformatted.
+    pub fn fields(&self, sys: &System) -> Vec<String> {
+        self.columns.iter().map(|c| self.field(sys, *c)).collect()
+    }
+
+    /// A system as a row.
+    pub fn row(&self, sys: &System) -> String {
+        self.join(&self.fields(sys), false)
+    }
+
+    /// Header and a row for each system, one per line.
+    #[allow(unused)]
+    pub fn table(&self, systems: &[System]) -> String {
+        let mut lines = vec![self.header()];
+        lines.extend(systems.iter().map(|s| self.row(s)));
+        let mut text = lines.join("\n");
+        text.push('\n');
+        text
+    }
+
+    // Value of one column of a system.
+    fn field(&self, sys: &System, col: Column) -> String {
+        match col {
+            Column::Name => sys.name.clone(),
+            Column::Type => sys.ptype.clone(),
+            Column::Raw => sys.raw.to_string(),
+            Column::Cap => sys.cap.to_string(),
+            Column::Pop => sys.pop.to_string(),
+            Column::Mor => sys.mor.to_string(),
+            Column::Ind => sys.ind.to_string(),
+            Column::Dev => sys.dev.to_string(),
+            Column::Fails => sys.fails.to_string(),
+            Column::Blockade => {
+                if sys.blockaded {
+                    self.labels.yes.clone()
+                } else {
+                    String::new()
+                }
+            }
+            Column::Policy => sys.policy.clone(),
+            Column::Owner => ownership::describe(sys),
+        }
+    }
+
+    // Join fields into a row in the view's format.
+    fn join(&self, fields: &[String], header: bool) -> String {
+        match self.format {
+            Format::Tab => fields.join("\t"),
+            Format::Aligned => {
+                let cells: Vec<String> = self
+                    .columns
+                    .iter()
+                    .zip(fields)
+                    .map(|(c, f)| {
+                        if c.numeric() {
+                            format!("{:>1$}", f, c.width())
+                        } else {
+                            format!("{:<1$}", f, c.width())
+                        }
+                    })
+                    .collect();
+                cells.join(" ").trim_end().to_string()
+            }
+            Format::Csv => {
+                let cells: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+                cells.join(",")
+            }
+            Format::Html => {
+                let tag = if header { "th" } else { "td" };
+                let cells: String = fields
+                    .iter()
+                    .map(|f| format!("<{0}>{1}</{0}>", tag, html_escape(f)))
+                    .collect();
+                format!("<tr>{}</tr>", cells)
+            }
+        }
+    }
+}
+
+// Quote a CSV field if it holds a separator, quote or line break.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Escape text for HTML.
+pub fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Column, Format, Labels, SystemFilter, SystemView};
+    use crate::campaign::system::tests::systems;
+
+    #[test]
+    fn compare() {
+        let mut v = systems();
+        v.sort_by(|a, b| Column::Name.compare(a, b));
+        let names: Vec<&str> = v.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(
+            vec!["Senor Prime", "Tibron", "Vadurrinia", "Zev'rch"],
+            names
+        );
+
+        v.sort_by(|a, b| Column::Pop.compare(b, a));
+        assert!(v.windows(2).all(|w| w[0].pop >= w[1].pop));
+        v.sort_by(|a, b| Column::Raw.compare(a, b));
+        assert!(v.windows(2).all(|w| w[0].raw <= w[1].raw));
+    }
+
+    #[test]
+    fn filter() {
+        let v = systems();
+        let count = |f: &SystemFilter| v.iter().filter(|s| f.matches(s)).count();
+        assert_eq!(v.len(), count(&SystemFilter::default()));
+
+        let f = SystemFilter {
+            search: " PRIME ".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(1, count(&f));
+        let f = SystemFilter {
+            search: "barren".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(2, count(&f));
+        let mut owned = v.clone();
+        owned[1].owner_name = "Senorian".to_string();
+        let f = SystemFilter {
+            search: "senor".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(2, owned.iter().filter(|s| f.matches(s)).count());
+
+        let f = SystemFilter {
+            ptype: Some(v[0].ptype.clone()),
+            ..Default::default()
+        };
+        assert_eq!(
+            v.iter().filter(|s| s.ptype == v[0].ptype).count(),
+            count(&f)
+        );
+
+        let f = SystemFilter {
+            owner: Some(v[0].owner),
+            search: "zzz".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(0, count(&f));
+    }
+
+    #[test]
+    fn formats() {
+        let mut sys = systems()[0].clone();
+        sys.blockaded = true;
+        assert_eq!(
+            "Senor Prime\tHW\t5\t12\t10\t8\t10\t0\t0\tYes\t\tNone",
+            SystemView::default().row(&sys)
+        );
+
+        let cols = [Column::Name, Column::Pop, Column::Owner];
+        let view = SystemView::new(Format::Csv).with_columns(&cols);
+        assert_eq!("Name,POP,Owner", view.header());
+        sys.name = "Senor, \"Prime\"".to_string();
+        assert_eq!("\"Senor, \"\"Prime\"\"\",10,None", view.row(&sys));
+
+        let view = SystemView::new(Format::Html).with_columns(&cols);
+        sys.name = "<Senor>".to_string();
+        assert_eq!(
+            "<tr><td>&lt;Senor&gt;</td><td>10</td><td>None</td></tr>",
+            view.row(&sys)
+        );
+        assert_eq!(
+            "<tr><th>Name</th><th>POP</th><th>Owner</th></tr>",
+            view.header()
+        );
+
+        let view = SystemView::new(Format::Aligned).with_columns(&[Column::Pop, Column::Name]);
+        assert_eq!("   10 <Senor>", view.row(&sys));
+    }
+
+    #[test]
+    fn labels() {
+        let mut labels = Labels::default();
+        labels.headers[Column::Blockade as usize] = "Blocus".to_string();
+        labels.yes = "Oui".to_string();
+        let view = SystemView::default()
+            .with_columns(&[Column::Blockade])
+            .with_labels(labels);
+        let mut sys = systems()[0].clone();
+        sys.blockaded = true;
+        assert_eq!("Blocus", view.header());
+        assert_eq!("Oui", view.row(&sys));
+    }
+}
@@ -0,0 +1,449 @@
+// Copyright 2022 David Terhune
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Economic calculations.
+
+use std::fmt;
+
+use super::empire::Empire;
+use super::government::Modifiers;
+use super::locale::Locale;
+use super::ownership;
+use super::ruleset::Ruleset;
+use super::system::{System, FREE_PORT, MARTIAL_LAW, TAX_EXEMPT};
+
+/// Control key of the optional colonial administration rule.
+pub const COLONIAL_ADMIN: &str = "colonial_admin";
+
+/// Control key of the deficit an empire's treasury may be taken to by hand.
+pub const DEFICIT_LIMIT: &str = "deficit_limit";
+
+/// Systems any empire can administer under the colonial administration
+/// rule, before tech and capital bonuses.
+pub const ADMIN_BASE: i32 = 4;
+
+/// Productivity lost for each system beyond the administration limit.
+pub const ADMIN_PENALTY: i32 = 2;
+
+/// Number of systems an empire can administer efficiently under the optional
+/// colonial administration rule.
+///
+/// Every empire manages four systems, plus one per tech level, plus one for
+/// every five points of population at its capital. The capital is taken to
+/// be the empire's most populous system.
+pub fn admin_limit(tech: i32, systems: &[System]) -> i32 {
+    let capital = systems.iter().map(|s| s.pop).max().unwrap_or(0);
+    ADMIN_BASE + tech.max(0) + capital / 5
+}
+
+/// How a system's income is computed, step by step.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct IncomeBreakdown {
+    pub base: i32,
+    pub morale: i32,
+    pub blockade: i32,
+    pub policy: i32,
+    pub trade: i32,
+}
+
+impl IncomeBreakdown {
+    /// Compute the income breakdown for a system under the rules in use.
+    ///
+    /// Base income is the system's income by the edition's formula. A
+    /// system with no morale produces nothing, and one whose morale is below
+    /// half its population loses half its base, unless it is under martial
+    /// law. A blockade halves whatever is left. A tax-exempt system then
+    /// yields nothing, and a free port that is not blockaded yields another
+    /// quarter. Trade is zero unless trade income has been assigned to the
+    /// system.
+    pub fn new(sys: &System, rules: &dyn Ruleset) -> Self {
+        let base = rules.system_income(sys);
+        let morale = if sys.policy == MARTIAL_LAW {
+            0
+        } else if sys.mor <= 0 {
+            -base
+        } else if 2 * sys.mor < sys.pop {
+            -(base / 2)
+        } else {
+            0
+        };
+        let blockade = if sys.blockaded {
+            -((base + morale) / 2)
+        } else {
+            0
+        };
+        let left = base + morale + blockade;
+        let policy = match sys.policy.as_str() {
+            TAX_EXEMPT => -left,
+            FREE_PORT if !sys.blockaded => left / 4,
+            _ => 0,
+        };
+
+        Self {
+            base,
+            morale,
+            blockade,
+            policy,
+            trade: 0,
+        }
+    }
+
+    /// Total income, never less than zero.
+    pub fn total(&self) -> i32 {
+        (self.base + self.morale + self.blockade + self.policy + self.trade).max(0)
+    }
+
+    /// Convert to string as a row of tab-separated fields.
+    pub fn as_row(&self) -> String {
+        format!(
+            "{}\t{}\t{}\t{}\t{}\t{}",
+            self.base,
+            self.morale,
+            self.blockade,
+            self.policy,
+            self.trade,
+            self.total()
+        )
+    }
+}
+
+impl fmt::Display for IncomeBreakdown {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Base income:\t{}", self.base)?;
+        writeln!(f, "Morale modifier:\t{}", self.morale)?;
+        writeln!(f, "Blockade penalty:\t{}", self.blockade)?;
+        writeln!(f, "Policy:\t{}", self.policy)?;
+        writeln!(f, "Trade contribution:\t{}", self.trade)?;
+        write!(f, "Total income:\t{}", self.total())
+    }
+}
+
+/// An empire's income for a turn.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EmpireIncome {
+    pub census: i32,
+    pub productivity: i32,
+    pub trade: i32,
+    pub admin: i32,
+    pub government: i32,
+    pub maintenance: i32,
+}
+
+impl EmpireIncome {
+    /// Compute an empire's income from the systems it owns and the
+    /// maintenance it owes, under the rules in use.
+    ///
+    /// Census income is the edition's census of the population of systems
+    /// that still have morale and are not tax-exempt. Productivity is the sum of the systems'
+    /// incomes apart from trade, which is totalled separately. A contested
+    /// system yields only the owner's share of each, rounded down.
+    pub fn new(systems: &[System], maintenance: i32, rules: &dyn Ruleset) -> Self {
+        let mut inc = Self {
+            maintenance,
+            ..Default::default()
+        };
+        for s in systems {
+            let share = |n: i32| n * ownership::owner_share(s) / 100;
+            if s.mor > 0 && s.policy != TAX_EXEMPT {
+                inc.census += share(rules.census(s.pop));
+            }
+            let b = IncomeBreakdown::new(s, rules);
+            inc.trade += share(b.trade);
+            inc.productivity += share(b.total() - b.trade);
+        }
+        inc
+    }
+
+    /// Apply the colonial administration rule. Each system beyond the
+    /// empire's limit costs some productivity, though never more than the
+    /// empire produces.
+    pub fn with_admin_limit(self, tech: i32, systems: &[System]) -> Self {
+        let excess = (systems.len() as i32 - admin_limit(tech, systems)).max(0);
+        Self {
+            admin: (excess * ADMIN_PENALTY).min(self.productivity),
+            ..self
+        }
+    }
+
+    /// Apply the modifiers of the empire's government and traits, which
+    /// add a percentage of its income apart from trade and another of its
+    /// trade. A loss never takes more than the empire earns.
+    pub fn with_government(self, m: &Modifiers) -> Self {
+        let base = (self.census + self.productivity - self.admin).max(0);
+        let trade = self.trade.max(0);
+        Self {
+            government: base * m.income / 100 + trade * m.trade / 100,
+            ..self
+        }
+    }
+
+    /// Income before maintenance.
+    pub fn gross(&self) -> i32 {
+        self.census + self.productivity - self.admin + self.trade + self.government
+    }
+
+    /// Income after maintenance.
+    pub fn net(&self) -> i32 {
+        self.gross() - self.maintenance
+    }
+}
+
+impl fmt::Display for EmpireIncome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Census:\t{}", self.census)?;
+        writeln!(f, "Productivity:\t{}", self.productivity)?;
+        writeln!(f, "Trade:\t{}", self.trade)?;
+        if self.admin > 0 {
+            writeln!(f, "Administration:\t{}", -self.admin)?;
+        }
+        if self.government != 0 {
+            writeln!(f, "Government:\t{}", self.government)?;
+        }
+        writeln!(f, "Maintenance:\t{}", -self.maintenance)?;
+        write!(f, "Net income:\t{}", self.net())
+    }
+}
+
+/// Produce the GM's economic phase report: the income breakdown of every
+/// owned system, grouped by owner, with a total per empire. If the colonial
+/// administration rule is in use, the total is reduced for empires beyond
+/// their limit, and those empires are flagged as over-extended. Totals are
+/// written in the campaign's locale.
+pub fn economic_report(
+    systems: &[System],
+    empires: &[Empire],
+    admin_limits: bool,
+    rules: &dyn Ruleset,
+    locale: &Locale,
+) -> String {
+    let mut owned: Vec<&System> = systems.iter().filter(|s| s.owner != 0).collect();
+    owned.sort_by(|a, b| {
+        a.owner_name
+            .cmp(&b.owner_name)
+            .then_with(|| a.name.cmp(&b.name))
+    });
+
+    let total = |owner: i64, name: &str, subtotal: i32| -> String {
+        let mut line = String::new();
+        if admin_limits {
+            let held: Vec<System> = systems
+                .iter()
+                .filter(|s| s.owner == owner)
+                .cloned()
+                .collect();
+            let tech = empires.iter().find(|e| e.id == owner).map_or(0, |e| e.tech);
+            let limit = admin_limit(tech, &held);
+            if held.len() as i32 > limit {
+                let inc = EmpireIncome::new(&held, 0, rules).with_admin_limit(tech, &held);
+                line.push_str(&format!(
+                    "OVER-EXTENDED: {} systems, limit {}\nAdministration:\t{}\n",
+                    held.len(),
+                    limit,
+                    -inc.admin
+                ));
+                return format!(
+                    "{}Total for {}:\t{}\n",
+                    line,
+                    name,
+                    locale.number((subtotal - inc.admin) as i64)
+                );
+            }
+        }
+        format!("Total for {}:\t{}\n", name, locale.number(subtotal as i64))
+    };
+
+    let mut report = String::from("Economic Phase Report\n");
+    let mut current: Option<&System> = None;
+    let mut subtotal = 0;
+    for s in owned {
+        if current.map(|c| c.owner_name.as_str()) != Some(s.owner_name.as_str()) {
+            if let Some(c) = current {
+                report.push_str(&total(c.owner, c.owner_name.as_str(), subtotal));
+            }
+            current = Some(s);
+            subtotal = 0;
+            report.push_str(&format!(
+                "\n{}\nSystem\tBase\tMorale\tBlockade\tPolicy\tTrade\tTotal\n",
+                s.owner_name
+            ));
+        }
+        let income = IncomeBreakdown::new(s, rules);
+        subtotal += income.total();
+        report.push_str(&format!("{}\t{}\n", s.name, income.as_row()));
+    }
+    if let Some(c) = current {
+        report.push_str(&total(c.owner, c.owner_name.as_str(), subtotal));
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{admin_limit, economic_report, EmpireIncome, IncomeBreakdown, ADMIN_PENALTY};
+    use crate::campaign::empire::tests::empires;
+    use crate::campaign::government::Modifiers;
+    use crate::campaign::locale::LOCALES;
+    use crate::campaign::ownership;
+    use crate::campaign::ruleset::SecondEdition;
+    use crate::campaign::system::tests::systems;
+    use crate::campaign::system::{FREE_PORT, MARTIAL_LAW, TAX_EXEMPT};
+
+    #[test]
+    fn base_income() {
+        let sys = &systems()[0];
+        let inc = IncomeBreakdown::new(sys, &SecondEdition);
+        assert_eq!(sys.raw + sys.ind, inc.base);
+        assert_eq!(0, inc.morale);
+        assert_eq!(0, inc.blockade);
+        assert_eq!(inc.base, inc.total());
+    }
+
+    #[test]
+    fn empire_income() {
+        let mut sys = systems();
+        sys[1].mor = 0;
+        let inc = EmpireIncome::new(&sys, 3, &SecondEdition);
+        assert_eq!(10 / 2 + 3 / 2 + 3 / 2, inc.census);
+        let productivity: i32 = sys
+            .iter()
+            .map(|s| IncomeBreakdown::new(s, &SecondEdition).total())
+            .sum();
+        assert_eq!(productivity, inc.productivity);
+        assert_eq!(0, inc.trade);
+        assert_eq!(inc.census + inc.productivity - 3, inc.net());
+    }
+
+    #[test]
+    fn government_income() {
+        let inc = EmpireIncome {
+            census: 5,
+            productivity: 15,
+            trade: 4,
+            ..Default::default()
+        };
+        let m = Modifiers {
+            income: 10,
+            trade: 50,
+            morale: 0,
+        };
+        let modified = inc.clone().with_government(&m);
+        assert_eq!(2 + 2, modified.government);
+        assert_eq!(inc.gross() + 4, modified.gross());
+        assert!(modified.to_string().contains("Government:\t4"));
+
+        let m = Modifiers {
+            income: -10,
+            ..Default::default()
+        };
+        assert_eq!(-2, inc.with_government(&m).government);
+    }
+
+    #[test]
+    fn contested_income() {
+        let mut sys = systems()[0].clone(); // census 5, productivity 15
+        sys.owner = 1;
+        sys.contender = 2;
+        sys.share = 60;
+        let inc = EmpireIncome::new(&[sys.clone()], 0, &SecondEdition);
+        assert_eq!((3, 9), (inc.census, inc.productivity));
+        let held = ownership::contender_view(&sys).unwrap();
+        let inc = EmpireIncome::new(&[held], 0, &SecondEdition);
+        assert_eq!((2, 6), (inc.census, inc.productivity));
+    }
+
+    #[test]
+    fn low_morale() {
+        let mut sys = systems()[0].clone();
+        sys.mor = 4; // population 10
+        let inc = IncomeBreakdown::new(&sys, &SecondEdition);
+        assert_eq!(-(inc.base / 2), inc.morale);
+
+        sys.mor = 0;
+        assert_eq!(0, IncomeBreakdown::new(&sys, &SecondEdition).total());
+    }
+
+    #[test]
+    fn blockade() {
+        let mut sys = systems()[0].clone();
+        sys.blockaded = true;
+        let inc = IncomeBreakdown::new(&sys, &SecondEdition);
+        assert_eq!(-(inc.base / 2), inc.blockade);
+        assert_eq!(inc.base - inc.base / 2, inc.total());
+    }
+
+    #[test]
+    fn policies() {
+        let mut sys = systems()[0].clone(); // census 5, productivity 15
+        sys.policy = TAX_EXEMPT.to_string();
+        let inc = IncomeBreakdown::new(&sys, &SecondEdition);
+        assert_eq!((-15, 0), (inc.policy, inc.total()));
+        assert_eq!(
+            0,
+            EmpireIncome::new(&[sys.clone()], 0, &SecondEdition).census
+        );
+
+        sys.policy = FREE_PORT.to_string();
+        assert_eq!(18, IncomeBreakdown::new(&sys, &SecondEdition).total());
+        sys.blockaded = true;
+        assert_eq!(8, IncomeBreakdown::new(&sys, &SecondEdition).total());
+
+        sys.blockaded = false;
+        sys.mor = 0;
+        assert_eq!(0, IncomeBreakdown::new(&sys, &SecondEdition).total());
+        sys.policy = MARTIAL_LAW.to_string();
+        assert_eq!(15, IncomeBreakdown::new(&sys, &SecondEdition).total());
+    }
+
+    #[test]
+    fn report_skips_unowned() {
+        let mut sys = systems();
+        sys[0].owner = 1;
+        sys[0].owner_name = "Senorian".to_string();
+        let report = economic_report(&sys, &empires(), false, &SecondEdition, &LOCALES[0]);
+        assert!(report.contains("Senor Prime"));
+        assert!(!report.contains("Tibron"));
+        assert!(report.contains("Total for Senorian:\t15"));
+    }
+
+    #[test]
+    fn colonial_administration() {
+        let mut sys = systems();
+        // Capital population 10 adds two to the base of four.
+        assert_eq!(6, admin_limit(0, &sys));
+        assert_eq!(8, admin_limit(2, &sys));
+
+        // Spread eight systems across one empire with a limit of six.
+        let extra = sys.clone();
+        sys.extend(extra);
+        for s in sys.iter_mut() {
+            s.owner = 1;
+            s.owner_name = "Senorian".to_string();
+        }
+        let inc = EmpireIncome::new(&sys, 0, &SecondEdition);
+        let limited = inc.clone().with_admin_limit(0, &sys);
+        assert_eq!(2 * ADMIN_PENALTY, limited.admin);
+        assert_eq!(inc.gross() - 2 * ADMIN_PENALTY, limited.gross());
+        assert_eq!(0, inc.clone().with_admin_limit(2, &sys).admin);
+
+        let mut emp = empires();
+        emp[0].id = 1;
+        let report = economic_report(&sys, &emp, true, &SecondEdition, &LOCALES[0]);
+        assert!(report.contains("OVER-EXTENDED: 8 systems, limit 6"));
+        assert!(
+            !economic_report(&sys, &emp, false, &SecondEdition, &LOCALES[0])
+                .contains("OVER-EXTENDED")
+        );
+    }
+}
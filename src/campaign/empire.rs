@@ -15,31 +15,134 @@
 
 //! Interface to empires.
 
+use super::government::{GOVERNMENTS, MONARCHY, TRAITS};
+
+/// An empire. Its traits are stored as a comma-separated list.
 #[allow(unused)]
-#[derive(sqlx::FromRow)]
+#[derive(sqlx::FromRow, Clone, Debug, PartialEq, Eq)]
 pub struct Empire {
     pub id: i64,
     pub name: String,
     pub treasury: i32,
     pub tech: i32,
+    pub government: String,
+    pub traits: String,
 }
 
 impl Empire {
+    /// Convert to string as a row of tab-separated fields.
+    pub fn as_row(&self) -> String {
+        format!(
+            "{}\t{}\t{}\t{}\t{}",
+            self.name,
+            self.treasury,
+            self.tech,
+            self.government,
+            self.trait_list().join(", ")
+        )
+    }
+
+    /// Check the empire's government and traits are ones the rules know.
+    pub fn check_government(&self) -> Result<(), String> {
+        if !GOVERNMENTS.contains(&self.government.as_str()) {
+            return Err(format!("Unknown government {}", self.government));
+        }
+        match self.trait_list().iter().find(|t| !TRAITS.contains(t)) {
+            Some(t) => Err(format!("Unknown trait {}", t)),
+            None => Ok(()),
+        }
+    }
+
+    /// Check a manual change to the empire's treasury and tech level. Tech
+    /// may not be negative, the treasury may not be lowered past the deficit
+    /// limit, and any change must give a reason.
+    pub fn check_financials(
+        &self,
+        treasury: i32,
+        tech: i32,
+        note: &str,
+        deficit_limit: i32,
+    ) -> Result<(), String> {
+        if tech < 0 {
+            return Err("Tech level cannot be negative".to_string());
+        }
+        if treasury < self.treasury && treasury < -deficit_limit {
+            return Err(format!(
+                "{} cannot run a deficit of more than {} EP",
+                self.name, deficit_limit
+            ));
+        }
+        if (treasury != self.treasury || tech != self.tech) && note.trim().is_empty() {
+            return Err("A note is required for manual changes".to_string());
+        }
+        Ok(())
+    }
+
     /// Create a new empire.
-    #[allow(unused)]
     pub fn new(name: &str) -> Empire {
         Self {
             id: 0,
             name: name.to_string(),
             treasury: 0,
             tech: 0,
+            government: MONARCHY.to_string(),
+            traits: String::new(),
         }
     }
+
+    /// Whether the empire has a trait.
+    pub fn has_trait(&self, t: &str) -> bool {
+        self.trait_list().contains(&t)
+    }
+
+    /// The empire's traits.
+    pub fn trait_list(&self) -> Vec<&str> {
+        self.traits
+            .split(',')
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+            .collect()
+    }
+}
+
+/// A manual change to an empire's treasury or tech level, with the reason
+/// for it.
+#[derive(sqlx::FromRow, Clone, Debug, PartialEq, Eq)]
+pub struct LedgerEntry {
+    pub id: i64,
+    pub empire: i64,
+    pub turn: i32,
+    pub amount: i32,
+    pub tech: i32,
+    pub note: String,
+}
+
+impl LedgerEntry {
+    /// Create a new entry.
+    pub fn new(empire: i64, turn: i32, amount: i32, tech: i32, note: &str) -> LedgerEntry {
+        Self {
+            id: 0,
+            empire,
+            turn,
+            amount,
+            tech,
+            note: note.trim().to_string(),
+        }
+    }
+
+    /// Convert to string as a row of tab-separated fields.
+    pub fn as_row(&self) -> String {
+        format!(
+            "{}\t{:+}\t{:+}\t{}",
+            self.turn, self.amount, self.tech, self.note
+        )
+    }
 }
 
 #[cfg(test)]
 pub mod tests {
     use crate::campaign::empire::Empire;
+    use crate::campaign::government::{DEMOCRACY, MERCANTILE, XENOPHOBIC};
 
     pub fn empires() -> Vec<Empire> {
         let mut emp = Vec::new();
@@ -53,5 +156,45 @@ pub mod tests {
         emp.push(Empire::new("Tirelon"));
         emp
     }
-    // TODO Add tests
+
+    #[test]
+    fn check_financials() {
+        let mut emp = Empire::new("Senorian");
+        emp.treasury = 10;
+        assert!(emp.check_financials(10, 0, "", 0).is_ok());
+        assert!(emp.check_financials(10, -1, "Sabotage", 0).is_err());
+        assert!(emp.check_financials(15, 0, " ", 0).is_err());
+        assert!(emp.check_financials(15, 0, "Tribute", 0).is_ok());
+        assert!(emp.check_financials(-5, 0, "Fine", 0).is_err());
+        assert!(emp.check_financials(-5, 0, "Fine", 5).is_ok());
+
+        // A treasury already past the limit may still be raised.
+        emp.treasury = -20;
+        assert!(emp.check_financials(-10, 0, "Loan", 5).is_ok());
+        assert!(emp.check_financials(-25, 0, "Fine", 5).is_err());
+    }
+
+    #[test]
+    fn government() {
+        let mut emp = Empire::new("Senorian");
+        assert!(emp.check_government().is_ok());
+        assert!(emp.trait_list().is_empty());
+
+        emp.government = DEMOCRACY.to_string();
+        emp.traits = format!("{}, {}", MERCANTILE, XENOPHOBIC);
+        assert!(emp.check_government().is_ok());
+        assert!(emp.has_trait(XENOPHOBIC));
+        assert_eq!(
+            "Senorian\t0\t0\tDemocracy\tMercantile, Xenophobic",
+            emp.as_row()
+        );
+
+        emp.traits = "Pacifist".to_string();
+        assert_eq!(
+            Err("Unknown trait Pacifist".to_string()),
+            emp.check_government()
+        );
+        emp.government = "Anarchy".to_string();
+        assert!(emp.check_government().is_err());
+    }
 }
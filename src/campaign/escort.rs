@@ -0,0 +1,188 @@
+// Copyright 2022 David Terhune
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Escorts: fleets that guard another fleet, such as a convoy or troop
+//! transports, and go wherever it goes.
+//!
+//! An escort and the fleet it guards form a group that moves together, at
+//! the pace of its slowest fleet, and fights together, the escorts taking
+//! fire before the ships they guard. A fleet either escorts or is escorted,
+//! never both, so groups do not chain. A fleet may be ordered to rendezvous
+//! with another it can reach this turn and escort it from there on.
+
+use std::collections::{BTreeSet, VecDeque};
+
+use super::lane::{self, Lane};
+use super::unit::FleetSummary;
+
+/// A fleet escorting another.
+#[derive(sqlx::FromRow, Clone, Debug, PartialEq, Eq)]
+pub struct Escort {
+    pub id: i64,
+    pub escort: i64,
+    pub convoy: i64,
+    #[sqlx(default)]
+    pub escort_name: String,
+    #[sqlx(default)]
+    pub convoy_name: String,
+}
+
+impl Escort {
+    /// Create a new escort of one fleet by another.
+    pub fn new(escort: i64, convoy: i64) -> Self {
+        Self {
+            id: 0,
+            escort,
+            convoy,
+            escort_name: String::new(),
+            convoy_name: String::new(),
+        }
+    }
+
+    /// Convert to string as a row of tab-separated fields.
+    pub fn as_row(&self) -> String {
+        format!("{}\t{}", self.escort_name, self.convoy_name)
+    }
+}
+
+/// Check that one fleet can escort another now: both belong to the same
+/// empire and are in the same system, and neither is already part of
+/// another group in a way that would chain them.
+pub fn check(
+    escort: &FleetSummary,
+    convoy: &FleetSummary,
+    escorts: &[Escort],
+) -> Result<(), String> {
+    if escort.id == convoy.id {
+        return Err(format!("{} cannot escort itself", escort.name));
+    }
+    if escort.owner != convoy.owner {
+        return Err(format!(
+            "{} and {} belong to different empires",
+            escort.name, convoy.name
+        ));
+    }
+    if escort.location != convoy.location {
+        return Err(format!(
+            "{} is at {}, not with {} at {}",
+            escort.name, escort.location_name, convoy.name, convoy.location_name
+        ));
+    }
+    if escorts.iter().any(|e| e.escort == escort.id) {
+        return Err(format!("{} is already escorting a fleet", escort.name));
+    }
+    if escorts.iter().any(|e| e.convoy == escort.id) {
+        return Err(format!("{} has escorts of its own", escort.name));
+    }
+    if escorts.iter().any(|e| e.escort == convoy.id) {
+        return Err(format!("{} is itself an escort", convoy.name));
+    }
+    Ok(())
+}
+
+/// The fleets that move with a fleet: the fleet it escorts or itself, and
+/// every escort of that one, in that order.
+pub fn group(escorts: &[Escort], fleet: i64) -> Vec<i64> {
+    let convoy = escorts
+        .iter()
+        .find(|e| e.escort == fleet)
+        .map_or(fleet, |e| e.convoy);
+    let mut v = vec![convoy];
+    v.extend(
+        escorts
+            .iter()
+            .filter(|e| e.convoy == convoy)
+            .map(|e| e.escort),
+    );
+    v
+}
+
+/// The fleet a fleet is escorting, if any.
+pub fn escorting(escorts: &[Escort], fleet: i64) -> Option<i64> {
+    escorts.iter().find(|e| e.escort == fleet).map(|e| e.convoy)
+}
+
+/// Jumps needed to get from one system to another along the lanes, if it
+/// can be reached at all.
+pub fn jumps_between(lanes: &[Lane], from: i64, to: i64) -> Option<i32> {
+    let mut seen = BTreeSet::from([from]);
+    let mut queue = VecDeque::from([(from, 0)]);
+    while let Some((sys, jumps)) = queue.pop_front() {
+        if sys == to {
+            return Some(jumps);
+        }
+        for next in lane::neighbors(lanes, sys) {
+            if seen.insert(next) {
+                queue.push_back((next, jumps + 1));
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check, escorting, group, jumps_between, Escort};
+    use crate::campaign::lane::tests::lanes;
+    use crate::campaign::unit::FleetSummary;
+
+    fn fleet(id: i64, owner: i64, location: i64) -> FleetSummary {
+        FleetSummary {
+            id,
+            name: format!("Fleet {}", id),
+            owner,
+            location,
+            speed: 0,
+            owner_name: String::new(),
+            location_name: String::new(),
+            ships: 1,
+            crippled: 0,
+            atk: 0,
+            def: 0,
+            maint: 0,
+            jumps: 1,
+        }
+    }
+
+    #[test]
+    fn assignments() {
+        let escorts = [Escort::new(2, 1)];
+        assert!(check(&fleet(3, 1, 1), &fleet(1, 1, 1), &escorts).is_ok());
+        assert!(check(&fleet(3, 1, 1), &fleet(3, 1, 1), &escorts).is_err());
+        assert!(check(&fleet(3, 2, 1), &fleet(1, 1, 1), &escorts).is_err());
+        assert!(check(&fleet(3, 1, 2), &fleet(1, 1, 1), &escorts).is_err());
+        assert!(check(&fleet(2, 1, 1), &fleet(3, 1, 1), &escorts).is_err());
+        assert!(check(&fleet(1, 1, 1), &fleet(3, 1, 1), &escorts).is_err());
+        assert!(check(&fleet(3, 1, 1), &fleet(2, 1, 1), &escorts).is_err());
+    }
+
+    #[test]
+    fn groups() {
+        let escorts = [Escort::new(2, 1), Escort::new(3, 1), Escort::new(5, 4)];
+        assert_eq!(vec![1, 2, 3], group(&escorts, 1));
+        assert_eq!(vec![1, 2, 3], group(&escorts, 3));
+        assert_eq!(vec![6], group(&escorts, 6));
+        assert_eq!(Some(4), escorting(&escorts, 5));
+        assert_eq!(None, escorting(&escorts, 4));
+    }
+
+    #[test]
+    fn rendezvous_distance() {
+        assert_eq!(Some(0), jumps_between(&lanes(), 2, 2));
+        assert_eq!(Some(1), jumps_between(&lanes(), 1, 4));
+        assert_eq!(Some(2), jumps_between(&lanes(), 2, 4));
+        assert_eq!(None, jumps_between(&lanes(), 1, 5));
+    }
+}
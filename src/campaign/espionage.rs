@@ -0,0 +1,191 @@
+// Copyright 2022 David Terhune
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Espionage between empires.
+//!
+//! Empires put intel points into operations against their rivals, and into
+//! counter-espionage at home, paying one EP per point when the order is
+//! given. In the intelligence phase each operation rolls a d10 and adds its
+//! points; it succeeds if that beats the target's counter-espionage points
+//! plus `DIFFICULTY`. A failed operation is discovered by its target.
+
+use super::tech;
+use super::unit::FleetSummary;
+
+/// Steal research toward the thief's next tech level from a more advanced
+/// empire.
+pub const STEAL_TECH: &str = "Steal Tech";
+
+/// Sabotage the target's shipyards, delaying every build it has under way.
+pub const SABOTAGE: &str = "Sabotage";
+
+/// Learn where the target's fleets are, and identify their ship classes.
+pub const REVEAL_FLEETS: &str = "Reveal Fleets";
+
+/// Guard against the operations of every other empire this turn.
+pub const COUNTER_ESPIONAGE: &str = "Counter-Espionage";
+
+/// What intel points can be put into.
+pub const MISSIONS: [&str; 4] = [STEAL_TECH, SABOTAGE, REVEAL_FLEETS, COUNTER_ESPIONAGE];
+
+/// Added to the target's counter-espionage points to give the total an
+/// operation's roll and points must beat.
+pub const DIFFICULTY: i32 = 5;
+
+/// Turns a successful sabotage adds to each of the target's builds.
+pub const SABOTAGE_DELAY: i32 = 1;
+
+/// Intel points an empire has put into a mission against a target for a
+/// turn. Counter-espionage targets the empire itself.
+#[derive(sqlx::FromRow, Clone, Debug, PartialEq, Eq)]
+pub struct Operation {
+    pub id: i64,
+    pub empire: i64,
+    pub turn: i32,
+    pub target: i64,
+    pub mission: String,
+    pub points: i32,
+    #[sqlx(default)]
+    pub empire_name: String,
+    #[sqlx(default)]
+    pub target_name: String,
+}
+
+impl Operation {
+    /// Create a new espionage operation.
+    pub fn new(empire: i64, turn: i32, target: i64, mission: &str, points: i32) -> Self {
+        Self {
+            id: 0,
+            empire,
+            turn,
+            target,
+            mission: mission.to_string(),
+            points,
+            empire_name: String::new(),
+            target_name: String::new(),
+        }
+    }
+
+    /// Convert to string as a row of tab-separated fields.
+    pub fn as_row(&self) -> String {
+        format!(
+            "{}\t{}\t{}\t{}\t{}",
+            self.turn, self.empire_name, self.target_name, self.mission, self.points
+        )
+    }
+}
+
+/// Counter-espionage points an empire has among the operations.
+pub fn counter_points(ops: &[Operation], empire: i64) -> i32 {
+    ops.iter()
+        .filter(|o| o.empire == empire && o.mission == COUNTER_ESPIONAGE)
+        .map(|o| o.points)
+        .sum()
+}
+
+/// Whether an operation with the given points and d10 roll gets past the
+/// target's counter-espionage.
+pub fn succeeds(points: i32, counter: i32, roll: i32) -> bool {
+    roll + points > counter + DIFFICULTY
+}
+
+/// Research points a thief at one tech level steals from a target at
+/// another: half the cost of the thief's next level, and nothing from a
+/// target no more advanced.
+pub fn stolen(thief_tech: i32, target_tech: i32) -> i32 {
+    if target_tech > thief_tech {
+        tech::level_cost(thief_tech) / 2
+    } else {
+        0
+    }
+}
+
+/// Intel report of the target's fleets, one line each.
+pub fn fleet_report(target_name: &str, fleets: &[FleetSummary]) -> String {
+    if fleets.is_empty() {
+        return format!("Agents report {} has no fleets.", target_name);
+    }
+    let mut text = format!("Agents report the fleets of {}:", target_name);
+    for f in fleets {
+        text.push_str(&format!(
+            "\n{} at {}: {} ships, ATK {} DEF {}",
+            f.name, f.location_name, f.ships, f.atk, f.def
+        ));
+    }
+    text
+}
+
+/// Seed for an operation's roll, so a turn resolves the same way however
+/// often it is replayed.
+pub fn seed(turn: i32, op: i64) -> u64 {
+    ((turn as u64) << 32) ^ (op as u64) ^ 0x5350_5920
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        counter_points, fleet_report, stolen, succeeds, Operation, COUNTER_ESPIONAGE, SABOTAGE,
+    };
+    use crate::campaign::unit::FleetSummary;
+
+    #[test]
+    fn rolls() {
+        let ops = [
+            Operation::new(2, 0, 2, COUNTER_ESPIONAGE, 3),
+            Operation::new(2, 0, 2, COUNTER_ESPIONAGE, 1),
+            Operation::new(1, 0, 2, SABOTAGE, 2),
+        ];
+        assert_eq!(4, counter_points(&ops, 2));
+        assert_eq!(0, counter_points(&ops, 1));
+
+        assert!(succeeds(2, 0, 4));
+        assert!(!succeeds(2, 0, 3));
+        assert!(!succeeds(2, 4, 7));
+        assert!(succeeds(2, 4, 8));
+    }
+
+    #[test]
+    fn theft() {
+        assert_eq!(5, stolen(0, 1));
+        assert_eq!(10, stolen(1, 3));
+        assert_eq!(0, stolen(2, 2));
+        assert_eq!(0, stolen(3, 1));
+    }
+
+    #[test]
+    fn fleets() {
+        let f = FleetSummary {
+            id: 1,
+            name: "Home Fleet".to_string(),
+            owner: 2,
+            location: 1,
+            speed: 0,
+            owner_name: "Human".to_string(),
+            location_name: "Senor Prime".to_string(),
+            ships: 3,
+            crippled: 0,
+            atk: 9,
+            def: 12,
+            maint: 0,
+            jumps: 0,
+        };
+        assert_eq!(
+            "Agents report Human has no fleets.",
+            fleet_report("Human", &[])
+        );
+        assert!(fleet_report("Human", &[f])
+            .ends_with("\nHome Fleet at Senor Prime: 3 ships, ATK 9 DEF 12"));
+    }
+}
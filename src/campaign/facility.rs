@@ -0,0 +1,214 @@
+// Copyright 2022 David Terhune
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! System facilities: the shipyards, starbases, trade stations and sensor
+//! posts built at an empire's systems.
+//!
+//! A facility belongs to whoever owns its system, and costs that empire
+//! maintenance every turn. A shipyard adds to the system's repair capacity,
+//! a starbase fights in the system's defense, a trade station carries supply
+//! a jump further, and a sensor post sees a jump further. A crippled
+//! facility still costs maintenance but does nothing until repaired.
+
+use std::collections::BTreeSet;
+
+use super::system::System;
+
+/// Adds repair capacity at its system.
+pub const SHIPYARD: &str = "Shipyard";
+
+/// Fights in the defense of its system.
+pub const STARBASE: &str = "Starbase";
+
+/// Carries its system's supply a jump further.
+pub const TRADE_STATION: &str = "Trade Station";
+
+/// Sees a jump further from its system.
+pub const SENSOR_POST: &str = "Sensor Post";
+
+/// Repair capacity a working shipyard adds to its system.
+pub const SHIPYARD_CAPACITY: i32 = 4;
+
+/// Jumps a trade station or sensor post adds to its system's reach.
+pub const REACH: i32 = 1;
+
+/// Facilities fight under their ID plus this, clear of any ship's.
+pub const COMBAT_ID: i64 = 1 << 40;
+
+/// What a kind of facility costs to build and keep, and how it fights.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Spec {
+    pub kind: &'static str,
+    pub cost: i32,
+    pub turns: i32,
+    pub maint: i32,
+    pub atk: i32,
+    pub def: i32,
+}
+
+/// Every kind of facility.
+pub const SPECS: [Spec; 4] = [
+    Spec {
+        kind: SHIPYARD,
+        cost: 10,
+        turns: 2,
+        maint: 1,
+        atk: 0,
+        def: 0,
+    },
+    Spec {
+        kind: STARBASE,
+        cost: 12,
+        turns: 3,
+        maint: 2,
+        atk: 6,
+        def: 8,
+    },
+    Spec {
+        kind: TRADE_STATION,
+        cost: 8,
+        turns: 2,
+        maint: 1,
+        atk: 0,
+        def: 0,
+    },
+    Spec {
+        kind: SENSOR_POST,
+        cost: 4,
+        turns: 1,
+        maint: 0,
+        atk: 0,
+        def: 0,
+    },
+];
+
+/// The spec of a kind of facility.
+pub fn spec(kind: &str) -> Option<&'static Spec> {
+    SPECS.iter().find(|s| s.kind == kind)
+}
+
+/// A facility at a system. Its owner and system name are only read from the
+/// store.
+#[derive(sqlx::FromRow, Clone, Debug, PartialEq, Eq)]
+pub struct Facility {
+    pub id: i64,
+    pub system: i64,
+    pub kind: String,
+    pub crip: bool,
+    #[sqlx(default)]
+    pub owner: i64,
+    #[sqlx(default)]
+    pub system_name: String,
+}
+
+impl Facility {
+    /// Create a new facility.
+    pub fn new(system: i64, kind: &str) -> Self {
+        Self {
+            id: 0,
+            system,
+            kind: kind.to_string(),
+            crip: false,
+            owner: 0,
+            system_name: String::new(),
+        }
+    }
+
+    /// Convert to string as a row of tab-separated fields.
+    pub fn as_row(&self) -> String {
+        format!(
+            "{}\t{}\t{}",
+            self.kind,
+            if self.crip { "Crippled" } else { "Active" },
+            self.maint()
+        )
+    }
+
+    /// Maintenance due each turn while active.
+    pub fn maint(&self) -> i32 {
+        spec(self.kind.as_str()).map_or(0, |s| s.maint)
+    }
+}
+
+/// Check a facility of the given kind can be built at a system, given the
+/// facilities already there. The system must have an owner, and holds only
+/// one facility of each kind.
+pub fn check(kind: &str, sys: &System, present: &[Facility]) -> Result<&'static Spec, String> {
+    let spec = match spec(kind) {
+        Some(s) => s,
+        None => return Err(format!("Unknown facility {}", kind)),
+    };
+    if sys.owner == 0 {
+        return Err(format!("{} has no owner to build there", sys.name));
+    }
+    if present.iter().any(|f| f.system == sys.id && f.kind == kind) {
+        return Err(format!("{} already has a {}", sys.name, kind));
+    }
+    Ok(spec)
+}
+
+/// The systems with a working facility of the given kind.
+pub fn working(facilities: &[Facility], kind: &str) -> BTreeSet<i64> {
+    facilities
+        .iter()
+        .filter(|f| f.kind == kind && !f.crip)
+        .map(|f| f.system)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::{check, spec, working, Facility, SENSOR_POST, SHIPYARD, SPECS, STARBASE};
+    use crate::campaign::system::tests::systems;
+
+    #[test]
+    fn specs() {
+        assert_eq!(Some(&SPECS[1]), spec(STARBASE));
+        assert_eq!(None, spec("Fortress"));
+        assert!(SPECS.iter().all(|s| s.cost > 0 && s.turns > 0));
+
+        let base = Facility::new(1, STARBASE);
+        assert_eq!("Starbase\tActive\t2", base.as_row());
+        assert_eq!(0, Facility::new(1, SENSOR_POST).maint());
+    }
+
+    #[test]
+    fn building() {
+        let mut sys = systems()[0].clone();
+        sys.id = 1;
+        assert!(check(SHIPYARD, &sys, &[]).is_err());
+
+        sys.owner = 1;
+        assert_eq!(Ok(10), check(SHIPYARD, &sys, &[]).map(|s| s.cost));
+        assert!(check("Fortress", &sys, &[]).is_err());
+
+        let yard = Facility::new(1, SHIPYARD);
+        assert_eq!(
+            Err("Senor Prime already has a Shipyard".to_string()),
+            check(SHIPYARD, &sys, std::slice::from_ref(&yard))
+        );
+        assert!(check(STARBASE, &sys, &[yard]).is_ok());
+    }
+
+    #[test]
+    fn working_facilities() {
+        let mut yards = vec![Facility::new(1, SHIPYARD), Facility::new(2, SHIPYARD)];
+        yards.push(Facility::new(3, STARBASE));
+        yards[1].crip = true;
+        assert_eq!(BTreeSet::from([1]), working(&yards, SHIPYARD));
+    }
+}
@@ -0,0 +1,279 @@
+// Copyright 2022 David Terhune
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Campaigns generated for tests of the turn engine and reports.
+//!
+//! A scenario builds a mid-game campaign from its seed: empires with their
+//! homeworlds, colonies and unclaimed systems joined by lanes, a fleet and a
+//! garrison at every homeworld, a war, and turns already played. The same
+//! scenario always builds the same campaign, so what a test produces from it
+//! can be compared with a golden copy kept under `tests/golden`. A missing
+//! golden copy fails its test; every copy is written again when
+//! `UPDATE_GOLDEN` is set, so a change to the resolution rules shows up as a
+//! change to the golden files.
+
+use std::fs;
+use std::path::PathBuf;
+
+use super::construction::Build;
+use super::data::tests::init_data;
+use super::data::DataStore;
+use super::dice::Dice;
+use super::empire::tests::empires;
+use super::empire::Empire;
+use super::lane::Lane;
+use super::map;
+use super::options::CampaignOptions;
+use super::system::System;
+use super::turn;
+use super::unit::ShipType;
+
+/// Environment variable that has golden copies written again.
+pub const UPDATE_GOLDEN: &str = "UPDATE_GOLDEN";
+
+// Planet types of systems that are not homeworlds, with their greatest CAP.
+const WORLDS: [(&str, i32); 3] = [("Adaptable", 10), ("Barren", 6), ("Hostile", 4)];
+
+/// How big a campaign to generate, and how far into it.
+#[derive(Clone, Debug)]
+pub struct Scenario {
+    pub empires: usize,
+    pub systems: usize,
+    pub turns: i32,
+    pub seed: u64,
+}
+
+impl Default for Scenario {
+    fn default() -> Self {
+        Self {
+            empires: 4,
+            systems: 16,
+            turns: 3,
+            seed: 548,
+        }
+    }
+}
+
+impl Scenario {
+    /// Generate the campaign, with its turns played.
+    pub async fn generate(&self) -> DataStore {
+        let data = init_data().await;
+        let options = CampaignOptions {
+            dice_seed: self.seed,
+            ..Default::default()
+        };
+        data.set_campaign_options(&options).await.unwrap();
+        let mut dice = Dice::new(self.seed).with_context("Fixture");
+
+        let names = empires();
+        let emp: Vec<Empire> = (0..self.empires)
+            .map(|n| match names.get(n) {
+                Some(e) => e.clone(),
+                None => Empire::new(&format!("Empire {}", n + 1)),
+            })
+            .collect();
+        data.add_empires(emp).await.unwrap();
+
+        let mut sys = Vec::new();
+        for n in 0..self.systems {
+            let mut s = if n < self.empires {
+                System::new(&format!("Home {}", n + 1), "HW", 5, 12, 10, 8, 10)
+            } else {
+                let (ptype, max_cap) = WORLDS[dice.roll(WORLDS.len() as i32) as usize - 1];
+                let cap = dice.roll(max_cap);
+                let pop = dice.roll(cap);
+                let mor = dice.roll(pop);
+                let ind = dice.roll(pop);
+                System::new(
+                    &format!("System {}", n + 1),
+                    ptype,
+                    dice.roll(5),
+                    cap,
+                    pop,
+                    mor,
+                    ind,
+                )
+            };
+            (s.x, s.y) = map::grid_position(n);
+            sys.push(s);
+        }
+        data.add_systems(sys).await.unwrap();
+
+        // Every homeworld belongs to its empire; the rest are colonies of a
+        // rolled empire, or left unclaimed.
+        let mut sys = data.get_systems().await.unwrap();
+        sys.sort_by_key(|s| s.id);
+        for (n, s) in sys.iter().enumerate() {
+            let owner = if n < self.empires {
+                n as i64 + 1
+            } else {
+                dice.roll(self.empires as i32 + 1) as i64 - 1
+            };
+            if owner != 0 {
+                let mut s = s.clone();
+                s.owner = owner;
+                data.update_system(&s).await.unwrap();
+            }
+        }
+
+        // A chain of major lanes through every system, with the odd minor
+        // lane across it.
+        for w in sys.windows(2) {
+            data.insert_lane(&Lane::new(w[0].id, w[1].id, "Major"))
+                .await
+                .unwrap();
+        }
+        for s in &sys {
+            let other = &sys[dice.roll(sys.len() as i32) as usize - 1];
+            if dice.roll(6) == 6 && (other.id - s.id).abs() > 1 {
+                data.insert_lane(&Lane::new(s.id, other.id, "Minor"))
+                    .await
+                    .unwrap();
+            }
+        }
+
+        let militia = data
+            .get_ground_types()
+            .await
+            .unwrap()
+            .into_iter()
+            .find(|t| t.abbr == "MIL")
+            .unwrap();
+        for (n, home) in sys.iter().take(self.empires).enumerate() {
+            let empire = n as i64 + 1;
+            let class = format!("Class {}", n + 1);
+            let item = data
+                .insert_ship_type(&ShipType::new(&class, "Cruiser", empire))
+                .await
+                .unwrap();
+            let mut build = Build::new(empire, home.id, "Ship", item, "");
+            build.count = dice.roll(3);
+            data.finish_build(&build).await.unwrap();
+            for _ in 0..dice.roll(3) {
+                data.insert_ground_unit(militia.id, home.id).await.unwrap();
+            }
+        }
+        if self.empires > 1 {
+            data.insert_wars(&[(1, 2)], 0).await.unwrap();
+        }
+
+        for t in 0..self.turns {
+            turn::advance(&data, t).await.unwrap();
+        }
+        data
+    }
+}
+
+/// Compare what a test produced with its golden copy, writing the copy
+/// instead if `UPDATE_GOLDEN` is set.
+pub fn golden(name: &str, actual: &str) {
+    let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "golden"]
+        .iter()
+        .collect::<PathBuf>()
+        .join(format!("{}.txt", name));
+    if std::env::var_os(UPDATE_GOLDEN).is_some() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, actual).unwrap();
+        return;
+    }
+    let expected = match fs::read_to_string(&path) {
+        Ok(text) => text.replace("\r\n", "\n"),
+        Err(e) => panic!(
+            "no golden copy of {} at {} ({}); set {} to write it",
+            name,
+            path.display(),
+            e,
+            UPDATE_GOLDEN
+        ),
+    };
+    assert_eq!(
+        expected,
+        actual,
+        "{} differs from {}; set {} to accept the change",
+        name,
+        path.display(),
+        UPDATE_GOLDEN
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{golden, Scenario};
+    use crate::campaign::report;
+    use crate::campaign::template::DEFAULT_PLAYER_PACKET;
+    use crate::campaign::turn;
+
+    #[tokio::test]
+    async fn deterministic() {
+        let scenario = Scenario::default();
+        let a = scenario.generate().await;
+        let b = scenario.generate().await;
+        assert_eq!(
+            a.get_systems().await.unwrap(),
+            b.get_systems().await.unwrap()
+        );
+        assert_eq!(
+            a.get_empires().await.unwrap(),
+            b.get_empires().await.unwrap()
+        );
+        assert_eq!(
+            turn::advance(&a, scenario.turns).await.unwrap().to_string(),
+            turn::advance(&b, scenario.turns).await.unwrap().to_string()
+        );
+
+        let other = Scenario {
+            seed: scenario.seed + 1,
+            ..scenario.clone()
+        };
+        let c = other.generate().await;
+        assert_eq!(scenario.turns, c.current_turn().await.unwrap());
+        assert_ne!(
+            a.get_systems().await.unwrap(),
+            c.get_systems().await.unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn turn_report() {
+        let scenario = Scenario::default();
+        let data = scenario.generate().await;
+        let report = turn::advance(&data, scenario.turns).await.unwrap();
+        golden("turn_report", &report.to_string());
+    }
+
+    #[tokio::test]
+    async fn player_packets() {
+        let scenario = Scenario::default();
+        let data = scenario.generate().await;
+        let options = data.get_campaign_options().await.unwrap();
+        let classes = data.get_ship_types().await.unwrap();
+        let mut text = String::new();
+        for emp in data.get_empires().await.unwrap() {
+            text.push_str(&report::player_packet(
+                DEFAULT_PLAYER_PACKET,
+                scenario.turns,
+                &emp,
+                &data.get_systems_by_owner(emp.id).await.unwrap(),
+                &data.get_ground_units_by_owner(emp.id).await.unwrap(),
+                &classes,
+                &data.get_intel(emp.id, scenario.turns).await.unwrap(),
+                options.ruleset(),
+                options.locale(),
+            ));
+            text.push('\n');
+        }
+        golden("player_packets", &text);
+    }
+}
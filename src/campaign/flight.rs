@@ -0,0 +1,187 @@
+// Copyright 2022 David Terhune
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Fighter flights and the carriers and bases that hold them.
+//!
+//! A flight is always aboard a carrier or at a base. A carrier holds as many
+//! flights as its class's carrier capacity, and moves them with its fleet; a
+//! system holds as many at its bases as it has industry. Flights fight in
+//! battles alongside their empire's ships, and are lost with their carrier.
+
+use super::system::System;
+use super::unit::{Flight, ShipLocation};
+
+/// Attack of a new flight.
+pub const FLIGHT_ATK: i32 = 1;
+
+/// Defense of a new flight.
+pub const FLIGHT_DEF: i32 = 1;
+
+/// Class name flights fight under.
+pub const FLIGHT: &str = "Flight";
+
+/// Where a flight can be stationed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Station {
+    Carrier(ShipLocation),
+    Base(System),
+}
+
+impl Station {
+    /// Flights the station can hold.
+    pub fn capacity(&self) -> i32 {
+        match self {
+            Self::Carrier(ship) => ship.cap,
+            Self::Base(sys) => base_capacity(sys),
+        }
+    }
+
+    /// Name of the station for lists and messages.
+    pub fn name(&self) -> String {
+        match self {
+            Self::Carrier(ship) => format!("{} #{}", ship.class, ship.id),
+            Self::Base(sys) => format!("{} base", sys.name),
+        }
+    }
+
+    /// Whether a flight is stationed here.
+    pub fn holds(&self, flight: &Flight) -> bool {
+        match self {
+            Self::Carrier(ship) => flight.carrier == Some(ship.id),
+            Self::Base(sys) => flight.base == Some(sys.id),
+        }
+    }
+}
+
+/// Flights a system's bases can hold.
+pub fn base_capacity(sys: &System) -> i32 {
+    sys.ind.max(0)
+}
+
+/// Check a flight can be stationed, given the flights already at its
+/// location. The station must belong to the flight's empire, be where the
+/// flight is, and have room for it.
+pub fn check(flight: &Flight, station: &Station, present: &[Flight]) -> Result<(), String> {
+    let (owner, location) = match station {
+        Station::Carrier(ship) => (ship.owner, ship.location),
+        Station::Base(sys) => (sys.owner, sys.id),
+    };
+    if owner != flight.owner {
+        return Err(format!("{} belongs to another empire", station.name()));
+    }
+    if location != flight.location {
+        return Err(format!("{} is not where the flight is", station.name()));
+    }
+    if let Station::Carrier(ship) = station {
+        if ship.moth {
+            return Err(format!("{} is mothballed", station.name()));
+        }
+    }
+    let aboard = present
+        .iter()
+        .filter(|f| f.id != flight.id && station.holds(f))
+        .count() as i32;
+    if aboard >= station.capacity() {
+        return Err(format!(
+            "{} can hold only {} flights",
+            station.name(),
+            station.capacity()
+        ));
+    }
+    Ok(())
+}
+
+/// Place a flight at a station, leaving any other.
+pub fn station(flight: &Flight, station: &Station) -> Flight {
+    let (carrier, base) = match station {
+        Station::Carrier(ship) => (Some(ship.id), None),
+        Station::Base(sys) => (None, Some(sys.id)),
+    };
+    Flight {
+        carrier,
+        base,
+        station: station.name(),
+        ..flight.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check, station, Station, FLIGHT_ATK, FLIGHT_DEF};
+    use crate::campaign::system::tests::systems;
+    use crate::campaign::unit::{Flight, ShipLocation};
+
+    fn carrier(id: i64, cap: i32) -> ShipLocation {
+        ShipLocation {
+            id,
+            class: "Starhawk".to_string(),
+            fleet_name: "Home Fleet".to_string(),
+            location_name: "Senor Prime".to_string(),
+            crip: false,
+            moth: false,
+            owner: 1,
+            location: 1,
+            cost: 12,
+            cap,
+        }
+    }
+
+    #[test]
+    fn carriers() {
+        let deck = Station::Carrier(carrier(5, 2));
+        let new = Flight::new(1, 1, FLIGHT_ATK, FLIGHT_DEF);
+        assert!(check(&new, &deck, &[]).is_ok());
+
+        let mut aboard = Vec::new();
+        for id in 1..=2 {
+            aboard.push(station(&Flight { id, ..new.clone() }, &deck));
+        }
+        assert_eq!(Some(5), aboard[0].carrier);
+        assert_eq!("Starhawk #5", aboard[0].station);
+        assert_eq!(
+            Err("Starhawk #5 can hold only 2 flights".to_string()),
+            check(&new, &deck, &aboard)
+        );
+        // A flight already aboard takes no more room.
+        assert!(check(&aboard[1], &deck, &aboard).is_ok());
+
+        assert!(check(&new, &Station::Carrier(carrier(6, 0)), &[]).is_err());
+        let elsewhere = Flight::new(1, 2, FLIGHT_ATK, FLIGHT_DEF);
+        assert!(check(&elsewhere, &deck, &[]).is_err());
+        let enemy = Flight::new(2, 1, FLIGHT_ATK, FLIGHT_DEF);
+        assert!(check(&enemy, &deck, &[]).is_err());
+    }
+
+    #[test]
+    fn bases() {
+        let mut sys = systems()[0].clone();
+        sys.id = 1;
+        sys.owner = 1;
+        sys.ind = 1;
+        let base = Station::Base(sys);
+        let flight = Flight::new(1, 1, FLIGHT_ATK, FLIGHT_DEF);
+        assert!(check(&flight, &base, &[]).is_ok());
+
+        let based = station(
+            &Flight {
+                id: 1,
+                ..flight.clone()
+            },
+            &base,
+        );
+        assert_eq!((None, Some(1)), (based.carrier, based.base));
+        assert!(check(&flight, &base, &[based]).is_err());
+    }
+}
@@ -0,0 +1,160 @@
+// Copyright 2022 David Terhune
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Empire governments and traits.
+//!
+//! Each empire is given a government and any number of traits when it is
+//! set up. Together they adjust its income, the morale of its systems, the
+//! treaties it will sign, and how the GM should play it when it is a
+//! non-player empire.
+
+use super::empire::Empire;
+
+/// Government with a voice for its people, who are the more content for it.
+pub const DEMOCRACY: &str = "Democracy";
+
+/// Government by a ruling house, with no modifiers.
+pub const MONARCHY: &str = "Monarchy";
+
+/// Government that squeezes more from its people, who resent it.
+pub const DICTATORSHIP: &str = "Dictatorship";
+
+/// Governments an empire can have.
+pub const GOVERNMENTS: [&str; 3] = [DEMOCRACY, MONARCHY, DICTATORSHIP];
+
+/// Trait of an empire that spends on its forces and rallies its people.
+pub const MILITARISTIC: &str = "Militaristic";
+
+/// Trait of an empire that profits more from trade.
+pub const MERCANTILE: &str = "Mercantile";
+
+/// Trait of an empire that keeps to itself.
+pub const XENOPHOBIC: &str = "Xenophobic";
+
+/// Traits an empire can have.
+pub const TRAITS: [&str; 3] = [MILITARISTIC, MERCANTILE, XENOPHOBIC];
+
+/// Kinds of treaty a xenophobic empire will not sign.
+pub const XENOPHOBE_REFUSES: [&str; 2] = ["Trade", "Alliance"];
+
+/// How an empire's government and traits change its play.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Modifiers {
+    /// Percentage added to the empire's income apart from trade.
+    pub income: i32,
+    /// Percentage added to the empire's trade income.
+    pub trade: i32,
+    /// Added to the morale check of each of the empire's systems.
+    pub morale: i32,
+}
+
+impl Modifiers {
+    /// The combined modifiers of an empire's government and traits.
+    pub fn new(emp: &Empire) -> Self {
+        let mut m = match emp.government.as_str() {
+            DEMOCRACY => Self {
+                morale: 1,
+                ..Default::default()
+            },
+            DICTATORSHIP => Self {
+                income: 10,
+                morale: -1,
+                ..Default::default()
+            },
+            _ => Self::default(),
+        };
+        for t in emp.trait_list() {
+            match t {
+                MILITARISTIC => {
+                    m.income -= 10;
+                    m.morale += 1;
+                }
+                MERCANTILE => m.trade += 50,
+                XENOPHOBIC => m.morale += 1,
+                _ => {}
+            }
+        }
+        m
+    }
+}
+
+/// Check that two empires' traits let them sign a treaty.
+pub fn check_treaty(a: &Empire, b: &Empire, ttype: &str) -> Result<(), String> {
+    for e in [a, b] {
+        if e.has_trait(XENOPHOBIC) && XENOPHOBE_REFUSES.contains(&ttype) {
+            return Err(format!(
+                "{} is xenophobic and signs no {} treaty",
+                e.name, ttype
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// How the GM should play the empire when no player runs it.
+pub fn npe_behavior(emp: &Empire) -> &'static str {
+    if emp.has_trait(MILITARISTIC) {
+        "Aggressive: builds warships and attacks weaker neighbors"
+    } else if emp.has_trait(XENOPHOBIC) {
+        "Isolationist: holds its borders and refuses trade"
+    } else if emp.has_trait(MERCANTILE) {
+        "Trader: seeks trade agreements and guards its supply lines"
+    } else {
+        "Balanced: expands steadily and answers threats"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        check_treaty, npe_behavior, Modifiers, DEMOCRACY, DICTATORSHIP, MERCANTILE, MILITARISTIC,
+        XENOPHOBIC,
+    };
+    use crate::campaign::empire::Empire;
+
+    fn empire(government: &str, traits: &[&str]) -> Empire {
+        Empire {
+            government: government.to_string(),
+            traits: traits.join(","),
+            ..Empire::new("Senorian")
+        }
+    }
+
+    #[test]
+    fn modifiers() {
+        assert_eq!(Modifiers::default(), Modifiers::new(&Empire::new("Human")));
+        assert_eq!(1, Modifiers::new(&empire(DEMOCRACY, &[])).morale);
+        let m = Modifiers::new(&empire(DICTATORSHIP, &[MILITARISTIC, MERCANTILE]));
+        assert_eq!(0, m.income);
+        assert_eq!(50, m.trade);
+        assert_eq!(0, m.morale);
+    }
+
+    #[test]
+    fn treaties_and_behavior() {
+        let human = Empire::new("Human");
+        let xeno = empire(DEMOCRACY, &[XENOPHOBIC]);
+        assert!(check_treaty(&human, &xeno, "Trade").is_err());
+        assert!(check_treaty(&xeno, &human, "Alliance").is_err());
+        assert!(check_treaty(&xeno, &human, "Non-Aggression").is_ok());
+        assert!(check_treaty(&human, &human, "Trade").is_ok());
+
+        assert!(npe_behavior(&xeno).starts_with("Isolationist"));
+        assert!(
+            npe_behavior(&empire(DEMOCRACY, &[XENOPHOBIC, MILITARISTIC])).starts_with("Aggressive")
+        );
+        assert!(npe_behavior(&human).starts_with("Balanced"));
+    }
+}
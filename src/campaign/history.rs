@@ -0,0 +1,166 @@
+// Copyright 2022 David Terhune
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Undo and redo of data edits.
+//!
+//! Each edit is kept as snapshots of the campaign's tables, in the same form
+//! as an archive, taken before and after it. Undoing restores the snapshot
+//! from before, and redoing the one from after. An edit is only undone or
+//! redone if the tables still match, so changes made since by anything that
+//! is not kept here are never silently lost.
+
+use serde_json::Value;
+
+/// Most edits that can be undone.
+pub const DEPTH: usize = 20;
+
+/// An edit: what it was, and the tables before and after it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Step {
+    pub label: String,
+    pub before: Value,
+    pub after: Value,
+}
+
+/// The undo and redo stacks of a campaign.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct History {
+    undo: Vec<Step>,
+    redo: Vec<Step>,
+}
+
+impl History {
+    /// Record an edit. Anything that could be redone is forgotten, and only
+    /// the last `DEPTH` edits are kept.
+    pub fn record(&mut self, label: &str, before: Value, after: Value) {
+        self.redo.clear();
+        self.undo.push(Step {
+            label: label.to_string(),
+            before,
+            after,
+        });
+        if self.undo.len() > DEPTH {
+            self.undo.remove(0);
+        }
+    }
+
+    /// Forget every edit.
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+
+    /// Take the last edit to undo, given the tables as they stand now. The
+    /// history is cleared if they have changed since the edit.
+    pub fn undo(&mut self, current: &Value) -> Result<Option<Step>, String> {
+        let step = match self.undo.pop() {
+            Some(s) => s,
+            None => return Ok(None),
+        };
+        if step.after != *current {
+            self.clear();
+            return Err(format!(
+                "The campaign has changed since \"{}\", so it cannot be undone",
+                step.label
+            ));
+        }
+        self.redo.push(step.clone());
+        Ok(Some(step))
+    }
+
+    /// Take the last undone edit to redo, given the tables as they stand
+    /// now. The edits to redo are forgotten if the tables have changed.
+    pub fn redo(&mut self, current: &Value) -> Result<Option<Step>, String> {
+        let step = match self.redo.pop() {
+            Some(s) => s,
+            None => return Ok(None),
+        };
+        if step.before != *current {
+            self.redo.clear();
+            return Err(format!(
+                "The campaign has changed since \"{}\" was undone, so it cannot be redone",
+                step.label
+            ));
+        }
+        self.undo.push(step.clone());
+        Ok(Some(step))
+    }
+
+    /// Description of the edit that would be undone.
+    pub fn undo_label(&self) -> Option<&str> {
+        self.undo.last().map(|s| s.label.as_str())
+    }
+
+    /// Description of the edit that would be redone.
+    pub fn redo_label(&self) -> Option<&str> {
+        self.redo.last().map(|s| s.label.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{History, DEPTH};
+    use serde_json::json;
+
+    #[test]
+    fn undo_redo() {
+        let mut h = History::default();
+        assert_eq!(None, h.undo(&json!(0)).unwrap());
+        h.record("Delete Tibron", json!(0), json!(1));
+        h.record("Edit Senorian", json!(1), json!(2));
+        assert_eq!(Some("Edit Senorian"), h.undo_label());
+
+        let step = h.undo(&json!(2)).unwrap().unwrap();
+        assert_eq!(json!(1), step.before);
+        assert_eq!(Some("Edit Senorian"), h.redo_label());
+        assert_eq!(Some("Delete Tibron"), h.undo_label());
+
+        let step = h.redo(&json!(1)).unwrap().unwrap();
+        assert_eq!(json!(2), step.after);
+        assert_eq!(None, h.redo_label());
+
+        // A new edit forgets what could be redone.
+        h.undo(&json!(2)).unwrap();
+        h.record("Edit Human", json!(1), json!(3));
+        assert_eq!(None, h.redo_label());
+    }
+
+    #[test]
+    fn changed_since() {
+        let mut h = History::default();
+        h.record("Delete Tibron", json!(0), json!(1));
+        assert!(h.undo(&json!(5)).is_err());
+        assert_eq!(None, h.undo_label());
+
+        h.record("Delete Tibron", json!(0), json!(1));
+        h.undo(&json!(1)).unwrap();
+        assert!(h.redo(&json!(5)).is_err());
+        assert_eq!(None, h.redo_label());
+    }
+
+    #[test]
+    fn depth() {
+        let mut h = History::default();
+        for i in 0..DEPTH + 5 {
+            h.record(format!("Edit {}", i).as_str(), json!(i), json!(i + 1));
+        }
+        let mut n = 0;
+        while let Some(step) = h.undo(&json!(DEPTH + 5 - n)).unwrap() {
+            assert_eq!(json!(DEPTH + 5 - n - 1), step.before);
+            n += 1;
+        }
+        assert_eq!(DEPTH, n);
+    }
+}
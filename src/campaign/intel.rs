@@ -0,0 +1,77 @@
+// Copyright 2022 David Terhune
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Interface to intelligence items passed to empires.
+
+/// Kinds of intel item the GM can attach to a player packet.
+pub const KINDS: [&str; 3] = ["Scan", "Rumor", "Misinformation"];
+
+/// Kind of intel item generated by the campaign itself, such as a treaty
+/// lapsing.
+pub const NOTICE: &str = "Notice";
+
+/// A piece of intelligence to be delivered in an empire's player packet for
+/// a turn.
+#[derive(sqlx::FromRow, Clone, Debug, PartialEq, Eq)]
+pub struct IntelItem {
+    pub id: i64,
+    pub empire: i64,
+    pub turn: i32,
+    pub kind: String,
+    pub text: String,
+}
+
+impl IntelItem {
+    /// Create a new intel item.
+    pub fn new(empire: i64, turn: i32, kind: &str, text: &str) -> Self {
+        Self {
+            id: 0,
+            empire,
+            turn,
+            kind: kind.to_string(),
+            text: text.to_string(),
+        }
+    }
+
+    /// Convert to string as a row of tab-separated fields.
+    pub fn as_row(&self) -> String {
+        format!("{}\t{}\t{}", self.turn, self.kind, self.text)
+    }
+
+    /// Heading the player sees. Misinformation is presented as a rumor.
+    pub fn heading(&self) -> &str {
+        match self.kind.as_str() {
+            "Scan" => "Scan Result",
+            NOTICE => "Notice",
+            _ => "Rumor",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IntelItem;
+
+    #[test]
+    fn heading() {
+        assert_eq!("Scan Result", IntelItem::new(1, 0, "Scan", "").heading());
+        assert_eq!("Notice", IntelItem::new(1, 0, "Notice", "").heading());
+        assert_eq!("Rumor", IntelItem::new(1, 0, "Rumor", "").heading());
+        assert_eq!(
+            "Rumor",
+            IntelItem::new(1, 0, "Misinformation", "").heading()
+        );
+    }
+}
@@ -0,0 +1,93 @@
+// Copyright 2022 David Terhune
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The GM's journal: narrative notes and rulings, one Markdown entry per
+//! turn.
+
+/// The journal entry for a turn.
+#[derive(sqlx::FromRow, Clone, Debug, PartialEq, Eq)]
+pub struct JournalEntry {
+    pub turn: i32,
+    pub text: String,
+}
+
+impl JournalEntry {
+    /// Create a new journal entry.
+    pub fn new(turn: i32, text: &str) -> Self {
+        Self {
+            turn,
+            text: text.to_string(),
+        }
+    }
+
+    /// First line of the entry with any heading marks removed, to stand
+    /// for it in a list.
+    pub fn summary(&self) -> &str {
+        self.text
+            .lines()
+            .map(|l| l.trim().trim_start_matches('#').trim())
+            .find(|l| !l.is_empty())
+            .unwrap_or("")
+    }
+
+    /// Convert to string as a row of tab-separated fields.
+    pub fn as_row(&self) -> String {
+        format!("{}\t{}", self.turn, self.summary())
+    }
+}
+
+/// The journal as one Markdown document titled with the campaign name, each
+/// entry under a heading for its turn, in turn order. Empty entries are
+/// left out.
+pub fn to_markdown(title: &str, entries: &[JournalEntry]) -> String {
+    let mut entries: Vec<&JournalEntry> = entries
+        .iter()
+        .filter(|e| !e.text.trim().is_empty())
+        .collect();
+    entries.sort_by_key(|e| e.turn);
+    let mut doc = format!("# {}\n", title);
+    for e in entries {
+        doc.push_str(&format!("\n## Turn {}\n\n{}\n", e.turn, e.text.trim_end()));
+    }
+    doc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{to_markdown, JournalEntry};
+
+    #[test]
+    fn summary() {
+        let e = JournalEntry::new(3, "\n## Senorian Civil War\n\nThe fleet mutinied.");
+        assert_eq!("Senorian Civil War", e.summary());
+        assert_eq!("3\tSenorian Civil War", e.as_row());
+        assert_eq!("", JournalEntry::new(1, "  \n").summary());
+    }
+
+    #[test]
+    fn markdown() {
+        let entries = [
+            JournalEntry::new(2, "Ruled that lanes close in nebulae.\n\n"),
+            JournalEntry::new(1, "Opening moves."),
+            JournalEntry::new(3, " "),
+        ];
+        assert_eq!(
+            "# Senorian Wars\n\
+            \n## Turn 1\n\nOpening moves.\n\
+            \n## Turn 2\n\nRuled that lanes close in nebulae.\n",
+            to_markdown("Senorian Wars", &entries)
+        );
+    }
+}
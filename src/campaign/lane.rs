@@ -0,0 +1,145 @@
+// Copyright 2022 David Terhune
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Interface to jump lanes connecting star systems.
+
+use std::collections::BTreeSet;
+
+/// Types of jump lane.
+pub const LANE_TYPES: [&str; 3] = ["Major", "Minor", "Restricted"];
+
+/// A jump lane between two systems. Lanes can be travelled in either
+/// direction. A defended lane ends in jump points fleets must assault to
+/// come through, under first fire from the system's defenders.
+#[derive(sqlx::FromRow, Clone, Debug, PartialEq, Eq)]
+pub struct Lane {
+    pub id: i64,
+    pub a: i64,
+    pub b: i64,
+    pub ltype: String,
+    pub defended: bool,
+    #[sqlx(default)]
+    pub a_name: String,
+    #[sqlx(default)]
+    pub b_name: String,
+}
+
+impl Lane {
+    /// Create a new lane between two systems.
+    pub fn new(a: i64, b: i64, ltype: &str) -> Self {
+        Self {
+            id: 0,
+            a,
+            b,
+            ltype: ltype.to_string(),
+            defended: false,
+            a_name: String::new(),
+            b_name: String::new(),
+        }
+    }
+
+    /// Convert to string as a row of tab-separated fields.
+    pub fn as_row(&self) -> String {
+        format!(
+            "{}\t{}\t{}\t{}",
+            self.a_name,
+            self.b_name,
+            self.ltype,
+            if self.defended { "Defended" } else { "" }
+        )
+    }
+
+    /// Whether this lane connects the two systems, in either direction.
+    pub fn connects(&self, x: i64, y: i64) -> bool {
+        (self.a == x && self.b == y) || (self.a == y && self.b == x)
+    }
+
+    /// The system at the other end of the lane from the given one, if the
+    /// lane touches it.
+    pub fn other_end(&self, x: i64) -> Option<i64> {
+        if self.a == x {
+            Some(self.b)
+        } else if self.b == x {
+            Some(self.a)
+        } else {
+            None
+        }
+    }
+}
+
+/// Systems reachable from a system by a single jump.
+pub fn neighbors(lanes: &[Lane], sys: i64) -> Vec<i64> {
+    lanes.iter().filter_map(|l| l.other_end(sys)).collect()
+}
+
+/// Systems within the given number of jumps of any of the starting ones,
+/// the starting systems included.
+pub fn within(lanes: &[Lane], from: impl IntoIterator<Item = i64>, jumps: i32) -> BTreeSet<i64> {
+    let mut reached: BTreeSet<i64> = from.into_iter().collect();
+    let mut frontier: Vec<i64> = reached.iter().copied().collect();
+    for _ in 0..jumps {
+        let mut next = Vec::new();
+        for sys in frontier {
+            for n in neighbors(lanes, sys) {
+                if reached.insert(n) {
+                    next.push(n);
+                }
+            }
+        }
+        frontier = next;
+    }
+    reached
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::collections::BTreeSet;
+
+    use super::{neighbors, within, Lane};
+
+    pub fn lanes() -> Vec<Lane> {
+        vec![
+            Lane::new(1, 2, "Major"),
+            Lane::new(2, 3, "Minor"),
+            Lane::new(3, 4, "Restricted"),
+            Lane::new(1, 4, "Major"),
+        ]
+    }
+
+    #[test]
+    fn connects() {
+        let l = Lane::new(1, 2, "Major");
+        assert!(l.connects(1, 2));
+        assert!(l.connects(2, 1));
+        assert!(!l.connects(1, 3));
+    }
+
+    #[test]
+    fn neighbors_both_directions() {
+        let mut n = neighbors(&lanes(), 1);
+        n.sort();
+        assert_eq!(vec![2, 4], n);
+        assert_eq!(vec![2, 4], neighbors(&lanes(), 3));
+        assert!(neighbors(&lanes(), 5).is_empty());
+    }
+
+    #[test]
+    fn reach() {
+        assert_eq!(BTreeSet::from([1]), within(&lanes(), [1], 0));
+        assert_eq!(BTreeSet::from([1, 2, 4]), within(&lanes(), [1], 1));
+        assert_eq!(BTreeSet::from([1, 2, 3, 4]), within(&lanes(), [1], 2));
+        assert_eq!(BTreeSet::from([5]), within(&lanes(), [5], 3));
+    }
+}
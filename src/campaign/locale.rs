@@ -0,0 +1,169 @@
+// Copyright 2022 David Terhune
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! How numbers and dates are written in reports and on screen.
+//!
+//! A campaign's locale is one of its options, so every document its players
+//! receive is written the same way. Dates are kept as ISO 8601 text whatever
+//! the locale; only the way they are shown changes. The international locale
+//! writes numbers without grouping and dates as they are kept, which is how
+//! campaigns have always been written.
+
+use super::deadline::is_date;
+
+/// Order of the parts of a date.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DateOrder {
+    YearMonthDay,
+    MonthDayYear,
+    DayMonthYear,
+}
+
+/// A way of writing numbers and dates.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Locale {
+    /// Tag the campaign keeps.
+    pub tag: &'static str,
+    /// Name to choose it by.
+    pub name: &'static str,
+    /// Separator between groups of thousands, if numbers are grouped.
+    pub grouping: Option<char>,
+    /// Order of the parts of a date.
+    pub order: DateOrder,
+    /// Separator between the parts of a date.
+    pub date_sep: char,
+}
+
+/// Locales a campaign can use. The first is the default.
+pub static LOCALES: [Locale; 5] = [
+    Locale {
+        tag: "iso",
+        name: "International",
+        grouping: None,
+        order: DateOrder::YearMonthDay,
+        date_sep: '-',
+    },
+    Locale {
+        tag: "en-US",
+        name: "English (US)",
+        grouping: Some(','),
+        order: DateOrder::MonthDayYear,
+        date_sep: '/',
+    },
+    Locale {
+        tag: "en-GB",
+        name: "English (UK)",
+        grouping: Some(','),
+        order: DateOrder::DayMonthYear,
+        date_sep: '/',
+    },
+    Locale {
+        tag: "de-DE",
+        name: "Deutsch",
+        grouping: Some('.'),
+        order: DateOrder::DayMonthYear,
+        date_sep: '.',
+    },
+    Locale {
+        tag: "fr-FR",
+        name: "Français",
+        grouping: Some('\u{a0}'),
+        order: DateOrder::DayMonthYear,
+        date_sep: '/',
+    },
+];
+
+/// The locale with the given tag, or the default if there is none.
+pub fn for_tag(tag: &str) -> &'static Locale {
+    LOCALES.iter().find(|l| l.tag == tag).unwrap_or(&LOCALES[0])
+}
+
+impl Locale {
+    /// Write a number, grouping its thousands.
+    pub fn number(&self, n: i64) -> String {
+        let digits = n.unsigned_abs().to_string();
+        let sep = match self.grouping {
+            Some(c) if digits.len() > 3 => c,
+            _ => return n.to_string(),
+        };
+        let mut text = String::new();
+        if n < 0 {
+            text.push('-');
+        }
+        for (i, c) in digits.chars().enumerate() {
+            if i > 0 && (digits.len() - i).is_multiple_of(3) {
+                text.push(sep);
+            }
+            text.push(c);
+        }
+        text
+    }
+
+    /// Write an amount of EP.
+    pub fn ep(&self, n: i32) -> String {
+        format!("{} EP", self.number(n as i64))
+    }
+
+    /// Write a date kept as ISO 8601 text. Any time after the date is kept
+    /// as it is, and text that is not a date is returned unchanged.
+    pub fn date(&self, iso: &str) -> String {
+        let (day, rest) = match iso.get(..10) {
+            Some(d) if is_date(d) => (d, &iso[10..]),
+            _ => return iso.to_string(),
+        };
+        let (y, m, d) = (&day[..4], &day[5..7], &day[8..]);
+        let parts = match self.order {
+            DateOrder::YearMonthDay => [y, m, d],
+            DateOrder::MonthDayYear => [m, d, y],
+            DateOrder::DayMonthYear => [d, m, y],
+        };
+        format!("{}{}", parts.join(&self.date_sep.to_string()), rest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{for_tag, LOCALES};
+
+    #[test]
+    fn number() {
+        let iso = &LOCALES[0];
+        assert_eq!("1234567", iso.number(1234567));
+        let us = for_tag("en-US");
+        assert_eq!("999", us.number(999));
+        assert_eq!("1,000", us.number(1000));
+        assert_eq!("-1,234,567", us.number(-1234567));
+        assert_eq!("12.345 EP", for_tag("de-DE").ep(12345));
+        assert_eq!("1\u{a0}500 EP", for_tag("fr-FR").ep(1500));
+    }
+
+    #[test]
+    fn date() {
+        assert_eq!("2026-10-15", LOCALES[0].date("2026-10-15"));
+        assert_eq!("10/15/2026", for_tag("en-US").date("2026-10-15"));
+        assert_eq!(
+            "15/10/2026 09:30:00",
+            for_tag("en-GB").date("2026-10-15 09:30:00")
+        );
+        assert_eq!("15.10.2026", for_tag("de-DE").date("2026-10-15"));
+        assert_eq!("", for_tag("de-DE").date(""));
+        assert_eq!("next week", for_tag("en-US").date("next week"));
+    }
+
+    #[test]
+    fn unknown_tag() {
+        assert_eq!(&LOCALES[0], for_tag("tlh"));
+    }
+}
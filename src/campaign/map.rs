@@ -0,0 +1,211 @@
+// Copyright 2022 David Terhune
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Layout of the campaign map.
+//!
+//! Systems sit at integer map coordinates. A view scales and pans those onto
+//! the screen, and finds the system under a point.
+
+use super::system::System;
+
+/// Map units between systems placed on the default grid.
+pub const GRID_SPACING: i32 = 60;
+
+/// Systems in a row of the default grid.
+pub const GRID_COLUMNS: i32 = 10;
+
+/// Radius of a system's node on screen, in pixels.
+pub const NODE_RADIUS: i32 = 8;
+
+/// Smallest and largest zoom.
+pub const MIN_SCALE: f64 = 0.25;
+pub const MAX_SCALE: f64 = 4.0;
+
+/// Colors given to owners, by empire ID.
+const OWNER_COLORS: [(u8, u8, u8); 8] = [
+    (200, 40, 40),
+    (40, 90, 200),
+    (40, 160, 60),
+    (220, 160, 20),
+    (150, 60, 180),
+    (20, 170, 170),
+    (220, 100, 160),
+    (130, 90, 50),
+];
+
+/// Color of unowned systems.
+const UNOWNED_COLOR: (u8, u8, u8) = (150, 150, 150);
+
+/// Position of the nth system on the default grid, for systems placed
+/// without coordinates.
+pub fn grid_position(n: usize) -> (i32, i32) {
+    let n = n as i32;
+    (
+        (n % GRID_COLUMNS) * GRID_SPACING,
+        (n / GRID_COLUMNS) * GRID_SPACING,
+    )
+}
+
+/// Color of an owner's systems, as red, green and blue. Unowned systems are
+/// grey; empires cycle through the palette.
+pub fn owner_color(owner: i64) -> (u8, u8, u8) {
+    if owner <= 0 {
+        UNOWNED_COLOR
+    } else {
+        OWNER_COLORS[(owner - 1) as usize % OWNER_COLORS.len()]
+    }
+}
+
+/// How the map is shown: screen pixels per map unit, and the screen position
+/// of the map origin.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MapView {
+    pub scale: f64,
+    pub pan_x: i32,
+    pub pan_y: i32,
+}
+
+impl Default for MapView {
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            pan_x: 0,
+            pan_y: 0,
+        }
+    }
+}
+
+impl MapView {
+    /// View that fits every system into a screen area of the given size,
+    /// with a margin around the edge.
+    pub fn fit(systems: &[System], width: i32, height: i32) -> Self {
+        if systems.is_empty() {
+            return Self::default();
+        }
+        let margin = 2 * NODE_RADIUS + 20;
+        let min_x = systems.iter().map(|s| s.x).min().unwrap();
+        let max_x = systems.iter().map(|s| s.x).max().unwrap();
+        let min_y = systems.iter().map(|s| s.y).min().unwrap();
+        let max_y = systems.iter().map(|s| s.y).max().unwrap();
+        let span_x = (max_x - min_x).max(1) as f64;
+        let span_y = (max_y - min_y).max(1) as f64;
+        let scale = ((width - 2 * margin) as f64 / span_x)
+            .min((height - 2 * margin) as f64 / span_y)
+            .clamp(MIN_SCALE, MAX_SCALE);
+        Self {
+            scale,
+            pan_x: margin - (min_x as f64 * scale).round() as i32,
+            pan_y: margin - (min_y as f64 * scale).round() as i32,
+        }
+    }
+
+    /// Screen position of a map point.
+    pub fn to_screen(self, x: i32, y: i32) -> (i32, i32) {
+        (
+            (x as f64 * self.scale).round() as i32 + self.pan_x,
+            (y as f64 * self.scale).round() as i32 + self.pan_y,
+        )
+    }
+
+    /// Move the map by a screen distance.
+    pub fn pan(&mut self, dx: i32, dy: i32) {
+        self.pan_x += dx;
+        self.pan_y += dy;
+    }
+
+    /// Zoom by a factor, keeping the map point under the screen position
+    /// where it is.
+    pub fn zoom(&mut self, factor: f64, px: i32, py: i32) {
+        let mx = (px - self.pan_x) as f64 / self.scale;
+        let my = (py - self.pan_y) as f64 / self.scale;
+        self.scale = (self.scale * factor).clamp(MIN_SCALE, MAX_SCALE);
+        self.pan_x = px - (mx * self.scale).round() as i32;
+        self.pan_y = py - (my * self.scale).round() as i32;
+    }
+
+    /// Move the map so a map point is at the middle of a screen area of the
+    /// given size, keeping the scale.
+    pub fn center_on(&mut self, x: i32, y: i32, width: i32, height: i32) {
+        let (px, py) = self.to_screen(x, y);
+        self.pan(width / 2 - px, height / 2 - py);
+    }
+
+    /// The system whose node is under a screen position, the nearest if
+    /// nodes overlap.
+    pub fn system_at<'a>(&self, systems: &'a [System], px: i32, py: i32) -> Option<&'a System> {
+        systems
+            .iter()
+            .map(|s| {
+                let (sx, sy) = self.to_screen(s.x, s.y);
+                (s, (sx - px).pow(2) + (sy - py).pow(2))
+            })
+            .filter(|(_, d)| *d <= NODE_RADIUS * NODE_RADIUS)
+            .min_by_key(|(_, d)| *d)
+            .map(|(s, _)| s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{grid_position, owner_color, MapView, GRID_SPACING, MAX_SCALE, UNOWNED_COLOR};
+    use crate::campaign::system::tests::systems;
+
+    #[test]
+    fn grid() {
+        assert_eq!((0, 0), grid_position(0));
+        assert_eq!((3 * GRID_SPACING, 0), grid_position(3));
+        assert_eq!((GRID_SPACING, 2 * GRID_SPACING), grid_position(21));
+    }
+
+    #[test]
+    fn colors() {
+        assert_eq!(UNOWNED_COLOR, owner_color(0));
+        assert_ne!(owner_color(1), owner_color(2));
+        assert_eq!(owner_color(1), owner_color(9));
+    }
+
+    #[test]
+    fn transform() {
+        let mut view = MapView::default();
+        view.pan(100, 50);
+        assert_eq!((160, 50), view.to_screen(60, 0));
+
+        // The point under the cursor stays put.
+        view.zoom(2.0, 160, 50);
+        assert_eq!((160, 50), view.to_screen(60, 0));
+        assert_eq!((280, 50), view.to_screen(120, 0));
+
+        view.zoom(100.0, 0, 0);
+        assert_eq!(MAX_SCALE, view.scale);
+
+        view.center_on(60, 0, 600, 400);
+        assert_eq!((300, 200), view.to_screen(60, 0));
+    }
+
+    #[test]
+    fn fit_and_pick() {
+        let mut v = systems();
+        for (n, s) in v.iter_mut().enumerate() {
+            (s.x, s.y) = grid_position(n);
+        }
+        let view = MapView::fit(&v, 600, 400);
+        for s in &v {
+            let (px, py) = view.to_screen(s.x, s.y);
+            assert!((0..600).contains(&px) && (0..400).contains(&py));
+            assert_eq!(Some(s), view.system_at(&v, px + 2, py - 2));
+        }
+        assert_eq!(None, view.system_at(&v, -100, -100));
+    }
+}
@@ -0,0 +1,207 @@
+// Copyright 2022 David Terhune
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Morale checks.
+
+use super::ownership::{self, CONTESTED_MORALE};
+use super::system::{System, MARTIAL_LAW, TAX_EXEMPT};
+
+/// Turns after its conquest that a system's morale suffers.
+pub const CONQUEST_TURNS: i32 = 3;
+
+/// The factors in a system's morale check for one turn.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MoraleCheck {
+    pub blockade: i32,
+    pub failures: i32,
+    pub conquest: i32,
+    pub contested: i32,
+    pub policy: i32,
+    pub empire: i32,
+    pub planet: i32,
+    pub government: i32,
+}
+
+impl MoraleCheck {
+    /// Gather the factors for an owned system.
+    ///
+    /// A blockade costs one point, as does any development failure since the
+    /// last check, and so does a conquest within the last `CONQUEST_TURNS`
+    /// turns, and being contested by another empire. A tax exemption gains a
+    /// point and martial law costs one. The owner's empire-wide modifier is
+    /// added as given.
+    pub fn new(sys: &System, recently_conquered: bool, empire: i32) -> Self {
+        Self {
+            blockade: if sys.blockaded { -1 } else { 0 },
+            failures: if sys.fails > 0 { -1 } else { 0 },
+            conquest: if recently_conquered { -1 } else { 0 },
+            contested: if ownership::is_contested(sys) {
+                CONTESTED_MORALE
+            } else {
+                0
+            },
+            policy: match sys.policy.as_str() {
+                TAX_EXEMPT => 1,
+                MARTIAL_LAW => -1,
+                _ => 0,
+            },
+            empire,
+            planet: 0,
+            government: 0,
+        }
+    }
+
+    /// Add the morale modifier of the system's planet type.
+    pub fn with_planet(self, mor_mod: i32) -> Self {
+        Self {
+            planet: mor_mod,
+            ..self
+        }
+    }
+
+    /// Add the morale modifier of the owner's government and traits.
+    pub fn with_government(self, mor_mod: i32) -> Self {
+        Self {
+            government: mor_mod,
+            ..self
+        }
+    }
+
+    /// Net of all factors.
+    pub fn net(&self) -> i32 {
+        self.blockade
+            + self.failures
+            + self.conquest
+            + self.contested
+            + self.policy
+            + self.empire
+            + self.planet
+            + self.government
+    }
+
+    /// Apply the check to a system. Morale falls by the net of the factors,
+    /// or recovers by one point if they are favorable, never rising above
+    /// the population nor falling below zero. Development failures are
+    /// cleared once counted.
+    pub fn apply(&self, sys: &System) -> System {
+        let change = self.net().min(1);
+        System {
+            mor: (sys.mor + change).min(sys.pop).max(0),
+            fails: 0,
+            ..sys.clone()
+        }
+    }
+
+    /// Describe the factors that applied.
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if self.blockade != 0 {
+            parts.push(format!("blockade {}", self.blockade));
+        }
+        if self.failures != 0 {
+            parts.push(format!("development failures {}", self.failures));
+        }
+        if self.conquest != 0 {
+            parts.push(format!("recent conquest {}", self.conquest));
+        }
+        if self.contested != 0 {
+            parts.push(format!("contested {}", self.contested));
+        }
+        if self.policy != 0 {
+            parts.push(format!("policy {:+}", self.policy));
+        }
+        if self.empire != 0 {
+            parts.push(format!("empire {:+}", self.empire));
+        }
+        if self.planet != 0 {
+            parts.push(format!("planet type {:+}", self.planet));
+        }
+        if self.government != 0 {
+            parts.push(format!("government {:+}", self.government));
+        }
+        if parts.is_empty() {
+            "no modifiers".to_string()
+        } else {
+            parts.join(", ")
+        }
+    }
+}
+
+/// Whether an owned system has fallen into rebellion.
+pub fn in_rebellion(sys: &System) -> bool {
+    sys.owner != 0 && sys.mor <= 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{in_rebellion, MoraleCheck};
+    use crate::campaign::system::tests::systems;
+    use crate::campaign::system::{MARTIAL_LAW, TAX_EXEMPT};
+
+    #[test]
+    fn factors() {
+        let mut sys = systems()[0].clone();
+        assert_eq!(0, MoraleCheck::new(&sys, false, 0).net());
+        sys.blockaded = true;
+        sys.fails = 2;
+        let check = MoraleCheck::new(&sys, true, -1);
+        assert_eq!(-4, check.net());
+        assert_eq!(
+            "blockade -1, development failures -1, recent conquest -1, empire -1",
+            check.describe()
+        );
+        let check = check.with_planet(-1);
+        assert_eq!(-5, check.net());
+        assert!(check.describe().ends_with("planet type -1"));
+        let check = check.with_government(1);
+        assert_eq!(-4, check.net());
+        assert!(check.describe().ends_with("government +1"));
+
+        sys.owner = 1;
+        sys.contender = 2;
+        let check = MoraleCheck::new(&sys, false, 0);
+        assert_eq!(-3, check.net());
+        assert!(check.describe().ends_with("contested -1"));
+
+        sys.contender = 0;
+        sys.policy = MARTIAL_LAW.to_string();
+        let check = MoraleCheck::new(&sys, false, 0);
+        assert_eq!(-3, check.net());
+        assert!(check.describe().ends_with("policy -1"));
+        sys.policy = TAX_EXEMPT.to_string();
+        assert_eq!(-1, MoraleCheck::new(&sys, false, 0).net());
+    }
+
+    #[test]
+    fn apply() {
+        let mut sys = systems()[0].clone(); // population 10, morale 8
+        sys.fails = 1;
+        let after = MoraleCheck::new(&sys, false, 0).apply(&sys);
+        assert_eq!(sys.mor - 1, after.mor);
+        assert_eq!(0, after.fails);
+
+        // Recovery is one point at most, and never above population.
+        let after = MoraleCheck::new(&after, false, 3).apply(&after);
+        assert_eq!(sys.mor, after.mor);
+        sys.mor = sys.pop;
+        assert_eq!(sys.pop, MoraleCheck::new(&sys, false, 1).apply(&sys).mor);
+
+        sys.mor = 1;
+        sys.owner = 1;
+        let after = MoraleCheck::new(&sys, true, -2).apply(&sys);
+        assert_eq!(0, after.mor);
+        assert!(in_rebellion(&after));
+    }
+}
@@ -0,0 +1,326 @@
+// Copyright 2022 David Terhune
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Fleet movement rules.
+//!
+//! A fleet ordered to a distant system follows the shortest way there along
+//! the jump lanes, as far as its allowance takes it each turn, and keeps to
+//! its course on later turns until it arrives. Fleets move a jump at a time
+//! together, so one that runs into a hostile fleet on the way is stopped
+//! there: by entering a system a hostile fleet is in, or by meeting one
+//! coming the other way along the same lane.
+
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use super::lane::{self, Lane};
+
+/// Jumps per turn for a fleet. A fleet moves at the speed of its slowest
+/// ship class; a fleet speed greater than zero, such as that of a freighter
+/// convoy, further limits it. An empty fleet with no speed of its own makes
+/// one jump.
+pub fn allowance(fleet_speed: i32, class_speed: Option<i32>) -> i32 {
+    match (fleet_speed > 0, class_speed) {
+        (true, Some(c)) => fleet_speed.min(c),
+        (true, None) => fleet_speed,
+        (false, Some(c)) => c,
+        (false, None) => 1,
+    }
+}
+
+/// Check a route, given as the list of systems visited starting with the
+/// fleet's current location, against the jump lanes and the fleet's
+/// allowance.
+pub fn validate_route(lanes: &[Lane], route: &[i64], allowance: i32) -> Result<(), String> {
+    let jumps = route.len().saturating_sub(1);
+    if jumps as i32 > allowance {
+        return Err(format!(
+            "Route needs {} jumps but the fleet can only make {}",
+            jumps, allowance
+        ));
+    }
+    for step in route.windows(2) {
+        if !lanes.iter().any(|l| l.connects(step[0], step[1])) {
+            return Err(format!(
+                "No jump lane from system {} to system {}",
+                step[0], step[1]
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// The systems passed through on the shortest way from one system to
+/// another along the lanes, ending with the destination, if it can be
+/// reached at all. Of equally short ways, the one through the systems with
+/// the lowest IDs is taken.
+pub fn path(lanes: &[Lane], from: i64, to: i64) -> Option<Vec<i64>> {
+    let mut came_from: BTreeMap<i64, i64> = BTreeMap::new();
+    let mut seen = BTreeSet::from([from]);
+    let mut queue = VecDeque::from([from]);
+    while let Some(sys) = queue.pop_front() {
+        if sys == to {
+            let mut v = Vec::new();
+            let mut at = to;
+            while at != from {
+                v.push(at);
+                at = came_from[&at];
+            }
+            v.reverse();
+            return Some(v);
+        }
+        let mut next = lane::neighbors(lanes, sys);
+        next.sort_unstable();
+        for n in next {
+            if seen.insert(n) {
+                came_from.insert(n, sys);
+                queue.push_back(n);
+            }
+        }
+    }
+    None
+}
+
+/// The course through each of the given systems in turn, the last being
+/// the destination, taking the shortest way between each. Fails naming the
+/// first that cannot be reached.
+pub fn plot(lanes: &[Lane], from: i64, waypoints: &[i64]) -> Result<Vec<i64>, i64> {
+    let mut course = Vec::new();
+    let mut at = from;
+    for w in waypoints {
+        course.extend(path(lanes, at, *w).ok_or(*w)?);
+        at = *w;
+    }
+    Ok(course)
+}
+
+/// A fleet, with any escorts, at the start of the movement phase: where it
+/// is, the course it is to follow and the jumps it can make this turn. A
+/// fleet standing still has no course.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Transit {
+    pub fleet: i64,
+    pub owner: i64,
+    pub at: i64,
+    pub course: Vec<i64>,
+    pub jumps: i32,
+}
+
+impl Transit {
+    /// A fleet setting out on its course from where it is now. A course that
+    /// no longer starts next to the fleet, because it was moved some other
+    /// way, is plotted afresh to the same destination.
+    pub fn resume(
+        lanes: &[Lane],
+        (fleet, owner, at): (i64, i64, i64),
+        course: &[i64],
+        jumps: i32,
+    ) -> Self {
+        let course = match course {
+            [] => Vec::new(),
+            [next, ..] if lanes.iter().any(|l| l.connects(at, *next)) => course.to_vec(),
+            [.., dest] => path(lanes, at, *dest).unwrap_or_default(),
+        };
+        Self {
+            fleet,
+            owner,
+            at,
+            course,
+            jumps,
+        }
+    }
+}
+
+/// A moving fleet stopped by a hostile one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Interception {
+    pub fleet: i64,
+    pub by: i64,
+    pub system: i64,
+}
+
+/// Move the fleets a jump at a time, as far as their allowances and courses
+/// take them, given which empires are hostile to each other. Each fleet is
+/// left where it stopped with the rest of its course. A fleet entering a
+/// system a hostile fleet is in is stopped there. Of two hostile fleets
+/// that would pass each other on a lane, the one with the lower ID makes
+/// its jump and the other is held, so they meet.
+pub fn resolve(transits: &mut [Transit], hostile: impl Fn(i64, i64) -> bool) -> Vec<Interception> {
+    let mut stopped = vec![false; transits.len()];
+    let mut caught = Vec::new();
+    let most = transits.iter().map(|t| t.jumps).max().unwrap_or(0);
+    for jump in 0..most {
+        let mut going: Vec<bool> = transits
+            .iter()
+            .zip(&stopped)
+            .map(|(t, s)| !s && jump < t.jumps && !t.course.is_empty())
+            .collect();
+        let mut held = Vec::new();
+        for (i, a) in transits.iter().enumerate() {
+            for (j, b) in transits.iter().enumerate().skip(i + 1) {
+                if going[i]
+                    && going[j]
+                    && hostile(a.owner, b.owner)
+                    && a.course[0] == b.at
+                    && b.course[0] == a.at
+                {
+                    held.push(if a.fleet < b.fleet {
+                        (j, a.fleet)
+                    } else {
+                        (i, b.fleet)
+                    });
+                }
+            }
+        }
+        for (i, by) in held {
+            going[i] = false;
+            stopped[i] = true;
+            caught.push(Interception {
+                fleet: transits[i].fleet,
+                by,
+                system: transits[i].at,
+            });
+        }
+        for (t, go) in transits.iter_mut().zip(&going) {
+            if *go {
+                t.at = t.course.remove(0);
+            }
+        }
+        for i in (0..transits.len()).filter(|i| going[*i]) {
+            let t = &transits[i];
+            let by = transits
+                .iter()
+                .find(|o| o.at == t.at && hostile(t.owner, o.owner))
+                .map(|o| o.fleet);
+            if let Some(by) = by {
+                stopped[i] = true;
+                caught.push(Interception {
+                    fleet: t.fleet,
+                    by,
+                    system: t.at,
+                });
+            }
+        }
+    }
+    caught
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{allowance, path, plot, resolve, validate_route, Interception, Transit};
+    use crate::campaign::lane::tests::lanes;
+
+    #[test]
+    fn fleet_allowance() {
+        assert_eq!(1, allowance(0, None));
+        assert_eq!(3, allowance(0, Some(3)));
+        assert_eq!(1, allowance(1, Some(3)));
+        assert_eq!(2, allowance(4, Some(2)));
+        assert_eq!(2, allowance(2, None));
+    }
+
+    #[test]
+    fn route_within_allowance() {
+        assert!(validate_route(&lanes(), &[1, 2, 3], 2).is_ok());
+        assert!(validate_route(&lanes(), &[1], 0).is_ok());
+    }
+
+    #[test]
+    fn route_too_long() {
+        assert!(validate_route(&lanes(), &[1, 2, 3], 1).is_err());
+    }
+
+    #[test]
+    fn route_without_lane() {
+        assert!(validate_route(&lanes(), &[1, 3], 2).is_err());
+    }
+
+    #[test]
+    fn shortest_path() {
+        let lanes = lanes();
+        assert_eq!(Some(vec![2, 3]), path(&lanes, 1, 3));
+        assert_eq!(Some(vec![4]), path(&lanes, 1, 4));
+        assert_eq!(Some(vec![]), path(&lanes, 2, 2));
+        assert_eq!(None, path(&lanes, 1, 5));
+        assert_eq!(Ok(vec![2, 3, 2]), plot(&lanes, 1, &[3, 2]));
+        assert_eq!(Ok(vec![2, 3, 4]), plot(&lanes, 1, &[2, 3, 4]));
+        assert_eq!(Err(5), plot(&lanes, 1, &[3, 5]));
+    }
+
+    #[test]
+    fn resumed_course() {
+        let lanes = lanes();
+        assert_eq!(
+            vec![2, 3],
+            Transit::resume(&lanes, (1, 1, 1), &[2, 3], 1).course
+        );
+        // Moved off its course, the fleet heads for the same destination.
+        assert_eq!(
+            vec![3],
+            Transit::resume(&lanes, (1, 1, 4), &[2, 3], 1).course
+        );
+        assert!(Transit::resume(&lanes, (1, 1, 4), &[], 1).course.is_empty());
+    }
+
+    #[test]
+    fn moving() {
+        let transit = |fleet: i64, owner: i64, at: i64, course: &[i64], jumps: i32| Transit {
+            fleet,
+            owner,
+            at,
+            course: course.to_vec(),
+            jumps,
+        };
+        let hostile = |a: i64, b: i64| a != b;
+
+        // Fleets of one empire pass freely; allowances limit the jumps.
+        let mut v = vec![transit(1, 1, 1, &[2, 3, 4], 2), transit(2, 1, 3, &[2], 1)];
+        assert!(resolve(&mut v, hostile).is_empty());
+        assert_eq!((3, vec![4]), (v[0].at, v[0].course.clone()));
+        assert_eq!(2, v[1].at);
+
+        // A fleet entering a system a hostile fleet is in stops there.
+        let mut v = vec![transit(1, 1, 1, &[2, 3, 4], 3), transit(2, 2, 3, &[], 1)];
+        assert_eq!(
+            vec![Interception {
+                fleet: 1,
+                by: 2,
+                system: 3
+            }],
+            resolve(&mut v, hostile)
+        );
+        assert_eq!((3, vec![4]), (v[0].at, v[0].course.clone()));
+
+        // Hostile fleets crossing a lane meet where the lower ID was going.
+        let mut v = vec![transit(2, 2, 3, &[2, 1], 2), transit(1, 1, 2, &[3, 4], 2)];
+        let caught = resolve(&mut v, hostile);
+        assert_eq!(
+            vec![
+                Interception {
+                    fleet: 2,
+                    by: 1,
+                    system: 3
+                },
+                Interception {
+                    fleet: 1,
+                    by: 2,
+                    system: 3
+                }
+            ],
+            caught
+        );
+        assert_eq!((3, 3), (v[0].at, v[1].at));
+        assert_eq!(vec![2, 1], v[0].course);
+    }
+}
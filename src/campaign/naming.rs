@@ -0,0 +1,106 @@
+// Copyright 2022 David Terhune
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Campaign names.
+//!
+//! A campaign's name becomes the name of its database file and of the
+//! folders holding its attachments and backups, with spaces written as
+//! underscores. Names are checked before a campaign is created so that each
+//! one maps to a file of its own on every platform.
+
+/// Longest name allowed, in characters.
+pub const MAX_LENGTH: usize = 64;
+
+/// Characters that cannot appear in a name: path separators, characters
+/// reserved in file names or SQLite URLs, and the underscore that stands for
+/// a space in file names.
+pub const RESERVED: [char; 12] = ['/', '\\', ':', '*', '?', '"', '<', '>', '|', '#', '%', '_'];
+
+/// Names already used for other files in the data folder, or reserved as
+/// device names on Windows. Compared regardless of case.
+const RESERVED_NAMES: [&str; 5] = ["backups", "con", "prn", "aux", "nul"];
+
+/// Check a name for a new campaign against the names of the existing ones,
+/// returning it without surrounding spaces.
+pub fn validate(name: &str, existing: &[String]) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("The campaign needs a name".to_string());
+    }
+    if name.chars().count() > MAX_LENGTH {
+        return Err(format!(
+            "Campaign names can be at most {} characters long",
+            MAX_LENGTH
+        ));
+    }
+    if let Some(c) = name
+        .chars()
+        .find(|c| RESERVED.contains(c) || c.is_control())
+    {
+        return Err(format!(
+            "Campaign names cannot contain {}",
+            if c.is_control() {
+                "control characters".to_string()
+            } else {
+                format!("'{}'", c)
+            }
+        ));
+    }
+    if name.starts_with('.') || name.ends_with('.') {
+        return Err("Campaign names cannot start or end with a period".to_string());
+    }
+    let lower = name.to_lowercase();
+    let device = lower
+        .strip_prefix("com")
+        .or_else(|| lower.strip_prefix("lpt"));
+    if RESERVED_NAMES.contains(&lower.as_str())
+        || device.is_some_and(|n| n.len() == 1 && n.chars().all(|c| c.is_ascii_digit()))
+    {
+        return Err(format!("{} is a reserved name", name));
+    }
+    if let Some(other) = existing.iter().find(|e| e.to_lowercase() == lower) {
+        return Err(format!("There is already a campaign named {}", other));
+    }
+    Ok(name.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::validate;
+
+    #[test]
+    fn names() {
+        let existing = vec!["Senor Wars".to_string()];
+        assert_eq!(
+            Ok("Kili Uprising".to_string()),
+            validate(" Kili Uprising ", &existing)
+        );
+        assert!(validate("  ", &existing).is_err());
+        assert!(validate(&"x".repeat(65), &existing).is_err());
+        assert!(validate("../escape", &existing).is_err());
+        assert!(validate("a\\b", &existing).is_err());
+        assert!(validate("Turn?mode=ro", &existing).is_err());
+        assert!(validate("Senor_Wars", &existing).is_err());
+        assert!(validate("tab\there", &existing).is_err());
+        assert!(validate(".hidden", &existing).is_err());
+        assert!(validate("Backups", &existing).is_err());
+        assert!(validate("COM1", &existing).is_err());
+        assert!(validate("Comet", &existing).is_ok());
+        assert_eq!(
+            Err("There is already a campaign named Senor Wars".to_string()),
+            validate("senor wars", &existing)
+        );
+    }
+}
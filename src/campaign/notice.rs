@@ -0,0 +1,84 @@
+// Copyright 2022 David Terhune
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Notices: brief messages that tell the GM something without interrupting
+//! them. Each is shown for a few seconds and kept in a history the GM can
+//! look back over. Errors still call for an alert.
+
+use std::collections::VecDeque;
+
+/// Seconds a notice stays on screen.
+pub const TOAST_SECONDS: f64 = 4.0;
+
+/// Notices kept in the history; the oldest are dropped beyond this.
+pub const HISTORY: usize = 100;
+
+/// The notices given this session, oldest first.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Notices {
+    items: VecDeque<String>,
+}
+
+impl Notices {
+    /// Add a notice to the history, dropping the oldest if it is full.
+    pub fn push(&mut self, text: &str) {
+        if self.items.len() >= HISTORY {
+            self.items.pop_front();
+        }
+        self.items.push_back(text.to_string());
+    }
+
+    /// The notices, newest first.
+    pub fn latest_first(&self) -> impl Iterator<Item = &String> {
+        self.items.iter().rev()
+    }
+
+    /// Forget every notice.
+    pub fn clear(&mut self) {
+        self.items.clear();
+    }
+
+    /// Whether there are no notices.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Notices, HISTORY};
+
+    #[test]
+    fn history() {
+        let mut n = Notices::default();
+        assert!(n.is_empty());
+        n.push("Opened Alpha campaign");
+        n.push("Nothing to undo.");
+        assert_eq!(
+            vec!["Nothing to undo.", "Opened Alpha campaign"],
+            n.latest_first().collect::<Vec<_>>()
+        );
+
+        // The oldest notices are dropped once the history is full.
+        for i in 0..HISTORY {
+            n.push(i.to_string().as_str());
+        }
+        assert_eq!(HISTORY, n.latest_first().count());
+        assert_eq!(Some(&"0".to_string()), n.latest_first().last());
+
+        n.clear();
+        assert!(n.is_empty());
+    }
+}
@@ -0,0 +1,348 @@
+// Copyright 2022 David Terhune
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Options chosen when a campaign is created.
+//!
+//! They are kept as settings in the control table alongside the turn, the
+//! optional rules using the same keys as their own toggles.
+
+use std::collections::BTreeMap;
+
+use super::attrition::ATTRITION;
+use super::economy::COLONIAL_ADMIN;
+use super::locale::{self, Locale, LOCALES};
+use super::raid::STRATEGIC_WARFARE;
+use super::ruleset::{self, Ruleset};
+
+/// Setting keys in the control table.
+const START_TURN: &str = "start_turn";
+const EDITION: &str = "edition";
+const MAP_SIZE: &str = "map_size";
+const EMPIRES: &str = "empires";
+const DIFFICULTY: &str = "difficulty";
+const DICE_SEED: &str = "dice_seed";
+const LOCALE: &str = "locale";
+
+/// Setting key of the name of the setting module the campaign uses.
+pub const SETTING: &str = "setting";
+
+/// Setting key of the file the setting module was loaded from.
+pub const SETTING_FILE: &str = "setting_file";
+
+/// Editions of the VBAM rules.
+pub const EDITIONS: [&str; 2] = ["Second", "First"];
+
+/// Map sizes, with the number of systems each is meant to have.
+pub const MAP_SIZES: [(&str, i32); 3] = [("Small", 20), ("Medium", 40), ("Large", 80)];
+
+/// Economic difficulties, with the percentage of income empires collect.
+pub const DIFFICULTIES: [(&str, i32); 3] = [("Easy", 125), ("Normal", 100), ("Hard", 75)];
+
+/// Most empires a campaign can be set up for.
+pub const MAX_EMPIRES: i32 = 12;
+
+/// How a campaign is played.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CampaignOptions {
+    /// Turn the campaign starts on.
+    pub start_turn: i32,
+    /// Edition of the rules.
+    pub edition: String,
+    /// Size of the map, one of `MAP_SIZES`.
+    pub map_size: String,
+    /// Number of empires taking part, 0 for no limit.
+    pub empires: i32,
+    /// Economic difficulty, one of `DIFFICULTIES`.
+    pub difficulty: String,
+    /// Whether the colonial administration rule is in use.
+    pub colonial_admin: bool,
+    /// Whether the strategic warfare rules are in use.
+    pub strategic_warfare: bool,
+    /// Whether the attrition rules are in use.
+    pub attrition: bool,
+    /// Name of the setting module the unit rosters came from.
+    pub setting: String,
+    /// File the setting module was loaded from, empty for the standard one.
+    pub setting_file: String,
+    /// Seed the dice of every turn take in, 0 for none.
+    pub dice_seed: u64,
+    /// Tag of the locale numbers and dates are written in, one of `LOCALES`.
+    pub locale: String,
+}
+
+impl Default for CampaignOptions {
+    fn default() -> Self {
+        Self {
+            start_turn: 0,
+            edition: EDITIONS[0].to_string(),
+            map_size: MAP_SIZES[1].0.to_string(),
+            empires: 0,
+            difficulty: DIFFICULTIES[1].0.to_string(),
+            colonial_admin: false,
+            strategic_warfare: false,
+            attrition: false,
+            setting: "Standard".to_string(),
+            setting_file: String::new(),
+            dice_seed: 0,
+            locale: LOCALES[0].tag.to_string(),
+        }
+    }
+}
+
+impl CampaignOptions {
+    /// Options read from control table settings. Settings never made keep
+    /// their defaults, so campaigns created before options were kept play
+    /// as they always have.
+    pub fn from_control(settings: &BTreeMap<String, String>) -> Self {
+        let mut options = Self::default();
+        let number = |key: &str, default: i32| {
+            settings
+                .get(key)
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        };
+        let flag = |key: &str| settings.get(key).map(String::as_str) == Some("1");
+        options.start_turn = number(START_TURN, options.start_turn);
+        options.empires = number(EMPIRES, options.empires);
+        if let Some(v) = settings.get(EDITION) {
+            options.edition = v.clone();
+        }
+        if let Some(v) = settings.get(MAP_SIZE) {
+            options.map_size = v.clone();
+        }
+        if let Some(v) = settings.get(DIFFICULTY) {
+            options.difficulty = v.clone();
+        }
+        if let Some(v) = settings.get(SETTING) {
+            options.setting = v.clone();
+        }
+        if let Some(v) = settings.get(SETTING_FILE) {
+            options.setting_file = v.clone();
+        }
+        if let Some(v) = settings.get(DICE_SEED).and_then(|v| v.parse().ok()) {
+            options.dice_seed = v;
+        }
+        if let Some(v) = settings.get(LOCALE) {
+            options.locale = v.clone();
+        }
+        options.colonial_admin = flag(COLONIAL_ADMIN);
+        options.strategic_warfare = flag(STRATEGIC_WARFARE);
+        options.attrition = flag(ATTRITION);
+        options
+    }
+
+    /// The options as control table settings.
+    pub fn to_control(&self) -> Vec<(&'static str, String)> {
+        let flag = |on: bool| if on { "1" } else { "0" }.to_string();
+        vec![
+            (START_TURN, self.start_turn.to_string()),
+            (EDITION, self.edition.clone()),
+            (MAP_SIZE, self.map_size.clone()),
+            (EMPIRES, self.empires.to_string()),
+            (DIFFICULTY, self.difficulty.clone()),
+            (COLONIAL_ADMIN, flag(self.colonial_admin)),
+            (STRATEGIC_WARFARE, flag(self.strategic_warfare)),
+            (ATTRITION, flag(self.attrition)),
+            (SETTING, self.setting.clone()),
+            (SETTING_FILE, self.setting_file.clone()),
+            (DICE_SEED, self.dice_seed.to_string()),
+            (LOCALE, self.locale.clone()),
+        ]
+    }
+
+    /// Check the options make sense together.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.start_turn < 0 {
+            return Err("The starting turn cannot be negative".to_string());
+        }
+        if !EDITIONS.contains(&self.edition.as_str()) {
+            return Err(format!("Unknown rules edition {}", self.edition));
+        }
+        if !MAP_SIZES.iter().any(|(s, _)| *s == self.map_size) {
+            return Err(format!("Unknown map size {}", self.map_size));
+        }
+        if !(0..=MAX_EMPIRES).contains(&self.empires) {
+            return Err(format!(
+                "A campaign can have at most {} empires",
+                MAX_EMPIRES
+            ));
+        }
+        if !DIFFICULTIES.iter().any(|(d, _)| *d == self.difficulty) {
+            return Err(format!("Unknown difficulty {}", self.difficulty));
+        }
+        if !LOCALES.iter().any(|l| l.tag == self.locale) {
+            return Err(format!("Unknown locale {}", self.locale));
+        }
+        Ok(())
+    }
+
+    /// Percentage of their income empires collect at this difficulty.
+    pub fn income_percent(&self) -> i32 {
+        DIFFICULTIES
+            .iter()
+            .find(|(d, _)| *d == self.difficulty)
+            .map_or(100, |(_, p)| *p)
+    }
+
+    /// Income an empire collects at this difficulty, rounded down.
+    pub fn scale_income(&self, income: i32) -> i32 {
+        income * self.income_percent() / 100
+    }
+
+    /// The ruleset of the campaign's edition.
+    pub fn ruleset(&self) -> &'static dyn Ruleset {
+        ruleset::for_edition(&self.edition)
+    }
+
+    /// The locale numbers and dates are written in.
+    pub fn locale(&self) -> &'static Locale {
+        locale::for_tag(&self.locale)
+    }
+
+    /// Number of systems the map is meant to have.
+    pub fn map_systems(&self) -> i32 {
+        MAP_SIZES
+            .iter()
+            .find(|(s, _)| *s == self.map_size)
+            .map_or(MAP_SIZES[1].1, |(_, n)| *n)
+    }
+
+    /// Whether another empire may join a campaign that has the given number.
+    pub fn empire_allowed(&self, count: usize) -> bool {
+        self.empires == 0 || (count as i32) < self.empires
+    }
+
+    /// The options as text, one setting per entry, for the rules reference.
+    pub fn describe(&self) -> Vec<(&'static str, String)> {
+        let on_off = |on: bool| if on { "In use" } else { "Not used" }.to_string();
+        let empires = if self.empires == 0 {
+            "No limit".to_string()
+        } else {
+            self.empires.to_string()
+        };
+        vec![
+            ("Rules Edition", self.edition.clone()),
+            ("Starting Turn", self.start_turn.to_string()),
+            (
+                "Map Size",
+                format!("{}, about {} systems", self.map_size, self.map_systems()),
+            ),
+            ("Empires", empires),
+            (
+                "Economic Difficulty",
+                format!(
+                    "{}, empires collect {}% of income",
+                    self.difficulty,
+                    self.income_percent()
+                ),
+            ),
+            ("Colonial Administration", on_off(self.colonial_admin)),
+            ("Strategic Warfare", on_off(self.strategic_warfare)),
+            ("Attrition", on_off(self.attrition)),
+            ("Setting", self.setting.clone()),
+            (
+                "Dice Seed",
+                match self.dice_seed {
+                    0 => "None".to_string(),
+                    n => n.to_string(),
+                },
+            ),
+            ("Locale", self.locale().name.to_string()),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::CampaignOptions;
+
+    #[test]
+    fn control_round_trip() {
+        let options = CampaignOptions {
+            start_turn: 3,
+            edition: "First".to_string(),
+            map_size: "Large".to_string(),
+            empires: 5,
+            difficulty: "Hard".to_string(),
+            colonial_admin: true,
+            strategic_warfare: false,
+            attrition: true,
+            setting: "Fading Suns".to_string(),
+            setting_file: "modules/fading-suns.json".to_string(),
+            dice_seed: 1234,
+            locale: "de-DE".to_string(),
+        };
+        let settings: BTreeMap<String, String> = options
+            .to_control()
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v))
+            .collect();
+        assert_eq!(options, CampaignOptions::from_control(&settings));
+
+        // Settings never made keep their defaults.
+        let settings = BTreeMap::from([("turn".to_string(), "7".to_string())]);
+        assert_eq!(
+            CampaignOptions::default(),
+            CampaignOptions::from_control(&settings)
+        );
+    }
+
+    #[test]
+    fn validate() {
+        assert!(CampaignOptions::default().validate().is_ok());
+        let bad = [
+            CampaignOptions {
+                start_turn: -1,
+                ..Default::default()
+            },
+            CampaignOptions {
+                map_size: "Huge".to_string(),
+                ..Default::default()
+            },
+            CampaignOptions {
+                empires: 13,
+                ..Default::default()
+            },
+            CampaignOptions {
+                difficulty: "Brutal".to_string(),
+                ..Default::default()
+            },
+            CampaignOptions {
+                locale: "tlh".to_string(),
+                ..Default::default()
+            },
+        ];
+        for o in bad {
+            assert!(o.validate().is_err());
+        }
+    }
+
+    #[test]
+    fn consulted() {
+        let mut options = CampaignOptions::default();
+        assert_eq!(10, options.scale_income(10));
+        options.difficulty = "Hard".to_string();
+        assert_eq!(7, options.scale_income(10));
+        options.difficulty = "Easy".to_string();
+        assert_eq!(12, options.scale_income(10));
+
+        assert!(options.empire_allowed(30));
+        options.empires = 2;
+        assert!(options.empire_allowed(1));
+        assert!(!options.empire_allowed(2));
+    }
+}
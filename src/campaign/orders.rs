@@ -0,0 +1,486 @@
+// Copyright 2022 David Terhune
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Interface to the orders empires submit each turn.
+
+use super::diplomacy::{Treaty, TREATY_TYPES};
+use super::empire::Empire;
+use super::shipyard;
+use super::system::System;
+use super::unit::{FleetSummary, ShipLocation, ShipType};
+
+/// Phase of spending on research and development.
+pub const ECONOMIC: &str = "Economic";
+
+/// Phase of building ships.
+pub const CONSTRUCTION: &str = "Construction";
+
+/// Phase of fleet movement.
+pub const MOVEMENT: &str = "Movement";
+
+/// Phase of dealings with other empires.
+pub const DIPLOMACY: &str = "Diplomacy";
+
+/// Phases orders are grouped by, in the order they are carried out.
+pub const PHASES: [&str; 4] = [ECONOMIC, CONSTRUCTION, MOVEMENT, DIPLOMACY];
+
+/// Heading of orders given without a phase, written in free form.
+pub const OTHER: &str = "Other";
+
+/// A single order an empire has given for a turn, with the phase it belongs
+/// to and what it costs in EP.
+#[derive(sqlx::FromRow, Clone, Debug, PartialEq, Eq)]
+pub struct Order {
+    pub id: i64,
+    pub empire: i64,
+    pub turn: i32,
+    pub text: String,
+    pub phase: String,
+    pub cost: i32,
+}
+
+impl Order {
+    /// Create a new free-form order, without a phase or cost.
+    pub fn new(empire: i64, turn: i32, text: &str) -> Self {
+        Self::in_phase(empire, turn, "", text, 0)
+    }
+
+    /// Create a new order for a phase.
+    pub fn in_phase(empire: i64, turn: i32, phase: &str, text: &str, cost: i32) -> Self {
+        Self {
+            id: 0,
+            empire,
+            turn,
+            text: text.to_string(),
+            phase: phase.to_string(),
+            cost,
+        }
+    }
+
+    /// Read an order from a line of text: its phase, cost and text separated
+    /// by tabs, or else a free-form order.
+    pub fn from_line(empire: i64, turn: i32, line: &str) -> Self {
+        let fields: Vec<&str> = line.splitn(3, '\t').collect();
+        match fields[..] {
+            [phase, cost, text] if PHASES.contains(&phase) => match cost.trim().parse() {
+                Ok(cost) => Self::in_phase(empire, turn, phase, text, cost),
+                Err(_) => Self::new(empire, turn, line),
+            },
+            _ => Self::new(empire, turn, line),
+        }
+    }
+
+    /// Convert to string as a row of tab-separated fields.
+    #[allow(unused)]
+    pub fn as_row(&self) -> String {
+        let cost = if self.cost == 0 {
+            String::new()
+        } else {
+            self.cost.to_string()
+        };
+        format!("{}\t{}\t{}", self.phase_name(), self.text, cost)
+    }
+
+    /// The order's phase, or `OTHER` if it has none.
+    pub fn phase_name(&self) -> &str {
+        if PHASES.contains(&self.phase.as_str()) {
+            self.phase.as_str()
+        } else {
+            OTHER
+        }
+    }
+
+    /// The same order for another turn.
+    pub fn for_turn(&self, turn: i32) -> Self {
+        Self {
+            id: 0,
+            turn,
+            ..self.clone()
+        }
+    }
+}
+
+/// Orders of one phase and their total cost.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PhaseOrders {
+    pub phase: String,
+    pub orders: Vec<Order>,
+}
+
+impl PhaseOrders {
+    /// Total EP cost of the phase's orders.
+    pub fn cost(&self) -> i32 {
+        self.orders.iter().map(|o| o.cost).sum()
+    }
+}
+
+/// Group orders by phase, in the order the phases are carried out. Every
+/// phase is listed even if it has no orders; free-form orders come last,
+/// under `OTHER`, if there are any.
+pub fn by_phase(orders: &[Order]) -> Vec<PhaseOrders> {
+    let mut groups: Vec<PhaseOrders> = PHASES
+        .iter()
+        .chain([OTHER].iter())
+        .map(|p| PhaseOrders {
+            phase: p.to_string(),
+            orders: orders
+                .iter()
+                .filter(|o| o.phase_name() == *p)
+                .cloned()
+                .collect(),
+        })
+        .collect();
+    if groups.last().is_some_and(|g| g.orders.is_empty()) {
+        groups.pop();
+    }
+    groups
+}
+
+/// Describe the total cost of orders against the treasury they are paid
+/// from.
+pub fn budget(orders: &[Order], treasury: i32) -> String {
+    let cost: i32 = orders.iter().map(|o| o.cost).sum();
+    if cost > treasury {
+        format!(
+            "Orders cost {} EP of {} EP: over budget by {} EP",
+            cost,
+            treasury,
+            cost - treasury
+        )
+    } else {
+        format!(
+            "Orders cost {} EP of {} EP, leaving {} EP",
+            cost,
+            treasury,
+            treasury - cost
+        )
+    }
+}
+
+/// Economic order spending EP on research, or on developing one of the
+/// empire's systems if one is given.
+pub fn economic(
+    empire: i64,
+    turn: i32,
+    sys: Option<&System>,
+    amount: i32,
+) -> Result<Order, String> {
+    if amount <= 0 {
+        return Err("Spending must be more than zero".to_string());
+    }
+    let text = match sys {
+        Some(s) if s.owner != empire => return Err(format!("{} is not the empire's", s.name)),
+        Some(s) => format!("Spend {} EP developing {}", amount, s.name),
+        None => format!("Spend {} EP on research", amount),
+    };
+    Ok(Order::in_phase(empire, turn, ECONOMIC, &text, amount))
+}
+
+/// Construction order building ships of one of the empire's current classes
+/// at one of its systems.
+pub fn construction(
+    empire: i64,
+    turn: i32,
+    sys: &System,
+    class: &ShipType,
+    count: i32,
+) -> Result<Order, String> {
+    if count <= 0 {
+        return Err("At least one ship must be built".to_string());
+    }
+    if sys.owner != empire {
+        return Err(format!("{} is not the empire's", sys.name));
+    }
+    if class.empire != empire {
+        return Err(format!("{} is not the empire's class", class.class));
+    }
+    if class.retired {
+        return Err(format!("{} class is retired", class.class));
+    }
+    let text = if count > 1 {
+        format!("Build {} \u{d7} {} at {}", count, class.class, sys.name)
+    } else {
+        format!("Build {} at {}", class.class, sys.name)
+    };
+    Ok(Order::in_phase(
+        empire,
+        turn,
+        CONSTRUCTION,
+        &text,
+        class.cost * count,
+    ))
+}
+
+/// Construction order repairing, mothballing, reactivating or scrapping one
+/// of the empire's ships at the system it is in.
+pub fn shipyard(
+    empire: i64,
+    turn: i32,
+    ship: &ShipLocation,
+    sys: &System,
+    action: &str,
+) -> Result<Order, String> {
+    if ship.owner != empire {
+        return Err(format!(
+            "{} #{} is not the empire's ship",
+            ship.class, ship.id
+        ));
+    }
+    let cost = shipyard::check(action, ship, sys)?;
+    let text = format!("{} {} #{}", action, ship.class, ship.id);
+    Ok(Order::in_phase(empire, turn, CONSTRUCTION, &text, cost))
+}
+
+/// Movement order sending one of the empire's fleets along a route, given
+/// as the names of the systems it passes through to its destination. The
+/// route itself is checked against the lanes by the caller.
+pub fn movement(
+    empire: i64,
+    turn: i32,
+    fleet: &FleetSummary,
+    route: &[String],
+) -> Result<Order, String> {
+    if fleet.owner != empire {
+        return Err(format!("{} is not the empire's fleet", fleet.name));
+    }
+    let text = match route {
+        [] => return Err("The route has no destination".to_string()),
+        [dest] => format!("Move {} to {}", fleet.name, dest),
+        [via @ .., dest] => format!("Move {} to {} via {}", fleet.name, dest, via.join(", ")),
+    };
+    Ok(Order::in_phase(empire, turn, MOVEMENT, &text, 0))
+}
+
+/// Read "Move FLEET [from LOCATION] to DESTINATION [via SYSTEM, ...]", giving
+/// the systems passed through in order, ending with the destination.
+pub fn parse_move(text: &str) -> Option<(&str, Option<&str>, Vec<&str>)> {
+    let (head, tail) = text.trim().strip_prefix("Move ")?.rsplit_once(" to ")?;
+    let (fleet, from) = match head.rsplit_once(" from ") {
+        Some((fleet, from)) => (fleet.trim(), Some(from.trim())),
+        None => (head.trim(), None),
+    };
+    let mut route: Vec<&str> = Vec::new();
+    let dest = match tail.split_once(" via ") {
+        Some((dest, via)) => {
+            route.extend(via.split(',').map(str::trim));
+            dest
+        }
+        None => tail,
+    };
+    route.push(dest.trim());
+    Some((fleet, from, route))
+}
+
+/// Diplomacy order proposing a treaty to another empire, which must not
+/// already have one of that kind with the empire.
+pub fn diplomacy(
+    empire: i64,
+    turn: i32,
+    other: &Empire,
+    ttype: &str,
+    treaties: &[Treaty],
+) -> Result<Order, String> {
+    if other.id == empire {
+        return Err("An empire cannot sign a treaty with itself".to_string());
+    }
+    if !TREATY_TYPES.contains(&ttype) {
+        return Err(format!("Unknown treaty type {}", ttype));
+    }
+    let signed = treaties.iter().any(|t| {
+        t.ttype == ttype
+            && ((t.a == empire && t.b == other.id) || (t.a == other.id && t.b == empire))
+    });
+    if signed {
+        return Err(format!(
+            "Already has a {} treaty with {}",
+            ttype, other.name
+        ));
+    }
+    let text = format!("Propose {} treaty to {}", ttype, other.name);
+    Ok(Order::in_phase(empire, turn, DIPLOMACY, &text, 0))
+}
+
+/// A named set of orders an empire gives every turn, such as standing
+/// patrols or recurring builds. The orders are stored one per line; an order
+/// with a phase is stored as its phase, cost and text separated by tabs.
+#[derive(sqlx::FromRow, Clone, Debug, PartialEq, Eq)]
+pub struct OrderTemplate {
+    pub id: i64,
+    pub empire: i64,
+    pub name: String,
+    pub text: String,
+}
+
+impl OrderTemplate {
+    /// Create a new template from a list of orders.
+    pub fn new(empire: i64, name: &str, orders: &[Order]) -> Self {
+        Self {
+            id: 0,
+            empire,
+            name: name.to_string(),
+            text: orders
+                .iter()
+                .map(|o| {
+                    if o.phase.is_empty() {
+                        o.text.clone()
+                    } else {
+                        format!("{}\t{}\t{}", o.phase, o.cost, o.text)
+                    }
+                })
+                .collect::<Vec<String>>()
+                .join("\n"),
+        }
+    }
+
+    /// Return the orders in the template for the given turn.
+    pub fn orders(&self, turn: i32) -> Vec<Order> {
+        self.text
+            .lines()
+            .filter(|l| !l.trim().is_empty())
+            .map(|l| Order::from_line(self.empire, turn, l))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        budget, by_phase, construction, diplomacy, economic, movement, parse_move, Order,
+        OrderTemplate, CONSTRUCTION, ECONOMIC, MOVEMENT, OTHER, PHASES,
+    };
+    use crate::campaign::diplomacy::Treaty;
+    use crate::campaign::empire::tests::empires;
+    use crate::campaign::system::tests::systems;
+    use crate::campaign::unit::{FleetSummary, ShipType};
+
+    #[test]
+    fn grouping() {
+        let orders = vec![
+            Order::in_phase(1, 3, MOVEMENT, "Move Home Fleet to Tibron", 0),
+            Order::in_phase(1, 3, CONSTRUCTION, "Build Corvette at Tibron", 6),
+            Order::in_phase(1, 3, ECONOMIC, "Spend 4 EP on research", 4),
+            Order::in_phase(1, 3, CONSTRUCTION, "Build Scout at Tibron", 2),
+        ];
+        let groups = by_phase(&orders);
+        let phases: Vec<&str> = groups.iter().map(|g| g.phase.as_str()).collect();
+        assert_eq!(PHASES.to_vec(), phases);
+        assert_eq!(8, groups[1].cost());
+        assert_eq!(2, groups[1].orders.len());
+        assert!(groups[3].orders.is_empty());
+
+        let mut orders = orders;
+        orders.push(Order::new(1, 3, "Hold"));
+        let groups = by_phase(&orders);
+        assert_eq!(OTHER, groups[4].phase);
+        assert_eq!("Other\tHold\t", groups[4].orders[0].as_row());
+
+        assert_eq!(
+            "Orders cost 12 EP of 20 EP, leaving 8 EP",
+            budget(&orders, 20)
+        );
+        assert_eq!(
+            "Orders cost 12 EP of 10 EP: over budget by 2 EP",
+            budget(&orders, 10)
+        );
+    }
+
+    #[test]
+    fn phase_orders() {
+        let mut sys = systems()[3].clone();
+        sys.owner = 1;
+        let o = economic(1, 3, Some(&sys), 5).unwrap();
+        assert_eq!("Spend 5 EP developing Tibron", o.text);
+        assert_eq!(5, o.cost);
+        assert!(economic(1, 3, None, 0).is_err());
+        assert!(economic(2, 3, Some(&sys), 5).is_err());
+
+        let mut class = ShipType::new("Corvette", "Corvette", 1);
+        class.cost = 3;
+        let o = construction(1, 3, &sys, &class, 2).unwrap();
+        assert_eq!(CONSTRUCTION, o.phase);
+        assert_eq!(6, o.cost);
+        class.retired = true;
+        assert!(construction(1, 3, &sys, &class, 2).is_err());
+
+        let fleet = FleetSummary {
+            id: 1,
+            name: "Home Fleet".to_string(),
+            owner: 1,
+            location: 1,
+            speed: 0,
+            owner_name: "Senorian".to_string(),
+            location_name: "Senor Prime".to_string(),
+            ships: 2,
+            crippled: 0,
+            atk: 4,
+            def: 4,
+            maint: 1,
+            jumps: 2,
+        };
+        let route = ["Vadurrinia".to_string(), "Tibron".to_string()];
+        let o = movement(1, 3, &fleet, &route).unwrap();
+        assert_eq!("Move Home Fleet to Tibron via Vadurrinia", o.text);
+        assert_eq!(
+            Some(("Home Fleet", None, vec!["Vadurrinia", "Tibron"])),
+            parse_move(&o.text)
+        );
+        assert!(movement(2, 3, &fleet, &route).is_err());
+        assert!(movement(1, 3, &fleet, &[]).is_err());
+
+        let mut v = empires();
+        for (n, e) in v.iter_mut().enumerate() {
+            e.id = n as i64 + 1;
+        }
+        let treaties = [Treaty::new(2, 1, "Trade", 1, None)];
+        let o = diplomacy(1, 3, &v[1], "Alliance", &treaties).unwrap();
+        assert_eq!("Propose Alliance treaty to Human", o.text);
+        assert!(diplomacy(1, 3, &v[1], "Trade", &treaties).is_err());
+        assert!(diplomacy(1, 3, &v[0], "Alliance", &treaties).is_err());
+    }
+
+    #[test]
+    fn template_keeps_phases() {
+        let orders = vec![
+            Order::in_phase(1, 3, CONSTRUCTION, "Build Corvette at Tibron", 6),
+            Order::new(1, 3, "Hold"),
+        ];
+        let tmpl = OrderTemplate::new(1, "Standing", &orders);
+        let exp: Vec<Order> = orders.iter().map(|o| o.for_turn(4)).collect();
+        assert_eq!(exp, tmpl.orders(4));
+    }
+
+    #[test]
+    fn template_round_trip() {
+        let orders = vec![
+            Order::new(1, 3, "Patrol Senor Prime"),
+            Order::new(1, 3, "Build Corvette at Tibron"),
+        ];
+        let tmpl = OrderTemplate::new(1, "Standing", &orders);
+        assert_eq!("Patrol Senor Prime\nBuild Corvette at Tibron", tmpl.text);
+
+        let exp = vec![
+            Order::new(1, 4, "Patrol Senor Prime"),
+            Order::new(1, 4, "Build Corvette at Tibron"),
+        ];
+        assert_eq!(exp, tmpl.orders(4));
+    }
+
+    #[test]
+    fn template_skips_blank_lines() {
+        let mut tmpl = OrderTemplate::new(2, "Blank", &[]);
+        tmpl.text = "Hold\n\n  \nScout".to_string();
+        assert_eq!(2, tmpl.orders(0).len());
+    }
+}
@@ -0,0 +1,343 @@
+// Copyright 2022 David Terhune
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Intelligence overlays drawn over the campaign map.
+//!
+//! Each overlay shows one part of the campaign on the map: how far each
+//! empire's supply reaches, what a chosen empire can see, the trade routes
+//! in use, where natives prey on shipping, and where battles were fought
+//! last turn. The layers are worked out together, and the map draws those
+//! the GM has turned on.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use super::facility::{self, Facility, SENSOR_POST, TRADE_STATION};
+use super::lane::{self, Lane};
+use super::ownership;
+use super::system::System;
+use super::trade::{self, Route};
+use super::unit::FleetSummary;
+
+/// Jumps an empire's supply reaches beyond its own systems, or a jump more
+/// from those with a trade station.
+pub const SUPPLY_JUMPS: i32 = 1;
+
+/// Jumps an empire sees beyond its systems and fleets, or a jump more from
+/// its systems with a sensor post.
+pub const SIGHT_JUMPS: i32 = 1;
+
+/// Piracy at a neutral system, whose natives raid all shipping there.
+pub const PIRATE_HAVEN: i32 = 2;
+
+/// Piracy in unclaimed systems next to a pirate haven.
+pub const PIRATE_RAIDS: i32 = 1;
+
+/// An overlay the map can show.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Overlay {
+    Supply,
+    Fog,
+    Trade,
+    Piracy,
+    Battles,
+}
+
+/// Every overlay, in the order the map offers them.
+pub const OVERLAYS: [Overlay; 5] = [
+    Overlay::Supply,
+    Overlay::Fog,
+    Overlay::Trade,
+    Overlay::Piracy,
+    Overlay::Battles,
+];
+
+impl Overlay {
+    /// Color the overlay is drawn in, as red, green and blue. Supply is
+    /// drawn in each empire's own color instead.
+    pub fn color(&self) -> (u8, u8, u8) {
+        match self {
+            Self::Supply => (200, 200, 200),
+            Self::Fog => (50, 50, 50),
+            Self::Trade => (230, 200, 40),
+            Self::Piracy => (220, 30, 30),
+            Self::Battles => (255, 140, 0),
+        }
+    }
+}
+
+impl fmt::Display for Overlay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            Self::Supply => "Supply",
+            Self::Fog => "Fog of War",
+            Self::Trade => "Trade",
+            Self::Piracy => "Piracy",
+            Self::Battles => "Battles",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+/// What the overlays show.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Layers {
+    /// The empires whose supply reaches each system.
+    pub supply: BTreeMap<i64, BTreeSet<i64>>,
+    /// The systems the chosen empire can see, if one was chosen.
+    pub visible: Option<BTreeSet<i64>>,
+    /// The open trade routes, with the systems each passes through.
+    pub trade: Vec<(Route, Vec<i64>)>,
+    /// Piracy level of each system troubled by it.
+    pub piracy: BTreeMap<i64, i32>,
+    /// Systems where battles were fought last turn.
+    pub battles: BTreeSet<i64>,
+}
+
+impl Layers {
+    /// Work out every layer. Fog of war is shown for the given empire, if
+    /// any.
+    pub fn new(
+        systems: &[System],
+        lanes: &[Lane],
+        fleets: &[FleetSummary],
+        facilities: &[Facility],
+        routes: &[Route],
+        battles: &[i64],
+        empire: Option<i64>,
+    ) -> Self {
+        Self {
+            supply: supply(systems, lanes, facilities),
+            visible: empire.map(|e| sight(e, systems, lanes, fleets, facilities)),
+            trade: routes
+                .iter()
+                .map(|r| (r.clone(), trade::path(r, systems, lanes)))
+                .filter(|(_, p)| !p.is_empty())
+                .collect(),
+            piracy: piracy(systems, lanes),
+            battles: battles.iter().copied().collect(),
+        }
+    }
+
+    /// Whether a system is hidden from the chosen empire.
+    pub fn fogged(&self, sys: i64) -> bool {
+        self.visible.as_ref().is_some_and(|v| !v.contains(&sys))
+    }
+}
+
+/// The empires supplying each system. Supply runs from an empire's systems
+/// that are not blockaded, out along the lanes, but not into systems held
+/// by natives. Working trade stations carry it a jump further.
+pub fn supply(
+    systems: &[System],
+    lanes: &[Lane],
+    facilities: &[Facility],
+) -> BTreeMap<i64, BTreeSet<i64>> {
+    let stations = facility::working(facilities, TRADE_STATION);
+    let neutral: BTreeSet<i64> = systems
+        .iter()
+        .filter(|s| ownership::is_neutral(s))
+        .map(|s| s.id)
+        .collect();
+    let owners: BTreeSet<i64> = systems.iter().map(|s| s.owner).filter(|o| *o > 0).collect();
+    let mut supplied: BTreeMap<i64, BTreeSet<i64>> = BTreeMap::new();
+    for empire in owners {
+        let depots: Vec<i64> = systems
+            .iter()
+            .filter(|s| s.owner == empire && !s.blockaded)
+            .map(|s| s.id)
+            .collect();
+        let far = depots.iter().copied().filter(|s| stations.contains(s));
+        let mut reach = lane::within(lanes, far, SUPPLY_JUMPS + facility::REACH);
+        reach.extend(lane::within(lanes, depots, SUPPLY_JUMPS));
+        for sys in reach {
+            if !neutral.contains(&sys) {
+                supplied.entry(sys).or_default().insert(empire);
+            }
+        }
+    }
+    supplied
+}
+
+/// The systems an empire can see: its own, those its fleets are in, and
+/// those nearby. Working sensor posts see a jump further.
+pub fn sight(
+    empire: i64,
+    systems: &[System],
+    lanes: &[Lane],
+    fleets: &[FleetSummary],
+    facilities: &[Facility],
+) -> BTreeSet<i64> {
+    let sensors = facility::working(facilities, SENSOR_POST);
+    let far = systems
+        .iter()
+        .filter(|s| s.owner == empire && sensors.contains(&s.id))
+        .map(|s| s.id);
+    let mut seen = lane::within(lanes, far, SIGHT_JUMPS + facility::REACH);
+    let posts = systems
+        .iter()
+        .filter(|s| s.owner == empire)
+        .map(|s| s.id)
+        .chain(
+            fleets
+                .iter()
+                .filter(|f| f.owner == empire)
+                .map(|f| f.location),
+        );
+    seen.extend(lane::within(lanes, posts, SIGHT_JUMPS));
+    seen
+}
+
+/// Piracy level of the systems troubled by it. Natives raid all shipping at
+/// their own systems, and in the unclaimed systems next to them; empires
+/// keep their own systems clear.
+pub fn piracy(systems: &[System], lanes: &[Lane]) -> BTreeMap<i64, i32> {
+    let mut levels = BTreeMap::new();
+    for haven in systems.iter().filter(|s| ownership::is_neutral(s)) {
+        levels.insert(haven.id, PIRATE_HAVEN);
+        for n in lane::neighbors(lanes, haven.id) {
+            if systems.iter().any(|s| s.id == n && s.owner == 0) {
+                let level = levels.entry(n).or_insert(0);
+                *level = (*level).max(PIRATE_RAIDS);
+            }
+        }
+    }
+    levels
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, BTreeSet};
+
+    use super::{piracy, sight, supply, Layers, PIRATE_HAVEN, PIRATE_RAIDS};
+    use crate::campaign::diplomacy::Treaty;
+    use crate::campaign::facility::{Facility, SENSOR_POST, TRADE_STATION};
+    use crate::campaign::lane::tests::lanes;
+    use crate::campaign::system::tests::systems;
+    use crate::campaign::system::System;
+    use crate::campaign::trade;
+    use crate::campaign::unit::FleetSummary;
+
+    // The test systems with their database IDs, Senor Prime held by empire
+    // 1, Zev'rch by empire 2, and Tibron by natives.
+    fn territory() -> Vec<System> {
+        let mut v = systems();
+        for (n, s) in v.iter_mut().enumerate() {
+            s.id = n as i64 + 1;
+            s.owner = 0;
+        }
+        v[0].owner = 1;
+        v[2].owner = 2;
+        v[3].minor = "Vrokk".to_string();
+        v
+    }
+
+    #[test]
+    fn supply_range() {
+        let mut v = territory();
+        let s = supply(&v, &lanes(), &[]);
+        assert_eq!(Some(&BTreeSet::from([1, 2])), s.get(&2));
+        assert_eq!(Some(&BTreeSet::from([1])), s.get(&1));
+        assert_eq!(None, s.get(&4));
+
+        // A blockaded system supplies nothing.
+        v[0].blockaded = true;
+        let s = supply(&v, &lanes(), &[]);
+        assert_eq!(Some(&BTreeSet::from([2])), s.get(&2));
+        assert_eq!(None, s.get(&1));
+    }
+
+    #[test]
+    fn facility_reach() {
+        let v = territory();
+        let mut posts = vec![Facility::new(1, TRADE_STATION)];
+        let s = supply(&v, &lanes(), &posts);
+        assert_eq!(Some(&BTreeSet::from([1, 2])), s.get(&3));
+        assert_eq!(
+            BTreeSet::from([1, 2, 4]),
+            sight(1, &v, &lanes(), &[], &posts)
+        );
+
+        posts.push(Facility::new(1, SENSOR_POST));
+        assert_eq!(
+            BTreeSet::from([1, 2, 3, 4]),
+            sight(1, &v, &lanes(), &[], &posts)
+        );
+
+        // Crippled facilities reach no further.
+        for f in posts.iter_mut() {
+            f.crip = true;
+        }
+        let s = supply(&v, &lanes(), &posts);
+        assert_eq!(Some(&BTreeSet::from([2])), s.get(&3));
+        assert_eq!(
+            BTreeSet::from([1, 2, 4]),
+            sight(1, &v, &lanes(), &[], &posts)
+        );
+    }
+
+    #[test]
+    fn fog_of_war() {
+        let v = territory();
+        assert_eq!(BTreeSet::from([1, 2, 4]), sight(1, &v, &lanes(), &[], &[]));
+
+        let scout = FleetSummary {
+            id: 1,
+            name: "Scout".to_string(),
+            owner: 1,
+            location: 3,
+            speed: 0,
+            owner_name: "Senorian".to_string(),
+            location_name: "Zev'rch".to_string(),
+            ships: 1,
+            crippled: 0,
+            atk: 0,
+            def: 1,
+            maint: 1,
+            jumps: 1,
+        };
+        assert_eq!(
+            BTreeSet::from([1, 2, 3, 4]),
+            sight(1, &v, &lanes(), &[scout], &[])
+        );
+    }
+
+    #[test]
+    fn pirates() {
+        let v = territory();
+        assert_eq!(BTreeMap::from([(4, PIRATE_HAVEN)]), piracy(&v, &lanes()));
+
+        let mut v = v;
+        v[0].owner = 0;
+        assert_eq!(
+            BTreeMap::from([(1, PIRATE_RAIDS), (4, PIRATE_HAVEN)]),
+            piracy(&v, &lanes())
+        );
+    }
+
+    #[test]
+    fn layers() {
+        let v = territory();
+        let routes = trade::routes(&[Treaty::new(1, 2, "Trade", 0, None)], &v, &lanes());
+        let layers = Layers::new(&v, &lanes(), &[], &[], &routes, &[3], Some(2));
+        assert_eq!(vec![1, 2, 3], layers.trade[0].1);
+        assert_eq!(BTreeSet::from([3]), layers.battles);
+        assert!(layers.fogged(1));
+        assert!(!layers.fogged(2));
+
+        let layers = Layers::new(&v, &lanes(), &[], &[], &routes, &[], None);
+        assert!(!layers.fogged(1));
+    }
+}
@@ -0,0 +1,59 @@
+// Copyright 2022 David Terhune
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Overview of every campaign in the data directory.
+
+/// Where a campaign stands, for choosing among several. The turn and empire
+/// count are missing if the campaign's database could not be read.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CampaignSummary {
+    pub name: String,
+    pub turn: Option<i32>,
+    pub empires: Option<i64>,
+    pub modified: String,
+}
+
+impl CampaignSummary {
+    /// Convert to string as a row of tab-separated fields.
+    pub fn as_row(&self) -> String {
+        let or_unknown = |v: Option<String>| v.unwrap_or_else(|| "?".to_string());
+        format!(
+            "{}\t{}\t{}\t{}",
+            self.name,
+            or_unknown(self.turn.map(|t| t.to_string())),
+            or_unknown(self.empires.map(|n| n.to_string())),
+            self.modified
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CampaignSummary;
+
+    #[test]
+    fn as_row() {
+        let mut s = CampaignSummary {
+            name: "Senorian Wars".to_string(),
+            turn: Some(12),
+            empires: Some(5),
+            modified: "2026-10-15 09:30:00".to_string(),
+        };
+        assert_eq!("Senorian Wars\t12\t5\t2026-10-15 09:30:00", s.as_row());
+        s.turn = None;
+        s.empires = None;
+        assert_eq!("Senorian Wars\t?\t?\t2026-10-15 09:30:00", s.as_row());
+    }
+}
@@ -0,0 +1,187 @@
+// Copyright 2022 David Terhune
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Ownership states between outright control and none.
+//!
+//! A system no empire owns may be held by a minor race, which makes it
+//! neutral. An owned system may be contested by a second empire holding part
+//! of it; the owner's share is its percentage of control, and the contender
+//! holds the rest.
+
+use super::system::System;
+
+/// Morale a contested system loses each turn.
+pub const CONTESTED_MORALE: i32 = -1;
+
+/// A party to a battle.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Side {
+    Empire(i64),
+    Minor(String),
+}
+
+/// Whether a system is held by a minor race rather than an empire.
+pub fn is_neutral(sys: &System) -> bool {
+    sys.owner == 0 && !sys.minor.is_empty()
+}
+
+/// Whether a second empire holds part of an owned system.
+pub fn is_contested(sys: &System) -> bool {
+    sys.owner != 0 && sys.contender != 0 && sys.contender != sys.owner
+}
+
+/// Percentage of the system its owner controls.
+pub fn owner_share(sys: &System) -> i32 {
+    if is_contested(sys) {
+        sys.share.clamp(0, 100)
+    } else {
+        100
+    }
+}
+
+/// The system as its contender holds it: the contender in the owner's place,
+/// with the rest of the control. None if the system is not contested.
+pub fn contender_view(sys: &System) -> Option<System> {
+    if !is_contested(sys) {
+        return None;
+    }
+    Some(System {
+        owner: sys.contender,
+        owner_name: sys.contender_name.clone(),
+        contender: sys.owner,
+        contender_name: sys.owner_name.clone(),
+        share: 100 - owner_share(sys),
+        ..sys.clone()
+    })
+}
+
+/// Who holds the system, as text.
+pub fn describe(sys: &System) -> String {
+    if is_neutral(sys) {
+        format!("{} (neutral)", sys.minor)
+    } else if is_contested(sys) {
+        format!(
+            "{} {}% / {} {}%",
+            sys.owner_name,
+            owner_share(sys),
+            sys.contender_name,
+            100 - owner_share(sys)
+        )
+    } else {
+        sys.owner_name.clone()
+    }
+}
+
+/// The sides that fight at a system this turn, given the owners of the
+/// fleets there. Each fleet's empire takes part, as do the owner and any
+/// contender, and the minor race of a neutral system. Minor races fight any
+/// empire; empires fight unless they are at peace. Without a fleet present,
+/// or without two hostile sides, there is no battle and nobody fights.
+pub fn battle<F>(sys: &System, fleet_owners: &[i64], at_peace: F) -> Vec<Side>
+where
+    F: Fn(i64, i64) -> bool,
+{
+    if fleet_owners.is_empty() {
+        return Vec::new();
+    }
+    let mut sides: Vec<Side> = fleet_owners.iter().map(|e| Side::Empire(*e)).collect();
+    if sys.owner != 0 {
+        sides.push(Side::Empire(sys.owner));
+    }
+    if is_contested(sys) {
+        sides.push(Side::Empire(sys.contender));
+    }
+    if is_neutral(sys) {
+        sides.push(Side::Minor(sys.minor.clone()));
+    }
+    sides.sort();
+    sides.dedup();
+
+    let hostile = |a: &Side, b: &Side| match (a, b) {
+        (Side::Empire(a), Side::Empire(b)) => !at_peace(*a, *b),
+        _ => true,
+    };
+    sides
+        .iter()
+        .filter(|a| sides.iter().any(|b| *a != b && hostile(a, b)))
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{battle, contender_view, describe, is_contested, is_neutral, owner_share, Side};
+    use crate::campaign::system::tests::systems;
+
+    #[test]
+    fn states() {
+        let mut sys = systems()[2].clone();
+        assert!(!is_neutral(&sys));
+        assert_eq!("None", describe(&sys));
+        sys.minor = "Zev".to_string();
+        assert!(is_neutral(&sys));
+        assert_eq!("Zev (neutral)", describe(&sys));
+
+        sys.owner = 1;
+        sys.owner_name = "Senorian".to_string();
+        assert!(!is_neutral(&sys));
+        assert!(!is_contested(&sys));
+        assert_eq!(100, owner_share(&sys));
+        assert!(contender_view(&sys).is_none());
+
+        sys.contender = 2;
+        sys.contender_name = "Human".to_string();
+        sys.share = 60;
+        assert!(is_contested(&sys));
+        assert_eq!(60, owner_share(&sys));
+        assert_eq!("Senorian 60% / Human 40%", describe(&sys));
+        let held = contender_view(&sys).unwrap();
+        assert_eq!(2, held.owner);
+        assert_eq!(40, owner_share(&held));
+        assert_eq!("Human 40% / Senorian 60%", describe(&held));
+    }
+
+    #[test]
+    fn battles() {
+        let mut sys = systems()[2].clone();
+        let never = |_, _| false;
+        assert!(battle(&sys, &[], never).is_empty());
+        assert!(battle(&sys, &[1, 1], never).is_empty());
+        assert_eq!(
+            vec![Side::Empire(1), Side::Empire(2)],
+            battle(&sys, &[2, 1], never)
+        );
+
+        // Natives fight any fleet.
+        sys.minor = "Zev".to_string();
+        assert_eq!(
+            vec![Side::Empire(1), Side::Minor("Zev".to_string())],
+            battle(&sys, &[1], never)
+        );
+
+        // A contender fights the owner; an empire at peace with both
+        // stays out of it.
+        sys.minor.clear();
+        sys.owner = 1;
+        sys.contender = 2;
+        assert_eq!(2, battle(&sys, &[1], never).len());
+        let peace = |a: i64, b: i64| a == 3 || b == 3;
+        assert_eq!(
+            vec![Side::Empire(1), Side::Empire(2)],
+            battle(&sys, &[3], peace)
+        );
+        assert!(battle(&sys, &[1], |_, _| true).is_empty());
+    }
+}
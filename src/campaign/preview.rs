@@ -0,0 +1,204 @@
+// Copyright 2022 David Terhune
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Turn previews: the current turn resolved against a throwaway copy of the
+//! campaign, so the GM can check the orders before advancing for real.
+//!
+//! Battles and other rolls are made afresh when the turn is advanced, so
+//! they may come out differently from the preview.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use super::data::{DataResult, DataStore};
+use super::turn::{self, TurnReport};
+
+/// Where an empire stands at one point in the campaign.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Standing {
+    pub treasury: i32,
+    pub tech: i32,
+    pub systems: i32,
+    pub ships: i32,
+    pub crippled: i32,
+}
+
+/// Where every empire stands, by name, and who owns each system.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Standings {
+    pub empires: BTreeMap<String, Standing>,
+    pub owners: BTreeMap<String, String>,
+}
+
+impl Standings {
+    /// Read the standings from a store.
+    pub async fn read(data: &DataStore) -> DataResult<Self> {
+        let mut standings = Self::default();
+        let systems = data.get_systems().await?;
+        for emp in data.get_empires().await? {
+            let ships = data.get_ships_by_owner(emp.id).await?;
+            standings.empires.insert(
+                emp.name,
+                Standing {
+                    treasury: emp.treasury,
+                    tech: emp.tech,
+                    systems: systems.iter().filter(|s| s.owner == emp.id).count() as i32,
+                    ships: ships.len() as i32,
+                    crippled: ships.iter().filter(|s| s.crip).count() as i32,
+                },
+            );
+        }
+        for sys in systems {
+            standings.owners.insert(sys.name, sys.owner_name);
+        }
+        Ok(standings)
+    }
+}
+
+/// What the current turn would do: its report, and the changes it would
+/// make to where the empires stand.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Preview {
+    pub report: TurnReport,
+    pub changes: Vec<String>,
+}
+
+impl fmt::Display for Preview {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Preview of turn {}\n\nChanges", self.report.turn)?;
+        if self.changes.is_empty() {
+            write!(f, "\n  None.")?;
+        }
+        for c in &self.changes {
+            write!(f, "\n  {}", c)?;
+        }
+        write!(f, "\n\n{}", self.report)
+    }
+}
+
+/// Resolve a turn against a copy of the store, leaving the store as it was.
+pub async fn preview(data: &DataStore, turn: i32) -> DataResult<Preview> {
+    let before = Standings::read(data).await?;
+    let copy = data.scratch().await?;
+    let resolved = resolve(&copy, turn).await;
+    copy.close().await;
+    let (report, after) = resolved?;
+    Ok(Preview {
+        report,
+        changes: changes(&before, &after),
+    })
+}
+
+/// The changes between two standings, one line per empire or system that
+/// changed.
+pub fn changes(before: &Standings, after: &Standings) -> Vec<String> {
+    let mut lines = Vec::new();
+    for (name, new) in &after.empires {
+        let old = before.empires.get(name).cloned().unwrap_or_default();
+        let fields = [
+            ("treasury", old.treasury, new.treasury),
+            ("tech", old.tech, new.tech),
+            ("systems", old.systems, new.systems),
+            ("ships", old.ships, new.ships),
+            ("crippled", old.crippled, new.crippled),
+        ];
+        let changed: Vec<String> = fields
+            .iter()
+            .filter(|(_, a, b)| a != b)
+            .map(|(field, a, b)| format!("{} {} -> {} ({:+})", field, a, b, b - a))
+            .collect();
+        if !changed.is_empty() {
+            lines.push(format!("{}: {}", name, changed.join(", ")));
+        }
+    }
+    for (sys, owner) in &after.owners {
+        if let Some(old) = before.owners.get(sys).filter(|old| *old != owner) {
+            lines.push(format!("{} passes from {} to {}", sys, old, owner));
+        }
+    }
+    lines
+}
+
+// Resolve a turn and read the standings that result.
+async fn resolve(data: &DataStore, turn: i32) -> DataResult<(TurnReport, Standings)> {
+    let report = turn::resolve(data, turn).await?;
+    Ok((report, Standings::read(data).await?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{changes, preview, Standing, Standings};
+    use crate::campaign::data::tests::init_data;
+    use crate::campaign::empire::tests::empires;
+    use crate::campaign::system::tests::systems;
+
+    #[test]
+    fn standing_changes() {
+        let mut before = Standings::default();
+        let standing = Standing {
+            treasury: 30,
+            tech: 2,
+            systems: 1,
+            ships: 4,
+            crippled: 0,
+        };
+        before
+            .empires
+            .insert("Senorian".to_string(), standing.clone());
+        before.empires.insert("Human".to_string(), standing);
+        before
+            .owners
+            .insert("Tibron".to_string(), "Senorian".to_string());
+        assert!(changes(&before, &before).is_empty());
+
+        let mut after = before.clone();
+        let senorian = after.empires.get_mut("Senorian").unwrap();
+        senorian.treasury = 42;
+        senorian.crippled = 1;
+        after
+            .owners
+            .insert("Tibron".to_string(), "Human".to_string());
+        assert_eq!(
+            vec![
+                "Senorian: treasury 30 -> 42 (+12), crippled 0 -> 1 (+1)",
+                "Tibron passes from Senorian to Human",
+            ],
+            changes(&before, &after)
+        );
+    }
+
+    #[tokio::test]
+    async fn store_untouched() {
+        let data = init_data().await;
+        data.add_empires(empires()).await.unwrap();
+        data.add_systems(systems()).await.unwrap();
+        let mut home = data.get_system_by_name("Senor Prime").await.unwrap();
+        home.owner = 1;
+        data.update_system(&home).await.unwrap();
+        let before = data.export().await.unwrap();
+
+        let p = preview(&data, 0).await.unwrap();
+        assert_eq!(0, p.report.turn);
+        assert!(p
+            .changes
+            .iter()
+            .any(|c| c.starts_with("Senorian: treasury 0 -> ")));
+        assert!(p
+            .to_string()
+            .starts_with("Preview of turn 0\n\nChanges\n  "));
+        assert_eq!(before, data.export().await.unwrap());
+        assert_eq!(0, data.current_turn().await.unwrap());
+    }
+}
@@ -0,0 +1,253 @@
+// Copyright 2022 David Terhune
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A printable page of where the campaign stands, for posting after a turn.
+//!
+//! The page is one HTML file with everything it needs inline: the map drawn
+//! as SVG, laid out as the map window lays it out but on white for printing,
+//! and a table of what each empire holds. It can be posted as it is, or
+//! printed to PDF from any browser. Treasuries and orders are left off, as
+//! they are each empire's own business.
+
+use super::display::html_escape;
+use super::economy::IncomeBreakdown;
+use super::empire::Empire;
+use super::lane::Lane;
+use super::locale::Locale;
+use super::map::{self, MapView};
+use super::ruleset::Ruleset;
+use super::system::System;
+use super::unit::FleetSummary;
+
+/// Size of the map on the page, in pixels.
+pub const MAP_WIDTH: i32 = 800;
+pub const MAP_HEIGHT: i32 = 600;
+
+// Color of the lanes.
+const LANE_COLOR: &str = "#5a5a6e";
+
+// Style of the page.
+const STYLE: &str = "body { font-family: Helvetica, Arial, sans-serif; margin: 2em; }
+table { border-collapse: collapse; }
+th, td { border: 1px solid #999; padding: 2px 8px; }
+td.n { text-align: right; }
+.swatch { display: inline-block; width: 1em; height: 1em; margin-right: 4px; }
+@media print { body { margin: 0; } svg { page-break-after: always; } }";
+
+/// What an empire holds, as shown on the page.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EmpireSummary {
+    pub empire: i64,
+    pub name: String,
+    pub systems: usize,
+    pub pop: i32,
+    pub ind: i32,
+    pub income: i32,
+    pub fleets: usize,
+    pub ships: i32,
+    pub tech: i32,
+}
+
+/// Sum up what each empire holds: its systems with their population,
+/// industry and income, and its fleets with their ships.
+pub fn summarize(
+    empires: &[Empire],
+    systems: &[System],
+    fleets: &[FleetSummary],
+    rules: &dyn Ruleset,
+) -> Vec<EmpireSummary> {
+    empires
+        .iter()
+        .map(|e| {
+            let held: Vec<&System> = systems.iter().filter(|s| s.owner == e.id).collect();
+            let own: Vec<&FleetSummary> = fleets.iter().filter(|f| f.owner == e.id).collect();
+            EmpireSummary {
+                empire: e.id,
+                name: e.name.clone(),
+                systems: held.len(),
+                pop: held.iter().map(|s| s.pop).sum(),
+                ind: held.iter().map(|s| s.ind).sum(),
+                income: held
+                    .iter()
+                    .map(|s| IncomeBreakdown::new(s, rules).total())
+                    .sum(),
+                fleets: own.len(),
+                ships: own.iter().map(|f| f.ships).sum(),
+                tech: e.tech,
+            }
+        })
+        .collect()
+}
+
+/// Draw the map as SVG: lanes by type, then each system in its owner's
+/// color with its name.
+pub fn map_svg(systems: &[System], lanes: &[Lane]) -> String {
+    let view = MapView::fit(systems, MAP_WIDTH, MAP_HEIGHT);
+    let at = |id: i64| {
+        systems
+            .iter()
+            .find(|s| s.id == id)
+            .map(|s| view.to_screen(s.x, s.y))
+    };
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" \
+        viewBox=\"0 0 {0} {1}\">\n<rect width=\"{0}\" height=\"{1}\" fill=\"white\"/>\n",
+        MAP_WIDTH, MAP_HEIGHT
+    );
+    for lane in lanes {
+        if let (Some(a), Some(b)) = (at(lane.a), at(lane.b)) {
+            let style = match lane.ltype.as_str() {
+                "Major" => "stroke-width=\"3\"",
+                "Minor" => "stroke-width=\"1\"",
+                _ => "stroke-width=\"1\" stroke-dasharray=\"4 3\"",
+            };
+            svg.push_str(&format!(
+                "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"{}\" {}/>\n",
+                a.0, a.1, b.0, b.1, LANE_COLOR, style
+            ));
+        }
+    }
+    for s in systems {
+        let (x, y) = view.to_screen(s.x, s.y);
+        svg.push_str(&format!(
+            "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"{}\" stroke=\"black\"/>\n",
+            x,
+            y,
+            map::NODE_RADIUS,
+            rgb(map::owner_color(s.owner))
+        ));
+        svg.push_str(&format!(
+            "<text x=\"{}\" y=\"{}\" font-size=\"11\">{}</text>\n",
+            x + map::NODE_RADIUS + 2,
+            y + 4,
+            html_escape(&s.name)
+        ));
+    }
+    svg.push_str("</svg>");
+    svg
+}
+
+/// Write the page for a campaign's turn: the map, then what each empire
+/// holds, with numbers in the campaign's locale.
+pub fn page(
+    title: &str,
+    systems: &[System],
+    lanes: &[Lane],
+    summaries: &[EmpireSummary],
+    locale: &Locale,
+) -> String {
+    let number = |n: i32| locale.number(n as i64);
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{0}</title>\n\
+        <style>\n{1}\n</style>\n</head>\n<body>\n<h1>{0}</h1>\n",
+        html_escape(title),
+        STYLE
+    );
+    html.push_str(&map_svg(systems, lanes));
+    html.push_str("\n<h2>Empires</h2>\n<table>\n");
+    html.push_str(
+        "<tr><th>Empire</th><th>Systems</th><th>POP</th><th>IND</th><th>Income</th>\
+        <th>Fleets</th><th>Ships</th><th>Tech</th></tr>\n",
+    );
+    for e in summaries {
+        html.push_str(&format!(
+            "<tr><td><span class=\"swatch\" style=\"background: {}\"></span>{}</td>",
+            rgb(map::owner_color(e.empire)),
+            html_escape(&e.name)
+        ));
+        for n in [
+            e.systems as i32,
+            e.pop,
+            e.ind,
+            e.income,
+            e.fleets as i32,
+            e.ships,
+            e.tech,
+        ] {
+            html.push_str(&format!("<td class=\"n\">{}</td>", number(n)));
+        }
+        html.push_str("</tr>\n");
+    }
+    html.push_str("</table>\n</body>\n</html>\n");
+    html
+}
+
+// A color as CSS.
+fn rgb((r, g, b): (u8, u8, u8)) -> String {
+    format!("rgb({}, {}, {})", r, g, b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{map_svg, page, summarize};
+    use crate::campaign::empire::tests::empires;
+    use crate::campaign::lane::Lane;
+    use crate::campaign::locale::{self, LOCALES};
+    use crate::campaign::map;
+    use crate::campaign::ruleset::SecondEdition;
+    use crate::campaign::system::tests::systems;
+
+    #[test]
+    fn summaries() {
+        let mut emp = empires();
+        emp.truncate(2);
+        for (n, e) in emp.iter_mut().enumerate() {
+            e.id = n as i64 + 1;
+        }
+        let mut sys = systems();
+        sys[0].owner = 1;
+        sys[1].owner = 1;
+        let v = summarize(&emp, &sys, &[], &SecondEdition);
+        assert_eq!(2, v[0].systems);
+        assert_eq!(sys[0].pop + sys[1].pop, v[0].pop);
+        assert_eq!(0, v[1].systems);
+        assert_eq!(0, v[1].income);
+    }
+
+    #[test]
+    fn svg_map() {
+        let mut sys = systems();
+        for (n, s) in sys.iter_mut().enumerate() {
+            s.id = n as i64 + 1;
+            (s.x, s.y) = map::grid_position(n);
+        }
+        sys[2].name = "<Zev>".to_string();
+        let lanes = vec![Lane::new(1, 2, "Major"), Lane::new(2, 9, "Minor")];
+        let svg = map_svg(&sys, &lanes);
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.ends_with("</svg>"));
+        assert_eq!(4, svg.matches("<circle ").count());
+        // A lane to a system that is not on the map is left out.
+        assert_eq!(1, svg.matches("<line ").count());
+        assert!(svg.contains("&lt;Zev&gt;"));
+    }
+
+    #[test]
+    fn whole_page() {
+        let mut emp = empires();
+        emp.truncate(1);
+        emp[0].id = 1;
+        let mut sys = systems();
+        sys[0].owner = 1;
+        sys[0].pop = 1500;
+        let summaries = summarize(&emp, &sys, &[], &SecondEdition);
+        let html = page("Senorian Wars Turn 3", &sys, &[], &summaries, &LOCALES[0]);
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<title>Senorian Wars Turn 3</title>"));
+        assert!(html.contains("Senorian</td><td class=\"n\">1</td><td class=\"n\">1500</td>"));
+        let html = page("", &sys, &[], &summaries, locale::for_tag("en-US"));
+        assert!(html.contains("<td class=\"n\">1,500</td>"));
+    }
+}
@@ -0,0 +1,198 @@
+// Copyright 2022 David Terhune
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Each empire's production in the construction phase.
+//!
+//! Builds are paid for when they are queued, so what an empire had to spend
+//! this turn is its treasury plus what it has queued. What is queued at each
+//! of its systems is set against that system's shipyard capacity, its
+//! industry plus what a working shipyard adds, and systems asked for more
+//! than they can manage are flagged.
+
+use std::collections::BTreeMap;
+
+use super::construction::Build;
+use super::empire::Empire;
+use super::facility::Facility;
+use super::locale::Locale;
+use super::repair;
+use super::shipyard;
+use super::system::System;
+
+/// What an empire has queued at one of its systems, against what the
+/// system can build.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct YardLoad {
+    pub system: i64,
+    pub name: String,
+    pub capacity: i32,
+    pub builds: usize,
+    pub queued: i32,
+}
+
+impl YardLoad {
+    /// Whether more is queued than the system can build.
+    pub fn is_over(&self) -> bool {
+        self.queued > self.capacity
+    }
+
+    /// Convert to string as a row of tab-separated fields.
+    pub fn as_row(&self) -> String {
+        format!(
+            "\t{}\t{}\t{}\t{}{}",
+            self.name,
+            self.capacity,
+            self.builds,
+            self.queued,
+            if self.is_over() { " (over)" } else { "" }
+        )
+    }
+}
+
+/// An empire's production: EP it had to spend, EP queued and EP left, with
+/// the load on each of its shipyards.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Production {
+    pub empire: i64,
+    pub name: String,
+    pub available: i32,
+    pub queued: i32,
+    pub remaining: i32,
+    pub yards: Vec<YardLoad>,
+}
+
+impl Production {
+    /// Convert to rows of tab-separated fields: the empire's totals, then a
+    /// row for each of its shipyards. Its totals are written in the given
+    /// locale.
+    pub fn as_rows(&self, locale: &Locale) -> Vec<String> {
+        let mut v = vec![format!(
+            "{}\t{} available, {} queued, {} left",
+            self.name,
+            locale.ep(self.available),
+            locale.number(self.queued as i64),
+            locale.number(self.remaining as i64)
+        )];
+        v.extend(self.yards.iter().map(YardLoad::as_row));
+        v
+    }
+}
+
+/// Sum up every empire's production from the construction queue. Each
+/// empire's shipyards are listed, with any other system it has queued builds
+/// at, in the order of the systems.
+pub fn summarize(
+    empires: &[Empire],
+    systems: &[System],
+    facilities: &[Facility],
+    builds: &[Build],
+) -> Vec<Production> {
+    let capacity = repair::capacity(systems, facilities);
+    let mut queued: BTreeMap<(i64, i64), (usize, i32)> = BTreeMap::new();
+    for b in builds {
+        let q = queued.entry((b.empire, b.system)).or_default();
+        q.0 += 1;
+        q.1 += b.cost;
+    }
+    empires
+        .iter()
+        .map(|e| {
+            let yards: Vec<YardLoad> = systems
+                .iter()
+                .filter(|s| shipyard::is_shipyard(s, e.id) || queued.contains_key(&(e.id, s.id)))
+                .map(|s| {
+                    let (builds, queued) = queued.get(&(e.id, s.id)).copied().unwrap_or_default();
+                    YardLoad {
+                        system: s.id,
+                        name: s.name.clone(),
+                        capacity: if shipyard::is_shipyard(s, e.id) {
+                            capacity.get(&s.id).copied().unwrap_or(0)
+                        } else {
+                            0
+                        },
+                        builds,
+                        queued,
+                    }
+                })
+                .collect();
+            let spent: i32 = builds
+                .iter()
+                .filter(|b| b.empire == e.id)
+                .map(|b| b.cost)
+                .sum();
+            Production {
+                empire: e.id,
+                name: e.name.clone(),
+                available: e.treasury + spent,
+                queued: spent,
+                remaining: e.treasury,
+                yards,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::summarize;
+    use crate::campaign::construction::Build;
+    use crate::campaign::empire::tests::empires;
+    use crate::campaign::locale::LOCALES;
+    use crate::campaign::system::tests::systems;
+
+    #[test]
+    fn summarize_queue() {
+        let mut emp = empires();
+        emp.truncate(2);
+        for (n, e) in emp.iter_mut().enumerate() {
+            e.id = n as i64 + 1;
+        }
+        emp[0].treasury = 12;
+        let mut sys = systems();
+        for (n, s) in sys.iter_mut().enumerate() {
+            s.id = n as i64 + 1;
+        }
+        sys[0].owner = 1; // industry 10
+        sys[1].owner = 1; // industry 3
+        sys[1].blockaded = true;
+        let mut a = Build::new(1, 1, "Ship", 1, "Avenger");
+        a.cost = 8;
+        let mut b = Build::new(1, 1, "Ground", 1, "Militia");
+        b.cost = 4;
+        let mut c = Build::new(1, 2, "Facility", 0, "Shipyard");
+        c.cost = 5;
+
+        let v = summarize(&emp, &sys, &[], &[a, b, c]);
+        assert_eq!(2, v.len());
+        let p = &v[0];
+        assert_eq!((29, 17, 12), (p.available, p.queued, p.remaining));
+        assert_eq!(2, p.yards.len());
+        assert_eq!(
+            (10, 2, 12),
+            (p.yards[0].capacity, p.yards[0].builds, p.yards[0].queued)
+        );
+        assert!(p.yards[0].is_over());
+        // A blockaded system is no shipyard, but its queue still shows.
+        assert_eq!((0, 5), (p.yards[1].capacity, p.yards[1].queued));
+        assert_eq!(
+            "Senorian\t29 EP available, 17 queued, 12 left",
+            p.as_rows(&LOCALES[0])[0]
+        );
+        assert_eq!("\tSenor Prime\t10\t2\t12 (over)", p.as_rows(&LOCALES[0])[1]);
+
+        assert!(v[1].yards.is_empty());
+        assert_eq!(0, v[1].queued);
+    }
+}
@@ -0,0 +1,368 @@
+// Copyright 2022 David Terhune
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Strategic warfare: raids on enemy infrastructure.
+
+use super::combat::Combatant;
+use super::dice::Dice;
+use super::economy::IncomeBreakdown;
+use super::facility::{self, Facility, SHIPYARD};
+use super::ruleset::Ruleset;
+use super::system::System;
+use super::unit::GroundUnit;
+
+/// Control key of the optional strategic warfare rules.
+pub const STRATEGIC_WARFARE: &str = "strategic_warfare";
+
+/// Raid on a system's shipyards, delaying the builds there and knocking out
+/// a shipyard facility.
+pub const SHIPYARDS: &str = "Shipyards";
+
+/// Raid on a system's trade, costing its owner part of the system's income.
+pub const TRADE: &str = "Trade";
+
+/// Raid on a system's population centers, lowering its population and
+/// morale.
+pub const POPULATION: &str = "Population";
+
+/// What a raid can strike at.
+pub const TARGETS: [&str; 3] = [SHIPYARDS, TRADE, POPULATION];
+
+/// Turns a successful shipyard raid adds to every build at the system.
+pub const SHIPYARD_DELAY: i32 = 1;
+
+/// A raid ordered for a turn: the empire's ships at the system strike at one
+/// kind of its infrastructure during the combat phase.
+#[derive(sqlx::FromRow, Clone, Debug, PartialEq, Eq)]
+pub struct Raid {
+    pub id: i64,
+    pub empire: i64,
+    pub turn: i32,
+    pub system: i64,
+    pub target: String,
+    #[sqlx(default)]
+    pub empire_name: String,
+    #[sqlx(default)]
+    pub system_name: String,
+}
+
+impl Raid {
+    /// Create a new raid order.
+    pub fn new(empire: i64, turn: i32, system: i64, target: &str) -> Self {
+        Self {
+            id: 0,
+            empire,
+            turn,
+            system,
+            target: target.to_string(),
+            empire_name: String::new(),
+            system_name: String::new(),
+        }
+    }
+
+    /// Convert to string as a row of tab-separated fields.
+    pub fn as_row(&self) -> String {
+        format!(
+            "{}\t{}\t{}\t{}",
+            self.turn, self.empire_name, self.system_name, self.target
+        )
+    }
+}
+
+/// Seed for a raid's rolls, so a turn resolves the same way however often it
+/// is replayed.
+pub fn seed(turn: i32, raid: i64) -> u64 {
+    ((turn as u64) << 32) ^ (raid as u64) ^ 0x5241_4944
+}
+
+/// The shipyard facilities at a system a successful shipyard raid knocks
+/// out: the first working one is crippled, or, if none is working, the
+/// first crippled one destroyed. They are given as crippled and destroyed
+/// combatants, under their IDs plus `facility::COMBAT_ID`.
+pub fn yard_damage(facilities: &[Facility], system: i64) -> (Vec<i64>, Vec<i64>) {
+    let yards: Vec<&Facility> = facilities
+        .iter()
+        .filter(|f| f.system == system && f.kind == SHIPYARD)
+        .collect();
+    match yards.iter().find(|f| !f.crip) {
+        Some(f) => (vec![facility::COMBAT_ID + f.id], Vec::new()),
+        None => match yards.first() {
+            Some(f) => (Vec::new(), vec![facility::COMBAT_ID + f.id]),
+            None => (Vec::new(), Vec::new()),
+        },
+    }
+}
+
+/// The result of a raid: the raiding ships crippled and destroyed by the
+/// defenders, the system as it is left, the turns its builds are delayed,
+/// the EP its owner loses, and the report of how it went.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RaidOutcome {
+    pub success: bool,
+    pub crippled: Vec<i64>,
+    pub destroyed: Vec<i64>,
+    pub system: System,
+    pub delay: i32,
+    pub loss: i32,
+    pub report: String,
+}
+
+/// Resolve a raid on a system by the raiding empire's ships there, against
+/// the owner's ships and garrison.
+///
+/// The defense is the attack of the owner's ships plus the defense of its
+/// ground units. If there is any, the defenders fire first: they roll a d10
+/// and score damage equal to the defense times the roll / 10, which cripples
+/// raiders in order, and destroys those already crippled, as in a battle.
+/// The surviving raiders then roll a d10, and the raid succeeds if their
+/// attack plus the roll is more than the defense plus 5. A shipyard raid
+/// delays the system's builds by `SHIPYARD_DELAY` turns, a trade raid costs
+/// the owner half the system's income, and a population raid lowers its
+/// population and morale by one.
+pub fn resolve_raid(
+    raid: &Raid,
+    target: &System,
+    raiders: &[Combatant],
+    defenders: &[Combatant],
+    garrison: &[GroundUnit],
+    rules: &dyn Ruleset,
+    dice: &mut Dice,
+) -> RaidOutcome {
+    let mut outcome = RaidOutcome {
+        success: false,
+        crippled: Vec::new(),
+        destroyed: Vec::new(),
+        system: target.clone(),
+        delay: 0,
+        loss: 0,
+        report: String::new(),
+    };
+    let mut report = format!(
+        "{} raid on the {} of {}\n",
+        raid.empire_name,
+        raid.target.to_lowercase(),
+        target.name
+    );
+    if raiders.is_empty() {
+        report.push_str("No raiding ships present; the raid is called off.\n");
+        outcome.report = report;
+        return outcome;
+    }
+
+    let defense: i32 = defenders.iter().map(|c| c.attack(rules)).sum::<i32>()
+        + garrison.iter().map(|u| u.def).sum::<i32>();
+    let mut fleet: Vec<Combatant> = raiders.to_vec();
+    if defense > 0 {
+        let roll = dice.roll(10);
+        let mut dmg = defense * roll / 10;
+        report.push_str(&format!(
+            "{} defends with {}, rolls {}, scores {} damage\n",
+            target.owner_name, defense, roll, dmg
+        ));
+        for ship in fleet.iter_mut() {
+            let def = ship.def.max(1);
+            if !ship.crip && dmg >= def {
+                dmg -= def;
+                ship.crip = true;
+                outcome.crippled.push(ship.id);
+                report.push_str(&format!("{} {} crippled\n", ship.owner_name, ship.class));
+            }
+            if ship.crip && dmg >= def {
+                dmg -= def;
+                outcome.crippled.retain(|id| *id != ship.id);
+                outcome.destroyed.push(ship.id);
+                report.push_str(&format!("{} {} destroyed\n", ship.owner_name, ship.class));
+            }
+            if dmg <= 0 {
+                break;
+            }
+        }
+        fleet.retain(|c| !outcome.destroyed.contains(&c.id));
+    } else {
+        report.push_str("The system is undefended.\n");
+    }
+
+    let attack: i32 = fleet.iter().map(|c| c.attack(rules)).sum();
+    if attack == 0 {
+        report.push_str("No raiders are left to strike.\n");
+        outcome.report = report;
+        return outcome;
+    }
+    let roll = dice.roll(10);
+    report.push_str(&format!(
+        "{} strikes with {}, rolls {} against {}\n",
+        raid.empire_name,
+        attack,
+        roll,
+        defense + 5
+    ));
+    outcome.success = attack + roll > defense + 5;
+    if !outcome.success {
+        report.push_str("The raid is beaten off.\n");
+        outcome.report = report;
+        return outcome;
+    }
+
+    match raid.target.as_str() {
+        SHIPYARDS => {
+            outcome.delay = SHIPYARD_DELAY;
+            report.push_str(&format!(
+                "Shipyards damaged; builds at {} are delayed {} turn(s).\n",
+                target.name, SHIPYARD_DELAY
+            ));
+        }
+        TRADE => {
+            outcome.loss = IncomeBreakdown::new(target, rules).total() / 2;
+            report.push_str(&format!(
+                "Trade disrupted; {} loses {} EP.\n",
+                target.owner_name, outcome.loss
+            ));
+        }
+        _ => {
+            outcome.system.pop = (target.pop - 1).max(0);
+            outcome.system.mor = (target.mor - 1).max(0);
+            report.push_str(&format!(
+                "Population centers struck; {} falls to POP {} MOR {}.\n",
+                target.name, outcome.system.pop, outcome.system.mor
+            ));
+        }
+    }
+    outcome.report = report;
+    outcome
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{resolve_raid, yard_damage, Raid, POPULATION, SHIPYARDS, SHIPYARD_DELAY, TRADE};
+    use crate::campaign::combat::Combatant;
+    use crate::campaign::dice::Dice;
+    use crate::campaign::economy::IncomeBreakdown;
+    use crate::campaign::facility::{Facility, COMBAT_ID, SHIPYARD, STARBASE};
+    use crate::campaign::ruleset::SecondEdition;
+    use crate::campaign::system::tests::systems;
+    use crate::campaign::unit::GroundUnit;
+
+    fn ship(id: i64, owner: i64, atk: i32, def: i32) -> Combatant {
+        Combatant {
+            id,
+            owner,
+            owner_name: format!("Empire {}", owner),
+            class: "Raider".to_string(),
+            stype: 0,
+            atk,
+            def,
+            crip: false,
+        }
+    }
+
+    #[test]
+    fn yards_knocked_out() {
+        let yard = |id: i64, system: i64, kind: &str, crip: bool| Facility {
+            id,
+            crip,
+            ..Facility::new(system, kind)
+        };
+        let mut yards = vec![
+            yard(1, 1, STARBASE, false),
+            yard(2, 2, SHIPYARD, false),
+            yard(3, 1, SHIPYARD, true),
+            yard(4, 1, SHIPYARD, false),
+        ];
+        assert_eq!((vec![COMBAT_ID + 4], vec![]), yard_damage(&yards, 1));
+        yards[3].crip = true;
+        assert_eq!((vec![], vec![COMBAT_ID + 3]), yard_damage(&yards, 1));
+        assert_eq!((vec![], vec![]), yard_damage(&yards, 3));
+    }
+
+    #[test]
+    fn undefended() {
+        let mut target = systems()[0].clone();
+        target.owner = 2;
+        let raiders = [ship(1, 1, 20, 3)];
+        // Any roll beats an undefended system with this much attack.
+        for (kind, seed) in [(SHIPYARDS, 1), (TRADE, 2), (POPULATION, 3)] {
+            let raid = Raid::new(1, 0, target.id, kind);
+            let o = resolve_raid(
+                &raid,
+                &target,
+                &raiders,
+                &[],
+                &[],
+                &SecondEdition,
+                &mut Dice::new(seed),
+            );
+            assert!(o.success);
+            assert!(o.crippled.is_empty() && o.destroyed.is_empty());
+            match kind {
+                SHIPYARDS => assert_eq!(SHIPYARD_DELAY, o.delay),
+                TRADE => assert_eq!(
+                    IncomeBreakdown::new(&target, &SecondEdition).total() / 2,
+                    o.loss
+                ),
+                _ => {
+                    assert_eq!(target.pop - 1, o.system.pop);
+                    assert_eq!(target.mor - 1, o.system.mor);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn defended() {
+        let target = systems()[0].clone();
+        let raid = Raid::new(1, 0, target.id, POPULATION);
+        let raiders = [ship(1, 1, 2, 1), ship(2, 1, 2, 1)];
+        let defenders = [ship(3, 2, 40, 5)];
+        let garrison = [GroundUnit {
+            id: 1,
+            gtype: 1,
+            loc: target.id,
+            name: "Militia".to_string(),
+            abbr: "MIL".to_string(),
+            atk: 4,
+            def: 4,
+        }];
+        // Too much defense for the raiders to ever succeed.
+        let o = resolve_raid(
+            &raid,
+            &target,
+            &raiders,
+            &defenders,
+            &garrison,
+            &SecondEdition,
+            &mut Dice::new(7),
+        );
+        assert!(!o.success);
+        assert_eq!(target, o.system);
+        assert!(!o.destroyed.is_empty());
+    }
+
+    #[test]
+    fn no_raiders() {
+        let target = systems()[0].clone();
+        let raid = Raid::new(1, 0, target.id, TRADE);
+        let o = resolve_raid(
+            &raid,
+            &target,
+            &[],
+            &[],
+            &[],
+            &SecondEdition,
+            &mut Dice::new(1),
+        );
+        assert!(!o.success);
+        assert!(o.report.contains("called off"));
+    }
+}
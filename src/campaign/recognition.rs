@@ -0,0 +1,199 @@
+// Copyright 2022 David Terhune
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! What each empire knows of other empires' ship designs.
+//!
+//! An empire knows its own classes. It identifies an enemy class by meeting
+//! it in battle or through espionage, and only then do the class's ratings
+//! appear in its player packet.
+
+use std::collections::BTreeSet;
+
+use super::combat::Combatant;
+use super::unit::ShipType;
+
+/// Identified by fighting ships of the class.
+pub const BATTLE: &str = "Battle";
+
+/// Identified by espionage, entered by the GM or found by an operation
+/// revealing the owner's fleets.
+pub const ESPIONAGE: &str = "Espionage";
+
+/// Identified by being given or sold a ship of the class.
+pub const TRANSFER: &str = "Transfer";
+
+/// A ship class an empire has identified, and how.
+#[derive(sqlx::FromRow, Clone, Debug, PartialEq, Eq)]
+pub struct Identification {
+    pub id: i64,
+    pub empire: i64,
+    pub stype: i64,
+    pub turn: i32,
+    pub source: String,
+    #[sqlx(default)]
+    pub class: String,
+    #[sqlx(default)]
+    pub owner_name: String,
+}
+
+impl Identification {
+    /// Create a new identification of a class by an empire.
+    pub fn new(empire: i64, stype: i64, turn: i32, source: &str) -> Self {
+        Self {
+            id: 0,
+            empire,
+            stype,
+            turn,
+            source: source.to_string(),
+            class: String::new(),
+            owner_name: String::new(),
+        }
+    }
+
+    /// Convert to string as a row of tab-separated fields.
+    pub fn as_row(&self) -> String {
+        format!(
+            "{}\t{}\t{}\t{}",
+            self.turn, self.owner_name, self.class, self.source
+        )
+    }
+}
+
+/// The classes identified in a battle: every side sees the classes of every
+/// other side's ships. Flights and facilities have no class to identify.
+pub fn from_battle(ships: &[Combatant], turn: i32) -> Vec<Identification> {
+    let sides: BTreeSet<i64> = ships.iter().map(|c| c.owner).collect();
+    let seen: BTreeSet<(i64, i64)> = ships
+        .iter()
+        .filter(|c| !c.is_flight() && !c.is_facility())
+        .map(|c| (c.owner, c.stype))
+        .collect();
+    let mut v = Vec::new();
+    for empire in sides {
+        for (owner, stype) in &seen {
+            if *owner != empire {
+                v.push(Identification::new(empire, *stype, turn, BATTLE));
+            }
+        }
+    }
+    v
+}
+
+/// Whether an empire may see a class's ratings: its own, or one it has
+/// identified.
+pub fn is_known(empire: i64, class: &ShipType, known: &[i64]) -> bool {
+    class.empire == empire || known.contains(&class.id)
+}
+
+/// The classes an empire may see, given the IDs of those it has identified.
+pub fn visible(empire: i64, classes: &[ShipType], known: &[i64]) -> Vec<ShipType> {
+    classes
+        .iter()
+        .filter(|c| is_known(empire, c, known))
+        .cloned()
+        .collect()
+}
+
+/// The player packet's listing of the classes an empire can see: its own
+/// classes in service, then the enemy classes it has identified.
+pub fn class_listing(empire: i64, classes: &[ShipType]) -> String {
+    let mut text = String::from("Ship Classes\nClass\tHull\tCost\tATK\tDEF\tCAP\tSpeed\n");
+    for c in classes.iter().filter(|c| c.empire == empire && !c.retired) {
+        text.push_str(&format!(
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+            c.class, c.hull, c.cost, c.atk, c.def, c.cap, c.speed
+        ));
+    }
+    let enemy: Vec<&ShipType> = classes.iter().filter(|c| c.empire != empire).collect();
+    if !enemy.is_empty() {
+        text.push_str("\nIdentified Enemy Classes\nEmpire\tClass\tHull\tATK\tDEF\tSpeed\n");
+        for c in enemy {
+            text.push_str(&format!(
+                "{}\t{}\t{}\t{}\t{}\t{}\n",
+                c.empire_name, c.class, c.hull, c.atk, c.def, c.speed
+            ));
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{class_listing, from_battle, is_known, visible, BATTLE};
+    use crate::campaign::combat::Combatant;
+    use crate::campaign::facility;
+    use crate::campaign::unit::ShipType;
+
+    fn ship(id: i64, owner: i64, stype: i64) -> Combatant {
+        Combatant {
+            id,
+            owner,
+            owner_name: format!("Empire {}", owner),
+            class: "Frigate".to_string(),
+            stype,
+            atk: 2,
+            def: 2,
+            crip: false,
+        }
+    }
+
+    fn class(id: i64, name: &str, empire: i64) -> ShipType {
+        let mut c = ShipType::new(name, "Frigate", empire);
+        c.id = id;
+        c.empire_name = format!("Empire {}", empire);
+        c
+    }
+
+    #[test]
+    fn battle_identifies() {
+        // The flight, with its negated ID, and the starbase reveal nothing.
+        let ships = [
+            ship(1, 1, 10),
+            ship(2, 1, 11),
+            ship(3, 2, 20),
+            ship(-1, 2, 0),
+            ship(facility::COMBAT_ID + 1, 2, 0),
+        ];
+        let mut seen: Vec<(i64, i64)> = from_battle(&ships, 4)
+            .iter()
+            .map(|i| {
+                assert_eq!((4, BATTLE), (i.turn, i.source.as_str()));
+                (i.empire, i.stype)
+            })
+            .collect();
+        seen.sort();
+        assert_eq!(vec![(1, 20), (2, 10), (2, 11)], seen);
+        assert!(from_battle(&ships[..2], 4).is_empty());
+    }
+
+    #[test]
+    fn listing() {
+        let classes = [
+            class(10, "Avenger", 1),
+            class(20, "Kestrel", 2),
+            class(21, "Warden", 2),
+        ];
+        assert!(is_known(1, &classes[0], &[]));
+        assert!(!is_known(1, &classes[1], &[]));
+        assert!(is_known(1, &classes[1], &[20]));
+
+        let text = class_listing(1, &visible(1, &classes, &[]));
+        assert!(text.contains("Avenger"));
+        assert!(!text.contains("Identified Enemy Classes"));
+        let text = class_listing(1, &visible(1, &classes, &[20]));
+        assert!(text.contains("Empire 2\tKestrel"));
+        assert!(!text.contains("Warden"));
+    }
+}
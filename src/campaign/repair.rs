@@ -0,0 +1,183 @@
+// Copyright 2022 David Terhune
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Repair of crippled ships during the construction phase.
+//!
+//! A crippled ship in a fleet at one of its empire's shipyards can be
+//! repaired there once the repair has been ordered and paid for. Each system
+//! repairs as much as its industry and shipyard allow in a turn; when that
+//! is not enough, ships are repaired in priority order and the rest wait
+//! for a later turn.
+
+use std::collections::BTreeMap;
+
+use super::facility::{self, Facility, SHIPYARD};
+use super::system::System;
+
+/// A crippled ship waiting for an ordered repair at one of its empire's
+/// systems.
+#[derive(sqlx::FromRow, Clone, Debug, PartialEq, Eq)]
+pub struct RepairJob {
+    pub ship: i64,
+    pub empire: i64,
+    pub system: i64,
+    pub class: String,
+    pub cost: i32,
+    pub priority: i32,
+    #[sqlx(default)]
+    pub empire_name: String,
+    #[sqlx(default)]
+    pub system_name: String,
+}
+
+impl RepairJob {
+    /// Repair capacity the ship needs, which is what its repair costs.
+    pub fn needs(&self) -> i32 {
+        cost(self.cost)
+    }
+
+    /// Convert to string as a row of tab-separated fields.
+    pub fn as_row(&self) -> String {
+        format!(
+            "{}\t{}\t{}\t{}\t{}\t{}",
+            self.priority,
+            self.empire_name,
+            self.class,
+            self.ship,
+            self.system_name,
+            self.needs()
+        )
+    }
+}
+
+/// What a turn's repairs come to: the ships repaired, and those left
+/// crippled for want of capacity, each in the order they were considered.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RepairPlan {
+    pub repaired: Vec<RepairJob>,
+    pub unrepaired: Vec<RepairJob>,
+}
+
+/// EP and repair capacity it takes to repair a ship of a class with the
+/// given cost: half the cost, rounded up, and at least one.
+pub fn cost(class_cost: i32) -> i32 {
+    ((class_cost + 1) / 2).max(1)
+}
+
+/// Repair capacity of each system for a turn, which is its industry plus
+/// what a working shipyard there adds.
+pub fn capacity(systems: &[System], facilities: &[Facility]) -> BTreeMap<i64, i32> {
+    let yards = facility::working(facilities, SHIPYARD);
+    systems
+        .iter()
+        .map(|s| {
+            let yard = if yards.contains(&s.id) {
+                facility::SHIPYARD_CAPACITY
+            } else {
+                0
+            };
+            (s.id, s.ind.max(0) + yard)
+        })
+        .collect()
+}
+
+/// Triage the waiting ships against each system's capacity. At each system
+/// higher priorities go first, then lower ship IDs. A ship too costly for
+/// what remains waits, and the capacity goes to the next one that fits.
+pub fn plan(jobs: &[RepairJob], capacity: &BTreeMap<i64, i32>) -> RepairPlan {
+    let mut jobs = jobs.to_vec();
+    jobs.sort_by(|a, b| {
+        a.system
+            .cmp(&b.system)
+            .then(b.priority.cmp(&a.priority))
+            .then(a.ship.cmp(&b.ship))
+    });
+    let mut left = capacity.clone();
+    let mut plan = RepairPlan::default();
+    for job in jobs {
+        let room = left.entry(job.system).or_insert(0);
+        if job.needs() <= *room {
+            *room -= job.needs();
+            plan.repaired.push(job);
+        } else {
+            plan.unrepaired.push(job);
+        }
+    }
+    plan
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::{capacity, plan, RepairJob};
+    use crate::campaign::facility::{Facility, SHIPYARD, SHIPYARD_CAPACITY};
+    use crate::campaign::system::tests::systems;
+
+    fn job(ship: i64, system: i64, cost: i32, priority: i32) -> RepairJob {
+        RepairJob {
+            ship,
+            empire: 1,
+            system,
+            class: "Cruiser".to_string(),
+            cost,
+            priority,
+            empire_name: "Senorian".to_string(),
+            system_name: "Senor Prime".to_string(),
+        }
+    }
+
+    #[test]
+    fn needs() {
+        assert_eq!(3, job(1, 1, 5, 0).needs());
+        assert_eq!(2, job(1, 1, 4, 0).needs());
+        assert_eq!(1, job(1, 1, 0, 0).needs());
+        assert_eq!(
+            "2\tSenorian\tCruiser\t1\tSenor Prime\t3",
+            job(1, 1, 5, 2).as_row()
+        );
+    }
+
+    #[test]
+    fn triage() {
+        let jobs = [
+            job(1, 1, 6, 0),
+            job(2, 1, 6, 5),
+            job(3, 1, 2, 0),
+            job(4, 2, 2, 0),
+        ];
+        let capacity = BTreeMap::from([(1, 4)]);
+        let p = plan(&jobs, &capacity);
+
+        // Ship 2 outranks ship 1, and ship 3 fits in what is left.
+        let ids = |v: &[RepairJob]| v.iter().map(|j| j.ship).collect::<Vec<i64>>();
+        assert_eq!(vec![2, 3], ids(&p.repaired));
+        assert_eq!(vec![1, 4], ids(&p.unrepaired));
+    }
+
+    #[test]
+    fn shipyards() {
+        let mut v = systems();
+        for (n, s) in v.iter_mut().enumerate() {
+            s.id = n as i64 + 1;
+        }
+        let mut yards = vec![Facility::new(1, SHIPYARD), Facility::new(2, SHIPYARD)];
+        yards[1].crip = true;
+        let c = capacity(&v, &yards);
+        assert_eq!(Some(&(v[0].ind + SHIPYARD_CAPACITY)), c.get(&1));
+        // A crippled shipyard adds nothing.
+        assert_eq!(Some(&v[1].ind), c.get(&2));
+    }
+}
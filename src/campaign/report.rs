@@ -0,0 +1,356 @@
+// Copyright 2022 David Terhune
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Player packet and GM report generation.
+
+use std::collections::BTreeMap;
+
+use super::economy::IncomeBreakdown;
+use super::empire::Empire;
+use super::intel::IntelItem;
+use super::locale::Locale;
+use super::recognition;
+use super::ruleset::Ruleset;
+use super::system::System;
+use super::template;
+use super::unit::{FleetSummary, GroundUnit, ShipType};
+
+/// Produce an empire's player packet for a turn from a template: its
+/// treasury and tech, the systems it owns with their income and garrisons,
+/// the ship classes it can see, and any intel the GM attached. EP are
+/// written in the campaign's locale.
+#[allow(clippy::too_many_arguments)]
+pub fn player_packet(
+    tmpl: &str,
+    turn: i32,
+    emp: &Empire,
+    systems: &[System],
+    garrisons: &[GroundUnit],
+    classes: &[ShipType],
+    intel: &[IntelItem],
+    rules: &dyn Ruleset,
+    locale: &Locale,
+) -> String {
+    template::render(
+        tmpl,
+        &packet_values(turn, emp, systems, garrisons, classes, intel, rules, locale),
+    )
+}
+
+// Values of the player packet template. See `template::DEFAULT_PLAYER_PACKET`
+// for their names.
+#[allow(clippy::too_many_arguments)]
+fn packet_values(
+    turn: i32,
+    emp: &Empire,
+    systems: &[System],
+    garrisons: &[GroundUnit],
+    classes: &[ShipType],
+    intel: &[IntelItem],
+    rules: &dyn Ruleset,
+    locale: &Locale,
+) -> BTreeMap<&'static str, String> {
+    let mut values = BTreeMap::new();
+    values.insert("empire", emp.name.clone());
+    values.insert("turn", turn.to_string());
+    values.insert("treasury", locale.number(emp.treasury as i64));
+    values.insert("tech", emp.tech.to_string());
+    let traits = emp.trait_list();
+    values.insert(
+        "government",
+        if traits.is_empty() {
+            emp.government.clone()
+        } else {
+            format!("{} ({})", emp.government, traits.join(", "))
+        },
+    );
+
+    let mut text = String::from("Systems\nName\tType\tPOP\tMOR\tIND\tIncome\n");
+    let mut total = 0;
+    for s in systems.iter().filter(|s| s.owner == emp.id) {
+        let income = IncomeBreakdown::new(s, rules).total();
+        total += income;
+        text.push_str(&format!(
+            "{}\t{}\t{}\t{}\t{}\t{}\n",
+            s.name, s.ptype, s.pop, s.mor, s.ind, income
+        ));
+    }
+    text.push_str(&format!("Total income:\t{}\n", locale.number(total as i64)));
+    values.insert("systems", text);
+
+    let mut text = String::from("Garrisons\nName\tUnits\tATK\tDEF\tStatus\n");
+    for s in systems.iter().filter(|s| s.owner == emp.id) {
+        let units: Vec<&GroundUnit> = garrisons.iter().filter(|u| u.loc == s.id).collect();
+        let mut counts: Vec<(&str, i32)> = Vec::new();
+        for u in &units {
+            match counts.iter_mut().find(|(a, _)| *a == u.abbr) {
+                Some((_, n)) => *n += 1,
+                None => counts.push((u.abbr.as_str(), 1)),
+            }
+        }
+        let listing: Vec<String> = counts.iter().map(|(a, n)| format!("{} {}", n, a)).collect();
+        text.push_str(&format!(
+            "{}\t{}\t{}\t{}\t{}\n",
+            s.name,
+            if listing.is_empty() {
+                "None".to_string()
+            } else {
+                listing.join(", ")
+            },
+            units.iter().map(|u| u.atk).sum::<i32>(),
+            units.iter().map(|u| u.def).sum::<i32>(),
+            defense_status(s, units.len())
+        ));
+    }
+    values.insert("garrisons", text);
+    values.insert("classes", recognition::class_listing(emp.id, classes));
+
+    let mut text = String::new();
+    let items: Vec<&IntelItem> = intel.iter().filter(|i| i.empire == emp.id).collect();
+    if !items.is_empty() {
+        text.push_str("\nIntelligence\n");
+        for i in items {
+            text.push_str(&format!("{}: {}\n", i.heading(), i.text));
+        }
+    }
+    values.insert("intel", text);
+    values
+}
+
+/// Produce the GM's military summary of a system: every fleet present,
+/// grouped by owner, with each owner's combined strength. The garrison is
+/// counted with the system's owner. A system where more than one empire has
+/// forces is flagged as contested.
+pub fn military_summary(sys: &System, fleets: &[FleetSummary], garrison: &[GroundUnit]) -> String {
+    let present: Vec<&FleetSummary> = fleets.iter().filter(|f| f.location == sys.id).collect();
+    let mut owners: Vec<(i64, &str)> = Vec::new();
+    if !garrison.is_empty() {
+        owners.push((sys.owner, sys.owner_name.as_str()));
+    }
+    for f in &present {
+        if !owners.iter().any(|(o, _)| *o == f.owner) {
+            owners.push((f.owner, f.owner_name.as_str()));
+        }
+    }
+    owners.sort_by(|a, b| a.1.cmp(b.1));
+
+    let mut summary = format!("Military Summary: {}\n", sys.name);
+    summary.push_str(&format!("Owner:\t{}\n", sys.owner_name));
+    if owners.len() > 1 {
+        summary.push_str("CONTESTED\n");
+    }
+    if owners.is_empty() {
+        summary.push_str("\nNo forces present.\n");
+    }
+
+    for (owner, name) in owners {
+        summary.push_str(&format!("\n{}\nFleet\tShips\tCrippled\tATK\tDEF\n", name));
+        let (mut ships, mut atk, mut def) = (0, 0, 0);
+        for f in present.iter().filter(|f| f.owner == owner) {
+            summary.push_str(&format!(
+                "{}\t{}\t{}\t{}\t{}\n",
+                f.name, f.ships, f.crippled, f.atk, f.def
+            ));
+            ships += f.ships;
+            atk += f.atk;
+            def += f.def;
+        }
+        if owner == sys.owner && !garrison.is_empty() {
+            let g_atk: i32 = garrison.iter().map(|u| u.atk).sum();
+            let g_def: i32 = garrison.iter().map(|u| u.def).sum();
+            summary.push_str(&format!(
+                "Garrison:\t{} units\t\t{}\t{}\n",
+                garrison.len(),
+                g_atk,
+                g_def
+            ));
+            atk += g_atk;
+            def += g_def;
+        }
+        summary.push_str(&format!("Total:\t{} ships\t\t{}\t{}\n", ships, atk, def));
+    }
+    summary
+}
+
+// Planetary defense status of a system with the given number of ground units.
+fn defense_status(sys: &System, units: usize) -> &'static str {
+    match (sys.blockaded, units) {
+        (true, 0) => "Blockaded, undefended",
+        (true, _) => "Blockaded",
+        (false, 0) => "Undefended",
+        (false, _) => "Garrisoned",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{military_summary, player_packet};
+    use crate::campaign::empire::Empire;
+    use crate::campaign::intel::IntelItem;
+    use crate::campaign::locale::{self, LOCALES};
+    use crate::campaign::ruleset::SecondEdition;
+    use crate::campaign::system::tests::systems;
+    use crate::campaign::template::DEFAULT_PLAYER_PACKET;
+    use crate::campaign::unit::{FleetSummary, GroundUnit, ShipType};
+
+    #[test]
+    fn packet_contents() {
+        let mut emp = Empire::new("Senorian");
+        emp.id = 1;
+        let mut sys = systems();
+        for (i, s) in sys.iter_mut().enumerate() {
+            s.id = i as i64 + 1;
+        }
+        sys[0].owner = 1;
+        let intel = vec![
+            IntelItem::new(1, 0, "Scan", "Kili fleet at Tibron"),
+            IntelItem::new(1, 0, "Misinformation", "Human fleet at Zev'rch"),
+            IntelItem::new(2, 0, "Rumor", "Not for the Senorians"),
+        ];
+        let garrison = vec![
+            militia(1, sys[0].id),
+            militia(2, sys[0].id),
+            militia(3, sys[1].id),
+        ];
+        let mut avenger = ShipType::new("Avenger", "Cruiser", 1);
+        avenger.atk = 6;
+        let classes = vec![avenger];
+        let packet = player_packet(
+            DEFAULT_PLAYER_PACKET,
+            0,
+            &emp,
+            &sys,
+            &garrison,
+            &classes,
+            &intel,
+            &SecondEdition,
+            &LOCALES[0],
+        );
+        assert!(packet.contains("Ship Classes\n"));
+        assert!(packet.contains("Avenger\tCruiser\t0\t6"));
+        assert!(packet.contains("Senor Prime"));
+        assert!(!packet.contains("Vadurrinia"));
+        assert!(packet.contains("Scan Result: Kili fleet at Tibron"));
+        assert!(packet.contains("Rumor: Human fleet at Zev'rch"));
+        assert!(!packet.contains("Misinformation"));
+        assert!(!packet.contains("Not for the Senorians"));
+        assert!(packet.contains("Senor Prime\t2 MIL\t8\t8\tGarrisoned"));
+        assert!(packet.contains("Government:\tMonarchy\n"));
+
+        emp.traits = "Mercantile".to_string();
+        let packet = player_packet(
+            "{{government}}",
+            0,
+            &emp,
+            &sys,
+            &[],
+            &[],
+            &[],
+            &SecondEdition,
+            &LOCALES[0],
+        );
+        assert_eq!("Monarchy (Mercantile)", packet);
+
+        let packet = player_packet(
+            "{{empire}}: {{treasury}} EP",
+            0,
+            &emp,
+            &sys,
+            &[],
+            &[],
+            &[],
+            &SecondEdition,
+            &LOCALES[0],
+        );
+        assert_eq!("Senorian: 0 EP", packet);
+
+        emp.treasury = 12345;
+        let packet = player_packet(
+            "{{empire}}: {{treasury}} EP",
+            0,
+            &emp,
+            &sys,
+            &[],
+            &[],
+            &[],
+            &SecondEdition,
+            locale::for_tag("en-US"),
+        );
+        assert_eq!("Senorian: 12,345 EP", packet);
+    }
+
+    #[test]
+    fn military() {
+        let mut sys = systems()[0].clone();
+        sys.id = 1;
+        sys.owner = 1;
+        sys.owner_name = "Senorian".to_string();
+        let fleets = vec![
+            fleet("Home", 1, "Senorian", 1, 3, 9),
+            fleet("Raid", 2, "Human", 1, 2, 4),
+            fleet("Elsewhere", 2, "Human", 2, 5, 5),
+        ];
+        let summary = military_summary(&sys, &fleets, &[militia(1, 1)]);
+        assert!(summary.contains("CONTESTED"));
+        assert!(summary.contains("Home\t3\t0\t9\t9"));
+        assert!(summary.contains("Garrison:\t1 units\t\t4\t4"));
+        assert!(summary.contains("Total:\t3 ships\t\t13\t13"));
+        assert!(summary.contains("Total:\t2 ships\t\t4\t4"));
+        assert!(!summary.contains("Elsewhere"));
+        // Senorian forces are listed after Human forces.
+        assert!(summary.find("Human").unwrap() < summary.find("Home").unwrap());
+
+        let quiet = military_summary(&sys, &fleets[..1], &[]);
+        assert!(!quiet.contains("CONTESTED"));
+        assert!(military_summary(&sys, &[], &[]).contains("No forces present"));
+    }
+
+    fn fleet(
+        name: &str,
+        owner: i64,
+        owner_name: &str,
+        loc: i64,
+        ships: i32,
+        strength: i32,
+    ) -> FleetSummary {
+        FleetSummary {
+            id: 0,
+            name: name.to_string(),
+            owner,
+            location: loc,
+            speed: 0,
+            owner_name: owner_name.to_string(),
+            location_name: String::new(),
+            ships,
+            crippled: 0,
+            atk: strength,
+            def: strength,
+            maint: 0,
+            jumps: 0,
+        }
+    }
+
+    fn militia(id: i64, loc: i64) -> GroundUnit {
+        GroundUnit {
+            id,
+            gtype: 1,
+            loc,
+            name: "Militia".to_string(),
+            abbr: "MIL".to_string(),
+            atk: 4,
+            def: 4,
+        }
+    }
+}
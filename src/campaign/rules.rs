@@ -0,0 +1,121 @@
+// Copyright 2022 David Terhune
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Quick reference to the rules in use by a campaign.
+
+use super::combat::CombatModifier;
+use super::options::CampaignOptions;
+use super::turn::SEQUENCE;
+use super::unit::{GroundType, Hull};
+
+/// A single entry in the rules reference.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RefEntry {
+    pub section: String,
+    pub topic: String,
+    pub text: String,
+}
+
+impl RefEntry {
+    /// Create a new reference entry.
+    pub fn new(section: &str, topic: &str, text: &str) -> Self {
+        Self {
+            section: section.to_string(),
+            topic: topic.to_string(),
+            text: text.to_string(),
+        }
+    }
+
+    /// Convert to string as a row of tab-separated fields.
+    pub fn as_row(&self) -> String {
+        format!("{}\t{}\t{}", self.section, self.topic, self.text)
+    }
+
+    /// Whether the entry mentions the search text, ignoring case. Empty
+    /// search text matches everything.
+    pub fn matches(&self, search: &str) -> bool {
+        let search = search.trim().to_lowercase();
+        search.is_empty()
+            || self.section.to_lowercase().contains(&search)
+            || self.topic.to_lowercase().contains(&search)
+            || self.text.to_lowercase().contains(&search)
+    }
+}
+
+/// Build the reference from the campaign's options, the sequence of play and
+/// the campaign's rules data.
+pub fn reference(
+    options: &CampaignOptions,
+    modifiers: &[CombatModifier],
+    hulls: &[Hull],
+    ground_types: &[GroundType],
+) -> Vec<RefEntry> {
+    let mut v = Vec::new();
+    for (topic, text) in options.describe() {
+        v.push(RefEntry::new("Campaign Options", topic, &text));
+    }
+    for (i, phase) in SEQUENCE.iter().enumerate() {
+        let topic = format!("{}. {}", i + 1, phase);
+        v.push(RefEntry::new("Sequence of Play", &topic, phase.summary()));
+    }
+    for m in modifiers {
+        let text = format!("{} ATK {:+} DEF {:+}", m.category, m.atk, m.def);
+        v.push(RefEntry::new("Combat Modifiers", &m.name, &text));
+    }
+    for h in hulls {
+        let text = format!(
+            "Size {}, maintenance {} EP, tech level {}",
+            h.size, h.maint, h.tech
+        );
+        v.push(RefEntry::new("Hulls", &h.name, &text));
+    }
+    for g in ground_types {
+        let text = format!("{} cost {} EP, ATK {} DEF {}", g.abbr, g.cost, g.atk, g.def);
+        v.push(RefEntry::new("Ground Units", &g.name, &text));
+    }
+    v
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{reference, RefEntry};
+    use crate::campaign::combat::CombatModifier;
+    use crate::campaign::options::CampaignOptions;
+    use crate::campaign::turn::SEQUENCE;
+
+    #[test]
+    fn matches() {
+        let e = RefEntry::new("Combat Modifiers", "Ion Storm", "Terrain ATK -2 DEF -1");
+        assert!(e.matches(""));
+        assert!(e.matches("ion"));
+        assert!(e.matches("TERRAIN"));
+        assert!(e.matches("combat"));
+        assert!(!e.matches("nebula"));
+    }
+
+    #[test]
+    fn reference_sections() {
+        let m = CombatModifier::new("Terrain", "Ion Storm", -2, -1);
+        let options = CampaignOptions::default();
+        let v = reference(&options, &[m], &[], &[]);
+        let described = options.describe().len();
+        assert_eq!(described + SEQUENCE.len() + 1, v.len());
+        assert_eq!("Rules Edition", v[0].topic);
+        assert_eq!("1. Income", v[described].topic);
+        let last = v.last().unwrap();
+        assert_eq!("Ion Storm", last.topic);
+        assert_eq!("Terrain ATK -2 DEF -1", last.text);
+    }
+}
@@ -0,0 +1,194 @@
+// Copyright 2022 David Terhune
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The numbers that differ between editions of the VBAM rules.
+//!
+//! A campaign is played by one edition, chosen when it is created and kept
+//! with its options. Economy, combat and maintenance ask the campaign's
+//! ruleset for the edition's formulas rather than assuming one.
+
+use super::options::EDITIONS;
+use super::system::System;
+use super::unit::ShipStatus;
+
+/// The formulas of one edition of the rules.
+pub trait Ruleset: Send + Sync {
+    /// Name of the edition, one of `options::EDITIONS`.
+    fn edition(&self) -> &'static str;
+
+    /// Income a system produces before morale, blockade and trade.
+    fn system_income(&self, sys: &System) -> i32;
+
+    /// Census income from a system's population.
+    fn census(&self, pop: i32) -> i32;
+
+    /// Damage scored in a round of combat by forces with the given total
+    /// attack, on a roll of a d10.
+    fn damage(&self, attack: i32, roll: i32) -> i32;
+
+    /// Rounds fought before a battle breaks off.
+    fn battle_rounds(&self) -> i32;
+
+    /// Rounds fought before a failed planetary assault withdraws.
+    fn assault_rounds(&self) -> i32;
+
+    /// Attack a crippled ship or flight of the given attack fights with.
+    fn crippled_attack(&self, atk: i32) -> i32;
+
+    /// Maintenance for one ship in a status, given its hull's base
+    /// maintenance.
+    fn maintenance(&self, status: ShipStatus, base: i32) -> i32;
+}
+
+/// The first edition of VBAM.
+///
+/// A system's income is its RAW plus its IND plus half its population, and
+/// there is no separate census. Combat damage is the attack times the roll
+/// / 10, battles last up to five rounds and assaults three. Crippled ships
+/// cannot fire. Crippled ships pay full maintenance and mothballed ones half,
+/// rounded up.
+pub struct FirstEdition;
+
+/// The second edition of VBAM.
+///
+/// A system's income is its RAW plus its IND, and census income is one EP
+/// for every two points of population. Combat damage is the attack times
+/// (5 + roll) / 10, battles last up to three rounds and assaults five.
+/// Crippled ships fight at half attack. Crippled ships pay half maintenance
+/// and mothballed ones a quarter, both rounded up.
+pub struct SecondEdition;
+
+impl Ruleset for FirstEdition {
+    fn edition(&self) -> &'static str {
+        EDITIONS[1]
+    }
+
+    fn system_income(&self, sys: &System) -> i32 {
+        sys.raw + sys.ind + sys.pop / 2
+    }
+
+    fn census(&self, _pop: i32) -> i32 {
+        0
+    }
+
+    fn damage(&self, attack: i32, roll: i32) -> i32 {
+        attack * roll / 10
+    }
+
+    fn battle_rounds(&self) -> i32 {
+        5
+    }
+
+    fn assault_rounds(&self) -> i32 {
+        3
+    }
+
+    fn crippled_attack(&self, _atk: i32) -> i32 {
+        0
+    }
+
+    fn maintenance(&self, status: ShipStatus, base: i32) -> i32 {
+        match status {
+            ShipStatus::Active | ShipStatus::Crippled => base,
+            ShipStatus::Mothballed => (base + 1) / 2,
+        }
+    }
+}
+
+impl Ruleset for SecondEdition {
+    fn edition(&self) -> &'static str {
+        EDITIONS[0]
+    }
+
+    fn system_income(&self, sys: &System) -> i32 {
+        sys.raw + sys.ind
+    }
+
+    fn census(&self, pop: i32) -> i32 {
+        pop / 2
+    }
+
+    fn damage(&self, attack: i32, roll: i32) -> i32 {
+        attack * (5 + roll) / 10
+    }
+
+    fn battle_rounds(&self) -> i32 {
+        3
+    }
+
+    fn assault_rounds(&self) -> i32 {
+        5
+    }
+
+    fn crippled_attack(&self, atk: i32) -> i32 {
+        atk / 2
+    }
+
+    fn maintenance(&self, status: ShipStatus, base: i32) -> i32 {
+        match status {
+            ShipStatus::Active => base,
+            ShipStatus::Crippled => (base + 1) / 2,
+            ShipStatus::Mothballed => (base + 3) / 4,
+        }
+    }
+}
+
+/// The ruleset of an edition. An edition not known is played by the second
+/// edition's rules, as campaigns were before editions could be chosen.
+pub fn for_edition(edition: &str) -> &'static dyn Ruleset {
+    if edition == FirstEdition.edition() {
+        &FirstEdition
+    } else {
+        &SecondEdition
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{for_edition, FirstEdition, Ruleset, SecondEdition};
+    use crate::campaign::system::System;
+    use crate::campaign::unit::ShipStatus;
+
+    #[test]
+    fn editions() {
+        assert_eq!("First", for_edition("First").edition());
+        assert_eq!("Second", for_edition("Second").edition());
+        assert_eq!("Second", for_edition("").edition());
+
+        let sys = System::new("Tibron", "Barren", 5, 6, 9, 2, 10);
+        assert_eq!(15, SecondEdition.system_income(&sys));
+        assert_eq!(19, FirstEdition.system_income(&sys));
+        assert_eq!(4, SecondEdition.census(sys.pop));
+        assert_eq!(0, FirstEdition.census(sys.pop));
+
+        assert_eq!(15, SecondEdition.damage(10, 10));
+        assert_eq!(10, FirstEdition.damage(10, 10));
+        assert_eq!(6, SecondEdition.damage(10, 1));
+        assert_eq!(1, FirstEdition.damage(10, 1));
+    }
+
+    #[test]
+    fn maintenance() {
+        let rules = SecondEdition;
+        assert_eq!(3, rules.maintenance(ShipStatus::Active, 3));
+        assert_eq!(2, rules.maintenance(ShipStatus::Crippled, 3));
+        assert_eq!(1, rules.maintenance(ShipStatus::Mothballed, 3));
+        assert_eq!(0, rules.maintenance(ShipStatus::Mothballed, 0));
+
+        let rules = FirstEdition;
+        assert_eq!(3, rules.maintenance(ShipStatus::Crippled, 3));
+        assert_eq!(2, rules.maintenance(ShipStatus::Mothballed, 3));
+    }
+}
@@ -0,0 +1,121 @@
+// Copyright 2022 David Terhune
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Scorched earth: a retreating owner destroying what an invader would
+//! capture.
+//!
+//! Orders stand for a turn. They are carried out only if the system falls,
+//! just before the invader takes possession.
+
+use super::system::System;
+
+/// Destroy the shipyards, losing every build at the system and its industry.
+pub const SHIPYARDS: &str = "Shipyards";
+
+/// Destroy the supply depots, halving the system's raw resources.
+pub const DEPOTS: &str = "Depots";
+
+/// Destroy habitat and infrastructure, halving the system's capacity and
+/// any population beyond it.
+pub const CAPACITY: &str = "Capacity";
+
+/// What can be destroyed.
+pub const TARGETS: [&str; 3] = [SHIPYARDS, DEPOTS, CAPACITY];
+
+/// Morale the system loses for each target destroyed, which the invader
+/// inherits.
+pub const MORALE_LOSS: i32 = 1;
+
+/// A standing order to destroy part of a system if it falls this turn.
+#[derive(sqlx::FromRow, Clone, Debug, PartialEq, Eq)]
+pub struct ScorchOrder {
+    pub id: i64,
+    pub empire: i64,
+    pub turn: i32,
+    pub system: i64,
+    pub target: String,
+    #[sqlx(default)]
+    pub empire_name: String,
+    #[sqlx(default)]
+    pub system_name: String,
+}
+
+impl ScorchOrder {
+    /// Create a new scorched earth order.
+    pub fn new(empire: i64, turn: i32, system: i64, target: &str) -> Self {
+        Self {
+            id: 0,
+            empire,
+            turn,
+            system,
+            target: target.to_string(),
+            empire_name: String::new(),
+            system_name: String::new(),
+        }
+    }
+}
+
+/// Destroy the targets at a system, returning what was done. Morale falls
+/// once for each target; shipyard builds are lost separately, since they are
+/// not part of the system.
+pub fn destroy(sys: &mut System, targets: &[&str]) -> String {
+    let mut report = String::new();
+    for target in targets {
+        match *target {
+            SHIPYARDS => {
+                sys.ind = 0;
+                report.push_str(&format!("The shipyards of {} were razed\n", sys.name));
+            }
+            DEPOTS => {
+                sys.raw /= 2;
+                report.push_str(&format!("The supply depots of {} were burned\n", sys.name));
+            }
+            CAPACITY => {
+                sys.cap /= 2;
+                sys.pop = sys.pop.min(sys.cap);
+                report.push_str(&format!(
+                    "The infrastructure of {} was destroyed\n",
+                    sys.name
+                ));
+            }
+            _ => continue,
+        }
+        sys.mor = (sys.mor - MORALE_LOSS).max(0);
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{destroy, CAPACITY, DEPOTS, MORALE_LOSS, SHIPYARDS};
+    use crate::campaign::system::tests::systems;
+
+    #[test]
+    fn destruction() {
+        let before = systems()[0].clone();
+        let mut sys = before.clone();
+        let report = destroy(&mut sys, &[SHIPYARDS, DEPOTS, CAPACITY]);
+        assert_eq!(3, report.lines().count());
+        assert_eq!(0, sys.ind);
+        assert_eq!(before.raw / 2, sys.raw);
+        assert_eq!(before.cap / 2, sys.cap);
+        assert!(sys.pop <= sys.cap);
+        assert_eq!(before.mor - 3 * MORALE_LOSS, sys.mor);
+
+        let mut sys = before.clone();
+        assert!(destroy(&mut sys, &["Granaries"]).is_empty());
+        assert_eq!(before, sys);
+    }
+}
@@ -0,0 +1,270 @@
+// Copyright 2022 David Terhune
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The service record of each ship class, to judge which designs earn
+//! their cost.
+//!
+//! Ships delivered by the construction phase are counted as they are built,
+//! and every battle adds what each class brought to it, lost in it and
+//! destroyed. Battles are resolved side against side, not ship against
+//! ship, so the enemy ships a side destroys are shared among its classes by
+//! the attack each brought.
+
+use std::collections::BTreeMap;
+
+use super::combat::Combatant;
+use super::ruleset::Ruleset;
+use super::war;
+
+/// What one ship class did on a turn: ships delivered, or ships brought to
+/// a battle, with those lost and the kills credited to them.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Service {
+    pub turn: i32,
+    pub stype: i64,
+    pub built: i32,
+    pub fought: i32,
+    pub lost: i32,
+    pub kills: f64,
+}
+
+/// The service record of a ship class over the campaign so far.
+#[derive(sqlx::FromRow, Clone, Debug, Default, PartialEq)]
+pub struct ClassRecord {
+    pub stype: i64,
+    pub class: String,
+    pub hull: String,
+    pub cost: i32,
+    pub empire_name: String,
+    pub built: i32,
+    pub active: i32,
+    pub fought: i32,
+    pub lost: i32,
+    pub kills: f64,
+}
+
+impl ClassRecord {
+    /// Kills for each ship lost, if any have been.
+    pub fn ratio(&self) -> Option<f64> {
+        if self.lost == 0 {
+            None
+        } else {
+            Some(self.kills / self.lost as f64)
+        }
+    }
+
+    /// Convert to string as a row of tab-separated fields.
+    pub fn as_row(&self) -> String {
+        format!(
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{:.1}\t{}",
+            self.empire_name,
+            self.class,
+            self.hull,
+            self.cost,
+            self.built,
+            self.active,
+            self.fought,
+            self.lost,
+            self.kills,
+            match self.ratio() {
+                Some(r) => format!("{:.2}", r),
+                None => "-".to_string(),
+            }
+        )
+    }
+}
+
+/// What each ship class did in a battle, given the ships destroyed. Each
+/// enemy ship destroyed is credited to the side it was fighting, as its
+/// loss is counted against that side's war.
+pub fn from_battle(
+    turn: i32,
+    ships: &[Combatant],
+    destroyed: &[i64],
+    rules: &dyn Ruleset,
+) -> Vec<Service> {
+    let hulls: Vec<&Combatant> = ships
+        .iter()
+        .filter(|c| !c.is_flight() && !c.is_facility())
+        .collect();
+    let mut credit: BTreeMap<i64, f64> = BTreeMap::new();
+    for c in hulls.iter().filter(|c| destroyed.contains(&c.id)) {
+        if let Some(foe) = war::enemy(c.owner, ships) {
+            *credit.entry(foe).or_default() += 1.0;
+        }
+    }
+    // Ships and attack of each class, and of each side.
+    let mut classes: BTreeMap<(i64, i64), Service> = BTreeMap::new();
+    let mut attack: BTreeMap<(i64, i64), i32> = BTreeMap::new();
+    let mut sides: BTreeMap<i64, (i32, i32)> = BTreeMap::new();
+    for c in &hulls {
+        let s = classes
+            .entry((c.owner, c.stype))
+            .or_insert_with(|| Service {
+                turn,
+                stype: c.stype,
+                ..Service::default()
+            });
+        s.fought += 1;
+        if destroyed.contains(&c.id) {
+            s.lost += 1;
+        }
+        *attack.entry((c.owner, c.stype)).or_default() += c.attack(rules);
+        let side = sides.entry(c.owner).or_default();
+        side.0 += 1;
+        side.1 += c.attack(rules);
+    }
+    classes
+        .into_iter()
+        .map(|((owner, stype), mut s)| {
+            let kills = credit.get(&owner).copied().unwrap_or(0.0);
+            let (count, total) = sides[&owner];
+            s.kills = if total > 0 {
+                kills * attack[&(owner, stype)] as f64 / total as f64
+            } else {
+                kills * s.fought as f64 / count as f64
+            };
+            s
+        })
+        .collect()
+}
+
+/// A report of every ship class's service record, by empire.
+pub fn report(records: &[ClassRecord]) -> String {
+    if records.is_empty() {
+        return "No ship classes have been designed.\n".to_string();
+    }
+    let mut s = String::new();
+    let mut empire = None;
+    for r in records {
+        if empire != Some(r.empire_name.as_str()) {
+            empire = Some(r.empire_name.as_str());
+            let name = if r.empire_name.is_empty() {
+                "Any empire"
+            } else {
+                r.empire_name.as_str()
+            };
+            s.push_str(&format!("{}\n", name));
+        }
+        s.push_str(&format!(
+            "  {} ({}, {} EP): {} built, {} active, {} lost in battle",
+            r.class, r.hull, r.cost, r.built, r.active, r.lost
+        ));
+        if r.fought == 0 {
+            s.push_str("; never in battle\n");
+            continue;
+        }
+        s.push_str(&format!(
+            "; {:.1} kills in {} engagements",
+            r.kills, r.fought
+        ));
+        match r.ratio() {
+            Some(ratio) => s.push_str(&format!(", {:.2} per loss\n", ratio)),
+            None => s.push_str(", no losses\n"),
+        }
+    }
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{from_battle, report, ClassRecord, Service};
+    use crate::campaign::combat::Combatant;
+    use crate::campaign::ruleset::SecondEdition;
+
+    fn ship(id: i64, owner: i64, stype: i64, atk: i32) -> Combatant {
+        Combatant {
+            id,
+            owner,
+            owner_name: String::new(),
+            class: "Avenger".to_string(),
+            stype,
+            atk,
+            def: 2,
+            crip: false,
+        }
+    }
+
+    #[test]
+    fn battle() {
+        let ships = vec![
+            ship(1, 1, 7, 3),
+            ship(2, 1, 8, 1),
+            ship(3, 2, 9, 2),
+            ship(4, 2, 9, 2),
+            ship(-1, 2, 0, 1),
+        ];
+        let v = from_battle(3, &ships, &[2, 3, 4, -1], &SecondEdition);
+        // Flights neither count as ships nor as kills.
+        assert_eq!(3, v.len());
+        assert_eq!(
+            Service {
+                turn: 3,
+                stype: 7,
+                fought: 1,
+                kills: 1.5,
+                ..Service::default()
+            },
+            v[0]
+        );
+        assert_eq!((1, 0.5), (v[1].lost, v[1].kills));
+        assert_eq!((2, 2, 1.0), (v[2].fought, v[2].lost, v[2].kills));
+
+        // A side with no attack left shares its kills by ships.
+        let ships = vec![ship(1, 1, 7, 0), ship(2, 1, 8, 0), ship(3, 2, 9, 2)];
+        let v = from_battle(3, &ships, &[3], &SecondEdition);
+        assert_eq!(
+            vec![0.5, 0.5, 0.0],
+            v.iter().map(|s| s.kills).collect::<Vec<f64>>()
+        );
+    }
+
+    #[test]
+    fn records() {
+        let mut r = ClassRecord {
+            stype: 7,
+            class: "Avenger".to_string(),
+            hull: "CA".to_string(),
+            cost: 10,
+            empire_name: "Senorian".to_string(),
+            built: 4,
+            active: 3,
+            fought: 6,
+            lost: 1,
+            kills: 2.5,
+        };
+        assert_eq!(Some(2.5), r.ratio());
+        assert_eq!(
+            "Senorian\tAvenger\tCA\t10\t4\t3\t6\t1\t2.5\t2.50",
+            r.as_row()
+        );
+        let mut idle = r.clone();
+        idle.class = "Kestrel".to_string();
+        idle.fought = 0;
+        idle.lost = 0;
+        idle.kills = 0.0;
+        assert_eq!(None, idle.ratio());
+        assert_eq!(
+            "Senorian\n  \
+            Avenger (CA, 10 EP): 4 built, 3 active, 1 lost in battle; \
+            2.5 kills in 6 engagements, 2.50 per loss\n  \
+            Kestrel (CA, 10 EP): 4 built, 3 active, 0 lost in battle; never in battle\n",
+            report(&[r.clone(), idle])
+        );
+        r.lost = 0;
+        assert!(report(&[r]).ends_with(", no losses\n"));
+        assert_eq!("No ship classes have been designed.\n", report(&[]));
+    }
+}
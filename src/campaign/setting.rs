@@ -0,0 +1,278 @@
+// Copyright 2022 David Terhune
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Setting modules: the unit rosters and planet types of a VBAM setting.
+//!
+//! A module is a JSON file naming the setting and listing any of its ground
+//! unit types, hull classes and planet types. See docs/decisions/0008.
+//!
+//! ```json
+//! {
+//!   "name": "Standard",
+//!   "ground_types": [{"name": "Militia", "abbr": "MIL", "cost": 2, "atk": 4, "def": 4}],
+//!   "hulls": [{"name": "Cruiser", "size": 5, "maint": 2, "tech": 0}],
+//!   "planet_types": [{"name": "HW", "description": "Homeworld", "max_cap": 15, "mor_mod": 0}]
+//! }
+//! ```
+//!
+//! A hull's tech is the tech level needed to build on it, and may be left
+//! out to use the standard requirement for its size. A list left out keeps
+//! what the campaign has.
+
+use serde_json::Value;
+use std::fs;
+
+use super::system::PlanetType;
+use super::tech;
+use super::unit::{GroundType, Hull};
+
+/// The ground units, hulls and planet types of a setting.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Setting {
+    pub name: String,
+    /// File the module was read from, empty for the standard setting.
+    pub file: String,
+    pub ground_types: Vec<GroundType>,
+    pub hulls: Vec<Hull>,
+    pub planet_types: Vec<PlanetType>,
+}
+
+/// The standard VBAM setting new campaigns start with.
+pub fn standard() -> Setting {
+    let ground = |name: &str, abbr: &str, cost, atk, def| GroundType {
+        id: 0,
+        name: name.to_string(),
+        abbr: abbr.to_string(),
+        cost,
+        atk,
+        def,
+    };
+    let hull = |name: &str, size, maint| Hull {
+        name: name.to_string(),
+        size,
+        maint,
+        tech: tech::hull_tech(size),
+    };
+    let planet = |name: &str, description: &str, max_cap, mor_mod| PlanetType {
+        name: name.to_string(),
+        description: description.to_string(),
+        max_cap,
+        mor_mod,
+    };
+    Setting {
+        name: "Standard".to_string(),
+        file: String::new(),
+        ground_types: vec![
+            ground("Militia", "MIL", 2, 4, 4),
+            ground("Light Infantry", "LI", 3, 4, 4),
+            ground("Mobile Infantry", "MI", 4, 4, 8),
+            ground("Light Armor", "LA", 4, 8, 4),
+            ground("Mech Infantry", "MECH", 8, 8, 8),
+            ground("Marines", "MAR", 6, 4, 8),
+        ],
+        hulls: vec![
+            hull("Corvette", 1, 1),
+            hull("Frigate", 2, 1),
+            hull("Destroyer", 3, 1),
+            hull("Light Cruiser", 4, 2),
+            hull("Cruiser", 5, 2),
+            hull("Heavy Cruiser", 6, 3),
+            hull("Battlecruiser", 7, 3),
+            hull("Battleship", 8, 4),
+            hull("Dreadnought", 10, 5),
+        ],
+        planet_types: vec![
+            planet("HW", "Homeworld of a species", 15, 0),
+            planet("Adaptable", "Habitable with some adaptation", 10, 0),
+            planet("Barren", "Airless or lifeless world", 6, 0),
+            planet("Hostile", "Toxic or crushing environment", 4, -1),
+        ],
+    }
+}
+
+/// Read a setting module from a file.
+pub fn read_from_file(file: &str) -> Result<Setting, String> {
+    let text = fs::read_to_string(file).map_err(|e| format!("{}: {}", file, e))?;
+    let mut setting = from_json(text.as_str()).map_err(|e| format!("{}: {}", file, e))?;
+    setting.file = file.to_string();
+    Ok(setting)
+}
+
+/// Read a setting module from its JSON text, checking that every entry is
+/// complete and that no list names the same thing twice.
+pub fn from_json(text: &str) -> Result<Setting, String> {
+    let module: Value = serde_json::from_str(text).map_err(|e| e.to_string())?;
+    let name = match module.get("name").and_then(|n| n.as_str()) {
+        Some(n) if !n.trim().is_empty() => n.trim().to_string(),
+        _ => return Err("The module has no setting name".to_string()),
+    };
+
+    let mut setting = Setting {
+        name,
+        ..Default::default()
+    };
+    for item in entries(&module, "ground_types")? {
+        setting.ground_types.push(GroundType {
+            id: 0,
+            name: text_field(item, "name")?,
+            abbr: text_field(item, "abbr")?,
+            cost: number(item, "cost", 0)?,
+            atk: number(item, "atk", 0)?,
+            def: number(item, "def", 0)?,
+        });
+    }
+    for item in entries(&module, "hulls")? {
+        let size = number(item, "size", 1)?;
+        setting.hulls.push(Hull {
+            name: text_field(item, "name")?,
+            size,
+            maint: number(item, "maint", 0)?,
+            tech: match item.get("tech") {
+                Some(_) => number(item, "tech", 0)?,
+                None => tech::hull_tech(size),
+            },
+        });
+    }
+    for item in entries(&module, "planet_types")? {
+        setting.planet_types.push(PlanetType {
+            name: text_field(item, "name")?,
+            description: match item.get("description").and_then(|d| d.as_str()) {
+                Some(d) => d.to_string(),
+                None => String::new(),
+            },
+            max_cap: number(item, "max_cap", 0)?,
+            mor_mod: number(item, "mor_mod", i32::MIN)?,
+        });
+    }
+
+    unique("ground type", setting.ground_types.iter().map(|g| &g.name))?;
+    unique("hull", setting.hulls.iter().map(|h| &h.name))?;
+    unique("planet type", setting.planet_types.iter().map(|p| &p.name))?;
+    Ok(setting)
+}
+
+// The entries of one of a module's lists, none if it is left out.
+fn entries<'a>(module: &'a Value, key: &str) -> Result<&'a [Value], String> {
+    match module.get(key) {
+        None => Ok(&[]),
+        Some(v) => match v.as_array() {
+            Some(a) => Ok(a.as_slice()),
+            None => Err(format!("{} is not a list", key)),
+        },
+    }
+}
+
+// A text field of an entry, which must not be blank.
+fn text_field(item: &Value, key: &str) -> Result<String, String> {
+    match item.get(key).and_then(|v| v.as_str()) {
+        Some(s) if !s.trim().is_empty() => Ok(s.trim().to_string()),
+        _ => Err(format!("An entry has no {}: {}", key, item)),
+    }
+}
+
+// A whole number field of an entry, no less than the given least value.
+fn number(item: &Value, key: &str, least: i32) -> Result<i32, String> {
+    match item.get(key).and_then(|v| v.as_i64()) {
+        Some(n) if n >= least as i64 && n <= i32::MAX as i64 => Ok(n as i32),
+        Some(_) => Err(format!("{} is out of range: {}", key, item)),
+        None => Err(format!("An entry has no {}: {}", key, item)),
+    }
+}
+
+// Refuse a list that names the same thing twice.
+fn unique<'a>(what: &str, names: impl Iterator<Item = &'a String>) -> Result<(), String> {
+    let mut seen: Vec<&String> = Vec::new();
+    for name in names {
+        if seen.contains(&name) {
+            return Err(format!("The {} {} is listed twice", what, name));
+        }
+        seen.push(name);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{from_json, read_from_file, standard};
+    use std::fs;
+
+    #[test]
+    fn standard_roster() {
+        let s = standard();
+        assert_eq!(6, s.ground_types.len());
+        assert_eq!(9, s.hulls.len());
+        assert_eq!(4, s.planet_types.len());
+        let dreadnought = s.hulls.iter().find(|h| h.name == "Dreadnought").unwrap();
+        assert_eq!(3, dreadnought.tech);
+    }
+
+    #[test]
+    fn read_module() {
+        let s = from_json(
+            r#"{
+                "name": "Fading Suns",
+                "ground_types": [
+                    {"name": "Legion", "abbr": "LEG", "cost": 5, "atk": 6, "def": 6}
+                ],
+                "hulls": [
+                    {"name": "Escort", "size": 2, "maint": 1},
+                    {"name": "Carrack", "size": 9, "maint": 4, "tech": 1}
+                ]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!("Fading Suns", s.name);
+        assert_eq!("LEG", s.ground_types[0].abbr);
+        assert_eq!(0, s.hulls[0].tech);
+        assert_eq!(1, s.hulls[1].tech);
+        assert!(s.planet_types.is_empty());
+    }
+
+    #[test]
+    fn bad_modules() {
+        assert!(from_json("not json").is_err());
+        assert!(from_json(r#"{"hulls": []}"#).is_err());
+        assert!(from_json(r#"{"name": "X", "hulls": {}}"#).is_err());
+        assert!(from_json(r#"{"name": "X", "hulls": [{"name": "Escort", "maint": 1}]}"#).is_err());
+        assert!(from_json(
+            r#"{"name": "X", "hulls": [{"name": "Escort", "size": 0, "maint": 1}]}"#
+        )
+        .is_err());
+        let twice = r#"{"name": "X", "planet_types": [
+            {"name": "HW", "max_cap": 15, "mor_mod": 0},
+            {"name": "HW", "max_cap": 10, "mor_mod": 0}
+        ]}"#;
+        assert_eq!(
+            "The planet type HW is listed twice",
+            from_json(twice).unwrap_err()
+        );
+    }
+
+    #[test]
+    fn read_file() {
+        let path =
+            std::env::temp_dir().join(format!("vbamcma-setting-{}.json", std::process::id()));
+        let file = path.to_str().unwrap();
+        fs::write(&path, r#"{"name": "Frontier"}"#).unwrap();
+        let s = read_from_file(file).unwrap();
+        assert_eq!(("Frontier", file), (s.name.as_str(), s.file.as_str()));
+
+        // An edit that breaks the module is refused when read again.
+        fs::write(&path, r#"{"name": ""}"#).unwrap();
+        assert!(read_from_file(file).is_err());
+        fs::remove_file(&path).unwrap();
+        assert!(read_from_file(file).is_err());
+    }
+}
@@ -0,0 +1,129 @@
+// Copyright 2022 David Terhune
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Work on ships at their empire's systems: repair, mothballing and
+//! scrapping.
+//!
+//! Repairs are ordered and paid for like builds, and need a shipyard: one
+//! of the empire's unblockaded systems with industry. They join the
+//! construction queue and are carried out in the construction phase as the
+//! system's repair capacity allows. Mothballing, reactivating and scrapping
+//! take effect at once at any of the empire's systems; a scrapped ship
+//! returns part of its cost.
+
+use super::repair;
+use super::system::System;
+use super::unit::ShipLocation;
+
+/// Repair a crippled ship.
+pub const REPAIR: &str = "Repair";
+
+/// Lay a ship up, cutting its maintenance.
+pub const MOTHBALL: &str = "Mothball";
+
+/// Return a mothballed ship to service.
+pub const ACTIVATE: &str = "Activate";
+
+/// Break a ship up, recovering part of its cost.
+pub const SCRAP: &str = "Scrap";
+
+/// What can be done to a ship.
+pub const ACTIONS: [&str; 4] = [REPAIR, MOTHBALL, ACTIVATE, SCRAP];
+
+/// Percentage of a ship's cost recovered by scrapping it.
+pub const SCRAP_PERCENT: i32 = 25;
+
+/// Whether a system can repair an empire's ships: the empire owns it, it is
+/// not blockaded, and it has industry.
+pub fn is_shipyard(sys: &System, empire: i64) -> bool {
+    sys.owner == empire && !sys.blockaded && sys.ind > 0
+}
+
+/// EP recovered by scrapping a ship.
+pub fn scrap_value(ship: &ShipLocation) -> i32 {
+    ship.cost * SCRAP_PERCENT / 100
+}
+
+/// Check an action can be taken on a ship at the system it is in, returning
+/// what it costs.
+pub fn check(action: &str, ship: &ShipLocation, sys: &System) -> Result<i32, String> {
+    let name = format!("{} #{}", ship.class, ship.id);
+    if sys.id != ship.location || sys.owner != ship.owner {
+        return Err(format!("{} is not at one of its empire's systems", name));
+    }
+    match action {
+        REPAIR if ship.moth => Err(format!("{} is mothballed", name)),
+        REPAIR if !ship.crip => Err(format!("{} is not crippled", name)),
+        REPAIR if !is_shipyard(sys, ship.owner) => {
+            Err(format!("{} has no shipyard to repair {}", sys.name, name))
+        }
+        REPAIR => Ok(repair::cost(ship.cost)),
+        MOTHBALL if ship.moth => Err(format!("{} is already mothballed", name)),
+        ACTIVATE if !ship.moth => Err(format!("{} is not mothballed", name)),
+        MOTHBALL | ACTIVATE | SCRAP => Ok(0),
+        _ => Err(format!("Unknown ship action {}", action)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check, is_shipyard, scrap_value, ACTIVATE, MOTHBALL, REPAIR, SCRAP};
+    use crate::campaign::system::tests::systems;
+    use crate::campaign::unit::ShipLocation;
+
+    fn ship(crip: bool, moth: bool) -> ShipLocation {
+        ShipLocation {
+            id: 12,
+            class: "Avenger".to_string(),
+            fleet_name: "Home Fleet".to_string(),
+            location_name: "Senor Prime".to_string(),
+            crip,
+            moth,
+            owner: 1,
+            location: 1,
+            cost: 10,
+            cap: 0,
+        }
+    }
+
+    #[test]
+    fn actions() {
+        let mut sys = systems()[0].clone();
+        sys.id = 1;
+        sys.owner = 1;
+        sys.ind = 2;
+        assert!(is_shipyard(&sys, 1));
+        assert_eq!(Ok(5), check(REPAIR, &ship(true, false), &sys));
+        assert!(check(REPAIR, &ship(false, false), &sys).is_err());
+        assert!(check(REPAIR, &ship(true, true), &sys).is_err());
+        assert_eq!(Ok(0), check(MOTHBALL, &ship(true, false), &sys));
+        assert!(check(MOTHBALL, &ship(false, true), &sys).is_err());
+        assert_eq!(Ok(0), check(ACTIVATE, &ship(false, true), &sys));
+        assert!(check(ACTIVATE, &ship(false, false), &sys).is_err());
+        assert_eq!(Ok(0), check(SCRAP, &ship(false, false), &sys));
+        assert_eq!(2, scrap_value(&ship(false, false)));
+        assert!(check("Refit", &ship(false, false), &sys).is_err());
+
+        // Only a shipyard repairs.
+        sys.blockaded = true;
+        assert!(!is_shipyard(&sys, 1));
+        assert!(check(REPAIR, &ship(true, false), &sys).is_err());
+        assert!(check(MOTHBALL, &ship(false, false), &sys).is_ok());
+
+        // Nothing is done away from the empire's systems.
+        sys.owner = 2;
+        assert!(check(SCRAP, &ship(false, false), &sys).is_err());
+    }
+}
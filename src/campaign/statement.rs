@@ -0,0 +1,147 @@
+// Copyright 2022 David Terhune
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! An empire's economy at a glance, for checking it between turns.
+//!
+//! The database totals where the empire stands: its treasury and tech level,
+//! the systems it holds, what it has under construction and what it is
+//! spending on research. To that is added the income the economic phase
+//! will bring, worked out by the same rules but without touching the
+//! treasury, and the income of each of its systems.
+
+use super::economy::{EmpireIncome, IncomeBreakdown};
+use super::locale::Locale;
+use super::ruleset::Ruleset;
+use super::system::System;
+
+/// Where an empire stands, as totalled by the database.
+#[derive(sqlx::FromRow, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Standing {
+    pub empire: i64,
+    pub name: String,
+    pub treasury: i32,
+    pub tech: i32,
+    pub systems: i32,
+    pub builds: i32,
+    pub building: i32,
+    pub research: i32,
+}
+
+/// An empire's economic statement: where it stands, the income it can
+/// expect this turn and where that income comes from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Statement {
+    pub standing: Standing,
+    pub income: EmpireIncome,
+    pub systems: Vec<(String, IncomeBreakdown)>,
+}
+
+impl Statement {
+    /// Draw up the statement from the empire's standing, its expected
+    /// income and the systems it holds.
+    pub fn new(
+        standing: Standing,
+        income: EmpireIncome,
+        systems: &[System],
+        rules: &dyn Ruleset,
+    ) -> Self {
+        let mut systems: Vec<(String, IncomeBreakdown)> = systems
+            .iter()
+            .map(|s| (s.name.clone(), IncomeBreakdown::new(s, rules)))
+            .collect();
+        systems.sort_by(|a, b| a.0.cmp(&b.0));
+        Self {
+            standing,
+            income,
+            systems,
+        }
+    }
+
+    /// The treasury once this turn's income is in. Research and builds are
+    /// paid for when they are ordered, so are already out of the treasury.
+    pub fn projected(&self) -> i32 {
+        self.standing.treasury + self.income.net()
+    }
+
+    /// Convert to rows of tab-separated fields: the empire's standing, its
+    /// income, then the income of each of its systems. Sums of EP are
+    /// written in the given locale.
+    pub fn as_rows(&self, locale: &Locale) -> Vec<String> {
+        let s = &self.standing;
+        let mut v = vec![
+            format!("Treasury:\t{}", locale.ep(s.treasury)),
+            format!("Tech level:\t{}", s.tech),
+            format!("Systems:\t{}", s.systems),
+            format!(
+                "Construction:\t{} in {} builds",
+                locale.ep(s.building),
+                s.builds
+            ),
+            format!("Research:\t{}", locale.ep(s.research)),
+            String::new(),
+        ];
+        v.extend(self.income.to_string().lines().map(String::from));
+        v.push(format!("Next treasury:\t{}", locale.ep(self.projected())));
+        v.push(String::new());
+        v.push("System\tBase\tMorale\tBlockade\tPolicy\tTrade\tTotal".to_string());
+        v.extend(
+            self.systems
+                .iter()
+                .map(|(name, b)| format!("{}\t{}", name, b.as_row())),
+        );
+        v
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Standing, Statement};
+    use crate::campaign::economy::EmpireIncome;
+    use crate::campaign::locale::{self, LOCALES};
+    use crate::campaign::ruleset::SecondEdition;
+    use crate::campaign::system::tests::systems;
+
+    #[test]
+    fn as_rows() {
+        let sys = systems();
+        let standing = Standing {
+            empire: 1,
+            name: "Senorian".to_string(),
+            treasury: 2000,
+            tech: 2,
+            systems: 2,
+            builds: 1,
+            building: 8,
+            research: 5,
+        };
+        let income = EmpireIncome::new(&sys[..2], 4, &SecondEdition);
+        let statement = Statement::new(standing, income.clone(), &sys[..2], &SecondEdition);
+        assert_eq!(2000 + income.net(), statement.projected());
+        // Systems are listed by name.
+        assert_eq!("Senor Prime", statement.systems[0].0);
+
+        let rows = statement.as_rows(&LOCALES[0]);
+        assert_eq!("Treasury:\t2000 EP", rows[0]);
+        assert_eq!("Construction:\t8 EP in 1 builds", rows[3]);
+        assert!(rows.contains(&"Maintenance:\t-4".to_string()));
+        let next = format!("Next treasury:\t{} EP", statement.projected());
+        assert!(rows.contains(&next));
+        assert_eq!(
+            "Treasury:\t2,000 EP",
+            statement.as_rows(locale::for_tag("en-US"))[0]
+        );
+        assert!(rows.last().unwrap().starts_with(&statement.systems[1].0));
+    }
+}
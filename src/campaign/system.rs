@@ -17,6 +17,24 @@
 
 use std::io;
 
+use super::dialect;
+use super::map;
+
+/// Policy of a system that pays no taxes. It yields no income, and its
+/// morale checks gain a point.
+pub const TAX_EXEMPT: &str = "Tax-exempt";
+
+/// Policy of a system held under martial law. Low morale costs it no
+/// income, but its morale checks lose a point.
+pub const MARTIAL_LAW: &str = "Martial law";
+
+/// Policy of a system open to all traders. It yields another quarter of its
+/// income unless blockaded.
+pub const FREE_PORT: &str = "Free port";
+
+/// Policies the GM may place a system under, one at a time.
+pub const POLICIES: [&str; 3] = [TAX_EXEMPT, MARTIAL_LAW, FREE_PORT];
+
 #[allow(unused)]
 #[derive(sqlx::FromRow, Clone, Debug, PartialEq, Eq)]
 pub struct System {
@@ -30,30 +48,98 @@ pub struct System {
     pub ind: i32,
     pub dev: i32,
     pub fails: i32,
+    pub blockaded: bool,
     pub owner: i64,
+    pub x: i32,
+    pub y: i32,
+    pub minor: String,
+    pub contender: i64,
+    pub share: i32,
+    /// One of `POLICIES`, or empty if the system is under none.
+    pub policy: String,
     #[sqlx(default)]
     pub owner_name: String,
+    #[sqlx(default)]
+    pub contender_name: String,
+}
+
+/// What an import does with a system named the same as one already in the
+/// campaign.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Duplicates {
+    /// Import nothing, naming the systems already there.
+    Refuse,
+    /// Leave the system in the campaign as it is.
+    Skip,
+    /// Replace the system with the one imported, unowned and undeveloped.
+    /// It keeps its ID, so the lanes, fleets and facilities there stay.
+    Overwrite,
+    /// Take the imported type and statistics, keeping the owner,
+    /// development and map position.
+    Merge,
+}
+
+/// A change made to many systems at once. What is left as `None`, or a
+/// morale adjustment of zero, is left as it is.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BulkEdit {
+    /// New owner, 0 for none.
+    pub owner: Option<i64>,
+    /// Points added to morale, which stays between zero and the population.
+    pub morale: i32,
+    pub blockaded: Option<bool>,
+}
+
+impl BulkEdit {
+    /// Whether the edit changes nothing.
+    pub fn is_empty(&self) -> bool {
+        self.owner.is_none() && self.morale == 0 && self.blockaded.is_none()
+    }
+
+    /// The system as the edit leaves it.
+    pub fn apply(&self, sys: &System) -> System {
+        System {
+            owner: self.owner.unwrap_or(sys.owner),
+            mor: (sys.mor + self.morale).min(sys.pop).max(0),
+            blockaded: self.blockaded.unwrap_or(sys.blockaded),
+            ..sys.clone()
+        }
+    }
+}
+
+/// A planet type from the reference table, with the rules modifiers that go
+/// with it.
+#[derive(sqlx::FromRow, Clone, Debug, PartialEq, Eq)]
+pub struct PlanetType {
+    pub name: String,
+    pub description: String,
+    pub max_cap: i32,
+    pub mor_mod: i32,
 }
 
 impl System {
-    /// Convert to string as a row of tab-separated fields.
-    pub fn as_row(&self) -> String {
-        format!(
-            "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
-            self.name,
-            self.ptype,
-            self.raw,
-            self.cap,
-            self.pop,
-            self.mor,
-            self.ind,
-            self.dev,
-            self.fails,
-            self.owner_name
-        )
+    /// Check the system against the planet types. Its type must be one of
+    /// them, and its capacity no more than the type allows. Its policy, if
+    /// any, must be one of `POLICIES`.
+    pub fn validate(&self, types: &[PlanetType]) -> Result<(), String> {
+        let ptype = match types.iter().find(|t| t.name == self.ptype) {
+            Some(t) => t,
+            None => return Err(format!("{}: unknown planet type {}", self.name, self.ptype)),
+        };
+        if self.cap > ptype.max_cap {
+            return Err(format!(
+                "{}: CAP {} is more than {} planets allow ({})",
+                self.name, self.cap, ptype.name, ptype.max_cap
+            ));
+        }
+        if !self.policy.is_empty() && !POLICIES.contains(&self.policy.as_str()) {
+            return Err(format!("{}: unknown policy {}", self.name, self.policy));
+        }
+        Ok(())
     }
 
-    /// Read systems from a CSV reader.
+    /// Read systems from a CSV reader. Systems without map coordinates are
+    /// placed on the default grid in the order read.
     pub fn read_csv<R>(mut rdr: csv::Reader<R>) -> Result<Vec<System>, String>
     where
         R: io::Read,
@@ -62,7 +148,7 @@ impl System {
         for result in rdr.records() {
             match result {
                 Ok(rcd) => {
-                    if let Ok(sys) = Self::from_csv(rcd) {
+                    if let Ok(sys) = Self::from_csv(rcd, v.len()) {
                         v.push(sys)
                     }
                 }
@@ -73,8 +159,8 @@ impl System {
         Ok(v)
     }
 
-    // Create a new system from a CSV record
-    fn from_csv(rcd: csv::StringRecord) -> Result<System, csv::Error> {
+    // Create a new system from a CSV record, the nth read.
+    fn from_csv(rcd: csv::StringRecord, n: usize) -> Result<System, csv::Error> {
         let err = csv::Error::from(io::Error::from(io::ErrorKind::InvalidInput));
         let name = match rcd.get(0) {
             Some(n) => n,
@@ -120,11 +206,27 @@ impl System {
             None => return Err(err),
         };
 
-        Ok(Self::new(name, ptype, raw, cap, pop, mor, ind))
+        let mut sys = Self::new(name, ptype, raw, cap, pop, mor, ind);
+        (sys.x, sys.y) = match (rcd.get(7), rcd.get(8)) {
+            (Some(x), Some(y)) => match (x.parse(), y.parse()) {
+                (Ok(x), Ok(y)) => (x, y),
+                _ => return Err(err),
+            },
+            _ => map::grid_position(n),
+        };
+        Ok(sys)
     }
 
-    // Create a new system.
-    fn new(name: &str, ptype: &str, raw: i32, cap: i32, pop: i32, mor: i32, ind: i32) -> System {
+    /// Create a new unowned system.
+    pub fn new(
+        name: &str,
+        ptype: &str,
+        raw: i32,
+        cap: i32,
+        pop: i32,
+        mor: i32,
+        ind: i32,
+    ) -> System {
         Self {
             id: 0,
             name: name.to_string(),
@@ -136,25 +238,84 @@ impl System {
             ind,
             dev: 0,
             fails: 0,
+            blockaded: false,
             owner: 0,
+            x: 0,
+            y: 0,
+            minor: String::new(),
+            contender: 0,
+            share: 100,
+            policy: String::new(),
             owner_name: "None".to_string(),
+            contender_name: String::new(),
         }
     }
 }
 
 /// Load a set of systems from a CSV file. Columns should be in order:
-/// NAME,TYPE,RAW,CAP,POP,MOR,IND
+/// NAME,TYPE,RAW,CAP,POP,MOR,IND, optionally followed by map coordinates X,Y.
+/// Fields may be separated by commas, semicolons or tabs.
 pub fn read_from_csv(file: &str) -> Result<Vec<System>, String> {
-    let r = match csv::Reader::from_path(file) {
+    let r = match dialect::open(file, false) {
         Ok(r) => r,
         Err(e) => return Err(e.to_string()),
     };
     System::read_csv(r)
 }
 
+/// Sort imported systems into those to add and those already in the
+/// campaign to replace, given what to do with duplicates. A file that names
+/// a system twice is refused.
+pub fn sort_imports(
+    imported: Vec<System>,
+    existing: &[System],
+    dup: Duplicates,
+) -> Result<(Vec<System>, Vec<System>), String> {
+    let mut added: Vec<System> = Vec::new();
+    let mut replaced = Vec::new();
+    let mut clashes = Vec::new();
+    for (n, sys) in imported.iter().enumerate() {
+        if imported[..n].iter().any(|s| s.name == sys.name) {
+            return Err(format!("{} appears more than once in the file", sys.name));
+        }
+        let old = match existing.iter().find(|s| s.name == sys.name) {
+            Some(old) => old,
+            None => {
+                added.push(sys.clone());
+                continue;
+            }
+        };
+        match dup {
+            Duplicates::Refuse => clashes.push(sys.name.clone()),
+            Duplicates::Skip => (),
+            Duplicates::Overwrite => replaced.push(System {
+                id: old.id,
+                ..sys.clone()
+            }),
+            Duplicates::Merge => replaced.push(System {
+                ptype: sys.ptype.clone(),
+                raw: sys.raw,
+                cap: sys.cap,
+                pop: sys.pop,
+                mor: sys.mor,
+                ind: sys.ind,
+                ..old.clone()
+            }),
+        }
+    }
+    if !clashes.is_empty() {
+        return Err(format!(
+            "Already in the campaign: {}. Skip, overwrite or merge them.",
+            clashes.join(", ")
+        ));
+    }
+    Ok((added, replaced))
+}
+
 #[cfg(test)]
 pub mod tests {
-    use crate::campaign::system::System;
+    use crate::campaign::map;
+    use crate::campaign::system::{sort_imports, BulkEdit, Duplicates, PlanetType, System};
     use csv::Reader;
 
     const SYSTEM_IMPORT: &[u8] = "NAME,TYPE,RAW,CAP,POP,MOR,IND\n\
@@ -170,9 +331,38 @@ pub mod tests {
         sys.push(System::new("Vadurrinia", "Adaptable", 3, 8, 4, 3, 3));
         sys.push(System::new("Zev'rch", "Barren", 2, 6, 3, 2, 2));
         sys.push(System::new("Tibron", "Barren", 4, 6, 3, 2, 3));
+        for (n, s) in sys.iter_mut().enumerate() {
+            (s.x, s.y) = map::grid_position(n);
+        }
         sys
     }
 
+    #[test]
+    fn bulk_edit() {
+        let sys = systems()[0].clone(); // population 10, morale 8
+        assert!(BulkEdit::default().is_empty());
+        assert_eq!(sys, BulkEdit::default().apply(&sys));
+
+        let edit = BulkEdit {
+            owner: Some(2),
+            morale: 5,
+            blockaded: Some(true),
+        };
+        assert!(!edit.is_empty());
+        let after = edit.apply(&sys);
+        assert_eq!(
+            (2, sys.pop, true),
+            (after.owner, after.mor, after.blockaded)
+        );
+
+        let edit = BulkEdit {
+            morale: -20,
+            ..Default::default()
+        };
+        assert_eq!(0, edit.apply(&sys).mor);
+        assert_eq!(sys.owner, edit.apply(&sys).owner);
+    }
+
     #[test]
     fn deserialize() {
         let exp = systems();
@@ -183,4 +373,76 @@ pub mod tests {
             assert!(exp.contains(&sys));
         }
     }
+
+    #[test]
+    fn coordinates() {
+        let csv = "NAME,TYPE,RAW,CAP,POP,MOR,IND,X,Y\n\
+            Senor Prime,HW,5,12,10,8,10,-30,45\n\
+            Tibron,Barren,4,6,3,2,3,x,45\n"
+            .as_bytes();
+        let act = System::read_csv(Reader::from_reader(csv)).unwrap();
+        assert_eq!(1, act.len());
+        assert_eq!((-30, 45), (act[0].x, act[0].y));
+    }
+
+    #[test]
+    fn duplicates() {
+        let mut existing = systems();
+        for (n, s) in existing.iter_mut().enumerate() {
+            s.id = n as i64 + 1;
+        }
+        existing[3].owner = 1;
+        existing[3].dev = 2;
+        let mut tibron = System::new("Tibron", "Barren", 5, 6, 4, 3, 4);
+        let imported = vec![System::new("Ixtli", "Poor", 1, 2, 1, 1, 1), tibron.clone()];
+
+        let err = sort_imports(imported.clone(), &existing, Duplicates::Refuse).unwrap_err();
+        assert!(err.starts_with("Already in the campaign: Tibron."));
+        let (added, replaced) =
+            sort_imports(imported.clone(), &existing, Duplicates::Skip).unwrap();
+        assert_eq!(
+            vec!["Ixtli"],
+            added.iter().map(|s| &s.name).collect::<Vec<_>>()
+        );
+        assert!(replaced.is_empty());
+
+        let (_, replaced) =
+            sort_imports(imported.clone(), &existing, Duplicates::Overwrite).unwrap();
+        tibron.id = 4;
+        assert_eq!(vec![tibron], replaced);
+        let (_, replaced) = sort_imports(imported.clone(), &existing, Duplicates::Merge).unwrap();
+        assert_eq!(
+            (4, 1, 2, 5),
+            (
+                replaced[0].id,
+                replaced[0].owner,
+                replaced[0].dev,
+                replaced[0].raw
+            )
+        );
+        assert_eq!(
+            (existing[3].x, existing[3].y),
+            (replaced[0].x, replaced[0].y)
+        );
+
+        let twice = vec![imported[0].clone(), imported[0].clone()];
+        assert!(sort_imports(twice, &[], Duplicates::Skip)
+            .unwrap_err()
+            .contains("more than once"));
+    }
+
+    #[test]
+    fn validate() {
+        let types = vec![PlanetType {
+            name: "Barren".to_string(),
+            description: String::new(),
+            max_cap: 6,
+            mor_mod: 0,
+        }];
+        let mut sys = systems()[2].clone();
+        assert!(sys.validate(&types).is_ok());
+        sys.cap = 7;
+        assert!(sys.validate(&types).is_err());
+        assert!(systems()[0].validate(&types).is_err());
+    }
 }
@@ -0,0 +1,121 @@
+// Copyright 2022 David Terhune
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tech advancement.
+
+use super::unit::Hull;
+
+/// Research points needed per tech level: reaching level N + 1 from level N
+/// takes this times N + 1.
+pub const LEVEL_COST: i32 = 10;
+
+/// Most tech levels that can discount a ship's cost.
+pub const MAX_DISCOUNT: i32 = 3;
+
+/// An empire's research standing for a turn: its tech level, what it is
+/// spending this turn, and the points carried toward the next level.
+#[derive(sqlx::FromRow, Clone, Debug, PartialEq, Eq)]
+pub struct TechStatus {
+    pub empire: i64,
+    pub empire_name: String,
+    pub tech: i32,
+    pub spending: i32,
+    pub points: i32,
+}
+
+impl TechStatus {
+    /// Convert to string as a row of tab-separated fields.
+    pub fn as_row(&self) -> String {
+        format!(
+            "{}\t{}\t{}\t{}\t{}",
+            self.empire_name,
+            self.tech,
+            self.spending,
+            self.points,
+            level_cost(self.tech)
+        )
+    }
+}
+
+/// Research points needed to advance from a tech level.
+pub fn level_cost(tech: i32) -> i32 {
+    LEVEL_COST * (tech.max(0) + 1)
+}
+
+/// Whether research succeeds this turn. An empire whose points cover the cost
+/// always advances; otherwise it advances if a d10 roll is no more than
+/// ten times its fraction of the cost.
+pub fn breakthrough(points: i32, cost: i32, roll: i32) -> bool {
+    points >= cost || roll * cost <= 10 * points
+}
+
+/// Seed for an empire's research roll on a turn, so a turn resolves the same
+/// way however often it is replayed.
+pub fn seed(turn: i32, empire: i64) -> u64 {
+    ((turn as u64) << 32) ^ (empire as u64)
+}
+
+/// Standard tech level needed to build on a hull of a size, for settings
+/// that don't give one. Hulls up to size 5 are always available, and each
+/// two points of size beyond that need another level.
+pub fn hull_tech(size: i32) -> i32 {
+    (size - 4).max(0) / 2
+}
+
+/// Cost of a ship of the given base cost for an empire of the given tech
+/// level. Each level above the hull's requirement takes 10% off, rounded up,
+/// up to `MAX_DISCOUNT` levels.
+pub fn ship_cost(base: i32, hull: &Hull, tech: i32) -> i32 {
+    let discount = (tech - hull.tech).clamp(0, MAX_DISCOUNT);
+    (base * (10 - discount) + 9) / 10
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{breakthrough, hull_tech, level_cost, ship_cost};
+    use crate::campaign::unit::Hull;
+
+    fn hull(size: i32) -> Hull {
+        Hull {
+            name: String::new(),
+            size,
+            maint: 1,
+            tech: hull_tech(size),
+        }
+    }
+
+    #[test]
+    fn levels() {
+        assert_eq!(10, level_cost(0));
+        assert_eq!(30, level_cost(2));
+        assert!(breakthrough(10, 10, 10));
+        assert!(breakthrough(5, 10, 5));
+        assert!(!breakthrough(5, 10, 6));
+        assert!(!breakthrough(0, 10, 1));
+    }
+
+    #[test]
+    fn hulls() {
+        assert_eq!(0, hull_tech(1));
+        assert_eq!(0, hull_tech(5));
+        assert_eq!(1, hull_tech(6));
+        assert_eq!(3, hull_tech(10));
+
+        assert_eq!(10, ship_cost(10, &hull(6), 1));
+        assert_eq!(9, ship_cost(10, &hull(6), 2));
+        assert_eq!(7, ship_cost(10, &hull(1), 5));
+        assert_eq!(8, ship_cost(11, &hull(1), 3));
+    }
+}
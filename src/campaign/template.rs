@@ -0,0 +1,127 @@
+// Copyright 2022 David Terhune
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Report templates.
+//!
+//! A template is plain text in which each `{{name}}` placeholder is replaced
+//! by a value the report provides. Campaigns may keep their own templates in
+//! the templates folder of their attached files; reports without one use the
+//! defaults here. See docs/decisions/0005 for the reasoning.
+
+use std::collections::BTreeMap;
+
+/// Folder of the attached files holding a campaign's templates.
+pub const FOLDER: &str = "templates";
+
+/// File name of the player packet template.
+pub const PLAYER_PACKET: &str = "player_packet.txt";
+
+/// File name of the GM's turn report template.
+pub const TURN_REPORT: &str = "turn_report.txt";
+
+/// Default player packet. Values: empire, turn, treasury, tech, government,
+/// systems, garrisons, classes and intel. Intel is empty when there is none, and
+/// otherwise starts with a blank line.
+pub const DEFAULT_PLAYER_PACKET: &str = "{{empire}} Player Packet, Turn {{turn}}
+
+Treasury:\t{{treasury}}
+Tech:\t{{tech}}
+Government:\t{{government}}
+
+{{systems}}
+{{garrisons}}
+{{classes}}{{intel}}";
+
+/// Default turn report. Values: turn, report for the whole report, and one
+/// per phase named after it in lower case.
+pub const DEFAULT_TURN_REPORT: &str = "Turn {{turn}}
+
+Income Phase
+{{income}}
+
+Maintenance Phase
+{{maintenance}}
+
+Construction Phase
+{{construction}}
+
+Research Phase
+{{research}}
+
+Intelligence Phase
+{{intelligence}}
+
+Movement Phase
+{{movement}}
+
+Combat Phase
+{{combat}}
+
+Morale Phase
+{{morale}}
+
+Diplomacy Phase
+{{diplomacy}}";
+
+/// Every template with its default, by file name.
+pub const DEFAULTS: [(&str, &str); 2] = [
+    (PLAYER_PACKET, DEFAULT_PLAYER_PACKET),
+    (TURN_REPORT, DEFAULT_TURN_REPORT),
+];
+
+/// Fill in a template. Placeholders without a value are left as written, so
+/// a misspelled name shows up in the report.
+pub fn render(template: &str, values: &BTreeMap<&str, String>) -> String {
+    let mut out = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        match after.find("}}") {
+            Some(end) => {
+                match values.get(after[..end].trim()) {
+                    Some(v) => out.push_str(v),
+                    None => out.push_str(&rest[start..start + end + 4]),
+                }
+                rest = &after[end + 2..];
+            }
+            None => {
+                out.push_str(&rest[start..]);
+                rest = "";
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::render;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn placeholders() {
+        let mut values = BTreeMap::new();
+        values.insert("empire", "Senorian".to_string());
+        values.insert("turn", "3".to_string());
+        assert_eq!(
+            "Senorian orders for turn 3",
+            render("{{empire}} orders for turn {{ turn }}", &values)
+        );
+        assert_eq!("{{emprie}} at {{", render("{{emprie}} at {{", &values));
+        assert_eq!("", render("", &values));
+    }
+}
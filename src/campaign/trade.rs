@@ -0,0 +1,256 @@
+// Copyright 2022 David Terhune
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Trade between empires.
+//!
+//! Empires with a trade agreement trade along a supply line: a chain of
+//! jump lanes through their own systems and unclaimed space. A blockaded
+//! system cuts the line, as does a neutral system, whose natives prey on
+//! passing convoys. While the line holds, each partner earns a share of the
+//! other's productivity in the income phase.
+
+use std::collections::{BTreeMap, VecDeque};
+
+use super::diplomacy::Treaty;
+use super::lane::{self, Lane};
+use super::ownership;
+use super::system::System;
+
+/// Kinds of treaty that open trade between the empires signing them.
+pub const AGREEMENTS: [&str; 2] = ["Trade", "Alliance"];
+
+/// Percentage of a partner's productivity an empire earns in trade.
+pub const TRADE_PERCENT: i32 = 10;
+
+/// The state of trade between two partners.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RouteStatus {
+    /// A supply line joins them, and trade flows.
+    Open,
+    /// Their territory is joined, but blockades or piracy cut every line.
+    Interrupted,
+    /// No supply line joins them.
+    Unconnected,
+}
+
+/// Trade between two empires with an agreement.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Route {
+    pub a: i64,
+    pub b: i64,
+    pub a_name: String,
+    pub b_name: String,
+    pub status: RouteStatus,
+}
+
+impl Route {
+    /// Description of the route for reports.
+    pub fn describe(&self) -> String {
+        let state = match self.status {
+            RouteStatus::Open => "open",
+            RouteStatus::Interrupted => "interrupted by blockade or piracy",
+            RouteStatus::Unconnected => "without a supply line",
+        };
+        format!(
+            "Trade between {} and {} is {}",
+            self.a_name, self.b_name, state
+        )
+    }
+}
+
+/// Whether a treaty opens trade.
+pub fn is_agreement(treaty: &Treaty) -> bool {
+    AGREEMENTS.contains(&treaty.ttype.as_str())
+}
+
+/// The shortest chain of systems joining the two empires' territory by
+/// lanes through systems that pass the test, from a system of one to a
+/// system of the other. Only the partners' systems and unowned ones can be
+/// crossed.
+fn line(
+    a: i64,
+    b: i64,
+    systems: &[System],
+    lanes: &[Lane],
+    open: impl Fn(&System) -> bool,
+) -> Option<Vec<i64>> {
+    let passable: BTreeMap<i64, &System> = systems
+        .iter()
+        .filter(|s| s.owner == a || s.owner == b || s.owner == 0)
+        .filter(|s| open(s))
+        .map(|s| (s.id, s))
+        .collect();
+    // Each system reached, with the one it was reached from.
+    let mut came_from: BTreeMap<i64, Option<i64>> = passable
+        .values()
+        .filter(|s| s.owner == a)
+        .map(|s| (s.id, None))
+        .collect();
+    let mut frontier: VecDeque<i64> = came_from.keys().copied().collect();
+    while let Some(id) = frontier.pop_front() {
+        if passable.get(&id).is_some_and(|s| s.owner == b) {
+            let mut path = vec![id];
+            while let Some(Some(prev)) = came_from.get(path.last().unwrap()) {
+                path.push(*prev);
+            }
+            path.reverse();
+            return Some(path);
+        }
+        for next in lane::neighbors(lanes, id) {
+            if passable.contains_key(&next) && !came_from.contains_key(&next) {
+                came_from.insert(next, Some(id));
+                frontier.push_back(next);
+            }
+        }
+    }
+    None
+}
+
+// Whether a system lets trade through.
+fn clear(sys: &System) -> bool {
+    !sys.blockaded && !ownership::is_neutral(sys)
+}
+
+/// The trade routes opened by the treaties, one for each pair of partners
+/// however many agreements they have signed.
+pub fn routes(treaties: &[Treaty], systems: &[System], lanes: &[Lane]) -> Vec<Route> {
+    let mut routes: Vec<Route> = Vec::new();
+    for t in treaties.iter().filter(|t| is_agreement(t)) {
+        if routes
+            .iter()
+            .any(|r| (r.a == t.a && r.b == t.b) || (r.a == t.b && r.b == t.a))
+        {
+            continue;
+        }
+        let status = if line(t.a, t.b, systems, lanes, clear).is_some() {
+            RouteStatus::Open
+        } else if line(t.a, t.b, systems, lanes, |_| true).is_some() {
+            RouteStatus::Interrupted
+        } else {
+            RouteStatus::Unconnected
+        };
+        routes.push(Route {
+            a: t.a,
+            b: t.b,
+            a_name: t.a_name.clone(),
+            b_name: t.b_name.clone(),
+            status,
+        });
+    }
+    routes
+}
+
+/// The systems an open route's trade passes through, from the first
+/// partner's territory to the second's. Empty if trade does not flow.
+pub fn path(route: &Route, systems: &[System], lanes: &[Lane]) -> Vec<i64> {
+    if route.status != RouteStatus::Open {
+        return Vec::new();
+    }
+    line(route.a, route.b, systems, lanes, clear).unwrap_or_default()
+}
+
+/// Trade income each empire earns over the open routes, given every
+/// empire's productivity.
+pub fn income(routes: &[Route], productivity: &BTreeMap<i64, i32>) -> BTreeMap<i64, i32> {
+    let mut earned = BTreeMap::new();
+    for r in routes.iter().filter(|r| r.status == RouteStatus::Open) {
+        for (to, from) in [(r.a, r.b), (r.b, r.a)] {
+            let partner = productivity.get(&from).copied().unwrap_or(0).max(0);
+            *earned.entry(to).or_insert(0) += partner * TRADE_PERCENT / 100;
+        }
+    }
+    earned
+}
+
+/// Report on the state of every trade route.
+pub fn report(routes: &[Route]) -> String {
+    if routes.is_empty() {
+        return String::new();
+    }
+    let mut text = String::from("\nTrade Routes\n");
+    for r in routes {
+        text.push_str(&r.describe());
+        text.push('\n');
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::{income, path, routes, RouteStatus};
+    use crate::campaign::diplomacy::Treaty;
+    use crate::campaign::lane::tests::lanes;
+    use crate::campaign::system::tests::systems;
+    use crate::campaign::system::System;
+
+    // The test systems with their database IDs, Senor Prime held by empire
+    // 1 and Zev'rch by empire 2. Vadurrinia and Tibron lie between them.
+    fn territory() -> Vec<System> {
+        let mut v = systems();
+        for (n, s) in v.iter_mut().enumerate() {
+            s.id = n as i64 + 1;
+        }
+        v[0].owner = 1;
+        v[2].owner = 2;
+        v
+    }
+
+    fn trade(a: i64, b: i64) -> Treaty {
+        Treaty::new(a, b, "Trade", 0, None)
+    }
+
+    #[test]
+    fn supply_lines() {
+        let mut v = territory();
+        let treaties = [trade(1, 2), Treaty::new(2, 1, "Alliance", 0, None)];
+        let r = routes(&treaties, &v, &lanes());
+        assert_eq!(1, r.len());
+        assert_eq!(RouteStatus::Open, r[0].status);
+        assert_eq!(3, path(&r[0], &v, &lanes()).len());
+
+        // One way round is still open.
+        v[1].blockaded = true;
+        let r = routes(&treaties, &v, &lanes());
+        assert_eq!(RouteStatus::Open, r[0].status);
+        assert_eq!(vec![1, 4, 3], path(&r[0], &v, &lanes()));
+
+        // Pirates hold the other.
+        v[3].minor = "Vrokk".to_string();
+        let r = routes(&treaties, &v, &lanes());
+        assert_eq!(RouteStatus::Interrupted, r[0].status);
+        assert!(path(&r[0], &v, &lanes()).is_empty());
+
+        // A third empire's space cannot be crossed.
+        v[1].owner = 3;
+        v[3].owner = 3;
+        let r = routes(&treaties, &v, &lanes());
+        assert_eq!(RouteStatus::Unconnected, r[0].status);
+
+        let treaties = [Treaty::new(1, 2, "Non-Aggression", 0, None)];
+        assert!(routes(&treaties, &v, &lanes()).is_empty());
+    }
+
+    #[test]
+    fn earnings() {
+        let v = territory();
+        let r = routes(&[trade(1, 2)], &v, &lanes());
+        let productivity = BTreeMap::from([(1, 45), (2, 20)]);
+        let earned = income(&r, &productivity);
+        assert_eq!(Some(&2), earned.get(&1));
+        assert_eq!(Some(&4), earned.get(&2));
+    }
+}
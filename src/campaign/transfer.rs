@@ -0,0 +1,174 @@
+// Copyright 2022 David Terhune
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Ships and ship classes sold or given by one empire to another.
+//!
+//! A transfer is agreed during a turn and carried out in the diplomacy
+//! phase. A ship changes hands where it is, joining a fleet of its new
+//! owner's there; a class is copied, so both empires can build it. The
+//! price, if any, is posted to both empires' ledgers.
+
+/// A ship or ship class to change hands in the diplomacy phase of a turn.
+/// Exactly one of the ship and the class is given. A price of zero makes
+/// it a gift.
+#[derive(sqlx::FromRow, Clone, Debug, PartialEq, Eq)]
+pub struct Transfer {
+    pub id: i64,
+    pub turn: i32,
+    pub seller: i64,
+    pub buyer: i64,
+    pub ship: Option<i64>,
+    pub stype: Option<i64>,
+    pub price: i32,
+    #[sqlx(default)]
+    pub seller_name: String,
+    #[sqlx(default)]
+    pub buyer_name: String,
+    #[sqlx(default)]
+    pub item_name: String,
+    /// The empire that holds the ship or class now.
+    #[sqlx(default)]
+    pub owner: i64,
+}
+
+impl Transfer {
+    /// Create a new transfer of a ship.
+    pub fn ship(seller: i64, buyer: i64, turn: i32, ship: i64, price: i32) -> Self {
+        Self::new(seller, buyer, turn, Some(ship), None, price)
+    }
+
+    /// Create a new transfer of a ship class.
+    pub fn class(seller: i64, buyer: i64, turn: i32, stype: i64, price: i32) -> Self {
+        Self::new(seller, buyer, turn, None, Some(stype), price)
+    }
+
+    fn new(
+        seller: i64,
+        buyer: i64,
+        turn: i32,
+        ship: Option<i64>,
+        stype: Option<i64>,
+        price: i32,
+    ) -> Self {
+        Self {
+            id: 0,
+            turn,
+            seller,
+            buyer,
+            ship,
+            stype,
+            price,
+            seller_name: String::new(),
+            buyer_name: String::new(),
+            item_name: String::new(),
+            owner: seller,
+        }
+    }
+
+    /// Whether the transfer is a gift.
+    pub fn is_gift(&self) -> bool {
+        self.price == 0
+    }
+
+    /// Convert to string as a row of tab-separated fields.
+    pub fn as_row(&self) -> String {
+        format!(
+            "{}\t{}\t{}\t{}\t{}",
+            self.turn,
+            self.seller_name,
+            self.buyer_name,
+            self.item_name,
+            if self.is_gift() {
+                "Gift".to_string()
+            } else {
+                self.price.to_string()
+            }
+        )
+    }
+
+    /// Description of the transfer for reports.
+    pub fn describe(&self) -> String {
+        if self.is_gift() {
+            format!(
+                "{} gave {} to {}",
+                self.seller_name, self.item_name, self.buyer_name
+            )
+        } else {
+            format!(
+                "{} sold {} to {} for {} EP",
+                self.seller_name, self.item_name, self.buyer_name, self.price
+            )
+        }
+    }
+
+    /// Check the terms of a transfer when it is agreed.
+    pub fn check_terms(&self) -> Result<(), String> {
+        if self.seller == self.buyer {
+            return Err("An empire cannot transfer to itself".to_string());
+        }
+        if self.price < 0 {
+            return Err("The price cannot be less than zero".to_string());
+        }
+        if self.ship.is_some() == self.stype.is_some() {
+            return Err("A transfer is of one ship or one class".to_string());
+        }
+        Ok(())
+    }
+
+    /// Check that the transfer can still be carried out, given what the
+    /// buyer has in its treasury.
+    pub fn check(&self, treasury: i32) -> Result<(), String> {
+        if self.owner != self.seller {
+            return Err(format!(
+                "{} no longer holds {}",
+                self.seller_name, self.item_name
+            ));
+        }
+        if self.price > treasury {
+            return Err(format!(
+                "{} cannot pay {} EP with {} EP in the treasury",
+                self.buyer_name, self.price, treasury
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Transfer;
+
+    #[test]
+    fn terms() {
+        let mut t = Transfer::ship(1, 2, 3, 4, 10);
+        assert!(t.check_terms().is_ok());
+        assert!(!t.is_gift());
+        t.seller_name = "Senorian".to_string();
+        t.buyer_name = "Human".to_string();
+        t.item_name = "Warhound #4".to_string();
+        assert_eq!("Senorian sold Warhound #4 to Human for 10 EP", t.describe());
+
+        assert!(t.check(10).is_ok());
+        assert!(t.check(9).is_err());
+        t.owner = 2;
+        assert!(t.check(10).is_err());
+
+        let t = Transfer::class(1, 2, 3, 4, 0);
+        assert!(t.is_gift());
+        assert!(t.check(0).is_ok());
+        assert!(Transfer::class(1, 1, 3, 4, 0).check_terms().is_err());
+        assert!(Transfer::class(1, 2, 3, 4, -1).check_terms().is_err());
+    }
+}
@@ -0,0 +1,1755 @@
+// Copyright 2022 David Terhune
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Turn advancement through the VBAM turn sequence.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use futures::future::try_join_all;
+use tokio::task::JoinHandle;
+
+use super::attrition::{self, ATTRITION};
+use super::audit::{self, LogEntry};
+use super::checklist;
+use super::combat::Combatant;
+use super::construction;
+use super::data::{DataResult, DataStore};
+use super::development::{self, Development, Resolution};
+use super::dice::{Dice, Roll};
+use super::economy::EmpireIncome;
+use super::empire::Empire;
+use super::escort::{self, Escort};
+use super::espionage;
+use super::government::Modifiers;
+use super::intel::{IntelItem, NOTICE};
+use super::lane::Lane;
+use super::morale::{in_rebellion, MoraleCheck, CONQUEST_TURNS};
+use super::movement::{self, Transit};
+use super::orders::{self, Order};
+use super::overlay;
+use super::ownership::{self, Side};
+use super::production;
+use super::raid::{self, resolve_raid, STRATEGIC_WARFARE};
+use super::recognition::{Identification, ESPIONAGE};
+use super::repair;
+use super::system::System;
+use super::tech;
+use super::trade::{self, RouteStatus};
+use super::unit::FleetSummary;
+
+/// Phases of the turn sequence.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Phase {
+    Income,
+    Maintenance,
+    Construction,
+    Research,
+    Intelligence,
+    Movement,
+    Combat,
+    Morale,
+    Diplomacy,
+}
+
+/// The phases in the order they are resolved.
+pub const SEQUENCE: [Phase; 9] = [
+    Phase::Income,
+    Phase::Maintenance,
+    Phase::Construction,
+    Phase::Research,
+    Phase::Intelligence,
+    Phase::Movement,
+    Phase::Combat,
+    Phase::Morale,
+    Phase::Diplomacy,
+];
+
+impl Phase {
+    /// Name of the phase's value in the turn report template.
+    pub fn key(&self) -> &'static str {
+        match self {
+            Self::Income => "income",
+            Self::Maintenance => "maintenance",
+            Self::Construction => "construction",
+            Self::Research => "research",
+            Self::Intelligence => "intelligence",
+            Self::Movement => "movement",
+            Self::Combat => "combat",
+            Self::Morale => "morale",
+            Self::Diplomacy => "diplomacy",
+        }
+    }
+
+    /// Short description of what happens during the phase.
+    pub fn summary(&self) -> &'static str {
+        match self {
+            Self::Income => {
+                "Empires collect census, productivity and trade income from the systems they \
+                own, and their share of contested systems. Partners in trade agreements each \
+                earn a tenth of the other's productivity while a supply line joins them \
+                unbroken by blockade or piracy."
+            }
+            Self::Maintenance => {
+                "Empires pay upkeep for their ships and facilities. Crippled ships cost \
+                half and mothballed ships a quarter, rounded up."
+            }
+            Self::Construction => {
+                "New units are purchased and placed at owned systems. Colony ships found \
+                the colonies ordered at unowned systems, and ordered development is attempted \
+                with a d10 roll, each failure counting against the system's morale. Crippled \
+                ships with repairs ordered at their owners' shipyards are repaired, highest \
+                priority first, as far as each system's industry allows."
+            }
+            Self::Research => {
+                "Research spending is added to each empire's points, and a d10 roll decides \
+                whether it reaches the next tech level."
+            }
+            Self::Intelligence => {
+                "Each espionage operation rolls a d10 and adds its intel points, and \
+                succeeds if that beats the target's counter-espionage points plus 5. \
+                Successful operations steal research from more advanced empires, delay the \
+                target's builds, or reveal its fleets and identify their classes. Failed \
+                operations are discovered by their targets."
+            }
+            Self::Movement => {
+                "Fleets move along jump lanes up to their jump allowance, escorts with \
+                the fleets they guard, taking the shortest way to their destinations and \
+                keeping to their courses on later turns until they arrive. A fleet that \
+                enters a system with a hostile fleet in it, or meets one coming the other \
+                way along a lane, is stopped there. Under the attrition rules, ships beyond \
+                their empire's supply then risk being crippled, or lost if already crippled."
+            }
+            Self::Combat => {
+                "Opposing forces in the same system fight, including the rival holders of \
+                contested systems and the natives of neutral ones. Under the strategic warfare \
+                rules, ordered raids strike at enemy shipyards, trade and population."
+            }
+            Self::Morale => {
+                "Owned systems check morale. Blockades, development failures, recent \
+                conquest, contested control, and empire and planet type modifiers lower \
+                it; systems at zero rebel."
+            }
+            Self::Diplomacy => {
+                "Ships and classes sold or given change hands, and treaties that have \
+                reached their expiration turn lapse."
+            }
+        }
+    }
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Income => "Income",
+                Self::Maintenance => "Maintenance",
+                Self::Construction => "Construction",
+                Self::Research => "Research",
+                Self::Intelligence => "Intelligence",
+                Self::Movement => "Movement",
+                Self::Combat => "Combat",
+                Self::Morale => "Morale",
+                Self::Diplomacy => "Diplomacy",
+            }
+        )
+    }
+}
+
+/// What happened during each phase of a turn, and the dice rolled for it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TurnReport {
+    pub turn: i32,
+    pub entries: Vec<(Phase, String)>,
+    pub rolls: Vec<Roll>,
+}
+
+impl TurnReport {
+    /// Create an empty report for a turn.
+    pub fn new(turn: i32) -> Self {
+        Self {
+            turn,
+            entries: Vec::new(),
+            rolls: Vec::new(),
+        }
+    }
+
+    /// Add an entry to the report.
+    pub fn add(&mut self, phase: Phase, text: String) {
+        self.entries.push((phase, text))
+    }
+
+    /// Keep the rolls made with some dice, for the audit log.
+    pub fn add_rolls(&mut self, dice: &Dice) {
+        self.rolls.extend_from_slice(dice.rolls())
+    }
+
+    /// Entries for the audit log: one for each line of the report, filed
+    /// under combat or the turn by its phase, then one for each roll.
+    pub fn log_entries(&self) -> Vec<LogEntry> {
+        self.entries
+            .iter()
+            .map(|(phase, text)| {
+                let category = match phase {
+                    Phase::Combat => audit::COMBAT,
+                    _ => audit::TURN,
+                };
+                LogEntry::new(self.turn, category, format!("{}: {}", phase, text).as_str())
+            })
+            .chain(
+                self.rolls
+                    .iter()
+                    .map(|roll| LogEntry::new(self.turn, audit::ROLL, &roll.to_string())),
+            )
+            .collect()
+    }
+
+    /// Values of the turn report template. See
+    /// `template::DEFAULT_TURN_REPORT` for their names.
+    pub fn values(&self) -> BTreeMap<&'static str, String> {
+        let mut values = BTreeMap::new();
+        values.insert("turn", self.turn.to_string());
+        values.insert("report", self.to_string());
+        for phase in SEQUENCE {
+            values.insert(phase.key(), self.phase_text(phase));
+        }
+        values
+    }
+
+    // Entries of one phase, one indented line each.
+    fn phase_text(&self, phase: Phase) -> String {
+        let lines: Vec<String> = self
+            .entries
+            .iter()
+            .filter(|(p, _)| *p == phase)
+            .map(|(_, text)| format!("  {}", text))
+            .collect();
+        if lines.is_empty() {
+            "  Nothing to report.".to_string()
+        } else {
+            lines.join("\n")
+        }
+    }
+}
+
+impl fmt::Display for TurnReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Turn {}", self.turn)?;
+        for phase in SEQUENCE {
+            write!(f, "\n\n{} Phase\n{}", phase, self.phase_text(phase))?;
+        }
+        Ok(())
+    }
+}
+
+/// Resolve every phase of the given turn and move the campaign to the next.
+/// The dice are seeded from the turn and the campaign's dice seed, so a turn
+/// resolved again from the same state comes out the same.
+///
+/// Every phase is resolved, and the report added to the audit log, in one
+/// unit of work committed once the turn is done, so a turn that fails part
+/// way through leaves the campaign as it was.
+pub async fn advance(data: &DataStore, turn: i32) -> DataResult<TurnReport> {
+    data.begin_work().await?;
+    let resolved = match resolve(data, turn).await {
+        Ok(report) => data.insert_log(&report.log_entries()).await.map(|_| report),
+        Err(e) => Err(e),
+    };
+    match resolved {
+        Ok(report) => {
+            data.commit_work().await?;
+            Ok(report)
+        }
+        Err(e) => {
+            data.rollback_work().await?;
+            Err(e)
+        }
+    }
+}
+
+/// Resolve every phase of the given turn against the store as it stands,
+/// writing each phase's changes as it goes.
+pub(super) async fn resolve(data: &DataStore, turn: i32) -> DataResult<TurnReport> {
+    let mut report = TurnReport::new(turn);
+    let seed = data.get_campaign_options().await?.dice_seed;
+    for phase in SEQUENCE {
+        match phase {
+            Phase::Income => income(data, &mut report).await?,
+            Phase::Maintenance => maintenance(data, &mut report).await?,
+            Phase::Construction => {
+                construction(data, &mut report).await?;
+                development(data, turn, seed, &mut report).await?;
+                repairs(data, &mut report).await?
+            }
+            Phase::Research => research(data, turn, seed, &mut report).await?,
+            Phase::Intelligence => intelligence(data, turn, seed, &mut report).await?,
+            Phase::Combat => {
+                battles(data, turn, &mut report).await?;
+                raids(data, turn, seed, &mut report).await?
+            }
+            Phase::Morale => morale(data, turn, &mut report).await?,
+            Phase::Diplomacy => {
+                transfers(data, turn, &mut report).await?;
+                diplomacy(data, turn, &mut report).await?
+            }
+            Phase::Movement => {
+                movement(data, turn, &mut report).await?;
+                attrition(data, turn, seed, &mut report).await?
+            }
+        }
+    }
+    data.set_turn(turn + 1).await?;
+    Ok(report)
+}
+
+// Report the battles that break out where fleets meet hostile forces: other
+// fleets, a contested system's rival holders, or a neutral system's natives.
+// Where they were fought is kept for the map.
+async fn battles(data: &DataStore, turn: i32, report: &mut TurnReport) -> DataResult<()> {
+    let names: BTreeMap<i64, String> = data
+        .get_empires()
+        .await?
+        .into_iter()
+        .map(|e| (e.id, e.name))
+        .collect();
+    let treaties = data.get_treaties().await?;
+    let at_peace = |a: i64, b: i64| {
+        treaties
+            .iter()
+            .any(|t| (t.a == a && t.b == b) || (t.a == b && t.b == a))
+    };
+    let fleets = data.get_fleet_summaries().await?;
+    for sys in data.get_systems().await? {
+        let present: Vec<i64> = fleets
+            .iter()
+            .filter(|f| f.location == sys.id && f.ships > 0)
+            .map(|f| f.owner)
+            .collect();
+        let sides = ownership::battle(&sys, &present, at_peace);
+        if sides.is_empty() {
+            continue;
+        }
+        data.insert_battle(turn, sys.id).await?;
+        let sides: Vec<String> = sides
+            .iter()
+            .map(|s| match s {
+                Side::Empire(e) => names.get(e).cloned().unwrap_or_else(|| "None".to_string()),
+                Side::Minor(m) => m.clone(),
+            })
+            .collect();
+        report.add(
+            Phase::Combat,
+            format!("BATTLE: {} at {}", sides.join(" against "), sys.name),
+        );
+    }
+    Ok(())
+}
+
+// Advance every build in the construction queue, delivering those that
+// complete this turn.
+async fn construction(data: &DataStore, report: &mut TurnReport) -> DataResult<()> {
+    for mut b in data.get_builds().await? {
+        if b.kind == construction::REPAIR && b.turns <= 1 {
+            // Due repairs are made as capacity allows.
+            continue;
+        }
+        if b.turns <= 1 {
+            data.finish_build(&b).await?;
+            report.add(
+                Phase::Construction,
+                format!(
+                    "{} completed {} at {}",
+                    b.empire_name,
+                    b.item_name(),
+                    b.system_name
+                ),
+            );
+        } else {
+            b.turns -= 1;
+            data.update_build(&b).await?;
+        }
+    }
+    Ok(())
+}
+
+// Found the colonies and make the development attempts ordered this turn.
+// What each system's orders need is read first; the orders are then worked
+// out in a task of their own for each system, and the results recorded
+// together. Orders that can no longer be carried out are refunded.
+async fn development(
+    data: &DataStore,
+    turn: i32,
+    seed: u64,
+    report: &mut TurnReport,
+) -> DataResult<()> {
+    let mut by_system: BTreeMap<i64, Vec<Development>> = BTreeMap::new();
+    for d in data.get_developments(turn).await? {
+        by_system.entry(d.system).or_default().push(d);
+    }
+    let mut tasks: Vec<JoinHandle<Resolution>> = Vec::new();
+    for (system, orders) in by_system {
+        let sys = data.get_system_by_id(system).await?;
+        let mut ships = BTreeMap::new();
+        for d in orders.iter().filter(|d| d.target == development::COLONY) {
+            if let Some(ship) = data.get_colony_ship(d.empire, system).await? {
+                ships.insert(d.empire, ship);
+            }
+        }
+        tasks.push(tokio::task::spawn_blocking(move || {
+            development::resolve(&sys, &orders, &ships, turn, seed)
+        }));
+    }
+    let resolutions = join_tasks(tasks).await?;
+    data.apply_developments(&resolutions).await?;
+    for r in resolutions {
+        for line in r.report {
+            report.add(Phase::Construction, line);
+        }
+        report.rolls.extend(r.rolls);
+    }
+    Ok(())
+}
+
+// End treaties that lapse this turn, telling both empires in their next
+// player packets.
+async fn diplomacy(data: &DataStore, turn: i32, report: &mut TurnReport) -> DataResult<()> {
+    for t in data.get_treaties().await? {
+        if t.lapses(turn) {
+            data.delete_treaty(&t).await?;
+            let text = format!("The {} has expired", t.describe());
+            for empire in [t.a, t.b] {
+                data.insert_intel(&IntelItem::new(empire, turn + 1, NOTICE, &text))
+                    .await?;
+            }
+            report.add(Phase::Diplomacy, text);
+        }
+    }
+    Ok(())
+}
+
+// Add each empire's income before maintenance to its treasury, including its
+// share of the systems it contests and its trade with its partners, adjusted
+// for its government and traits and scaled by the campaign's economic
+// difficulty. The systems each empire holds and contests are read first; its
+// income is then worked out in a task of its own, and all are paid together.
+async fn income(data: &DataStore, report: &mut TurnReport) -> DataResult<()> {
+    let options = data.get_campaign_options().await?;
+    let rules = options.ruleset();
+    let mut tasks: Vec<JoinHandle<(Empire, EmpireIncome)>> = Vec::new();
+    for e in data.get_empires().await? {
+        let systems = data.get_systems_by_owner(e.id).await?;
+        let held: Vec<System> = data
+            .get_systems_contested_by(e.id)
+            .await?
+            .iter()
+            .filter_map(ownership::contender_view)
+            .collect();
+        let admin = options.colonial_admin;
+        tasks.push(tokio::task::spawn_blocking(move || {
+            let mut income = EmpireIncome::new(&systems, 0, rules);
+            if admin {
+                income = income.with_admin_limit(e.tech, &systems);
+            }
+            let held = EmpireIncome::new(&held, 0, rules);
+            income.census += held.census;
+            income.productivity += held.productivity;
+            income.trade += held.trade;
+            (e, income)
+        }));
+    }
+    let mut incomes = join_tasks(tasks).await?;
+
+    let routes = trade::routes(
+        &data.get_treaties().await?,
+        &data.get_systems().await?,
+        &data.get_lanes().await?,
+    );
+    let productivity: BTreeMap<i64, i32> = incomes
+        .iter()
+        .map(|(e, i)| (e.id, i.productivity - i.admin))
+        .collect();
+    let earned = trade::income(&routes, &productivity);
+    for (e, i) in incomes.iter_mut() {
+        i.trade += earned.get(&e.id).copied().unwrap_or(0);
+        *i = i.clone().with_government(&Modifiers::new(e));
+    }
+
+    let changes: Vec<(i64, i32)> = incomes
+        .iter()
+        .map(|(e, i)| (e.id, options.scale_income(i.gross())))
+        .collect();
+    data.adjust_treasuries(&changes).await?;
+    for (e, i) in incomes {
+        let mut text = format!(
+            "{} collected {} EP",
+            e.name,
+            options.scale_income(i.gross())
+        );
+        if i.trade > 0 {
+            text.push_str(&format!(
+                ", {} of it from trade",
+                options.scale_income(i.trade)
+            ));
+        }
+        report.add(Phase::Income, text);
+    }
+    for r in routes.iter().filter(|r| r.status != RouteStatus::Open) {
+        report.add(Phase::Income, r.describe());
+    }
+    Ok(())
+}
+
+// Resolve the espionage operations ordered for the turn against their
+// targets' counter-espionage, telling the empires involved what came of them
+// in their next player packets.
+async fn intelligence(
+    data: &DataStore,
+    turn: i32,
+    seed: u64,
+    report: &mut TurnReport,
+) -> DataResult<()> {
+    let ops = data.get_operations(turn).await?;
+    for op in ops
+        .iter()
+        .filter(|o| o.mission != espionage::COUNTER_ESPIONAGE)
+    {
+        let counter = espionage::counter_points(&ops, op.target);
+        let mut dice = Dice::for_turn(seed, espionage::seed(turn, op.id)).with_context(
+            format!(
+                "{} {} against {}",
+                op.empire_name, op.mission, op.target_name
+            )
+            .as_str(),
+        );
+        let roll = dice.roll(10);
+        report.add_rolls(&dice);
+        if !espionage::succeeds(op.points, counter, roll) {
+            let caught = format!(
+                "{} agents were caught attempting {} against {}",
+                op.empire_name,
+                op.mission.to_lowercase(),
+                op.target_name
+            );
+            for empire in [op.empire, op.target] {
+                data.insert_intel(&IntelItem::new(empire, turn + 1, NOTICE, &caught))
+                    .await?;
+            }
+            report.add(Phase::Intelligence, caught);
+            continue;
+        }
+
+        let text = match op.mission.as_str() {
+            espionage::STEAL_TECH => {
+                let status = data.get_tech_status(turn).await?;
+                let tech_of = |empire| status.iter().find(|t| t.empire == empire);
+                match (tech_of(op.empire), tech_of(op.target)) {
+                    (Some(thief), Some(target)) => {
+                        let points = espionage::stolen(thief.tech, target.tech);
+                        if points > 0 {
+                            data.set_tech(op.empire, thief.tech, thief.points + points)
+                                .await?;
+                        }
+                        format!(
+                            "{} agents stole {} research points from {}",
+                            op.empire_name, points, op.target_name
+                        )
+                    }
+                    _ => continue,
+                }
+            }
+            espionage::SABOTAGE => {
+                let mut delayed = 0;
+                for mut b in data.get_builds().await? {
+                    if b.empire == op.target {
+                        b.turns += espionage::SABOTAGE_DELAY;
+                        data.update_build(&b).await?;
+                        delayed += 1;
+                    }
+                }
+                let text = format!(
+                    "{} agents sabotaged {} builds of {}",
+                    op.empire_name, delayed, op.target_name
+                );
+                if delayed > 0 {
+                    data.insert_intel(&IntelItem::new(
+                        op.target,
+                        turn + 1,
+                        NOTICE,
+                        &format!("Saboteurs delayed {} of our builds", delayed),
+                    ))
+                    .await?;
+                }
+                text
+            }
+            espionage::REVEAL_FLEETS => {
+                let fleets: Vec<_> = data
+                    .get_fleet_summaries()
+                    .await?
+                    .into_iter()
+                    .filter(|f| f.owner == op.target)
+                    .collect();
+                let mut classes = Vec::new();
+                for f in &fleets {
+                    for s in data.get_fleet_ships(f.id).await? {
+                        classes.push(s.class);
+                    }
+                }
+                let found: Vec<Identification> = data
+                    .get_ship_types()
+                    .await?
+                    .into_iter()
+                    .filter(|t| t.empire == op.target && classes.contains(&t.class))
+                    .map(|t| Identification::new(op.empire, t.id, turn, ESPIONAGE))
+                    .collect();
+                data.insert_identifications(&found).await?;
+                data.insert_intel(&IntelItem::new(
+                    op.empire,
+                    turn + 1,
+                    "Scan",
+                    &espionage::fleet_report(&op.target_name, &fleets),
+                ))
+                .await?;
+                format!(
+                    "{} agents revealed {} fleets of {}",
+                    op.empire_name,
+                    fleets.len(),
+                    op.target_name
+                )
+            }
+            _ => continue,
+        };
+        data.insert_intel(&IntelItem::new(op.empire, turn + 1, NOTICE, &text))
+            .await?;
+        report.add(Phase::Intelligence, text);
+    }
+    Ok(())
+}
+
+// The construction queued by a system's owner beyond what the system can
+// build, in the words of the checklist.
+async fn overbooked(data: &DataStore, sys: &System) -> DataResult<Vec<String>> {
+    let mut production = production::summarize(
+        &data.get_empires().await?,
+        &data.get_systems().await?,
+        &data.get_facilities().await?,
+        &data.get_builds().await?,
+    );
+    production.retain(|p| p.empire == sys.owner);
+    for p in production.iter_mut() {
+        p.yards.retain(|y| y.system == sys.id);
+    }
+    Ok(checklist::over_capacity(&production)
+        .into_iter()
+        .map(|i| i.text)
+        .collect())
+}
+
+// Wait for the tasks working on parts of a phase, returning their results
+// in the order the tasks were started.
+async fn join_tasks<T>(tasks: Vec<JoinHandle<T>>) -> DataResult<Vec<T>> {
+    Ok(try_join_all(tasks).await?)
+}
+
+// Deduct each empire's ship maintenance from its treasury.
+async fn maintenance(data: &DataStore, report: &mut TurnReport) -> DataResult<()> {
+    for e in data.get_empires().await? {
+        let due = data.get_maintenance(e.id).await?.total();
+        if due > 0 {
+            data.adjust_treasury(e.id, -due).await?;
+            report.add(
+                Phase::Maintenance,
+                format!("{} paid {} EP maintenance", e.name, due),
+            );
+        }
+    }
+    Ok(())
+}
+
+// Check the morale of every owned system, flagging those in rebellion.
+async fn morale(data: &DataStore, turn: i32, report: &mut TurnReport) -> DataResult<()> {
+    let conquered = data.get_conquered_since(turn - CONQUEST_TURNS + 1).await?;
+    let mods = data.get_morale_modifiers().await?;
+    let governments: BTreeMap<i64, Modifiers> = data
+        .get_empires()
+        .await?
+        .iter()
+        .map(|e| (e.id, Modifiers::new(e)))
+        .collect();
+    let types = data.get_planet_types().await?;
+    for s in data.get_systems().await? {
+        if s.owner == 0 {
+            continue;
+        }
+        let planet = types
+            .iter()
+            .find(|t| t.name == s.ptype)
+            .map_or(0, |t| t.mor_mod);
+        let check = MoraleCheck::new(
+            &s,
+            conquered.contains(&s.id),
+            mods.get(&s.owner).copied().unwrap_or(0),
+        )
+        .with_planet(planet)
+        .with_government(governments.get(&s.owner).map_or(0, |m| m.morale));
+        let after = check.apply(&s);
+        if after != s {
+            data.update_system(&after).await?;
+        }
+        if after.mor != s.mor {
+            report.add(
+                Phase::Morale,
+                format!(
+                    "{} morale {} to {} ({})",
+                    s.name,
+                    if after.mor < s.mor { "fell" } else { "rose" },
+                    after.mor,
+                    check.describe()
+                ),
+            );
+        }
+        if in_rebellion(&after) {
+            report.add(
+                Phase::Morale,
+                format!(
+                    "REBELLION: {} is in revolt against {}",
+                    s.name, s.owner_name
+                ),
+            );
+        }
+    }
+    Ok(())
+}
+
+// Move the fleets along their courses: those set by this turn's movement
+// orders, and those still under way from earlier turns. Fleets stopped by
+// hostile ones are reported, and both empires told in their next packets.
+async fn movement(data: &DataStore, turn: i32, report: &mut TurnReport) -> DataResult<()> {
+    let systems = data.get_systems().await?;
+    let lanes = data.get_lanes().await?;
+    let fleets = data.get_fleet_summaries().await?;
+    let escorts = data.get_escorts().await?;
+    let name = |id: i64| {
+        systems
+            .iter()
+            .find(|s| s.id == id)
+            .map_or_else(|| "None".to_string(), |s| s.name.clone())
+    };
+    let mut courses = data.get_courses().await?;
+    for o in data.get_movement_orders(turn).await? {
+        if !o.text.trim().starts_with("Move ") {
+            continue;
+        }
+        match course(&o, &fleets, &systems, &lanes, &escorts) {
+            Ok((fleet, c)) => {
+                courses.insert(fleet, c);
+            }
+            Err(e) => report.add(
+                Phase::Movement,
+                format!("{} not carried out: {}", o.text, e),
+            ),
+        }
+    }
+
+    // Escorts go where the fleets they guard go, at the group's pace.
+    let mut transits: Vec<Transit> = fleets
+        .iter()
+        .filter(|f| f.location != 0 && escort::escorting(&escorts, f.id).is_none())
+        .map(|f| {
+            let group = escort::group(&escorts, f.id);
+            let jumps = fleets
+                .iter()
+                .filter(|g| group.contains(&g.id))
+                .map(|g| g.jumps)
+                .min()
+                .unwrap_or(f.jumps);
+            let course = courses.get(&f.id).map_or(&[][..], Vec::as_slice);
+            Transit::resume(&lanes, (f.id, f.owner, f.location), course, jumps)
+        })
+        .collect();
+    let started: Vec<(i64, bool)> = transits
+        .iter()
+        .map(|t| (t.at, !t.course.is_empty()))
+        .collect();
+    let treaties = data.get_treaties().await?;
+    let hostile = |a: i64, b: i64| {
+        a != b
+            && a != 0
+            && b != 0
+            && !treaties
+                .iter()
+                .any(|t| (t.a == a && t.b == b) || (t.a == b && t.b == a))
+    };
+    let caught = movement::resolve(&mut transits, hostile);
+
+    let fleet = |id: i64| fleets.iter().find(|f| f.id == id).unwrap();
+    for (t, (from, under_way)) in transits.iter().zip(&started) {
+        if !under_way || t.at == *from {
+            continue;
+        }
+        let f = fleet(t.fleet);
+        let line = match t.course.last() {
+            None => format!("{} moved from {} to {}", f.name, name(*from), name(t.at)),
+            Some(dest) => format!(
+                "{} moved from {} to {}, bound for {}",
+                f.name,
+                name(*from),
+                name(t.at),
+                name(*dest)
+            ),
+        };
+        report.add(Phase::Movement, format!("{}: {}", f.owner_name, line));
+    }
+    for i in caught {
+        let (f, by) = (fleet(i.fleet), fleet(i.by));
+        let line = format!(
+            "INTERCEPTED: {} {} stopped at {} by {} {}",
+            f.owner_name,
+            f.name,
+            name(i.system),
+            by.owner_name,
+            by.name
+        );
+        for empire in [f.owner, by.owner] {
+            data.insert_intel(&IntelItem::new(empire, turn + 1, NOTICE, line.as_str()))
+                .await?;
+        }
+        report.add(Phase::Movement, line);
+    }
+
+    let mut moved = transits.clone();
+    for t in &transits {
+        for id in escort::group(&escorts, t.fleet).into_iter().skip(1) {
+            moved.push(Transit {
+                fleet: id,
+                course: Vec::new(),
+                ..t.clone()
+            });
+        }
+    }
+    data.apply_movement(&moved).await
+}
+
+// The course a movement order sets a fleet on, through any systems it names
+// on the way to its destination.
+fn course(
+    order: &Order,
+    fleets: &[FleetSummary],
+    systems: &[System],
+    lanes: &[Lane],
+    escorts: &[Escort],
+) -> Result<(i64, Vec<i64>), String> {
+    let (fleet, _, route) =
+        orders::parse_move(&order.text).ok_or_else(|| "Order not understood".to_string())?;
+    let fleet = fleets
+        .iter()
+        .find(|f| f.owner == order.empire && f.name.eq_ignore_ascii_case(fleet))
+        .ok_or_else(|| format!("Unknown fleet {}", fleet))?;
+    if escort::escorting(escorts, fleet.id).is_some() {
+        return Err(format!("{} moves with the fleet it escorts", fleet.name));
+    }
+    let mut waypoints = Vec::new();
+    for name in route {
+        let sys = systems
+            .iter()
+            .find(|s| s.name.eq_ignore_ascii_case(name))
+            .ok_or_else(|| format!("Unknown system {}", name))?;
+        waypoints.push(sys.id);
+    }
+    let course = movement::plot(lanes, fleet.location, &waypoints).map_err(|id| {
+        let sys = systems.iter().find(|s| s.id == id);
+        format!("No way to reach {}", sys.map_or("", |s| s.name.as_str()))
+    })?;
+    Ok((fleet.id, course))
+}
+
+// Wear down the ships beyond their empires' supply, if the attrition rules
+// are in use, telling each empire what it lost in its next player packet.
+async fn attrition(
+    data: &DataStore,
+    turn: i32,
+    seed: u64,
+    report: &mut TurnReport,
+) -> DataResult<()> {
+    if !data.get_rule_option(ATTRITION).await? {
+        return Ok(());
+    }
+    let systems = data.get_systems().await?;
+    let supplied = overlay::supply(
+        &systems,
+        &data.get_lanes().await?,
+        &data.get_facilities().await?,
+    );
+    let mut ships = Vec::new();
+    for e in data.get_empires().await? {
+        ships.extend(data.get_ships_by_owner(e.id).await?);
+    }
+    let mut dice = Dice::for_turn(seed, attrition::seed(turn)).with_context("Attrition");
+    let outcome = attrition::resolve(
+        &ships,
+        &systems,
+        &supplied,
+        &data.get_wars().await?,
+        &mut dice,
+    );
+    report.add_rolls(&dice);
+    data.apply_battle(&outcome.crippled, &outcome.destroyed)
+        .await?;
+    for (empire, line) in outcome.report {
+        data.insert_intel(&IntelItem::new(empire, turn + 1, NOTICE, line.as_str()))
+            .await?;
+        report.add(Phase::Movement, line);
+    }
+    Ok(())
+}
+
+// Resolve the raids ordered for the turn, if the strategic warfare rules are
+// in use, telling both empires how each went in their next player packets.
+// A successful shipyard raid also knocks out one of the system's shipyards,
+// and its builds are checked at once against what it can still build.
+async fn raids(data: &DataStore, turn: i32, seed: u64, report: &mut TurnReport) -> DataResult<()> {
+    if !data.get_rule_option(STRATEGIC_WARFARE).await? {
+        return Ok(());
+    }
+    let rules = data.get_campaign_options().await?.ruleset();
+    for r in data.get_raids(turn).await? {
+        let target = data.get_system_by_id(r.system).await?;
+        if target.owner == 0 || target.owner == r.empire {
+            report.add(
+                Phase::Combat,
+                format!(
+                    "{} raid on {} called off: not an enemy system",
+                    r.empire_name, target.name
+                ),
+            );
+            continue;
+        }
+        let (raiders, others): (Vec<Combatant>, Vec<Combatant>) = data
+            .get_combatants(target.id)
+            .await?
+            .into_iter()
+            .partition(|c| c.owner == r.empire);
+        let defenders: Vec<Combatant> = others
+            .into_iter()
+            .filter(|c| c.owner == target.owner)
+            .collect();
+        let garrison = data.get_ground_units(target.id).await?;
+        let mut dice = Dice::for_turn(seed, raid::seed(turn, r.id))
+            .with_context(format!("{} raid on {}", r.empire_name, target.name).as_str());
+        let outcome = resolve_raid(
+            &r, &target, &raiders, &defenders, &garrison, rules, &mut dice,
+        );
+        report.add_rolls(&dice);
+
+        data.apply_battle(&outcome.crippled, &outcome.destroyed)
+            .await?;
+        if outcome.system != target {
+            data.update_system(&outcome.system).await?;
+        }
+        if outcome.loss > 0 {
+            data.adjust_treasury(target.owner, -outcome.loss).await?;
+        }
+        if outcome.delay > 0 {
+            for mut b in data.get_builds().await? {
+                if b.system == target.id {
+                    b.turns += outcome.delay;
+                    data.update_build(&b).await?;
+                }
+            }
+        }
+        let mut text = outcome.report.clone();
+        if outcome.success && r.target == raid::SHIPYARDS {
+            let (crippled, destroyed) = raid::yard_damage(&data.get_facilities().await?, target.id);
+            data.apply_battle(&crippled, &destroyed).await?;
+            if !crippled.is_empty() {
+                text.push_str(&format!("A shipyard at {} is knocked out.\n", target.name));
+            }
+            if !destroyed.is_empty() {
+                text.push_str(&format!("A shipyard at {} is destroyed.\n", target.name));
+            }
+            for line in overbooked(data, &target).await? {
+                text.push_str(&format!("{}.\n", line));
+                report.add(Phase::Construction, line);
+            }
+        }
+
+        for empire in [r.empire, target.owner] {
+            data.insert_intel(&IntelItem::new(empire, turn + 1, NOTICE, text.trim_end()))
+                .await?;
+        }
+        report.add(
+            Phase::Combat,
+            format!(
+                "{} raid on the {} of {} {}",
+                r.empire_name,
+                r.target.to_lowercase(),
+                target.name,
+                if outcome.success {
+                    "succeeded"
+                } else {
+                    "failed"
+                }
+            ),
+        );
+    }
+    Ok(())
+}
+
+// Repair crippled ships at their owners' systems as far as each system's
+// capacity allows, reporting those left waiting.
+async fn repairs(data: &DataStore, report: &mut TurnReport) -> DataResult<()> {
+    let capacity = repair::capacity(&data.get_systems().await?, &data.get_facilities().await?);
+    let plan = repair::plan(&data.get_repair_jobs().await?, &capacity);
+    let ships: Vec<i64> = plan.repaired.iter().map(|j| j.ship).collect();
+    data.repair_ships(&ships).await?;
+    for j in plan.repaired {
+        report.add(
+            Phase::Construction,
+            format!(
+                "{} repaired {} #{} at {}",
+                j.empire_name, j.class, j.ship, j.system_name
+            ),
+        );
+    }
+    for j in plan.unrepaired {
+        report.add(
+            Phase::Construction,
+            format!(
+                "{} {} #{} left unrepaired at {} for lack of capacity",
+                j.empire_name, j.class, j.ship, j.system_name
+            ),
+        );
+    }
+    Ok(())
+}
+
+// Add each empire's research spending to its points and roll for the next
+// tech level.
+async fn research(
+    data: &DataStore,
+    turn: i32,
+    seed: u64,
+    report: &mut TurnReport,
+) -> DataResult<()> {
+    for t in data.get_tech_status(turn).await? {
+        if t.spending == 0 && t.points == 0 {
+            continue;
+        }
+        let points = t.points + t.spending;
+        let cost = tech::level_cost(t.tech);
+        let mut dice = Dice::for_turn(seed, tech::seed(turn, t.empire))
+            .with_context(format!("{} research", t.empire_name).as_str());
+        let roll = dice.roll(10);
+        report.add_rolls(&dice);
+        if tech::breakthrough(points, cost, roll) {
+            data.set_tech(t.empire, t.tech + 1, (points - cost).max(0))
+                .await?;
+            report.add(
+                Phase::Research,
+                format!("{} advanced to tech level {}", t.empire_name, t.tech + 1),
+            );
+        } else {
+            data.set_tech(t.empire, t.tech, points).await?;
+            report.add(
+                Phase::Research,
+                format!(
+                    "{} has {} of {} research points toward tech level {}",
+                    t.empire_name,
+                    points,
+                    cost,
+                    t.tech + 1
+                ),
+            );
+        }
+    }
+    Ok(())
+}
+
+// Carry out the ship and class transfers agreed this turn, telling both
+// empires in their next player packets. A transfer falls through if the
+// seller no longer holds what it agreed to transfer, or the buyer cannot
+// pay. Each is looked up again before it is made, since one made before it
+// may have moved the same ship or spent the buyer's treasury.
+async fn transfers(data: &DataStore, turn: i32, report: &mut TurnReport) -> DataResult<()> {
+    let ids: Vec<i64> = data
+        .get_transfers(turn)
+        .await?
+        .iter()
+        .map(|t| t.id)
+        .collect();
+    for id in ids {
+        let t = match data
+            .get_transfers(turn)
+            .await?
+            .into_iter()
+            .find(|t| t.id == id)
+        {
+            Some(t) => t,
+            None => continue,
+        };
+        let treasury = data
+            .get_empires()
+            .await?
+            .iter()
+            .find(|e| e.id == t.buyer)
+            .map_or(0, |e| e.treasury);
+        let text = match t.check(treasury) {
+            Ok(_) => {
+                data.transfer(&t).await?;
+                t.describe()
+            }
+            Err(e) => format!(
+                "Transfer of {} from {} to {} fell through: {}",
+                t.item_name, t.seller_name, t.buyer_name, e
+            ),
+        };
+        for empire in [t.seller, t.buyer] {
+            data.insert_intel(&IntelItem::new(empire, turn + 1, NOTICE, &text))
+                .await?;
+        }
+        report.add(Phase::Diplomacy, text);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::{advance, Phase, TurnReport};
+    use crate::campaign::attrition::{ATTRITION, FAIL};
+    use crate::campaign::construction::{self, Build};
+    use crate::campaign::data::tests::init_data;
+    use crate::campaign::development::{self, Development};
+    use crate::campaign::diplomacy::Treaty;
+    use crate::campaign::economy::EmpireIncome;
+    use crate::campaign::empire::{tests::empires, Empire};
+    use crate::campaign::espionage::{self, Operation};
+    use crate::campaign::facility::{Facility, SHIPYARD};
+    use crate::campaign::government::{self, Modifiers};
+    use crate::campaign::intel::NOTICE;
+    use crate::campaign::lane::tests::lanes;
+    use crate::campaign::options::CampaignOptions;
+    use crate::campaign::orders::{self, Order};
+    use crate::campaign::ownership;
+    use crate::campaign::raid::{Raid, POPULATION, SHIPYARDS, STRATEGIC_WARFARE};
+    use crate::campaign::ruleset::SecondEdition;
+    use crate::campaign::system::tests::systems;
+    use crate::campaign::template::{render, DEFAULT_TURN_REPORT};
+    use crate::campaign::unit::{FleetSummary, ShipType};
+
+    #[test]
+    fn default_template() {
+        let mut report = TurnReport::new(4);
+        report.add(Phase::Income, "Senorian income 12".to_string());
+        report.add(Phase::Income, "Human income 9".to_string());
+        report.add(Phase::Combat, "Battle at Tibron".to_string());
+        let text = render(DEFAULT_TURN_REPORT, &report.values());
+        assert_eq!(report.to_string(), text);
+        assert!(text.contains("Income Phase\n  Senorian income 12\n  Human income 9\n"));
+        assert_eq!("  Battle at Tibron", report.values()["combat"]);
+    }
+
+    #[tokio::test]
+    async fn advance_turn() {
+        let data = init_data().await;
+        data.add_empires(empires()).await.unwrap();
+        data.add_systems(systems()).await.unwrap();
+        let mut home = data.get_system_by_name("Senor Prime").await.unwrap();
+        home.owner = 1;
+        home.blockaded = true;
+        data.update_system(&home).await.unwrap();
+
+        let report = advance(&data, 0).await.unwrap();
+        assert_eq!(0, report.turn);
+        assert_eq!(1, data.current_turn().await.unwrap());
+
+        let income = EmpireIncome::new(&[home.clone()], 0, &SecondEdition).gross();
+        let senorian = data
+            .get_empires()
+            .await
+            .unwrap()
+            .into_iter()
+            .find(|e| e.id == 1)
+            .unwrap();
+        assert_eq!(income, senorian.treasury);
+
+        let after = data.get_system_by_id(home.id).await.unwrap();
+        assert_eq!(home.mor - 1, after.mor);
+        assert!(report.entries.iter().any(|(p, _)| *p == Phase::Morale));
+    }
+
+    #[tokio::test]
+    async fn contested_and_neutral() {
+        let data = init_data().await;
+        data.add_empires(empires()).await.unwrap();
+        data.add_systems(systems()).await.unwrap();
+        let mut home = data.get_system_by_name("Senor Prime").await.unwrap();
+        home.owner = 1;
+        home.contender = 2;
+        home.share = 60;
+        data.update_system(&home).await.unwrap();
+        let mut natives = data.get_system_by_name("Zev'rch").await.unwrap();
+        natives.minor = "Zev".to_string();
+        data.update_system(&natives).await.unwrap();
+        let item = data
+            .insert_ship_type(&ShipType::new("Avenger", "Cruiser", 1))
+            .await
+            .unwrap();
+        data.finish_build(&Build::new(1, natives.id, "Ship", item, ""))
+            .await
+            .unwrap();
+        // The owner pays for its ship out of its share.
+        let upkeep = data.get_maintenance(1).await.unwrap().total();
+        assert!(upkeep > 0);
+
+        let report = advance(&data, 0).await.unwrap();
+        let treasury: BTreeMap<i64, i32> = data
+            .get_empires()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|e| (e.id, e.treasury))
+            .collect();
+        let held = ownership::contender_view(&home).unwrap();
+        assert_eq!(
+            EmpireIncome::new(&[home.clone()], upkeep, &SecondEdition).net(),
+            treasury[&1]
+        );
+        assert_eq!(
+            EmpireIncome::new(&[held], 0, &SecondEdition).gross(),
+            treasury[&2]
+        );
+
+        let texts: Vec<&str> = report.entries.iter().map(|(_, t)| t.as_str()).collect();
+        assert!(texts.contains(&"BATTLE: Senorian against Zev at Zev'rch"));
+        assert_eq!(vec![natives.id], data.get_battle_sites(0).await.unwrap());
+        assert!(texts
+            .iter()
+            .any(|t| t.starts_with("Senor Prime morale") && t.contains("contested -1")));
+    }
+
+    #[tokio::test]
+    async fn difficulty() {
+        let data = init_data().await;
+        data.add_empires(empires()).await.unwrap();
+        data.add_systems(systems()).await.unwrap();
+        let mut home = data.get_system_by_name("Senor Prime").await.unwrap();
+        home.owner = 1;
+        data.update_system(&home).await.unwrap();
+        let options = CampaignOptions {
+            difficulty: "Hard".to_string(),
+            ..Default::default()
+        };
+        data.set_campaign_options(&options).await.unwrap();
+
+        advance(&data, 0).await.unwrap();
+        let income = EmpireIncome::new(&[home.clone()], 0, &SecondEdition).gross();
+        let senorian = data
+            .get_empires()
+            .await
+            .unwrap()
+            .into_iter()
+            .find(|e| e.id == 1)
+            .unwrap();
+        assert_eq!(income * 75 / 100, senorian.treasury);
+    }
+
+    #[tokio::test]
+    async fn government() {
+        let data = init_data().await;
+        data.add_empires(empires()).await.unwrap();
+        data.add_systems(systems()).await.unwrap();
+        let mut home = data.get_system_by_name("Senor Prime").await.unwrap();
+        home.owner = 1;
+        data.update_system(&home).await.unwrap();
+        let mut senorian = data
+            .get_empires()
+            .await
+            .unwrap()
+            .into_iter()
+            .find(|e| e.id == 1)
+            .unwrap();
+        senorian.government = government::DICTATORSHIP.to_string();
+        data.update_empire(&senorian).await.unwrap();
+
+        let report = advance(&data, 0).await.unwrap();
+        let income = EmpireIncome::new(&[home.clone()], 0, &SecondEdition)
+            .with_government(&Modifiers::new(&senorian))
+            .gross();
+        let treasury: BTreeMap<i64, i32> = data
+            .get_empires()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|e| (e.id, e.treasury))
+            .collect();
+        assert_eq!(income, treasury[&1]);
+        assert!(income > EmpireIncome::new(&[home.clone()], 0, &SecondEdition).gross());
+
+        let after = data.get_system_by_id(home.id).await.unwrap();
+        assert_eq!(home.mor - 1, after.mor);
+        assert!(report
+            .entries
+            .iter()
+            .any(|(_, t)| t.starts_with("Senor Prime morale") && t.contains("government -1")));
+    }
+
+    #[tokio::test]
+    async fn research() {
+        let data = init_data().await;
+        data.add_empires(empires()).await.unwrap();
+        // Enough spending always advances, with the excess carried over.
+        data.set_research(1, 0, 13).await.unwrap();
+
+        let report = advance(&data, 0).await.unwrap();
+        let status = data.get_tech_status(1).await.unwrap();
+        let senorian = status.iter().find(|t| t.empire == 1).unwrap();
+        assert_eq!((1, 3), (senorian.tech, senorian.points));
+        assert!(report
+            .entries
+            .iter()
+            .any(|(p, t)| *p == Phase::Research && t.contains("Senorian advanced")));
+        // The roll is kept for the audit log.
+        assert_eq!(1, report.rolls.len());
+        assert!(report.rolls[0]
+            .to_string()
+            .starts_with("Senorian research: d10"));
+    }
+
+    #[tokio::test]
+    async fn attrition() {
+        let data = init_data().await;
+        data.add_empires(empires()).await.unwrap();
+        data.add_systems(systems()).await.unwrap();
+        let mut home = data.get_system_by_name("Senor Prime").await.unwrap();
+        home.owner = 1;
+        data.update_system(&home).await.unwrap();
+        // With no lanes, supply reaches no further than Senor Prime.
+        let far = data.get_system_by_name("Tibron").await.unwrap();
+        let item = data
+            .insert_ship_type(&ShipType::new("Avenger", "Cruiser", 1))
+            .await
+            .unwrap();
+        for sys in [home.id, far.id] {
+            let mut build = Build::new(1, sys, "Ship", item, "");
+            build.count = 30;
+            data.finish_build(&build).await.unwrap();
+        }
+
+        let report = advance(&data, 0).await.unwrap();
+        assert!(report.rolls.is_empty());
+
+        data.set_rule_option(ATTRITION, true).await.unwrap();
+        let report = advance(&data, 1).await.unwrap();
+        assert_eq!(30, report.rolls.len());
+        assert!(report.rolls.iter().all(|r| r.context == "Attrition"));
+        let failed = report.rolls.iter().filter(|r| r.result <= FAIL).count();
+        let worn: Vec<&String> = report
+            .entries
+            .iter()
+            .filter(|(p, _)| *p == Phase::Movement)
+            .map(|(_, t)| t)
+            .collect();
+        assert_eq!(failed, worn.len());
+        assert!(worn.iter().all(|t| t.ends_with("by attrition at Tibron")));
+        let ships = data.get_ships_by_owner(1).await.unwrap();
+        assert_eq!(failed, ships.iter().filter(|s| s.crip).count());
+        assert!(ships.iter().all(|s| !s.crip || s.location == far.id));
+    }
+
+    #[tokio::test]
+    async fn fleet_movement() {
+        let data = init_data().await;
+        data.add_empires(empires()).await.unwrap();
+        data.add_systems(systems()).await.unwrap();
+        for lane in lanes() {
+            data.insert_lane(&lane).await.unwrap();
+        }
+        let home = data.get_system_by_name("Senor Prime").await.unwrap();
+        let target = data.get_system_by_name("Zev'rch").await.unwrap();
+        for empire in [1, 2] {
+            let item = data
+                .insert_ship_type(&ShipType::new("Avenger", "Cruiser", empire))
+                .await
+                .unwrap();
+            let at = if empire == 1 { home.id } else { target.id };
+            data.finish_build(&Build::new(empire, at, "Ship", item, ""))
+                .await
+                .unwrap();
+        }
+        let order = |text: &str| Order::in_phase(1, 0, orders::MOVEMENT, text, 0);
+        data.insert_orders(&[
+            order("Move New Construction to Zev'rch"),
+            order("Move Phantom Fleet to Tibron"),
+        ])
+        .await
+        .unwrap();
+        let moves = |report: &TurnReport| -> Vec<String> {
+            report
+                .entries
+                .iter()
+                .filter(|(p, _)| *p == Phase::Movement)
+                .map(|(_, t)| t.clone())
+                .collect()
+        };
+
+        // One jump a turn, by the shortest way.
+        let report = advance(&data, 0).await.unwrap();
+        assert_eq!(
+            vec![
+                "Move Phantom Fleet to Tibron not carried out: Unknown fleet Phantom Fleet",
+                "Senorian: New Construction moved from Senor Prime to Vadurrinia, \
+                bound for Zev'rch",
+            ],
+            moves(&report)
+        );
+        let fleet = |v: &[FleetSummary]| v.iter().find(|f| f.owner == 1).unwrap().location;
+        let vadurrinia = data.get_system_by_name("Vadurrinia").await.unwrap();
+        assert_eq!(
+            vadurrinia.id,
+            fleet(&data.get_fleet_summaries().await.unwrap())
+        );
+
+        // The course is kept to the next turn, and the Human fleet stops it.
+        let report = advance(&data, 1).await.unwrap();
+        assert!(moves(&report).contains(
+            &"INTERCEPTED: Senorian New Construction stopped at Zev'rch by Human New Construction"
+                .to_string()
+        ));
+        assert_eq!(target.id, fleet(&data.get_fleet_summaries().await.unwrap()));
+        assert!(data.get_courses().await.unwrap().is_empty());
+        let intel = data.get_intel(2, 2).await.unwrap();
+        assert!(intel.iter().any(|i| i.text.starts_with("INTERCEPTED")));
+    }
+
+    #[tokio::test]
+    async fn raid() {
+        let data = init_data().await;
+        data.add_empires(empires()).await.unwrap();
+        data.add_systems(systems()).await.unwrap();
+        let mut target = data.get_system_by_name("Tibron").await.unwrap();
+        target.owner = 2;
+        data.update_system(&target).await.unwrap();
+        let target = data.get_system_by_id(target.id).await.unwrap();
+
+        // An undefended system cannot hold off this much attack.
+        let mut stype = ShipType::new("Marauder", "Cruiser", 1);
+        stype.atk = 20;
+        stype.def = 3;
+        let item = data.insert_ship_type(&stype).await.unwrap();
+        let ship = Build::new(1, target.id, "Ship", item, "");
+        data.finish_build(&ship).await.unwrap();
+        data.insert_raid(&Raid::new(1, 0, target.id, POPULATION))
+            .await
+            .unwrap();
+
+        // Nothing happens unless the strategic warfare rules are in use.
+        advance(&data, 0).await.unwrap();
+        let before = data.get_system_by_id(target.id).await.unwrap();
+        assert_eq!(target.pop, before.pop);
+
+        data.set_rule_option(STRATEGIC_WARFARE, true).await.unwrap();
+        data.insert_raid(&Raid::new(1, 1, target.id, POPULATION))
+            .await
+            .unwrap();
+        let report = advance(&data, 1).await.unwrap();
+        let after = data.get_system_by_id(target.id).await.unwrap();
+        assert_eq!(target.pop - 1, after.pop);
+        assert!(report
+            .entries
+            .iter()
+            .any(|(p, t)| *p == Phase::Combat && t.contains("succeeded")));
+        for empire in [1, 2] {
+            let intel = data.get_intel(empire, 2).await.unwrap();
+            assert!(intel.iter().any(|i| i.kind == NOTICE));
+        }
+    }
+
+    #[tokio::test]
+    async fn shipyard_raid() {
+        let data = init_data().await;
+        data.add_empires(empires()).await.unwrap();
+        data.add_systems(systems()).await.unwrap();
+        data.set_rule_option(STRATEGIC_WARFARE, true).await.unwrap();
+        let mut target = data.get_system_by_name("Tibron").await.unwrap();
+        target.owner = 2;
+        target.ind = 2;
+        data.update_system(&target).await.unwrap();
+        data.insert_facility(&Facility::new(target.id, SHIPYARD))
+            .await
+            .unwrap();
+
+        let mut stype = ShipType::new("Marauder", "Cruiser", 1);
+        stype.atk = 20;
+        stype.def = 3;
+        let item = data.insert_ship_type(&stype).await.unwrap();
+        data.finish_build(&Build::new(1, target.id, "Ship", item, ""))
+            .await
+            .unwrap();
+        // Only the shipyard lets Tibron build all this.
+        let mut b = Build::new(2, target.id, "Ship", item, "");
+        b.cost = 5;
+        b.turns = 5;
+        data.insert_build(&b).await.unwrap();
+        data.insert_raid(&Raid::new(1, 0, target.id, SHIPYARDS))
+            .await
+            .unwrap();
+
+        let report = advance(&data, 0).await.unwrap();
+        assert!(data.get_facilities().await.unwrap()[0].crip);
+        assert!(report
+            .entries
+            .iter()
+            .any(|(p, t)| *p == Phase::Construction && t.contains("5 EP queued at Tibron")));
+        let intel = data.get_intel(2, 1).await.unwrap();
+        assert!(intel.iter().any(|i| i.text.contains("is knocked out")));
+    }
+
+    #[tokio::test]
+    async fn rebellion() {
+        let data = init_data().await;
+        data.add_empires(empires()).await.unwrap();
+        data.add_systems(systems()).await.unwrap();
+        let mut sys = data.get_system_by_name("Tibron").await.unwrap();
+        sys.mor = 2;
+        sys.fails = 1;
+        data.update_system(&sys).await.unwrap();
+        data.apply_invasion(sys.id, Some(2), &[], &[])
+            .await
+            .unwrap();
+        data.set_morale_modifier(2, -1).await.unwrap();
+
+        let report = advance(&data, 0).await.unwrap();
+        let after = data.get_system_by_id(sys.id).await.unwrap();
+        assert_eq!(0, after.mor);
+        assert_eq!(0, after.fails);
+        assert!(report
+            .entries
+            .iter()
+            .any(|(p, t)| *p == Phase::Morale && t.contains("REBELLION: Tibron")));
+    }
+
+    #[tokio::test]
+    async fn construction_completes() {
+        let data = init_data().await;
+        data.add_empires(empires()).await.unwrap();
+        data.add_systems(systems()).await.unwrap();
+        let mut b = Build::new(1, 1, "Ground", 1, "Militia");
+        b.turns = 2;
+        data.insert_build(&b).await.unwrap();
+
+        advance(&data, 0).await.unwrap();
+        assert_eq!(1, data.get_builds().await.unwrap()[0].turns);
+
+        let report = advance(&data, 1).await.unwrap();
+        assert!(data.get_builds().await.unwrap().is_empty());
+        assert!(report
+            .entries
+            .iter()
+            .any(|(p, t)| *p == Phase::Construction && t.contains("Militia")));
+    }
+
+    #[tokio::test]
+    async fn development_and_colonies() {
+        let data = init_data().await;
+        data.add_empires(empires()).await.unwrap();
+        data.add_systems(systems()).await.unwrap();
+        let mut home = data.get_system_by_name("Senor Prime").await.unwrap();
+        home.owner = 1;
+        data.update_system(&home).await.unwrap();
+        let tibron = data.get_system_by_name("Tibron").await.unwrap();
+        let zevrch = data.get_system_by_name("Zev'rch").await.unwrap();
+        let mut ark = ShipType::new("Ark", "Frigate", 2);
+        ark.cap = 1;
+        let ark = data.insert_ship_type(&ark).await.unwrap();
+        data.finish_build(&Build::new(2, tibron.id, "Ship", ark, ""))
+            .await
+            .unwrap();
+        for (empire, system, target) in [
+            (1, home.id, development::POPULATION),
+            (2, tibron.id, development::COLONY),
+            (3, zevrch.id, development::COLONY),
+        ] {
+            data.insert_development(&Development::new(empire, 0, system, target, 5))
+                .await
+                .unwrap();
+        }
+        // Development is paid for as it is ordered. Empires are listed by
+        // name, so look the Kili up by ID.
+        let kili = |v: Vec<Empire>| v.into_iter().find(|e| e.id == 3).unwrap().treasury;
+        let treasury = kili(data.get_empires().await.unwrap());
+        assert_eq!(-5, treasury);
+
+        let report = advance(&data, 0).await.unwrap();
+        let entries: Vec<&String> = report
+            .entries
+            .iter()
+            .filter(|(p, _)| *p == Phase::Construction)
+            .map(|(_, t)| t)
+            .collect();
+        assert!(entries
+            .iter()
+            .any(|t| t.contains("to raise the population of Senor Prime")));
+        assert!(entries
+            .iter()
+            .any(|t| t.contains("founded a colony at Tibron")));
+        assert!(entries.iter().any(|t| t.contains("refunded")));
+
+        let after = data.get_system_by_id(tibron.id).await.unwrap();
+        assert_eq!((2, 1), (after.owner, after.pop));
+        assert!(data.get_ships_by_class(ark).await.unwrap().is_empty());
+        // The Kili have no income or upkeep, so only the refund shows.
+        assert_eq!(treasury + 5, kili(data.get_empires().await.unwrap()));
+    }
+
+    #[tokio::test]
+    async fn repairs() {
+        let data = init_data().await;
+        data.add_empires(empires()).await.unwrap();
+        data.add_systems(systems()).await.unwrap();
+        // Tibron's industry of 3 covers one cruiser costing 6.
+        let mut home = data.get_system_by_name("Tibron").await.unwrap();
+        home.owner = 1;
+        data.update_system(&home).await.unwrap();
+        let mut stype = ShipType::new("Avenger", "Cruiser", 1);
+        stype.cost = 6;
+        let item = data.insert_ship_type(&stype).await.unwrap();
+        for _ in 0..2 {
+            data.finish_build(&Build::new(1, home.id, "Ship", item, ""))
+                .await
+                .unwrap();
+        }
+        let ships: Vec<i64> = data
+            .get_ships_by_class(item)
+            .await
+            .unwrap()
+            .iter()
+            .map(|s| s.id)
+            .collect();
+        data.apply_battle(&ships, &[]).await.unwrap();
+        data.set_repair_priority(ships[1], 1).await.unwrap();
+        for ship in &ships {
+            data.insert_build(&Build::new(
+                1,
+                home.id,
+                construction::REPAIR,
+                *ship,
+                "Avenger",
+            ))
+            .await
+            .unwrap();
+        }
+
+        let report = advance(&data, 0).await.unwrap();
+        let after = data.get_ships_by_class(item).await.unwrap();
+        assert!(after.iter().any(|s| s.id == ships[0] && s.crip));
+        assert!(after.iter().any(|s| s.id == ships[1] && !s.crip));
+        assert!(report
+            .entries
+            .iter()
+            .any(|(p, t)| *p == Phase::Construction && t.contains("left unrepaired")));
+    }
+
+    #[tokio::test]
+    async fn trade_income() {
+        let data = init_data().await;
+        data.add_empires(empires()).await.unwrap();
+        data.add_systems(systems()).await.unwrap();
+        for lane in lanes() {
+            data.insert_lane(&lane).await.unwrap();
+        }
+        let mut home = data.get_system_by_name("Senor Prime").await.unwrap();
+        home.owner = 1;
+        data.update_system(&home).await.unwrap();
+        let mut colony = data.get_system_by_name("Zev'rch").await.unwrap();
+        colony.owner = 2;
+        data.update_system(&colony).await.unwrap();
+        data.insert_treaty(&Treaty::new(1, 2, "Trade", 0, None))
+            .await
+            .unwrap();
+
+        // The Humans earn a tenth of Senor Prime's productivity of 15.
+        let report = advance(&data, 0).await.unwrap();
+        let treasury: BTreeMap<i64, i32> = data
+            .get_empires()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|e| (e.id, e.treasury))
+            .collect();
+        assert_eq!(
+            EmpireIncome::new(&[colony], 0, &SecondEdition).gross() + 1,
+            treasury[&2]
+        );
+        assert!(report
+            .entries
+            .iter()
+            .any(|(p, t)| *p == Phase::Income && t.ends_with("1 of it from trade")));
+    }
+
+    #[tokio::test]
+    async fn espionage() {
+        let data = init_data().await;
+        data.add_empires(empires()).await.unwrap();
+        data.set_tech(2, 2, 0).await.unwrap();
+        // Enough points always succeed, and too few against a strong
+        // counter-espionage always fail.
+        for (empire, target, mission, points) in [
+            (1, 2, espionage::STEAL_TECH, 15),
+            (2, 1, espionage::REVEAL_FLEETS, 1),
+            (1, 1, espionage::COUNTER_ESPIONAGE, 20),
+        ] {
+            data.insert_operation(&Operation::new(empire, 0, target, mission, points))
+                .await
+                .unwrap();
+        }
+
+        let report = advance(&data, 0).await.unwrap();
+        let status = data.get_tech_status(1).await.unwrap();
+        let senorian = status.iter().find(|t| t.empire == 1).unwrap();
+        assert_eq!((0, 5), (senorian.tech, senorian.points));
+        let lines: Vec<&String> = report
+            .entries
+            .iter()
+            .filter(|(p, _)| *p == Phase::Intelligence)
+            .map(|(_, t)| t)
+            .collect();
+        assert_eq!(2, lines.len());
+        assert!(lines[1].contains("caught"));
+        let intel = data.get_intel(1, 1).await.unwrap();
+        assert_eq!(2, intel.len());
+        assert!(intel.iter().all(|i| i.kind == NOTICE));
+    }
+
+    #[tokio::test]
+    async fn treaty_lapses() {
+        let data = init_data().await;
+        data.add_empires(empires()).await.unwrap();
+        data.insert_treaty(&Treaty::new(1, 2, "Armistice", 0, Some(1)))
+            .await
+            .unwrap();
+
+        advance(&data, 0).await.unwrap();
+        assert_eq!(1, data.get_treaties().await.unwrap().len());
+
+        let report = advance(&data, 1).await.unwrap();
+        assert!(data.get_treaties().await.unwrap().is_empty());
+        assert!(report.entries.iter().any(|(p, _)| *p == Phase::Diplomacy));
+        assert_eq!(1, data.get_intel(1, 2).await.unwrap().len());
+        assert_eq!(1, data.get_intel(2, 2).await.unwrap().len());
+    }
+}
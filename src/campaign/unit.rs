@@ -15,43 +15,215 @@
 
 //! Interface to all unit types: ships, ground, stations, etc.
 
-#[allow(unused)]
-#[derive(sqlx::FromRow)]
-struct GroundType {
-    id: i64,
-    name: String,
-    abbr: String,
-    cost: i32,
-    atk: i32,
-    def: i32,
+use std::fmt;
+
+#[derive(sqlx::FromRow, Clone, Debug, PartialEq, Eq)]
+pub struct GroundType {
+    pub id: i64,
+    pub name: String,
+    pub abbr: String,
+    pub cost: i32,
+    pub atk: i32,
+    pub def: i32,
 }
 
 impl GroundType {}
 
-#[allow(unused)]
-#[derive(sqlx::FromRow)]
-struct GroundUnit {
-    id: i64,
-    gtype: i64,
-    loc: i64,
+/// A ground unit garrisoned at a system, with its type's name and ratings.
+#[derive(sqlx::FromRow, Clone, Debug, PartialEq, Eq)]
+pub struct GroundUnit {
+    pub id: i64,
+    pub gtype: i64,
+    pub loc: i64,
+    #[sqlx(default)]
+    pub name: String,
+    #[sqlx(default)]
+    pub abbr: String,
+    #[sqlx(default)]
+    pub atk: i32,
+    #[sqlx(default)]
+    pub def: i32,
+}
+
+impl GroundUnit {
+    /// Convert to string as a row of tab-separated fields.
+    pub fn as_row(&self) -> String {
+        format!(
+            "{}\t{}\t{}\t{}\t{}",
+            self.id, self.name, self.abbr, self.atk, self.def
+        )
+    }
+}
+
+/// A flight of fighters, carried aboard a ship or based at a system. Where
+/// it is, and the name of its carrier or base, are only read from the store.
+#[derive(sqlx::FromRow, Clone, Debug, PartialEq, Eq)]
+pub struct Flight {
+    pub id: i64,
+    pub owner: i64,
+    pub carrier: Option<i64>,
+    pub base: Option<i64>,
+    pub atk: i32,
+    pub def: i32,
+    pub crip: bool,
+    #[sqlx(default)]
+    pub location: i64,
+    #[sqlx(default)]
+    pub station: String,
 }
 
-impl GroundUnit {}
+impl Flight {
+    /// Create a new flight at the given location, not yet assigned to a
+    /// carrier or base.
+    pub fn new(owner: i64, location: i64, atk: i32, def: i32) -> Self {
+        Self {
+            id: 0,
+            owner,
+            carrier: None,
+            base: None,
+            atk,
+            def,
+            crip: false,
+            location,
+            station: String::new(),
+        }
+    }
+
+    /// Convert to string as a row of tab-separated fields.
+    pub fn as_row(&self) -> String {
+        format!(
+            "{}\t{}\t{}\t{}\t{}",
+            self.id,
+            self.station,
+            self.atk,
+            self.def,
+            if self.crip { "Crippled" } else { "Active" }
+        )
+    }
+}
 
 #[allow(unused)]
-#[derive(sqlx::FromRow)]
-struct ShipType {
-    id: i64,
-    class: String,
-    hull: String,
-    cost: i32,
-    cr: i32,
-    atk: i32,
-    def: i32,
-    cap: i32,
+#[derive(sqlx::FromRow, Clone, Debug, PartialEq, Eq)]
+pub struct ShipType {
+    pub id: i64,
+    pub class: String,
+    pub hull: String,
+    pub cost: i32,
+    pub cr: i32,
+    pub atk: i32,
+    pub def: i32,
+    pub cap: i32,
+    pub speed: i32,
+    pub empire: i64,
+    pub obsolete: Option<i32>,
+    pub retired: bool,
+    #[sqlx(default)]
+    pub empire_name: String,
+    #[sqlx(default)]
+    pub active: i32,
 }
 
-impl ShipType {}
+impl ShipType {
+    /// Create a new ship class on the given hull. The cost is left at zero
+    /// until it is calculated.
+    pub fn new(class: &str, hull: &str, empire: i64) -> Self {
+        Self {
+            id: 0,
+            class: class.to_string(),
+            hull: hull.to_string(),
+            cost: 0,
+            cr: 0,
+            atk: 0,
+            def: 0,
+            cap: 0,
+            speed: 1,
+            empire,
+            obsolete: None,
+            retired: false,
+            empire_name: String::new(),
+            active: 0,
+        }
+    }
+
+    /// Construction cost of the class: the hull size, plus half the combined
+    /// attack and defense rounded up, plus the carrier capacity.
+    pub fn construction_cost(&self, hull: &Hull) -> i32 {
+        hull.size + (self.atk + self.def + 1) / 2 + self.cap
+    }
+
+    /// Check the class against its hull. Attack, defense and capacity share
+    /// the hull's stat budget, and the command rating cannot exceed the hull
+    /// size.
+    pub fn validate(&self, hull: &Hull) -> Result<(), String> {
+        if self.class.trim().is_empty() {
+            return Err("The class must have a name".to_string());
+        }
+        if self.hull != hull.name {
+            return Err(format!(
+                "{} is not built on a {} hull",
+                self.class, hull.name
+            ));
+        }
+        if self.cr < 0 || self.atk < 0 || self.def < 0 || self.cap < 0 {
+            return Err("Ratings cannot be negative".to_string());
+        }
+        if self.speed < 1 {
+            return Err("Speed must be at least 1".to_string());
+        }
+        if self.cr > hull.size {
+            return Err(format!(
+                "CR {} exceeds the {} hull size of {}",
+                self.cr, hull.name, hull.size
+            ));
+        }
+        let used = self.atk + self.def + self.cap;
+        if used > hull.budget() {
+            return Err(format!(
+                "ATK + DEF + capacity of {} exceeds the {} budget of {}",
+                used,
+                hull.name,
+                hull.budget()
+            ));
+        }
+        Ok(())
+    }
+
+    /// Convert to string as a row of tab-separated fields.
+    pub fn as_row(&self) -> String {
+        format!(
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            self.empire_name,
+            self.class,
+            self.hull,
+            self.cost,
+            self.cr,
+            self.atk,
+            self.def,
+            self.cap,
+            self.speed,
+            self.active,
+            self.status()
+        )
+    }
+
+    /// Whether the class is in production, obsolete, or retired. A class is
+    /// obsolete once its empire designs a newer class on the same hull, but
+    /// can still be built until it is retired.
+    pub fn status(&self) -> String {
+        if self.retired {
+            "Retired".to_string()
+        } else if let Some(turn) = self.obsolete {
+            format!("Obsolete ({})", turn)
+        } else {
+            "Current".to_string()
+        }
+    }
+
+    /// Label identifying the class and its empire.
+    pub fn label(&self) -> String {
+        format!("{}: {} ({})", self.empire_name, self.class, self.hull)
+    }
+}
 
 #[allow(unused)]
 #[derive(sqlx::FromRow)]
@@ -65,18 +237,299 @@ struct Ship {
 
 impl Ship {}
 
+#[derive(sqlx::FromRow, Clone, Debug, PartialEq, Eq)]
+pub struct Fleet {
+    pub id: i64,
+    pub name: String,
+    pub owner: i64,
+    pub location: i64,
+    pub speed: i32,
+}
+
+impl Fleet {
+    /// Create a new fleet.
+    pub fn new(name: &str, owner: i64, location: i64) -> Self {
+        Self {
+            id: 0,
+            name: name.to_string(),
+            owner,
+            location,
+            speed: 0,
+        }
+    }
+}
+
+/// Where a ship is: its fleet and the fleet's location. The fleet's owner
+/// and location, and the class's cost and carrier capacity, are only read
+/// for a fleet's ships.
+#[derive(sqlx::FromRow, Clone, Debug, PartialEq, Eq)]
+pub struct ShipLocation {
+    pub id: i64,
+    pub class: String,
+    pub fleet_name: String,
+    pub location_name: String,
+    pub crip: bool,
+    pub moth: bool,
+    #[sqlx(default)]
+    pub owner: i64,
+    #[sqlx(default)]
+    pub location: i64,
+    #[sqlx(default)]
+    pub cost: i32,
+    #[sqlx(default)]
+    pub cap: i32,
+}
+
+impl ShipLocation {
+    /// Convert to string as a row of tab-separated fields.
+    pub fn as_row(&self) -> String {
+        format!(
+            "{}\t{}\t{}\t{}\t{}",
+            self.id,
+            self.class,
+            self.fleet_name,
+            self.location_name,
+            ShipStatus::from_flags(self.crip, self.moth)
+        )
+    }
+}
+
+/// Condition of a fleet: how many ships it has, how many are crippled, their
+/// combined attack and defense, and the maintenance they cost.
+#[derive(sqlx::FromRow, Clone, Debug, PartialEq, Eq)]
+pub struct FleetSummary {
+    pub id: i64,
+    pub name: String,
+    pub owner: i64,
+    pub location: i64,
+    pub speed: i32,
+    pub owner_name: String,
+    pub location_name: String,
+    pub ships: i32,
+    pub crippled: i32,
+    pub atk: i32,
+    pub def: i32,
+    #[sqlx(default)]
+    pub maint: i32,
+    #[sqlx(default)]
+    pub jumps: i32,
+}
+
+impl FleetSummary {
+    /// The fleet being summarized.
+    pub fn fleet(&self) -> Fleet {
+        Fleet {
+            id: self.id,
+            name: self.name.clone(),
+            owner: self.owner,
+            location: self.location,
+            speed: self.speed,
+        }
+    }
+
+    /// Convert to string as a row of tab-separated fields.
+    pub fn as_row(&self) -> String {
+        format!(
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            self.name,
+            self.owner_name,
+            self.location_name,
+            self.ships,
+            self.crippled,
+            self.atk,
+            self.def,
+            self.maint,
+            self.jumps
+        )
+    }
+}
+
+/// Hull class reference data. Maintenance is the per-turn cost of one active
+/// ship of the hull.
 #[allow(unused)]
-#[derive(sqlx::FromRow)]
-struct Fleet {
-    id: i64,
-    name: String,
-    owner: i64,
-    location: i64,
+#[derive(sqlx::FromRow, Clone, Debug, PartialEq, Eq)]
+pub struct Hull {
+    pub name: String,
+    pub size: i32,
+    pub maint: i32,
+    /// Tech level needed to build on the hull.
+    pub tech: i32,
+}
+
+impl Hull {
+    /// Points of attack, defense and capacity a class on this hull may have.
+    pub fn budget(&self) -> i32 {
+        2 * self.size
+    }
+}
+
+/// Condition of a ship for maintenance purposes.
+#[allow(unused)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShipStatus {
+    Active,
+    Crippled,
+    Mothballed,
+}
+
+impl ShipStatus {
+    /// Status from the ship's crippled and mothballed flags. A mothballed ship
+    /// counts as mothballed even if it is also crippled.
+    #[allow(unused)]
+    pub fn from_flags(crip: bool, moth: bool) -> Self {
+        if moth {
+            Self::Mothballed
+        } else if crip {
+            Self::Crippled
+        } else {
+            Self::Active
+        }
+    }
+}
+
+impl fmt::Display for ShipStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Active => "Active",
+                Self::Crippled => "Crippled",
+                Self::Mothballed => "Mothballed",
+            }
+        )
+    }
 }
 
-impl Fleet {}
+/// One line of a maintenance breakdown: all of an empire's ships sharing a
+/// hull and status.
+#[allow(unused)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MaintenanceLine {
+    pub hull: String,
+    pub status: ShipStatus,
+    pub count: i32,
+    pub each: i32,
+}
+
+impl MaintenanceLine {
+    /// Maintenance due for the whole line.
+    pub fn total(&self) -> i32 {
+        self.count * self.each
+    }
+}
+
+/// Maintenance due from an empire, broken down by hull and status.
+#[allow(unused)]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Maintenance {
+    pub lines: Vec<MaintenanceLine>,
+}
+
+impl Maintenance {
+    /// Total maintenance due.
+    pub fn total(&self) -> i32 {
+        self.lines.iter().map(|l| l.total()).sum()
+    }
+}
+
+impl fmt::Display for Maintenance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Hull\tStatus\tShips\tEach\tTotal")?;
+        for l in &self.lines {
+            writeln!(
+                f,
+                "{}\t{}\t{}\t{}\t{}",
+                l.hull,
+                l.status,
+                l.count,
+                l.each,
+                l.total()
+            )?;
+        }
+        write!(f, "Total maintenance: {}", self.total())
+    }
+}
 
 #[cfg(test)]
 mod tests {
-    // TODO Add tests
+    use super::{Hull, Maintenance, MaintenanceLine, ShipStatus, ShipType};
+
+    fn cruiser() -> Hull {
+        Hull {
+            name: "Cruiser".to_string(),
+            size: 5,
+            maint: 2,
+            tech: 0,
+        }
+    }
+
+    #[test]
+    fn construction_cost() {
+        let mut t = ShipType::new("Avenger", "Cruiser", 1);
+        t.atk = 4;
+        t.def = 3;
+        t.cap = 1;
+        assert_eq!(5 + 4 + 1, t.construction_cost(&cruiser()));
+    }
+
+    #[test]
+    fn validate_ship_type() {
+        let mut t = ShipType::new("Avenger", "Cruiser", 1);
+        t.cr = 5;
+        t.atk = 5;
+        t.def = 5;
+        assert_eq!(Ok(()), t.validate(&cruiser()));
+
+        t.cap = 1;
+        assert!(t.validate(&cruiser()).is_err());
+        t.cap = 0;
+        t.cr = 6;
+        assert!(t.validate(&cruiser()).is_err());
+        t.cr = 5;
+        t.speed = 0;
+        assert!(t.validate(&cruiser()).is_err());
+        t.speed = 1;
+        t.hull = "Frigate".to_string();
+        assert!(t.validate(&cruiser()).is_err());
+    }
+
+    #[test]
+    fn class_status() {
+        let mut t = ShipType::new("Avenger", "Cruiser", 1);
+        assert_eq!("Current", t.status());
+        t.obsolete = Some(12);
+        assert_eq!("Obsolete (12)", t.status());
+        t.retired = true;
+        assert_eq!("Retired", t.status());
+    }
+
+    #[test]
+    fn status_from_flags() {
+        assert_eq!(ShipStatus::Active, ShipStatus::from_flags(false, false));
+        assert_eq!(ShipStatus::Crippled, ShipStatus::from_flags(true, false));
+        assert_eq!(ShipStatus::Mothballed, ShipStatus::from_flags(false, true));
+        assert_eq!(ShipStatus::Mothballed, ShipStatus::from_flags(true, true));
+    }
+
+    #[test]
+    fn maintenance_total() {
+        let m = Maintenance {
+            lines: vec![
+                MaintenanceLine {
+                    hull: "Frigate".to_string(),
+                    status: ShipStatus::Active,
+                    count: 4,
+                    each: 1,
+                },
+                MaintenanceLine {
+                    hull: "Cruiser".to_string(),
+                    status: ShipStatus::Crippled,
+                    count: 2,
+                    each: 1,
+                },
+            ],
+        };
+        assert_eq!(6, m.total());
+    }
 }
@@ -0,0 +1,539 @@
+// Copyright 2022 David Terhune
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Validation of orders before the turn is processed.
+//!
+//! Orders written by players are read back into what they ask for and
+//! checked against the campaign as it stands, so that the moderator can
+//! return illegal ones before resolving the turn. Free-form orders cannot be
+//! read and are left to the moderator.
+
+use super::diplomacy::Treaty;
+use super::empire::Empire;
+use super::escort::{self, Escort};
+use super::government;
+use super::lane::Lane;
+use super::movement;
+use super::orders::{self, parse_move, Order, CONSTRUCTION, DIPLOMACY, ECONOMIC, MOVEMENT};
+use super::shipyard;
+use super::system::System;
+use super::unit::{FleetSummary, ShipLocation, ShipType};
+
+/// What an empire's orders are checked against.
+pub struct OrderContext<'a> {
+    pub empire: &'a Empire,
+    pub systems: &'a [System],
+    pub fleets: &'a [FleetSummary],
+    pub classes: &'a [ShipType],
+    pub lanes: &'a [Lane],
+    pub empires: &'a [Empire],
+    pub treaties: &'a [Treaty],
+    pub escorts: &'a [Escort],
+    pub ships: &'a [ShipLocation],
+}
+
+/// An order that cannot be carried out, and why.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Rejection {
+    pub order: Order,
+    pub reason: String,
+}
+
+/// Check an empire's orders, returning those that are illegal. Orders are
+/// paid for phase by phase in the order given, so once the treasury runs
+/// out every later order that costs anything is rejected for want of funds.
+pub fn check(orders: &[Order], ctx: &OrderContext) -> Vec<Rejection> {
+    let mut left = ctx.empire.treasury;
+    let mut rejected = Vec::new();
+    for group in orders::by_phase(orders) {
+        for order in group.orders {
+            match check_order(&order, ctx) {
+                Ok(cost) if cost > left => rejected.push(Rejection {
+                    order,
+                    reason: format!(
+                        "Insufficient funds: costs {} EP with {} EP left",
+                        cost,
+                        left.max(0)
+                    ),
+                }),
+                Ok(cost) => left -= cost,
+                Err(reason) => rejected.push(Rejection { order, reason }),
+            }
+        }
+    }
+    rejected
+}
+
+/// Validation report of an empire's rejected orders.
+pub fn report(empire: &str, rejected: &[Rejection]) -> String {
+    if rejected.is_empty() {
+        return format!("All orders from {} are valid.\n", empire);
+    }
+    let mut report = format!("{} illegal orders from {}:\n", rejected.len(), empire);
+    for r in rejected {
+        report.push_str(&format!(
+            "\n{}: {}\n    {}\n",
+            r.order.phase_name(),
+            r.order.text,
+            r.reason
+        ));
+    }
+    report
+}
+
+// Check one order, returning what it costs.
+fn check_order(order: &Order, ctx: &OrderContext) -> Result<i32, String> {
+    let empire = ctx.empire.id;
+    let not_understood = || "Order not understood".to_string();
+    match order.phase.as_str() {
+        ECONOMIC => {
+            let (amount, sys) = parse_spend(&order.text).ok_or_else(not_understood)?;
+            let sys = match sys {
+                Some(name) => Some(find_system(ctx, name)?),
+                None => None,
+            };
+            orders::economic(empire, order.turn, sys, amount).map(|o| o.cost)
+        }
+        CONSTRUCTION if parse_ship(&order.text).is_some() => {
+            let (action, id) = parse_ship(&order.text).ok_or_else(not_understood)?;
+            let ship = ctx
+                .ships
+                .iter()
+                .find(|s| s.id == id && s.owner == empire)
+                .ok_or_else(|| format!("Unknown ship #{}", id))?;
+            let sys = ctx
+                .systems
+                .iter()
+                .find(|s| s.id == ship.location)
+                .ok_or_else(|| format!("{} #{} is not in any system", ship.class, ship.id))?;
+            orders::shipyard(empire, order.turn, ship, sys, action).map(|o| o.cost)
+        }
+        CONSTRUCTION => {
+            let (count, class, sys) = parse_build(&order.text).ok_or_else(not_understood)?;
+            let sys = find_system(ctx, sys)?;
+            let class = ctx
+                .classes
+                .iter()
+                .find(|c| c.empire == empire && c.class.eq_ignore_ascii_case(class))
+                .ok_or_else(|| format!("Unknown ship class {}", class))?;
+            orders::construction(empire, order.turn, sys, class, count).map(|o| o.cost)
+        }
+        MOVEMENT if order.text.trim().starts_with("Escort ") => {
+            let (convoy, fleet) = parse_escort(&order.text).ok_or_else(not_understood)?;
+            let convoy = find_fleet(ctx, convoy)?;
+            let fleet = find_fleet(ctx, fleet)?;
+            match escort::jumps_between(ctx.lanes, fleet.location, convoy.location) {
+                Some(jumps) if jumps <= fleet.jumps => (),
+                _ => {
+                    return Err(format!(
+                        "{} cannot reach {} at {} this turn",
+                        fleet.name, convoy.name, convoy.location_name
+                    ))
+                }
+            }
+            // Checked as if the fleet had already made the rendezvous.
+            let met = FleetSummary {
+                location: convoy.location,
+                ..fleet.clone()
+            };
+            escort::check(&met, convoy, ctx.escorts).map(|_| 0)
+        }
+        MOVEMENT => {
+            let (fleet, from, route) = parse_move(&order.text).ok_or_else(not_understood)?;
+            let fleet = find_fleet(ctx, fleet)?;
+            if let Some(convoy) = escort::escorting(ctx.escorts, fleet.id) {
+                let convoy = ctx.fleets.iter().find(|f| f.id == convoy);
+                return Err(format!(
+                    "{} is escorting {}, and moves with it",
+                    fleet.name,
+                    convoy.map_or("another fleet", |c| c.name.as_str())
+                ));
+            }
+            if let Some(from) = from {
+                if !fleet.location_name.eq_ignore_ascii_case(from) {
+                    return Err(format!(
+                        "{} is at {}, not {}",
+                        fleet.name, fleet.location_name, from
+                    ));
+                }
+            }
+            // A destination alone is reached by the shortest way, over as
+            // many turns as it takes; a route given jump by jump is this
+            // turn's and must be within the fleet's allowance.
+            if let [dest] = route[..] {
+                let sys = find_system(ctx, dest)?;
+                return match movement::path(ctx.lanes, fleet.location, sys.id) {
+                    Some(_) => Ok(0),
+                    None => Err(format!(
+                        "No way from {} to {}",
+                        fleet.location_name, sys.name
+                    )),
+                };
+            }
+            let mut ids = vec![fleet.location];
+            let mut names = vec![fleet.location_name.as_str()];
+            for name in route {
+                let sys = find_system(ctx, name)?;
+                ids.push(sys.id);
+                names.push(sys.name.as_str());
+            }
+            for (step, name) in ids.windows(2).zip(names.windows(2)) {
+                if !ctx.lanes.iter().any(|l| l.connects(step[0], step[1])) {
+                    return Err(format!("No jump lane from {} to {}", name[0], name[1]));
+                }
+            }
+            let jumps = escort::group(ctx.escorts, fleet.id)
+                .iter()
+                .filter_map(|id| ctx.fleets.iter().find(|f| f.id == *id))
+                .map(|f| f.jumps)
+                .min()
+                .unwrap_or(fleet.jumps);
+            movement::validate_route(ctx.lanes, &ids, jumps)?;
+            Ok(0)
+        }
+        DIPLOMACY => {
+            let (ttype, other) = parse_proposal(&order.text).ok_or_else(not_understood)?;
+            let other = ctx
+                .empires
+                .iter()
+                .find(|e| e.name.eq_ignore_ascii_case(other))
+                .ok_or_else(|| format!("Unknown empire {}", other))?;
+            government::check_treaty(ctx.empire, other, ttype)?;
+            orders::diplomacy(empire, order.turn, other, ttype, ctx.treaties).map(|o| o.cost)
+        }
+        _ => Ok(order.cost),
+    }
+}
+
+// Find one of the empire's fleets by name, regardless of case.
+fn find_fleet<'a>(ctx: &'a OrderContext, name: &str) -> Result<&'a FleetSummary, String> {
+    ctx.fleets
+        .iter()
+        .find(|f| f.owner == ctx.empire.id && f.name.eq_ignore_ascii_case(name))
+        .ok_or_else(|| format!("Unknown fleet {}", name))
+}
+
+// Find a system by name, regardless of case.
+fn find_system<'a>(ctx: &'a OrderContext, name: &str) -> Result<&'a System, String> {
+    ctx.systems
+        .iter()
+        .find(|s| s.name.eq_ignore_ascii_case(name))
+        .ok_or_else(|| format!("Unknown system {}", name))
+}
+
+// Read "Spend N EP on research" or "Spend N EP developing SYSTEM".
+fn parse_spend(text: &str) -> Option<(i32, Option<&str>)> {
+    let (amount, rest) = text.trim().strip_prefix("Spend ")?.split_once(" EP ")?;
+    let amount = amount.trim().parse().ok()?;
+    match rest.trim() {
+        "on research" => Some((amount, None)),
+        rest => Some((amount, Some(rest.strip_prefix("developing ")?.trim()))),
+    }
+}
+
+// Read "Build CLASS at SYSTEM" or "Build N × CLASS at SYSTEM", also written
+// with an x for the multiplication sign.
+fn parse_build(text: &str) -> Option<(i32, &str, &str)> {
+    let (what, sys) = text.trim().strip_prefix("Build ")?.rsplit_once(" at ")?;
+    let (count, class) = match what
+        .split_once(" \u{d7} ")
+        .or_else(|| what.split_once(" x "))
+    {
+        Some((n, class)) => (n.trim().parse().ok()?, class),
+        None => (1, what),
+    };
+    Some((count, class.trim(), sys.trim()))
+}
+
+// Read "ACTION [CLASS] #SHIP" for work on a ship, such as "Repair Avenger
+// #12", giving the action and the ship's ID.
+fn parse_ship(text: &str) -> Option<(&str, i64)> {
+    let (action, ship) = text.trim().split_once(' ')?;
+    let action = shipyard::ACTIONS.iter().find(|a| **a == action)?;
+    let (_, id) = ship.rsplit_once('#')?;
+    Some((action, id.trim().parse().ok()?))
+}
+
+// Read "Escort CONVOY with FLEET", giving the convoy and its escort.
+fn parse_escort(text: &str) -> Option<(&str, &str)> {
+    let (convoy, fleet) = text.trim().strip_prefix("Escort ")?.rsplit_once(" with ")?;
+    Some((convoy.trim(), fleet.trim()))
+}
+
+// Read "Propose TYPE treaty to EMPIRE".
+fn parse_proposal(text: &str) -> Option<(&str, &str)> {
+    let (ttype, other) = text
+        .trim()
+        .strip_prefix("Propose ")?
+        .split_once(" treaty to ")?;
+    Some((ttype.trim(), other.trim()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        check, parse_build, parse_escort, parse_move, parse_ship, parse_spend, report, OrderContext,
+    };
+    use crate::campaign::empire::tests::empires;
+    use crate::campaign::escort::Escort;
+    use crate::campaign::government;
+    use crate::campaign::lane::tests::lanes;
+    use crate::campaign::orders::{Order, CONSTRUCTION, DIPLOMACY, ECONOMIC, MOVEMENT};
+    use crate::campaign::system::tests::systems;
+    use crate::campaign::unit::{FleetSummary, ShipLocation, ShipType};
+
+    #[test]
+    fn reading() {
+        assert_eq!(Some((4, None)), parse_spend("Spend 4 EP on research"));
+        assert_eq!(
+            Some((2, Some("Tibron"))),
+            parse_spend("Spend 2 EP developing Tibron")
+        );
+        assert_eq!(None, parse_spend("Spend lots on research"));
+        assert_eq!(
+            Some((1, "Corvette", "Tibron")),
+            parse_build("Build Corvette at Tibron")
+        );
+        assert_eq!(
+            Some((3, "Corvette", "Tibron")),
+            parse_build("Build 3 \u{d7} Corvette at Tibron")
+        );
+        assert_eq!(
+            Some((3, "Corvette", "Tibron")),
+            parse_build("Build 3 x Corvette at Tibron")
+        );
+        assert_eq!(
+            Some(("Home Fleet", None, vec!["Vadurrinia", "Zev'rch"])),
+            parse_move("Move Home Fleet to Zev'rch via Vadurrinia")
+        );
+        assert_eq!(
+            Some(("Home Fleet", Some("Tibron"), vec!["Senor Prime"])),
+            parse_move("Move Home Fleet from Tibron to Senor Prime")
+        );
+        assert_eq!(
+            Some(("Troop Convoy", "Home Fleet")),
+            parse_escort("Escort Troop Convoy with Home Fleet")
+        );
+        assert_eq!(Some(("Repair", 12)), parse_ship("Repair Avenger #12"));
+        assert_eq!(Some(("Scrap", 3)), parse_ship("Scrap #3"));
+        assert_eq!(None, parse_ship("Refit Avenger #12"));
+    }
+
+    #[test]
+    fn illegal_orders() {
+        let mut sys = systems();
+        for (n, s) in sys.iter_mut().enumerate() {
+            s.id = n as i64 + 1;
+            s.owner = 1;
+        }
+        let mut emp = empires();
+        for (n, e) in emp.iter_mut().enumerate() {
+            e.id = n as i64 + 1;
+        }
+        emp[0].treasury = 10;
+        emp[2].traits = government::XENOPHOBIC.to_string();
+        let mut class = ShipType::new("Corvette", "Corvette", 1);
+        class.cost = 4;
+        let fleet = FleetSummary {
+            id: 1,
+            name: "Home Fleet".to_string(),
+            owner: 1,
+            location: 1,
+            speed: 0,
+            owner_name: "Senorian".to_string(),
+            location_name: "Senor Prime".to_string(),
+            ships: 2,
+            crippled: 0,
+            atk: 4,
+            def: 4,
+            maint: 1,
+            jumps: 2,
+        };
+        let lanes = lanes();
+        let ctx = OrderContext {
+            empire: &emp[0],
+            systems: &sys,
+            fleets: &[fleet],
+            classes: &[class],
+            lanes: &lanes,
+            empires: &emp,
+            treaties: &[],
+            escorts: &[],
+            ships: &[],
+        };
+
+        let order = |phase: &str, text: &str| Order::in_phase(1, 3, phase, text, 0);
+        let orders = vec![
+            order(ECONOMIC, "Spend 4 EP on research"),
+            order(CONSTRUCTION, "Build Corvette at Tibron"),
+            order(CONSTRUCTION, "Build Corvette at Zev'rch"),
+            order(CONSTRUCTION, "Build Dreadnought at Tibron"),
+            order(MOVEMENT, "Move Home Fleet to Zev'rch via Vadurrinia"),
+            order(MOVEMENT, "Move Home Fleet from Tibron to Senor Prime"),
+            order(MOVEMENT, "Move Home Fleet to Zev'rch"),
+            order(MOVEMENT, "Move Home Fleet to Xanadu"),
+            order(DIPLOMACY, "Propose Alliance treaty to Human"),
+            order(DIPLOMACY, "Propose Alliance treaty to Xenon"),
+            order(DIPLOMACY, "Propose Trade treaty to Kili"),
+            Order::new(1, 3, "Hold the line"),
+        ];
+        let rejected = check(&orders, &ctx);
+        let reasons: Vec<&str> = rejected.iter().map(|r| r.reason.as_str()).collect();
+        assert_eq!(
+            vec![
+                "Insufficient funds: costs 4 EP with 2 EP left",
+                "Unknown ship class Dreadnought",
+                "Home Fleet is at Senor Prime, not Tibron",
+                "Unknown system Xanadu",
+                "Unknown empire Xenon",
+                "Kili is xenophobic and signs no Trade treaty",
+            ],
+            reasons
+        );
+
+        let text = report("Senorian", &rejected);
+        assert!(text.starts_with("6 illegal orders from Senorian:"));
+        assert!(text.contains("Construction: Build Dreadnought at Tibron\n"));
+        assert_eq!(
+            "All orders from Senorian are valid.\n",
+            report("Senorian", &[])
+        );
+    }
+
+    #[test]
+    fn escort_orders() {
+        let mut sys = systems();
+        for (n, s) in sys.iter_mut().enumerate() {
+            s.id = n as i64 + 1;
+        }
+        let mut emp = empires();
+        emp[0].id = 1;
+        let fleet = |id: i64, name: &str, location: i64, jumps: i32| FleetSummary {
+            id,
+            name: name.to_string(),
+            owner: 1,
+            location,
+            speed: 0,
+            owner_name: "Senorian".to_string(),
+            location_name: sys[location as usize - 1].name.clone(),
+            ships: 1,
+            crippled: 0,
+            atk: 1,
+            def: 1,
+            maint: 1,
+            jumps,
+        };
+        let fleets = [
+            fleet(1, "Convoy", 1, 1),
+            fleet(2, "Guard", 1, 3),
+            fleet(3, "Patrol", 3, 1),
+        ];
+        let lanes = lanes();
+        let escorts = [Escort::new(2, 1)];
+        let ctx = OrderContext {
+            empire: &emp[0],
+            systems: &sys,
+            fleets: &fleets,
+            classes: &[],
+            lanes: &lanes,
+            empires: &emp,
+            treaties: &[],
+            escorts: &escorts,
+            ships: &[],
+        };
+
+        let order = |text: &str| Order::in_phase(1, 3, MOVEMENT, text, 0);
+        let orders = vec![
+            order("Move Convoy to Vadurrinia"),
+            order("Move Convoy to Zev'rch via Vadurrinia"),
+            order("Move Guard to Vadurrinia"),
+            order("Escort Convoy with Patrol"),
+            order("Escort Patrol with Guard"),
+        ];
+        let rejected = check(&orders, &ctx);
+        let reasons: Vec<&str> = rejected.iter().map(|r| r.reason.as_str()).collect();
+        assert_eq!(
+            vec![
+                "Route needs 2 jumps but the fleet can only make 1",
+                "Guard is escorting Convoy, and moves with it",
+                "Patrol cannot reach Convoy at Senor Prime this turn",
+                "Guard is already escorting a fleet",
+            ],
+            reasons
+        );
+    }
+
+    #[test]
+    fn ship_orders() {
+        let mut sys = systems();
+        for (n, s) in sys.iter_mut().enumerate() {
+            s.id = n as i64 + 1;
+            s.owner = 1;
+            s.ind = 0;
+        }
+        sys[0].ind = 3;
+        let mut emp = empires();
+        emp[0].id = 1;
+        emp[0].treasury = 10;
+        let ship = |id: i64, location: i64, crip: bool, moth: bool| ShipLocation {
+            id,
+            class: "Avenger".to_string(),
+            fleet_name: "Home Fleet".to_string(),
+            location_name: sys[location as usize - 1].name.clone(),
+            crip,
+            moth,
+            owner: 1,
+            location,
+            cost: 8,
+            cap: 0,
+        };
+        let ships = [
+            ship(1, 1, true, false),
+            ship(2, 2, true, false),
+            ship(3, 1, false, true),
+        ];
+        let lanes = lanes();
+        let ctx = OrderContext {
+            empire: &emp[0],
+            systems: &sys,
+            fleets: &[],
+            classes: &[],
+            lanes: &lanes,
+            empires: &emp,
+            treaties: &[],
+            escorts: &[],
+            ships: &ships,
+        };
+
+        let order = |text: &str| Order::in_phase(1, 3, CONSTRUCTION, text, 0);
+        let orders = vec![
+            order("Repair Avenger #1"),
+            order("Repair Avenger #2"),
+            order("Activate Avenger #3"),
+            order("Mothball Avenger #3"),
+            order("Scrap Avenger #9"),
+        ];
+        let rejected = check(&orders, &ctx);
+        let reasons: Vec<&str> = rejected.iter().map(|r| r.reason.as_str()).collect();
+        assert_eq!(
+            vec![
+                "Vadurrinia has no shipyard to repair Avenger #2",
+                "Avenger #3 is already mothballed",
+                "Unknown ship #9",
+            ],
+            reasons
+        );
+    }
+}
@@ -0,0 +1,385 @@
+// Copyright 2022 David Terhune
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Wars between empires, and what they cost each side.
+//!
+//! Two empires are at war from the first battle or invasion they fight
+//! until they sign a treaty. The units each loses count against that war,
+//! so peace talks can go by what the fighting actually cost.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use super::combat::Combatant;
+use super::facility;
+use super::flight;
+use super::unit::GroundUnit;
+
+/// A ship lost or crippled.
+pub const SHIP: &str = "Ship";
+
+/// A facility lost or crippled.
+pub const FACILITY: &str = "Facility";
+
+/// A ground unit lost.
+pub const GROUND: &str = "Ground";
+
+/// A war between two empires, the lower ID first. A war still being fought
+/// has not ended.
+#[derive(sqlx::FromRow, Clone, Debug, PartialEq, Eq)]
+pub struct War {
+    pub id: i64,
+    pub a: i64,
+    pub b: i64,
+    pub started: i32,
+    pub ended: Option<i32>,
+    #[sqlx(default)]
+    pub a_name: String,
+    #[sqlx(default)]
+    pub b_name: String,
+}
+
+impl War {
+    /// Name of the war for reports.
+    pub fn name(&self) -> String {
+        format!("{}-{} War", self.a_name, self.b_name)
+    }
+
+    /// Convert to string as a row of tab-separated fields.
+    pub fn as_row(&self) -> String {
+        format!(
+            "{}\t{}\t{}",
+            self.name(),
+            self.started,
+            match self.ended {
+                Some(t) => t.to_string(),
+                None => "Ongoing".to_string(),
+            }
+        )
+    }
+}
+
+/// A unit an empire lost or had crippled fighting an enemy. Its cost is
+/// counted only if it was destroyed. The war is set by the store.
+#[derive(sqlx::FromRow, Clone, Debug, PartialEq, Eq)]
+pub struct Casualty {
+    pub id: i64,
+    pub war: i64,
+    pub turn: i32,
+    pub empire: i64,
+    pub enemy: i64,
+    pub kind: String,
+    pub destroyed: bool,
+    pub cost: i32,
+}
+
+impl Casualty {
+    /// Create a new casualty.
+    pub fn new(turn: i32, empire: i64, enemy: i64, kind: &str, destroyed: bool, cost: i32) -> Self {
+        Self {
+            id: 0,
+            war: 0,
+            turn,
+            empire,
+            enemy,
+            kind: kind.to_string(),
+            destroyed,
+            cost,
+        }
+    }
+}
+
+/// What a war has cost one empire.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Attrition {
+    pub crippled: i32,
+    pub hulls: i32,
+    pub flights: i32,
+    pub facilities: i32,
+    pub ground: i32,
+    pub ep: i32,
+}
+
+impl Attrition {
+    /// Count a casualty.
+    pub fn add(&mut self, c: &Casualty) {
+        if !c.destroyed {
+            self.crippled += 1;
+            return;
+        }
+        match c.kind.as_str() {
+            SHIP => self.hulls += 1,
+            FACILITY => self.facilities += 1,
+            GROUND => self.ground += 1,
+            _ => self.flights += 1,
+        }
+        self.ep += c.cost;
+    }
+}
+
+impl fmt::Display for Attrition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} hulls, {} flights, {} facilities and {} ground units lost ({} EP); {} crippled",
+            self.hulls, self.flights, self.facilities, self.ground, self.ep, self.crippled
+        )
+    }
+}
+
+/// The attrition of each empire in each war, by war and empire ID.
+pub fn tally(casualties: &[Casualty]) -> BTreeMap<(i64, i64), Attrition> {
+    let mut totals: BTreeMap<(i64, i64), Attrition> = BTreeMap::new();
+    for c in casualties {
+        totals.entry((c.war, c.empire)).or_default().add(c);
+    }
+    totals
+}
+
+/// The pairs of empires fighting in a battle, the lower ID first.
+pub fn fighting(ships: &[Combatant]) -> Vec<(i64, i64)> {
+    let mut owners: Vec<i64> = ships.iter().map(|c| c.owner).filter(|o| *o != 0).collect();
+    owners.sort_unstable();
+    owners.dedup();
+    let mut pairs = Vec::new();
+    for (i, a) in owners.iter().enumerate() {
+        for b in &owners[i + 1..] {
+            pairs.push((*a, *b));
+        }
+    }
+    pairs
+}
+
+/// The enemy a unit of the given owner was fighting in a battle: the other
+/// empire with the most units present, the lower ID on a tie.
+pub fn enemy(owner: i64, ships: &[Combatant]) -> Option<i64> {
+    let mut counts: BTreeMap<i64, usize> = BTreeMap::new();
+    for c in ships.iter().filter(|c| c.owner != owner && c.owner != 0) {
+        *counts.entry(c.owner).or_default() += 1;
+    }
+    // The largest count, and of those the first in ID order.
+    counts
+        .iter()
+        .rev()
+        .max_by_key(|(_, n)| **n)
+        .map(|(id, _)| *id)
+}
+
+/// The casualties of a battle, given the ship classes' costs by ID.
+pub fn battle_casualties(
+    turn: i32,
+    ships: &[Combatant],
+    crippled: &[i64],
+    destroyed: &[i64],
+    costs: &BTreeMap<i64, i32>,
+) -> Vec<Casualty> {
+    let mut v = Vec::new();
+    for c in ships {
+        let lost = destroyed.contains(&c.id);
+        if !lost && !crippled.contains(&c.id) {
+            continue;
+        }
+        let foe = match enemy(c.owner, ships) {
+            Some(e) => e,
+            None => continue,
+        };
+        let (kind, cost) = if c.is_facility() {
+            let cost = facility::spec(c.class.as_str()).map_or(0, |s| s.cost);
+            (FACILITY, cost)
+        } else if c.is_flight() {
+            (flight::FLIGHT, 0)
+        } else {
+            (SHIP, costs.get(&c.stype).copied().unwrap_or(0))
+        };
+        v.push(Casualty::new(
+            turn,
+            c.owner,
+            foe,
+            kind,
+            lost,
+            if lost { cost } else { 0 },
+        ));
+    }
+    v
+}
+
+/// The casualties of an invasion of a system, given the ground types'
+/// costs by ID. An unowned system's garrison fights no war.
+pub fn invasion_casualties(
+    turn: i32,
+    (invader, defender): (i64, i64),
+    attackers: &[GroundUnit],
+    defenders: &[GroundUnit],
+    lost: &[i64],
+    costs: &BTreeMap<i64, i32>,
+) -> Vec<Casualty> {
+    let mut v = Vec::new();
+    if invader == 0 || defender == 0 {
+        return v;
+    }
+    for (units, empire, enemy) in [
+        (attackers, invader, defender),
+        (defenders, defender, invader),
+    ] {
+        for u in units.iter().filter(|u| lost.contains(&u.id)) {
+            let cost = costs.get(&u.gtype).copied().unwrap_or(0);
+            v.push(Casualty::new(turn, empire, enemy, GROUND, true, cost));
+        }
+    }
+    v
+}
+
+/// A report of every war, with what it has cost each side.
+pub fn report(wars: &[War], casualties: &[Casualty]) -> String {
+    if wars.is_empty() {
+        return "No wars have been fought.\n".to_string();
+    }
+    let totals = tally(casualties);
+    let mut s = String::new();
+    for w in wars {
+        match w.ended {
+            Some(t) => s.push_str(&format!("{}, turns {}-{}\n", w.name(), w.started, t)),
+            None => s.push_str(&format!("{}, since turn {}\n", w.name(), w.started)),
+        }
+        for (id, name) in [(w.a, &w.a_name), (w.b, &w.b_name)] {
+            let a = totals.get(&(w.id, id)).cloned().unwrap_or_default();
+            s.push_str(&format!("  {}: {}\n", name, a));
+        }
+    }
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::{
+        battle_casualties, enemy, fighting, invasion_casualties, report, tally, Attrition,
+        Casualty, War, FACILITY, GROUND, SHIP,
+    };
+    use crate::campaign::combat::Combatant;
+    use crate::campaign::facility;
+    use crate::campaign::unit::GroundUnit;
+
+    fn ship(id: i64, owner: i64, stype: i64) -> Combatant {
+        Combatant {
+            id,
+            owner,
+            owner_name: String::new(),
+            class: "Avenger".to_string(),
+            stype,
+            atk: 2,
+            def: 2,
+            crip: false,
+        }
+    }
+
+    fn war() -> War {
+        War {
+            id: 1,
+            a: 1,
+            b: 2,
+            started: 3,
+            ended: None,
+            a_name: "Senorian".to_string(),
+            b_name: "Human".to_string(),
+        }
+    }
+
+    #[test]
+    fn enemies() {
+        let ships = vec![ship(1, 1, 1), ship(2, 2, 1), ship(3, 3, 1), ship(4, 3, 1)];
+        assert_eq!(vec![(1, 2), (1, 3), (2, 3)], fighting(&ships));
+        assert_eq!(Some(3), enemy(1, &ships));
+        assert_eq!(Some(1), enemy(3, &ships));
+        assert_eq!(None, enemy(1, &ships[..1]));
+    }
+
+    #[test]
+    fn casualties() {
+        let mut base = ship(facility::COMBAT_ID + 1, 2, 0);
+        base.class = facility::STARBASE.to_string();
+        let ships = vec![ship(1, 1, 7), ship(2, 1, 7), ship(3, 2, 8), base];
+        let costs = BTreeMap::from([(7, 10), (8, 6)]);
+        let v = battle_casualties(3, &ships, &[2], &[1, facility::COMBAT_ID + 1], &costs);
+        assert_eq!(
+            vec![
+                Casualty::new(3, 1, 2, SHIP, true, 10),
+                Casualty::new(3, 1, 2, SHIP, false, 0),
+                Casualty::new(3, 2, 1, FACILITY, true, 12),
+            ],
+            v
+        );
+
+        let unit = |id, gtype| GroundUnit {
+            id,
+            gtype,
+            loc: 1,
+            name: String::new(),
+            abbr: String::new(),
+            atk: 4,
+            def: 4,
+        };
+        let attackers = vec![unit(2, 2), unit(3, 1)];
+        let defenders = vec![unit(1, 1)];
+        let costs = BTreeMap::from([(1, 2), (2, 6)]);
+        let v = invasion_casualties(4, (2, 1), &attackers, &defenders, &[1, 2], &costs);
+        assert_eq!(
+            vec![
+                Casualty::new(4, 2, 1, GROUND, true, 6),
+                Casualty::new(4, 1, 2, GROUND, true, 2),
+            ],
+            v
+        );
+        let v = invasion_casualties(4, (2, 0), &attackers, &defenders, &[1], &costs);
+        assert!(v.is_empty());
+    }
+
+    #[test]
+    fn attrition() {
+        let mut v = vec![
+            Casualty::new(3, 1, 2, SHIP, true, 10),
+            Casualty::new(3, 1, 2, SHIP, false, 0),
+            Casualty::new(4, 2, 1, GROUND, true, 6),
+        ];
+        for c in v.iter_mut() {
+            c.war = 1;
+        }
+        let totals = tally(&v);
+        assert_eq!(
+            Attrition {
+                crippled: 1,
+                hulls: 1,
+                ep: 10,
+                ..Attrition::default()
+            },
+            totals[&(1, 1)]
+        );
+
+        let mut w = war();
+        assert_eq!("Senorian-Human War\t3\tOngoing", w.as_row());
+        assert_eq!(
+            "Senorian-Human War, since turn 3\n  \
+            Senorian: 1 hulls, 0 flights, 0 facilities and 0 ground units lost (10 EP); \
+            1 crippled\n  \
+            Human: 0 hulls, 0 flights, 0 facilities and 1 ground units lost (6 EP); \
+            0 crippled\n",
+            report(&[w.clone()], &v)
+        );
+        w.ended = Some(5);
+        assert!(report(&[w], &[]).starts_with("Senorian-Human War, turns 3-5\n"));
+        assert_eq!("No wars have been fought.\n", report(&[], &v));
+    }
+}
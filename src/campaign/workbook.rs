@@ -0,0 +1,451 @@
+// Copyright 2022 David Terhune
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Import of a starting scenario from the community campaign workbook.
+//!
+//! Saved as CSV, one file per sheet named after it, the workbook's Empires,
+//! Systems, Classes and Fleets sheets can build a new campaign. Each sheet
+//! starts with a heading row. Columns are found by heading regardless of
+//! case or order, and columns the importer does not use are ignored. Rows
+//! with an empty first cell, such as the workbook's spacer and totals rows,
+//! are skipped. Numbers left blank or entered as "-" count as zero, and an
+//! owner left blank or entered as "-", "None" or "Unowned" means none. A
+//! fleet lists its ships in one cell, as in "2x Warhound, Kraken". Sheets
+//! may separate fields with commas, semicolons or tabs.
+
+use std::io;
+use std::path;
+
+use super::dialect;
+use super::empire::Empire;
+use super::map;
+use super::system::{PlanetType, System};
+use super::unit::{Hull, ShipType};
+
+/// File names of the sheets. The Classes and Fleets sheets may be left out.
+pub const EMPIRES: &str = "Empires.csv";
+pub const SYSTEMS: &str = "Systems.csv";
+pub const CLASSES: &str = "Classes.csv";
+pub const FLEETS: &str = "Fleets.csv";
+
+/// Entries meaning no owner.
+const UNOWNED: [&str; 4] = ["", "-", "none", "unowned"];
+
+/// A fleet from the Fleets sheet: its owner, location, and ships by class
+/// with how many of each.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FleetEntry {
+    pub name: String,
+    pub empire: String,
+    pub location: String,
+    pub ships: Vec<(String, i32)>,
+}
+
+/// What was read from the workbook. Systems carry their owners in
+/// `owner_name` and classes theirs in `empire_name`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Workbook {
+    pub empires: Vec<Empire>,
+    pub systems: Vec<System>,
+    pub classes: Vec<ShipType>,
+    pub fleets: Vec<FleetEntry>,
+}
+
+impl Workbook {
+    /// Check that everything named in the sheets exists: owners and
+    /// locations among the empires and systems read, planet types and hulls
+    /// in the campaign's reference tables, and each fleet's classes among its
+    /// empire's. Returns every problem found, a line each.
+    pub fn check(&self, types: &[PlanetType], hulls: &[Hull]) -> Result<(), String> {
+        let mut errors = Vec::new();
+        let empire = |name: &str| self.empires.iter().any(|e| e.name == name);
+        // Names are unique in a campaign.
+        for (n, e) in self.empires.iter().enumerate() {
+            if self.empires[..n].iter().any(|x| x.name == e.name) {
+                errors.push(format!("{}: empire listed more than once", e.name));
+            }
+        }
+        for (n, s) in self.systems.iter().enumerate() {
+            if self.systems[..n].iter().any(|x| x.name == s.name) {
+                errors.push(format!("{}: system listed more than once", s.name));
+            }
+        }
+        for s in &self.systems {
+            if let Err(e) = s.validate(types) {
+                errors.push(e);
+            }
+            if !s.owner_name.is_empty() && !empire(&s.owner_name) {
+                errors.push(format!("{}: unknown owner {}", s.name, s.owner_name));
+            }
+        }
+        for c in &self.classes {
+            if !empire(&c.empire_name) {
+                errors.push(format!("{}: unknown empire {}", c.class, c.empire_name));
+            }
+            if !hulls.iter().any(|h| h.name == c.hull) {
+                errors.push(format!("{}: unknown hull {}", c.class, c.hull));
+            }
+        }
+        for f in &self.fleets {
+            if !empire(&f.empire) {
+                errors.push(format!("{}: unknown empire {}", f.name, f.empire));
+            }
+            if !self.systems.iter().any(|s| s.name == f.location) {
+                errors.push(format!("{}: unknown system {}", f.name, f.location));
+            }
+            for (class, _) in &f.ships {
+                if !self
+                    .classes
+                    .iter()
+                    .any(|c| c.class == *class && c.empire_name == f.empire)
+                {
+                    errors.push(format!("{}: {} has no {} class", f.name, f.empire, class));
+                }
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors.join("\n"))
+        }
+    }
+}
+
+// A sheet's heading row and the rows below it worth reading.
+struct Sheet {
+    name: &'static str,
+    headings: Vec<String>,
+    rows: Vec<csv::StringRecord>,
+}
+
+impl Sheet {
+    // Read a sheet, skipping rows with an empty first cell.
+    fn read<R: io::Read>(name: &'static str, mut rdr: csv::Reader<R>) -> Result<Self, String> {
+        let headings = match rdr.headers() {
+            Ok(h) => h.iter().map(|h| h.trim().to_lowercase()).collect(),
+            Err(e) => return Err(format!("{} sheet: {}", name, e)),
+        };
+        let mut rows = Vec::new();
+        for result in rdr.records() {
+            match result {
+                Ok(r) if r.get(0).is_none_or(|c| c.trim().is_empty()) => (),
+                Ok(r) => rows.push(r),
+                Err(e) => return Err(format!("{} sheet: {}", name, e)),
+            }
+        }
+        Ok(Self {
+            name,
+            headings,
+            rows,
+        })
+    }
+
+    // Index of the column with the heading, if there is one.
+    fn find(&self, heading: &str) -> Option<usize> {
+        self.headings
+            .iter()
+            .position(|h| h == &heading.to_lowercase())
+    }
+
+    // Index of the column with the heading, which must be there.
+    fn column(&self, heading: &str) -> Result<usize, String> {
+        match self.find(heading) {
+            Some(i) => Ok(i),
+            None => Err(format!("{} sheet has no {} column", self.name, heading)),
+        }
+    }
+
+    // Text of a cell, trimmed.
+    fn text(row: &csv::StringRecord, col: usize) -> String {
+        row.get(col).unwrap_or("").trim().to_string()
+    }
+
+    // Number in a cell, which is zero if left blank or entered as "-".
+    fn number(&self, row: &csv::StringRecord, col: usize) -> Result<i32, String> {
+        let text = Self::text(row, col);
+        if text.is_empty() || text == "-" {
+            return Ok(0);
+        }
+        match text.parse() {
+            Ok(n) => Ok(n),
+            Err(_) => Err(format!(
+                "{} sheet, {}: {} is not a number in the {} column",
+                self.name,
+                Self::text(row, 0),
+                text,
+                self.headings[col].to_uppercase()
+            )),
+        }
+    }
+}
+
+/// Read the Empires sheet. Columns: Empire, EP and Tech.
+pub fn read_empires<R: io::Read>(rdr: csv::Reader<R>) -> Result<Vec<Empire>, String> {
+    let sheet = Sheet::read("Empires", rdr)?;
+    let name = sheet.column("Empire")?;
+    let ep = sheet.column("EP")?;
+    let tech = sheet.column("Tech")?;
+    let mut v = Vec::new();
+    for row in &sheet.rows {
+        let mut e = Empire::new(&Sheet::text(row, name));
+        e.treasury = sheet.number(row, ep)?;
+        e.tech = sheet.number(row, tech)?;
+        v.push(e);
+    }
+    Ok(v)
+}
+
+/// Read the Systems sheet. Columns: System, Type, RAW, CAP, POP, MOR, IND
+/// and Owner, with map coordinates X and Y if the workbook has them.
+/// Systems without them are placed on the default grid.
+pub fn read_systems<R: io::Read>(rdr: csv::Reader<R>) -> Result<Vec<System>, String> {
+    let sheet = Sheet::read("Systems", rdr)?;
+    let name = sheet.column("System")?;
+    let ptype = sheet.column("Type")?;
+    let mut stats = Vec::new();
+    for heading in ["RAW", "CAP", "POP", "MOR", "IND"] {
+        stats.push(sheet.column(heading)?);
+    }
+    let owner = sheet.column("Owner")?;
+    let coords = sheet.find("X").zip(sheet.find("Y"));
+    let mut v = Vec::new();
+    for (n, row) in sheet.rows.iter().enumerate() {
+        let mut sys = System::new(
+            &Sheet::text(row, name),
+            &Sheet::text(row, ptype),
+            sheet.number(row, stats[0])?,
+            sheet.number(row, stats[1])?,
+            sheet.number(row, stats[2])?,
+            sheet.number(row, stats[3])?,
+            sheet.number(row, stats[4])?,
+        );
+        let owner = Sheet::text(row, owner);
+        sys.owner_name = if UNOWNED.contains(&owner.to_lowercase().as_str()) {
+            String::new()
+        } else {
+            owner
+        };
+        (sys.x, sys.y) = match coords {
+            Some((x, y)) => (sheet.number(row, x)?, sheet.number(row, y)?),
+            None => map::grid_position(n),
+        };
+        v.push(sys);
+    }
+    Ok(v)
+}
+
+/// Read the Classes sheet. Columns: Class, Empire, Hull, Cost, CR, ATK, DEF,
+/// Cargo and Speed. A class without a speed has speed 1.
+pub fn read_classes<R: io::Read>(rdr: csv::Reader<R>) -> Result<Vec<ShipType>, String> {
+    let sheet = Sheet::read("Classes", rdr)?;
+    let class = sheet.column("Class")?;
+    let empire = sheet.column("Empire")?;
+    let hull = sheet.column("Hull")?;
+    let mut stats = Vec::new();
+    for heading in ["Cost", "CR", "ATK", "DEF", "Cargo", "Speed"] {
+        stats.push(sheet.column(heading)?);
+    }
+    let mut v = Vec::new();
+    for row in &sheet.rows {
+        let mut t = ShipType::new(&Sheet::text(row, class), &Sheet::text(row, hull), 0);
+        t.empire_name = Sheet::text(row, empire);
+        t.cost = sheet.number(row, stats[0])?;
+        t.cr = sheet.number(row, stats[1])?;
+        t.atk = sheet.number(row, stats[2])?;
+        t.def = sheet.number(row, stats[3])?;
+        t.cap = sheet.number(row, stats[4])?;
+        t.speed = sheet.number(row, stats[5])?.max(1);
+        v.push(t);
+    }
+    Ok(v)
+}
+
+/// Read the Fleets sheet. Columns: Fleet, Empire, Location and Ships.
+pub fn read_fleets<R: io::Read>(rdr: csv::Reader<R>) -> Result<Vec<FleetEntry>, String> {
+    let sheet = Sheet::read("Fleets", rdr)?;
+    let name = sheet.column("Fleet")?;
+    let empire = sheet.column("Empire")?;
+    let location = sheet.column("Location")?;
+    let ships = sheet.column("Ships")?;
+    let mut v = Vec::new();
+    for row in &sheet.rows {
+        let name = Sheet::text(row, name);
+        let ships = match parse_ships(&Sheet::text(row, ships)) {
+            Ok(s) => s,
+            Err(e) => return Err(format!("Fleets sheet, {}: {}", name, e)),
+        };
+        v.push(FleetEntry {
+            name,
+            empire: Sheet::text(row, empire),
+            location: Sheet::text(row, location),
+            ships,
+        });
+    }
+    Ok(v)
+}
+
+/// Parse a fleet's ship list: classes separated by commas, each optionally
+/// preceded by a count such as "2x" or "2 x".
+pub fn parse_ships(text: &str) -> Result<Vec<(String, i32)>, String> {
+    let mut v = Vec::new();
+    for item in text.split(',').map(str::trim).filter(|i| !i.is_empty()) {
+        let digits = item.chars().take_while(char::is_ascii_digit).count();
+        let rest = item[digits..].trim_start();
+        let (count, class) = match rest.strip_prefix(['x', 'X']) {
+            Some(class) if digits > 0 && class.starts_with(' ') => {
+                (item[..digits].parse().unwrap_or(0), class.trim())
+            }
+            _ => (1, item),
+        };
+        if count < 1 || class.is_empty() {
+            return Err(format!("cannot read ships \"{}\"", item));
+        }
+        v.push((class.to_string(), count));
+    }
+    Ok(v)
+}
+
+/// Read the sheets saved in a folder.
+pub fn read_folder(folder: &str) -> Result<Workbook, String> {
+    let dir = path::Path::new(folder);
+    let open = |file: &str| dialect::open(dir.join(file), true);
+    let mut book = Workbook {
+        empires: match open(EMPIRES) {
+            Ok(r) => read_empires(r)?,
+            Err(e) => return Err(format!("{}: {}", EMPIRES, e)),
+        },
+        systems: match open(SYSTEMS) {
+            Ok(r) => read_systems(r)?,
+            Err(e) => return Err(format!("{}: {}", SYSTEMS, e)),
+        },
+        ..Workbook::default()
+    };
+    if dir.join(CLASSES).exists() {
+        book.classes = match open(CLASSES) {
+            Ok(r) => read_classes(r)?,
+            Err(e) => return Err(format!("{}: {}", CLASSES, e)),
+        };
+    }
+    if dir.join(FLEETS).exists() {
+        book.fleets = match open(FLEETS) {
+            Ok(r) => read_fleets(r)?,
+            Err(e) => return Err(format!("{}: {}", FLEETS, e)),
+        };
+    }
+    Ok(book)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use csv::{Reader, ReaderBuilder};
+
+    use super::{parse_ships, read_classes, read_empires, read_fleets, read_systems, Workbook};
+    use crate::campaign::system::PlanetType;
+    use crate::campaign::unit::Hull;
+
+    fn reader(text: &str) -> Reader<&[u8]> {
+        ReaderBuilder::new()
+            .flexible(true)
+            .from_reader(text.as_bytes())
+    }
+
+    pub fn workbook() -> Workbook {
+        Workbook {
+            empires: read_empires(reader(
+                "Empire,EP,Tech,Notes\n\
+                Senorian,40,1,Aggressive\n\
+                Human,-,,\n\
+                ,85,1,Totals\n",
+            ))
+            .unwrap(),
+            systems: read_systems(reader(
+                "Owner,System,Type,RAW,CAP,POP,MOR,IND\n\
+                Senorian,Senor Prime,Barren,5,6,4,3,4\n\
+                Unowned,Tibron,Barren,4,6,3,2,3\n",
+            ))
+            .unwrap(),
+            classes: read_classes(reader(
+                "CLASS,EMPIRE,HULL,COST,CR,ATK,DEF,CARGO,SPEED\n\
+                Warhound,Senorian,Frigate,6,2,2,2,0,\n",
+            ))
+            .unwrap(),
+            fleets: read_fleets(reader(
+                "Fleet,Empire,Location,Ships\n\
+                Home Fleet,Senorian,Senor Prime,2x Warhound\n",
+            ))
+            .unwrap(),
+        }
+    }
+
+    #[test]
+    fn sheets() {
+        let book = workbook();
+        assert_eq!(2, book.empires.len());
+        assert_eq!((40, 1), (book.empires[0].treasury, book.empires[0].tech));
+        assert_eq!((0, 0), (book.empires[1].treasury, book.empires[1].tech));
+        assert_eq!("Senorian", book.systems[0].owner_name);
+        assert!(book.systems[1].owner_name.is_empty());
+        assert_eq!(1, book.classes[0].speed);
+        assert_eq!(vec![("Warhound".to_string(), 2)], book.fleets[0].ships);
+
+        assert!(read_empires(reader("Empire,Tech\nHuman,1\n"))
+            .unwrap_err()
+            .contains("no EP column"));
+        assert!(read_empires(reader("Empire,EP,Tech\nHuman,lots,1\n"))
+            .unwrap_err()
+            .contains("lots is not a number"));
+    }
+
+    #[test]
+    fn ships() {
+        assert_eq!(
+            vec![
+                ("Warhound".to_string(), 2),
+                ("Kraken".to_string(), 1),
+                ("X-Wing".to_string(), 3)
+            ],
+            parse_ships("2x Warhound, Kraken, 3 x X-Wing").unwrap()
+        );
+        assert!(parse_ships("").unwrap().is_empty());
+        assert!(parse_ships("0x Warhound").is_err());
+    }
+
+    #[test]
+    fn check() {
+        let types = vec![PlanetType {
+            name: "Barren".to_string(),
+            description: String::new(),
+            max_cap: 6,
+            mor_mod: 0,
+        }];
+        let hulls = vec![Hull {
+            name: "Frigate".to_string(),
+            size: 2,
+            maint: 1,
+            tech: 0,
+        }];
+        let mut book = workbook();
+        assert!(book.check(&types, &hulls).is_ok());
+
+        book.systems[1].owner_name = "Kili".to_string();
+        book.fleets[0].ships.push(("Kraken".to_string(), 1));
+        book.systems.push(book.systems[0].clone());
+        let errors = book.check(&types, &hulls).unwrap_err();
+        assert_eq!(3, errors.lines().count());
+        assert!(errors.contains("unknown owner Kili"));
+        assert!(errors.contains("Senorian has no Kraken class"));
+        assert!(errors.contains("Senor Prime: system listed more than once"));
+    }
+}
@@ -0,0 +1,387 @@
+// Copyright 2022 David Terhune
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Command-line interface for scripting campaign work without the GUI.
+//!
+//! Started as `vbam-cma --no-gui <command> ...`, the program runs one
+//! command against a campaign and exits, printing what it produced.
+
+use crate::campaign::system::Duplicates;
+use crate::campaign::Campaign;
+
+/// Argument that selects the command-line interface.
+pub const NO_GUI: &str = "--no-gui";
+
+/// Usage text.
+pub const USAGE: &str = "\
+Usage: vbam-cma --no-gui <command> [arguments]
+
+Commands:
+  list                                   List the campaigns
+  import-systems <campaign> <csv> [--skip|--overwrite|--merge]
+                                         Import systems from a CSV file, refusing
+                                         those already in the campaign unless told
+                                         what to do with them
+  import-workbook <campaign> <folder>    Create a campaign from workbook sheets saved as CSV
+  import-orders <campaign> <empire> <file>
+                                         Import an empire's orders and validate them
+  validate-orders <campaign> <empire>    Report an empire's illegal orders
+  preview-turn <campaign>                Print what the turn would do, changing nothing
+  advance-turn <campaign>                Resolve the turn and print its report
+  export <campaign> <file> [--no-journal]
+                                         Write the campaign to an archive file
+  report <campaign> classes              Print each ship class's service record
+  report <campaign> economic             Print the economic phase report
+  report <campaign> journal              Print the GM journal as Markdown
+  report <campaign> packet <empire>      Print an empire's player packet
+  report <campaign> wars                 Print what each war has cost each side
+  help                                   Show this text
+";
+
+/// What can be reported.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Report {
+    Classes,
+    Economic,
+    Journal,
+    Packet(String),
+    Wars,
+}
+
+/// A command to run.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Command {
+    Help,
+    List,
+    ImportSystems {
+        campaign: String,
+        file: String,
+        duplicates: Duplicates,
+    },
+    ImportWorkbook {
+        campaign: String,
+        folder: String,
+    },
+    ImportOrders {
+        campaign: String,
+        empire: String,
+        file: String,
+    },
+    ValidateOrders {
+        campaign: String,
+        empire: String,
+    },
+    PreviewTurn {
+        campaign: String,
+    },
+    AdvanceTurn {
+        campaign: String,
+    },
+    Export {
+        campaign: String,
+        file: String,
+        journal: bool,
+    },
+    Report {
+        campaign: String,
+        report: Report,
+    },
+}
+
+impl Command {
+    /// Parse the arguments that follow `--no-gui`.
+    pub fn parse(args: &[String]) -> Result<Self, String> {
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        let cmd = match args.as_slice() {
+            [] | ["help"] | ["--help"] | ["-h"] => Self::Help,
+            ["list"] => Self::List,
+            ["import-systems", campaign, file] => Self::ImportSystems {
+                campaign: campaign.to_string(),
+                file: file.to_string(),
+                duplicates: Duplicates::Refuse,
+            },
+            ["import-systems", campaign, file, flag] => Self::ImportSystems {
+                campaign: campaign.to_string(),
+                file: file.to_string(),
+                duplicates: match *flag {
+                    "--skip" => Duplicates::Skip,
+                    "--overwrite" => Duplicates::Overwrite,
+                    "--merge" => Duplicates::Merge,
+                    _ => return Err(format!("Unknown option {}\n\n{}", flag, USAGE)),
+                },
+            },
+            ["import-workbook", campaign, folder] => Self::ImportWorkbook {
+                campaign: campaign.to_string(),
+                folder: folder.to_string(),
+            },
+            ["import-orders", campaign, empire, file] => Self::ImportOrders {
+                campaign: campaign.to_string(),
+                empire: empire.to_string(),
+                file: file.to_string(),
+            },
+            ["validate-orders", campaign, empire] => Self::ValidateOrders {
+                campaign: campaign.to_string(),
+                empire: empire.to_string(),
+            },
+            ["preview-turn", campaign] => Self::PreviewTurn {
+                campaign: campaign.to_string(),
+            },
+            ["advance-turn", campaign] => Self::AdvanceTurn {
+                campaign: campaign.to_string(),
+            },
+            ["export", campaign, file] => Self::Export {
+                campaign: campaign.to_string(),
+                file: file.to_string(),
+                journal: true,
+            },
+            ["export", campaign, file, "--no-journal"] => Self::Export {
+                campaign: campaign.to_string(),
+                file: file.to_string(),
+                journal: false,
+            },
+            ["report", campaign, "classes"] => Self::Report {
+                campaign: campaign.to_string(),
+                report: Report::Classes,
+            },
+            ["report", campaign, "economic"] => Self::Report {
+                campaign: campaign.to_string(),
+                report: Report::Economic,
+            },
+            ["report", campaign, "journal"] => Self::Report {
+                campaign: campaign.to_string(),
+                report: Report::Journal,
+            },
+            ["report", campaign, "packet", empire] => Self::Report {
+                campaign: campaign.to_string(),
+                report: Report::Packet(empire.to_string()),
+            },
+            ["report", campaign, "wars"] => Self::Report {
+                campaign: campaign.to_string(),
+                report: Report::Wars,
+            },
+            [cmd, ..] => return Err(format!("Bad arguments for {}\n\n{}", cmd, USAGE)),
+        };
+        Ok(cmd)
+    }
+
+    /// Run the command, returning the text to print.
+    pub async fn run(&self) -> Result<String, String> {
+        match self {
+            Self::Help => Ok(USAGE.to_string()),
+            Self::List => Ok(Campaign::campaigns()?
+                .iter()
+                .map(|c| format!("{}\n", c))
+                .collect()),
+            Self::ImportSystems {
+                campaign,
+                file,
+                duplicates,
+            } => {
+                let mut c = Campaign::open(campaign).await?;
+                let result = c.import_systems(file, *duplicates).await;
+                c.close().await;
+                result.map(|_| format!("Imported systems from {} into {}\n", file, campaign))
+            }
+            Self::ImportWorkbook { campaign, folder } => {
+                let c = Campaign::import_workbook(campaign.to_string(), folder).await?;
+                c.close().await;
+                Ok(format!(
+                    "Created {} from the workbook in {}\n",
+                    campaign, folder
+                ))
+            }
+            Self::ImportOrders {
+                campaign,
+                empire,
+                file,
+            } => {
+                let c = Campaign::open(campaign).await?;
+                let result = match Self::empire(&c, empire).await {
+                    Ok(id) => c.import_orders(id, file).await,
+                    Err(e) => Err(e),
+                };
+                c.close().await;
+                result
+            }
+            Self::ValidateOrders { campaign, empire } => {
+                let c = Campaign::open(campaign).await?;
+                let result = match Self::empire(&c, empire).await {
+                    Ok(id) => c.validate_orders(id).await,
+                    Err(e) => Err(e),
+                };
+                c.close().await;
+                result
+            }
+            Self::PreviewTurn { campaign } => {
+                let c = Campaign::open(campaign).await?;
+                let result = c.preview_turn().await;
+                c.close().await;
+                result.map(|p| p.to_string())
+            }
+            Self::AdvanceTurn { campaign } => {
+                let mut c = Campaign::open(campaign).await?;
+                let result = c.advance_turn().await;
+                c.close().await;
+                result.map(|r| r.to_string())
+            }
+            Self::Export {
+                campaign,
+                file,
+                journal,
+            } => {
+                let c = Campaign::open(campaign).await?;
+                let result = c.export(file, *journal).await;
+                c.close().await;
+                result.map(|_| format!("Exported {} to {}\n", campaign, file))
+            }
+            Self::Report { campaign, report } => {
+                let c = Campaign::open(campaign).await?;
+                let result = Self::report(&c, report).await;
+                c.close().await;
+                result
+            }
+        }
+    }
+
+    // Find an empire's ID by name, regardless of case.
+    async fn empire(c: &Campaign, name: &str) -> Result<i64, String> {
+        let names = c.empire_names().await?;
+        match names.iter().find(|(_, n)| n.eq_ignore_ascii_case(name)) {
+            Some((id, _)) => Ok(*id),
+            None => Err(format!("No empire is named {}", name)),
+        }
+    }
+
+    // Produce a report from an open campaign.
+    async fn report(c: &Campaign, report: &Report) -> Result<String, String> {
+        match report {
+            Report::Classes => c.class_report().await,
+            Report::Economic => c.economic_report().await,
+            Report::Journal => {
+                let entries = c.journal().await?;
+                Ok(crate::campaign::journal::to_markdown(c.name(), &entries))
+            }
+            Report::Packet(name) => {
+                let empires = c.empires().await?;
+                match empires.iter().find(|e| e.name.eq_ignore_ascii_case(name)) {
+                    Some(e) => c.player_packet(e).await,
+                    None => Err(format!("No empire is named {}", name)),
+                }
+            }
+            Report::Wars => c.war_report().await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Command, Duplicates, Report};
+
+    fn parse(line: &str) -> Result<Command, String> {
+        let args: Vec<String> = line.split_whitespace().map(String::from).collect();
+        Command::parse(&args)
+    }
+
+    #[test]
+    fn commands() {
+        assert_eq!(Ok(Command::Help), parse(""));
+        assert_eq!(Ok(Command::List), parse("list"));
+        assert_eq!(
+            Ok(Command::ImportSystems {
+                campaign: "Test".to_string(),
+                file: "systems.csv".to_string(),
+                duplicates: Duplicates::Refuse
+            }),
+            parse("import-systems Test systems.csv")
+        );
+        assert_eq!(
+            Ok(Command::ImportSystems {
+                campaign: "Test".to_string(),
+                file: "systems.csv".to_string(),
+                duplicates: Duplicates::Merge
+            }),
+            parse("import-systems Test systems.csv --merge")
+        );
+        assert_eq!(
+            Ok(Command::ImportOrders {
+                campaign: "Test".to_string(),
+                empire: "Human".to_string(),
+                file: "orders.txt".to_string()
+            }),
+            parse("import-orders Test Human orders.txt")
+        );
+        assert_eq!(
+            Ok(Command::ImportWorkbook {
+                campaign: "Test".to_string(),
+                folder: "sheets".to_string()
+            }),
+            parse("import-workbook Test sheets")
+        );
+        assert_eq!(
+            Ok(Command::AdvanceTurn {
+                campaign: "Test".to_string()
+            }),
+            parse("advance-turn Test")
+        );
+        assert_eq!(
+            Ok(Command::PreviewTurn {
+                campaign: "Test".to_string()
+            }),
+            parse("preview-turn Test")
+        );
+        assert_eq!(
+            Ok(Command::Export {
+                campaign: "Test".to_string(),
+                file: "test.json".to_string(),
+                journal: false
+            }),
+            parse("export Test test.json --no-journal")
+        );
+        assert_eq!(
+            Ok(Command::Report {
+                campaign: "Test".to_string(),
+                report: Report::Packet("Human".to_string())
+            }),
+            parse("report Test packet Human")
+        );
+        assert_eq!(
+            Ok(Command::Report {
+                campaign: "Test".to_string(),
+                report: Report::Wars
+            }),
+            parse("report Test wars")
+        );
+        assert_eq!(
+            Ok(Command::Report {
+                campaign: "Test".to_string(),
+                report: Report::Classes
+            }),
+            parse("report Test classes")
+        );
+    }
+
+    #[test]
+    fn bad_arguments() {
+        assert!(parse("advance-turn").is_err());
+        assert!(parse("validate-orders Test").is_err());
+        assert!(parse("report Test weather").is_err());
+        assert!(parse("import-systems Test systems.csv --twice")
+            .unwrap_err()
+            .starts_with("Unknown option --twice"));
+        assert!(parse("launch Test")
+            .unwrap_err()
+            .starts_with("Bad arguments for launch"));
+    }
+}
@@ -13,13 +13,67 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 mod campaign;
+mod cli;
+mod pump;
+mod table;
 
-use campaign::system::System;
+use campaign::archive;
+use campaign::audit;
+use campaign::checklist::Fix;
+use campaign::combat::{self, CombatModifier};
+use campaign::config;
+use campaign::construction;
+use campaign::deadline::Deadline;
+use campaign::development::{self, Development};
+use campaign::dice;
+use campaign::diplomacy;
+use campaign::display::{SystemFilter, SystemView};
+use campaign::economy::IncomeBreakdown;
+use campaign::empire::Empire;
+use campaign::escort::Escort;
+use campaign::espionage::{self, Operation};
+use campaign::facility::{self, Facility};
+use campaign::flight::{self, Station};
+use campaign::government;
+use campaign::intel;
+use campaign::journal::{self, JournalEntry};
+use campaign::lane::{Lane, LANE_TYPES};
+use campaign::locale::LOCALES;
+use campaign::map::{self, MapView};
+use campaign::naming;
+use campaign::notice::{self, Notices};
+use campaign::options::{self, CampaignOptions};
+use campaign::orders::{self, Order};
+use campaign::overlay::{Overlay, OVERLAYS};
+use campaign::overview::CampaignSummary;
+use campaign::raid::{self, Raid};
+use campaign::recognition::Identification;
+use campaign::repair::RepairJob;
+use campaign::scorch;
+use campaign::shipyard;
+use campaign::system::{BulkEdit, Duplicates, System, POLICIES};
+use campaign::tech::TechStatus;
+use campaign::transfer::Transfer;
+use campaign::unit::{Fleet, FleetSummary, Flight, GroundUnit, ShipLocation, ShipStatus, ShipType};
+use campaign::war::War;
 use campaign::Campaign;
 
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path;
+use std::rc::Rc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use table::RecordTable;
+
 use fltk::{
-    app, browser::SelectBrowser, button, dialog, enums::Shortcut, frame, input, menu, prelude::*,
-    window,
+    app, browser,
+    browser::SelectBrowser,
+    button, dialog,
+    draw::{self, LineStyle},
+    enums::{Align, CallbackTrigger, Color, Event, Font, FrameType, Key, Shortcut},
+    frame, input, menu,
+    prelude::*,
+    text, window,
 };
 
 // Main window base title
@@ -45,11 +99,51 @@ enum Message {
     Quit,
     NewCampaign,
     OpenCampaign,
+    OpenFile,
+    Preferences,
+    CampaignOverview,
+    RenameCampaign,
+    SaveCampaignAs,
     CloseCampaign,
     DeleteCampaign,
+    ImportCampaign,
+    ImportWorkbook,
+    ExportCampaign,
+    RestoreSnapshot,
+    PreviewTurn,
+    AdvanceTurn,
+    Undo,
+    Redo,
+    EconomicReport,
+    Journal,
+    AuditLog,
+    WarReport,
+    ClassReport,
+    ReportTemplates,
+    Notifications,
+    PrintableExport,
+    Locale,
+    CombatModifiers,
+    RulesReference,
+    ColonialAdmin,
+    DeficitLimit,
+    DiceSeed,
+    StrategicWarfare,
+    Attrition,
+    LoadSetting,
+    ReloadSetting,
     HelpAbout,
     ShowSystems,
     ShowEmpires,
+    ShowFleets,
+    ShowLanes,
+    ShowShipTypes,
+    ShowConstruction,
+    ShowTreaties,
+    ShowMap,
+    SetDeadline,
+    MarkSubmitted,
+    FillMissingOrders,
 }
 
 // Application type.
@@ -58,6 +152,10 @@ struct VBAMApp {
     main_win: window::Window,
     rcvr: app::Receiver<Message>,
     cmpgn: Option<campaign::Campaign>,
+    dashboard: SelectBrowser,
+    toast: frame::Frame,
+    toast_timer: Option<app::TimeoutHandle>,
+    notices: Notices,
 }
 
 impl VBAMApp {
@@ -73,6 +171,13 @@ impl VBAMApp {
 
         let mut menu = menu::MenuBar::default().with_size(MAIN_WIDTH, TEXT_HEIGHT);
 
+        menu.add_emit(
+            "&File/&Preferences...\t",
+            Shortcut::None,
+            menu::MenuFlag::MenuDivider,
+            s.clone(),
+            Message::Preferences,
+        );
         menu.add_emit(
             "&File/&Quit\t",
             Shortcut::Ctrl | 'q',
@@ -96,6 +201,36 @@ impl VBAMApp {
             s.clone(),
             Message::OpenCampaign,
         );
+        menu.add_emit(
+            "&Campaign/Open &File...\t",
+            Shortcut::None,
+            menu::MenuFlag::Normal,
+            s.clone(),
+            Message::OpenFile,
+        );
+
+        menu.add_emit(
+            "&Campaign/O&verview...\t",
+            Shortcut::None,
+            menu::MenuFlag::Normal,
+            s.clone(),
+            Message::CampaignOverview,
+        );
+
+        menu.add_emit(
+            "&Campaign/&Rename...\t",
+            Shortcut::None,
+            menu::MenuFlag::Normal,
+            s.clone(),
+            Message::RenameCampaign,
+        );
+        menu.add_emit(
+            "&Campaign/Save &As...\t",
+            Shortcut::None,
+            menu::MenuFlag::Normal,
+            s.clone(),
+            Message::SaveCampaignAs,
+        );
 
         menu.add_emit(
             "&Campaign/&Close...\t",
@@ -113,6 +248,205 @@ impl VBAMApp {
             Message::DeleteCampaign,
         );
 
+        menu.add_emit(
+            "&Campaign/&Import Archive...\t",
+            Shortcut::None,
+            menu::MenuFlag::Normal,
+            s.clone(),
+            Message::ImportCampaign,
+        );
+
+        menu.add_emit(
+            "&Campaign/Import &Workbook...\t",
+            Shortcut::None,
+            menu::MenuFlag::Normal,
+            s.clone(),
+            Message::ImportWorkbook,
+        );
+
+        menu.add_emit(
+            "&Campaign/&Export Archive...\t",
+            Shortcut::None,
+            menu::MenuFlag::Normal,
+            s.clone(),
+            Message::ExportCampaign,
+        );
+
+        menu.add_emit(
+            "&Campaign/&Restore Snapshot...\t",
+            Shortcut::None,
+            menu::MenuFlag::MenuDivider,
+            s.clone(),
+            Message::RestoreSnapshot,
+        );
+
+        menu.add_emit(
+            "&Campaign/&Preview Turn...\t",
+            Shortcut::None,
+            menu::MenuFlag::Normal,
+            s.clone(),
+            Message::PreviewTurn,
+        );
+
+        menu.add_emit(
+            "&Campaign/&Advance Turn...\t",
+            Shortcut::Ctrl | 't',
+            menu::MenuFlag::Normal,
+            s.clone(),
+            Message::AdvanceTurn,
+        );
+
+        menu.add_emit(
+            "&Edit/&Undo...\t",
+            Shortcut::Ctrl | 'z',
+            menu::MenuFlag::Normal,
+            s.clone(),
+            Message::Undo,
+        );
+        menu.add_emit(
+            "&Edit/&Redo...\t",
+            Shortcut::Ctrl | 'y',
+            menu::MenuFlag::Normal,
+            s.clone(),
+            Message::Redo,
+        );
+        menu.add_emit(
+            "&Edit/&Find System...\t",
+            Shortcut::Ctrl | 'f',
+            menu::MenuFlag::Normal,
+            s.clone(),
+            Message::ShowSystems,
+        );
+
+        menu.add_emit(
+            "&Reports/&Economic Phase...\t",
+            Shortcut::None,
+            menu::MenuFlag::Normal,
+            s.clone(),
+            Message::EconomicReport,
+        );
+        menu.add_emit(
+            "&Reports/&Journal...\t",
+            Shortcut::None,
+            menu::MenuFlag::Normal,
+            s.clone(),
+            Message::Journal,
+        );
+        menu.add_emit(
+            "&Reports/&Log...\t",
+            Shortcut::None,
+            menu::MenuFlag::Normal,
+            s.clone(),
+            Message::AuditLog,
+        );
+        menu.add_emit(
+            "&Reports/&Wars...\t",
+            Shortcut::None,
+            menu::MenuFlag::Normal,
+            s.clone(),
+            Message::WarReport,
+        );
+        menu.add_emit(
+            "&Reports/Ship &Classes...\t",
+            Shortcut::None,
+            menu::MenuFlag::Normal,
+            s.clone(),
+            Message::ClassReport,
+        );
+
+        menu.add_emit(
+            "&Reports/&Templates...\t",
+            Shortcut::None,
+            menu::MenuFlag::Normal,
+            s.clone(),
+            Message::ReportTemplates,
+        );
+        menu.add_emit(
+            "&Reports/&Notifications...\t",
+            Shortcut::None,
+            menu::MenuFlag::Normal,
+            s.clone(),
+            Message::Notifications,
+        );
+        menu.add_emit(
+            "&Reports/&Printable Page...\t",
+            Shortcut::None,
+            menu::MenuFlag::Normal,
+            s.clone(),
+            Message::PrintableExport,
+        );
+        menu.add_emit(
+            "&Reports/L&ocale...\t",
+            Shortcut::None,
+            menu::MenuFlag::Normal,
+            s.clone(),
+            Message::Locale,
+        );
+
+        menu.add_emit(
+            "&Rules/&Combat Modifiers...\t",
+            Shortcut::None,
+            menu::MenuFlag::Normal,
+            s.clone(),
+            Message::CombatModifiers,
+        );
+        menu.add_emit(
+            "&Rules/Colonial &Administration...\t",
+            Shortcut::None,
+            menu::MenuFlag::Normal,
+            s.clone(),
+            Message::ColonialAdmin,
+        );
+        menu.add_emit(
+            "&Rules/&Deficit Limit...\t",
+            Shortcut::None,
+            menu::MenuFlag::Normal,
+            s.clone(),
+            Message::DeficitLimit,
+        );
+        menu.add_emit(
+            "&Rules/D&ice Seed...\t",
+            Shortcut::None,
+            menu::MenuFlag::Normal,
+            s.clone(),
+            Message::DiceSeed,
+        );
+        menu.add_emit(
+            "&Rules/&Strategic Warfare...\t",
+            Shortcut::None,
+            menu::MenuFlag::Normal,
+            s.clone(),
+            Message::StrategicWarfare,
+        );
+        menu.add_emit(
+            "&Rules/&Attrition...\t",
+            Shortcut::None,
+            menu::MenuFlag::Normal,
+            s.clone(),
+            Message::Attrition,
+        );
+        menu.add_emit(
+            "&Rules/&Load Setting...\t",
+            Shortcut::None,
+            menu::MenuFlag::Normal,
+            s.clone(),
+            Message::LoadSetting,
+        );
+        menu.add_emit(
+            "&Rules/Re&load Setting\t",
+            Shortcut::None,
+            menu::MenuFlag::Normal,
+            s.clone(),
+            Message::ReloadSetting,
+        );
+        menu.add_emit(
+            "&Rules/&Reference...\t",
+            Shortcut::Ctrl | 'r',
+            menu::MenuFlag::Normal,
+            s.clone(),
+            Message::RulesReference,
+        );
+
         menu.add_emit(
             "&Help/&About...\t",
             Shortcut::None,
@@ -132,7 +466,73 @@ impl VBAMApp {
             .with_label("Empires")
             .with_pos(BTN_WIDTH + 2 * SPACING, button_y)
             .with_size(BTN_WIDTH, BTN_HEIGHT)
-            .emit(s, Message::ShowEmpires);
+            .emit(s.clone(), Message::ShowEmpires);
+        button::Button::default()
+            .with_label("Fleets")
+            .with_pos(2 * BTN_WIDTH + 3 * SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s.clone(), Message::ShowFleets);
+        button::Button::default()
+            .with_label("Lanes")
+            .with_pos(3 * BTN_WIDTH + 4 * SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s.clone(), Message::ShowLanes);
+        button::Button::default()
+            .with_label("Ship Classes")
+            .with_pos(4 * BTN_WIDTH + 5 * SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s.clone(), Message::ShowShipTypes);
+        button::Button::default()
+            .with_label("Construction")
+            .with_pos(5 * BTN_WIDTH + 6 * SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s.clone(), Message::ShowConstruction);
+        button::Button::default()
+            .with_label("Treaties")
+            .with_pos(6 * BTN_WIDTH + 7 * SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s.clone(), Message::ShowTreaties);
+
+        // Dashboard of each empire's order status for the turn.
+        let dashboard_y = button_y + BTN_HEIGHT + SPACING;
+        let button_y = MAIN_HEIGHT - BTN_HEIGHT - SPACING;
+        let mut dashboard = SelectBrowser::default()
+            .with_pos(SPACING, dashboard_y)
+            .with_size(MAIN_WIDTH - 2 * SPACING, button_y - dashboard_y - SPACING);
+        dashboard.set_column_widths(&[200, 120, 200, 120]);
+        dashboard.set_column_char('\t');
+        button::Button::default()
+            .with_label("Set Deadline")
+            .with_pos(SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s.clone(), Message::SetDeadline);
+        button::Button::default()
+            .with_label("Submitted")
+            .with_pos(BTN_WIDTH + 2 * SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s.clone(), Message::MarkSubmitted);
+        button::Button::default()
+            .with_label("Fill Missing")
+            .with_pos(2 * BTN_WIDTH + 3 * SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s.clone(), Message::FillMissingOrders);
+        button::Button::default()
+            .with_label("Map")
+            .with_pos(MAIN_WIDTH - BTN_WIDTH - SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, Message::ShowMap);
+
+        // Notices show over the bottom of the dashboard for a few seconds.
+        let toast_width = MAIN_WIDTH / 2;
+        let mut toast = frame::Frame::default()
+            .with_pos(
+                MAIN_WIDTH - toast_width - 2 * SPACING,
+                button_y - TEXT_HEIGHT - 2 * SPACING,
+            )
+            .with_size(toast_width, TEXT_HEIGHT);
+        toast.set_frame(FrameType::RoundedBox);
+        toast.set_color(Color::from_rgb(255, 255, 200));
+        toast.hide();
 
         main_win.end();
         main_win.show();
@@ -142,113 +542,405 @@ impl VBAMApp {
             main_win,
             rcvr,
             cmpgn: Option::None,
+            dashboard,
+            toast,
+            toast_timer: None,
+            notices: Notices::default(),
         }
     }
 
-    // Run the application message loop.
-    async fn run(&mut self) {
+    // Run the application message loop. Each message is handled from the
+    // event loop, so the window stays responsive while the campaign works.
+    fn run(&mut self) {
         while self.app.wait() {
             if let Some(msg) = self.rcvr.recv() {
-                match msg {
-                    Message::Quit => {
-                        if let Some(c) = &self.cmpgn {
-                            c.close().await
-                        }
-                        app::quit()
-                    }
-                    Message::NewCampaign => self.new_campaign().await,
-                    Message::OpenCampaign => self.open_campaign().await,
-                    Message::CloseCampaign => self.close_campaign().await,
-                    Message::DeleteCampaign => self.delete_campaign().await,
-                    Message::HelpAbout => show_about(),
-                    Message::ShowSystems => self.show_systems().await,
-                    Message::ShowEmpires => self.show_empires().await,
-                }
+                pump::block_on(self.handle(msg));
             }
         }
     }
 
-    // Pop up new campaign dialog and set parameters.
-    async fn new_campaign(&mut self) {
-        if let Some(cm) = &self.cmpgn {
-            cm.close().await;
-            self.cmpgn = None;
+    // Handle a message from the main window.
+    async fn handle(&mut self, msg: Message) {
+        let leaving = matches!(
+            msg,
+            Message::Quit
+                | Message::Preferences
+                | Message::NewCampaign
+                | Message::OpenCampaign
+                | Message::OpenFile
+                | Message::CloseCampaign
+        );
+        if !leaving && !self.connected().await {
+            return;
         }
+        match msg {
+            Message::Quit => {
+                if let Some(c) = &self.cmpgn {
+                    c.close().await
+                }
+                app::quit()
+            }
+            Message::NewCampaign => self.new_campaign().await,
+            Message::OpenCampaign => self.open_campaign().await,
+            Message::OpenFile => self.open_file().await,
+            Message::Preferences => self.preferences().await,
+            Message::CampaignOverview => self.campaign_overview().await,
+            Message::RenameCampaign => self.rename_campaign().await,
+            Message::SaveCampaignAs => self.save_campaign_as().await,
+            Message::CloseCampaign => self.close_campaign().await,
+            Message::DeleteCampaign => self.delete_campaign().await,
+            Message::ImportCampaign => self.import_campaign().await,
+            Message::ImportWorkbook => self.import_workbook().await,
+            Message::ExportCampaign => self.export_campaign().await,
+            Message::RestoreSnapshot => self.restore_snapshot().await,
+            Message::PreviewTurn => self.preview_turn().await,
+            Message::AdvanceTurn => self.advance_turn().await,
+            Message::Undo => self.undo().await,
+            Message::Redo => self.redo().await,
+            Message::EconomicReport => self.economic_report().await,
+            Message::Journal => self.show_journal().await,
+            Message::AuditLog => self.show_log().await,
+            Message::WarReport => self.war_report().await,
+            Message::ClassReport => self.class_report().await,
+            Message::ReportTemplates => self.report_templates(),
+            Message::Notifications => self.show_notifications(),
+            Message::PrintableExport => self.export_printable().await,
+            Message::Locale => self.choose_locale().await,
+            Message::CombatModifiers => self.show_combat_modifiers().await,
+            Message::RulesReference => self.show_rules_reference().await,
+            Message::ColonialAdmin => self.colonial_admin().await,
+            Message::DeficitLimit => self.deficit_limit().await,
+            Message::DiceSeed => self.dice_seed().await,
+            Message::StrategicWarfare => self.strategic_warfare().await,
+            Message::Attrition => self.attrition().await,
+            Message::LoadSetting => self.load_setting().await,
+            Message::ReloadSetting => self.reload_setting().await,
+            Message::HelpAbout => show_about(),
+            Message::ShowSystems => self.show_systems().await,
+            Message::ShowEmpires => self.show_empires().await,
+            Message::ShowFleets => self.show_fleets().await,
+            Message::ShowLanes => self.show_lanes().await,
+            Message::ShowShipTypes => self.show_ship_types().await,
+            Message::ShowConstruction => self.show_construction().await,
+            Message::ShowTreaties => self.show_treaties().await,
+            Message::ShowMap => self.show_map().await,
+            Message::SetDeadline => self.set_deadline().await,
+            Message::MarkSubmitted => self.mark_submitted().await,
+            Message::FillMissingOrders => self.fill_missing_orders().await,
+        }
+        self.fill_dashboard().await;
+    }
 
-        let total_width = 300;
-        let total_height = 300;
-        let full_width = total_width - 2 * SPACING;
+    // Show a notice over the dashboard for a few seconds, without waiting on
+    // the GM, and keep it in the notice history.
+    fn notify(&mut self, text: &str) {
+        self.notices.push(text);
+        if let Some(t) = self.toast_timer.take() {
+            app::remove_timeout3(t);
+        }
+        self.toast.set_label(text);
+        self.toast.show();
+        self.main_win.redraw();
+        let mut toast = self.toast.clone();
+        self.toast_timer = Some(app::add_timeout3(notice::TOAST_SECONDS, move |_| {
+            toast.hide();
+            app::redraw();
+        }));
+    }
 
+    // Show the notices given this session, newest first.
+    fn show_notifications(&mut self) {
+        if self.notices.is_empty() {
+            dialog::message_default("No notices have been given this session.");
+            return;
+        }
         let mut wind = window::Window::default()
-            .with_size(total_width, total_height)
-            .center_screen()
-            .with_label("Create New Campaign");
-
-        frame::Frame::default()
-            .with_label("New Campaign Name")
-            .with_pos(SPACING, SPACING)
-            .with_size(full_width, TEXT_HEIGHT);
-        let name_input = input::Input::default()
-            .with_pos(SPACING, 2 * SPACING + TEXT_HEIGHT)
-            .with_size(full_width, TEXT_HEIGHT);
+            .with_size(600, 400)
+            .with_label("Notifications")
+            .center_screen();
+        let mut browse = SelectBrowser::default().with_pos(5, 5).with_size(590, 300);
+        for n in self.notices.latest_first() {
+            browse.add(n.as_str());
+        }
 
-        // TODO Add Campaign options controls
+        let (s, r) = app::channel();
 
-        let button_y = total_height - BTN_HEIGHT - SPACING;
-        let mut ok = button::Button::default()
-            .with_label("Ok")
+        let button_y = 310; // Browser bottom plus spacing
+        button::Button::default()
+            .with_label("Clear")
             .with_pos(SPACING, button_y)
-            .with_size(BTN_WIDTH, BTN_HEIGHT);
-        let mut cancel = button::Button::default()
-            .with_label("Cancel")
-            .with_pos(BTN_WIDTH + 2 * SPACING, button_y)
-            .with_size(BTN_WIDTH, BTN_HEIGHT);
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Clear");
 
         wind.end();
-        wind.make_modal(true);
         wind.show();
 
-        let (s, r) = app::channel();
-        ok.emit(s, true);
-        cancel.emit(s, false);
-
-        let mut is_ok = false;
-        while wind.shown() && self.app.wait() {
-            if let Some(a) = r.recv() {
-                is_ok = match a {
-                    true => true,
-                    false => false,
-                };
-                wind.hide();
+        while wind.shown() && app::wait() {
+            if let Some("Clear") = r.recv() {
+                self.notices.clear();
+                browse.clear();
             }
         }
+    }
 
-        if is_ok && !name_input.value().is_empty() {
-            let c = campaign::Campaign::new(name_input.value()).await;
-            self.cmpgn = match c {
-                Ok(cm) => {
-                    println!("Created {} campaign", cm.name());
-                    Some(cm)
-                }
-                Err(s) => {
-                    dialog::alert_default(s.as_str());
-                    None
+    // Fill the dashboard with each empire's order status for the turn.
+    async fn fill_dashboard(&mut self) {
+        self.dashboard.clear();
+        let c = match &self.cmpgn {
+            Some(c) => c,
+            None => return,
+        };
+        self.dashboard.add("Empire\tDue\tSubmitted\tStatus");
+        let today = c.today().await.unwrap_or_default();
+        let locale = c.locale().await.unwrap_or(&LOCALES[0]);
+        match c.deadlines().await {
+            Ok(v) => {
+                for d in v {
+                    self.dashboard
+                        .add_with_data(d.as_row(today.as_str(), locale).as_str(), d);
                 }
-            };
-            self.set_title();
+            }
+            Err(e) => dialog::alert_default(e.as_str()),
         }
     }
 
-    // Pop up list of campaigns to select from.
-    async fn open_campaign(&mut self) {
-        if let Some(name) = self.list_campaigns("Open".to_string()) {
-            if let Some(cm) = &self.cmpgn {
-                cm.close().await;
+    // Set the order deadline of the selected empire, or of every empire if
+    // none is selected.
+    async fn set_deadline(&mut self) {
+        let c = match &self.cmpgn {
+            Some(c) => c,
+            None => return,
+        };
+        let sel = self.dashboard.value();
+        // Ignore header, so only use the selection if 2+
+        let selected = if sel > 1 {
+            unsafe { self.dashboard.data::<Deadline>(sel) }
+        } else {
+            None
+        };
+        let prompt = match &selected {
+            Some(d) => format!("Orders due for {} (YYYY-MM-DD)", d.empire_name),
+            None => "Orders due for all empires (YYYY-MM-DD)".to_string(),
+        };
+        let today = c.today().await.unwrap_or_default();
+        let due = match dialog::input_default(prompt.as_str(), &today[..today.len().min(10)]) {
+            Some(d) => d,
+            None => return,
+        };
+        let empires: Vec<i64> = match selected {
+            Some(d) => vec![d.empire],
+            None => match c.deadlines().await {
+                Ok(v) => v.iter().map(|d| d.empire).collect(),
+                Err(e) => {
+                    dialog::alert_default(e.as_str());
+                    return;
+                }
+            },
+        };
+        for e in empires {
+            if let Err(e) = c.set_deadline(e, due.as_str()).await {
+                dialog::alert_default(e.as_str());
+                return;
+            }
+        }
+    }
+
+    // Record that the selected empire has submitted its orders.
+    async fn mark_submitted(&mut self) {
+        let c = match &self.cmpgn {
+            Some(c) => c,
+            None => return,
+        };
+        let sel = self.dashboard.value();
+        if sel > 1 {
+            // Ignore header, so only mark if 2+
+            if let Some(d) = unsafe { self.dashboard.data::<Deadline>(sel) } {
+                if let Err(e) = c.submit_orders(d.empire).await {
+                    dialog::alert_default(e.as_str());
+                }
+            }
+        }
+    }
+
+    // Give default orders to empires that missed their deadline.
+    async fn fill_missing_orders(&mut self) {
+        let c = match &self.cmpgn {
+            Some(c) => c,
+            None => return,
+        };
+        match c.fill_missing_orders().await {
+            Ok(v) if v.is_empty() => self.notify("No orders are missing."),
+            Ok(v) => {
+                let text = format!("Default orders given to {}.", v.join(", "));
+                self.notify(text.as_str());
+            }
+            Err(e) => dialog::alert_default(e.as_str()),
+        }
+    }
+
+    // Pop up new campaign dialog and set parameters.
+    async fn new_campaign(&mut self) {
+        if let Some(cm) = &self.cmpgn {
+            cm.close().await;
+            self.cmpgn = None;
+        }
+
+        let total_width = 300;
+        let total_height = 340 + 2 * (TEXT_HEIGHT + SPACING);
+        let full_width = total_width - 2 * SPACING;
+        let label_width = 120;
+        let input_width = total_width - label_width - 2 * SPACING;
+        let defaults = CampaignOptions::default();
+
+        let mut wind = window::Window::default()
+            .with_size(total_width, total_height)
+            .center_screen()
+            .with_label("Create New Campaign");
+
+        frame::Frame::default()
+            .with_label("New Campaign Name")
+            .with_pos(SPACING, SPACING)
+            .with_size(full_width, TEXT_HEIGHT);
+        let name_input = input::Input::default()
+            .with_pos(SPACING, 2 * SPACING + TEXT_HEIGHT)
+            .with_size(full_width, TEXT_HEIGHT);
+
+        // Campaign options, one per row below the name.
+        let row_y = |n: i32| (n + 3) * SPACING + (n + 2) * TEXT_HEIGHT;
+        let mut turn_input = input::IntInput::default()
+            .with_label("Starting turn")
+            .with_pos(label_width + SPACING, row_y(0))
+            .with_size(input_width, TEXT_HEIGHT);
+        turn_input.set_value(defaults.start_turn.to_string().as_str());
+        let mut edition_choice = menu::Choice::default()
+            .with_label("Rules edition")
+            .with_pos(label_width + SPACING, row_y(1))
+            .with_size(input_width, TEXT_HEIGHT);
+        for e in options::EDITIONS {
+            edition_choice.add_choice(e);
+        }
+        edition_choice.set_value(0);
+        let mut map_choice = menu::Choice::default()
+            .with_label("Map size")
+            .with_pos(label_width + SPACING, row_y(2))
+            .with_size(input_width, TEXT_HEIGHT);
+        for (size, systems) in options::MAP_SIZES {
+            map_choice.add_choice(format!("{} ({} systems)", size, systems).as_str());
+        }
+        map_choice.set_value(
+            options::MAP_SIZES
+                .iter()
+                .position(|(m, _)| *m == defaults.map_size)
+                .unwrap_or(0) as i32,
+        );
+        let mut empires_choice = menu::Choice::default()
+            .with_label("Empires")
+            .with_pos(label_width + SPACING, row_y(3))
+            .with_size(input_width, TEXT_HEIGHT);
+        empires_choice.add_choice("No limit");
+        for n in 2..=options::MAX_EMPIRES {
+            empires_choice.add_choice(n.to_string().as_str());
+        }
+        empires_choice.set_value(0);
+        let mut difficulty_choice = menu::Choice::default()
+            .with_label("Economy")
+            .with_pos(label_width + SPACING, row_y(4))
+            .with_size(input_width, TEXT_HEIGHT);
+        for (d, percent) in options::DIFFICULTIES {
+            difficulty_choice.add_choice(format!("{} ({}% income)", d, percent).as_str());
+        }
+        difficulty_choice.set_value(
+            options::DIFFICULTIES
+                .iter()
+                .position(|(d, _)| *d == defaults.difficulty)
+                .unwrap_or(0) as i32,
+        );
+        let admin_check = button::CheckButton::default()
+            .with_label("Colonial administration")
+            .with_pos(SPACING, row_y(5))
+            .with_size(full_width, TEXT_HEIGHT);
+        let warfare_check = button::CheckButton::default()
+            .with_label("Strategic warfare")
+            .with_pos(SPACING, row_y(6))
+            .with_size(full_width, TEXT_HEIGHT);
+        let attrition_check = button::CheckButton::default()
+            .with_label("Attrition")
+            .with_pos(SPACING, row_y(7))
+            .with_size(full_width, TEXT_HEIGHT);
+        let seed_input = input::Input::default()
+            .with_label("Dice seed")
+            .with_pos(label_width + SPACING, row_y(8))
+            .with_size(input_width, TEXT_HEIGHT);
+
+        let button_y = total_height - BTN_HEIGHT - SPACING;
+        let mut ok = button::Button::default()
+            .with_label("Ok")
+            .with_pos(SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT);
+        let mut cancel = button::Button::default()
+            .with_label("Cancel")
+            .with_pos(BTN_WIDTH + 2 * SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT);
+
+        wind.end();
+        wind.make_modal(true);
+        wind.show();
+
+        let (s, r) = app::channel();
+        ok.emit(s, true);
+        cancel.emit(s, false);
+
+        // Check the name before closing, so it can be corrected.
+        let existing = match campaign::Campaign::campaigns() {
+            Ok(v) => v,
+            Err(e) => {
+                dialog::alert_default(e.as_str());
+                Vec::new()
+            }
+        };
+        let mut is_ok = false;
+        while wind.shown() && self.app.wait() {
+            if let Some(a) = r.recv() {
+                if a {
+                    if let Err(e) = naming::validate(&name_input.value(), &existing) {
+                        dialog::alert_default(e.as_str());
+                        continue;
+                    }
+                    if let Err(e) = dice::parse_seed(&seed_input.value()) {
+                        dialog::alert_default(e.as_str());
+                        continue;
+                    }
+                }
+                is_ok = a;
+                wind.hide();
             }
-            let c = campaign::Campaign::open(&name).await;
+        }
+
+        if is_ok {
+            let empires = empires_choice.value();
+            let options = CampaignOptions {
+                start_turn: turn_input.value().parse().unwrap_or(defaults.start_turn),
+                edition: options::EDITIONS[edition_choice.value().max(0) as usize].to_string(),
+                map_size: options::MAP_SIZES[map_choice.value().max(0) as usize]
+                    .0
+                    .to_string(),
+                // The first choice is no limit, and the rest start at two.
+                empires: if empires > 0 { empires + 1 } else { 0 },
+                difficulty: options::DIFFICULTIES[difficulty_choice.value().max(0) as usize]
+                    .0
+                    .to_string(),
+                colonial_admin: admin_check.is_checked(),
+                strategic_warfare: warfare_check.is_checked(),
+                attrition: attrition_check.is_checked(),
+                setting: defaults.setting.clone(),
+                setting_file: defaults.setting_file.clone(),
+                dice_seed: dice::parse_seed(&seed_input.value()).unwrap_or(0),
+                locale: defaults.locale.clone(),
+            };
+            let c = campaign::Campaign::new(name_input.value(), &options).await;
             self.cmpgn = match c {
                 Ok(cm) => {
-                    println!("Opened {} campaign", name);
+                    self.notify(format!("Created {} campaign", cm.name()).as_str());
                     Some(cm)
                 }
                 Err(s) => {
@@ -256,91 +948,5634 @@ impl VBAMApp {
                     None
                 }
             };
+            self.set_title();
+        }
+    }
+
+    // Pop up list of campaigns to select from.
+    async fn open_campaign(&mut self) {
+        if let Some(name) = self.list_campaigns("Open".to_string()) {
+            self.open_named(&name).await;
         }
         self.set_title();
     }
 
-    // Close the current campaign, if any.
-    async fn close_campaign(&mut self) {
-        if let Some(cm) = &self.cmpgn {
+    // Open a campaign database from anywhere, leaving the folder campaigns
+    // are kept in as it is.
+    async fn open_file(&mut self) {
+        let mut chooser = dialog::NativeFileChooser::new(dialog::NativeFileChooserType::BrowseFile);
+        chooser.set_title("Open campaign database");
+        chooser.set_filter("Campaign databases\t*.db");
+        chooser.show();
+        let file = chooser.filename();
+        if file.as_os_str().is_empty() {
+            return;
+        }
+        if let Some(cm) = self.cmpgn.take() {
             cm.close().await;
-            self.cmpgn = None;
         }
+        match Campaign::open_file(&file).await {
+            Ok(cm) => {
+                self.notify(format!("Opened {} campaign", cm.name()).as_str());
+                self.cmpgn = Some(cm);
+            }
+            Err(e) => dialog::alert_default(e.as_str()),
+        }
+        self.set_title();
+    }
 
+    // Choose the folder campaigns are kept in. The open campaign is closed
+    // first, as its attached files are kept there too.
+    async fn preferences(&mut self) {
+        let prompt = format!("Campaigns are kept in\n{}", Campaign::data_dir().display());
+        let dir = match dialog::choice2_default(
+            prompt.as_str(),
+            "Cancel",
+            "Choose Folder...",
+            "Use Default",
+        ) {
+            Some(1) => {
+                let mut chooser =
+                    dialog::NativeFileChooser::new(dialog::NativeFileChooserType::BrowseDir);
+                chooser.set_title("Keep campaigns in");
+                chooser.show();
+                let dir = chooser.filename();
+                if dir.as_os_str().is_empty() {
+                    return;
+                }
+                dir
+            }
+            Some(2) => config::default_data_dir(),
+            _ => return,
+        };
+        if let Some(cm) = self.cmpgn.take() {
+            cm.close().await;
+        }
+        match Campaign::set_data_dir(&dir) {
+            Ok(_) => self.notify(format!("Campaigns are kept in {}", dir.display()).as_str()),
+            Err(e) => dialog::alert_default(e.as_str()),
+        }
         self.set_title();
     }
 
-    // Pop up list of campaigns to select one to delete.
-    async fn delete_campaign(&mut self) {
-        if let Some(name) = self.list_campaigns("Delete".to_string()) {
-            match &self.cmpgn {
-                Some(cm) => {
-                    cm.close().await;
-                    self.cmpgn = None;
-                    self.set_title();
+    // Close the current campaign, if any, and open the named one.
+    async fn open_named(&mut self, name: &str) {
+        if let Some(cm) = &self.cmpgn {
+            cm.close().await;
+        }
+        let c = campaign::Campaign::open(name).await;
+        self.cmpgn = match c {
+            Ok(cm) => {
+                self.notify(format!("Opened {} campaign", name).as_str());
+                Some(cm)
+            }
+            Err(s) => {
+                dialog::alert_default(s.as_str());
+                None
+            }
+        };
+    }
+
+    // Show every campaign with its turn, empire count, and when it was last
+    // modified, and open the one selected.
+    async fn campaign_overview(&mut self) {
+        let summaries = match Campaign::campaign_summaries().await {
+            Ok(v) => v,
+            Err(e) => {
+                dialog::alert_default(e.as_str());
+                return;
+            }
+        };
+
+        let mut wind = window::Window::default()
+            .with_size(600, 400)
+            .with_label("Campaign Overview")
+            .center_screen();
+        let mut browse = SelectBrowser::default().with_pos(5, 5).with_size(590, 300);
+        browse.set_column_widths(&[250, 60, 80, 180]);
+        browse.set_column_char('\t');
+        browse.add("Campaign\tTurn\tEmpires\tLast Modified");
+        for c in summaries {
+            browse.add_with_data(c.as_row().as_str(), c);
+        }
+
+        let (s, r) = app::channel();
+
+        let button_y = 310; // Browser bottom plus spacing
+        button::Button::default()
+            .with_label("Open")
+            .with_pos(SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Open");
+
+        wind.end();
+        wind.show();
+
+        let mut chosen = None;
+        while wind.shown() && app::wait() {
+            if let Some(m) = r.recv() {
+                if m == "Open" {
+                    let sel = browse.value();
+                    if sel > 1 {
+                        // Ignore header, so only open if 2+
+                        unsafe {
+                            if let Some(c) = browse.data::<CampaignSummary>(sel) {
+                                chosen = Some(c.name);
+                                wind.hide();
+                            }
+                        }
+                    }
                 }
-                None => (),
             }
-            match campaign::Campaign::delete(&name) {
-                Ok(_) => println!("Deleted {} campaign", name),
-                Err(s) => dialog::alert_default(s.as_str()),
+        }
+
+        if let Some(name) = chosen {
+            self.open_named(&name).await;
+        }
+    }
+
+    // Import a campaign from an archive file and make it current.
+    async fn import_campaign(&mut self) {
+        let file =
+            match dialog::file_chooser("Import archive from...", "*.{vbamcma,json}", ".", true) {
+                Some(f) => f,
+                None => return,
+            };
+        match Campaign::import(file.as_str()).await {
+            Ok(c) => {
+                if let Some(cm) = &self.cmpgn {
+                    cm.close().await;
+                }
+                self.notify(format!("Imported {} campaign", c.name()).as_str());
+                self.cmpgn = Some(c);
             }
+            Err(e) => dialog::alert_default(e.as_str()),
         }
+        self.set_title();
     }
 
-    // Edit the system. Returns None if canceled, Some(system) if edited.
-    async fn edit_system(&mut self, sys: System) -> Option<System> {
-        println!("System: {}", sys.as_row());
-        None
+    // Create a campaign from the sheets of a campaign workbook saved as CSV,
+    // and make it current.
+    async fn import_workbook(&mut self) {
+        let folder = match dialog::dir_chooser("Workbook sheets folder...", ".", true) {
+            Some(f) => f,
+            None => return,
+        };
+        let suggested = std::path::Path::new(&folder)
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("")
+            .to_string();
+        let name = match dialog::input_default("New campaign name", suggested.as_str()) {
+            Some(n) => n,
+            None => return,
+        };
+        match Campaign::import_workbook(name, folder.as_str()).await {
+            Ok(c) => {
+                if let Some(cm) = &self.cmpgn {
+                    cm.close().await;
+                }
+                self.notify(format!("Imported {} campaign from workbook", c.name()).as_str());
+                self.cmpgn = Some(c);
+            }
+            Err(e) => dialog::alert_default(e.as_str()),
+        }
+        self.set_title();
     }
 
-    // Fill the system browser with the campaign's data.
-    async fn fill_system_browser(browse: &mut SelectBrowser, c: &Campaign) {
-        browse.clear();
-        browse.add("Name\tType\tRAW\tCAP\tPOP\tMOR\tIND\tDev\tFails\tOwner");
+    // Roll the current campaign back to one of its backup snapshots.
+    async fn restore_snapshot(&mut self) {
+        let names = match &self.cmpgn {
+            Some(c) => match c.backups() {
+                Ok(v) => v,
+                Err(e) => {
+                    dialog::alert_default(e.as_str());
+                    return;
+                }
+            },
+            None => return,
+        };
+        if names.is_empty() {
+            self.notify("There are no snapshots of this campaign yet.");
+            return;
+        }
+
+        let total_width = 400;
+        let total_height = 300;
+
+        let mut wind = window::Window::default()
+            .with_size(total_width, total_height)
+            .with_label("Restore Snapshot")
+            .center_screen();
+        let button_y = total_height - SPACING - BTN_HEIGHT;
+        let mut browse = SelectBrowser::default()
+            .with_pos(SPACING, SPACING)
+            .with_size(total_width - 2 * SPACING, button_y - 2 * SPACING);
+        for n in &names {
+            browse.add(n.as_str());
+        }
+        let mut ok = button::Button::default()
+            .with_label("Restore")
+            .with_pos(SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT);
+        let mut cancel = button::Button::default()
+            .with_label("Cancel")
+            .with_pos(BTN_WIDTH + 2 * SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT);
+        wind.end();
+        wind.make_modal(true);
+        wind.show();
+
+        let (s, r) = app::channel();
+        ok.emit(s, true);
+        cancel.emit(s, false);
+
+        let mut chosen = None;
+        while wind.shown() && self.app.wait() {
+            if let Some(a) = r.recv() {
+                if a && browse.value() > 0 {
+                    chosen = browse.text(browse.value());
+                }
+                wind.hide();
+            }
+        }
 
-        if let Ok(v) = c.systems().await {
-            for s in v {
-                browse.add_with_data(s.as_row().as_str(), s);
+        if let Some(name) = chosen {
+            let prompt = format!(
+                "Roll the campaign back to {}? Changes since then will be lost.",
+                name
+            );
+            if dialog::choice2_default(prompt.as_str(), "Cancel", "Restore", "") == Some(1) {
+                if let Some(c) = &mut self.cmpgn {
+                    match c.restore_backup(name.as_str()).await {
+                        Ok(_) => self.notify(format!("Restored snapshot {}", name).as_str()),
+                        Err(e) => dialog::alert_default(e.as_str()),
+                    }
+                }
             }
         }
+        self.set_title();
     }
 
-    // Import a list of systems from a CSV file.
-    async fn import_systems(&mut self) {
-        let c = match &mut self.cmpgn {
+    // Export the current campaign to an archive file.
+    async fn export_campaign(&mut self) {
+        let c = match &self.cmpgn {
             Some(c) => c,
             None => return,
         };
-
-        // Choose the CSV file
-        if let Some(file) = dialog::file_chooser("Import systems from...", "*.csv", ".", true) {
-            if let Err(e) = c.import_systems(file.as_str()).await {
-                dialog::alert_default(e.as_str())
+        if let Some(mut file) = dialog::file_chooser("Export archive to...", "*.vbamcma", ".", true)
+        {
+            if path::Path::new(file.as_str()).extension().is_none() {
+                file = format!("{}.{}", file, archive::EXTENSION);
+            }
+            // Offer to include the GM's journal if there is one.
+            let journal = match c.journal().await {
+                Ok(v) if !v.is_empty() => {
+                    dialog::choice2_default("Include the GM journal?", "No", "Yes", "") == Some(1)
+                }
+                _ => false,
+            };
+            let done = format!("Exported {} to {}", c.name(), file);
+            match c.export(file.as_str(), journal).await {
+                Ok(_) => self.notify(done.as_str()),
+                Err(e) => dialog::alert_default(e.as_str()),
             }
         }
     }
 
-    // Pop up the select campaign dialog and return the user's choice.
-    fn list_campaigns(&mut self, function: String) -> Option<String> {
-        let names = match Campaign::campaigns() {
-            Ok(v) => v.join("|"),
-            _ => return None,
-        };
+    // Close the current campaign, if any.
+    async fn close_campaign(&mut self) {
+        if let Some(cm) = &self.cmpgn {
+            cm.close().await;
+            self.cmpgn = None;
+        }
 
-        let total_width = SPACING + 2 * (BTN_WIDTH + SPACING);
-        let total_height = 150;
+        self.set_title();
+    }
+
+    // Make sure the open campaign's database can still be reached, offering
+    // to reconnect until it can be or the campaign is closed. Returns whether
+    // the campaign is still open.
+    async fn connected(&mut self) -> bool {
+        let c = match &mut self.cmpgn {
+            Some(c) => c,
+            None => return true,
+        };
+        let mut problem = match c.check_connection().await {
+            Ok(_) => return true,
+            Err(e) => e,
+        };
+        loop {
+            let prompt = format!(
+                "{}\n\nIf the campaign is kept on a network drive or in a synced folder, \
+                make sure it can be reached, then retry.",
+                problem
+            );
+            if dialog::choice2_default(prompt.as_str(), "Close Campaign", "Retry", "") != Some(1) {
+                break;
+            }
+            match c.reconnect().await {
+                Ok(_) => return true,
+                Err(e) => problem = e,
+            }
+        }
+        self.cmpgn = None;
+        self.set_title();
+        false
+    }
+
+    // Rename the current campaign, with its files.
+    async fn rename_campaign(&mut self) {
+        let c = match &mut self.cmpgn {
+            Some(c) => c,
+            None => return,
+        };
+        let old = c.name().clone();
+        let name = match dialog::input_default("New name for the campaign", old.as_str()) {
+            Some(n) => n,
+            None => return,
+        };
+        match c.rename(name.as_str()).await {
+            Ok(_) => {
+                let text = format!("Renamed {} campaign to {}", old, c.name());
+                self.notify(text.as_str());
+            }
+            Err(e) => dialog::alert_default(e.as_str()),
+        }
+        self.set_title();
+    }
+
+    // Copy the current campaign under a new name and carry on in the copy,
+    // leaving the original as it was.
+    async fn save_campaign_as(&mut self) {
+        let c = match &self.cmpgn {
+            Some(c) => c,
+            None => return,
+        };
+        let suggested = format!("{} Copy", c.name());
+        let name = match dialog::input_default("Name for the copy", suggested.as_str()) {
+            Some(n) => n,
+            None => return,
+        };
+        match c.save_as(name.as_str()).await {
+            Ok(copy) => {
+                c.close().await;
+                let text = format!("Saved {} as {}", c.name(), copy.name());
+                self.cmpgn = Some(copy);
+                self.notify(text.as_str());
+            }
+            Err(e) => dialog::alert_default(e.as_str()),
+        }
+        self.set_title();
+    }
+
+    // Pop up list of campaigns to select one to delete.
+    async fn delete_campaign(&mut self) {
+        if let Some(name) = self.list_campaigns("Delete".to_string()) {
+            match &self.cmpgn {
+                Some(cm) => {
+                    cm.close().await;
+                    self.cmpgn = None;
+                    self.set_title();
+                }
+                None => (),
+            }
+            match campaign::Campaign::delete(&name) {
+                Ok(_) => self.notify(format!("Deleted {} campaign", name).as_str()),
+                Err(s) => dialog::alert_default(s.as_str()),
+            }
+        }
+    }
+
+    // Show what resolving the current turn would do, without doing it.
+    async fn preview_turn(&mut self) {
+        if let Some(c) = &self.cmpgn {
+            match c.preview_turn().await {
+                Ok(p) => show_report(
+                    format!("Turn {} Preview", p.report.turn).as_str(),
+                    p.to_string().as_str(),
+                ),
+                Err(e) => dialog::alert_default(e.as_str()),
+            }
+        }
+    }
+
+    // Resolve the current turn and show what happened, once the checklist
+    // is clear.
+    async fn advance_turn(&mut self) {
+        if self.cmpgn.is_none() || !self.show_checklist().await {
+            return;
+        }
+        if let Some(c) = &mut self.cmpgn {
+            let prompt = format!("Resolve {} and advance to the next turn?", c.title());
+            if dialog::choice2_default(prompt.as_str(), "Cancel", "Advance", "") != Some(1) {
+                return;
+            }
+            match c.advance_turn().await {
+                Ok(r) => show_report(
+                    format!("Turn {} Report", r.turn).as_str(),
+                    r.to_string().as_str(),
+                ),
+                Err(e) => dialog::alert_default(e.as_str()),
+            }
+        }
+        self.set_title();
+    }
+
+    async fn fill_checklist_browser(browse: &mut browser::HoldBrowser, c: &Campaign) -> bool {
+        browse.clear();
+        browse.add("Check\tProblem");
+        match c.checklist().await {
+            Ok(v) => {
+                let clear = v.is_empty();
+                for item in v {
+                    browse.add_with_data(item.as_row().as_str(), item.fix);
+                }
+                clear
+            }
+            Err(e) => {
+                dialog::alert_default(e.as_str());
+                false
+            }
+        }
+    }
+
+    // Show what must be put right before the turn can be advanced, with a
+    // way to each fix. Returns whether every check has passed.
+    async fn show_checklist(&mut self) -> bool {
+        match self.cmpgn.as_ref().unwrap().checklist().await {
+            Ok(v) if v.is_empty() => return true,
+            Ok(_) => (),
+            Err(e) => {
+                dialog::alert_default(e.as_str());
+                return false;
+            }
+        }
+
+        let mut wind = window::Window::default()
+            .with_size(600, 400)
+            .with_label("Before Advancing")
+            .center_screen();
+        let mut browse = browser::HoldBrowser::default()
+            .with_pos(5, 5)
+            .with_size(590, 350);
+        browse.set_column_widths(&[120, 470]);
+        browse.set_column_char('\t');
+        Self::fill_checklist_browser(&mut browse, self.cmpgn.as_ref().unwrap()).await;
+
+        let (s, r) = app::channel();
+
+        let button_y = 360; // Browser bottom plus spacing
+        button::Button::default()
+            .with_label("Fix")
+            .with_pos(SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Fix");
+        button::Button::default()
+            .with_label("Check Again")
+            .with_pos(BTN_WIDTH + 2 * SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Check");
+        let mut advance = button::Button::default()
+            .with_label("Advance")
+            .with_pos(SPACING + 2 * (BTN_WIDTH + SPACING), button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT);
+        advance.emit(s, "Advance");
+        advance.deactivate();
+
+        wind.end();
+        wind.show();
+
+        while wind.shown() && app::wait() {
+            if let Some(m) = r.recv() {
+                match m {
+                    "Fix" => {
+                        let sel = browse.value();
+                        if sel > 1 {
+                            // Ignore header, so only fix if 2+
+                            if let Some(fix) = unsafe { browse.data::<Fix>(sel) } {
+                                self.fix_check(fix).await;
+                            }
+                        }
+                    }
+                    "Advance" => {
+                        wind.hide();
+                        return true;
+                    }
+                    _ => (),
+                }
+                let c = self.cmpgn.as_ref().unwrap();
+                if Self::fill_checklist_browser(&mut browse, c).await {
+                    browse.add("\tEvery check has passed");
+                    advance.activate();
+                } else {
+                    advance.deactivate();
+                }
+            }
+        }
+        false
+    }
+
+    // Go where a failed check can be put right.
+    async fn fix_check(&mut self, fix: Fix) {
+        let c = self.cmpgn.as_ref().unwrap();
+        let (systems, empires) = match (c.systems().await, c.empires().await) {
+            (Ok(s), Ok(e)) => (s, e),
+            (Err(e), _) | (_, Err(e)) => {
+                dialog::alert_default(e.as_str());
+                return;
+            }
+        };
+        let system = |id: i64| systems.iter().find(|s| s.id == id).cloned();
+        match fix {
+            Fix::Orders(id) => {
+                if let Some(emp) = empires.into_iter().find(|e| e.id == id) {
+                    self.show_orders(&emp).await;
+                }
+            }
+            Fix::Battle(id) => {
+                if let Some(sys) = system(id) {
+                    self.fight_battle(&sys).await;
+                }
+            }
+            Fix::Construction => self.show_construction().await,
+            Fix::Garrison(id) => {
+                if let Some(sys) = system(id) {
+                    self.show_garrison(&sys).await;
+                }
+            }
+            Fix::Fleets => self.show_fleets().await,
+        }
+    }
+
+    // Undo the last edit, after confirming it.
+    async fn undo(&mut self) {
+        if let Some(c) = &mut self.cmpgn {
+            let label = match c.undo_label() {
+                Some(l) => l,
+                None => {
+                    self.notify("Nothing to undo.");
+                    return;
+                }
+            };
+            let prompt = format!("Undo {}?", label);
+            if dialog::choice2_default(prompt.as_str(), "Cancel", "Undo", "") != Some(1) {
+                return;
+            }
+            if let Err(e) = c.undo().await {
+                dialog::alert_default(e.as_str());
+            }
+        }
+        self.set_title();
+    }
+
+    // Redo the last undone edit, after confirming it.
+    async fn redo(&mut self) {
+        if let Some(c) = &mut self.cmpgn {
+            let label = match c.redo_label() {
+                Some(l) => l,
+                None => {
+                    self.notify("Nothing to redo.");
+                    return;
+                }
+            };
+            let prompt = format!("Redo {}?", label);
+            if dialog::choice2_default(prompt.as_str(), "Cancel", "Redo", "") != Some(1) {
+                return;
+            }
+            if let Err(e) = c.redo().await {
+                dialog::alert_default(e.as_str());
+            }
+        }
+        self.set_title();
+    }
+
+    // Turn the optional colonial administration rule on or off.
+    async fn colonial_admin(&mut self) {
+        if let Some(c) = &self.cmpgn {
+            let on = match c.colonial_admin().await {
+                Ok(v) => v,
+                Err(e) => {
+                    dialog::alert_default(e.as_str());
+                    return;
+                }
+            };
+            let prompt = format!(
+                "Colonial administration limits are {}. Empires holding more systems \
+                than they can administer lose income.",
+                if on { "in use" } else { "not in use" }
+            );
+            let answer = dialog::choice2_default(
+                prompt.as_str(),
+                "Cancel",
+                if on { "Turn Off" } else { "Turn On" },
+                "",
+            );
+            if answer == Some(1) {
+                if let Err(e) = c.set_colonial_admin(!on).await {
+                    dialog::alert_default(e.as_str());
+                }
+            }
+        }
+    }
+
+    // Set how far into deficit a treasury may be taken by hand.
+    async fn deficit_limit(&mut self) {
+        if let Some(c) = &self.cmpgn {
+            let limit = match c.deficit_limit().await {
+                Ok(v) => v,
+                Err(e) => {
+                    dialog::alert_default(e.as_str());
+                    return;
+                }
+            };
+            if let Some(v) = dialog::input_default(
+                "Deficit an empire's treasury may be set to by hand (EP)",
+                limit.to_string().as_str(),
+            ) {
+                let result = match v.trim().parse::<i32>() {
+                    Ok(l) => c.set_deficit_limit(l).await,
+                    Err(_) => Err("The limit must be a whole number.".to_string()),
+                };
+                if let Err(e) = result {
+                    dialog::alert_default(e.as_str());
+                }
+            }
+        }
+    }
+
+    // Set the seed the dice of every turn take in.
+    async fn dice_seed(&mut self) {
+        if let Some(c) = &self.cmpgn {
+            let seed = match c.options().await {
+                Ok(o) => o.dice_seed,
+                Err(e) => {
+                    dialog::alert_default(e.as_str());
+                    return;
+                }
+            };
+            if let Some(v) = dialog::input_default(
+                "Dice seed for resolving turns (blank for none)",
+                if seed == 0 {
+                    String::new()
+                } else {
+                    seed.to_string()
+                }
+                .as_str(),
+            ) {
+                let result = match dice::parse_seed(&v) {
+                    Ok(s) => c.set_dice_seed(s).await,
+                    Err(e) => Err(e),
+                };
+                if let Err(e) = result {
+                    dialog::alert_default(e.as_str());
+                }
+            }
+        }
+    }
+
+    // Load the unit rosters and planet types of a setting module file.
+    async fn load_setting(&mut self) {
+        let c = match &self.cmpgn {
+            Some(c) => c,
+            None => return,
+        };
+        let file = match dialog::file_chooser("Load setting module...", "*.json", ".", true) {
+            Some(f) => f,
+            None => return,
+        };
+        match c.load_setting(file.as_str()).await {
+            Ok(name) => self.notify(format!("Loaded {} setting", name).as_str()),
+            Err(e) => dialog::alert_default(e.as_str()),
+        }
+    }
+
+    // Read the campaign's setting module file again after it was edited.
+    async fn reload_setting(&mut self) {
+        let c = match &self.cmpgn {
+            Some(c) => c,
+            None => return,
+        };
+        match c.reload_setting().await {
+            Ok(name) => self.notify(format!("Reloaded {} setting", name).as_str()),
+            Err(e) => dialog::alert_default(e.as_str()),
+        }
+    }
+
+    // Turn the optional attrition rules on or off.
+    async fn attrition(&mut self) {
+        if let Some(c) = &self.cmpgn {
+            let on = match c.attrition().await {
+                Ok(v) => v,
+                Err(e) => {
+                    dialog::alert_default(e.as_str());
+                    return;
+                }
+            };
+            let prompt = format!(
+                "Attrition rules are {}. Ships beyond their empire's supply may be \
+                crippled, or lost if already crippled, at the end of the movement phase.",
+                if on { "in use" } else { "not in use" }
+            );
+            let answer = dialog::choice2_default(
+                prompt.as_str(),
+                "Cancel",
+                if on { "Turn Off" } else { "Turn On" },
+                "",
+            );
+            if answer == Some(1) {
+                if let Err(e) = c.set_attrition(!on).await {
+                    dialog::alert_default(e.as_str());
+                }
+            }
+        }
+    }
+
+    // Turn the optional strategic warfare rules on or off.
+    async fn strategic_warfare(&mut self) {
+        if let Some(c) = &self.cmpgn {
+            let on = match c.strategic_warfare().await {
+                Ok(v) => v,
+                Err(e) => {
+                    dialog::alert_default(e.as_str());
+                    return;
+                }
+            };
+            let prompt = format!(
+                "Strategic warfare rules are {}. Empires may order raids on enemy \
+                shipyards, trade and population, resolved in the combat phase.",
+                if on { "in use" } else { "not in use" }
+            );
+            let answer = dialog::choice2_default(
+                prompt.as_str(),
+                "Cancel",
+                if on { "Turn Off" } else { "Turn On" },
+                "",
+            );
+            if answer == Some(1) {
+                if let Err(e) = c.set_strategic_warfare(!on).await {
+                    dialog::alert_default(e.as_str());
+                }
+            }
+        }
+    }
+
+    // Set an empire's empire-wide morale modifier.
+    async fn set_morale_modifier(&mut self, emp: &Empire) {
+        let c = self.cmpgn.as_ref().unwrap();
+        let current = match c.morale_modifiers().await {
+            Ok(m) => m.get(&emp.id).copied().unwrap_or(0),
+            Err(e) => {
+                dialog::alert_default(e.as_str());
+                return;
+            }
+        };
+        let prompt = format!("Empire-wide morale modifier for {}", emp.name);
+        if let Some(v) = dialog::input_default(prompt.as_str(), current.to_string().as_str()) {
+            let amount = match v.trim().parse::<i32>() {
+                Ok(a) => a,
+                Err(_) => {
+                    dialog::alert_default("The modifier must be a whole number.");
+                    return;
+                }
+            };
+            if let Err(e) = c.set_morale_modifier(emp.id, amount).await {
+                dialog::alert_default(e.as_str());
+            }
+        }
+    }
+
+    // Fill the research browser with each empire's research standing.
+    async fn fill_research_browser(browse: &mut SelectBrowser, c: &Campaign) {
+        browse.clear();
+        browse.add("Empire\tTech\tSpending\tPoints\tNext Level");
+
+        match c.tech_status().await {
+            Ok(v) => {
+                for t in v {
+                    browse.add_with_data(t.as_row().as_str(), t);
+                }
+            }
+            Err(e) => dialog::alert_default(e.as_str()),
+        }
+    }
+
+    // Show each empire's research and record its spending for the turn.
+    async fn show_research(&mut self) {
+        if self.cmpgn.is_none() {
+            return;
+        }
+
+        let mut wind = window::Window::default()
+            .with_size(600, 400)
+            .with_label("Research")
+            .center_screen();
+        let mut browse = SelectBrowser::default().with_pos(5, 5).with_size(590, 300);
+        browse.set_column_widths(&[200, 60, 80, 80, 80]);
+        browse.set_column_char('\t');
+        Self::fill_research_browser(&mut browse, self.cmpgn.as_ref().unwrap()).await;
+
+        let (s, r) = app::channel();
+
+        let button_y = 310; // Browser bottom plus spacing
+        button::Button::default()
+            .with_label("Spending")
+            .with_pos(SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Spending");
+
+        wind.end();
+        wind.show();
+
+        while wind.shown() && app::wait() {
+            if let Some(m) = r.recv() {
+                if m == "Spending" {
+                    let sel = browse.value();
+                    if sel > 1 {
+                        // Ignore header, so only set if 2+
+                        if let Some(t) = unsafe { browse.data::<TechStatus>(sel) } {
+                            let prompt = format!("Research spending for {} (EP)", t.empire_name);
+                            if let Some(v) = dialog::input_default(
+                                prompt.as_str(),
+                                t.spending.to_string().as_str(),
+                            ) {
+                                let result = match v.trim().parse::<i32>() {
+                                    Ok(a) => {
+                                        self.cmpgn.as_ref().unwrap().set_research(t.empire, a).await
+                                    }
+                                    Err(_) => Err("Spending must be a whole number.".to_string()),
+                                };
+                                if let Err(e) = result {
+                                    dialog::alert_default(e.as_str());
+                                }
+                            }
+                        }
+                    }
+                }
+                Self::fill_research_browser(&mut browse, self.cmpgn.as_ref().unwrap()).await;
+            }
+        }
+    }
+
+    // Write the default report templates and say where to edit them.
+    fn report_templates(&mut self) {
+        if let Some(c) = &self.cmpgn {
+            match c.install_templates() {
+                Ok(dir) => {
+                    let text = format!(
+                        "Report templates are in\n{}\n\nEdit them to change the layout of \
+                        player packets and turn reports. Placeholders such as {{{{turn}}}} \
+                        are filled in when a report is made.",
+                        dir.display()
+                    );
+                    dialog::message_default(text.as_str());
+                }
+                Err(e) => dialog::alert_default(e.as_str()),
+            }
+        }
+    }
+
+    // Fill the journal browser with a row for each turn so far, showing the
+    // start of its entry.
+    async fn fill_journal_browser(browse: &mut SelectBrowser, c: &Campaign) {
+        browse.clear();
+        browse.add("Turn\tEntry");
+
+        match c.journal().await {
+            Ok(v) => {
+                for turn in 0..=c.turn() {
+                    let entry = v
+                        .iter()
+                        .find(|e| e.turn == turn)
+                        .cloned()
+                        .unwrap_or_else(|| JournalEntry::new(turn, ""));
+                    browse.add_with_data(entry.as_row().as_str(), turn);
+                }
+            }
+            Err(e) => dialog::alert_default(e.as_str()),
+        }
+    }
+
+    // Show the audit log of every change to the campaign, filtered by turn
+    // and category.
+    async fn show_log(&mut self) {
+        let log = match &self.cmpgn {
+            Some(c) => match c.log().await {
+                Ok(v) => v,
+                Err(e) => {
+                    dialog::alert_default(e.as_str());
+                    return;
+                }
+            },
+            None => return,
+        };
+        // Turn filter choices: all turns, then each turn logged, latest first.
+        let mut turns: Vec<Option<i32>> = vec![None];
+        let mut logged: Vec<i32> = log.iter().map(|e| e.turn).collect();
+        logged.sort_unstable_by(|a, b| b.cmp(a));
+        logged.dedup();
+        turns.extend(logged.into_iter().map(Some));
+        let turn_names: Vec<String> = turns
+            .iter()
+            .map(|t| t.map_or("All".to_string(), |t| t.to_string()))
+            .collect();
+        let categories: Vec<Option<&str>> = std::iter::once(None)
+            .chain(audit::CATEGORIES.iter().map(|c| Some(*c)))
+            .collect();
+
+        let mut wind = window::Window::default()
+            .with_size(600, 400)
+            .with_label("Log")
+            .center_screen();
+        let mut turn_choice = menu::Choice::default()
+            .with_label("Turn")
+            .with_pos(60, SPACING)
+            .with_size(100, TEXT_HEIGHT);
+        turn_choice.add_choice(turn_names.join("|").as_str());
+        turn_choice.set_value(0);
+        let mut category_choice = menu::Choice::default()
+            .with_label("Category")
+            .with_pos(240, SPACING)
+            .with_size(150, TEXT_HEIGHT);
+        category_choice.add_choice("All");
+        for c in audit::CATEGORIES {
+            category_choice.add_choice(c);
+        }
+        category_choice.set_value(0);
+        let browse_y = 2 * SPACING + TEXT_HEIGHT;
+        let mut browse = SelectBrowser::default()
+            .with_pos(SPACING, browse_y)
+            .with_size(590, 400 - browse_y - SPACING);
+        browse.set_column_widths(&[40, 140, 70]);
+        browse.set_column_char('\t');
+
+        let fill = |browse: &mut SelectBrowser, turn: i32, category: i32| {
+            let turn = turns.get(turn.max(0) as usize).copied().flatten();
+            let category = categories.get(category.max(0) as usize).copied().flatten();
+            browse.clear();
+            browse.add("Turn\tTime\tCategory\tEntry");
+            for e in audit::filter(&log, turn, category) {
+                browse.add(e.as_row().as_str());
+            }
+        };
+        fill(&mut browse, 0, 0);
+
+        let (s, r) = app::channel();
+        turn_choice.emit(s, "Filter");
+        category_choice.emit(s, "Filter");
+
+        wind.end();
+        wind.show();
+
+        while wind.shown() && app::wait() {
+            if let Some("Filter") = r.recv() {
+                fill(&mut browse, turn_choice.value(), category_choice.value());
+            }
+        }
+    }
+
+    // Show the GM's journal by turn, to write entries and export them.
+    async fn show_journal(&mut self) {
+        if self.cmpgn.is_none() {
+            return;
+        }
+
+        let mut wind = window::Window::default()
+            .with_size(600, 400)
+            .with_label("Journal")
+            .center_screen();
+        let mut browse = SelectBrowser::default().with_pos(5, 5).with_size(590, 300);
+        browse.set_column_widths(&[60, 520]);
+        browse.set_column_char('\t');
+        Self::fill_journal_browser(&mut browse, self.cmpgn.as_ref().unwrap()).await;
+
+        let (s, r) = app::channel();
+
+        let button_y = 310; // Browser bottom plus spacing
+        button::Button::default()
+            .with_label("Edit")
+            .with_pos(SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Edit");
+        button::Button::default()
+            .with_label("View")
+            .with_pos(BTN_WIDTH + 2 * SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "View");
+        button::Button::default()
+            .with_label("Export")
+            .with_pos(SPACING + 2 * (BTN_WIDTH + SPACING), button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Export");
+
+        wind.end();
+        wind.show();
+
+        while wind.shown() && app::wait() {
+            if let Some(m) = r.recv() {
+                match m {
+                    "Edit" => {
+                        let sel = browse.value();
+                        if sel > 1 {
+                            // Ignore header, so only edit if 2+
+                            let turn = unsafe { browse.data::<i32>(sel) }.unwrap_or(0);
+                            let c = self.cmpgn.as_ref().unwrap();
+                            let entry = match c.journal_entry(turn).await {
+                                Ok(e) => e,
+                                Err(e) => {
+                                    dialog::alert_default(e.as_str());
+                                    continue;
+                                }
+                            };
+                            if let Some(text) = self.edit_journal_entry(&entry) {
+                                let c = self.cmpgn.as_ref().unwrap();
+                                if let Err(e) = c.set_journal_entry(turn, text.as_str()).await {
+                                    dialog::alert_default(e.as_str());
+                                }
+                                Self::fill_journal_browser(&mut browse, c).await;
+                            }
+                        }
+                    }
+                    "View" => {
+                        let c = self.cmpgn.as_ref().unwrap();
+                        match c.journal().await {
+                            Ok(v) => show_report(
+                                "Journal",
+                                journal::to_markdown(c.name().as_str(), &v).as_str(),
+                            ),
+                            Err(e) => dialog::alert_default(e.as_str()),
+                        }
+                    }
+                    "Export" => {
+                        if let Some(mut file) =
+                            dialog::file_chooser("Export journal to...", "*.md", ".", true)
+                        {
+                            if path::Path::new(file.as_str()).extension().is_none() {
+                                file = format!("{}.md", file);
+                            }
+                            let c = self.cmpgn.as_ref().unwrap();
+                            if let Err(e) = c.export_journal(file.as_str()).await {
+                                dialog::alert_default(e.as_str());
+                            }
+                        }
+                    }
+                    _ => (),
+                }
+            }
+        }
+    }
+
+    // Edit the text of a journal entry. Returns None if canceled, Some(text)
+    // if edited.
+    fn edit_journal_entry(&mut self, entry: &JournalEntry) -> Option<String> {
+        let width = 600;
+        let height = 400;
+        let mut wind = window::Window::default()
+            .with_size(width, height)
+            .with_label(format!("Journal for Turn {}", entry.turn).as_str())
+            .center_screen();
+        let mut buf = text::TextBuffer::default();
+        buf.set_text(entry.text.as_str());
+        let mut editor = text::TextEditor::default()
+            .with_pos(SPACING, SPACING)
+            .with_size(width - 2 * SPACING, height - BTN_HEIGHT - 3 * SPACING);
+        editor.set_buffer(buf.clone());
+        editor.wrap_mode(text::WrapMode::AtBounds, 0);
+        let button_y = height - BTN_HEIGHT - SPACING;
+        let mut ok = button::Button::default()
+            .with_label("Ok")
+            .with_pos(SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT);
+        let mut cancel = button::Button::default()
+            .with_label("Cancel")
+            .with_pos(BTN_WIDTH + 2 * SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT);
+        wind.end();
+        wind.make_modal(true);
+        wind.show();
+
+        let (s, r) = app::channel();
+        ok.emit(s, true);
+        cancel.emit(s, false);
+
+        let mut is_ok = false;
+        while wind.shown() && self.app.wait() {
+            if let Some(a) = r.recv() {
+                is_ok = a;
+                wind.hide();
+            }
+        }
+
+        if is_ok {
+            Some(buf.text())
+        } else {
+            None
+        }
+    }
+
+    // Show the economic phase report for all systems.
+    async fn economic_report(&mut self) {
+        if let Some(c) = &self.cmpgn {
+            match c.economic_report().await {
+                Ok(r) => show_report("Economic Phase Report", r.as_str()),
+                Err(e) => dialog::alert_default(e.as_str()),
+            }
+        }
+    }
+
+    // Choose the locale the campaign's numbers and dates are written in.
+    async fn choose_locale(&mut self) {
+        let c = match &self.cmpgn {
+            Some(c) => c,
+            None => return,
+        };
+        let current = c.locale().await.unwrap_or(&LOCALES[0]);
+        let names: Vec<String> = LOCALES
+            .iter()
+            .map(|l| {
+                format!(
+                    "{}: {}, {}",
+                    l.name,
+                    l.number(1234567),
+                    l.date("2026-10-15")
+                )
+            })
+            .collect();
+        let title = format!("Locale (now {})", current.name);
+        if let Some(i) = self.choose(title.as_str(), &names) {
+            let tag = LOCALES[i].tag;
+            match self.cmpgn.as_ref().unwrap().set_locale(tag).await {
+                Ok(_) => self
+                    .notify(format!("Writing numbers and dates for {}", LOCALES[i].name).as_str()),
+                Err(e) => dialog::alert_default(e.as_str()),
+            }
+        }
+    }
+
+    // Write the map and each empire's holdings to an HTML page for posting.
+    async fn export_printable(&mut self) {
+        let c = match &self.cmpgn {
+            Some(c) => c,
+            None => return,
+        };
+        if let Some(mut file) = dialog::file_chooser("Export page to...", "*.html", ".", true) {
+            if path::Path::new(file.as_str()).extension().is_none() {
+                file = format!("{}.html", file);
+            }
+            match c.export_printable(file.as_str()).await {
+                Ok(_) => self.notify(format!("Wrote {}", file).as_str()),
+                Err(e) => dialog::alert_default(e.as_str()),
+            }
+        }
+    }
+
+    // Show every war, and what the one picked has cost the empires fighting
+    // it.
+    async fn war_report(&mut self) {
+        let wars = match &self.cmpgn {
+            Some(c) => match c.wars().await {
+                Ok(v) => v,
+                Err(e) => {
+                    dialog::alert_default(e.as_str());
+                    return;
+                }
+            },
+            None => return,
+        };
+        if wars.is_empty() {
+            dialog::message_default("No wars have been fought.");
+            return;
+        }
+
+        let width = MAIN_WIDTH - 100;
+        let height = MAIN_HEIGHT - 100;
+        let browse_height = 150;
+        let mut wind = window::Window::default()
+            .with_size(width, height)
+            .with_label("Wars")
+            .center_screen();
+        let mut browse = browser::HoldBrowser::default()
+            .with_pos(SPACING, SPACING)
+            .with_size(width - 2 * SPACING, browse_height);
+        browse.set_column_widths(&[250, 80]);
+        browse.set_column_char('\t');
+        browse.add("War\tStarted\tEnded");
+        for w in wars {
+            browse.add_with_data(w.as_row().as_str(), w);
+        }
+        let mut buf = text::TextBuffer::default();
+        let mut disp = text::TextDisplay::default()
+            .with_pos(SPACING, browse_height + 2 * SPACING)
+            .with_size(width - 2 * SPACING, height - browse_height - 3 * SPACING);
+        disp.set_buffer(buf.clone());
+
+        let (s, r) = app::channel();
+        browse.emit(s, "Select");
+
+        wind.end();
+        wind.show();
+
+        while wind.shown() && app::wait() {
+            if let Some("Select") = r.recv() {
+                // Ignore header, so only act on a selection if 2+
+                let sel = browse.value();
+                if sel < 2 {
+                    continue;
+                }
+                if let Some(w) = unsafe { browse.data::<War>(sel) } {
+                    match self.cmpgn.as_ref().unwrap().war_costs(&w).await {
+                        Ok(t) => buf.set_text(t.as_str()),
+                        Err(e) => dialog::alert_default(e.as_str()),
+                    }
+                }
+            }
+        }
+    }
+
+    // Show how each ship class has served, to judge which designs earn their
+    // cost.
+    async fn class_report(&mut self) {
+        let records = match &self.cmpgn {
+            Some(c) => match c.class_records().await {
+                Ok(v) => v,
+                Err(e) => {
+                    dialog::alert_default(e.as_str());
+                    return;
+                }
+            },
+            None => return,
+        };
+        if records.is_empty() {
+            dialog::message_default("No ship classes have been designed.");
+            return;
+        }
+
+        let width = MAIN_WIDTH - 100;
+        let height = MAIN_HEIGHT - 100;
+        let mut wind = window::Window::default()
+            .with_size(width, height)
+            .with_label("Ship Classes")
+            .center_screen();
+        let mut browse = browser::HoldBrowser::default()
+            .with_pos(SPACING, SPACING)
+            .with_size(width - 2 * SPACING, height - 2 * SPACING);
+        browse.set_column_widths(&[100, 110, 90, 50, 50, 50, 60, 50, 50]);
+        browse.set_column_char('\t');
+        browse.add("Empire\tClass\tHull\tCost\tBuilt\tActive\tFought\tLost\tKills\tPer Loss");
+        for r in &records {
+            browse.add(r.as_row().as_str());
+        }
+        wind.end();
+        wind.make_modal(true);
+        wind.show();
+        while wind.shown() {
+            app::wait();
+        }
+    }
+
+    // Resolve a battle between the fleets at a system and show the report.
+    async fn fight_battle(&mut self, sys: &System) {
+        let prompt = format!("Resolve a battle at {}?", sys.name);
+        if dialog::choice2_default(prompt.as_str(), "No", "Yes", "") != Some(1) {
+            return;
+        }
+        // Attackers coming through defended jump points assault them.
+        let lanes: Vec<Lane> = match self.cmpgn.as_ref().unwrap().lanes().await {
+            Ok(v) => v
+                .into_iter()
+                .filter(|l| l.defended && l.other_end(sys.id).is_some())
+                .collect(),
+            Err(e) => {
+                dialog::alert_default(e.as_str());
+                return;
+            }
+        };
+        let mut lane = None;
+        if sys.owner != 0 && !lanes.is_empty() {
+            let mut names = vec!["Open battle".to_string()];
+            for l in &lanes {
+                let from = if l.a == sys.id { &l.b_name } else { &l.a_name };
+                names.push(format!("Assault from {}", from));
+            }
+            lane = match self.choose("Battle", &names) {
+                Some(0) => None,
+                Some(n) => lanes.get(n - 1),
+                None => return,
+            };
+        }
+        // The terrain is the same for every side; crew and leader are
+        // chosen for each.
+        let c = self.cmpgn.as_ref().unwrap();
+        let (ships, table) = match (c.combatants(sys).await, c.combat_modifiers().await) {
+            (Ok(s), Ok(t)) => (s, t),
+            (Err(e), _) | (_, Err(e)) => {
+                dialog::alert_default(e.as_str());
+                return;
+            }
+        };
+        let names_in = |category: &str| -> Vec<String> {
+            table
+                .iter()
+                .filter(|m| m.category == category)
+                .map(|m| m.name.clone())
+                .collect()
+        };
+        let sides: BTreeMap<i64, String> = ships
+            .iter()
+            .map(|s| (s.owner, s.owner_name.clone()))
+            .collect();
+        let mut chosen: BTreeMap<i64, Vec<(String, String)>> = BTreeMap::new();
+        if sides.len() > 1 {
+            let terrains = names_in(combat::TERRAIN);
+            let terrain = if terrains.is_empty() {
+                None
+            } else {
+                match self.choose("Terrain", &terrains) {
+                    Some(i) => Some(terrains[i].clone()),
+                    None => return,
+                }
+            };
+            for (owner, name) in &sides {
+                let mut picks = Vec::new();
+                if let Some(t) = &terrain {
+                    picks.push((combat::TERRAIN.to_string(), t.clone()));
+                }
+                for category in [combat::CREW, combat::LEADER] {
+                    let mut names = names_in(category);
+                    if names.is_empty() {
+                        continue;
+                    }
+                    if category == combat::LEADER {
+                        names.insert(0, "No leader".to_string());
+                    }
+                    let title = format!("{} {}", name, category);
+                    match self.choose(title.as_str(), &names) {
+                        Some(0) if category == combat::LEADER => (),
+                        Some(i) => picks.push((category.to_string(), names[i].clone())),
+                        None => return,
+                    }
+                }
+                chosen.insert(*owner, picks);
+            }
+        }
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos() as u64);
+        let c = self.cmpgn.as_ref().unwrap();
+        let result = match lane {
+            Some(l) => c.resolve_assault(sys, l, &chosen, seed).await,
+            None => c.resolve_battle(sys, &chosen, seed).await,
+        };
+        match result {
+            Ok(r) => show_report("Battle Report", r.as_str()),
+            Err(e) => dialog::alert_default(e.as_str()),
+        }
+    }
+
+    // Order development of a system by its owner, or a colony at an unowned
+    // system by an empire of the user's choice.
+    async fn order_development(&mut self, sys: &System) {
+        let empires: Vec<Empire> = match self.cmpgn.as_ref().unwrap().empires().await {
+            Ok(v) => v
+                .into_iter()
+                .filter(|e| sys.owner == 0 || e.id == sys.owner)
+                .collect(),
+            Err(e) => {
+                dialog::alert_default(e.as_str());
+                return;
+            }
+        };
+        let empire = if sys.owner == 0 {
+            let names: Vec<String> = empires.iter().map(|e| e.name.clone()).collect();
+            match self.choose("Colonizing Empire", &names) {
+                Some(i) => &empires[i],
+                None => return,
+            }
+        } else {
+            match empires.first() {
+                Some(e) => e,
+                None => return,
+            }
+        };
+        let targets: Vec<&str> = development::TARGETS
+            .iter()
+            .copied()
+            .filter(|t| (*t == development::COLONY) == (sys.owner == 0))
+            .collect();
+        let names: Vec<String> = targets
+            .iter()
+            .map(|t| format!("{} ({} EP)", t, development::cost(sys, t)))
+            .collect();
+        let target = match self.choose("Development", &names) {
+            Some(i) => targets[i],
+            None => return,
+        };
+        match self
+            .cmpgn
+            .as_ref()
+            .unwrap()
+            .order_development(empire, sys, target)
+            .await
+        {
+            Ok(d) if d.target == development::COLONY => dialog::message_default(
+                format!(
+                    "{} will found a colony at {} this turn for {} EP",
+                    empire.name, sys.name, d.amount
+                )
+                .as_str(),
+            ),
+            Ok(d) => dialog::message_default(
+                format!(
+                    "{} will try to raise the {} of {} this turn for {} EP",
+                    empire.name,
+                    d.target.to_lowercase(),
+                    sys.name,
+                    d.amount
+                )
+                .as_str(),
+            ),
+            Err(e) => dialog::alert_default(e.as_str()),
+        }
+    }
+
+    // Put an empire's intel points into an espionage mission.
+    async fn order_operation(&mut self, emp: &Empire) {
+        let missions: Vec<String> = espionage::MISSIONS.iter().map(|m| m.to_string()).collect();
+        let mission = match self.choose("Mission", &missions) {
+            Some(i) => espionage::MISSIONS[i],
+            None => return,
+        };
+        let target = if mission == espionage::COUNTER_ESPIONAGE {
+            emp.clone()
+        } else {
+            let others: Vec<Empire> = match self.cmpgn.as_ref().unwrap().empires().await {
+                Ok(v) => v.into_iter().filter(|e| e.id != emp.id).collect(),
+                Err(e) => {
+                    dialog::alert_default(e.as_str());
+                    return;
+                }
+            };
+            let names: Vec<String> = others.iter().map(|e| e.name.clone()).collect();
+            match self.choose("Target Empire", &names) {
+                Some(i) => others[i].clone(),
+                None => return,
+            }
+        };
+        let prompt = format!("Intel points for {} (1 EP each)", mission);
+        let points = match dialog::input_default(prompt.as_str(), "1") {
+            Some(v) => match v.trim().parse::<i32>() {
+                Ok(p) => p,
+                Err(_) => {
+                    dialog::alert_default("Intel points must be a whole number.");
+                    return;
+                }
+            },
+            None => return,
+        };
+        match self
+            .cmpgn
+            .as_ref()
+            .unwrap()
+            .order_operation(emp, &target, mission, points)
+            .await
+        {
+            Ok(op) => dialog::message_default(
+                format!(
+                    "{} put {} intel points into {} against {}",
+                    emp.name,
+                    op.points,
+                    mission.to_lowercase(),
+                    target.name
+                )
+                .as_str(),
+            ),
+            Err(e) => dialog::alert_default(e.as_str()),
+        }
+    }
+
+    // Order a raid on a system by one of the other empires.
+    async fn order_raid(&mut self, sys: &System) {
+        let empires: Vec<Empire> = match self.cmpgn.as_ref().unwrap().empires().await {
+            Ok(v) => v.into_iter().filter(|e| e.id != sys.owner).collect(),
+            Err(e) => {
+                dialog::alert_default(e.as_str());
+                return;
+            }
+        };
+        let names: Vec<String> = empires.iter().map(|e| e.name.clone()).collect();
+        let raider = match self.choose("Raiding Empire", &names) {
+            Some(i) => &empires[i],
+            None => return,
+        };
+        let targets: Vec<String> = raid::TARGETS.iter().map(|t| t.to_string()).collect();
+        let target = match self.choose("Raid Target", &targets) {
+            Some(i) => raid::TARGETS[i],
+            None => return,
+        };
+        match self
+            .cmpgn
+            .as_ref()
+            .unwrap()
+            .order_raid(raider.id, sys, target)
+            .await
+        {
+            Ok(r) => dialog::message_default(
+                format!(
+                    "{} will raid the {} of {} this turn",
+                    raider.name,
+                    r.target.to_lowercase(),
+                    sys.name
+                )
+                .as_str(),
+            ),
+            Err(e) => dialog::alert_default(e.as_str()),
+        }
+    }
+
+    // Show the development orders given for the turn.
+    async fn show_developments(&mut self) {
+        let mut wind = window::Window::default()
+            .with_size(600, 400)
+            .with_label("Developments")
+            .center_screen();
+        let mut browse = SelectBrowser::default().with_pos(5, 5).with_size(590, 300);
+        browse.set_column_widths(&[60, 160, 160, 120]);
+        browse.set_column_char('\t');
+        Self::fill_development_browser(&mut browse, self.cmpgn.as_ref().unwrap()).await;
+
+        let (s, r) = app::channel();
+
+        let button_y = 310; // Browser bottom plus spacing
+        button::Button::default()
+            .with_label("Cancel Order")
+            .with_pos(SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Cancel");
+
+        wind.end();
+        wind.show();
+
+        while wind.shown() && app::wait() {
+            if let Some(m) = r.recv() {
+                if m == "Cancel" {
+                    let sel = browse.value();
+                    if sel > 1 {
+                        // Ignore header, so only cancel if 2+
+                        unsafe {
+                            if let Some(d) = browse.data::<Development>(sel) {
+                                if let Err(e) =
+                                    self.cmpgn.as_ref().unwrap().cancel_development(&d).await
+                                {
+                                    dialog::alert_default(e.as_str());
+                                }
+                            }
+                        }
+                    }
+                }
+                Self::fill_development_browser(&mut browse, self.cmpgn.as_ref().unwrap()).await;
+            }
+        }
+    }
+
+    // Show the espionage operations ordered for the turn.
+    async fn show_operations(&mut self) {
+        let mut wind = window::Window::default()
+            .with_size(600, 400)
+            .with_label("Espionage Operations")
+            .center_screen();
+        let mut browse = SelectBrowser::default().with_pos(5, 5).with_size(590, 300);
+        browse.set_column_widths(&[60, 140, 140, 160]);
+        browse.set_column_char('\t');
+        Self::fill_operation_browser(&mut browse, self.cmpgn.as_ref().unwrap()).await;
+
+        let (s, r) = app::channel();
+
+        let button_y = 310; // Browser bottom plus spacing
+        button::Button::default()
+            .with_label("Cancel Order")
+            .with_pos(SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Cancel");
+
+        wind.end();
+        wind.show();
+
+        while wind.shown() && app::wait() {
+            if let Some(m) = r.recv() {
+                if m == "Cancel" {
+                    let sel = browse.value();
+                    if sel > 1 {
+                        // Ignore header, so only cancel if 2+
+                        unsafe {
+                            if let Some(op) = browse.data::<Operation>(sel) {
+                                if let Err(e) =
+                                    self.cmpgn.as_ref().unwrap().cancel_operation(&op).await
+                                {
+                                    dialog::alert_default(e.as_str());
+                                }
+                            }
+                        }
+                    }
+                }
+                Self::fill_operation_browser(&mut browse, self.cmpgn.as_ref().unwrap()).await;
+            }
+        }
+    }
+
+    // Show the raids ordered for the turn.
+    async fn show_raids(&mut self) {
+        let mut wind = window::Window::default()
+            .with_size(600, 400)
+            .with_label("Raids")
+            .center_screen();
+        let mut browse = SelectBrowser::default().with_pos(5, 5).with_size(590, 300);
+        browse.set_column_widths(&[60, 160, 160, 120]);
+        browse.set_column_char('\t');
+        Self::fill_raid_browser(&mut browse, self.cmpgn.as_ref().unwrap()).await;
+
+        let (s, r) = app::channel();
+
+        let button_y = 310; // Browser bottom plus spacing
+        button::Button::default()
+            .with_label("Cancel Raid")
+            .with_pos(SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Cancel");
+
+        wind.end();
+        wind.show();
+
+        while wind.shown() && app::wait() {
+            if let Some(m) = r.recv() {
+                if m == "Cancel" {
+                    let sel = browse.value();
+                    if sel > 1 {
+                        // Ignore header, so only cancel if 2+
+                        unsafe {
+                            if let Some(raid) = browse.data::<Raid>(sel) {
+                                if let Err(e) =
+                                    self.cmpgn.as_ref().unwrap().cancel_raid(&raid).await
+                                {
+                                    dialog::alert_default(e.as_str());
+                                }
+                            }
+                        }
+                    }
+                }
+                Self::fill_raid_browser(&mut browse, self.cmpgn.as_ref().unwrap()).await;
+            }
+        }
+    }
+
+    // Edit the combat modifier. Returns None if canceled, Some(modifier) if
+    // edited.
+    async fn edit_combat_modifier(&mut self, m: CombatModifier) -> Option<CombatModifier> {
+        let total_width = 300;
+        let total_height = 200;
+        let label_width = 80;
+        let input_width = total_width - label_width - 2 * SPACING;
+
+        let mut wind = window::Window::default()
+            .with_size(total_width, total_height)
+            .center_screen()
+            .with_label("Edit Combat Modifier");
+
+        let mut category_input = input::Input::default()
+            .with_label("Category")
+            .with_pos(label_width + SPACING, SPACING)
+            .with_size(input_width, TEXT_HEIGHT);
+        category_input.set_value(m.category.as_str());
+        let mut name_input = input::Input::default()
+            .with_label("Name")
+            .with_pos(label_width + SPACING, 2 * SPACING + TEXT_HEIGHT)
+            .with_size(input_width, TEXT_HEIGHT);
+        name_input.set_value(m.name.as_str());
+        let mut atk_input = input::IntInput::default()
+            .with_label("ATK")
+            .with_pos(label_width + SPACING, 3 * SPACING + 2 * TEXT_HEIGHT)
+            .with_size(input_width, TEXT_HEIGHT);
+        atk_input.set_value(m.atk.to_string().as_str());
+        let mut def_input = input::IntInput::default()
+            .with_label("DEF")
+            .with_pos(label_width + SPACING, 4 * SPACING + 3 * TEXT_HEIGHT)
+            .with_size(input_width, TEXT_HEIGHT);
+        def_input.set_value(m.def.to_string().as_str());
+
+        let button_y = total_height - BTN_HEIGHT - SPACING;
+        let mut ok = button::Button::default()
+            .with_label("Ok")
+            .with_pos(SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT);
+        let mut cancel = button::Button::default()
+            .with_label("Cancel")
+            .with_pos(BTN_WIDTH + 2 * SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT);
+
+        wind.end();
+        wind.make_modal(true);
+        wind.show();
+
+        let (s, r) = app::channel();
+        ok.emit(s, true);
+        cancel.emit(s, false);
+
+        let mut is_ok = false;
+        while wind.shown() && self.app.wait() {
+            if let Some(a) = r.recv() {
+                is_ok = a;
+                wind.hide();
+            }
+        }
+
+        if !is_ok || category_input.value().is_empty() || name_input.value().is_empty() {
+            return None;
+        }
+        let atk = atk_input.value().parse().unwrap_or(m.atk);
+        let def = def_input.value().parse().unwrap_or(m.def);
+        Some(CombatModifier {
+            category: category_input.value(),
+            name: name_input.value(),
+            atk,
+            def,
+            ..m
+        })
+    }
+
+    // Edit the empire, with its government and traits. Returns None if
+    // canceled, Some(empire) if edited.
+    async fn edit_empire(&mut self, emp: Empire) -> Option<Empire> {
+        let rows = 3 + government::TRAITS.len() as i32;
+        let total_width = 400;
+        let total_height = rows * (TEXT_HEIGHT + SPACING) + BTN_HEIGHT + 2 * SPACING;
+        let label_width = 80;
+        let input_width = total_width - label_width - 2 * SPACING;
+
+        let mut wind = window::Window::default()
+            .with_size(total_width, total_height)
+            .center_screen()
+            .with_label("Edit Empire");
+
+        let mut name_input = input::Input::default()
+            .with_label("Name")
+            .with_pos(label_width + SPACING, SPACING)
+            .with_size(input_width, TEXT_HEIGHT);
+        name_input.set_value(emp.name.as_str());
+
+        let row_y = |n: i32| SPACING + n * (TEXT_HEIGHT + SPACING);
+        let mut government_choice = menu::Choice::default()
+            .with_label("Government")
+            .with_pos(label_width + SPACING, row_y(1))
+            .with_size(input_width, TEXT_HEIGHT);
+        for g in government::GOVERNMENTS {
+            government_choice.add_choice(g);
+        }
+        government_choice.set_value(
+            government::GOVERNMENTS
+                .iter()
+                .position(|g| *g == emp.government)
+                .unwrap_or(0) as i32,
+        );
+        let mut trait_checks = Vec::new();
+        for (n, t) in government::TRAITS.iter().enumerate() {
+            let check = button::CheckButton::default()
+                .with_label(t)
+                .with_pos(label_width + SPACING, row_y(2 + n as i32))
+                .with_size(input_width, TEXT_HEIGHT);
+            check.set_checked(emp.has_trait(t));
+            trait_checks.push(check);
+        }
+        let mut behavior = frame::Frame::default()
+            .with_pos(SPACING, row_y(rows - 1))
+            .with_size(total_width - 2 * SPACING, TEXT_HEIGHT)
+            .with_align(Align::Left | Align::Inside);
+
+        let button_y = total_height - BTN_HEIGHT - SPACING;
+        let mut ok = button::Button::default()
+            .with_label("Ok")
+            .with_pos(SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT);
+        let mut cancel = button::Button::default()
+            .with_label("Cancel")
+            .with_pos(BTN_WIDTH + 2 * SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT);
+
+        wind.end();
+        wind.make_modal(true);
+        wind.show();
+
+        let (s, r) = app::channel();
+        ok.emit(s, Some(true));
+        cancel.emit(s, Some(false));
+        government_choice.emit(s, None);
+        for check in trait_checks.iter_mut() {
+            check.emit(s, None);
+        }
+
+        // The empire as chosen so far.
+        let chosen = |name: String| Empire {
+            name,
+            government: government_choice.choice().unwrap_or_default(),
+            traits: government::TRAITS
+                .iter()
+                .zip(trait_checks.iter())
+                .filter(|(_, c)| c.is_checked())
+                .map(|(t, _)| *t)
+                .collect::<Vec<&str>>()
+                .join(","),
+            ..emp.clone()
+        };
+        let show_behavior = |f: &mut frame::Frame, e: &Empire| {
+            f.set_label(format!("NPE: {}", government::npe_behavior(e)).as_str());
+        };
+        show_behavior(&mut behavior, &emp);
+
+        let mut is_ok = false;
+        while wind.shown() && self.app.wait() {
+            match r.recv() {
+                Some(Some(a)) => {
+                    is_ok = a;
+                    wind.hide();
+                }
+                Some(None) => show_behavior(&mut behavior, &chosen(name_input.value())),
+                None => {}
+            }
+        }
+
+        if !is_ok || name_input.value().is_empty() {
+            return None;
+        }
+        Some(chosen(name_input.value()))
+    }
+
+    // Edit an empire's treasury and tech level, posting the change to its
+    // ledger. Returns None if canceled, Some(empire) if changed.
+    async fn edit_financials(&mut self, emp: Empire) -> Option<Empire> {
+        let c = self.cmpgn.as_ref()?;
+        let ledger = match c.ledger(emp.id).await {
+            Ok(v) => v,
+            Err(e) => {
+                dialog::alert_default(e.as_str());
+                return None;
+            }
+        };
+
+        let total_width = 400;
+        let total_height = 300;
+        let label_width = 80;
+        let input_width = total_width - label_width - 2 * SPACING;
+
+        let mut wind = window::Window::default()
+            .with_size(total_width, total_height)
+            .center_screen()
+            .with_label(format!("{} Finances", emp.name).as_str());
+
+        let mut treasury_input = input::IntInput::default()
+            .with_label("Treasury")
+            .with_pos(label_width + SPACING, SPACING)
+            .with_size(input_width, TEXT_HEIGHT);
+        treasury_input.set_value(emp.treasury.to_string().as_str());
+        let mut tech_input = input::IntInput::default()
+            .with_label("Tech")
+            .with_pos(label_width + SPACING, 2 * SPACING + TEXT_HEIGHT)
+            .with_size(input_width, TEXT_HEIGHT);
+        tech_input.set_value(emp.tech.to_string().as_str());
+        let note_input = input::Input::default()
+            .with_label("Note")
+            .with_pos(label_width + SPACING, 3 * SPACING + 2 * TEXT_HEIGHT)
+            .with_size(input_width, TEXT_HEIGHT);
+
+        let browse_y = 4 * SPACING + 3 * TEXT_HEIGHT;
+        let button_y = total_height - BTN_HEIGHT - SPACING;
+        let mut browse = browser::Browser::default()
+            .with_pos(SPACING, browse_y)
+            .with_size(total_width - 2 * SPACING, button_y - browse_y - SPACING);
+        browse.set_column_widths(&[50, 60, 50, 220]);
+        browse.set_column_char('\t');
+        browse.add("Turn\tEP\tTech\tNote");
+        for l in ledger {
+            browse.add(l.as_row().as_str());
+        }
+
+        let mut ok = button::Button::default()
+            .with_label("Ok")
+            .with_pos(SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT);
+        let mut cancel = button::Button::default()
+            .with_label("Cancel")
+            .with_pos(BTN_WIDTH + 2 * SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT);
+
+        wind.end();
+        wind.make_modal(true);
+        wind.show();
+
+        let (s, r) = app::channel();
+        ok.emit(s, true);
+        cancel.emit(s, false);
+
+        let mut changed = None;
+        while wind.shown() && self.app.wait() {
+            if let Some(a) = r.recv() {
+                if !a {
+                    wind.hide();
+                    continue;
+                }
+                let treasury = treasury_input.value().parse().unwrap_or(emp.treasury);
+                let tech = tech_input.value().parse().unwrap_or(emp.tech);
+                match c
+                    .adjust_financials(&emp, treasury, tech, note_input.value().as_str())
+                    .await
+                {
+                    Ok(e) => {
+                        changed = Some(e);
+                        wind.hide();
+                    }
+                    Err(e) => dialog::alert_default(e.as_str()),
+                }
+            }
+        }
+        changed
+    }
+
+    // Edit the fleet. Returns None if canceled, Some(fleet) if edited.
+    async fn edit_fleet(&mut self, fleet: Fleet) -> Option<Fleet> {
+        let c = self.cmpgn.as_ref()?;
+        let empires = match c.empires().await {
+            Ok(v) => v,
+            Err(e) => {
+                dialog::alert_default(e.as_str());
+                return None;
+            }
+        };
+        let systems = match c.systems().await {
+            Ok(v) => v,
+            Err(e) => {
+                dialog::alert_default(e.as_str());
+                return None;
+            }
+        };
+
+        let total_width = 300;
+        let total_height = 200;
+        let label_width = 80;
+        let input_width = total_width - label_width - 2 * SPACING;
+
+        let mut wind = window::Window::default()
+            .with_size(total_width, total_height)
+            .center_screen()
+            .with_label("Edit Fleet");
+
+        let mut name_input = input::Input::default()
+            .with_label("Name")
+            .with_pos(label_width + SPACING, SPACING)
+            .with_size(input_width, TEXT_HEIGHT);
+        name_input.set_value(fleet.name.as_str());
+        let mut owner_choice = menu::Choice::default()
+            .with_label("Owner")
+            .with_pos(label_width + SPACING, 2 * SPACING + TEXT_HEIGHT)
+            .with_size(input_width, TEXT_HEIGHT);
+        for e in &empires {
+            owner_choice.add_choice(e.name.replace('/', "\\/").as_str());
+        }
+        owner_choice.set_value(
+            empires
+                .iter()
+                .position(|e| e.id == fleet.owner)
+                .unwrap_or(0) as i32,
+        );
+        let mut location_choice = menu::Choice::default()
+            .with_label("Location")
+            .with_pos(label_width + SPACING, 3 * SPACING + 2 * TEXT_HEIGHT)
+            .with_size(input_width, TEXT_HEIGHT);
+        for s in &systems {
+            location_choice.add_choice(s.name.replace('/', "\\/").as_str());
+        }
+        location_choice.set_value(
+            systems
+                .iter()
+                .position(|s| s.id == fleet.location)
+                .unwrap_or(0) as i32,
+        );
+        let mut speed_input = input::IntInput::default()
+            .with_label("Speed limit")
+            .with_pos(label_width + SPACING, 4 * SPACING + 3 * TEXT_HEIGHT)
+            .with_size(input_width, TEXT_HEIGHT);
+        speed_input.set_value(fleet.speed.to_string().as_str());
+
+        let button_y = total_height - BTN_HEIGHT - SPACING;
+        let mut ok = button::Button::default()
+            .with_label("Ok")
+            .with_pos(SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT);
+        let mut cancel = button::Button::default()
+            .with_label("Cancel")
+            .with_pos(BTN_WIDTH + 2 * SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT);
+
+        wind.end();
+        wind.make_modal(true);
+        wind.show();
+
+        let (s, r) = app::channel();
+        ok.emit(s, true);
+        cancel.emit(s, false);
+
+        let mut is_ok = false;
+        while wind.shown() && self.app.wait() {
+            if let Some(a) = r.recv() {
+                is_ok = a;
+                wind.hide();
+            }
+        }
+
+        if !is_ok || name_input.value().is_empty() {
+            return None;
+        }
+        let owner = empires.get(owner_choice.value() as usize)?;
+        let location = systems.get(location_choice.value() as usize)?;
+        let speed = speed_input.value().parse().unwrap_or(fleet.speed).max(0);
+        Some(Fleet {
+            name: name_input.value(),
+            owner: owner.id,
+            location: location.id,
+            speed,
+            ..fleet
+        })
+    }
+
+    // Edit the jump lane. Returns None if canceled, Some(lane) if edited.
+    async fn edit_lane(&mut self, lane: Lane, systems: &[System]) -> Option<Lane> {
+        let total_width = 300;
+        let total_height = 200;
+        let label_width = 80;
+        let input_width = total_width - label_width - 2 * SPACING;
+
+        let mut wind = window::Window::default()
+            .with_size(total_width, total_height)
+            .center_screen()
+            .with_label("Edit Lane");
+
+        let names: Vec<String> = systems.iter().map(|s| s.name.replace('/', "\\/")).collect();
+        let mut a_choice = menu::Choice::default()
+            .with_label("From")
+            .with_pos(label_width + SPACING, SPACING)
+            .with_size(input_width, TEXT_HEIGHT);
+        a_choice.add_choice(names.join("|").as_str());
+        a_choice.set_value(systems.iter().position(|s| s.id == lane.a).unwrap_or(0) as i32);
+        let mut b_choice = menu::Choice::default()
+            .with_label("To")
+            .with_pos(label_width + SPACING, 2 * SPACING + TEXT_HEIGHT)
+            .with_size(input_width, TEXT_HEIGHT);
+        b_choice.add_choice(names.join("|").as_str());
+        b_choice.set_value(systems.iter().position(|s| s.id == lane.b).unwrap_or(0) as i32);
+        let mut type_choice = menu::Choice::default()
+            .with_label("Type")
+            .with_pos(label_width + SPACING, 3 * SPACING + 2 * TEXT_HEIGHT)
+            .with_size(input_width, TEXT_HEIGHT);
+        type_choice.add_choice(LANE_TYPES.join("|").as_str());
+        type_choice.set_value(
+            LANE_TYPES
+                .iter()
+                .position(|t| *t == lane.ltype)
+                .unwrap_or(0) as i32,
+        );
+        let defended_check = button::CheckButton::default()
+            .with_label("Defended jump points")
+            .with_pos(label_width + SPACING, 4 * SPACING + 3 * TEXT_HEIGHT)
+            .with_size(input_width, TEXT_HEIGHT);
+        defended_check.set_checked(lane.defended);
+
+        let button_y = total_height - BTN_HEIGHT - SPACING;
+        let mut ok = button::Button::default()
+            .with_label("Ok")
+            .with_pos(SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT);
+        let mut cancel = button::Button::default()
+            .with_label("Cancel")
+            .with_pos(BTN_WIDTH + 2 * SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT);
+
+        wind.end();
+        wind.make_modal(true);
+        wind.show();
+
+        let (s, r) = app::channel();
+        ok.emit(s, true);
+        cancel.emit(s, false);
+
+        let mut is_ok = false;
+        while wind.shown() && self.app.wait() {
+            if let Some(a) = r.recv() {
+                is_ok = a;
+                wind.hide();
+            }
+        }
+
+        if !is_ok {
+            return None;
+        }
+        let a = systems.get(a_choice.value() as usize)?;
+        let b = systems.get(b_choice.value() as usize)?;
+        if a.id == b.id {
+            dialog::alert_default("A lane must connect two different systems.");
+            return None;
+        }
+        let ltype = LANE_TYPES.get(type_choice.value() as usize)?;
+        Some(Lane {
+            a: a.id,
+            b: b.id,
+            ltype: ltype.to_string(),
+            defended: defended_check.is_checked(),
+            a_name: a.name.clone(),
+            b_name: b.name.clone(),
+            ..lane
+        })
+    }
+
+    // Edit the ship class. Returns None if canceled, Some(class) if edited.
+    async fn edit_ship_type(&mut self, stype: ShipType) -> Option<ShipType> {
+        let c = self.cmpgn.as_ref()?;
+        let hulls = match c.hulls().await {
+            Ok(v) => v,
+            Err(e) => {
+                dialog::alert_default(e.as_str());
+                return None;
+            }
+        };
+        let empires = match c.empires().await {
+            Ok(v) => v,
+            Err(e) => {
+                dialog::alert_default(e.as_str());
+                return None;
+            }
+        };
+
+        let total_width = 300;
+        let total_height = 300;
+        let label_width = 80;
+        let input_width = total_width - label_width - 2 * SPACING;
+        let row = |n: i32| SPACING + n * (TEXT_HEIGHT + SPACING);
+
+        let mut wind = window::Window::default()
+            .with_size(total_width, total_height)
+            .center_screen()
+            .with_label("Edit Ship Class");
+
+        let mut class_input = input::Input::default()
+            .with_label("Class")
+            .with_pos(label_width + SPACING, row(0))
+            .with_size(input_width, TEXT_HEIGHT);
+        class_input.set_value(stype.class.as_str());
+        let mut hull_choice = menu::Choice::default()
+            .with_label("Hull")
+            .with_pos(label_width + SPACING, row(1))
+            .with_size(input_width, TEXT_HEIGHT);
+        for h in &hulls {
+            hull_choice.add_choice(format!("{} ({})", h.name, h.size).as_str());
+        }
+        hull_choice.set_value(hulls.iter().position(|h| h.name == stype.hull).unwrap_or(0) as i32);
+        let mut empire_choice = menu::Choice::default()
+            .with_label("Empire")
+            .with_pos(label_width + SPACING, row(2))
+            .with_size(input_width, TEXT_HEIGHT);
+        for e in &empires {
+            empire_choice.add_choice(e.name.replace('/', "\\/").as_str());
+        }
+        empire_choice.set_value(
+            empires
+                .iter()
+                .position(|e| e.id == stype.empire)
+                .unwrap_or(0) as i32,
+        );
+        let mut inputs = Vec::new();
+        for (i, (label, value)) in [
+            ("CR", stype.cr),
+            ("ATK", stype.atk),
+            ("DEF", stype.def),
+            ("Capacity", stype.cap),
+            ("Speed", stype.speed),
+        ]
+        .iter()
+        .enumerate()
+        {
+            let mut input = input::IntInput::default()
+                .with_label(label)
+                .with_pos(label_width + SPACING, row(3 + i as i32))
+                .with_size(input_width, TEXT_HEIGHT);
+            input.set_value(value.to_string().as_str());
+            inputs.push(input);
+        }
+
+        let button_y = total_height - BTN_HEIGHT - SPACING;
+        let mut ok = button::Button::default()
+            .with_label("Ok")
+            .with_pos(SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT);
+        let mut cancel = button::Button::default()
+            .with_label("Cancel")
+            .with_pos(BTN_WIDTH + 2 * SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT);
+
+        wind.end();
+        wind.make_modal(true);
+        wind.show();
+
+        let (s, r) = app::channel();
+        ok.emit(s, true);
+        cancel.emit(s, false);
+
+        let mut is_ok = false;
+        while wind.shown() && self.app.wait() {
+            if let Some(a) = r.recv() {
+                is_ok = a;
+                wind.hide();
+            }
+        }
+
+        if !is_ok {
+            return None;
+        }
+        let hull = hulls.get(hull_choice.value() as usize)?;
+        let empire = empires.get(empire_choice.value() as usize)?;
+        let values: Vec<i32> = inputs
+            .iter()
+            .map(|i| i.value().parse().unwrap_or(0))
+            .collect();
+        Some(ShipType {
+            class: class_input.value(),
+            hull: hull.name.clone(),
+            empire: empire.id,
+            cr: values[0],
+            atk: values[1],
+            def: values[2],
+            cap: values[3],
+            speed: values[4],
+            ..stype
+        })
+    }
+
+    // Choose a change to make to a number of systems at once. Returns None
+    // if canceled.
+    async fn bulk_edit_systems(&mut self, count: usize) -> Option<BulkEdit> {
+        let empires = match self.cmpgn.as_ref()?.empires().await {
+            Ok(v) => v,
+            Err(e) => {
+                dialog::alert_default(e.as_str());
+                return None;
+            }
+        };
+
+        let total_width = 3 * BTN_WIDTH + 4 * SPACING;
+        let total_height = 3 * (TEXT_HEIGHT + SPACING) + BTN_HEIGHT + 2 * SPACING;
+        let label_width = 80;
+        let input_width = total_width - label_width - 2 * SPACING;
+        let row_y = |n: i32| SPACING + n * (TEXT_HEIGHT + SPACING);
+
+        let mut wind = window::Window::default()
+            .with_size(total_width, total_height)
+            .center_screen()
+            .with_label(format!("Edit {} Systems", count).as_str());
+
+        // Owner choices: unchanged, none, then each empire.
+        let mut owner_choice = menu::Choice::default()
+            .with_label("Owner")
+            .with_pos(label_width + SPACING, row_y(0))
+            .with_size(input_width, TEXT_HEIGHT);
+        owner_choice.add_choice("Unchanged|None");
+        for e in &empires {
+            owner_choice.add_choice(e.name.replace('/', "\\/").as_str());
+        }
+        owner_choice.set_value(0);
+        let mut morale_input = input::IntInput::default()
+            .with_label("MOR +/-")
+            .with_pos(label_width + SPACING, row_y(1))
+            .with_size(input_width, TEXT_HEIGHT);
+        morale_input.set_value("0");
+        morale_input.set_tooltip("Points added to morale, or taken away if negative");
+        let mut blockade_choice = menu::Choice::default()
+            .with_label("Blockade")
+            .with_pos(label_width + SPACING, row_y(2))
+            .with_size(input_width, TEXT_HEIGHT);
+        blockade_choice.add_choice("Unchanged|Blockaded|Not blockaded");
+        blockade_choice.set_value(0);
+
+        let button_y = total_height - BTN_HEIGHT - SPACING;
+        let mut ok = button::Button::default()
+            .with_label("Ok")
+            .with_pos(SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT);
+        let mut cancel = button::Button::default()
+            .with_label("Cancel")
+            .with_pos(BTN_WIDTH + 2 * SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT);
+
+        wind.end();
+        wind.make_modal(true);
+        wind.show();
+
+        let (s, r) = app::channel();
+        ok.emit(s, "Ok");
+        cancel.emit(s, "Cancel");
+
+        let mut is_ok = false;
+        while wind.shown() && self.app.wait() {
+            if let Some(a) = r.recv() {
+                is_ok = a == "Ok";
+                wind.hide();
+            }
+        }
+        if !is_ok {
+            return None;
+        }
+
+        let owner = match owner_choice.value() {
+            n if n > 1 => Some(empires.get(n as usize - 2)?.id),
+            1 => Some(0),
+            _ => None,
+        };
+        let blockaded = match blockade_choice.value() {
+            1 => Some(true),
+            2 => Some(false),
+            _ => None,
+        };
+        Some(BulkEdit {
+            owner,
+            morale: morale_input.value().parse().unwrap_or(0),
+            blockaded,
+        })
+    }
+
+    // Edit the system. Returns None if canceled, Some(system) if edited.
+    async fn edit_system(&mut self, sys: System) -> Option<System> {
+        let c = self.cmpgn.as_ref()?;
+        let types = match c.planet_types().await {
+            Ok(v) => v,
+            Err(e) => {
+                dialog::alert_default(e.as_str());
+                return None;
+            }
+        };
+        let empires = match c.empires().await {
+            Ok(v) => v,
+            Err(e) => {
+                dialog::alert_default(e.as_str());
+                return None;
+            }
+        };
+
+        let total_width = 3 * BTN_WIDTH + 4 * SPACING;
+        let total_height = 13 * (TEXT_HEIGHT + SPACING) + BTN_HEIGHT + 2 * SPACING;
+        let label_width = 80;
+        let input_width = total_width - label_width - 2 * SPACING;
+        let row_y = |n: i32| SPACING + n * (TEXT_HEIGHT + SPACING);
+
+        let mut wind = window::Window::default()
+            .with_size(total_width, total_height)
+            .center_screen()
+            .with_label("Edit System");
+
+        let mut name_input = input::Input::default()
+            .with_label("Name")
+            .with_pos(label_width + SPACING, row_y(0))
+            .with_size(input_width, TEXT_HEIGHT);
+        name_input.set_value(sys.name.as_str());
+        let mut type_choice = menu::Choice::default()
+            .with_label("Type")
+            .with_pos(label_width + SPACING, row_y(1))
+            .with_size(input_width, TEXT_HEIGHT);
+        for t in &types {
+            type_choice.add_choice(t.name.replace('/', "\\/").as_str());
+        }
+        type_choice.set_value(types.iter().position(|t| t.name == sys.ptype).unwrap_or(0) as i32);
+        let mut values = Vec::new();
+        for (n, (label, v)) in [
+            ("RAW", sys.raw),
+            ("CAP", sys.cap),
+            ("POP", sys.pop),
+            ("MOR", sys.mor),
+            ("IND", sys.ind),
+        ]
+        .iter()
+        .enumerate()
+        {
+            let mut input = input::IntInput::default()
+                .with_label(label)
+                .with_pos(label_width + SPACING, row_y(n as i32 + 2))
+                .with_size(input_width, TEXT_HEIGHT);
+            input.set_value(v.to_string().as_str());
+            values.push(input);
+        }
+        let mut owner_choice = menu::Choice::default()
+            .with_label("Owner")
+            .with_pos(label_width + SPACING, row_y(7))
+            .with_size(input_width, TEXT_HEIGHT);
+        owner_choice.add_choice("None");
+        for e in &empires {
+            owner_choice.add_choice(e.name.replace('/', "\\/").as_str());
+        }
+        owner_choice.set_value(
+            empires
+                .iter()
+                .position(|e| e.id == sys.owner)
+                .map_or(0, |p| p + 1) as i32,
+        );
+        // Map coordinates share a row.
+        let coord_width = (input_width - 20) / 2;
+        let mut x_input = input::IntInput::default()
+            .with_label("X")
+            .with_pos(label_width + SPACING, row_y(8))
+            .with_size(coord_width, TEXT_HEIGHT);
+        x_input.set_value(sys.x.to_string().as_str());
+        let mut y_input = input::IntInput::default()
+            .with_label("Y")
+            .with_pos(total_width - SPACING - coord_width, row_y(8))
+            .with_size(coord_width, TEXT_HEIGHT);
+        y_input.set_value(sys.y.to_string().as_str());
+        // A minor race holds the system if no empire owns it.
+        let mut minor_input = input::Input::default()
+            .with_label("Minor Race")
+            .with_pos(label_width + SPACING, row_y(9))
+            .with_size(input_width, TEXT_HEIGHT);
+        minor_input.set_value(sys.minor.as_str());
+        let mut contender_choice = menu::Choice::default()
+            .with_label("Contender")
+            .with_pos(label_width + SPACING, row_y(10))
+            .with_size(input_width, TEXT_HEIGHT);
+        contender_choice.add_choice("None");
+        for e in &empires {
+            contender_choice.add_choice(e.name.replace('/', "\\/").as_str());
+        }
+        contender_choice.set_value(
+            empires
+                .iter()
+                .position(|e| e.id == sys.contender)
+                .map_or(0, |p| p + 1) as i32,
+        );
+        let mut share_input = input::IntInput::default()
+            .with_label("Owner %")
+            .with_pos(label_width + SPACING, row_y(11))
+            .with_size(input_width, TEXT_HEIGHT);
+        share_input.set_value(sys.share.to_string().as_str());
+        let mut policy_choice = menu::Choice::default()
+            .with_label("Policy")
+            .with_pos(label_width + SPACING, row_y(12))
+            .with_size(input_width, TEXT_HEIGHT);
+        policy_choice.add_choice("None");
+        for p in POLICIES {
+            policy_choice.add_choice(p);
+        }
+        policy_choice.set_value(
+            POLICIES
+                .iter()
+                .position(|p| *p == sys.policy)
+                .map_or(0, |p| p + 1) as i32,
+        );
+
+        let button_y = total_height - BTN_HEIGHT - SPACING;
+        let mut ok = button::Button::default()
+            .with_label("Ok")
+            .with_pos(SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT);
+        let mut cancel = button::Button::default()
+            .with_label("Cancel")
+            .with_pos(BTN_WIDTH + 2 * SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT);
+        // A system must be saved before it can have facilities.
+        let mut facilities = button::Button::default()
+            .with_label("Facilities")
+            .with_pos(2 * BTN_WIDTH + 3 * SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT);
+        if sys.id == 0 {
+            facilities.deactivate();
+        }
+
+        wind.end();
+        wind.make_modal(true);
+        wind.show();
+
+        let (s, r) = app::channel();
+        ok.emit(s, "Ok");
+        cancel.emit(s, "Cancel");
+        facilities.emit(s, "Facilities");
+
+        let mut is_ok = false;
+        while wind.shown() && self.app.wait() {
+            match r.recv() {
+                Some("Facilities") => self.show_facilities(&sys).await,
+                Some(a) => {
+                    is_ok = a == "Ok";
+                    wind.hide();
+                }
+                None => (),
+            }
+        }
+
+        if !is_ok || name_input.value().is_empty() {
+            return None;
+        }
+        let ptype = types.get(type_choice.value() as usize)?;
+        let (owner, owner_name) = match owner_choice.value() {
+            n if n > 0 => {
+                let e = empires.get(n as usize - 1)?;
+                (e.id, e.name.clone())
+            }
+            _ => (0, "None".to_string()),
+        };
+        // An owner cannot contest its own system.
+        let contender = match contender_choice.value() {
+            n if n > 0 => empires.get(n as usize - 1).filter(|e| e.id != owner),
+            _ => None,
+        };
+        let (contender, contender_name) =
+            contender.map_or((0, String::new()), |e| (e.id, e.name.clone()));
+        let v: Vec<i32> = values
+            .iter()
+            .zip([sys.raw, sys.cap, sys.pop, sys.mor, sys.ind])
+            .map(|(i, old)| i.value().parse().unwrap_or(old).max(0))
+            .collect();
+        Some(System {
+            name: name_input.value(),
+            ptype: ptype.name.clone(),
+            raw: v[0],
+            cap: v[1],
+            pop: v[2],
+            mor: v[3],
+            ind: v[4],
+            owner,
+            owner_name,
+            x: x_input.value().parse().unwrap_or(sys.x),
+            y: y_input.value().parse().unwrap_or(sys.y),
+            minor: if owner == 0 {
+                minor_input.value().trim().to_string()
+            } else {
+                String::new()
+            },
+            contender,
+            contender_name,
+            share: share_input
+                .value()
+                .parse()
+                .unwrap_or(sys.share)
+                .clamp(0, 100),
+            policy: match policy_choice.value() {
+                n if n > 0 => POLICIES[n as usize - 1].to_string(),
+                _ => String::new(),
+            },
+            ..sys
+        })
+    }
+
+    // Show the facilities at a system, to build, place, repair and demolish
+    // them.
+    async fn show_facilities(&mut self, sys: &System) {
+        let mut wind = window::Window::default()
+            .with_size(600, 400)
+            .with_label(format!("{} Facilities", sys.name).as_str())
+            .center_screen();
+        let mut browse = SelectBrowser::default().with_pos(5, 5).with_size(590, 300);
+        browse.set_column_widths(&[200, 100]);
+        browse.set_column_char('\t');
+        Self::fill_facility_browser(&mut browse, self.cmpgn.as_ref().unwrap(), sys).await;
+
+        let (s, r) = app::channel();
+
+        let button_y = 310; // Browser bottom plus spacing
+        for (n, label) in ["Build", "Add", "Repair", "Demolish"].iter().enumerate() {
+            button::Button::default()
+                .with_label(label)
+                .with_pos(SPACING + n as i32 * (BTN_WIDTH + SPACING), button_y)
+                .with_size(BTN_WIDTH, BTN_HEIGHT)
+                .emit(s, *label);
+        }
+
+        wind.end();
+        wind.make_modal(true);
+        wind.show();
+
+        while wind.shown() && app::wait() {
+            if let Some(m) = r.recv() {
+                let sel = browse.value();
+                // Ignore header, so only act on a selection if 2+
+                let selected = if sel > 1 {
+                    unsafe { browse.data::<Facility>(sel) }
+                } else {
+                    None
+                };
+                let result = match (m, selected) {
+                    ("Build", _) => match self.choose_facility() {
+                        Some(kind) => {
+                            let c = self.cmpgn.as_ref().unwrap();
+                            c.queue_facility(sys, kind).await.map(|b| {
+                                dialog::message_default(
+                                    format!("{} queued for {} EP", b.name, b.cost).as_str(),
+                                )
+                            })
+                        }
+                        None => Ok(()),
+                    },
+                    ("Add", _) => match self.choose_facility() {
+                        Some(kind) => {
+                            let c = self.cmpgn.as_ref().unwrap();
+                            c.add_facility(sys, kind).await.map(|_| ())
+                        }
+                        None => Ok(()),
+                    },
+                    ("Repair", Some(f)) => self.cmpgn.as_ref().unwrap().repair_facility(&f).await,
+                    ("Demolish", Some(f)) => {
+                        let prompt = format!("Demolish the {} at {}?", f.kind, sys.name);
+                        if dialog::choice2_default(prompt.as_str(), "Cancel", "Demolish", "")
+                            == Some(1)
+                        {
+                            self.cmpgn.as_ref().unwrap().demolish_facility(&f).await
+                        } else {
+                            Ok(())
+                        }
+                    }
+                    _ => Ok(()),
+                };
+                if let Err(e) = result {
+                    dialog::alert_default(e.as_str());
+                }
+                Self::fill_facility_browser(&mut browse, self.cmpgn.as_ref().unwrap(), sys).await;
+                browse.select(sel);
+            }
+        }
+    }
+
+    // Choose a kind of facility, showing what each costs.
+    fn choose_facility(&mut self) -> Option<&'static str> {
+        let names: Vec<String> = facility::SPECS
+            .iter()
+            .map(|s| format!("{} ({} EP, {} turns)", s.kind, s.cost, s.turns))
+            .collect();
+        let n = self.choose("Facility", &names)?;
+        Some(facility::SPECS[n].kind)
+    }
+
+    // Fill the facility browser with the facilities at a system.
+    async fn fill_facility_browser(browse: &mut SelectBrowser, c: &Campaign, sys: &System) {
+        browse.clear();
+        browse.add("Type\tStatus\tUpkeep");
+
+        match c.facilities().await {
+            Ok(v) => {
+                for f in v.into_iter().filter(|f| f.system == sys.id) {
+                    browse.add_with_data(f.as_row().as_str(), f);
+                }
+            }
+            Err(e) => dialog::alert_default(e.as_str()),
+        }
+    }
+
+    // Fill the garrison browser with the ground units at a system.
+    async fn fill_garrison_browser(browse: &mut browser::MultiBrowser, c: &Campaign, sys: &System) {
+        browse.clear();
+        browse.add("ID\tType\tAbbr\tATK\tDEF");
+
+        match c.garrison(sys).await {
+            Ok(v) => {
+                for u in v {
+                    browse.add_with_data(u.as_row().as_str(), u);
+                }
+            }
+            Err(e) => dialog::alert_default(e.as_str()),
+        }
+    }
+
+    // Fill the system table with the campaign's systems that pass the
+    // filter.
+    async fn fill_system_table(
+        table: &mut RecordTable<System>,
+        c: &Campaign,
+        filter: &SystemFilter,
+    ) {
+        let systems = match filter.owner {
+            Some(o) => c.systems_by_owner(o).await,
+            None => c.systems().await,
+        };
+        match systems {
+            Ok(v) => table.set_records(v.into_iter().filter(|s| filter.matches(s)).collect()),
+            Err(e) => dialog::alert_default(e.as_str()),
+        }
+    }
+
+    // Fill the construction browser with the campaign's construction queue.
+    async fn fill_build_browser(browse: &mut SelectBrowser, c: &Campaign) {
+        browse.clear();
+        browse.add("Empire\tSystem\tKind\tItem\tCost\tTurns");
+
+        match c.builds().await {
+            Ok(v) => {
+                for b in v {
+                    browse.add_with_data(b.as_row().as_str(), b);
+                }
+            }
+            Err(e) => dialog::alert_default(e.as_str()),
+        }
+    }
+
+    // Fill the production browser with each empire's budget and the load on
+    // its shipyards.
+    async fn fill_production_browser(browse: &mut browser::HoldBrowser, c: &Campaign) {
+        browse.clear();
+        browse.add("Empire\tShipyard\tCapacity\tBuilds\tQueued EP");
+
+        let locale = c.locale().await.unwrap_or(&LOCALES[0]);
+        match c.production().await {
+            Ok(v) => {
+                for p in v {
+                    for row in p.as_rows(locale) {
+                        browse.add(row.as_str());
+                    }
+                }
+            }
+            Err(e) => dialog::alert_default(e.as_str()),
+        }
+    }
+
+    // Fill the empire browser with the campaign's empires.
+    async fn fill_empire_browser(browse: &mut SelectBrowser, c: &Campaign) {
+        browse.clear();
+        browse.add("Name\tTreasury\tTech\tGovernment\tTraits");
+
+        match c.empires().await {
+            Ok(v) => {
+                for e in v {
+                    browse.add_with_data(e.as_row().as_str(), e);
+                }
+            }
+            Err(e) => dialog::alert_default(e.as_str()),
+        }
+    }
+
+    // Fill the escort browser with every fleet escorting another.
+    async fn fill_escort_browser(browse: &mut SelectBrowser, c: &Campaign) {
+        browse.clear();
+        browse.add("Escort\tConvoy");
+
+        match c.escorts().await {
+            Ok(v) => {
+                for e in v {
+                    browse.add_with_data(e.as_row().as_str(), e);
+                }
+            }
+            Err(e) => dialog::alert_default(e.as_str()),
+        }
+    }
+
+    // Fill the fleet browser with the campaign's fleet summaries.
+    async fn fill_fleet_browser(browse: &mut SelectBrowser, c: &Campaign) {
+        browse.clear();
+        browse.add("Name\tOwner\tLocation\tShips\tCrippled\tATK\tDEF\tMaint\tJumps");
+
+        match c.fleet_summaries().await {
+            Ok(v) => {
+                for f in v {
+                    browse.add_with_data(f.as_row().as_str(), f);
+                }
+            }
+            Err(e) => dialog::alert_default(e.as_str()),
+        }
+    }
+
+    // Fill the flight browser with the flights of a fleet's empire where the
+    // fleet is.
+    async fn fill_flight_browser(browse: &mut SelectBrowser, c: &Campaign, fleet: &FleetSummary) {
+        browse.clear();
+        browse.add("ID\tStation\tATK\tDEF\tStatus");
+
+        match c.flights(fleet.location).await {
+            Ok(v) => {
+                for f in v.into_iter().filter(|f| f.owner == fleet.owner) {
+                    browse.add_with_data(f.as_row().as_str(), f);
+                }
+            }
+            Err(e) => dialog::alert_default(e.as_str()),
+        }
+    }
+
+    // Fill the ship browser with the ships of a fleet.
+    async fn fill_fleet_ship_browser(browse: &mut SelectBrowser, c: &Campaign, fleet: i64) {
+        browse.clear();
+        browse.add("ID\tClass\tStatus\tLocation");
+
+        match c.fleet_ships(fleet).await {
+            Ok(v) => {
+                for ship in v {
+                    let row = format!(
+                        "{}\t{}\t{}\t{}",
+                        ship.id,
+                        ship.class,
+                        ShipStatus::from_flags(ship.crip, ship.moth),
+                        ship.location_name
+                    );
+                    browse.add_with_data(row.as_str(), ship);
+                }
+            }
+            Err(e) => dialog::alert_default(e.as_str()),
+        }
+    }
+
+    // Fill the ship class browser with the campaign's ship classes.
+    async fn fill_ship_type_browser(browse: &mut SelectBrowser, c: &Campaign) {
+        browse.clear();
+        browse.add("Empire\tClass\tHull\tCost\tCR\tATK\tDEF\tCap\tSpeed\tShips\tStatus");
+
+        match c.ship_types().await {
+            Ok(v) => {
+                for t in v {
+                    browse.add_with_data(t.as_row().as_str(), t);
+                }
+            }
+            Err(e) => dialog::alert_default(e.as_str()),
+        }
+    }
+
+    // Fill the treaty browser with the campaign's treaties.
+    async fn fill_treaty_browser(browse: &mut SelectBrowser, c: &Campaign) {
+        browse.clear();
+        browse.add("Empire\tEmpire\tType\tSigned\tExpires");
+
+        match c.treaties().await {
+            Ok(v) => {
+                for t in v {
+                    browse.add_with_data(t.as_row().as_str(), t);
+                }
+            }
+            Err(e) => dialog::alert_default(e.as_str()),
+        }
+    }
+
+    // Fill the development browser with the orders given for the turn.
+    async fn fill_development_browser(browse: &mut SelectBrowser, c: &Campaign) {
+        browse.clear();
+        browse.add("Turn\tEmpire\tSystem\tTarget\tEP");
+
+        match c.developments().await {
+            Ok(v) => {
+                for d in v {
+                    browse.add_with_data(d.as_row().as_str(), d);
+                }
+            }
+            Err(e) => dialog::alert_default(e.as_str()),
+        }
+    }
+
+    // Fill the operation browser with the espionage operations ordered for
+    // the turn.
+    async fn fill_operation_browser(browse: &mut SelectBrowser, c: &Campaign) {
+        browse.clear();
+        browse.add("Turn\tEmpire\tTarget\tMission\tPoints");
+
+        match c.operations().await {
+            Ok(v) => {
+                for op in v {
+                    browse.add_with_data(op.as_row().as_str(), op);
+                }
+            }
+            Err(e) => dialog::alert_default(e.as_str()),
+        }
+    }
+
+    // Fill the transfer browser with the transfers agreed for the turn.
+    async fn fill_transfer_browser(browse: &mut SelectBrowser, c: &Campaign) {
+        browse.clear();
+        browse.add("Turn\tFrom\tTo\tShip or Class\tPrice");
+
+        match c.transfers().await {
+            Ok(v) => {
+                for t in v {
+                    browse.add_with_data(t.as_row().as_str(), t);
+                }
+            }
+            Err(e) => dialog::alert_default(e.as_str()),
+        }
+    }
+
+    // Fill the raid browser with the raids ordered for the turn.
+    async fn fill_raid_browser(browse: &mut SelectBrowser, c: &Campaign) {
+        browse.clear();
+        browse.add("Turn\tEmpire\tSystem\tTarget");
+
+        match c.raids().await {
+            Ok(v) => {
+                for r in v {
+                    browse.add_with_data(r.as_row().as_str(), r);
+                }
+            }
+            Err(e) => dialog::alert_default(e.as_str()),
+        }
+    }
+
+    // Pop up a list of items to choose from and return the index of the
+    // user's choice.
+    fn choose(&mut self, title: &str, items: &[String]) -> Option<usize> {
+        let total_width = SPACING + 2 * (BTN_WIDTH + SPACING);
+        let total_height = 150;
+        let full_width = total_width - 2 * SPACING;
+
+        let mut wind = window::Window::default()
+            .with_size(total_width, total_height)
+            .with_label(title)
+            .center_screen();
+        let mut choice = menu::Choice::default()
+            .with_pos(SPACING, SPACING)
+            .with_size(full_width, TEXT_HEIGHT);
+        for i in items {
+            choice.add_choice(i.replace('/', "\\/").as_str());
+        }
+        choice.set_value(0);
+        let button_y = total_height - SPACING - BTN_HEIGHT;
+        let mut ok = button::Button::default()
+            .with_label("Ok")
+            .with_pos(SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT);
+        let mut cancel = button::Button::default()
+            .with_label("Cancel")
+            .with_pos(BTN_WIDTH + 2 * SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT);
+        wind.end();
+        wind.make_modal(true);
+        wind.show();
+
+        let (s, r) = app::channel();
+        ok.emit(s, true);
+        cancel.emit(s, false);
+
+        let mut is_ok = false;
+        while wind.shown() && self.app.wait() {
+            if let Some(a) = r.recv() {
+                is_ok = a;
+                wind.hide();
+            }
+        }
+
+        if is_ok && choice.value() >= 0 {
+            Some(choice.value() as usize)
+        } else {
+            None
+        }
+    }
+
+    // Import a list of systems from a CSV file.
+    async fn import_systems(&mut self) {
+        let c = match &mut self.cmpgn {
+            Some(c) => c,
+            None => return,
+        };
+
+        // Choose the CSV file
+        let file =
+            match dialog::file_chooser("Import systems from...", "*.{csv,tsv,txt}", ".", true) {
+                Some(f) => f,
+                None => return,
+            };
+        let dups = match c.duplicate_systems(file.as_str()).await {
+            Ok(v) => v,
+            Err(e) => {
+                dialog::alert_default(e.as_str());
+                return;
+            }
+        };
+        // Ask what to do with systems the campaign already has.
+        let dup = if dups.is_empty() {
+            Duplicates::Refuse
+        } else {
+            let prompt = format!(
+                "Already in the campaign: {}\n\nSkip them, overwrite them with the file, \
+                or merge in the file's statistics keeping owners and development?",
+                dups.join(", ")
+            );
+            match dialog::choice2_default(prompt.as_str(), "Skip", "Overwrite", "Merge") {
+                Some(0) => Duplicates::Skip,
+                Some(1) => Duplicates::Overwrite,
+                Some(2) => Duplicates::Merge,
+                _ => return,
+            }
+        };
+        if let Err(e) = c.import_systems(file.as_str(), dup).await {
+            dialog::alert_default(e.as_str())
+        }
+    }
+
+    // Pop up the select campaign dialog and return the user's choice.
+    fn list_campaigns(&mut self, function: String) -> Option<String> {
+        let names = match Campaign::campaigns() {
+            Ok(v) => v.join("|"),
+            _ => return None,
+        };
+
+        let total_width = SPACING + 2 * (BTN_WIDTH + SPACING);
+        let total_height = 150;
+        let full_width = total_width - 2 * SPACING;
+
+        let mut wind = window::Window::default()
+            .with_size(total_width, total_height)
+            .with_label(format!("{} Campaign", function).as_str())
+            .center_screen();
+        let mut choice = menu::Choice::default()
+            .with_pos(SPACING, SPACING)
+            .with_size(full_width, TEXT_HEIGHT);
+        choice.add_choice(names.as_str());
+        let button_y = total_height - SPACING - BTN_HEIGHT;
+        let mut ok = button::Button::default()
+            .with_label("Ok")
+            .with_pos(SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT);
+        let mut cancel = button::Button::default()
+            .with_label("Cancel")
+            .with_pos(BTN_WIDTH + 2 * SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT);
+        wind.end();
+        wind.make_modal(true);
+        wind.show();
+
+        let (s, r) = app::channel();
+        ok.emit(s, true);
+        cancel.emit(s, false);
+
+        let mut is_ok = false;
+        while wind.shown() && self.app.wait() {
+            if let Some(a) = r.recv() {
+                is_ok = match a {
+                    true => true,
+                    false => false,
+                };
+                wind.hide();
+            }
+        }
+
+        if is_ok {
+            choice.choice()
+        } else {
+            None
+        }
+    }
+
+    // Set the main window title. Includes campaign name if one is active.
+    fn set_title(&mut self) {
+        let title = match &self.cmpgn {
+            Some(cm) => format!("{} ({})", MAIN_TITLE, cm.title()),
+            None => MAIN_TITLE.to_string(),
+        };
+        self.main_win.set_label(title.as_str());
+    }
+
+    // Show the combat modifier tables for editing.
+    async fn show_combat_modifiers(&mut self) {
+        if self.cmpgn.is_none() {
+            return;
+        }
+
+        let mut wind = window::Window::default()
+            .with_size(600, 400)
+            .with_label("Combat Modifiers")
+            .center_screen();
+        let mut browse = SelectBrowser::default().with_pos(5, 5).with_size(590, 300);
+        browse.set_column_widths(&[120, 200, 60, 60]);
+        browse.set_column_char('\t');
+        browse.add("Category\tName\tATK\tDEF");
+        match self.cmpgn.as_ref().unwrap().combat_modifiers().await {
+            Ok(v) => {
+                for m in v {
+                    browse.add_with_data(m.as_row().as_str(), m);
+                }
+            }
+            Err(e) => dialog::alert_default(e.as_str()),
+        }
+
+        let (s, r) = app::channel();
+
+        let button_y = 310; // Browser bottom plus spacing
+        button::Button::default()
+            .with_label("New")
+            .with_pos(SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "New");
+        button::Button::default()
+            .with_label("Edit")
+            .with_pos(BTN_WIDTH + 2 * SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Edit");
+        button::Button::default()
+            .with_label("Delete")
+            .with_pos(SPACING + 2 * (BTN_WIDTH + SPACING), button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Delete");
+
+        wind.end();
+        wind.show();
+
+        while wind.shown() && app::wait() {
+            if let Some(m) = r.recv() {
+                match m {
+                    "New" => {
+                        let blank = CombatModifier::new("", "", 0, 0);
+                        if let Some(mut m) = self.edit_combat_modifier(blank).await {
+                            match self.cmpgn.as_ref().unwrap().add_combat_modifier(&m).await {
+                                Ok(id) => {
+                                    m.id = id;
+                                    browse.add_with_data(m.as_row().as_str(), m);
+                                }
+                                Err(e) => dialog::alert_default(e.as_str()),
+                            }
+                        }
+                    }
+                    "Edit" => {
+                        let sel = browse.value();
+                        if sel > 1 {
+                            // Ignore header, so only edit if 2+
+                            unsafe {
+                                if let Some(m) = browse.data(sel) {
+                                    if let Some(m) = self.edit_combat_modifier(m).await {
+                                        match self
+                                            .cmpgn
+                                            .as_ref()
+                                            .unwrap()
+                                            .update_combat_modifier(&m)
+                                            .await
+                                        {
+                                            Ok(_) => {
+                                                browse.set_text(sel, m.as_row().as_str());
+                                                browse.set_data(sel, m);
+                                            }
+                                            Err(e) => dialog::alert_default(e.as_str()),
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    "Delete" => {
+                        let sel = browse.value();
+                        if sel > 1 {
+                            // Ignore header, so only delete if 2+
+                            unsafe {
+                                if let Some(m) = browse.data::<CombatModifier>(sel) {
+                                    match self
+                                        .cmpgn
+                                        .as_ref()
+                                        .unwrap()
+                                        .delete_combat_modifier(&m)
+                                        .await
+                                    {
+                                        Ok(_) => browse.remove(sel),
+                                        Err(e) => dialog::alert_default(e.as_str()),
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    _ => (),
+                }
+            }
+        }
+    }
+
+    // Show the empires.
+    async fn show_empires(&mut self) {
+        if self.cmpgn.is_none() {
+            return;
+        }
+
+        let mut wind = window::Window::default()
+            .with_size(600, 435)
+            .with_label("Empires")
+            .center_screen();
+        let mut browse = SelectBrowser::default().with_pos(5, 5).with_size(590, 300);
+        browse.set_column_widths(&[150, 70, 50, 100]);
+        browse.set_column_char('\t');
+        Self::fill_empire_browser(&mut browse, self.cmpgn.as_ref().unwrap()).await;
+
+        let (s, r) = app::channel();
+
+        let button_y = 310; // Browser bottom plus spacing
+        button::Button::default()
+            .with_label("New")
+            .with_pos(SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "New");
+        button::Button::default()
+            .with_label("Edit")
+            .with_pos(BTN_WIDTH + 2 * SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Edit");
+        button::Button::default()
+            .with_label("Delete")
+            .with_pos(SPACING + 2 * (BTN_WIDTH + SPACING), button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Delete");
+        button::Button::default()
+            .with_label("Systems")
+            .with_pos(SPACING + 3 * (BTN_WIDTH + SPACING), button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Systems");
+        button::Button::default()
+            .with_label("Finances")
+            .with_pos(SPACING + 4 * (BTN_WIDTH + SPACING), button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Finances");
+        let button_y = button_y + BTN_HEIGHT + SPACING;
+        button::Button::default()
+            .with_label("Intel")
+            .with_pos(SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Intel");
+        button::Button::default()
+            .with_label("Packet")
+            .with_pos(BTN_WIDTH + 2 * SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Packet");
+        button::Button::default()
+            .with_label("Orders")
+            .with_pos(SPACING + 2 * (BTN_WIDTH + SPACING), button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Orders");
+        button::Button::default()
+            .with_label("Morale")
+            .with_pos(SPACING + 3 * (BTN_WIDTH + SPACING), button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Morale");
+        button::Button::default()
+            .with_label("Research")
+            .with_pos(SPACING + 4 * (BTN_WIDTH + SPACING), button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Research");
+        let button_y = button_y + BTN_HEIGHT + SPACING;
+        button::Button::default()
+            .with_label("Espionage")
+            .with_pos(SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Espionage");
+        button::Button::default()
+            .with_label("Operations")
+            .with_pos(BTN_WIDTH + 2 * SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Operations");
+        button::Button::default()
+            .with_label("Economy")
+            .with_pos(SPACING + 2 * (BTN_WIDTH + SPACING), button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Economy");
+
+        wind.end();
+        wind.show();
+
+        while wind.shown() && app::wait() {
+            if let Some(m) = r.recv() {
+                match m {
+                    "New" => {
+                        if let Some(mut emp) = self.edit_empire(Empire::new("")).await {
+                            match self.cmpgn.as_ref().unwrap().add_empire(&emp).await {
+                                Ok(id) => {
+                                    emp.id = id;
+                                    browse.add_with_data(emp.as_row().as_str(), emp);
+                                }
+                                Err(e) => dialog::alert_default(e.as_str()),
+                            }
+                        }
+                    }
+                    "Edit" => {
+                        let sel = browse.value();
+                        if sel > 1 {
+                            // Ignore header, so only edit if 2+
+                            unsafe {
+                                if let Some(emp) = browse.data(sel) {
+                                    if let Some(emp) = self.edit_empire(emp).await {
+                                        match self.cmpgn.as_ref().unwrap().update_empire(&emp).await
+                                        {
+                                            Ok(_) => {
+                                                browse.set_text(sel, emp.as_row().as_str());
+                                                browse.set_data(sel, emp);
+                                            }
+                                            Err(e) => dialog::alert_default(e.as_str()),
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    "Delete" => {
+                        let sel = browse.value();
+                        if sel > 1 {
+                            // Ignore header, so only delete if 2+
+                            unsafe {
+                                if let Some(emp) = browse.data::<Empire>(sel) {
+                                    let prompt = format!(
+                                        "Delete {}? Its systems will become unowned.",
+                                        emp.name
+                                    );
+                                    if dialog::choice2_default(
+                                        prompt.as_str(),
+                                        "Cancel",
+                                        "Delete",
+                                        "",
+                                    ) == Some(1)
+                                    {
+                                        match self.cmpgn.as_ref().unwrap().delete_empire(&emp).await
+                                        {
+                                            Ok(_) => browse.remove(sel),
+                                            Err(e) => dialog::alert_default(e.as_str()),
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    "Finances" => {
+                        let sel = browse.value();
+                        if sel > 1 {
+                            // Ignore header, so only edit if 2+
+                            unsafe {
+                                if let Some(emp) = browse.data::<Empire>(sel) {
+                                    if let Some(emp) = self.edit_financials(emp).await {
+                                        browse.set_text(sel, emp.as_row().as_str());
+                                        browse.set_data(sel, emp);
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    "Systems" => {
+                        let sel = browse.value();
+                        if sel > 1 {
+                            // Ignore header, so only show if 2+
+                            unsafe {
+                                if let Some(emp) = browse.data::<Empire>(sel) {
+                                    self.show_systems_window(Some(emp.id)).await;
+                                }
+                            }
+                        }
+                    }
+                    "Intel" => {
+                        let sel = browse.value();
+                        if sel > 1 {
+                            // Ignore header, so only show if 2+
+                            unsafe {
+                                if let Some(emp) = browse.data::<Empire>(sel) {
+                                    self.show_intel(&emp).await;
+                                }
+                            }
+                        }
+                    }
+                    "Orders" => {
+                        let sel = browse.value();
+                        if sel > 1 {
+                            // Ignore header, so only show if 2+
+                            unsafe {
+                                if let Some(emp) = browse.data::<Empire>(sel) {
+                                    self.show_orders(&emp).await;
+                                }
+                            }
+                        }
+                    }
+                    "Research" => self.show_research().await,
+                    "Espionage" => {
+                        let sel = browse.value();
+                        if sel > 1 {
+                            // Ignore header, so only order if 2+
+                            unsafe {
+                                if let Some(emp) = browse.data::<Empire>(sel) {
+                                    self.order_operation(&emp).await;
+                                }
+                            }
+                        }
+                    }
+                    "Operations" => self.show_operations().await,
+                    "Economy" => {
+                        let sel = browse.value();
+                        if sel > 1 {
+                            // Ignore header, so only show if 2+
+                            unsafe {
+                                if let Some(emp) = browse.data::<Empire>(sel) {
+                                    self.show_statement(&emp).await;
+                                }
+                            }
+                        }
+                    }
+                    "Morale" => {
+                        let sel = browse.value();
+                        if sel > 1 {
+                            // Ignore header, so only set if 2+
+                            unsafe {
+                                if let Some(emp) = browse.data::<Empire>(sel) {
+                                    self.set_morale_modifier(&emp).await;
+                                }
+                            }
+                        }
+                    }
+                    "Packet" => {
+                        let sel = browse.value();
+                        if sel > 1 {
+                            // Ignore header, so only show if 2+
+                            unsafe {
+                                if let Some(emp) = browse.data::<Empire>(sel) {
+                                    match self.cmpgn.as_ref().unwrap().player_packet(&emp).await {
+                                        Ok(p) => show_report(
+                                            format!("{} Player Packet", emp.name).as_str(),
+                                            p.as_str(),
+                                        ),
+                                        Err(e) => dialog::alert_default(e.as_str()),
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    _ => (),
+                }
+            }
+        }
+    }
+
+    async fn fill_statement_browser(browse: &mut browser::HoldBrowser, c: &Campaign, emp: &Empire) {
+        browse.clear();
+        let locale = c.locale().await.unwrap_or(&LOCALES[0]);
+        match c.statement(emp.id).await {
+            Ok(s) => {
+                for row in s.as_rows(locale) {
+                    browse.add(row.as_str());
+                }
+            }
+            Err(e) => dialog::alert_default(e.as_str()),
+        }
+    }
+
+    // Show an empire's economy at a glance: its treasury, tech level and
+    // construction, with the income it can expect and where that comes from.
+    async fn show_statement(&mut self, emp: &Empire) {
+        if self.cmpgn.is_none() {
+            return;
+        }
+
+        let mut wind = window::Window::default()
+            .with_size(600, 480)
+            .with_label(format!("{} Economy", emp.name).as_str())
+            .center_screen();
+        let mut browse = browser::HoldBrowser::default()
+            .with_pos(5, 5)
+            .with_size(590, 430);
+        browse.set_column_widths(&[150, 60, 60, 70, 60, 60, 60]);
+        browse.set_column_char('\t');
+        Self::fill_statement_browser(&mut browse, self.cmpgn.as_ref().unwrap(), emp).await;
+
+        let (s, r) = app::channel();
+
+        let button_y = 440; // Browser bottom plus spacing
+        button::Button::default()
+            .with_label("Refresh")
+            .with_pos(SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Refresh");
+
+        wind.end();
+        wind.show();
+
+        while wind.shown() && app::wait() {
+            if let Some("Refresh") = r.recv() {
+                Self::fill_statement_browser(&mut browse, self.cmpgn.as_ref().unwrap(), emp).await;
+            }
+        }
+    }
+
+    // Show the ground units garrisoned at a system.
+    async fn show_garrison(&mut self, sys: &System) {
+        if self.cmpgn.is_none() {
+            return;
+        }
+
+        let mut wind = window::Window::default()
+            .with_size(600, 400)
+            .with_label(format!("{} Garrison", sys.name).as_str())
+            .center_screen();
+        let mut browse = browser::MultiBrowser::default()
+            .with_pos(5, 5)
+            .with_size(590, 300);
+        browse.set_column_widths(&[60, 200, 60, 40, 40]);
+        browse.set_column_char('\t');
+        Self::fill_garrison_browser(&mut browse, self.cmpgn.as_ref().unwrap(), sys).await;
+
+        let (s, r) = app::channel();
+
+        let button_y = 310; // Browser bottom plus spacing
+        button::Button::default()
+            .with_label("Raise")
+            .with_pos(SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Raise");
+        button::Button::default()
+            .with_label("Disband")
+            .with_pos(BTN_WIDTH + 2 * SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Disband");
+        button::Button::default()
+            .with_label("Transfer")
+            .with_pos(SPACING + 2 * (BTN_WIDTH + SPACING), button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Transfer");
+        button::Button::default()
+            .with_label("Invade")
+            .with_pos(SPACING + 3 * (BTN_WIDTH + SPACING), button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Invade");
+        button::Button::default()
+            .with_label("Scorch")
+            .with_pos(SPACING + 4 * (BTN_WIDTH + SPACING), button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Scorch");
+
+        wind.end();
+        wind.show();
+
+        while wind.shown() && app::wait() {
+            if let Some(m) = r.recv() {
+                // Selected units, ignoring the header
+                let mut units = Vec::new();
+                for line in 2..=browse.size() {
+                    if browse.selected(line) {
+                        if let Some(u) = unsafe { browse.data::<GroundUnit>(line) } {
+                            units.push(u.id);
+                        }
+                    }
+                }
+                let result = match m {
+                    "Raise" => {
+                        let types = match self.cmpgn.as_ref().unwrap().ground_types().await {
+                            Ok(v) => v,
+                            Err(e) => {
+                                dialog::alert_default(e.as_str());
+                                continue;
+                            }
+                        };
+                        let names: Vec<String> = types.iter().map(|t| t.name.clone()).collect();
+                        match self.choose("Raise Unit", &names) {
+                            Some(i) => {
+                                self.cmpgn
+                                    .as_ref()
+                                    .unwrap()
+                                    .raise_ground_unit(sys, &types[i])
+                                    .await
+                            }
+                            None => continue,
+                        }
+                    }
+                    "Disband" if !units.is_empty() => {
+                        let prompt = format!("Disband {} units?", units.len());
+                        if dialog::choice2_default(prompt.as_str(), "No", "Yes", "") != Some(1) {
+                            continue;
+                        }
+                        self.cmpgn
+                            .as_ref()
+                            .unwrap()
+                            .disband_ground_units(&units)
+                            .await
+                    }
+                    "Transfer" if !units.is_empty() => {
+                        let systems: Vec<System> =
+                            match self.cmpgn.as_ref().unwrap().systems().await {
+                                Ok(v) => v.into_iter().filter(|s| s.id != sys.id).collect(),
+                                Err(e) => {
+                                    dialog::alert_default(e.as_str());
+                                    continue;
+                                }
+                            };
+                        let names: Vec<String> = systems.iter().map(|s| s.name.clone()).collect();
+                        match self.choose("Transfer To", &names) {
+                            Some(i) => {
+                                self.cmpgn
+                                    .as_ref()
+                                    .unwrap()
+                                    .transfer_ground_units(sys, &systems[i], &units)
+                                    .await
+                            }
+                            None => continue,
+                        }
+                    }
+                    "Invade" if !units.is_empty() => {
+                        let systems: Vec<System> =
+                            match self.cmpgn.as_ref().unwrap().systems().await {
+                                Ok(v) => v.into_iter().filter(|s| s.owner != sys.owner).collect(),
+                                Err(e) => {
+                                    dialog::alert_default(e.as_str());
+                                    continue;
+                                }
+                            };
+                        let names: Vec<String> = systems
+                            .iter()
+                            .map(|s| format!("{} ({})", s.name, s.owner_name))
+                            .collect();
+                        let target = match self.choose("Invade", &names) {
+                            Some(i) => &systems[i],
+                            None => continue,
+                        };
+                        let seed = SystemTime::now()
+                            .duration_since(UNIX_EPOCH)
+                            .map_or(0, |d| d.as_nanos() as u64);
+                        match self
+                            .cmpgn
+                            .as_ref()
+                            .unwrap()
+                            .invade(sys, target, &units, seed)
+                            .await
+                        {
+                            Ok(r) => {
+                                show_report("Planetary Assault", r.as_str());
+                                Ok(())
+                            }
+                            Err(e) => Err(e),
+                        }
+                    }
+                    "Scorch" => self.order_scorch(sys).await,
+                    _ => continue,
+                };
+                if let Err(e) = result {
+                    dialog::alert_default(e.as_str());
+                }
+                Self::fill_garrison_browser(&mut browse, self.cmpgn.as_ref().unwrap(), sys).await;
+            }
+        }
+    }
+
+    // Order or cancel the destruction of part of a system should it fall
+    // this turn.
+    async fn order_scorch(&mut self, sys: &System) -> Result<(), String> {
+        let orders = self.cmpgn.as_ref().unwrap().scorch_orders(sys).await?;
+        let names: Vec<String> = scorch::TARGETS
+            .iter()
+            .map(|t| {
+                if orders.iter().any(|o| o.target == *t) {
+                    format!("{} (ordered)", t)
+                } else {
+                    t.to_string()
+                }
+            })
+            .collect();
+        let target = match self.choose("Scorched Earth", &names) {
+            Some(i) => scorch::TARGETS[i],
+            None => return Ok(()),
+        };
+        let c = self.cmpgn.as_ref().unwrap();
+        match orders.iter().find(|o| o.target == target) {
+            Some(o) => {
+                let prompt = format!("Cancel the order to destroy the {}?", target.to_lowercase());
+                if dialog::choice2_default(prompt.as_str(), "No", "Yes", "") == Some(1) {
+                    c.cancel_scorch(o).await?;
+                }
+            }
+            None => {
+                c.order_scorch(sys, target).await?;
+                dialog::message_default(
+                    format!(
+                        "{} will destroy the {} of {} if it falls this turn",
+                        sys.owner_name,
+                        target.to_lowercase(),
+                        sys.name
+                    )
+                    .as_str(),
+                );
+            }
+        }
+        Ok(())
+    }
+
+    // Show the intel attached to an empire's next player packet.
+    async fn show_intel(&mut self, emp: &Empire) {
+        let c = match &self.cmpgn {
+            Some(c) => c,
+            None => return,
+        };
+
+        let mut wind = window::Window::default()
+            .with_size(600, 400)
+            .with_label(format!("{} Intel", emp.name).as_str())
+            .center_screen();
+        let mut browse = SelectBrowser::default().with_pos(5, 5).with_size(590, 300);
+        browse.set_column_widths(&[40, 120, 430]);
+        browse.set_column_char('\t');
+        browse.add("Turn\tKind\tText");
+        match c.intel(emp.id).await {
+            Ok(v) => {
+                for i in v {
+                    browse.add_with_data(i.as_row().as_str(), i);
+                }
+            }
+            Err(e) => dialog::alert_default(e.as_str()),
+        }
+
+        let (s, r) = app::channel();
+
+        let button_y = 310; // Browser bottom plus spacing
+        button::Button::default()
+            .with_label("Add")
+            .with_pos(SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Add");
+        button::Button::default()
+            .with_label("Remove")
+            .with_pos(BTN_WIDTH + 2 * SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Remove");
+        button::Button::default()
+            .with_label("Known Classes")
+            .with_pos(SPACING + 2 * (BTN_WIDTH + SPACING), button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Classes");
+
+        wind.end();
+        wind.show();
+
+        while wind.shown() && app::wait() {
+            if let Some(m) = r.recv() {
+                match m {
+                    "Classes" => self.show_identifications(emp).await,
+                    "Add" => {
+                        let kind = match dialog::choice2_default(
+                            "What kind of intel is this?",
+                            intel::KINDS[0],
+                            intel::KINDS[1],
+                            intel::KINDS[2],
+                        ) {
+                            Some(k) => intel::KINDS[k as usize],
+                            None => continue,
+                        };
+                        if let Some(text) = dialog::input_default("Intel text", "") {
+                            if text.is_empty() {
+                                continue;
+                            }
+                            let c = self.cmpgn.as_ref().unwrap();
+                            match c.add_intel(emp.id, kind, text.as_str()).await {
+                                Ok(i) => browse.add_with_data(i.as_row().as_str(), i),
+                                Err(e) => dialog::alert_default(e.as_str()),
+                            }
+                        }
+                    }
+                    "Remove" => {
+                        let sel = browse.value();
+                        if sel > 1 {
+                            // Ignore header, so only remove if 2+
+                            unsafe {
+                                if let Some(item) = browse.data::<intel::IntelItem>(sel) {
+                                    let c = self.cmpgn.as_ref().unwrap();
+                                    match c.delete_intel(&item).await {
+                                        Ok(_) => browse.remove(sel),
+                                        Err(e) => dialog::alert_default(e.as_str()),
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    _ => (),
+                }
+            }
+        }
+    }
+
+    // Show the enemy ship classes an empire has identified, letting the GM
+    // record more found by espionage.
+    async fn show_identifications(&mut self, emp: &Empire) {
+        if self.cmpgn.is_none() {
+            return;
+        }
+
+        let mut wind = window::Window::default()
+            .with_size(600, 400)
+            .with_label(format!("{} Known Classes", emp.name).as_str())
+            .center_screen();
+        let mut browse = SelectBrowser::default().with_pos(5, 5).with_size(590, 300);
+        browse.set_column_widths(&[40, 150, 250, 150]);
+        browse.set_column_char('\t');
+        Self::fill_identification_browser(&mut browse, self.cmpgn.as_ref().unwrap(), emp).await;
+
+        let (s, r) = app::channel();
+
+        let button_y = 310; // Browser bottom plus spacing
+        button::Button::default()
+            .with_label("Identify")
+            .with_pos(SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Identify");
+        button::Button::default()
+            .with_label("Forget")
+            .with_pos(BTN_WIDTH + 2 * SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Forget");
+
+        wind.end();
+        wind.show();
+
+        while wind.shown() && app::wait() {
+            if let Some(m) = r.recv() {
+                match m {
+                    "Identify" => {
+                        let c = self.cmpgn.as_ref().unwrap();
+                        let known: Vec<i64> = match c.identifications(emp.id).await {
+                            Ok(v) => v.iter().map(|i| i.stype).collect(),
+                            Err(e) => {
+                                dialog::alert_default(e.as_str());
+                                continue;
+                            }
+                        };
+                        let classes: Vec<ShipType> = match c.ship_types().await {
+                            Ok(v) => v
+                                .into_iter()
+                                .filter(|t| t.empire != emp.id && !known.contains(&t.id))
+                                .collect(),
+                            Err(e) => {
+                                dialog::alert_default(e.as_str());
+                                continue;
+                            }
+                        };
+                        if classes.is_empty() {
+                            dialog::alert_default("Every enemy class is already known.");
+                            continue;
+                        }
+                        let names: Vec<String> = classes
+                            .iter()
+                            .map(|t| format!("{} {}", t.empire_name, t.class))
+                            .collect();
+                        if let Some(i) = self.choose("Identify Class", &names) {
+                            let c = self.cmpgn.as_ref().unwrap();
+                            if let Err(e) = c.identify_class(emp.id, &classes[i]).await {
+                                dialog::alert_default(e.as_str());
+                            }
+                        }
+                    }
+                    "Forget" => {
+                        let sel = browse.value();
+                        if sel > 1 {
+                            // Ignore header, so only forget if 2+
+                            unsafe {
+                                if let Some(item) = browse.data::<Identification>(sel) {
+                                    let c = self.cmpgn.as_ref().unwrap();
+                                    if let Err(e) = c.delete_identification(&item).await {
+                                        dialog::alert_default(e.as_str());
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    _ => (),
+                }
+                Self::fill_identification_browser(&mut browse, self.cmpgn.as_ref().unwrap(), emp)
+                    .await;
+            }
+        }
+    }
+
+    // Fill the identification browser with the classes an empire knows.
+    async fn fill_identification_browser(browse: &mut SelectBrowser, c: &Campaign, emp: &Empire) {
+        browse.clear();
+        browse.add("Turn\tEmpire\tClass\tSource");
+        match c.identifications(emp.id).await {
+            Ok(v) => {
+                for i in v {
+                    browse.add_with_data(i.as_row().as_str(), i);
+                }
+            }
+            Err(e) => dialog::alert_default(e.as_str()),
+        }
+    }
+
+    // Fill the order browser with an empire's orders for the turn, grouped
+    // by phase with each phase's cost, and show their total against the
+    // empire's treasury.
+    async fn fill_order_browser(
+        browse: &mut SelectBrowser,
+        budget: &mut frame::Frame,
+        c: &Campaign,
+        emp: &Empire,
+    ) {
+        browse.clear();
+        browse.add("Phase\tOrder\tEP");
+
+        let v = match c.orders(emp.id).await {
+            Ok(v) => v,
+            Err(e) => {
+                dialog::alert_default(e.as_str());
+                return;
+            }
+        };
+        for group in orders::by_phase(&v) {
+            browse.add(format!("@b{}\t\t@b{}", group.phase, group.cost()).as_str());
+            for o in group.orders {
+                let cost = if o.cost == 0 {
+                    String::new()
+                } else {
+                    o.cost.to_string()
+                };
+                browse.add_with_data(format!("\t{}\t{}", o.text, cost).as_str(), o);
+            }
+        }
+        // The treasury may have changed since the window was opened.
+        let treasury = match c.empires().await {
+            Ok(e) => e
+                .iter()
+                .find(|e| e.id == emp.id)
+                .map_or(emp.treasury, |e| e.treasury),
+            Err(_) => emp.treasury,
+        };
+        budget.set_label(orders::budget(&v, treasury).as_str());
+    }
+
+    // Enter an order for a phase with that phase's editor. Returns None if
+    // canceled.
+    async fn phase_order(&mut self, emp: &Empire, phase: &str) -> Result<Option<Order>, String> {
+        let c = self.cmpgn.as_ref().unwrap();
+        let owned = c.systems_by_owner(emp.id).await?;
+        match phase {
+            orders::ECONOMIC => {
+                let mut targets = vec!["Research".to_string()];
+                targets.extend(owned.iter().map(|s| format!("Develop {}", s.name)));
+                let target = match self.choose("Spend EP On", &targets) {
+                    Some(i) => i,
+                    None => return Ok(None),
+                };
+                let amount = match dialog::input_default("EP to spend", "") {
+                    Some(a) => a.trim().parse().unwrap_or(0),
+                    None => return Ok(None),
+                };
+                let sys = target.checked_sub(1).map(|i| &owned[i]);
+                let c = self.cmpgn.as_ref().unwrap();
+                c.economic_order(emp.id, sys, amount).map(Some)
+            }
+            orders::CONSTRUCTION => {
+                // Work on a ship at the shipyard where it is, or build new ones.
+                let mut kinds = vec!["Build Ships".to_string()];
+                kinds.extend(shipyard::ACTIONS.iter().map(|a| format!("{} a Ship", a)));
+                let action = match self.choose("Construction", &kinds) {
+                    Some(0) => None,
+                    Some(i) => Some(shipyard::ACTIONS[i - 1]),
+                    None => return Ok(None),
+                };
+                let c = self.cmpgn.as_ref().unwrap();
+                if let Some(action) = action {
+                    let ships = c.empire_ships(emp.id).await?;
+                    if ships.is_empty() {
+                        return Err(format!("{} has no ships", emp.name));
+                    }
+                    let names: Vec<String> = ships
+                        .iter()
+                        .map(|s| format!("{} #{} at {}", s.class, s.id, s.location_name))
+                        .collect();
+                    let ship = match self.choose("Ship", &names) {
+                        Some(i) => &ships[i],
+                        None => return Ok(None),
+                    };
+                    let c = self.cmpgn.as_ref().unwrap();
+                    return c.shipyard_order(emp.id, ship, action).await.map(Some);
+                }
+                let classes: Vec<ShipType> = c
+                    .ship_types()
+                    .await?
+                    .into_iter()
+                    .filter(|t| t.empire == emp.id && !t.retired)
+                    .collect();
+                if owned.is_empty() || classes.is_empty() {
+                    return Err(format!("{} has no systems or classes to build", emp.name));
+                }
+                let names: Vec<String> = owned.iter().map(|s| s.name.clone()).collect();
+                let sys = match self.choose("Build At", &names) {
+                    Some(i) => &owned[i],
+                    None => return Ok(None),
+                };
+                let names: Vec<String> = classes
+                    .iter()
+                    .map(|t| format!("{} ({} EP)", t.class, t.cost))
+                    .collect();
+                let class = match self.choose("Ship Class", &names) {
+                    Some(i) => &classes[i],
+                    None => return Ok(None),
+                };
+                let count = match dialog::input_default("Number to build", "1") {
+                    Some(n) => n.trim().parse().unwrap_or(0),
+                    None => return Ok(None),
+                };
+                let c = self.cmpgn.as_ref().unwrap();
+                c.construction_order(emp.id, sys, class, count).map(Some)
+            }
+            orders::MOVEMENT => {
+                let fleets: Vec<FleetSummary> = c
+                    .fleet_summaries()
+                    .await?
+                    .into_iter()
+                    .filter(|f| f.owner == emp.id)
+                    .collect();
+                if fleets.is_empty() {
+                    return Err(format!("{} has no fleets", emp.name));
+                }
+                let systems = c.systems().await?;
+                let lanes = c.lanes().await?;
+                let names: Vec<String> = fleets
+                    .iter()
+                    .map(|f| format!("{} at {}", f.name, f.location_name))
+                    .collect();
+                let fleet = match self.choose("Fleet", &names) {
+                    Some(i) => &fleets[i],
+                    None => return Ok(None),
+                };
+                // A destination is reached by the shortest way, over as many
+                // turns as it takes.
+                let others: Vec<&System> =
+                    systems.iter().filter(|s| s.id != fleet.location).collect();
+                let mut names = vec!["(Plot jump by jump)".to_string()];
+                names.extend(others.iter().map(|s| s.name.clone()));
+                match self.choose("Destination", &names) {
+                    Some(0) => (),
+                    Some(i) => {
+                        let c = self.cmpgn.as_ref().unwrap();
+                        return c
+                            .destination_order(emp.id, fleet, others[i - 1])
+                            .await
+                            .map(Some);
+                    }
+                    None => return Ok(None),
+                }
+                // Plot the route a jump at a time along the lanes.
+                let mut route: Vec<System> = Vec::new();
+                let mut at = fleet.location;
+                for jump in 1..=fleet.jumps {
+                    let next: Vec<&System> = systems
+                        .iter()
+                        .filter(|s| lanes.iter().any(|l| l.other_end(at) == Some(s.id)))
+                        .collect();
+                    let mut names = Vec::new();
+                    if !route.is_empty() {
+                        names.push("(Stop here)".to_string());
+                    }
+                    names.extend(next.iter().map(|s| s.name.clone()));
+                    let title = format!("Jump {} of {}", jump, fleet.jumps);
+                    let i = match self.choose(title.as_str(), &names) {
+                        Some(i) => i,
+                        None => return Ok(None),
+                    };
+                    if !route.is_empty() {
+                        if i == 0 {
+                            break;
+                        }
+                        route.push(next[i - 1].clone());
+                    } else {
+                        route.push(next[i].clone());
+                    }
+                    at = route.last().unwrap().id;
+                }
+                let c = self.cmpgn.as_ref().unwrap();
+                c.movement_order(emp.id, fleet, &route).await.map(Some)
+            }
+            _ => {
+                let others: Vec<Empire> = c
+                    .empires()
+                    .await?
+                    .into_iter()
+                    .filter(|e| e.id != emp.id)
+                    .collect();
+                let names: Vec<String> = others.iter().map(|e| e.name.clone()).collect();
+                let other = match self.choose("Propose To", &names) {
+                    Some(i) => &others[i],
+                    None => return Ok(None),
+                };
+                let types: Vec<String> = diplomacy::TREATY_TYPES
+                    .iter()
+                    .map(|t| t.to_string())
+                    .collect();
+                let ttype = match self.choose("Treaty", &types) {
+                    Some(i) => diplomacy::TREATY_TYPES[i],
+                    None => return Ok(None),
+                };
+                let c = self.cmpgn.as_ref().unwrap();
+                c.diplomacy_order(emp.id, other, ttype).await.map(Some)
+            }
+        }
+    }
+
+    // Show the orders an empire has given for the current turn, grouped by
+    // phase.
+    async fn show_orders(&mut self, emp: &Empire) {
+        if self.cmpgn.is_none() {
+            return;
+        }
+
+        let mut wind = window::Window::default()
+            .with_size(600, 435)
+            .with_label(format!("{} Orders", emp.name).as_str())
+            .center_screen();
+        let mut browse = SelectBrowser::default().with_pos(5, 5).with_size(590, 275);
+        browse.set_column_widths(&[100, 430, 50]);
+        browse.set_column_char('\t');
+        let mut budget = frame::Frame::default()
+            .with_pos(5, 282)
+            .with_size(590, 24)
+            .with_align(Align::Left | Align::Inside);
+        Self::fill_order_browser(&mut browse, &mut budget, self.cmpgn.as_ref().unwrap(), emp).await;
+
+        let (s, r) = app::channel();
+
+        // One editor for each phase, then one for free-form orders.
+        let button_y = 310; // Browser bottom plus spacing
+        for (n, phase) in orders::PHASES
+            .iter()
+            .chain([orders::OTHER].iter())
+            .enumerate()
+        {
+            button::Button::default()
+                .with_label(phase)
+                .with_pos(SPACING + n as i32 * (BTN_WIDTH + SPACING), button_y)
+                .with_size(BTN_WIDTH, BTN_HEIGHT)
+                .emit(s, *phase);
+        }
+        let button_y = button_y + BTN_HEIGHT + SPACING;
+        button::Button::default()
+            .with_label("Remove")
+            .with_pos(SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Remove");
+        button::Button::default()
+            .with_label("Repeat Last")
+            .with_pos(BTN_WIDTH + 2 * SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Repeat");
+        button::Button::default()
+            .with_label("Use Template")
+            .with_pos(SPACING + 2 * (BTN_WIDTH + SPACING), button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Apply");
+        button::Button::default()
+            .with_label("Save Template")
+            .with_pos(SPACING + 3 * (BTN_WIDTH + SPACING), button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Save");
+        button::Button::default()
+            .with_label("Drop Template")
+            .with_pos(SPACING + 4 * (BTN_WIDTH + SPACING), button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Drop");
+        let button_y = button_y + BTN_HEIGHT + SPACING;
+        button::Button::default()
+            .with_label("Import...")
+            .with_pos(SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Import");
+        button::Button::default()
+            .with_label("Validate")
+            .with_pos(BTN_WIDTH + 2 * SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Validate");
+
+        wind.end();
+        wind.show();
+
+        while wind.shown() && app::wait() {
+            if let Some(m) = r.recv() {
+                match m {
+                    orders::OTHER => {
+                        if let Some(text) = dialog::input_default("Order", "") {
+                            if text.is_empty() {
+                                continue;
+                            }
+                            if let Err(e) = self
+                                .cmpgn
+                                .as_ref()
+                                .unwrap()
+                                .add_order(emp.id, text.as_str())
+                                .await
+                            {
+                                dialog::alert_default(e.as_str());
+                            }
+                        }
+                    }
+                    orders::ECONOMIC
+                    | orders::CONSTRUCTION
+                    | orders::MOVEMENT
+                    | orders::DIPLOMACY => match self.phase_order(emp, m).await {
+                        Ok(Some(o)) => {
+                            if let Err(e) = self.cmpgn.as_ref().unwrap().add_phase_order(&o).await {
+                                dialog::alert_default(e.as_str());
+                            }
+                        }
+                        Ok(None) => (),
+                        Err(e) => dialog::alert_default(e.as_str()),
+                    },
+                    "Remove" => {
+                        let sel = browse.value();
+                        if sel > 1 {
+                            // Ignore header and phase rows, which have no order
+                            unsafe {
+                                if let Some(o) = browse.data::<Order>(sel) {
+                                    if let Err(e) =
+                                        self.cmpgn.as_ref().unwrap().delete_order(&o).await
+                                    {
+                                        dialog::alert_default(e.as_str());
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    "Import" => {
+                        let file = match dialog::file_chooser(
+                            "Import orders from...",
+                            "*.{txt,tsv}",
+                            ".",
+                            true,
+                        ) {
+                            Some(f) => f,
+                            None => continue,
+                        };
+                        let c = self.cmpgn.as_ref().unwrap();
+                        match c.import_orders(emp.id, file.as_str()).await {
+                            Ok(report) => show_report("Order Validation", &report),
+                            Err(e) => dialog::alert_default(e.as_str()),
+                        }
+                    }
+                    "Validate" => {
+                        match self.cmpgn.as_ref().unwrap().validate_orders(emp.id).await {
+                            Ok(report) => show_report("Order Validation", &report),
+                            Err(e) => dialog::alert_default(e.as_str()),
+                        }
+                    }
+                    "Repeat" => {
+                        if let Err(e) = self
+                            .cmpgn
+                            .as_ref()
+                            .unwrap()
+                            .repeat_last_orders(emp.id)
+                            .await
+                        {
+                            dialog::alert_default(e.as_str());
+                        }
+                    }
+                    "Apply" | "Drop" => {
+                        let templates =
+                            match self.cmpgn.as_ref().unwrap().order_templates(emp.id).await {
+                                Ok(v) => v,
+                                Err(e) => {
+                                    dialog::alert_default(e.as_str());
+                                    continue;
+                                }
+                            };
+                        if templates.is_empty() {
+                            dialog::alert_default("No order templates have been saved.");
+                            continue;
+                        }
+                        let names: Vec<String> = templates.iter().map(|t| t.name.clone()).collect();
+                        let i = match self.choose("Order Template", &names) {
+                            Some(i) => i,
+                            None => continue,
+                        };
+                        let c = self.cmpgn.as_ref().unwrap();
+                        if m == "Apply" {
+                            if let Err(e) = c.apply_order_template(&templates[i]).await {
+                                dialog::alert_default(e.as_str());
+                            }
+                        } else if let Err(e) = c.delete_order_template(&templates[i]).await {
+                            dialog::alert_default(e.as_str());
+                        }
+                    }
+                    "Save" => {
+                        if let Some(name) = dialog::input_default("Template name", "") {
+                            if name.is_empty() {
+                                continue;
+                            }
+                            if let Err(e) = self
+                                .cmpgn
+                                .as_ref()
+                                .unwrap()
+                                .save_order_template(emp.id, name.as_str())
+                                .await
+                            {
+                                dialog::alert_default(e.as_str());
+                            }
+                        }
+                    }
+                    _ => (),
+                }
+                Self::fill_order_browser(
+                    &mut browse,
+                    &mut budget,
+                    self.cmpgn.as_ref().unwrap(),
+                    emp,
+                )
+                .await;
+            }
+        }
+    }
+
+    // Show the searchable rules reference docked beside the main window.
+    async fn show_rules_reference(&mut self) {
+        let entries = match &self.cmpgn {
+            Some(c) => match c.rules_reference().await {
+                Ok(v) => v,
+                Err(e) => {
+                    dialog::alert_default(e.as_str());
+                    return;
+                }
+            },
+            None => return,
+        };
+
+        let total_width = 500;
+        let full_width = total_width - 2 * SPACING;
+        let mut wind = window::Window::default()
+            .with_size(total_width, self.main_win.h())
+            .with_pos(self.main_win.x() + self.main_win.w(), self.main_win.y())
+            .with_label("Rules Reference");
+        let mut search = input::Input::default()
+            .with_label("Find")
+            .with_pos(40, SPACING)
+            .with_size(total_width - 40 - SPACING, TEXT_HEIGHT);
+        search.set_trigger(CallbackTrigger::Changed);
+        let mut browse = browser::HoldBrowser::default()
+            .with_pos(SPACING, 2 * SPACING + TEXT_HEIGHT)
+            .with_size(full_width, self.main_win.h() - TEXT_HEIGHT - 3 * SPACING);
+        browse.set_column_widths(&[120, 110, 250]);
+        browse.set_column_char('\t');
+        wind.end();
+        wind.show();
+
+        let (s, r) = app::channel();
+        search.emit(s, "Search");
+
+        let mut filter = String::new();
+        loop {
+            browse.clear();
+            browse.add("Section\tTopic\tSummary");
+            for e in entries.iter().filter(|e| e.matches(filter.as_str())) {
+                browse.add(e.as_row().as_str());
+            }
+
+            // Wait for the search text to change.
+            loop {
+                if !(wind.shown() && app::wait()) {
+                    return;
+                }
+                if let Some("Search") = r.recv() {
+                    filter = search.value();
+                    break;
+                }
+            }
+        }
+    }
+
+    // Show the ship classes of all empires.
+    async fn show_ship_types(&mut self) {
+        if self.cmpgn.is_none() {
+            return;
+        }
+
+        let mut wind = window::Window::default()
+            .with_size(600, 400)
+            .with_label("Ship Classes")
+            .center_screen();
+        let mut browse = SelectBrowser::default().with_pos(5, 5).with_size(590, 300);
+        browse.set_column_widths(&[80, 100, 90, 35, 35, 35, 35, 35, 40, 40, 70]);
+        browse.set_column_char('\t');
+        Self::fill_ship_type_browser(&mut browse, self.cmpgn.as_ref().unwrap()).await;
+
+        let (s, r) = app::channel();
+
+        let button_y = 310; // Browser bottom plus spacing
+        button::Button::default()
+            .with_label("New")
+            .with_pos(SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "New");
+        button::Button::default()
+            .with_label("Edit")
+            .with_pos(BTN_WIDTH + 2 * SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Edit");
+        button::Button::default()
+            .with_label("Delete")
+            .with_pos(SPACING + 2 * (BTN_WIDTH + SPACING), button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Delete");
+        button::Button::default()
+            .with_label("Retire")
+            .with_pos(SPACING + 3 * (BTN_WIDTH + SPACING), button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Retire");
+        button::Button::default()
+            .with_label("Transfer")
+            .with_pos(SPACING + 4 * (BTN_WIDTH + SPACING), button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Transfer");
+
+        wind.end();
+        wind.show();
+
+        while wind.shown() && app::wait() {
+            if let Some(m) = r.recv() {
+                let sel = browse.value();
+                // Ignore header, so only act on a selection if 2+
+                let stype = if sel > 1 {
+                    unsafe { browse.data::<ShipType>(sel) }
+                } else {
+                    None
+                };
+                match m {
+                    "New" => {
+                        // Keep editing until the class is valid or canceled.
+                        let mut next = self.edit_ship_type(ShipType::new("", "", 0)).await;
+                        while let Some(t) = next {
+                            match self.cmpgn.as_ref().unwrap().add_ship_type(&t).await {
+                                Ok(_) => break,
+                                Err(e) => dialog::alert_default(e.as_str()),
+                            }
+                            next = self.edit_ship_type(t).await;
+                        }
+                    }
+                    "Edit" => {
+                        if let Some(t) = stype {
+                            let mut next = self.edit_ship_type(t).await;
+                            while let Some(t) = next {
+                                match self.cmpgn.as_ref().unwrap().update_ship_type(&t).await {
+                                    Ok(_) => break,
+                                    Err(e) => dialog::alert_default(e.as_str()),
+                                }
+                                next = self.edit_ship_type(t).await;
+                            }
+                        }
+                    }
+                    "Delete" => {
+                        if let Some(t) = stype {
+                            let prompt = format!("Delete the {} class?", t.class);
+                            if dialog::choice2_default(prompt.as_str(), "Cancel", "Delete", "")
+                                == Some(1)
+                            {
+                                if let Err(e) =
+                                    self.cmpgn.as_ref().unwrap().delete_ship_type(&t).await
+                                {
+                                    dialog::alert_default(e.as_str());
+                                }
+                            }
+                        }
+                    }
+                    "Retire" => {
+                        if let Some(t) = stype {
+                            let prompt = if t.retired {
+                                format!("Return the {} class to production?", t.class)
+                            } else {
+                                format!(
+                                    "Retire the {} class? Its {} active ships stay in service, \
+                                    but no more can be built.",
+                                    t.class, t.active
+                                )
+                            };
+                            if dialog::choice2_default(prompt.as_str(), "Cancel", "Yes", "")
+                                == Some(1)
+                            {
+                                if let Err(e) = self
+                                    .cmpgn
+                                    .as_ref()
+                                    .unwrap()
+                                    .retire_ship_type(&t, !t.retired)
+                                    .await
+                                {
+                                    dialog::alert_default(e.as_str());
+                                }
+                            }
+                        }
+                    }
+                    "Transfer" => {
+                        if let Some(t) = stype {
+                            self.transfer_class(&t).await;
+                        }
+                    }
+                    _ => (),
+                }
+                Self::fill_ship_type_browser(&mut browse, self.cmpgn.as_ref().unwrap()).await;
+                browse.select(sel);
+            }
+        }
+    }
+
+    // Show the construction queue of all empires.
+    async fn show_construction(&mut self) {
+        if self.cmpgn.is_none() {
+            return;
+        }
+
+        let mut wind = window::Window::default()
+            .with_size(600, 560)
+            .with_label("Construction")
+            .center_screen();
+        let mut browse = SelectBrowser::default().with_pos(5, 5).with_size(590, 300);
+        browse.set_column_widths(&[110, 130, 70, 170, 50, 50]);
+        browse.set_column_char('\t');
+        Self::fill_build_browser(&mut browse, self.cmpgn.as_ref().unwrap()).await;
+        // Each empire's production, kept up to date as the queue changes.
+        let mut summary = browser::HoldBrowser::default()
+            .with_pos(5, 310 + BTN_HEIGHT + SPACING)
+            .with_size(590, 560 - 315 - BTN_HEIGHT - SPACING);
+        summary.set_column_widths(&[110, 130, 70, 60, 220]);
+        summary.set_column_char('\t');
+        Self::fill_production_browser(&mut summary, self.cmpgn.as_ref().unwrap()).await;
+
+        let (s, r) = app::channel();
+
+        let button_y = 310; // Browser bottom plus spacing
+        button::Button::default()
+            .with_label("Build")
+            .with_pos(SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Build");
+        button::Button::default()
+            .with_label("Cancel Build")
+            .with_pos(BTN_WIDTH + 2 * SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Cancel");
+        button::Button::default()
+            .with_label("Batch Build")
+            .with_pos(2 * BTN_WIDTH + 3 * SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Batch");
+        button::Button::default()
+            .with_label("Repairs")
+            .with_pos(SPACING + 3 * (BTN_WIDTH + SPACING), button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Repairs");
+
+        wind.end();
+        wind.show();
+
+        while wind.shown() && app::wait() {
+            if let Some(m) = r.recv() {
+                match m {
+                    "Build" => self.queue_build().await,
+                    "Repairs" => self.show_repairs().await,
+                    "Batch" => {
+                        if let Some(line) =
+                            dialog::input_default("Ships to build (6 x Frigate at Senor Prime)", "")
+                        {
+                            if let Err(e) = self
+                                .cmpgn
+                                .as_ref()
+                                .unwrap()
+                                .queue_batch(line.as_str())
+                                .await
+                            {
+                                dialog::alert_default(e.as_str());
+                            }
+                        }
+                    }
+                    "Cancel" => {
+                        let sel = browse.value();
+                        if sel > 1 {
+                            // Ignore header, so only cancel if 2+
+                            unsafe {
+                                if let Some(b) = browse.data::<construction::Build>(sel) {
+                                    let prompt = format!(
+                                        "Cancel {} at {}? {} EP will be refunded.",
+                                        b.item_name(),
+                                        b.system_name,
+                                        b.cost
+                                    );
+                                    if dialog::choice2_default(prompt.as_str(), "No", "Yes", "")
+                                        == Some(1)
+                                    {
+                                        if let Err(e) =
+                                            self.cmpgn.as_ref().unwrap().cancel_build(&b).await
+                                        {
+                                            dialog::alert_default(e.as_str());
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    _ => (),
+                }
+                Self::fill_build_browser(&mut browse, self.cmpgn.as_ref().unwrap()).await;
+                Self::fill_production_browser(&mut summary, self.cmpgn.as_ref().unwrap()).await;
+            }
+        }
+    }
+
+    // Fill the repair browser with the crippled ships waiting for repair and
+    // whether this turn's capacity will reach them.
+    async fn fill_repair_browser(browse: &mut SelectBrowser, c: &Campaign) {
+        browse.clear();
+        browse.add("Priority\tEmpire\tClass\tShip\tSystem\tNeeds\tOutcome");
+        match c.repair_plan().await {
+            Ok(plan) => {
+                for j in plan.repaired {
+                    browse.add_with_data(format!("{}\tRepair", j.as_row()).as_str(), j);
+                }
+                for j in plan.unrepaired {
+                    browse.add_with_data(format!("{}\tWaits", j.as_row()).as_str(), j);
+                }
+            }
+            Err(e) => dialog::alert_default(e.as_str()),
+        }
+    }
+
+    // Show the crippled ships waiting for repair, and let their repair
+    // priorities be set.
+    async fn show_repairs(&mut self) {
+        let mut wind = window::Window::default()
+            .with_size(600, 400)
+            .with_label("Repairs")
+            .center_screen();
+        let mut browse = SelectBrowser::default().with_pos(5, 5).with_size(590, 300);
+        browse.set_column_widths(&[60, 110, 110, 50, 130, 50, 60]);
+        browse.set_column_char('\t');
+        Self::fill_repair_browser(&mut browse, self.cmpgn.as_ref().unwrap()).await;
+
+        let (s, r) = app::channel();
+
+        let button_y = 310; // Browser bottom plus spacing
+        button::Button::default()
+            .with_label("Set Priority")
+            .with_pos(SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Priority");
+
+        wind.end();
+        wind.show();
+
+        while wind.shown() && app::wait() {
+            if let Some(m) = r.recv() {
+                if m == "Priority" {
+                    let sel = browse.value();
+                    if sel > 1 {
+                        // Ignore header, so only set if 2+
+                        unsafe {
+                            if let Some(j) = browse.data::<RepairJob>(sel) {
+                                let prompt = format!(
+                                    "Repair priority of {} #{} (higher is repaired first)",
+                                    j.class, j.ship
+                                );
+                                if let Some(p) = dialog::input_default(
+                                    prompt.as_str(),
+                                    j.priority.to_string().as_str(),
+                                ) {
+                                    match p.trim().parse() {
+                                        Ok(p) => {
+                                            if let Err(e) = self
+                                                .cmpgn
+                                                .as_ref()
+                                                .unwrap()
+                                                .set_repair_priority(&j, p)
+                                                .await
+                                            {
+                                                dialog::alert_default(e.as_str());
+                                            }
+                                        }
+                                        Err(_) => dialog::alert_default("Enter a whole number."),
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+                Self::fill_repair_browser(&mut browse, self.cmpgn.as_ref().unwrap()).await;
+            }
+        }
+    }
+
+    // Ask what to build and where, then add it to the construction queue.
+    async fn queue_build(&mut self) {
+        let systems: Vec<System> = match self.cmpgn.as_ref().unwrap().systems().await {
+            Ok(v) => v.into_iter().filter(|s| s.owner != 0).collect(),
+            Err(e) => {
+                dialog::alert_default(e.as_str());
+                return;
+            }
+        };
+        if systems.is_empty() {
+            dialog::alert_default("No system has an owner to build there.");
+            return;
+        }
+        let names: Vec<String> = systems
+            .iter()
+            .map(|s| format!("{} ({})", s.name, s.owner_name))
+            .collect();
+        let sys = match self.choose("Build At", &names) {
+            Some(i) => &systems[i],
+            None => return,
+        };
+        let kind = match dialog::choice2_default(
+            "What will be built?",
+            construction::KINDS[0],
+            construction::KINDS[1],
+            construction::KINDS[2],
+        ) {
+            Some(k) => construction::KINDS[k as usize],
+            None => return,
+        };
+
+        let result = match kind {
+            "Ship" => {
+                let types: Vec<ShipType> = match self.cmpgn.as_ref().unwrap().ship_types().await {
+                    Ok(v) => v
+                        .into_iter()
+                        .filter(|t| t.empire == sys.owner && !t.retired)
+                        .collect(),
+                    Err(e) => {
+                        dialog::alert_default(e.as_str());
+                        return;
+                    }
+                };
+                if types.is_empty() {
+                    dialog::alert_default("The owner has no ship classes in production.");
+                    return;
+                }
+                let names: Vec<String> = types
+                    .iter()
+                    .map(|t| format!("{} ({} EP)", t.class, t.cost))
+                    .collect();
+                match self.choose("Ship Class", &names) {
+                    Some(i) => {
+                        self.cmpgn
+                            .as_ref()
+                            .unwrap()
+                            .queue_ship(sys, &types[i])
+                            .await
+                    }
+                    None => return,
+                }
+            }
+            "Ground" => {
+                let types = match self.cmpgn.as_ref().unwrap().ground_types().await {
+                    Ok(v) => v,
+                    Err(e) => {
+                        dialog::alert_default(e.as_str());
+                        return;
+                    }
+                };
+                let names: Vec<String> = types
+                    .iter()
+                    .map(|t| format!("{} ({} EP)", t.name, t.cost))
+                    .collect();
+                match self.choose("Ground Unit", &names) {
+                    Some(i) => {
+                        self.cmpgn
+                            .as_ref()
+                            .unwrap()
+                            .queue_ground(sys, &types[i])
+                            .await
+                    }
+                    None => return,
+                }
+            }
+            _ => match self.choose_facility() {
+                Some(kind) => self.cmpgn.as_ref().unwrap().queue_facility(sys, kind).await,
+                None => return,
+            },
+        };
+        if let Err(e) = result {
+            dialog::alert_default(e.as_str());
+        }
+    }
+
+    // Show the treaties between empires.
+    async fn show_treaties(&mut self) {
+        if self.cmpgn.is_none() {
+            return;
+        }
+
+        let mut wind = window::Window::default()
+            .with_size(600, 400)
+            .with_label("Treaties")
+            .center_screen();
+        let mut browse = SelectBrowser::default().with_pos(5, 5).with_size(590, 300);
+        browse.set_column_widths(&[140, 140, 120, 60, 60]);
+        browse.set_column_char('\t');
+        Self::fill_treaty_browser(&mut browse, self.cmpgn.as_ref().unwrap()).await;
+
+        let (s, r) = app::channel();
+
+        let button_y = 310; // Browser bottom plus spacing
+        button::Button::default()
+            .with_label("Sign")
+            .with_pos(SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Sign");
+        button::Button::default()
+            .with_label("End")
+            .with_pos(BTN_WIDTH + 2 * SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "End");
+        button::Button::default()
+            .with_label("Transfers")
+            .with_pos(SPACING + 2 * (BTN_WIDTH + SPACING), button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Transfers");
+
+        wind.end();
+        wind.show();
+
+        while wind.shown() && app::wait() {
+            if let Some(m) = r.recv() {
+                match m {
+                    "Sign" => self.sign_treaty().await,
+                    "Transfers" => self.show_transfers().await,
+                    "End" => {
+                        let sel = browse.value();
+                        if sel > 1 {
+                            // Ignore header, so only end if 2+
+                            unsafe {
+                                if let Some(t) = browse.data::<diplomacy::Treaty>(sel) {
+                                    let prompt = format!("End the {}?", t.describe());
+                                    if dialog::choice2_default(prompt.as_str(), "No", "Yes", "")
+                                        == Some(1)
+                                    {
+                                        if let Err(e) =
+                                            self.cmpgn.as_ref().unwrap().end_treaty(&t).await
+                                        {
+                                            dialog::alert_default(e.as_str());
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    _ => (),
+                }
+                Self::fill_treaty_browser(&mut browse, self.cmpgn.as_ref().unwrap()).await;
+            }
+        }
+    }
+
+    // Show the ship and class transfers agreed for the turn.
+    async fn show_transfers(&mut self) {
+        let mut wind = window::Window::default()
+            .with_size(600, 400)
+            .with_label("Transfers")
+            .center_screen();
+        let mut browse = SelectBrowser::default().with_pos(5, 5).with_size(590, 300);
+        browse.set_column_widths(&[60, 140, 140, 160]);
+        browse.set_column_char('\t');
+        Self::fill_transfer_browser(&mut browse, self.cmpgn.as_ref().unwrap()).await;
+
+        let (s, r) = app::channel();
+
+        let button_y = 310; // Browser bottom plus spacing
+        button::Button::default()
+            .with_label("Cancel Transfer")
+            .with_pos(SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Cancel");
+
+        wind.end();
+        wind.show();
+
+        while wind.shown() && app::wait() {
+            if let Some(m) = r.recv() {
+                if m == "Cancel" {
+                    let sel = browse.value();
+                    if sel > 1 {
+                        // Ignore header, so only cancel if 2+
+                        unsafe {
+                            if let Some(t) = browse.data::<Transfer>(sel) {
+                                if let Err(e) =
+                                    self.cmpgn.as_ref().unwrap().cancel_transfer(&t).await
+                                {
+                                    dialog::alert_default(e.as_str());
+                                }
+                            }
+                        }
+                    }
+                }
+                Self::fill_transfer_browser(&mut browse, self.cmpgn.as_ref().unwrap()).await;
+            }
+        }
+    }
+
+    // Agree a sale or gift of one of an empire's ship classes to another.
+    async fn transfer_class(&mut self, class: &ShipType) {
+        let (buyer, price) = match self.transfer_terms(class.empire).await {
+            Some(t) => t,
+            None => return,
+        };
+        match self
+            .cmpgn
+            .as_ref()
+            .unwrap()
+            .transfer_class(class, buyer.id, price)
+            .await
+        {
+            Ok(t) => dialog::message_default(
+                format!(
+                    "{} will go to {} in the diplomacy phase",
+                    t.item_name, buyer.name
+                )
+                .as_str(),
+            ),
+            Err(e) => dialog::alert_default(e.as_str()),
+        }
+    }
+
+    // Agree a sale or gift of one of an empire's ships to another.
+    async fn transfer_ship(&mut self, ship: &ShipLocation) {
+        let (buyer, price) = match self.transfer_terms(ship.owner).await {
+            Some(t) => t,
+            None => return,
+        };
+        match self
+            .cmpgn
+            .as_ref()
+            .unwrap()
+            .transfer_ship(ship, buyer.id, price)
+            .await
+        {
+            Ok(t) => dialog::message_default(
+                format!(
+                    "{} will go to {} in the diplomacy phase",
+                    t.item_name, buyer.name
+                )
+                .as_str(),
+            ),
+            Err(e) => dialog::alert_default(e.as_str()),
+        }
+    }
+
+    // Ask which empire a ship or class goes to, and for what price. A price
+    // of zero makes it a gift.
+    async fn transfer_terms(&mut self, seller: i64) -> Option<(Empire, i32)> {
+        let empires: Vec<Empire> = match self.cmpgn.as_ref()?.empires().await {
+            Ok(v) => v.into_iter().filter(|e| e.id != seller).collect(),
+            Err(e) => {
+                dialog::alert_default(e.as_str());
+                return None;
+            }
+        };
+        let names: Vec<String> = empires.iter().map(|e| e.name.clone()).collect();
+        let buyer = empires.get(self.choose("Transfer To", &names)?)?.clone();
+        let price = dialog::input_default("Price in EP, or 0 for a gift", "0")?;
+        match price.trim().parse() {
+            Ok(p) => Some((buyer, p)),
+            Err(_) => {
+                dialog::alert_default("The price must be a whole number of EP");
+                None
+            }
+        }
+    }
+
+    // Ask for the terms of a new treaty and sign it.
+    async fn sign_treaty(&mut self) {
+        let c = self.cmpgn.as_ref().unwrap();
+        let empires = match c.empires().await {
+            Ok(v) => v,
+            Err(e) => {
+                dialog::alert_default(e.as_str());
+                return;
+            }
+        };
+        let turn = c.turn();
+
+        let total_width = 300;
+        let total_height = 200;
+        let label_width = 80;
+        let input_width = total_width - label_width - 2 * SPACING;
+        let row = |n: i32| SPACING + n * (TEXT_HEIGHT + SPACING);
+
+        let mut wind = window::Window::default()
+            .with_size(total_width, total_height)
+            .center_screen()
+            .with_label("Sign Treaty");
+
+        let names: Vec<String> = empires.iter().map(|e| e.name.replace('/', "\\/")).collect();
+        let mut a_choice = menu::Choice::default()
+            .with_label("Between")
+            .with_pos(label_width + SPACING, row(0))
+            .with_size(input_width, TEXT_HEIGHT);
+        a_choice.add_choice(names.join("|").as_str());
+        a_choice.set_value(0);
+        let mut b_choice = menu::Choice::default()
+            .with_label("And")
+            .with_pos(label_width + SPACING, row(1))
+            .with_size(input_width, TEXT_HEIGHT);
+        b_choice.add_choice(names.join("|").as_str());
+        b_choice.set_value(1);
+        let mut type_choice = menu::Choice::default()
+            .with_label("Type")
+            .with_pos(label_width + SPACING, row(2))
+            .with_size(input_width, TEXT_HEIGHT);
+        type_choice.add_choice(diplomacy::TREATY_TYPES.join("|").as_str());
+        type_choice.set_value(0);
+        let mut term_choice = menu::Choice::default()
+            .with_label("Term")
+            .with_pos(label_width + SPACING, row(3))
+            .with_size(input_width / 2, TEXT_HEIGHT);
+        term_choice.add_choice("Indefinite|For turns|Until turn");
+        term_choice.set_value(0);
+        let mut term_input = input::IntInput::default()
+            .with_pos(label_width + SPACING + input_width / 2, row(3))
+            .with_size(input_width / 2, TEXT_HEIGHT);
+        term_input.set_value("0");
+
+        let button_y = total_height - BTN_HEIGHT - SPACING;
+        let mut ok = button::Button::default()
+            .with_label("Ok")
+            .with_pos(SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT);
+        let mut cancel = button::Button::default()
+            .with_label("Cancel")
+            .with_pos(BTN_WIDTH + 2 * SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT);
+
+        wind.end();
+        wind.make_modal(true);
+        wind.show();
+
+        let (s, r) = app::channel();
+        ok.emit(s, true);
+        cancel.emit(s, false);
+
+        let mut is_ok = false;
+        while wind.shown() && self.app.wait() {
+            if let Some(a) = r.recv() {
+                is_ok = a;
+                wind.hide();
+            }
+        }
+
+        if !is_ok {
+            return;
+        }
+        let (a, b) = match (
+            empires.get(a_choice.value() as usize),
+            empires.get(b_choice.value() as usize),
+        ) {
+            (Some(a), Some(b)) => (a.id, b.id),
+            _ => return,
+        };
+        let ttype = match diplomacy::TREATY_TYPES.get(type_choice.value() as usize) {
+            Some(t) => t,
+            None => return,
+        };
+        let term: i32 = term_input.value().parse().unwrap_or(0);
+        let expires = match term_choice.value() {
+            1 => diplomacy::expiry(turn, term),
+            2 => Some(term),
+            _ => None,
+        };
+        if let Err(e) = self
+            .cmpgn
+            .as_ref()
+            .unwrap()
+            .sign_treaty(a, b, ttype, expires)
+            .await
+        {
+            dialog::alert_default(e.as_str());
+        }
+    }
+
+    // Show all fleets with their condition.
+    async fn show_fleets(&mut self) {
+        if self.cmpgn.is_none() {
+            return;
+        }
+
+        let mut wind = window::Window::default()
+            .with_size(640, 400)
+            .with_label("Fleets")
+            .center_screen();
+        let mut browse = SelectBrowser::default().with_pos(5, 5).with_size(630, 300);
+        browse.set_column_widths(&[120, 100, 100, 40, 60, 40, 40, 40, 40]);
+        browse.set_column_char('\t');
+        Self::fill_fleet_browser(&mut browse, self.cmpgn.as_ref().unwrap()).await;
+
+        let (s, r) = app::channel();
+
+        let button_y = 310; // Browser bottom plus spacing
+        button::Button::default()
+            .with_label("New")
+            .with_pos(SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "New");
+        button::Button::default()
+            .with_label("Edit")
+            .with_pos(BTN_WIDTH + 2 * SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Edit");
+        button::Button::default()
+            .with_label("Merge")
+            .with_pos(SPACING + 2 * (BTN_WIDTH + SPACING), button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Merge");
+        button::Button::default()
+            .with_label("Split")
+            .with_pos(SPACING + 3 * (BTN_WIDTH + SPACING), button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Split");
+        button::Button::default()
+            .with_label("Disband")
+            .with_pos(SPACING + 4 * (BTN_WIDTH + SPACING), button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Disband");
+        let button_y = button_y + BTN_HEIGHT + SPACING;
+        button::Button::default()
+            .with_label("Refresh")
+            .with_pos(SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Refresh");
+        button::Button::default()
+            .with_label("Find Class")
+            .with_pos(BTN_WIDTH + 2 * SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Find");
+        button::Button::default()
+            .with_label("Escort")
+            .with_pos(SPACING + 2 * (BTN_WIDTH + SPACING), button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Escort");
+        button::Button::default()
+            .with_label("Escorts")
+            .with_pos(SPACING + 3 * (BTN_WIDTH + SPACING), button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Escorts");
+        button::Button::default()
+            .with_label("Ships")
+            .with_pos(SPACING + 4 * (BTN_WIDTH + SPACING), button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Ships");
+        button::Button::default()
+            .with_label("Course")
+            .with_pos(SPACING + 5 * (BTN_WIDTH + SPACING), button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Course");
+
+        wind.end();
+        wind.show();
+
+        while wind.shown() && app::wait() {
+            if let Some(m) = r.recv() {
+                let sel = browse.value();
+                // Ignore header, so only act on a selection if 2+
+                let fleet = if sel > 1 {
+                    unsafe { browse.data::<FleetSummary>(sel) }
+                } else {
+                    None
+                };
+                match m {
+                    "New" => {
+                        if let Some(f) = self.edit_fleet(Fleet::new("", 0, 0)).await {
+                            if let Err(e) = self.cmpgn.as_ref().unwrap().create_fleet(&f).await {
+                                dialog::alert_default(e.as_str());
+                            }
+                        }
+                    }
+                    "Edit" => {
+                        if let Some(f) = fleet {
+                            if let Some(f) = self.edit_fleet(f.fleet()).await {
+                                if let Err(e) = self.cmpgn.as_ref().unwrap().update_fleet(&f).await
+                                {
+                                    dialog::alert_default(e.as_str());
+                                }
+                            }
+                        }
+                    }
+                    "Merge" => {
+                        if let Some(f) = fleet {
+                            self.merge_fleet(&f).await;
+                        }
+                    }
+                    "Split" => {
+                        if let Some(f) = fleet {
+                            self.split_fleet(&f).await;
+                        }
+                    }
+                    "Disband" => {
+                        if let Some(f) = fleet {
+                            let prompt = format!(
+                                "Disband {}? Its {} ships will be removed.",
+                                f.name, f.ships
+                            );
+                            if dialog::choice2_default(prompt.as_str(), "Cancel", "Disband", "")
+                                == Some(1)
+                            {
+                                if let Err(e) =
+                                    self.cmpgn.as_ref().unwrap().disband_fleet(f.id).await
+                                {
+                                    dialog::alert_default(e.as_str());
+                                }
+                            }
+                        }
+                    }
+                    "Find" => self.find_ships_by_class().await,
+                    "Escort" => {
+                        if let Some(f) = fleet {
+                            self.escort_fleet(&f).await;
+                        }
+                    }
+                    "Escorts" => self.show_escorts().await,
+                    "Ships" => {
+                        if let Some(f) = fleet {
+                            self.show_fleet_ships(&f).await;
+                        }
+                    }
+                    "Course" => {
+                        if let Some(f) = fleet {
+                            self.set_course(&f).await;
+                        }
+                    }
+                    _ => (),
+                }
+                Self::fill_fleet_browser(&mut browse, self.cmpgn.as_ref().unwrap()).await;
+                browse.select(sel);
+            }
+        }
+    }
+
+    // Set the system a fleet is bound for, which it makes for by the shortest
+    // way over the turns that follow, or clear its course.
+    async fn set_course(&mut self, fleet: &FleetSummary) {
+        let systems: Vec<System> = match self.cmpgn.as_ref().unwrap().systems().await {
+            Ok(v) => v.into_iter().filter(|s| s.id != fleet.location).collect(),
+            Err(e) => {
+                dialog::alert_default(e.as_str());
+                return;
+            }
+        };
+        let mut names = vec!["(No course)".to_string()];
+        names.extend(systems.iter().map(|s| s.name.clone()));
+        let title = format!("{} Course", fleet.name);
+        let dest = match self.choose(title.as_str(), &names) {
+            Some(0) => None,
+            Some(i) => Some(&systems[i - 1]),
+            None => return,
+        };
+        if let Err(e) = self.cmpgn.as_ref().unwrap().set_course(fleet, dest).await {
+            dialog::alert_default(e.as_str());
+        }
+    }
+
+    // Set the fleet escorting another fleet of the same empire in the same system.
+    async fn escort_fleet(&mut self, escort: &FleetSummary) {
+        let c = self.cmpgn.as_ref().unwrap();
+        let convoys: Vec<FleetSummary> = match c.fleet_summaries().await {
+            Ok(v) => v
+                .into_iter()
+                .filter(|f| {
+                    f.id != escort.id && f.owner == escort.owner && f.location == escort.location
+                })
+                .collect(),
+            Err(e) => {
+                dialog::alert_default(e.as_str());
+                return;
+            }
+        };
+        if convoys.is_empty() {
+            dialog::alert_default("No other fleet of the same empire is in this system.");
+            return;
+        }
+
+        let names: Vec<String> = convoys.iter().map(|f| f.name.clone()).collect();
+        let title = format!("{} Escorts", escort.name);
+        if let Some(i) = self.choose(title.as_str(), &names) {
+            if let Err(e) = self
+                .cmpgn
+                .as_ref()
+                .unwrap()
+                .assign_escort(escort, &convoys[i])
+                .await
+            {
+                dialog::alert_default(e.as_str());
+            }
+        }
+    }
+
+    // Show every fleet escorting another, to release escorts from their
+    // convoys.
+    async fn show_escorts(&mut self) {
+        let mut wind = window::Window::default()
+            .with_size(400, 300)
+            .with_label("Escorts")
+            .center_screen();
+        let mut browse = SelectBrowser::default().with_pos(5, 5).with_size(390, 250);
+        browse.set_column_widths(&[190]);
+        browse.set_column_char('\t');
+        Self::fill_escort_browser(&mut browse, self.cmpgn.as_ref().unwrap()).await;
+
+        let (s, r) = app::channel();
+        button::Button::default()
+            .with_label("Release")
+            .with_pos(SPACING, 260)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Release");
+
+        wind.end();
+        wind.show();
+
+        while wind.shown() && app::wait() {
+            if let Some("Release") = r.recv() {
+                // Ignore header, so only act on a selection if 2+
+                let sel = browse.value();
+                if sel < 2 {
+                    continue;
+                }
+                if let Some(escort) = unsafe { browse.data::<Escort>(sel) } {
+                    let c = self.cmpgn.as_ref().unwrap();
+                    if let Err(e) = c.release_escort(&escort).await {
+                        dialog::alert_default(e.as_str());
+                    }
+                    Self::fill_escort_browser(&mut browse, c).await;
+                }
+            }
+        }
+    }
+
+    // Merge the fleet into another fleet of the same empire in the same system.
+    async fn merge_fleet(&mut self, from: &FleetSummary) {
+        let c = self.cmpgn.as_ref().unwrap();
+        let targets: Vec<FleetSummary> = match c.fleet_summaries().await {
+            Ok(v) => v
+                .into_iter()
+                .filter(|f| f.id != from.id && f.owner == from.owner && f.location == from.location)
+                .collect(),
+            Err(e) => {
+                dialog::alert_default(e.as_str());
+                return;
+            }
+        };
+        if targets.is_empty() {
+            dialog::alert_default("No other fleet of the same empire is in this system.");
+            return;
+        }
+
+        let names: Vec<String> = targets.iter().map(|f| f.name.clone()).collect();
+        let title = format!("Merge {} Into", from.name);
+        if let Some(i) = self.choose(title.as_str(), &names) {
+            if let Err(e) = self
+                .cmpgn
+                .as_ref()
+                .unwrap()
+                .merge_fleets(from.id, targets[i].id)
+                .await
+            {
+                dialog::alert_default(e.as_str());
+            }
+        }
+    }
+
+    // Show the ships of a fleet, for repair, mothballing and scrapping.
+    async fn show_fleet_ships(&mut self, fleet: &FleetSummary) {
+        let mut wind = window::Window::default()
+            .with_size(600, 400)
+            .with_label(format!("{} Ships", fleet.name).as_str())
+            .center_screen();
+        let mut browse = SelectBrowser::default().with_pos(5, 5).with_size(590, 300);
+        browse.set_column_widths(&[60, 150, 150, 150]);
+        browse.set_column_char('\t');
+        Self::fill_fleet_ship_browser(&mut browse, self.cmpgn.as_ref().unwrap(), fleet.id).await;
+
+        let (s, r) = app::channel();
+
+        let button_y = 310; // Browser bottom plus spacing
+        for (n, action) in shipyard::ACTIONS.iter().enumerate() {
+            button::Button::default()
+                .with_label(action)
+                .with_pos(SPACING + n as i32 * (BTN_WIDTH + SPACING), button_y)
+                .with_size(BTN_WIDTH, BTN_HEIGHT)
+                .emit(s, *action);
+        }
+        button::Button::default()
+            .with_label("Flights")
+            .with_pos(SPACING + 4 * (BTN_WIDTH + SPACING), button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Flights");
+        let button_y = button_y + BTN_HEIGHT + SPACING;
+        button::Button::default()
+            .with_label("Transfer")
+            .with_pos(SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Transfer");
+
+        wind.end();
+        wind.show();
+
+        while wind.shown() && app::wait() {
+            if let Some(action) = r.recv() {
+                if action == "Flights" {
+                    self.show_flights(fleet).await;
+                    continue;
+                }
+                let sel = browse.value();
+                // Ignore header, so only act on a selection if 2+
+                let ship = if sel > 1 {
+                    unsafe { browse.data::<ShipLocation>(sel) }
+                } else {
+                    None
+                };
+                if action == "Transfer" {
+                    // The ship changes hands in the diplomacy phase, so the
+                    // fleet is the same until then.
+                    if let Some(ship) = ship {
+                        self.transfer_ship(&ship).await;
+                    }
+                    continue;
+                }
+                if let Some(ship) = ship {
+                    let c = self.cmpgn.as_ref().unwrap();
+                    let result = match action {
+                        shipyard::REPAIR => c.order_repair(&ship).await.map(|b| {
+                            let text = format!("Repair of {} queued for {} EP.", b.name, b.cost);
+                            dialog::message_default(text.as_str());
+                        }),
+                        shipyard::MOTHBALL => c.mothball_ships(&[ship], true).await,
+                        shipyard::ACTIVATE => c.mothball_ships(&[ship], false).await,
+                        shipyard::SCRAP => {
+                            let prompt = format!(
+                                "Scrap {} #{}? It recovers {} EP.",
+                                ship.class,
+                                ship.id,
+                                shipyard::scrap_value(&ship)
+                            );
+                            if dialog::choice2_default(prompt.as_str(), "Cancel", "Scrap", "")
+                                == Some(1)
+                            {
+                                c.scrap_ship(&ship).await.map(|ep| {
+                                    let text = format!("Scrapping recovered {} EP.", ep);
+                                    dialog::message_default(text.as_str());
+                                })
+                            } else {
+                                Ok(())
+                            }
+                        }
+                        _ => Ok(()),
+                    };
+                    if let Err(e) = result {
+                        dialog::alert_default(e.as_str());
+                    }
+                }
+                Self::fill_fleet_ship_browser(&mut browse, self.cmpgn.as_ref().unwrap(), fleet.id)
+                    .await;
+                browse.select(sel);
+            }
+        }
+    }
+
+    // Show the flights of a fleet's empire where the fleet is, to add,
+    // restation and disband them.
+    async fn show_flights(&mut self, fleet: &FleetSummary) {
+        let mut wind = window::Window::default()
+            .with_size(600, 400)
+            .with_label(format!("{} Flights at {}", fleet.owner_name, fleet.location_name).as_str())
+            .center_screen();
+        let mut browse = SelectBrowser::default().with_pos(5, 5).with_size(590, 300);
+        browse.set_column_widths(&[60, 200, 60, 60]);
+        browse.set_column_char('\t');
+        Self::fill_flight_browser(&mut browse, self.cmpgn.as_ref().unwrap(), fleet).await;
+
+        let (s, r) = app::channel();
+
+        let button_y = 310; // Browser bottom plus spacing
+        button::Button::default()
+            .with_label("New")
+            .with_pos(SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "New");
+        button::Button::default()
+            .with_label("Station")
+            .with_pos(BTN_WIDTH + 2 * SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Station");
+        button::Button::default()
+            .with_label("Disband")
+            .with_pos(SPACING + 2 * (BTN_WIDTH + SPACING), button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Disband");
+
+        wind.end();
+        wind.show();
+
+        while wind.shown() && app::wait() {
+            if let Some(m) = r.recv() {
+                let sel = browse.value();
+                // Ignore header, so only act on a selection if 2+
+                let flight = if sel > 1 {
+                    unsafe { browse.data::<Flight>(sel) }
+                } else {
+                    None
+                };
+                let result = match (m, flight) {
+                    ("New", _) => {
+                        let new = Flight::new(
+                            fleet.owner,
+                            fleet.location,
+                            flight::FLIGHT_ATK,
+                            flight::FLIGHT_DEF,
+                        );
+                        match self.choose_station(fleet).await {
+                            Some(to) => {
+                                let c = self.cmpgn.as_ref().unwrap();
+                                c.station_flight(&new, &to).await.map(|_| ())
+                            }
+                            None => Ok(()),
+                        }
+                    }
+                    ("Station", Some(f)) => match self.choose_station(fleet).await {
+                        Some(to) => {
+                            let c = self.cmpgn.as_ref().unwrap();
+                            c.station_flight(&f, &to).await.map(|_| ())
+                        }
+                        None => Ok(()),
+                    },
+                    ("Disband", Some(f)) => {
+                        let prompt = format!("Disband the flight at {}?", f.station);
+                        if dialog::choice2_default(prompt.as_str(), "Cancel", "Disband", "")
+                            == Some(1)
+                        {
+                            self.cmpgn.as_ref().unwrap().disband_flight(&f).await
+                        } else {
+                            Ok(())
+                        }
+                    }
+                    _ => Ok(()),
+                };
+                if let Err(e) = result {
+                    dialog::alert_default(e.as_str());
+                }
+                Self::fill_flight_browser(&mut browse, self.cmpgn.as_ref().unwrap(), fleet).await;
+                browse.select(sel);
+            }
+        }
+    }
+
+    // Choose where to station a flight: one of the empire's carriers where the
+    // fleet is, or the system's bases if the empire holds it.
+    async fn choose_station(&mut self, fleet: &FleetSummary) -> Option<Station> {
+        let c = self.cmpgn.as_ref()?;
+        let ships = match c.empire_ships(fleet.owner).await {
+            Ok(v) => v,
+            Err(e) => {
+                dialog::alert_default(e.as_str());
+                return None;
+            }
+        };
+        let systems = match c.systems().await {
+            Ok(v) => v,
+            Err(e) => {
+                dialog::alert_default(e.as_str());
+                return None;
+            }
+        };
+        let mut stations: Vec<Station> = ships
+            .into_iter()
+            .filter(|s| s.location == fleet.location && s.cap > 0)
+            .map(Station::Carrier)
+            .collect();
+        stations.extend(
+            systems
+                .into_iter()
+                .filter(|s| s.id == fleet.location && s.owner == fleet.owner)
+                .map(Station::Base),
+        );
+        if stations.is_empty() {
+            dialog::alert_default("No carrier or base here can take flights");
+            return None;
+        }
+        let names: Vec<String> = stations
+            .iter()
+            .map(|s| format!("{} ({} flights)", s.name(), s.capacity()))
+            .collect();
+        let n = self.choose("Station", &names)?;
+        stations.into_iter().nth(n)
+    }
+
+    // Split selected ships off the fleet into a new fleet.
+    async fn split_fleet(&mut self, from: &FleetSummary) {
+        let ships = match self.cmpgn.as_ref().unwrap().fleet_ships(from.id).await {
+            Ok(v) => v,
+            Err(e) => {
+                dialog::alert_default(e.as_str());
+                return;
+            }
+        };
+
+        let total_width = 400;
+        let total_height = 400;
         let full_width = total_width - 2 * SPACING;
 
         let mut wind = window::Window::default()
             .with_size(total_width, total_height)
-            .with_label(format!("{} Campaign", function).as_str())
-            .center_screen();
-        let mut choice = menu::Choice::default()
-            .with_pos(SPACING, SPACING)
-            .with_size(full_width, TEXT_HEIGHT);
-        choice.add_choice(names.as_str());
-        let button_y = total_height - SPACING - BTN_HEIGHT;
+            .center_screen()
+            .with_label(format!("Split {}", from.name).as_str());
+        let mut name_input = input::Input::default()
+            .with_label("Name")
+            .with_pos(60, SPACING)
+            .with_size(total_width - 60 - SPACING, TEXT_HEIGHT);
+        name_input.set_value(format!("{} Detachment", from.name).as_str());
+        let mut browse = browser::MultiBrowser::default()
+            .with_pos(SPACING, 2 * SPACING + TEXT_HEIGHT)
+            .with_size(
+                full_width,
+                total_height - BTN_HEIGHT - TEXT_HEIGHT - 4 * SPACING,
+            );
+        browse.set_column_widths(&[60, 150, 100]);
+        browse.set_column_char('\t');
+        for ship in &ships {
+            browse.add(
+                format!(
+                    "{}\t{}\t{}",
+                    ship.id,
+                    ship.class,
+                    ShipStatus::from_flags(ship.crip, ship.moth)
+                )
+                .as_str(),
+            );
+        }
+
+        let button_y = total_height - BTN_HEIGHT - SPACING;
         let mut ok = button::Button::default()
             .with_label("Ok")
             .with_pos(SPACING, button_y)
@@ -349,6 +6584,7 @@ impl VBAMApp {
             .with_label("Cancel")
             .with_pos(BTN_WIDTH + 2 * SPACING, button_y)
             .with_size(BTN_WIDTH, BTN_HEIGHT);
+
         wind.end();
         wind.make_modal(true);
         wind.show();
@@ -360,52 +6596,390 @@ impl VBAMApp {
         let mut is_ok = false;
         while wind.shown() && self.app.wait() {
             if let Some(a) = r.recv() {
-                is_ok = match a {
-                    true => true,
-                    false => false,
-                };
+                is_ok = a;
                 wind.hide();
             }
         }
 
-        if is_ok {
-            choice.choice()
-        } else {
-            None
+        if !is_ok || name_input.value().is_empty() {
+            return;
+        }
+        let selected: Vec<i64> = ships
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| browse.selected(*i as i32 + 1))
+            .map(|(_, s)| s.id)
+            .collect();
+        if let Err(e) = self
+            .cmpgn
+            .as_ref()
+            .unwrap()
+            .split_fleet(from.id, name_input.value().as_str(), &selected)
+            .await
+        {
+            dialog::alert_default(e.as_str());
         }
     }
 
-    // Set the main window title. Includes campaign name if one is active.
-    fn set_title(&mut self) {
-        let title = match &self.cmpgn {
-            Some(cm) => format!("{} ({})", MAIN_TITLE, cm.title()),
-            None => MAIN_TITLE.to_string(),
+    // Show where every ship of a chosen class is.
+    async fn find_ships_by_class(&mut self) {
+        let c = match &self.cmpgn {
+            Some(c) => c,
+            None => return,
         };
-        self.main_win.set_label(title.as_str());
+        let types = match c.ship_types().await {
+            Ok(v) => v,
+            Err(e) => {
+                dialog::alert_default(e.as_str());
+                return;
+            }
+        };
+
+        let mut wind = window::Window::default()
+            .with_size(600, 400)
+            .with_label("Find Ships by Class")
+            .center_screen();
+        let mut class_choice = menu::Choice::default()
+            .with_label("Class")
+            .with_pos(60, SPACING)
+            .with_size(300, TEXT_HEIGHT);
+        for t in &types {
+            class_choice.add_choice(t.label().replace('/', "\\/").as_str());
+        }
+        let mut browse = SelectBrowser::default()
+            .with_pos(5, 2 * SPACING + TEXT_HEIGHT)
+            .with_size(590, 400 - 3 * SPACING - TEXT_HEIGHT);
+        browse.set_column_widths(&[60, 120, 150, 150, 80]);
+        browse.set_column_char('\t');
+
+        let (s, r) = app::channel();
+        class_choice.emit(s, "Class");
+
+        wind.end();
+        wind.show();
+
+        while wind.shown() && app::wait() {
+            if r.recv().is_some() {
+                browse.clear();
+                browse.add("Ship\tClass\tFleet\tLocation\tStatus");
+                if let Some(t) = types.get(class_choice.value() as usize) {
+                    match c.ships_by_class(t.id).await {
+                        Ok(v) => {
+                            for l in v {
+                                browse.add(l.as_row().as_str());
+                            }
+                        }
+                        Err(e) => dialog::alert_default(e.as_str()),
+                    }
+                }
+            }
+        }
     }
 
-    // Show the empires.
-    async fn show_empires(&mut self) {
-        // TODO Show the empires display
-        println!("Show empires");
+    // Show the jump lanes for editing.
+    // Show the campaign map: systems in their owners' colors, joined by
+    // their jump lanes.
+    async fn show_map(&mut self) {
+        self.show_map_window(None).await
     }
 
-    // Show the complete set of systems, regardless of owner.
-    async fn show_systems(&mut self) {
+    // Show the map, centered on the given system if any, which is ringed to
+    // pick it out.
+    async fn show_map_window(&mut self, focus: Option<i64>) {
         if self.cmpgn.is_none() {
             return;
         }
+        let c = self.cmpgn.as_ref().unwrap();
+        let (systems, lanes, empires) =
+            match (c.systems().await, c.lanes().await, c.empires().await) {
+                (Ok(s), Ok(l), Ok(e)) => (s, l, e),
+                (Err(e), _, _) | (_, Err(e), _) | (_, _, Err(e)) => {
+                    dialog::alert_default(e.as_str());
+                    return;
+                }
+            };
+        let layers = match c.overlays(None).await {
+            Ok(l) => Rc::new(RefCell::new(l)),
+            Err(e) => {
+                dialog::alert_default(e.as_str());
+                return;
+            }
+        };
+
+        let (width, height) = (700, 500);
+        let mut wind = window::Window::default()
+            .with_size(width, height + TEXT_HEIGHT + 2 * SPACING)
+            .with_label("Map")
+            .center_screen();
+        let mut canvas = frame::Frame::default()
+            .with_pos(0, 0)
+            .with_size(width, height);
+
+        // A check box for each overlay, and the empire whose fog of war is
+        // shown.
+        let (s, r) = app::channel();
+        let check_width = 90;
+        let mut checks = Vec::new();
+        for (n, o) in OVERLAYS.iter().enumerate() {
+            let mut check = button::CheckButton::default()
+                .with_label(o.to_string().as_str())
+                .with_pos(SPACING + n as i32 * check_width, height + SPACING)
+                .with_size(check_width, TEXT_HEIGHT);
+            check.emit(s, "Overlay");
+            checks.push(check);
+        }
+        let mut fog_choice = menu::Choice::default()
+            .with_pos(
+                SPACING + OVERLAYS.len() as i32 * check_width,
+                height + SPACING,
+            )
+            .with_size(
+                width - 2 * SPACING - OVERLAYS.len() as i32 * check_width,
+                TEXT_HEIGHT,
+            );
+        for e in &empires {
+            fog_choice.add_choice(e.name.as_str());
+        }
+        fog_choice.set_value(0);
+        fog_choice.emit(s, "Fog");
+        wind.end();
+
+        let mut fitted = MapView::fit(&systems, width, height);
+        if let Some(s) = systems.iter().find(|s| Some(s.id) == focus) {
+            fitted.center_on(s.x, s.y, width, height);
+        }
+        let view = Rc::new(RefCell::new(fitted));
+        let shown = Rc::new(RefCell::new(systems));
+        let clicked: Rc<RefCell<Option<System>>> = Rc::new(RefCell::new(None));
+        let on: Rc<RefCell<BTreeSet<Overlay>>> = Rc::new(RefCell::new(BTreeSet::new()));
+
+        canvas.draw({
+            let view = view.clone();
+            let shown = shown.clone();
+            let layers = layers.clone();
+            let on = on.clone();
+            move |f| {
+                let view = view.borrow();
+                let systems = shown.borrow();
+                let layers = layers.borrow();
+                let on = on.borrow();
+                let rgb = |(r, g, b): (u8, u8, u8)| Color::from_rgb(r, g, b);
+                let at = |id: i64| {
+                    systems.iter().find(|s| s.id == id).map(|s| {
+                        let (x, y) = view.to_screen(s.x, s.y);
+                        (x + f.x(), y + f.y())
+                    })
+                };
+                draw::push_clip(f.x(), f.y(), f.w(), f.h());
+                draw::draw_rect_fill(f.x(), f.y(), f.w(), f.h(), Color::Black);
+
+                // Lanes first, so the systems are drawn over them.
+                draw::set_draw_color(Color::from_rgb(90, 90, 110));
+                for lane in lanes.iter() {
+                    if let (Some(a), Some(b)) = (at(lane.a), at(lane.b)) {
+                        match lane.ltype.as_str() {
+                            "Major" => draw::set_line_style(LineStyle::Solid, 3),
+                            "Minor" => draw::set_line_style(LineStyle::Solid, 1),
+                            _ => draw::set_line_style(LineStyle::Dash, 1),
+                        }
+                        draw::draw_line(a.0, a.1, b.0, b.1);
+                    }
+                }
+                if on.contains(&Overlay::Trade) {
+                    draw::set_draw_color(rgb(Overlay::Trade.color()));
+                    draw::set_line_style(LineStyle::Solid, 3);
+                    for (_, path) in layers.trade.iter() {
+                        for hop in path.windows(2) {
+                            if let (Some(a), Some(b)) = (at(hop[0]), at(hop[1])) {
+                                draw::draw_line(a.0, a.1, b.0, b.1);
+                            }
+                        }
+                    }
+                }
+                draw::set_line_style(LineStyle::Solid, 0);
+
+                let ring = |x: i32, y: i32, r: i32| {
+                    draw::draw_arc(x - r, y - r, 2 * r, 2 * r, 0.0, 360.0);
+                };
+                draw::set_font(Font::Helvetica, 11);
+                for s in systems.iter() {
+                    if let Some((x, y)) = at(s.id) {
+                        let fogged = on.contains(&Overlay::Fog) && layers.fogged(s.id);
+                        if fogged {
+                            draw::set_draw_color(rgb(Overlay::Fog.color()));
+                        } else {
+                            draw::set_draw_color(rgb(map::owner_color(s.owner)));
+                        }
+                        draw::draw_pie(
+                            x - map::NODE_RADIUS,
+                            y - map::NODE_RADIUS,
+                            2 * map::NODE_RADIUS,
+                            2 * map::NODE_RADIUS,
+                            0.0,
+                            360.0,
+                        );
+                        if fogged {
+                            draw::set_draw_color(Color::Dark3);
+                            draw::draw_text(s.name.as_str(), x + map::NODE_RADIUS + 2, y + 4);
+                            continue;
+                        }
+                        draw::set_draw_color(Color::White);
+                        draw::draw_text(s.name.as_str(), x + map::NODE_RADIUS + 2, y + 4);
+                        if Some(s.id) == focus {
+                            ring(x, y, map::NODE_RADIUS + 6);
+                        }
+
+                        // A ring for each empire supplying the system.
+                        if on.contains(&Overlay::Supply) {
+                            let empires = layers.supply.get(&s.id).into_iter().flatten();
+                            for (n, e) in empires.enumerate() {
+                                draw::set_draw_color(rgb(map::owner_color(*e)));
+                                ring(x, y, map::NODE_RADIUS + 3 + 3 * n as i32);
+                            }
+                        }
+                        if on.contains(&Overlay::Piracy) {
+                            if let Some(level) = layers.piracy.get(&s.id) {
+                                draw::set_draw_color(rgb(Overlay::Piracy.color()));
+                                draw::set_line_style(LineStyle::Dash, *level);
+                                ring(x, y, map::NODE_RADIUS + 2);
+                                draw::set_line_style(LineStyle::Solid, 0);
+                            }
+                        }
+                        if on.contains(&Overlay::Battles) && layers.battles.contains(&s.id) {
+                            let r = map::NODE_RADIUS;
+                            draw::set_draw_color(rgb(Overlay::Battles.color()));
+                            draw::set_line_style(LineStyle::Solid, 2);
+                            draw::draw_line(x - r, y - r, x + r, y + r);
+                            draw::draw_line(x - r, y + r, x + r, y - r);
+                            draw::set_line_style(LineStyle::Solid, 0);
+                        }
+                    }
+                }
+                draw::pop_clip();
+            }
+        });
+
+        // Drag to pan, scroll to zoom, and click a system to edit it.
+        canvas.handle({
+            let view = view.clone();
+            let shown = shown.clone();
+            let clicked = clicked.clone();
+            let mut last = (0, 0);
+            let mut dragged = false;
+            move |f, ev| match ev {
+                Event::Enter | Event::Move => true,
+                Event::Push => {
+                    last = app::event_coords();
+                    dragged = false;
+                    true
+                }
+                Event::Drag => {
+                    let (x, y) = app::event_coords();
+                    view.borrow_mut().pan(x - last.0, y - last.1);
+                    last = (x, y);
+                    dragged = true;
+                    f.redraw();
+                    true
+                }
+                Event::Released => {
+                    if !dragged {
+                        let (x, y) = app::event_coords();
+                        let systems = shown.borrow();
+                        let sys = view
+                            .borrow()
+                            .system_at(&systems, x - f.x(), y - f.y())
+                            .cloned();
+                        if sys.is_some() {
+                            *clicked.borrow_mut() = sys;
+                            s.send("Edit");
+                        }
+                    }
+                    true
+                }
+                Event::MouseWheel => {
+                    let factor = match app::event_dy() {
+                        app::MouseWheel::Up => 1.25,
+                        app::MouseWheel::Down => 0.8,
+                        _ => return false,
+                    };
+                    let (x, y) = app::event_coords();
+                    view.borrow_mut().zoom(factor, x - f.x(), y - f.y());
+                    f.redraw();
+                    true
+                }
+                _ => false,
+            }
+        });
+
+        wind.show();
+
+        while wind.shown() && app::wait() {
+            match r.recv() {
+                Some("Edit") => {
+                    let sys = clicked.borrow_mut().take();
+                    if let Some(sys) = sys {
+                        if let Some(sys) = self.edit_system(sys).await {
+                            let c = self.cmpgn.as_ref().unwrap();
+                            if let Err(e) = c.update_system(&sys).await {
+                                dialog::alert_default(e.as_str());
+                            }
+                            if let Ok(v) = c.systems().await {
+                                *shown.borrow_mut() = v;
+                            }
+                        }
+                    }
+                }
+                Some("Overlay") => {
+                    *on.borrow_mut() = OVERLAYS
+                        .iter()
+                        .zip(checks.iter())
+                        .filter(|(_, c)| c.is_checked())
+                        .map(|(o, _)| *o)
+                        .collect();
+                }
+                Some("Fog") => (),
+                _ => continue,
+            }
+            // Edits and the chosen empire can change any layer.
+            let empire = empires
+                .get(fog_choice.value().max(0) as usize)
+                .map(|e| e.id);
+            match self.cmpgn.as_ref().unwrap().overlays(empire).await {
+                Ok(l) => *layers.borrow_mut() = l,
+                Err(e) => dialog::alert_default(e.as_str()),
+            }
+            canvas.redraw();
+        }
+    }
+
+    async fn show_lanes(&mut self) {
+        let systems = match &self.cmpgn {
+            Some(c) => match c.systems().await {
+                Ok(v) => v,
+                Err(e) => {
+                    dialog::alert_default(e.as_str());
+                    return;
+                }
+            },
+            None => return,
+        };
 
         let mut wind = window::Window::default()
             .with_size(600, 400)
-            .with_label("Systems")
+            .with_label("Jump Lanes")
             .center_screen();
-        let mut browse = fltk::browser::SelectBrowser::default()
-            .with_pos(5, 5)
-            .with_size(MAIN_WIDTH - 10, 300);
-        browse.set_column_widths(&[100, 100, 40, 40, 40, 40, 40, 40, 40, 100]);
+        let mut browse = SelectBrowser::default().with_pos(5, 5).with_size(590, 300);
+        browse.set_column_widths(&[200, 200, 100, 80]);
         browse.set_column_char('\t');
-        Self::fill_system_browser(&mut browse, self.cmpgn.as_ref().unwrap()).await;
+        browse.add("From\tTo\tType\tJump Points");
+        match self.cmpgn.as_ref().unwrap().lanes().await {
+            Ok(v) => {
+                for l in v {
+                    browse.add_with_data(l.as_row().as_str(), l);
+                }
+            }
+            Err(e) => dialog::alert_default(e.as_str()),
+        }
 
         let (s, r) = app::channel();
 
@@ -425,11 +6999,6 @@ impl VBAMApp {
             .with_pos(SPACING + 2 * (BTN_WIDTH + SPACING), button_y)
             .with_size(BTN_WIDTH, BTN_HEIGHT)
             .emit(s, "Delete");
-        button::Button::default()
-            .with_label("Import")
-            .with_pos(SPACING + 3 * (BTN_WIDTH + SPACING), button_y)
-            .with_size(BTN_WIDTH, BTN_HEIGHT)
-            .emit(s, "Import");
 
         wind.end();
         wind.show();
@@ -437,19 +7006,29 @@ impl VBAMApp {
         while wind.shown() && app::wait() {
             if let Some(m) = r.recv() {
                 match m {
-                    "New" => println!("New system"),
+                    "New" => {
+                        let blank = Lane::new(0, 0, LANE_TYPES[0]);
+                        if let Some(mut l) = self.edit_lane(blank, &systems).await {
+                            match self.cmpgn.as_ref().unwrap().add_lane(&l).await {
+                                Ok(id) => {
+                                    l.id = id;
+                                    browse.add_with_data(l.as_row().as_str(), l);
+                                }
+                                Err(e) => dialog::alert_default(e.as_str()),
+                            }
+                        }
+                    }
                     "Edit" => {
                         let sel = browse.value();
                         if sel > 1 {
                             // Ignore header, so only edit if 2+
                             unsafe {
-                                if let Some(sys) = browse.data(sel) {
-                                    if let Some(sys) = self.edit_system(sys).await {
-                                        match self.cmpgn.as_ref().unwrap().update_system(&sys).await
-                                        {
+                                if let Some(l) = browse.data(sel) {
+                                    if let Some(l) = self.edit_lane(l, &systems).await {
+                                        match self.cmpgn.as_ref().unwrap().update_lane(&l).await {
                                             Ok(_) => {
-                                                browse.set_text(sel, sys.as_row().as_str());
-                                                browse.set_data(sel, sys);
+                                                browse.set_text(sel, l.as_row().as_str());
+                                                browse.set_data(sel, l);
                                             }
                                             Err(e) => dialog::alert_default(e.as_str()),
                                         }
@@ -463,8 +7042,8 @@ impl VBAMApp {
                         if sel > 1 {
                             // Ignore header, so only delete if 2+
                             unsafe {
-                                if let Some(sys) = browse.data(sel) {
-                                    match self.cmpgn.as_ref().unwrap().delete_system(&sys).await {
+                                if let Some(l) = browse.data::<Lane>(sel) {
+                                    match self.cmpgn.as_ref().unwrap().delete_lane(&l).await {
                                         Ok(_) => browse.remove(sel),
                                         Err(e) => dialog::alert_default(e.as_str()),
                                     }
@@ -472,9 +7051,315 @@ impl VBAMApp {
                             }
                         }
                     }
+                    _ => (),
+                }
+            }
+        }
+    }
+
+    // Show the complete set of systems, regardless of owner.
+    async fn show_systems(&mut self) {
+        self.show_systems_window(None).await
+    }
+
+    // Show the systems window, initially filtered to the given owner.
+    async fn show_systems_window(&mut self, owner: Option<i64>) {
+        if self.cmpgn.is_none() {
+            return;
+        }
+
+        // Owner filter choices: all systems, unowned systems, then each empire.
+        let mut owners = vec![None, Some(0)];
+        let mut owner_names = vec!["All".to_string(), "None".to_string()];
+        if let Ok(v) = self.cmpgn.as_ref().unwrap().empires().await {
+            for e in v {
+                owners.push(Some(e.id));
+                owner_names.push(e.name);
+            }
+        }
+        // Planet type filter choices: all types, then each type.
+        let mut ptypes = vec![None];
+        let mut ptype_names = vec!["All".to_string()];
+        if let Ok(v) = self.cmpgn.as_ref().unwrap().planet_types().await {
+            for t in v {
+                ptypes.push(Some(t.name.clone()));
+                ptype_names.push(t.name);
+            }
+        }
+        let mut filter = SystemFilter {
+            owner,
+            ..Default::default()
+        };
+
+        let mut wind = window::Window::default()
+            .with_size(600, 435)
+            .with_label("Systems")
+            .center_screen();
+        let mut owner_choice = menu::Choice::default()
+            .with_label("Owner")
+            .with_pos(60, SPACING)
+            .with_size(150, TEXT_HEIGHT);
+        owner_choice.add_choice(owner_names.join("|").as_str());
+        owner_choice.set_value(owners.iter().position(|o| *o == owner).unwrap_or(0) as i32);
+        let mut ptype_choice = menu::Choice::default()
+            .with_label("Type")
+            .with_pos(265, SPACING)
+            .with_size(100, TEXT_HEIGHT);
+        ptype_choice.add_choice(ptype_names.join("|").as_str());
+        ptype_choice.set_value(0);
+        // Searches name, owner and type as the GM types.
+        let mut search_input = input::Input::default()
+            .with_label("Search")
+            .with_pos(420, SPACING)
+            .with_size(175, TEXT_HEIGHT);
+        search_input.set_trigger(CallbackTrigger::Changed);
+        search_input.set_tooltip("Find systems by name, owner or type (Ctrl+F)");
+
+        let view = SystemView::default();
+        let widths = [100, 80, 40, 40, 40, 40, 40, 40, 40, 60, 80, 100];
+        let columns: Vec<(String, i32)> = view.headers().into_iter().zip(widths).collect();
+        let sort_columns = view.columns.clone();
+        let mut table = RecordTable::new(
+            5,
+            2 * SPACING + TEXT_HEIGHT,
+            590,
+            270,
+            &columns,
+            move |s: &System| view.fields(s),
+            move |a: &System, b: &System, col| sort_columns[col].compare(a, b),
+        );
+        Self::fill_system_table(&mut table, self.cmpgn.as_ref().unwrap(), &filter).await;
+
+        let (s, r) = app::channel();
+        owner_choice.emit(s, "Filter");
+        ptype_choice.emit(s, "Filter");
+        search_input.emit(s, "Filter");
+
+        let button_y = 310; // Table bottom plus spacing
+        button::Button::default()
+            .with_label("New")
+            .with_pos(SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "New");
+        button::Button::default()
+            .with_label("Edit")
+            .with_pos(BTN_WIDTH + 2 * SPACING, button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Edit");
+        button::Button::default()
+            .with_label("Delete")
+            .with_pos(SPACING + 2 * (BTN_WIDTH + SPACING), button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Delete");
+        button::Button::default()
+            .with_label("Import")
+            .with_pos(SPACING + 3 * (BTN_WIDTH + SPACING), button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Import");
+        button::Button::default()
+            .with_label("Income")
+            .with_pos(SPACING + 4 * (BTN_WIDTH + SPACING), button_y)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Income");
+        button::Button::default()
+            .with_label("Garrison")
+            .with_pos(SPACING, button_y + BTN_HEIGHT + SPACING)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Garrison");
+        button::Button::default()
+            .with_label("Battle")
+            .with_pos(BTN_WIDTH + 2 * SPACING, button_y + BTN_HEIGHT + SPACING)
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Battle");
+        button::Button::default()
+            .with_label("Military")
+            .with_pos(
+                SPACING + 2 * (BTN_WIDTH + SPACING),
+                button_y + BTN_HEIGHT + SPACING,
+            )
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Military");
+        button::Button::default()
+            .with_label("Raid")
+            .with_pos(
+                SPACING + 3 * (BTN_WIDTH + SPACING),
+                button_y + BTN_HEIGHT + SPACING,
+            )
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Raid");
+        button::Button::default()
+            .with_label("Raids")
+            .with_pos(
+                SPACING + 4 * (BTN_WIDTH + SPACING),
+                button_y + BTN_HEIGHT + SPACING,
+            )
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Raids");
+        button::Button::default()
+            .with_label("Develop")
+            .with_pos(SPACING, button_y + 2 * (BTN_HEIGHT + SPACING))
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Develop");
+        button::Button::default()
+            .with_label("Developments")
+            .with_pos(
+                BTN_WIDTH + 2 * SPACING,
+                button_y + 2 * (BTN_HEIGHT + SPACING),
+            )
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Developments");
+        button::Button::default()
+            .with_label("Map")
+            .with_pos(
+                SPACING + 2 * (BTN_WIDTH + SPACING),
+                button_y + 2 * (BTN_HEIGHT + SPACING),
+            )
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Map");
+        button::Button::default()
+            .with_label("Bulk Edit")
+            .with_pos(
+                SPACING + 3 * (BTN_WIDTH + SPACING),
+                button_y + 2 * (BTN_HEIGHT + SPACING),
+            )
+            .with_size(BTN_WIDTH, BTN_HEIGHT)
+            .emit(s, "Bulk Edit");
+
+        wind.end();
+        wind.handle({
+            let mut search_input = search_input.clone();
+            move |_, ev| {
+                if ev == Event::Shortcut
+                    && app::is_event_ctrl()
+                    && app::event_key() == Key::from_char('f')
+                {
+                    search_input.take_focus().ok();
+                    search_input.set_position(0).ok();
+                    search_input
+                        .set_mark(search_input.value().len() as i32)
+                        .ok();
+                    return true;
+                }
+                false
+            }
+        });
+        wind.show();
+        search_input.take_focus().ok();
+
+        while wind.shown() && app::wait() {
+            if let Some(m) = r.recv() {
+                match m {
+                    "New" => println!("New system"),
+                    "Edit" => {
+                        if let Some(sys) = table.first_selected() {
+                            if let Some(sys) = self.edit_system(sys).await {
+                                match self.cmpgn.as_ref().unwrap().update_system(&sys).await {
+                                    Ok(_) => {
+                                        Self::fill_system_table(
+                                            &mut table,
+                                            self.cmpgn.as_ref().unwrap(),
+                                            &filter,
+                                        )
+                                        .await
+                                    }
+                                    Err(e) => dialog::alert_default(e.as_str()),
+                                }
+                            }
+                        }
+                    }
+                    "Bulk Edit" => {
+                        // The change is made to every selected system.
+                        let selected = table.selected();
+                        if selected.is_empty() {
+                            continue;
+                        }
+                        if let Some(edit) = self.bulk_edit_systems(selected.len()).await {
+                            let c = self.cmpgn.as_ref().unwrap();
+                            if let Err(e) = c.bulk_update_systems(&selected, &edit).await {
+                                dialog::alert_default(e.as_str());
+                            }
+                            Self::fill_system_table(&mut table, c, &filter).await
+                        }
+                    }
+                    "Delete" => {
+                        // Every selected system is deleted.
+                        for sys in table.selected() {
+                            if let Err(e) = self.cmpgn.as_ref().unwrap().delete_system(&sys).await {
+                                dialog::alert_default(e.as_str());
+                                break;
+                            }
+                        }
+                        Self::fill_system_table(&mut table, self.cmpgn.as_ref().unwrap(), &filter)
+                            .await
+                    }
                     "Import" => {
                         self.import_systems().await;
-                        Self::fill_system_browser(&mut browse, self.cmpgn.as_ref().unwrap()).await
+                        Self::fill_system_table(&mut table, self.cmpgn.as_ref().unwrap(), &filter)
+                            .await
+                    }
+                    "Filter" => {
+                        filter.owner = owners.get(owner_choice.value() as usize).copied().flatten();
+                        filter.ptype = ptypes.get(ptype_choice.value() as usize).cloned().flatten();
+                        filter.search = search_input.value();
+                        Self::fill_system_table(&mut table, self.cmpgn.as_ref().unwrap(), &filter)
+                            .await
+                    }
+                    "Income" => {
+                        if let Some(sys) = table.first_selected() {
+                            let title = format!("Income for {}", sys.name);
+                            match self.cmpgn.as_ref().unwrap().ruleset().await {
+                                Ok(rules) => {
+                                    let text = IncomeBreakdown::new(&sys, rules).to_string();
+                                    show_report(title.as_str(), text.as_str());
+                                }
+                                Err(e) => dialog::alert_default(e.as_str()),
+                            }
+                        }
+                    }
+                    "Garrison" => {
+                        if let Some(sys) = table.first_selected() {
+                            self.show_garrison(&sys).await;
+                        }
+                    }
+                    "Battle" => {
+                        if let Some(sys) = table.first_selected() {
+                            self.fight_battle(&sys).await;
+                        }
+                    }
+                    "Raid" => {
+                        if let Some(sys) = table.first_selected() {
+                            self.order_raid(&sys).await;
+                        }
+                    }
+                    "Raids" => self.show_raids().await,
+                    "Develop" => {
+                        if let Some(sys) = table.first_selected() {
+                            self.order_development(&sys).await;
+                            Self::fill_system_table(
+                                &mut table,
+                                self.cmpgn.as_ref().unwrap(),
+                                &filter,
+                            )
+                            .await
+                        }
+                    }
+                    "Developments" => {
+                        self.show_developments().await;
+                        Self::fill_system_table(&mut table, self.cmpgn.as_ref().unwrap(), &filter)
+                            .await
+                    }
+                    "Map" => {
+                        if let Some(sys) = table.first_selected() {
+                            self.show_map_window(Some(sys.id)).await;
+                        }
+                    }
+                    "Military" => {
+                        if let Some(sys) = table.first_selected() {
+                            match self.cmpgn.as_ref().unwrap().military_summary(&sys).await {
+                                Ok(r) => show_report("Military Summary", r.as_str()),
+                                Err(e) => dialog::alert_default(e.as_str()),
+                            }
+                        }
                     }
                     _ => (),
                 }
@@ -518,7 +7403,44 @@ fn show_about() {
     }
 }
 
+// Show a text report in its own window.
+fn show_report(title: &str, report: &str) {
+    let width = MAIN_WIDTH - 100;
+    let height = MAIN_HEIGHT - 100;
+    let mut wind = window::Window::default()
+        .with_size(width, height)
+        .with_label(title)
+        .center_screen();
+    let mut buf = text::TextBuffer::default();
+    buf.set_text(report);
+    let mut disp = text::TextDisplay::default()
+        .with_pos(SPACING, SPACING)
+        .with_size(width - 2 * SPACING, height - 2 * SPACING);
+    disp.set_buffer(buf);
+    wind.end();
+    wind.make_modal(true);
+    wind.show();
+    while wind.shown() {
+        app::wait();
+    }
+}
+
 #[tokio::main]
 async fn main() {
-    VBAMApp::new().run().await;
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some(cli::NO_GUI) {
+        let result = match cli::Command::parse(&args[1..]) {
+            Ok(cmd) => cmd.run().await,
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(text) => print!("{}", text),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+    VBAMApp::new().run();
 }
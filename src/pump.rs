@@ -0,0 +1,63 @@
+// Copyright 2022 David Terhune
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Waiting on the campaign without freezing the window.
+//!
+//! The database does its work on the tokio runtime and on sqlx's own
+//! threads. Awaiting it from the FLTK thread would park that thread, and the
+//! window with it, until the work is done. Instead the future is polled from
+//! the event loop: the loop goes on drawing and handling events while the
+//! work is outstanding, and the future's waker calls `app::awake` so it is
+//! polled again as soon as there is progress. Messages sent meanwhile wait
+//! in their channel until the future is done.
+
+use std::future::Future;
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread;
+use std::time::Duration;
+
+use fltk::app;
+
+// How long to sleep between polls once there is no window left to wait on.
+const IDLE: Duration = Duration::from_millis(10);
+
+// Wakes the event loop when a future can make progress.
+struct Awake;
+
+impl Wake for Awake {
+    fn wake(self: Arc<Self>) {
+        app::awake();
+    }
+}
+
+/// Run a future to completion on the FLTK thread, handling events while it
+/// waits. Must be called from within the tokio runtime, which the future
+/// may depend on.
+pub fn block_on<F: Future>(fut: F) -> F::Output {
+    let waker = Waker::from(Arc::new(Awake));
+    let mut cx = Context::from_waker(&waker);
+    let mut fut = Box::pin(fut);
+    loop {
+        if let Poll::Ready(v) = fut.as_mut().poll(&mut cx) {
+            return v;
+        }
+        // With every window closed there are no events to wait on, but the
+        // future still has to finish, e.g. closing the campaign on quit.
+        if !app::wait() {
+            thread::sleep(IDLE);
+        }
+    }
+}
@@ -0,0 +1,217 @@
+// Copyright 2022 David Terhune
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Table of records with a header, click-to-sort columns and multiple
+//! selection.
+
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::rc::Rc;
+
+use fltk::{
+    draw,
+    enums::{Align, Color, Font, FrameType},
+    prelude::*,
+    table::{TableContext, TableRow, TableRowSelectFlag, TableRowSelectMode},
+};
+
+// Height of every row, header included.
+const ROW_HEIGHT: i32 = 20;
+
+// Font size of the cells.
+const FONT_SIZE: i32 = 12;
+
+// How a record is laid out as cells of text.
+type TextFn<T> = Box<dyn Fn(&T) -> Vec<String>>;
+
+// How two records compare on a column.
+type CompareFn<T> = Box<dyn Fn(&T, &T, usize) -> Ordering>;
+
+// Records and how they are shown, shared with the drawing and click
+// callbacks.
+struct State<T> {
+    headers: Vec<String>,
+    records: Vec<T>,
+    cells: Vec<Vec<String>>,
+    sort: Option<(usize, bool)>,
+    text: TextFn<T>,
+    compare: CompareFn<T>,
+}
+
+impl<T> State<T> {
+    // Put the records in the sort order and lay out their cells.
+    fn arrange(&mut self) {
+        if let Some((col, ascending)) = self.sort {
+            let compare = &self.compare;
+            self.records.sort_by(|a, b| {
+                let ord = compare(a, b, col);
+                if ascending {
+                    ord
+                } else {
+                    ord.reverse()
+                }
+            });
+        }
+        self.cells = self.records.iter().map(|r| (self.text)(r)).collect();
+    }
+
+    // Header label, marked with the direction if the table is sorted on it.
+    fn header(&self, col: usize) -> String {
+        let label = self.headers.get(col).cloned().unwrap_or_default();
+        match self.sort {
+            Some((c, true)) if c == col => format!("{} ^", label),
+            Some((c, false)) if c == col => format!("{} v", label),
+            _ => label,
+        }
+    }
+}
+
+/// A table of records. Each record is shown as a row of text; clicking a
+/// column header sorts on that column, and clicking it again reverses the
+/// order.
+pub struct RecordTable<T: 'static> {
+    table: TableRow,
+    state: Rc<RefCell<State<T>>>,
+}
+
+impl<T: Clone + 'static> RecordTable<T> {
+    /// Create a table with the given columns, each a header and a width.
+    /// `text` gives the cells of a record and `compare` orders two records
+    /// by a column.
+    pub fn new<F, C>(
+        x: i32,
+        y: i32,
+        w: i32,
+        h: i32,
+        columns: &[(String, i32)],
+        text: F,
+        compare: C,
+    ) -> Self
+    where
+        F: Fn(&T) -> Vec<String> + 'static,
+        C: Fn(&T, &T, usize) -> Ordering + 'static,
+    {
+        let state = Rc::new(RefCell::new(State {
+            headers: columns.iter().map(|(h, _)| h.clone()).collect(),
+            records: Vec::new(),
+            cells: Vec::new(),
+            sort: None,
+            text: Box::new(text),
+            compare: Box::new(compare),
+        }));
+
+        let mut table = TableRow::new(x, y, w, h, None);
+        table.set_type(TableRowSelectMode::Multi);
+        table.set_rows(0);
+        table.set_cols(columns.len() as i32);
+        table.set_col_header(true);
+        table.set_col_header_height(ROW_HEIGHT);
+        table.set_row_height_all(ROW_HEIGHT);
+        table.set_col_resize(true);
+        for (col, (_, width)) in columns.iter().enumerate() {
+            table.set_col_width(col as i32, *width);
+        }
+        table.end();
+
+        let draw_state = state.clone();
+        table.draw_cell(move |t, ctx, row, col, x, y, w, h| match ctx {
+            TableContext::StartPage => draw::set_font(Font::Helvetica, FONT_SIZE),
+            TableContext::ColHeader => {
+                let label = draw_state.borrow().header(col as usize);
+                draw::push_clip(x, y, w, h);
+                draw::draw_box(FrameType::ThinUpBox, x, y, w, h, Color::FrameDefault);
+                draw::set_draw_color(Color::Black);
+                draw::draw_text2(&label, x + 2, y, w - 4, h, Align::Left);
+                draw::pop_clip();
+            }
+            TableContext::Cell => {
+                let st = draw_state.borrow();
+                let text = st
+                    .cells
+                    .get(row as usize)
+                    .and_then(|r| r.get(col as usize))
+                    .map(String::as_str)
+                    .unwrap_or("");
+                let selected = t.row_selected(row);
+                draw::push_clip(x, y, w, h);
+                draw::set_draw_color(if selected {
+                    Color::Selection
+                } else {
+                    Color::Background2
+                });
+                draw::draw_rectf(x, y, w, h);
+                draw::set_draw_color(if selected {
+                    Color::Background2
+                } else {
+                    Color::Foreground
+                });
+                draw::draw_text2(text, x + 2, y, w - 4, h, Align::Left);
+                draw::set_draw_color(Color::Light2);
+                draw::draw_rect(x, y, w, h);
+                draw::pop_clip();
+            }
+            _ => (),
+        });
+
+        let sort_state = state.clone();
+        table.set_callback(move |t| {
+            if let TableContext::ColHeader = t.callback_context() {
+                let col = t.callback_col() as usize;
+                {
+                    let mut st = sort_state.borrow_mut();
+                    st.sort = match st.sort {
+                        Some((c, ascending)) if c == col => Some((col, !ascending)),
+                        _ => Some((col, true)),
+                    };
+                    st.arrange();
+                }
+                // Selection follows rows, not records, so it would be wrong
+                // after a sort.
+                t.select_all_rows(TableRowSelectFlag::Deselect);
+                t.redraw();
+            }
+        });
+
+        Self { table, state }
+    }
+
+    /// Show the given records, in the current sort order. Any selection is
+    /// cleared.
+    pub fn set_records(&mut self, records: Vec<T>) {
+        let rows = records.len() as i32;
+        {
+            let mut st = self.state.borrow_mut();
+            st.records = records;
+            st.arrange();
+        }
+        self.table.set_rows(rows);
+        self.table.select_all_rows(TableRowSelectFlag::Deselect);
+        self.table.redraw();
+    }
+
+    /// The selected records, top to bottom.
+    pub fn selected(&mut self) -> Vec<T> {
+        let st = self.state.borrow();
+        (0..st.records.len())
+            .filter(|r| self.table.row_selected(*r as i32))
+            .map(|r| st.records[r].clone())
+            .collect()
+    }
+
+    /// The first selected record, if any.
+    pub fn first_selected(&mut self) -> Option<T> {
+        self.selected().into_iter().next()
+    }
+}